@@ -127,6 +127,7 @@ async fn main() {
                 }
             })),
             on_progress: None,
+            timeout: None,
         };
         (ctx, buf)
     };
@@ -132,6 +132,7 @@ async fn main() {
             }
         })),
         on_progress: None,
+        timeout: None,
     };
 
     let result = lead_analyst
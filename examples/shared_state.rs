@@ -112,6 +112,7 @@ async fn main() {
         cancel: tokio_util::sync::CancellationToken::new(),
         on_update: None,
         on_progress: None,
+        timeout: None,
     };
 
     let (r1, r2, r3) = tokio::join!(
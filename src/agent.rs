@@ -2,11 +2,12 @@
 //! steering/follow-up queues, and abort support.
 
 use crate::agent_loop::{
-    agent_loop, agent_loop_continue, AfterTurnFn, AgentLoopConfig, BeforeTurnFn, OnErrorFn,
+    agent_loop, agent_loop_continue, AfterTurnFn, AgentLoopConfig, BeforeTurnFn, EventSender,
+    OnErrorFn, OnMessageFn, PrepareTurnFn, SystemPromptHookFn,
 };
 use crate::context::{CompactionStrategy, ContextConfig, ExecutionLimits};
 use crate::mcp::{McpClient, McpError, McpToolAdapter};
-use crate::provider::{ModelConfig, StreamProvider};
+use crate::provider::{ModelConfig, ResponseFormat, StreamProvider, ToolChoice};
 use crate::types::*;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -54,17 +55,32 @@ pub struct Agent {
     pub cache_config: CacheConfig,
     pub tool_execution: ToolExecutionStrategy,
     pub retry_config: crate::retry::RetryConfig,
+    circuit_breaker: Option<Arc<crate::retry::CircuitBreaker>>,
+    rate_limiter: Option<Arc<crate::rate_limit::RateLimiter>>,
 
     // Lifecycle callbacks
     before_turn: Option<BeforeTurnFn>,
+    prepare_turn: Option<PrepareTurnFn>,
     after_turn: Option<AfterTurnFn>,
+    on_message: Option<OnMessageFn>,
+    system_prompt_hook: Option<SystemPromptHookFn>,
     on_error: Option<OnErrorFn>,
 
     // Input filters
-    input_filters: Vec<Arc<dyn InputFilter>>,
+    input_filters: Vec<Arc<dyn AsyncInputFilter>>,
+    output_filters: Vec<Arc<dyn OutputFilter>>,
 
     // Tool middleware (permissions/policy hooks)
     tool_middleware: Vec<Arc<dyn ToolMiddleware>>,
+    tool_timeout: Option<std::time::Duration>,
+    tool_cache: Option<Arc<dyn crate::tools::ToolCache>>,
+    metrics_sink: Option<Arc<dyn crate::metrics::MetricsSink>>,
+    approve_tool: Option<crate::agent_loop::ApproveToolFn>,
+    on_tool_call: Option<crate::agent_loop::OnToolCallFn>,
+    tool_choice: ToolChoice,
+    stop_sequences: Option<Vec<String>>,
+    response_format: ResponseFormat,
+    server_tools: Vec<crate::provider::ServerTool>,
 
     // Custom compaction strategy
     compaction_strategy: Option<Arc<dyn CompactionStrategy>>,
@@ -262,11 +278,26 @@ impl Agent {
             cache_config: CacheConfig::default(),
             tool_execution: ToolExecutionStrategy::default(),
             retry_config: crate::retry::RetryConfig::default(),
+            circuit_breaker: None,
+            rate_limiter: None,
             before_turn: None,
+            prepare_turn: None,
             after_turn: None,
+            on_message: None,
+            system_prompt_hook: None,
             on_error: None,
             input_filters: Vec::new(),
+            output_filters: Vec::new(),
             tool_middleware: Vec::new(),
+            tool_timeout: None,
+            tool_cache: None,
+            metrics_sink: None,
+            approve_tool: None,
+            on_tool_call: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
             compaction_strategy: None,
             cancel: None,
             is_streaming: false,
@@ -349,6 +380,53 @@ impl Agent {
         self
     }
 
+    /// Stop calling the provider for a cooldown after repeated failures (see
+    /// [`CircuitBreaker`](crate::retry::CircuitBreaker)). Pass the same `Arc`
+    /// to every agent that shares the provider.
+    pub fn with_circuit_breaker(mut self, breaker: Arc<crate::retry::CircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Wait for `limiter` before every provider call (see
+    /// [`RateLimiter`](crate::rate_limit::RateLimiter)). Pass the same `Arc`
+    /// to every agent that should share the budget.
+    pub fn with_rate_limiter(mut self, limiter: Arc<crate::rate_limit::RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Constrain how the model may use its tools (see [`ToolChoice`]). A
+    /// forcing choice applies until the model calls a tool, then the rest of
+    /// the run is `Auto`.
+    pub fn with_tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.tool_choice = choice;
+        self
+    }
+
+    /// Stop generation whenever the model emits one of these strings.
+    pub fn with_stop_sequences(mut self, sequences: Vec<String>) -> Self {
+        self.stop_sequences = Some(sequences);
+        self
+    }
+
+    /// Ask for replies in this format on every turn, e.g.
+    /// [`ResponseFormat::JsonObject`] for JSON mode. Read the result with
+    /// [`Message::parse_json`]. For a specific schema use
+    /// [`prompt_structured`](Self::prompt_structured).
+    pub fn with_response_format(mut self, format: ResponseFormat) -> Self {
+        self.response_format = format;
+        self
+    }
+
+    /// Offer provider-executed tools, e.g.
+    /// [`ServerTool::web_search`](crate::provider::ServerTool::web_search),
+    /// on every turn. The provider runs them; no [`AgentTool`] is involved.
+    pub fn with_server_tools(mut self, tools: Vec<crate::provider::ServerTool>) -> Self {
+        self.server_tools = tools;
+        self
+    }
+
     /// Load skills and append their index to the system prompt.
     ///
     /// The skills index is appended as XML per the [AgentSkills standard](https://agentskills.io).
@@ -366,6 +444,17 @@ impl Agent {
         self
     }
 
+    /// Like [`with_skills`](Self::with_skills), and also add one
+    /// `skill_<name>` tool per skill (see
+    /// [`SkillSet::as_tools`](crate::skills::SkillSet::as_tools)), so the
+    /// agent activates a skill with a single call instead of reading its
+    /// SKILL.md. The tools are added to the current ones — call this after
+    /// [`with_tools`](Self::with_tools), which replaces them.
+    pub fn with_skill_tools(mut self, skills: crate::skills::SkillSet) -> Self {
+        self.tools.extend(skills.as_tools());
+        self.with_skills(skills)
+    }
+
     pub fn with_execution_limits(mut self, limits: ExecutionLimits) -> Self {
         self.execution_limits = Some(limits);
         self
@@ -384,6 +473,28 @@ impl Agent {
         self
     }
 
+    /// Run before each turn with the mutable [`AgentContext`] — add or
+    /// remove tools between turns (say, after an MCP server reports its
+    /// tool list changed). Tool changes persist on the agent after the run.
+    pub fn on_prepare_turn(
+        mut self,
+        f: impl Fn(&mut AgentContext, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.prepare_turn = Some(Arc::new(f));
+        self
+    }
+
+    /// Rewrite the system prompt sent on each turn, given the agent's
+    /// prompt and the turn number (0-based). The agent's own prompt is left
+    /// as is. [`DateInjector::hook`](crate::DateInjector::hook) is a ready-made one.
+    pub fn with_system_prompt_hook(
+        mut self,
+        f: impl Fn(&str, usize) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.system_prompt_hook = Some(Arc::new(f));
+        self
+    }
+
     pub fn on_after_turn(
         mut self,
         f: impl Fn(&[AgentMessage], &Usage) + Send + Sync + 'static,
@@ -392,17 +503,32 @@ impl Agent {
         self
     }
 
+    /// Observe every message added to the conversation during a run —
+    /// prompts, assistant replies and tool results — as it is appended.
+    pub fn on_message(mut self, f: impl Fn(&AgentMessage) + Send + Sync + 'static) -> Self {
+        self.on_message = Some(Arc::new(f));
+        self
+    }
+
     pub fn on_error(mut self, f: impl Fn(&str) + Send + Sync + 'static) -> Self {
         self.on_error = Some(Arc::new(f));
         self
     }
 
     /// Add an input filter. Filters run in order on user messages before the LLM call.
-    pub fn with_input_filter(mut self, filter: impl InputFilter + 'static) -> Self {
+    /// Accepts both sync [`InputFilter`]s and [`AsyncInputFilter`]s.
+    pub fn with_input_filter(mut self, filter: impl AsyncInputFilter + 'static) -> Self {
         self.input_filters.push(Arc::new(filter));
         self
     }
 
+    /// Add an output filter. Filters run in order on each assistant message
+    /// before it is added to the conversation (see [`OutputFilter`]).
+    pub fn with_output_filter(mut self, filter: impl OutputFilter + 'static) -> Self {
+        self.output_filters.push(Arc::new(filter));
+        self
+    }
+
     /// Add a tool middleware — an async approve/deny/modify hook that gates
     /// every tool call (see [`ToolMiddleware`]). Middleware run in
     /// installation order; each may rewrite the arguments seen by later ones,
@@ -413,6 +539,54 @@ impl Agent {
         self
     }
 
+    /// Limit how long any single tool call may run. On expiry the call is
+    /// cancelled and the LLM receives a timeout error result for it.
+    pub fn with_tool_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    /// Serve repeated identical tool calls from `cache` instead of running
+    /// them again (see [`ToolCache`](crate::tools::ToolCache)). Only
+    /// successful results of
+    /// [`AgentTool::cacheable`](crate::types::AgentTool::cacheable) tools are cached.
+    pub fn with_tool_cache(mut self, cache: impl crate::tools::ToolCache + 'static) -> Self {
+        self.tool_cache = Some(Arc::new(cache));
+        self
+    }
+
+    /// Report every LLM call and tool call to `sink`, e.g. a
+    /// [`PrometheusMetrics`](crate::metrics) (feature `prometheus`). Keep a
+    /// clone of the `Arc` to read the metrics.
+    pub fn with_metrics_sink(mut self, sink: Arc<dyn crate::metrics::MetricsSink>) -> Self {
+        self.metrics_sink = Some(sink);
+        self
+    }
+
+    /// Ask `f(tool_call_id, tool_name, args)` before every tool call.
+    /// `Deny` skips the call with an error result; `AlwaysAllow` stops asking
+    /// about that tool for the rest of the run. Each request is announced
+    /// with [`AgentEvent::ToolApprovalRequired`].
+    pub fn with_approve_tool(
+        mut self,
+        f: impl Fn(&str, &str, &serde_json::Value) -> ToolApproval + Send + Sync + 'static,
+    ) -> Self {
+        self.approve_tool = Some(Arc::new(f));
+        self
+    }
+
+    /// Call `f(tool_call_id, tool_name, args)` before every tool runs; a
+    /// `Some(result)` is used instead of running the tool. Handy for
+    /// injecting canned results for expensive tools in tests. Intercepted
+    /// results carry `"intercepted": true` in their details.
+    pub fn on_tool_call(
+        mut self,
+        f: impl Fn(&str, &str, &serde_json::Value) -> Option<ToolResult> + Send + Sync + 'static,
+    ) -> Self {
+        self.on_tool_call = Some(Arc::new(f));
+        self
+    }
+
     /// Set a custom compaction strategy. When set, replaces the default
     /// `compact_messages()` call during context compaction.
     pub fn with_compaction_strategy(mut self, strategy: impl CompactionStrategy + 'static) -> Self {
@@ -511,6 +685,17 @@ impl Agent {
         Ok(self)
     }
 
+    /// Connect to an MCP server via HTTP+SSE and add its tools to the agent.
+    pub async fn with_mcp_server_sse(mut self, url: &str) -> Result<Self, McpError> {
+        let client = McpClient::connect_sse(url).await?;
+        let client = Arc::new(tokio::sync::Mutex::new(client));
+        let adapters = McpToolAdapter::from_client(client).await?;
+        for adapter in adapters {
+            self.tools.push(Box::new(adapter));
+        }
+        Ok(self)
+    }
+
     // -- State access --
 
     pub fn messages(&self) -> &[AgentMessage] {
@@ -687,6 +872,17 @@ impl Agent {
         self.prompt_messages(vec![msg]).await
     }
 
+    /// [`prompt`](Self::prompt) as a [`Stream`](futures::Stream) of events,
+    /// for callers that consume with `StreamExt` combinators instead of
+    /// `recv()`. Same lifecycle: the context is kept on the agent, and
+    /// [`finish()`](Self::finish) (or the next prompt) restores it.
+    pub async fn run(
+        &mut self,
+        text: impl Into<String>,
+    ) -> impl futures::Stream<Item = AgentEvent> + Send + Unpin + 'static {
+        tokio_stream::wrappers::UnboundedReceiverStream::new(self.prompt(text).await)
+    }
+
     /// Send a prompt and parse the reply into `T`, with the JSON Schema
     /// enforced natively by the provider (Anthropic: forced tool call;
     /// OpenAI-compatible: `json_schema` response format; Gemini:
@@ -758,15 +954,11 @@ impl Agent {
             .ok_or(StructuredPromptError::NoOutput)?;
 
         // Defensive: some models wrap JSON in markdown fences.
-        let cleaned = raw
-            .trim()
-            .trim_start_matches("```json")
-            .trim_start_matches("```")
-            .trim_end_matches("```")
-            .trim();
-        serde_json::from_str(cleaned).map_err(|source| StructuredPromptError::Parse {
-            source,
-            raw: raw.clone(),
+        serde_json::from_str(strip_json_fences(&raw)).map_err(|source| {
+            StructuredPromptError::Parse {
+                source,
+                raw: raw.clone(),
+            }
         })
     }
 
@@ -834,7 +1026,9 @@ impl Agent {
     /// The caller provides an external sender and sets up a consumer task
     /// before calling this method. This method blocks until the loop finishes
     /// and state is restored — unlike [`prompt()`](Self::prompt) which spawns
-    /// the loop concurrently and returns immediately.
+    /// the loop concurrently and returns immediately. A bounded
+    /// `mpsc::Sender` works too: events that don't fit are dropped rather
+    /// than queued (see [`EventSender`]).
     ///
     /// ```rust,no_run
     /// # use yoagent::Agent;
@@ -860,7 +1054,7 @@ impl Agent {
     pub async fn prompt_with_sender(
         &mut self,
         text: impl Into<String>,
-        tx: mpsc::UnboundedSender<AgentEvent>,
+        tx: impl Into<EventSender>,
     ) {
         let msg = AgentMessage::Llm(Message::user(text));
         self.prompt_messages_with_sender(vec![msg], tx).await;
@@ -880,7 +1074,7 @@ impl Agent {
     pub async fn prompt_messages_with_sender(
         &mut self,
         messages: Vec<AgentMessage>,
-        tx: mpsc::UnboundedSender<AgentEvent>,
+        tx: impl Into<EventSender>,
     ) {
         self.finish().await; // restore from previous if needed
 
@@ -959,7 +1153,7 @@ impl Agent {
     /// `*_with_sender` future was dropped mid-run (the agent is stuck in the
     /// streaming state; [`Agent::finish`] cannot recover it — recreate the
     /// agent). A misuse-`Result` variant is planned for 0.10.
-    pub async fn continue_loop_with_sender(&mut self, tx: mpsc::UnboundedSender<AgentEvent>) {
+    pub async fn continue_loop_with_sender(&mut self, tx: impl Into<EventSender>) {
         self.finish().await; // restore from previous if needed
 
         assert!(!self.is_streaming, "Agent is already streaming.");
@@ -1099,18 +1293,25 @@ impl Agent {
             context_config: if self.context_management_disabled {
                 None
             } else {
-                Some(self.context_config.clone().unwrap_or_else(|| {
+                let mut context_config = self.context_config.clone().unwrap_or_else(|| {
                     self.model_config
                         .as_ref()
                         .map(|m| ContextConfig::from_context_window(m.context_window))
                         .unwrap_or_default()
-                }))
+                });
+                // Size messages with the agent's model tokenizer unless overridden.
+                context_config
+                    .model
+                    .get_or_insert_with(|| self.model.clone());
+                Some(context_config)
             },
             compaction_strategy: self.compaction_strategy.clone(),
             execution_limits: self.execution_limits.clone(),
             cache_config: self.cache_config.clone(),
             tool_execution: self.tool_execution.clone(),
             retry_config: self.retry_config.clone(),
+            circuit_breaker: self.circuit_breaker.clone(),
+            rate_limiter: self.rate_limiter.clone(),
             get_follow_up_messages: Some(Box::new(move || {
                 let mut queue = follow_up_queue.lock().unwrap();
                 match follow_up_mode {
@@ -1125,11 +1326,28 @@ impl Agent {
                 }
             })),
             before_turn: self.before_turn.clone(),
+            prepare_turn: self.prepare_turn.clone(),
             after_turn: self.after_turn.clone(),
+            checkpoint: None,
+            stop_if: None,
+            on_context_compact: None,
+            on_message: self.on_message.clone(),
+            event_buffer_size: None,
+            system_prompt_hook: self.system_prompt_hook.clone(),
             on_error: self.on_error.clone(),
             input_filters: self.input_filters.clone(),
+            output_filters: self.output_filters.clone(),
             tool_middleware: self.tool_middleware.clone(),
+            tool_timeout: self.tool_timeout,
+            tool_cache: self.tool_cache.clone(),
+            metrics_sink: self.metrics_sink.clone(),
+            approve_tool: self.approve_tool.clone(),
+            on_tool_call: self.on_tool_call.clone(),
             output_schema: None,
+            tool_choice: self.tool_choice.clone(),
+            stop_sequences: self.stop_sequences.clone(),
+            response_format: self.response_format.clone(),
+            server_tools: self.server_tools.clone(),
             turn_delay: None,
         }
     }
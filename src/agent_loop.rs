@@ -11,8 +11,17 @@ use crate::context::{
     self, CompactionStrategy, ContextConfig, ContextTracker, DefaultCompaction, ExecutionLimits,
     ExecutionTracker,
 };
-use crate::provider::{ModelConfig, StreamConfig, StreamEvent, StreamProvider, ToolDefinition};
+use crate::metrics::MetricsSink;
+use crate::provider::traits::civil_from_days;
+use crate::provider::{
+    ModelConfig, ProviderError, ResponseFormat, StreamConfig, StreamEvent, StreamProvider,
+    ToolChoice, ToolDefinition,
+};
+use crate::rate_limit::RateLimiter;
+use crate::retry::CircuitBreaker;
+use crate::tools::ToolCache;
 use crate::types::*;
+use std::collections::HashMap;
 use std::sync::Arc;
 
 /// Type alias for convert_to_llm callback.
@@ -23,13 +32,74 @@ pub type TransformContextFn = Box<dyn Fn(Vec<AgentMessage>) -> Vec<AgentMessage>
 pub type GetMessagesFn = Box<dyn Fn() -> Vec<AgentMessage> + Send + Sync>;
 /// Called before each LLM turn. Return `false` to abort the loop.
 pub type BeforeTurnFn = Arc<dyn Fn(&[AgentMessage], usize) -> bool + Send + Sync>;
+/// Called before each LLM turn, after `before_turn`, with mutable access to
+/// the context — e.g. to add or remove tools between turns.
+pub type PrepareTurnFn = Arc<dyn Fn(&mut AgentContext, usize) + Send + Sync>;
 /// Called after each LLM turn with the current messages and the turn's usage.
 pub type AfterTurnFn = Arc<dyn Fn(&[AgentMessage], &Usage) + Send + Sync>;
+/// Called at the end of each turn with the context and the turn number
+/// (1-based), to persist state for [`agent_loop_resume`].
+pub type CheckpointFn = Arc<dyn Fn(&AgentContext, usize) + Send + Sync>;
+/// Called before each provider call with the context's system prompt and the
+/// turn number (0-based, as for `before_turn`); returns the prompt to send.
+pub type SystemPromptHookFn = Arc<dyn Fn(&str, usize) -> String + Send + Sync>;
+/// Called after each completed turn with the messages so far. Return `true`
+/// to end the run there.
+pub type StopIfFn = Arc<dyn Fn(&[AgentMessage]) -> bool + Send + Sync>;
+/// Called after compaction shrinks the context, with the token counts
+/// before and after.
+pub type OnContextCompactFn = Arc<dyn Fn(usize, usize) + Send + Sync>;
+/// Called with each message as it is added to the context.
+pub type OnMessageFn = Arc<dyn Fn(&AgentMessage) + Send + Sync>;
 /// Called when the LLM returns a `StopReason::Error`.
 pub type OnErrorFn = Arc<dyn Fn(&str) + Send + Sync>;
+/// Asked before each tool call with `(tool_call_id, tool_name, args)`.
+pub type ApproveToolFn = Arc<dyn Fn(&str, &str, &serde_json::Value) -> ToolApproval + Send + Sync>;
+/// Asked before each tool runs with `(tool_call_id, tool_name, args)`.
+/// `Some(result)` is used instead of running the tool.
+pub type OnToolCallFn =
+    Arc<dyn Fn(&str, &str, &serde_json::Value) -> Option<ToolResult> + Send + Sync>;
 use tokio::sync::mpsc;
 use tracing::warn;
 
+/// A system prompt hook that prepends `Current date: YYYY-MM-DD` (UTC) on
+/// every turn, so an agent running across midnight keeps the right date.
+///
+/// ```
+/// use std::sync::Arc;
+/// use yoagent::agent_loop::{AgentLoopConfigBuilder, DateInjector};
+/// use yoagent::provider::MockProvider;
+///
+/// let config = AgentLoopConfigBuilder::new(Arc::new(MockProvider::text("hi")), "mock", "key")
+///     .with_system_prompt_hook(DateInjector::hook)
+///     .build();
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DateInjector;
+
+impl DateInjector {
+    /// The hook itself: `prompt` with today's date prepended. Pass it to
+    /// `with_system_prompt_hook`, or wrap it in an `Arc` for
+    /// [`AgentLoopConfig::system_prompt_hook`].
+    pub fn hook(prompt: &str, _turn: usize) -> String {
+        Self::inject(prompt, std::time::SystemTime::now())
+    }
+
+    /// `prompt` with the date at `now` prepended.
+    pub fn inject(prompt: &str, now: std::time::SystemTime) -> String {
+        let days = now
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |since| since.as_secs() / 86_400);
+        let (year, month, day) = civil_from_days(days as i64);
+        let date = format!("Current date: {:04}-{:02}-{:02}", year, month, day);
+        if prompt.is_empty() {
+            date
+        } else {
+            format!("{}\n\n{}", date, prompt)
+        }
+    }
+}
+
 /// Configuration for the agent loop
 pub struct AgentLoopConfig {
     pub provider: Arc<dyn StreamProvider>,
@@ -63,6 +133,27 @@ pub struct AgentLoopConfig {
     /// Custom compaction strategy. When set, replaces the default
     /// `compact_messages()` call. Invoked when `context_config` is `Some`.
     pub compaction_strategy: Option<Arc<dyn CompactionStrategy>>,
+    /// Called with `(tokens_before, tokens_after)` whenever compaction
+    /// shrinks the context, alongside [`AgentEvent::ContextCompacted`].
+    pub on_context_compact: Option<OnContextCompactFn>,
+
+    /// Called once for every message added to the context during the run
+    /// — prompts, assistant replies, tool results, steering and follow-up
+    /// messages — right after it is appended, in order. Purely
+    /// observational: for logging, per-message accounting or UI updates
+    /// without consuming the event stream.
+    pub on_message: Option<OnMessageFn>,
+
+    /// Capacity of the event channel created by [`agent_loop_stream`] and
+    /// [`agent_loop_continue_stream`]. `None` (the default) is unbounded;
+    /// `Some(0)` is treated as 1. When set, events that arrive while the
+    /// channel is full are dropped with a warning instead of piling up
+    /// behind a slow consumer — see [`EventSender`].
+    ///
+    /// Only the stream entry points read this. [`agent_loop`] and
+    /// `Agent::*_with_sender` use the sender you pass (a bounded
+    /// `mpsc::Sender` behaves the same way); `Agent::prompt` is unbounded.
+    pub event_buffer_size: Option<usize>,
 
     /// Execution limits (max turns, tokens, duration).
     pub execution_limits: Option<ExecutionLimits>,
@@ -77,25 +168,105 @@ pub struct AgentLoopConfig {
     /// executes (see [`ToolMiddleware`]). Empty = allow all.
     pub tool_middleware: Vec<Arc<dyn ToolMiddleware>>,
 
+    /// Human-in-the-loop approval, consulted before every tool call (after
+    /// middleware). A [`AgentEvent::ToolApprovalRequired`] is emitted first so
+    /// UIs can prompt; `Deny` skips the call with an error result, and
+    /// `AlwaysAllow` stops asking about that tool for the rest of the run.
+    /// `None` = no approval step.
+    pub approve_tool: Option<ApproveToolFn>,
+
+    /// Intercept tool calls, e.g. to serve canned results in tests. Called
+    /// after middleware and approval; `Some(result)` is used instead of
+    /// running the tool (or the cache). `ToolExecutionStart`/`End` still
+    /// fire, with `"intercepted": true` in the result's details. `None` =
+    /// every call runs.
+    pub on_tool_call: Option<OnToolCallFn>,
+
+    /// Deadline for a single tool call. On expiry the call's cancellation
+    /// token is fired and the LLM receives an error result ("Tool '{name}'
+    /// timed out after {n}s"). Tools can override it with
+    /// [`AgentTool::timeout`]. `None` = no limit.
+    pub tool_timeout: Option<std::time::Duration>,
+
+    /// Cache of successful results of [`AgentTool::cacheable`] tools (see
+    /// [`ToolCache`]). A hit skips execution; `ToolExecutionStart`/`End`
+    /// still fire, with `"from_cache": true` in the result's details. Any
+    /// other tool call clears it. `None` = no caching.
+    pub tool_cache: Option<Arc<dyn ToolCache>>,
+
+    /// Receives the model, token usage and duration of every LLM call, and
+    /// the outcome and duration of every tool call (see
+    /// [`MetricsSink`](crate::metrics::MetricsSink)). `None` = no metrics.
+    pub metrics_sink: Option<Arc<dyn MetricsSink>>,
+
     /// Structured-output constraint, passed through to the provider (see
     /// [`OutputSchema`](crate::provider::OutputSchema)). Usually set via
     /// [`Agent::prompt_structured`](crate::Agent::prompt_structured).
     pub output_schema: Option<crate::provider::OutputSchema>,
 
+    /// How the model may use the tools offered each turn. A forcing choice
+    /// (`Required` / `Specific`) applies until the model has called a tool,
+    /// then reverts to `Auto` for the rest of the run — otherwise every turn
+    /// would be forced into another tool call and the loop could never end.
+    pub tool_choice: ToolChoice,
+
+    /// Strings that end generation when the model emits them, passed through
+    /// to every request.
+    pub stop_sequences: Option<Vec<String>>,
+
+    /// Reply format for every request (e.g. JSON mode). Ignored when
+    /// `output_schema` is set. On Anthropic, JSON mode is a forced tool call
+    /// that the loop unwraps back into text.
+    pub response_format: ResponseFormat,
+
+    /// Provider-executed tools (e.g. Anthropic web search) offered every
+    /// turn. The model's calls to them come back as
+    /// [`Content::ServerToolUse`] blocks and never reach `tools`.
+    pub server_tools: Vec<crate::provider::ServerTool>,
+
     /// Retry configuration for transient provider errors.
     pub retry_config: crate::retry::RetryConfig,
+    /// Stops calling the provider for a while after repeated failures (see
+    /// [`CircuitBreaker`]). Share the `Arc` between agents using the same
+    /// provider. `None` = always call.
+    pub circuit_breaker: Option<Arc<CircuitBreaker>>,
+    /// Awaited before every provider call to stay under requests- and
+    /// tokens-per-minute limits (see [`RateLimiter`]). Share the `Arc` to
+    /// enforce one budget across agents. `None` = no client-side limit.
+    pub rate_limiter: Option<Arc<RateLimiter>>,
 
     /// Called before each LLM turn. Return `false` to abort the loop.
     pub before_turn: Option<BeforeTurnFn>,
+    /// Called before each LLM turn (after `before_turn`) with the mutable
+    /// context. Tools added or removed here are offered from this turn on.
+    pub prepare_turn: Option<PrepareTurnFn>,
     /// Called after each LLM turn with the current messages and the turn's usage.
     pub after_turn: Option<AfterTurnFn>,
+    /// Called at every `TurnEnd` (after `after_turn`, tool results included)
+    /// with the context and turn number. Save both to restart with
+    /// [`agent_loop_resume`] after a crash.
+    pub checkpoint: Option<CheckpointFn>,
+    /// Rewrites the system prompt for each turn's request — the current
+    /// date, a summary of state that changes between turns. Gets
+    /// `AgentContext::system_prompt` and the turn number; the context itself
+    /// is never changed, so each turn starts from the base prompt.
+    pub system_prompt_hook: Option<SystemPromptHookFn>,
+    /// Checked after each completed turn, tool results included. Returning
+    /// `true` ends the run after that turn's `TurnEnd`, without another
+    /// provider call, steering or follow-ups.
+    pub stop_if: Option<StopIfFn>,
     /// Called when the LLM returns a `StopReason::Error`.
     pub on_error: Option<OnErrorFn>,
 
     /// Input filters applied to user messages before the LLM call.
     /// Filters run in order; first `Reject` wins and discards any accumulated
     /// warnings. `Warn` messages accumulate and are appended to the user message.
-    pub input_filters: Vec<Arc<dyn InputFilter>>,
+    pub input_filters: Vec<Arc<dyn AsyncInputFilter>>,
+
+    /// Output filters applied to each assistant message before it enters
+    /// context. Filters run in order; `Redact` feeds the next filter, the
+    /// first `Block` wins.
+    pub output_filters: Vec<Arc<dyn OutputFilter>>,
 
     /// Optional delay between turns. Useful for rate-limit-sensitive scenarios
     /// (e.g., OAuth tokens with low request-per-minute caps). Skipped on the
@@ -103,6 +274,382 @@ pub struct AgentLoopConfig {
     pub turn_delay: Option<std::time::Duration>,
 }
 
+impl AgentLoopConfig {
+    /// Start an [`AgentLoopConfigBuilder`] for `provider`. Model and API key
+    /// default to empty — set them with `with_model` / `with_api_key`, or use
+    /// [`AgentLoopConfigBuilder::new`] to pass them up front.
+    pub fn builder(provider: Arc<dyn StreamProvider>) -> AgentLoopConfigBuilder {
+        AgentLoopConfigBuilder::new(provider, "", "")
+    }
+}
+
+/// Method-chaining builder for [`AgentLoopConfig`].
+///
+/// Everything not set explicitly takes a neutral default: no callbacks, no
+/// filters or middleware, no context management or execution limits,
+/// thinking off, default caching/retry, parallel tool execution. (This is
+/// deliberately leaner than [`Agent`](crate::Agent), which enables
+/// compaction and execution limits by default.)
+///
+/// ```
+/// use std::sync::Arc;
+/// use yoagent::agent_loop::AgentLoopConfigBuilder;
+/// use yoagent::provider::MockProvider;
+/// use yoagent::ThinkingLevel;
+///
+/// let config = AgentLoopConfigBuilder::new(Arc::new(MockProvider::text("hi")), "mock", "key")
+///     .with_thinking_level(ThinkingLevel::Medium)
+///     .with_before_turn(|_messages, turn| turn < 10)
+///     .build();
+/// assert_eq!(config.thinking_level, ThinkingLevel::Medium);
+/// ```
+pub struct AgentLoopConfigBuilder {
+    config: AgentLoopConfig,
+}
+
+impl AgentLoopConfigBuilder {
+    pub fn new(
+        provider: Arc<dyn StreamProvider>,
+        model: impl Into<String>,
+        api_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            config: AgentLoopConfig {
+                provider,
+                model: model.into(),
+                api_key: api_key.into(),
+                thinking_level: ThinkingLevel::Off,
+                max_tokens: None,
+                temperature: None,
+                model_config: None,
+                convert_to_llm: None,
+                transform_context: None,
+                get_steering_messages: None,
+                get_follow_up_messages: None,
+                context_config: None,
+                compaction_strategy: None,
+                execution_limits: None,
+                cache_config: CacheConfig::default(),
+                tool_execution: ToolExecutionStrategy::default(),
+                tool_middleware: Vec::new(),
+                tool_timeout: None,
+                tool_cache: None,
+                metrics_sink: None,
+                approve_tool: None,
+                on_tool_call: None,
+                output_schema: None,
+                tool_choice: ToolChoice::Auto,
+                stop_sequences: None,
+                response_format: ResponseFormat::Text,
+                server_tools: Vec::new(),
+                retry_config: crate::retry::RetryConfig::default(),
+                circuit_breaker: None,
+                rate_limiter: None,
+                before_turn: None,
+                prepare_turn: None,
+                after_turn: None,
+                checkpoint: None,
+                stop_if: None,
+                on_context_compact: None,
+                on_message: None,
+                event_buffer_size: None,
+                system_prompt_hook: None,
+                on_error: None,
+                input_filters: Vec::new(),
+                output_filters: Vec::new(),
+                turn_delay: None,
+            },
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.config.model = model.into();
+        self
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.config.api_key = api_key.into();
+        self
+    }
+
+    pub fn with_thinking_level(mut self, level: ThinkingLevel) -> Self {
+        self.config.thinking_level = level;
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max: u32) -> Self {
+        self.config.max_tokens = Some(max);
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.config.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_model_config(mut self, config: ModelConfig) -> Self {
+        self.config.model_config = Some(config);
+        self
+    }
+
+    pub fn with_convert_to_llm(
+        mut self,
+        f: impl Fn(&[AgentMessage]) -> Vec<Message> + Send + Sync + 'static,
+    ) -> Self {
+        self.config.convert_to_llm = Some(Box::new(f));
+        self
+    }
+
+    pub fn with_transform_context(
+        mut self,
+        f: impl Fn(Vec<AgentMessage>) -> Vec<AgentMessage> + Send + Sync + 'static,
+    ) -> Self {
+        self.config.transform_context = Some(Box::new(f));
+        self
+    }
+
+    /// Source of steering messages, polled between tool executions.
+    pub fn with_steering_messages(
+        mut self,
+        f: impl Fn() -> Vec<AgentMessage> + Send + Sync + 'static,
+    ) -> Self {
+        self.config.get_steering_messages = Some(Box::new(f));
+        self
+    }
+
+    /// Source of follow-up messages, polled when the agent would stop.
+    pub fn with_follow_up_messages(
+        mut self,
+        f: impl Fn() -> Vec<AgentMessage> + Send + Sync + 'static,
+    ) -> Self {
+        self.config.get_follow_up_messages = Some(Box::new(f));
+        self
+    }
+
+    pub fn with_context_config(mut self, config: ContextConfig) -> Self {
+        self.config.context_config = Some(config);
+        self
+    }
+
+    pub fn with_compaction_strategy(mut self, strategy: impl CompactionStrategy + 'static) -> Self {
+        self.config.compaction_strategy = Some(Arc::new(strategy));
+        self
+    }
+
+    pub fn with_execution_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.config.execution_limits = Some(limits);
+        self
+    }
+
+    pub fn with_cache_config(mut self, config: CacheConfig) -> Self {
+        self.config.cache_config = config;
+        self
+    }
+
+    pub fn with_tool_execution(mut self, strategy: ToolExecutionStrategy) -> Self {
+        self.config.tool_execution = strategy;
+        self
+    }
+
+    /// Ask `f` before each tool call runs (see [`AgentLoopConfig::approve_tool`]).
+    pub fn with_approve_tool(
+        mut self,
+        f: impl Fn(&str, &str, &serde_json::Value) -> ToolApproval + Send + Sync + 'static,
+    ) -> Self {
+        self.config.approve_tool = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_on_tool_call(
+        mut self,
+        f: impl Fn(&str, &str, &serde_json::Value) -> Option<ToolResult> + Send + Sync + 'static,
+    ) -> Self {
+        self.config.on_tool_call = Some(Arc::new(f));
+        self
+    }
+
+    /// Append a tool middleware to the chain (see [`ToolMiddleware`]).
+    pub fn with_tool_middleware(mut self, middleware: impl ToolMiddleware + 'static) -> Self {
+        self.config.tool_middleware.push(Arc::new(middleware));
+        self
+    }
+
+    pub fn with_output_schema(mut self, schema: crate::provider::OutputSchema) -> Self {
+        self.config.output_schema = Some(schema);
+        self
+    }
+
+    pub fn with_retry_config(mut self, config: crate::retry::RetryConfig) -> Self {
+        self.config.retry_config = config;
+        self
+    }
+
+    pub fn with_tool_choice(mut self, choice: ToolChoice) -> Self {
+        self.config.tool_choice = choice;
+        self
+    }
+
+    pub fn with_stop_sequences(mut self, sequences: Vec<String>) -> Self {
+        self.config.stop_sequences = Some(sequences);
+        self
+    }
+
+    pub fn with_response_format(mut self, format: ResponseFormat) -> Self {
+        self.config.response_format = format;
+        self
+    }
+
+    pub fn with_server_tools(mut self, tools: Vec<crate::provider::ServerTool>) -> Self {
+        self.config.server_tools = tools;
+        self
+    }
+
+    pub fn with_before_turn(
+        mut self,
+        f: impl Fn(&[AgentMessage], usize) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.config.before_turn = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_prepare_turn(
+        mut self,
+        f: impl Fn(&mut AgentContext, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.config.prepare_turn = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_after_turn(
+        mut self,
+        f: impl Fn(&[AgentMessage], &Usage) + Send + Sync + 'static,
+    ) -> Self {
+        self.config.after_turn = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_checkpoint(
+        mut self,
+        f: impl Fn(&AgentContext, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.config.checkpoint = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_on_context_compact(
+        mut self,
+        f: impl Fn(usize, usize) + Send + Sync + 'static,
+    ) -> Self {
+        self.config.on_context_compact = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_on_message(mut self, f: impl Fn(&AgentMessage) + Send + Sync + 'static) -> Self {
+        self.config.on_message = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_system_prompt_hook(
+        mut self,
+        f: impl Fn(&str, usize) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.config.system_prompt_hook = Some(Arc::new(f));
+        self
+    }
+
+    pub fn with_stop_if(
+        mut self,
+        f: impl Fn(&[AgentMessage]) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.config.stop_if = Some(Arc::new(f));
+        self
+    }
+
+    /// Bound the event channel of the stream entry points; see
+    /// [`AgentLoopConfig::event_buffer_size`].
+    pub fn with_event_buffer_size(mut self, size: usize) -> Self {
+        self.config.event_buffer_size = Some(size);
+        self
+    }
+
+    pub fn with_on_error(mut self, f: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.config.on_error = Some(Arc::new(f));
+        self
+    }
+
+    /// Append an input filter; filters run in installation order.
+    pub fn with_input_filter(mut self, filter: impl AsyncInputFilter + 'static) -> Self {
+        self.config.input_filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Append an output filter; filters run in installation order.
+    pub fn with_output_filter(mut self, filter: impl OutputFilter + 'static) -> Self {
+        self.config.output_filters.push(Arc::new(filter));
+        self
+    }
+
+    pub fn with_turn_delay(mut self, delay: std::time::Duration) -> Self {
+        self.config.turn_delay = Some(delay);
+        self
+    }
+
+    pub fn with_tool_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.config.tool_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_tool_cache(mut self, cache: impl ToolCache + 'static) -> Self {
+        self.config.tool_cache = Some(Arc::new(cache));
+        self
+    }
+
+    pub fn with_metrics_sink(mut self, sink: Arc<dyn MetricsSink>) -> Self {
+        self.config.metrics_sink = Some(sink);
+        self
+    }
+
+    pub fn with_circuit_breaker(mut self, breaker: Arc<CircuitBreaker>) -> Self {
+        self.config.circuit_breaker = Some(breaker);
+        self
+    }
+
+    pub fn with_rate_limiter(mut self, limiter: Arc<RateLimiter>) -> Self {
+        self.config.rate_limiter = Some(limiter);
+        self
+    }
+
+    /// Finish the config. Settings the provider's
+    /// [`capabilities`](StreamProvider::capabilities) rule out (thinking,
+    /// JSON mode, server tools) are logged as warnings; the request is still sent as
+    /// configured.
+    pub fn build(self) -> AgentLoopConfig {
+        let caps = self.config.provider.capabilities();
+        if self.config.thinking_level != ThinkingLevel::Off && !caps.supports_thinking {
+            warn!(
+                "thinking_level {:?} is set but the provider for model '{}' does not support thinking",
+                self.config.thinking_level, self.config.model
+            );
+        }
+        if (self.config.response_format == ResponseFormat::JsonObject
+            || self.config.output_schema.is_some())
+            && !caps.supports_json_mode
+        {
+            warn!(
+                "JSON output is requested but the provider for model '{}' does not support JSON mode",
+                self.config.model
+            );
+        }
+        if !self.config.server_tools.is_empty() && !caps.supports_server_tools {
+            warn!(
+                "server tools are set but the provider for model '{}' does not support them",
+                self.config.model
+            );
+        }
+        self.config
+    }
+}
+
 /// Default convert_to_llm: keep only user/assistant/toolResult messages.
 fn default_convert_to_llm(messages: &[AgentMessage]) -> Vec<Message> {
     messages
@@ -111,14 +658,91 @@ fn default_convert_to_llm(messages: &[AgentMessage]) -> Vec<Message> {
         .collect()
 }
 
+/// Where the loop sends its [`AgentEvent`]s: an unbounded channel, or a
+/// bounded one that drops events rather than grow without limit.
+///
+/// The loop never waits on a full bounded channel mid-run — that would
+/// stall the provider stream and tool execution behind the consumer.
+/// Instead the event is dropped and a warning logged, so a consumer that
+/// falls behind misses events (text deltas, tool updates) but the run itself
+/// is unaffected. Size the buffer for how far behind the consumer may fall.
+/// The one exception is the terminal `AgentEnd`: the loop waits for room to
+/// deliver it, so a consumer always sees the run end.
+///
+/// Every entry point accepts either sender type via `Into`:
+///
+/// ```rust,no_run
+/// # use yoagent::{agent_loop, agent_loop::AgentLoopConfig, AgentContext};
+/// # async fn example(mut context: AgentContext, config: AgentLoopConfig) {
+/// # let prompts = vec![];
+/// let (tx, mut rx) = tokio::sync::mpsc::channel(64);
+/// tokio::spawn(async move {
+///     while let Some(event) = rx.recv().await { /* render */ }
+/// });
+/// let cancel = tokio_util::sync::CancellationToken::new();
+/// let new_messages = agent_loop(prompts, &mut context, &config, tx, cancel).await;
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub enum EventSender {
+    Unbounded(mpsc::UnboundedSender<AgentEvent>),
+    Bounded(mpsc::Sender<AgentEvent>),
+}
+
+impl EventSender {
+    /// Send `event` without waiting. Fails only when the receiver is gone
+    /// (the event is discarded); an event dropped because a bounded channel
+    /// is full counts as sent.
+    pub fn send(&self, event: AgentEvent) -> Result<(), mpsc::error::SendError<()>> {
+        match self {
+            EventSender::Unbounded(tx) => tx.send(event).map_err(|_| mpsc::error::SendError(())),
+            EventSender::Bounded(tx) => match tx.try_send(event) {
+                Ok(()) => Ok(()),
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    warn!(
+                        capacity = tx.max_capacity(),
+                        "agent event channel is full; dropping event"
+                    );
+                    Ok(())
+                }
+                Err(mpsc::error::TrySendError::Closed(_)) => Err(mpsc::error::SendError(())),
+            },
+        }
+    }
+
+    /// Send the terminal [`AgentEvent::AgentEnd`], waiting for room in a
+    /// bounded channel rather than dropping it: consumers rely on it to know
+    /// the run is over.
+    async fn send_end(&self, messages: Vec<AgentMessage>) {
+        let event = AgentEvent::AgentEnd { messages };
+        match self {
+            EventSender::Unbounded(tx) => tx.send(event).ok(),
+            EventSender::Bounded(tx) => tx.send(event).await.ok(),
+        };
+    }
+}
+
+impl From<mpsc::UnboundedSender<AgentEvent>> for EventSender {
+    fn from(tx: mpsc::UnboundedSender<AgentEvent>) -> Self {
+        EventSender::Unbounded(tx)
+    }
+}
+
+impl From<mpsc::Sender<AgentEvent>> for EventSender {
+    fn from(tx: mpsc::Sender<AgentEvent>) -> Self {
+        EventSender::Bounded(tx)
+    }
+}
+
 /// Start an agent loop with new prompt messages.
 pub async fn agent_loop(
     prompts: Vec<AgentMessage>,
     context: &mut AgentContext,
     config: &AgentLoopConfig,
-    tx: mpsc::UnboundedSender<AgentEvent>,
+    tx: impl Into<EventSender>,
     cancel: tokio_util::sync::CancellationToken,
 ) -> Vec<AgentMessage> {
+    let tx = tx.into();
     tx.send(AgentEvent::AgentStart).ok();
 
     // Apply input filters before adding prompts to context
@@ -149,7 +773,7 @@ pub async fn agent_loop(
 
         let mut warnings: Vec<String> = Vec::new();
         for filter in &config.input_filters {
-            match filter.filter(&user_text) {
+            match filter.filter(&user_text).await {
                 FilterResult::Pass => {}
                 FilterResult::Warn(w) => warnings.push(w),
                 FilterResult::Reject(reason) => {
@@ -157,7 +781,7 @@ pub async fn agent_loop(
                         reason: reason.clone(),
                     })
                     .ok();
-                    tx.send(AgentEvent::AgentEnd { messages: vec![] }).ok();
+                    tx.send_end(vec![]).await;
                     return vec![];
                 }
             }
@@ -192,6 +816,7 @@ pub async fn agent_loop(
     // Add prompts to context
     for prompt in &prompts {
         context.messages.push(prompt.clone());
+        notify_message(config, prompt);
     }
 
     tx.send(AgentEvent::TurnStart).ok();
@@ -210,34 +835,68 @@ pub async fn agent_loop(
 
     {
         use tracing::Instrument;
-        run_loop(context, &mut new_messages, config, &tx, &cancel)
+        run_loop(context, &mut new_messages, config, 0, &tx, &cancel)
             .instrument(tracing::info_span!("agent_loop", model = %config.model))
             .await;
     }
 
-    tx.send(AgentEvent::AgentEnd {
-        messages: new_messages.clone(),
-    })
-    .ok();
+    tx.send_end(new_messages.clone()).await;
     new_messages
 }
 
 /// Continue an agent loop from existing context (for retries).
+///
+/// # Panics
+///
+/// If the context has no messages or fails [`AgentContext::validate`] —
+/// e.g. it ends with an assistant message.
 pub async fn agent_loop_continue(
     context: &mut AgentContext,
     config: &AgentLoopConfig,
-    tx: mpsc::UnboundedSender<AgentEvent>,
+    tx: impl Into<EventSender>,
+    cancel: tokio_util::sync::CancellationToken,
+) -> Vec<AgentMessage> {
+    agent_loop_resume(context, 0, config, tx, cancel).await
+}
+
+/// Resume a run from a [`checkpoint`](AgentLoopConfig::checkpoint): a
+/// context restored from what the callback saved, and the turn number it
+/// was saved at.
+///
+/// Like [`agent_loop_continue`], except turn numbering carries on from
+/// `turn` — the next turn is `turn + 1` for `before_turn`, `prepare_turn`
+/// and `checkpoint` — and the completed turns count towards
+/// [`ExecutionLimits::max_turns`](crate::context::ExecutionLimits::max_turns).
+///
+/// A checkpoint taken after the final turn ends with the assistant's reply:
+/// that run is finished and can't be resumed. Check with
+/// [`AgentContext::validate`] first, or append a user message and use
+/// [`agent_loop`] instead.
+///
+/// # Panics
+///
+/// If the context has no messages or fails [`AgentContext::validate`] —
+/// e.g. it ends with an assistant message.
+pub async fn agent_loop_resume(
+    context: &mut AgentContext,
+    turn: usize,
+    config: &AgentLoopConfig,
+    tx: impl Into<EventSender>,
     cancel: tokio_util::sync::CancellationToken,
 ) -> Vec<AgentMessage> {
+    let tx = tx.into();
     assert!(
         !context.messages.is_empty(),
         "Cannot continue: no messages in context"
     );
 
-    if let Some(last) = context.messages.last() {
-        assert!(
-            last.role() != "assistant",
-            "Cannot continue from assistant message"
+    if let Err(errors) = context.validate() {
+        let errors: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        panic!(
+            "Cannot continue from an invalid message history:\n  {}\n\
+             (AgentContext::repair() fixes orphaned tool results, empty messages \
+             and consecutive assistant messages)",
+            errors.join("\n  ")
         );
     }
 
@@ -248,19 +907,116 @@ pub async fn agent_loop_continue(
 
     {
         use tracing::Instrument;
-        run_loop(context, &mut new_messages, config, &tx, &cancel)
+        run_loop(context, &mut new_messages, config, turn, &tx, &cancel)
             .instrument(tracing::info_span!("agent_loop", model = %config.model))
             .await;
     }
 
-    tx.send(AgentEvent::AgentEnd {
-        messages: new_messages.clone(),
-    })
-    .ok();
+    tx.send_end(new_messages.clone()).await;
     new_messages
 }
 
-/// Main loop logic shared by agent_loop and agent_loop_continue.
+/// [`futures::Stream`] of events from an agent loop running on a spawned
+/// task — returned by [`agent_loop_stream`] and
+/// [`agent_loop_continue_stream`].
+///
+/// The stream ends after the terminal [`AgentEvent::AgentEnd`], which
+/// carries the new messages. Await [`finish`](Self::finish) to get the
+/// updated context back. Dropping the stream does not stop the loop; cancel
+/// it through the token passed in.
+///
+/// With [`AgentLoopConfig::event_buffer_size`] set, events that arrive
+/// while the buffer is full are dropped rather than queued; see
+/// [`EventSender`]. The terminal `AgentEnd` is never dropped.
+pub struct AgentEventStream {
+    events: futures::stream::BoxStream<'static, AgentEvent>,
+    handle: tokio::task::JoinHandle<(AgentContext, Vec<AgentMessage>)>,
+}
+
+impl AgentEventStream {
+    /// Wait for the loop to finish and return the context (with the run's
+    /// messages appended) and the new messages. Events not yet read from the
+    /// stream are discarded.
+    pub async fn finish(self) -> (AgentContext, Vec<AgentMessage>) {
+        match self.handle.await {
+            Ok(result) => result,
+            Err(e) => std::panic::resume_unwind(e.into_panic()),
+        }
+    }
+}
+
+impl futures::Stream for AgentEventStream {
+    type Item = AgentEvent;
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<AgentEvent>> {
+        std::pin::Pin::new(&mut self.events).poll_next(cx)
+    }
+}
+
+/// [`agent_loop`] as a [`futures::Stream`]: spawns the loop (so it must be
+/// called from a Tokio runtime) and yields its events.
+pub fn agent_loop_stream(
+    prompts: Vec<AgentMessage>,
+    mut context: AgentContext,
+    config: AgentLoopConfig,
+    cancel: tokio_util::sync::CancellationToken,
+) -> AgentEventStream {
+    let (tx, events) = event_channel(config.event_buffer_size);
+    let handle = tokio::spawn(async move {
+        let new_messages = agent_loop(prompts, &mut context, &config, tx, cancel).await;
+        (context, new_messages)
+    });
+    AgentEventStream { events, handle }
+}
+
+/// [`agent_loop_continue`] as a [`futures::Stream`]; see
+/// [`agent_loop_stream`].
+///
+/// # Panics
+///
+/// The spawned loop panics if `context` has no messages; the panic resurfaces
+/// from [`AgentEventStream::finish`].
+pub fn agent_loop_continue_stream(
+    mut context: AgentContext,
+    config: AgentLoopConfig,
+    cancel: tokio_util::sync::CancellationToken,
+) -> AgentEventStream {
+    let (tx, events) = event_channel(config.event_buffer_size);
+    let handle = tokio::spawn(async move {
+        let new_messages = agent_loop_continue(&mut context, &config, tx, cancel).await;
+        (context, new_messages)
+    });
+    AgentEventStream { events, handle }
+}
+
+/// The event channel for the stream entry points: bounded when
+/// `buffer_size` is set.
+fn event_channel(
+    buffer_size: Option<usize>,
+) -> (EventSender, futures::stream::BoxStream<'static, AgentEvent>) {
+    use futures::StreamExt;
+    match buffer_size {
+        Some(size) => {
+            let (tx, rx) = mpsc::channel(size.max(1));
+            (
+                tx.into(),
+                tokio_stream::wrappers::ReceiverStream::new(rx).boxed(),
+            )
+        }
+        None => {
+            let (tx, rx) = mpsc::unbounded_channel();
+            (
+                tx.into(),
+                tokio_stream::wrappers::UnboundedReceiverStream::new(rx).boxed(),
+            )
+        }
+    }
+}
+
+/// Main loop logic shared by agent_loop and agent_loop_resume.
 ///
 /// Outer loop: continues when follow-up messages arrive after agent would stop.
 /// Inner loop: process tool calls and steering messages.
@@ -268,17 +1024,35 @@ async fn run_loop(
     context: &mut AgentContext,
     new_messages: &mut Vec<AgentMessage>,
     config: &AgentLoopConfig,
-    tx: &mpsc::UnboundedSender<AgentEvent>,
+    start_turn: usize,
+    tx: &EventSender,
     cancel: &tokio_util::sync::CancellationToken,
 ) {
     let mut first_turn = true;
-    let mut turn_number: usize = 0;
+    let mut turn_number = start_turn;
+    // Per-run copy: a forcing choice is relaxed once a tool has been called.
+    let mut tool_choice = config.tool_choice.clone();
+    // Tools the approval callback answered `AlwaysAllow` for, this run only.
+    let always_allowed = std::sync::Mutex::new(std::collections::HashSet::new());
+    // Size messages with the loop model's tokenizer unless the context
+    // config names one.
+    let context_config = config.context_config.as_ref().map(|c| {
+        let mut c = c.clone();
+        if c.model.is_none() && !config.model.is_empty() {
+            c.model = Some(config.model.clone());
+        }
+        c
+    });
     // Blends real provider usage with estimation for compaction sizing.
-    let mut context_tracker = ContextTracker::new();
-    let mut tracker = config
-        .execution_limits
-        .as_ref()
-        .map(|limits| ExecutionTracker::new(limits.clone()));
+    let mut context_tracker = match context_config.as_ref().and_then(|c| c.model.as_deref()) {
+        Some(model) => ContextTracker::new().with_model(model),
+        None => ContextTracker::new(),
+    };
+    let mut tracker = config.execution_limits.as_ref().map(|limits| {
+        let mut tracker = ExecutionTracker::new(limits.clone());
+        tracker.turns = start_turn;
+        tracker
+    });
 
     // Check for steering messages at start
     let mut pending: Vec<AgentMessage> = config
@@ -319,6 +1093,7 @@ async fn run_loop(
                     })
                     .ok();
                     context.messages.push(msg.clone());
+                    notify_message(config, &msg);
                     new_messages.push(msg);
                 }
             }
@@ -342,6 +1117,7 @@ async fn run_loop(
                     })
                     .ok();
                     context.messages.push(limit_msg.clone());
+                    notify_message(config, &limit_msg);
                     new_messages.push(limit_msg);
                     return;
                 }
@@ -353,6 +1129,9 @@ async fn run_loop(
                     return;
                 }
             }
+            if let Some(ref prepare_turn) = config.prepare_turn {
+                prepare_turn(context, turn_number);
+            }
 
             // Inter-turn delay — throttle API calls to stay under rate limits.
             // Skipped on the first turn so the agent starts immediately.
@@ -377,8 +1156,9 @@ async fn run_loop(
             // config because the measured overhead already includes the real
             // system prompt. A floor of 10% of the configured budget
             // guarantees a mis-measured overhead can never wipe the history.
-            if let Some(ref ctx_config) = config.context_config {
-                let estimated = context::total_tokens(&context.messages);
+            if let Some(ref ctx_config) = context_config {
+                let estimated =
+                    context::total_tokens_for(&context.messages, ctx_config.model.as_deref());
                 let hybrid = context_tracker.estimate_context_tokens(&context.messages);
                 let overhead = hybrid.saturating_sub(estimated);
                 let calibrated;
@@ -407,12 +1187,29 @@ async fn run_loop(
                     .as_deref()
                     .unwrap_or(&DefaultCompaction);
                 let before_len = context.messages.len();
-                context.messages =
-                    strategy.compact(std::mem::take(&mut context.messages), effective_config);
+                let (compacted, level) = strategy
+                    .compact_with_level(
+                        std::mem::take(&mut context.messages),
+                        effective_config,
+                        cancel,
+                    )
+                    .await;
+                context.messages = compacted;
                 if context.messages.len() != before_len {
                     // Messages shifted; re-baseline from the next real usage.
                     context_tracker.reset();
                 }
+                if let Some(level) = level {
+                    let after =
+                        context::total_tokens_for(&context.messages, ctx_config.model.as_deref());
+                    notify_compacted(
+                        config,
+                        tx,
+                        (before_len, context.messages.len()),
+                        (estimated, after),
+                        level,
+                    );
+                }
             }
 
             // Stream assistant response, under an llm_stream span that
@@ -427,16 +1224,27 @@ async fn run_loop(
                 cost_usd = tracing::field::Empty,
                 error = tracing::field::Empty,
             );
-            let message = {
+            let llm_started = std::time::Instant::now();
+            let (message, timed_out) = {
                 use tracing::Instrument;
-                stream_assistant_response(context, config, tx, cancel)
-                    .instrument(llm_span.clone())
-                    .await
+                stream_assistant_response(
+                    context,
+                    config,
+                    turn_number - 1,
+                    &tool_choice,
+                    tx,
+                    cancel,
+                )
+                .instrument(llm_span.clone())
+                .await
             };
             if let Message::Assistant {
                 usage, stop_reason, ..
             } = &message
             {
+                if let Some(sink) = &config.metrics_sink {
+                    sink.record_turn(&config.model, usage, llm_started.elapsed());
+                }
                 llm_span.record("error", *stop_reason == StopReason::Error);
                 llm_span.record("tokens_in", usage.input);
                 llm_span.record("tokens_out", usage.output);
@@ -450,11 +1258,19 @@ async fn run_loop(
             // Tool-forcing providers (Anthropic) deliver structured output as
             // a forced tool call — unwrap it into plain text BEFORE tool-call
             // extraction, so the loop never tries to execute the synthetic tool.
-            let message = unwrap_structured_tool_call(message, config.output_schema.as_ref());
+            let structured_tool = match (&config.output_schema, &config.response_format) {
+                (Some(schema), _) => Some(schema.name.as_str()),
+                (None, ResponseFormat::JsonObject) => {
+                    Some(crate::provider::traits::JSON_OBJECT_TOOL_NAME)
+                }
+                (None, ResponseFormat::Text) => None,
+            };
+            let message = unwrap_structured_tool_call(message, structured_tool);
 
             let agent_msg: AgentMessage = message.clone().into();
             context.messages.push(agent_msg.clone());
             new_messages.push(agent_msg.clone());
+            notify_message(config, &agent_msg);
             if let Message::Assistant { usage, .. } = &message {
                 context_tracker.record_usage(usage, context.messages.len() - 1);
             }
@@ -467,6 +1283,26 @@ async fn run_loop(
                 ..
             } = message
             {
+                if timed_out {
+                    // Only this turn is lost: count it, then start the next
+                    // one (subject to limits and before_turn).
+                    warn!("{}", error_message.as_deref().unwrap_or("Turn timed out"));
+                    if let Some(ref mut tracker) = tracker {
+                        tracker.record_turn(0, turn_cost(config, usage));
+                    }
+                    if let Some(ref after_turn) = config.after_turn {
+                        after_turn(&context.messages, usage);
+                    }
+                    if let Some(ref checkpoint) = config.checkpoint {
+                        checkpoint(context, turn_number);
+                    }
+                    tx.send(AgentEvent::TurnEnd {
+                        message: agent_msg,
+                        tool_results: vec![],
+                    })
+                    .ok();
+                    continue;
+                }
                 if *stop_reason == StopReason::Error || *stop_reason == StopReason::Aborted {
                     if *stop_reason == StopReason::Error {
                         if let Some(ref on_error) = config.on_error {
@@ -478,6 +1314,9 @@ async fn run_loop(
                     if let Some(ref after_turn) = config.after_turn {
                         after_turn(&context.messages, usage);
                     }
+                    if let Some(ref checkpoint) = config.checkpoint {
+                        checkpoint(context, turn_number);
+                    }
                     tx.send(AgentEvent::TurnEnd {
                         message: agent_msg,
                         tool_results: vec![],
@@ -505,6 +1344,18 @@ async fn run_loop(
             };
 
             let has_tool_calls = !tool_calls.is_empty();
+            // A paused turn (server tools still running) is resumed by
+            // sending the conversation back as-is.
+            let paused = matches!(
+                message,
+                Message::Assistant {
+                    stop_reason: StopReason::PauseTurn,
+                    ..
+                }
+            );
+            if has_tool_calls && tool_choice.forces_tool_call() {
+                tool_choice = ToolChoice::Auto;
+            }
             let mut tool_results: Vec<Message> = Vec::new();
 
             if has_tool_calls {
@@ -515,7 +1366,20 @@ async fn run_loop(
                     cancel,
                     config.get_steering_messages.as_ref(),
                     &config.tool_execution,
-                    &config.tool_middleware,
+                    ToolRunSettings {
+                        middleware: &config.tool_middleware,
+                        approval: config
+                            .approve_tool
+                            .as_ref()
+                            .map(|approve| ToolApprovalGate {
+                                approve,
+                                always_allowed: &always_allowed,
+                            }),
+                        intercept: config.on_tool_call.as_ref(),
+                        timeout: config.tool_timeout,
+                        cache: config.tool_cache.as_deref(),
+                        metrics: config.metrics_sink.as_deref(),
+                    },
                 )
                 .await;
 
@@ -525,19 +1389,22 @@ async fn run_loop(
                 for result in &tool_results {
                     let am: AgentMessage = result.clone().into();
                     context.messages.push(am.clone());
+                    notify_message(config, &am);
                     new_messages.push(am);
                 }
             }
 
             // Track turn for execution limits
             if let Some(ref mut tracker) = tracker {
-                let turn_tokens = match &message {
-                    Message::Assistant { usage, .. } => {
-                        (usage.input + usage.output + usage.cache_read + usage.cache_write) as usize
-                    }
-                    _ => context::message_tokens(&agent_msg),
+                let (turn_tokens, cost) = match &message {
+                    Message::Assistant { usage, .. } => (
+                        (usage.input + usage.output + usage.cache_read + usage.cache_write)
+                            as usize,
+                        turn_cost(config, usage),
+                    ),
+                    _ => (context::message_tokens(&agent_msg), 0.0),
                 };
-                tracker.record_turn(turn_tokens);
+                tracker.record_turn(turn_tokens, cost);
             }
 
             // after_turn callback
@@ -548,6 +1415,9 @@ async fn run_loop(
                 };
                 after_turn(&context.messages, &usage);
             }
+            if let Some(ref checkpoint) = config.checkpoint {
+                checkpoint(context, turn_number);
+            }
 
             tx.send(AgentEvent::TurnEnd {
                 message: agent_msg,
@@ -555,6 +1425,13 @@ async fn run_loop(
             })
             .ok();
 
+            if let Some(ref stop_if) = config.stop_if {
+                if stop_if(&context.messages) {
+                    tracing::debug!("stop_if matched after turn {}; ending the run", turn_number);
+                    return;
+                }
+            }
+
             // Check steering after turn
             if let Some(steering) = steering_after_tools.take() {
                 if !steering.is_empty() {
@@ -569,8 +1446,9 @@ async fn run_loop(
                 .map(|f| f())
                 .unwrap_or_default();
 
-            // Exit inner loop if no more tool calls and no pending messages
-            if !has_tool_calls && pending.is_empty() {
+            // Exit inner loop if no more tool calls, no paused turn to resume
+            // and no pending messages
+            if !has_tool_calls && !paused && pending.is_empty() {
                 break;
             }
         }
@@ -591,13 +1469,126 @@ async fn run_loop(
     }
 }
 
+/// Pass a message just added to the context to `on_message`.
+fn notify_message(config: &AgentLoopConfig, message: &AgentMessage) {
+    if let Some(ref on_message) = config.on_message {
+        on_message(message);
+    }
+}
+
+/// Report a compaction through the event stream and `on_context_compact`.
+fn notify_compacted(
+    config: &AgentLoopConfig,
+    tx: &EventSender,
+    (messages_before, messages_after): (usize, usize),
+    (tokens_before, tokens_after): (usize, usize),
+    level: context::CompactionLevel,
+) {
+    if let Some(ref on_context_compact) = config.on_context_compact {
+        on_context_compact(tokens_before, tokens_after);
+    }
+    tx.send(AgentEvent::ContextCompacted {
+        messages_before,
+        messages_after,
+        tokens_before,
+        tokens_after,
+        level,
+    })
+    .ok();
+}
+
+/// Estimated dollar cost of one turn's usage, or 0.0 when the model's
+/// pricing isn't configured.
+fn turn_cost(config: &AgentLoopConfig, usage: &Usage) -> f64 {
+    config
+        .model_config
+        .as_ref()
+        .filter(|mc| mc.cost.is_configured())
+        .map_or(0.0, |mc| mc.cost.cost_usd(usage))
+}
+
+/// Argument buffer for a tool call the provider is still streaming.
+struct PartialToolCall {
+    id: String,
+    name: String,
+    buffer: String,
+    last_args: Option<serde_json::Value>,
+}
+
+/// Parse a possibly truncated JSON document.
+///
+/// Complete documents parse as-is. Otherwise an unterminated string is
+/// closed and open objects/arrays are closed in order; if that still doesn't
+/// parse (a dangling key, `:`, or half a literal), the input is cut back to
+/// the last complete member and closed from there. Returns `None` when
+/// nothing usable has arrived yet.
+fn parse_partial_json(buf: &str) -> Option<serde_json::Value> {
+    if let Ok(value) = serde_json::from_str(buf) {
+        return Some(value);
+    }
+
+    let mut closers: Vec<char> = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    // Last position the document can be cut at, with the closers open there.
+    let mut cut: Option<(usize, Vec<char>)> = None;
+    for (i, c) in buf.char_indices() {
+        if in_string {
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                closers.push(if c == '{' { '}' } else { ']' });
+                cut = Some((i + 1, closers.clone()));
+            }
+            '}' | ']' => {
+                closers.pop();
+            }
+            ',' => cut = Some((i, closers.clone())),
+            _ => {}
+        }
+    }
+
+    let close = |prefix: &str, open: &[char]| {
+        let mut doc = prefix.to_string();
+        doc.extend(open.iter().rev());
+        serde_json::from_str(&doc).ok()
+    };
+
+    let mut repaired = buf.to_string();
+    if in_string {
+        if escaped {
+            repaired.pop();
+        }
+        repaired.push('"');
+    }
+    close(&repaired, &closers).or_else(|| {
+        let (at, open) = cut?;
+        close(&buf[..at], &open)
+    })
+}
+
 /// Stream an assistant response from the LLM.
+///
+/// Also returns whether the response was cut off by
+/// [`ExecutionLimits::turn_timeout`](crate::context::ExecutionLimits::turn_timeout),
+/// in which case the message is an `Aborted` placeholder.
 async fn stream_assistant_response(
     context: &AgentContext,
     config: &AgentLoopConfig,
-    tx: &mpsc::UnboundedSender<AgentEvent>,
+    turn: usize,
+    tool_choice: &ToolChoice,
+    tx: &EventSender,
     cancel: &tokio_util::sync::CancellationToken,
-) -> Message {
+) -> (Message, bool) {
     // Apply context transform
     let messages = if let Some(transform) = &config.transform_context {
         transform(context.messages.clone())
@@ -607,11 +1598,67 @@ async fn stream_assistant_response(
 
     // Convert to LLM messages
     let convert = config.convert_to_llm.as_ref();
-    let llm_messages = match convert {
+    let mut llm_messages = match convert {
         Some(f) => f(&messages),
         None => default_convert_to_llm(&messages),
     };
 
+    // Final safety pass: the hooks above run after the loop's compaction
+    // and can push the request back over the window. Compact what is
+    // actually sent, with the configured strategy, rather than let the
+    // provider reject it. `NoopCompaction` reports no level, so the request
+    // goes out as is.
+    if let Some(ctx_config) = &config.context_config {
+        let model = ctx_config.model.as_deref().or(Some(config.model.as_str()));
+        // Conversion drops pin markers; put them back so compaction still
+        // keeps pinned messages.
+        let converted = context::repin(&messages, llm_messages.clone());
+        let before_tokens = context::total_tokens_for(&converted, model);
+        let budget = ctx_config
+            .max_context_tokens
+            .saturating_sub(ctx_config.system_prompt_tokens);
+        if before_tokens > budget {
+            let sized = ContextConfig {
+                model: model.map(str::to_string),
+                ..ctx_config.clone()
+            };
+            let before_len = llm_messages.len();
+            let strategy: &dyn CompactionStrategy = config
+                .compaction_strategy
+                .as_deref()
+                .unwrap_or(&DefaultCompaction);
+            let (compacted, level) = strategy.compact_with_level(converted, &sized, cancel).await;
+            if let Some(level) = level {
+                let after_tokens = context::total_tokens_for(&compacted, model);
+                tracing::warn!(
+                    "request over the context budget after conversion ({} > {} tokens); compacted to {}",
+                    before_tokens,
+                    budget,
+                    after_tokens
+                );
+                llm_messages = compacted
+                    .into_iter()
+                    .filter_map(|m| match m {
+                        AgentMessage::Llm(m) => Some(m),
+                        AgentMessage::Extension(_) => None,
+                    })
+                    .collect();
+                notify_compacted(
+                    config,
+                    tx,
+                    (before_len, llm_messages.len()),
+                    (before_tokens, after_tokens),
+                    level,
+                );
+            }
+        }
+    }
+
+    let system_prompt = match &config.system_prompt_hook {
+        Some(hook) => hook(&context.system_prompt, turn),
+        None => context.system_prompt.clone(),
+    };
+
     // Build tool definitions
     let tool_defs: Vec<ToolDefinition> = context
         .tools
@@ -623,13 +1670,54 @@ async fn stream_assistant_response(
         })
         .collect();
 
+    // The turn timeout spans every retry attempt, including the backoff
+    // sleeps and rate-limiter waits between them.
+    let deadline = config
+        .execution_limits
+        .as_ref()
+        .and_then(|limits| limits.turn_timeout)
+        .map(|limit| (tokio::time::Instant::now() + limit, limit));
+
+    // What each attempt costs against the rate limiter's token budget.
+    let estimated_tokens = match &config.rate_limiter {
+        Some(_) => {
+            let converted: Vec<AgentMessage> = llm_messages
+                .iter()
+                .cloned()
+                .map(AgentMessage::Llm)
+                .collect();
+            context::total_tokens(&converted) + context::estimate_tokens(&system_prompt)
+        }
+        None => 0,
+    };
+
     // Retry loop for transient provider errors
     let retry = &config.retry_config;
     let mut attempt = 0;
-    let result = loop {
+    let (result, done) = loop {
+        // Throttling waits rather than fails; only cancellation ends it.
+        if let Some(limiter) = &config.rate_limiter {
+            match before_deadline(deadline, limiter.acquire(estimated_tokens, cancel)).await {
+                Ok(true) => {}
+                Ok(false) => break (Err(ProviderError::Cancelled), false),
+                Err(limit) => return (turn_timeout_message(config, limit, false, tx), true),
+            }
+        }
+        if let Some(breaker) = &config.circuit_breaker {
+            if let Err(wait) = breaker.try_acquire() {
+                break (
+                    Err(ProviderError::Other(format!(
+                        "Circuit breaker open after repeated provider failures; not calling the provider for another {:.1}s",
+                        wait.as_secs_f64()
+                    ))),
+                    false,
+                );
+            }
+        }
+
         let stream_config = StreamConfig {
             model: config.model.clone(),
-            system_prompt: context.system_prompt.clone(),
+            system_prompt: system_prompt.clone(),
             messages: llm_messages.clone(),
             tools: tool_defs.clone(),
             thinking_level: config.thinking_level,
@@ -639,16 +1727,27 @@ async fn stream_assistant_response(
             model_config: config.model_config.clone(),
             cache_config: config.cache_config.clone(),
             output_schema: config.output_schema.clone(),
+            tool_choice: tool_choice.clone(),
+            stop_sequences: config.stop_sequences.clone(),
+            response_format: config.response_format.clone(),
+            server_tools: config.server_tools.clone(),
         };
 
         let (stream_tx, mut stream_rx) = mpsc::unbounded_channel();
-        let provider_cancel = cancel.clone();
+        // A child token, so a turn timeout stops this call but not the run.
+        let provider_cancel = cancel.child_token();
 
         // Spawn a task to forward events in real-time as the provider streams
         let event_tx = tx.clone();
         let model_for_events = config.model.clone();
+        // Resolves to whether a message was started but never ended, and
+        // whether the provider sent `Done` (its `MessageEnd` is sent once the
+        // final message has been through the output filters).
         let forward_handle = tokio::spawn(async move {
             let mut partial_message: Option<AgentMessage> = None;
+            let mut open = false;
+            let mut done = false;
+            let mut tool_args: HashMap<usize, PartialToolCall> = HashMap::new();
             while let Some(event) = stream_rx.recv().await {
                 match &event {
                     StreamEvent::Start => {
@@ -662,6 +1761,7 @@ async fn stream_assistant_response(
                             error_message: None,
                         });
                         partial_message = Some(placeholder.clone());
+                        open = true;
                         event_tx
                             .send(AgentEvent::MessageStart {
                                 message: placeholder,
@@ -692,7 +1792,25 @@ async fn stream_assistant_response(
                                 .ok();
                         }
                     }
-                    StreamEvent::ToolCallDelta { delta, .. } => {
+                    StreamEvent::ToolCallStart {
+                        content_index,
+                        id,
+                        name,
+                    } => {
+                        tool_args.insert(
+                            *content_index,
+                            PartialToolCall {
+                                id: id.clone(),
+                                name: name.clone(),
+                                buffer: String::new(),
+                                last_args: None,
+                            },
+                        );
+                    }
+                    StreamEvent::ToolCallDelta {
+                        content_index,
+                        delta,
+                    } => {
                         if let Some(ref msg) = partial_message {
                             event_tx
                                 .send(AgentEvent::MessageUpdate {
@@ -703,11 +1821,28 @@ async fn stream_assistant_response(
                                 })
                                 .ok();
                         }
+                        if let Some(call) = tool_args.get_mut(content_index) {
+                            call.buffer.push_str(delta);
+                            if let Some(args) = parse_partial_json(&call.buffer) {
+                                // Fragments that don't change the parsed view
+                                // (e.g. half a number) aren't worth an event.
+                                if call.last_args.as_ref() != Some(&args) {
+                                    call.last_args = Some(args.clone());
+                                    event_tx
+                                        .send(AgentEvent::ToolCallArgsUpdate {
+                                            tool_call_id: call.id.clone(),
+                                            tool_name: call.name.clone(),
+                                            partial_args: args,
+                                        })
+                                        .ok();
+                                }
+                            }
+                        }
                     }
                     StreamEvent::Done { message } => {
-                        let am: AgentMessage = message.clone().into();
-                        partial_message = Some(am.clone());
-                        event_tx.send(AgentEvent::MessageEnd { message: am }).ok();
+                        partial_message = Some(message.clone().into());
+                        open = false;
+                        done = true;
                     }
                     StreamEvent::Error { message } => {
                         let am: AgentMessage = message.clone().into();
@@ -719,19 +1854,42 @@ async fn stream_assistant_response(
                                 .ok();
                         }
                         partial_message = Some(am.clone());
+                        open = false;
                         event_tx.send(AgentEvent::MessageEnd { message: am }).ok();
                     }
                     _ => {}
                 }
             }
+            (open, done)
         });
 
         // Provider streams concurrently — events are forwarded in real-time
         // When provider returns, stream_tx is dropped, ending the forwarder
-        let result = config
+        let stop_provider = provider_cancel.clone();
+        let stream = config
             .provider
-            .stream(stream_config, stream_tx, provider_cancel)
-            .await;
+            .stream(stream_config, stream_tx, provider_cancel);
+        let result = match deadline {
+            Some((deadline, limit)) => {
+                match tokio::time::timeout_at(deadline, stream).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        // The provider future (and its sender) is dropped;
+                        // drain the forwarder so events stay in order.
+                        stop_provider.cancel();
+                        if let Some(breaker) = &config.circuit_breaker {
+                            breaker.record_failure();
+                        }
+                        let (open, _) = forward_handle.await.unwrap_or_default();
+                        return (turn_timeout_message(config, limit, open, tx), true);
+                    }
+                }
+            }
+            None => stream.await,
+        };
+        if let Some(breaker) = &config.circuit_breaker {
+            breaker.record(&result);
+        }
 
         match &result {
             Err(e) if e.is_retryable() && attempt < retry.max_retries && !cancel.is_cancelled() => {
@@ -745,18 +1903,20 @@ async fn stream_assistant_response(
                     .map(|d| d.min(std::time::Duration::from_millis(retry.max_delay_ms)))
                     .unwrap_or_else(|| retry.delay_for_attempt(attempt));
                 crate::retry::log_retry(attempt, retry.max_retries, &delay, e);
-                tokio::time::sleep(delay).await;
+                if let Err(limit) = before_deadline(deadline, tokio::time::sleep(delay)).await {
+                    return (turn_timeout_message(config, limit, false, tx), true);
+                }
                 continue;
             }
             _ => {
                 // Final attempt — wait for forwarder to finish processing remaining events
-                let _ = forward_handle.await;
-                break result;
+                let (_, done) = forward_handle.await.unwrap_or_default();
+                break (result, done);
             }
         }
     };
 
-    match result {
+    let message = match result {
         Ok(msg) => msg,
         Err(e) => {
             warn!("Provider error: {}", e);
@@ -772,7 +1932,85 @@ async fn stream_assistant_response(
                 error_message: Some(e.to_string()),
             }
         }
+    };
+    let message = apply_output_filters(&config.output_filters, message);
+    if done {
+        tx.send(AgentEvent::MessageEnd {
+            message: message.clone().into(),
+        })
+        .ok();
     }
+    (message, false)
+}
+
+/// Run `message` through the output filters in order. A `Block` replaces
+/// the whole content with its text and ends the turn's tool use.
+fn apply_output_filters(filters: &[Arc<dyn OutputFilter>], mut message: Message) -> Message {
+    for filter in filters {
+        match filter.filter(&message) {
+            OutputFilterResult::Pass => {}
+            OutputFilterResult::Redact(redacted) => message = redacted,
+            OutputFilterResult::Block(substitute) => {
+                warn!("Output filter blocked an assistant message");
+                if let Message::Assistant {
+                    content,
+                    stop_reason,
+                    ..
+                } = &mut message
+                {
+                    *content = vec![Content::Text { text: substitute }];
+                    if *stop_reason == StopReason::ToolUse {
+                        *stop_reason = StopReason::Stop;
+                    }
+                }
+                break;
+            }
+        }
+    }
+    message
+}
+
+/// Await `wait` unless the turn deadline passes first, in which case the
+/// turn's limit comes back as the error.
+async fn before_deadline<F: std::future::Future>(
+    deadline: Option<(tokio::time::Instant, std::time::Duration)>,
+    wait: F,
+) -> Result<F::Output, std::time::Duration> {
+    match deadline {
+        Some((at, limit)) => tokio::time::timeout_at(at, wait).await.map_err(|_| limit),
+        None => Ok(wait.await),
+    }
+}
+
+/// The `Aborted` message recorded for a turn that hit `turn_timeout`, sent
+/// as a `MessageEnd` (preceded by `MessageStart` unless the provider had
+/// already started a message).
+fn turn_timeout_message(
+    config: &AgentLoopConfig,
+    limit: std::time::Duration,
+    started: bool,
+    tx: &EventSender,
+) -> Message {
+    let message = Message::Assistant {
+        content: vec![Content::Text {
+            text: String::new(),
+        }],
+        stop_reason: StopReason::Aborted,
+        model: config.model.clone(),
+        provider: "unknown".into(),
+        usage: Usage::default(),
+        timestamp: now_ms(),
+        error_message: Some(format!("Turn timeout after {}s", limit.as_secs_f64())),
+    };
+    let am: AgentMessage = message.clone().into();
+    if !started {
+        tx.send(AgentEvent::MessageStart {
+            message: am.clone(),
+        })
+        .ok();
+    }
+    tx.send(AgentEvent::MessageEnd { message: am }).ok();
+    message
 }
 
 // ---------------------------------------------------------------------------
@@ -785,13 +2023,10 @@ struct ToolExecutionResult {
 }
 
 /// Convert a forced structured-output tool call back into a plain-text
-/// assistant message with `StopReason::Stop`. No-op unless a schema is set
-/// and the message carries a tool call named after it.
-fn unwrap_structured_tool_call(
-    message: Message,
-    schema: Option<&crate::provider::OutputSchema>,
-) -> Message {
-    let Some(schema) = schema else {
+/// assistant message with `StopReason::Stop`. No-op unless structured output
+/// or JSON mode is on and the message carries a call to its synthetic tool.
+fn unwrap_structured_tool_call(message: Message, tool_name: Option<&str>) -> Message {
+    let Some(tool_name) = tool_name else {
         return message;
     };
     let Message::Assistant {
@@ -808,7 +2043,7 @@ fn unwrap_structured_tool_call(
     let Some(payload) = content.iter().find_map(|c| match c {
         Content::ToolCall {
             name, arguments, ..
-        } if *name == schema.name => Some(arguments.clone()),
+        } if name == tool_name => Some(arguments.clone()),
         _ => None,
     }) else {
         return message;
@@ -820,7 +2055,7 @@ fn unwrap_structured_tool_call(
     // last text block.
     let mut new_content: Vec<Content> = content
         .iter()
-        .filter(|c| !matches!(c, Content::ToolCall { name, .. } if *name == schema.name))
+        .filter(|c| !matches!(c, Content::ToolCall { name, .. } if name == tool_name))
         .cloned()
         .collect();
     new_content.push(Content::Text {
@@ -843,28 +2078,59 @@ fn unwrap_structured_tool_call(
     )
 }
 
+/// The run's `approve_tool` callback plus the tools it has always-allowed.
+#[derive(Clone, Copy)]
+struct ToolApprovalGate<'a> {
+    approve: &'a ApproveToolFn,
+    always_allowed: &'a std::sync::Mutex<std::collections::HashSet<String>>,
+}
+
+/// Per-call settings shared by every tool invocation in a turn.
+#[derive(Clone, Copy)]
+struct ToolRunSettings<'a> {
+    middleware: &'a [Arc<dyn ToolMiddleware>],
+    approval: Option<ToolApprovalGate<'a>>,
+    intercept: Option<&'a OnToolCallFn>,
+    timeout: Option<std::time::Duration>,
+    cache: Option<&'a dyn ToolCache>,
+    metrics: Option<&'a dyn MetricsSink>,
+}
+
 async fn execute_tool_calls(
     tools: &[Box<dyn AgentTool>],
     tool_calls: &[(String, String, serde_json::Value)],
-    tx: &mpsc::UnboundedSender<AgentEvent>,
+    tx: &EventSender,
     cancel: &tokio_util::sync::CancellationToken,
     get_steering: Option<&GetMessagesFn>,
     strategy: &ToolExecutionStrategy,
-    middleware: &[Arc<dyn ToolMiddleware>],
+    settings: ToolRunSettings<'_>,
 ) -> ToolExecutionResult {
     match strategy {
         ToolExecutionStrategy::Sequential => {
-            execute_sequential(tools, tool_calls, tx, cancel, get_steering, middleware).await
+            execute_sequential(tools, tool_calls, tx, cancel, get_steering, settings).await
         }
         ToolExecutionStrategy::Parallel => {
-            execute_batch(tools, tool_calls, tx, cancel, get_steering, middleware).await
+            execute_batch(tools, tool_calls, tx, cancel, get_steering, None, settings).await
+        }
+        ToolExecutionStrategy::ThrottledParallel { limit } => {
+            execute_batch(
+                tools,
+                tool_calls,
+                tx,
+                cancel,
+                get_steering,
+                Some(*limit),
+                settings,
+            )
+            .await
         }
         ToolExecutionStrategy::Batched { size } => {
             let mut results: Vec<Message> = Vec::new();
             let mut steering_messages: Option<Vec<AgentMessage>> = None;
 
             for (batch_idx, batch) in tool_calls.chunks(*size).enumerate() {
-                let batch_result = execute_batch(tools, batch, tx, cancel, None, middleware).await;
+                let batch_result =
+                    execute_batch(tools, batch, tx, cancel, None, None, settings).await;
                 results.extend(batch_result.tool_results);
 
                 // Check steering between batches
@@ -896,17 +2162,17 @@ async fn execute_tool_calls(
 async fn execute_sequential(
     tools: &[Box<dyn AgentTool>],
     tool_calls: &[(String, String, serde_json::Value)],
-    tx: &mpsc::UnboundedSender<AgentEvent>,
+    tx: &EventSender,
     cancel: &tokio_util::sync::CancellationToken,
     get_steering: Option<&GetMessagesFn>,
-    middleware: &[Arc<dyn ToolMiddleware>],
+    settings: ToolRunSettings<'_>,
 ) -> ToolExecutionResult {
     let mut results: Vec<Message> = Vec::new();
     let mut steering_messages: Option<Vec<AgentMessage>> = None;
 
     for (index, (id, name, args)) in tool_calls.iter().enumerate() {
         let (result_msg, _is_error) =
-            execute_single_tool(tools, id, name, args, tx, cancel, middleware).await;
+            execute_single_tool(tools, id, name, args, tx, cancel, settings).await;
         results.push(result_msg);
 
         // Check for steering — skip remaining tools if user interrupted
@@ -928,23 +2194,35 @@ async fn execute_sequential(
     }
 }
 
-/// Execute a batch of tool calls concurrently using futures::join_all.
+/// Execute a batch of tool calls concurrently using futures::join_all, or
+/// at most `max_concurrency` at a time.
 async fn execute_batch(
     tools: &[Box<dyn AgentTool>],
     tool_calls: &[(String, String, serde_json::Value)],
-    tx: &mpsc::UnboundedSender<AgentEvent>,
+    tx: &EventSender,
     cancel: &tokio_util::sync::CancellationToken,
     get_steering: Option<&GetMessagesFn>,
-    middleware: &[Arc<dyn ToolMiddleware>],
+    max_concurrency: Option<usize>,
+    settings: ToolRunSettings<'_>,
 ) -> ToolExecutionResult {
     use futures::future::join_all;
+    use futures::StreamExt;
 
     let futures: Vec<_> = tool_calls
         .iter()
-        .map(|(id, name, args)| execute_single_tool(tools, id, name, args, tx, cancel, middleware))
+        .map(|(id, name, args)| execute_single_tool(tools, id, name, args, tx, cancel, settings))
         .collect();
 
-    let batch_results = join_all(futures).await;
+    let batch_results: Vec<_> = match max_concurrency {
+        None => join_all(futures).await,
+        // `buffered` keeps results in call order.
+        Some(limit) => {
+            futures::stream::iter(futures)
+                .buffered(limit.max(1))
+                .collect()
+                .await
+        }
+    };
 
     let results: Vec<Message> = batch_results.into_iter().map(|(msg, _)| msg).collect();
 
@@ -972,15 +2250,15 @@ async fn execute_single_tool(
     id: &str,
     name: &str,
     args: &serde_json::Value,
-    tx: &mpsc::UnboundedSender<AgentEvent>,
+    tx: &EventSender,
     cancel: &tokio_util::sync::CancellationToken,
-    middleware: &[Arc<dyn ToolMiddleware>],
+    settings: ToolRunSettings<'_>,
 ) -> (Message, bool) {
     // Middleware chain runs first: each hook may rewrite the args seen by
     // later hooks; the first Deny short-circuits into an error tool result
     // (the LLM sees the reason and can adapt — the loop continues).
     let mut effective_args = args.clone();
-    for mw in middleware {
+    for mw in settings.middleware {
         let call = ToolCallRequest {
             tool_call_id: id,
             tool_name: name,
@@ -1002,12 +2280,33 @@ async fn execute_single_tool(
             ToolDecision::Allow => {}
             ToolDecision::Modify(new_args) => effective_args = new_args,
             ToolDecision::Deny(reason) => {
-                return denied_tool_call(id, name, &effective_args, &reason, tx);
+                return denied_tool_call(id, name, &effective_args, &reason, "middleware", tx);
             }
         }
     }
     let args = &effective_args;
 
+    if let Some(gate) = settings.approval {
+        let pre_approved = gate.always_allowed.lock().unwrap().contains(name);
+        if !pre_approved {
+            tx.send(AgentEvent::ToolApprovalRequired {
+                tool_call_id: id.to_string(),
+                tool_name: name.to_string(),
+                args: args.clone(),
+            })
+            .ok();
+            match (gate.approve)(id, name, args) {
+                ToolApproval::Allow => {}
+                ToolApproval::AlwaysAllow => {
+                    gate.always_allowed.lock().unwrap().insert(name.to_string());
+                }
+                ToolApproval::Deny(reason) => {
+                    return denied_tool_call(id, name, args, &reason, "approval", tx);
+                }
+            }
+        }
+    }
+
     let tool = tools.iter().find(|t| t.name() == name);
 
     // The Start event carries the effective (post-middleware) args — what
@@ -1047,12 +2346,15 @@ async fn execute_single_tool(
         }))
     };
 
+    let timeout = tool.and_then(|t| t.timeout()).or(settings.timeout);
+    let tool_cancel = cancel.child_token();
     let ctx = ToolContext {
         tool_call_id: id.to_string(),
         tool_name: name.to_string(),
-        cancel: cancel.child_token(),
+        cancel: tool_cancel.clone(),
         on_update,
         on_progress,
+        timeout,
     };
 
     let tool_span = tracing::info_span!(
@@ -1062,37 +2364,104 @@ async fn execute_single_tool(
         is_error = tracing::field::Empty,
     );
     use tracing::Instrument;
-    let (result, is_error) = match tool {
-        Some(tool) => {
-            let execution = tool
-                .execute(args.clone(), ctx)
-                .instrument(tool_span.clone())
-                .await;
-            match execution {
-                Ok(r) => (r, false),
-                Err(e) => (
-                    ToolResult {
-                        content: vec![Content::Text {
-                            text: e.to_string(),
-                        }],
-                        details: serde_json::Value::Null,
+    let started = std::time::Instant::now();
+    let intercepted = settings.intercept.and_then(|f| f(id, name, args));
+    let was_intercepted = intercepted.is_some();
+    let cacheable = tool.is_some_and(|t| t.cacheable());
+    let cached = match intercepted {
+        Some(_) => None,
+        None if cacheable => settings.cache.and_then(|cache| cache.get(name, args)),
+        None => None,
+    };
+    let from_cache = cached.is_some();
+    let (result, is_error) = if let Some(mut result) = intercepted {
+        mark_details(&mut result.details, "intercepted");
+        (result, false)
+    } else if let Some(mut hit) = cached {
+        mark_details(&mut hit.details, "from_cache");
+        (hit, false)
+    } else {
+        match tool {
+            Some(tool) => {
+                let validation = validate_tool_args(tool.as_ref(), args);
+                let execution = tool
+                    .execute(args.clone(), ctx)
+                    .instrument(tool_span.clone());
+                // Errors as the text the LLM sees.
+                let execution = match (validation, timeout) {
+                    (Err(e), _) => Err(e.to_string()),
+                    (Ok(()), Some(limit)) => match tokio::time::timeout(limit, execution).await {
+                        Ok(result) => result.map_err(|e| e.to_string()),
+                        Err(_) => {
+                            // Dropping the future stops it at its next await point;
+                            // cancelling the token also reaches any work the tool
+                            // spawned off the future (child processes, tasks).
+                            tool_cancel.cancel();
+                            tracing::warn!(
+                                tool = name,
+                                tool_call_id = id,
+                                ?limit,
+                                "tool call timed out"
+                            );
+                            Err(format!(
+                                "Tool '{}' timed out after {}s",
+                                name,
+                                limit.as_secs_f64()
+                            ))
+                        }
                     },
-                    true,
-                ),
+                    (Ok(()), None) => execution.await.map_err(|e| e.to_string()),
+                };
+                match execution {
+                    Ok(r) => (r, false),
+                    Err(text) => (
+                        ToolResult {
+                            content: vec![Content::Text { text }],
+                            details: serde_json::Value::Null,
+                        },
+                        true,
+                    ),
+                }
             }
+            None => (
+                ToolResult {
+                    content: vec![Content::Text {
+                        text: format!("Tool {} not found", name),
+                    }],
+                    details: serde_json::Value::Null,
+                },
+                true,
+            ),
         }
-        None => (
-            ToolResult {
-                content: vec![Content::Text {
-                    text: format!("Tool {} not found", name),
-                }],
-                details: serde_json::Value::Null,
-            },
-            true,
-        ),
     };
 
+    if !from_cache && !was_intercepted {
+        if let Some(cache) = settings.cache {
+            if !cacheable {
+                // The call may have written files or run commands, so any
+                // cached read could now be stale.
+                if tool.is_some() {
+                    cache.clear();
+                }
+            } else if !is_error {
+                cache.insert(name, args, result.clone());
+            }
+        }
+    }
+
     tool_span.record("is_error", is_error);
+    if let Some(metrics) = settings.metrics {
+        let duration = started.elapsed();
+        metrics.record_tool_call(name, is_error, duration);
+        metrics.record_tool_result(&crate::metrics::ToolCallRecord {
+            tool_call_id: id,
+            tool_name: name,
+            args,
+            result: &result,
+            is_error,
+            duration,
+        });
+    }
 
     tx.send(AgentEvent::ToolExecutionEnd {
         tool_call_id: id.to_string(),
@@ -1122,14 +2491,62 @@ async fn execute_single_tool(
     (tool_result_msg, is_error)
 }
 
-/// Emit events and build the error tool result for a middleware-denied call.
-/// Start/End are both emitted so UI event pairing stays intact.
+/// Check `args` against the tool's parameter schema, unless the tool opts
+/// out. A schema that doesn't compile isn't enforced — it is logged rather
+/// than allowed to block every call.
+fn validate_tool_args(tool: &dyn AgentTool, args: &serde_json::Value) -> Result<(), ToolError> {
+    if !tool.validate_args() {
+        return Ok(());
+    }
+    let validator = match jsonschema::validator_for(&tool.parameters_schema()) {
+        Ok(validator) => validator,
+        Err(e) => {
+            tracing::warn!(
+                tool = tool.name(),
+                error = %e,
+                "invalid parameter schema; skipping argument validation"
+            );
+            return Ok(());
+        }
+    };
+    let errors: Vec<String> = validator
+        .iter_errors(args)
+        .map(|e| match e.instance_path().to_string() {
+            path if path.is_empty() => e.to_string(),
+            path => format!("{}: {}", path, e),
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(ToolError::InvalidArgs(errors.join("; ")))
+    }
+}
+
+/// Flag a cached or intercepted result in its `details` (`"from_cache":
+/// true`, `"intercepted": true`), wrapping non-object details so the
+/// original value is kept.
+fn mark_details(details: &mut serde_json::Value, flag: &str) {
+    match details.take() {
+        serde_json::Value::Object(mut map) => {
+            map.insert(flag.into(), serde_json::Value::Bool(true));
+            *details = serde_json::Value::Object(map);
+        }
+        serde_json::Value::Null => *details = serde_json::json!({ flag: true }),
+        other => *details = serde_json::json!({ flag: true, "details": other }),
+    }
+}
+
+/// Emit events and build the error tool result for a call denied by
+/// middleware or the approval callback (`by`). Start/End are both emitted so
+/// UI event pairing stays intact.
 fn denied_tool_call(
     id: &str,
     name: &str,
     args: &serde_json::Value,
     reason: &str,
-    tx: &mpsc::UnboundedSender<AgentEvent>,
+    by: &'static str,
+    tx: &EventSender,
 ) -> (Message, bool) {
     // Operator-visible signal: without this, a denial exists only in the
     // event stream / message history, invisible to telemetry.
@@ -1137,7 +2554,8 @@ fn denied_tool_call(
         tool = name,
         tool_call_id = id,
         reason,
-        "tool call denied by middleware"
+        by,
+        "tool call denied"
     );
     tx.send(AgentEvent::ToolExecutionStart {
         tool_call_id: id.to_string(),
@@ -1181,11 +2599,7 @@ fn denied_tool_call(
     (msg, true)
 }
 
-fn skip_tool_call(
-    tool_call_id: &str,
-    tool_name: &str,
-    tx: &mpsc::UnboundedSender<AgentEvent>,
-) -> Message {
+fn skip_tool_call(tool_call_id: &str, tool_name: &str, tx: &EventSender) -> Message {
     let result = ToolResult {
         content: vec![Content::Text {
             text: "Skipped due to queued user message.".into(),
@@ -1,45 +1,97 @@
 //! Context window management — smart truncation and token counting.
 //!
 //! The #1 engineering challenge for agents. This module provides:
-//! - Token estimation (fast, no external deps)
+//! - Token estimation (fast chars/4, or BPE with the `tiktoken` feature)
 //! - Tiered compaction (tool output truncation → turn summarization → full summary)
 //! - Execution limits (max turns, tokens, duration)
 //!
 //! Designed based on Claude Code's approach: clear old tool outputs first,
 //! then summarize conversation if needed.
 
+use crate::provider::{ModelConfig, StreamConfig, StreamProvider};
 use crate::types::*;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
 
 // ---------------------------------------------------------------------------
 // Token estimation
 // ---------------------------------------------------------------------------
 
-/// Rough token estimate: ~4 chars per token for English text.
-/// Good enough for context budgeting. Use tiktoken-rs for precision.
+/// Token estimate for context budgeting.
+///
+/// By default a rough ~4 chars per token, which drifts for code and
+/// non-English text. With the `tiktoken` feature enabled this is the
+/// `cl100k_base` BPE count instead; use [`estimate_tokens_for`] to pick the
+/// encoder for a specific model.
 pub fn estimate_tokens(text: &str) -> usize {
-    text.len().div_ceil(4)
+    #[cfg(feature = "tiktoken")]
+    {
+        tiktoken_rs::cl100k_base_singleton()
+            .encode_with_special_tokens(text)
+            .len()
+    }
+    #[cfg(not(feature = "tiktoken"))]
+    {
+        text.len().div_ceil(4)
+    }
+}
+
+/// Estimate tokens for `text` as seen by `model`.
+///
+/// With the `tiktoken` feature enabled this runs the model's BPE encoder
+/// (models tiktoken doesn't know, e.g. Claude or Gemini, use `cl100k_base`,
+/// which is within a few percent for code and English). Without the feature,
+/// or when `model` is `None`, this is [`estimate_tokens`].
+pub fn estimate_tokens_for(text: &str, model: Option<&str>) -> usize {
+    match model {
+        #[cfg(feature = "tiktoken")]
+        Some(model) => bpe_for_model(model).encode_with_special_tokens(text).len(),
+        #[cfg(not(feature = "tiktoken"))]
+        Some(_) => estimate_tokens(text),
+        None => estimate_tokens(text),
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+fn bpe_for_model(model: &str) -> &'static tiktoken_rs::CoreBPE {
+    use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+    // Strip provider prefixes like "openai/gpt-4o" (OpenRouter style).
+    let name = model.rsplit('/').next().unwrap_or(model);
+    match get_tokenizer(name) {
+        Some(Tokenizer::O200kBase) => tiktoken_rs::o200k_base_singleton(),
+        Some(Tokenizer::P50kBase) => tiktoken_rs::p50k_base_singleton(),
+        Some(Tokenizer::P50kEdit) => tiktoken_rs::p50k_edit_singleton(),
+        Some(Tokenizer::R50kBase) | Some(Tokenizer::Gpt2) => tiktoken_rs::r50k_base_singleton(),
+        _ => tiktoken_rs::cl100k_base_singleton(),
+    }
 }
 
 /// Estimate tokens for a single message
 pub fn message_tokens(msg: &AgentMessage) -> usize {
+    message_tokens_for(msg, None)
+}
+
+/// Estimate tokens for a single message as seen by `model`.
+/// See [`estimate_tokens_for`].
+pub fn message_tokens_for(msg: &AgentMessage, model: Option<&str>) -> usize {
     match msg {
         AgentMessage::Llm(m) => match m {
-            Message::User { content, .. } => content_tokens(content) + 4,
-            Message::Assistant { content, .. } => content_tokens(content) + 4,
+            Message::User { content, .. } => content_tokens(content, model) + 4,
+            Message::Assistant { content, .. } => content_tokens(content, model) + 4,
             Message::ToolResult {
                 content, tool_name, ..
-            } => content_tokens(content) + estimate_tokens(tool_name) + 8,
+            } => content_tokens(content, model) + estimate_tokens_for(tool_name, model) + 8,
         },
-        AgentMessage::Extension(ext) => estimate_tokens(&ext.data.to_string()) + 4,
+        AgentMessage::Extension(ext) => estimate_tokens_for(&ext.data.to_string(), model) + 4,
     }
 }
 
-fn content_tokens(content: &[Content]) -> usize {
+fn content_tokens(content: &[Content], model: Option<&str>) -> usize {
     content
         .iter()
         .map(|c| match c {
-            Content::Text { text } => estimate_tokens(text),
+            Content::Text { text } => estimate_tokens_for(text, model),
             Content::Image { data, .. } => {
                 // Estimate tokens from base64 data length:
                 // base64 len * 3/4 = raw bytes; ~750 bytes per token for images.
@@ -47,17 +99,39 @@ fn content_tokens(content: &[Content]) -> usize {
                 let raw_bytes = data.len() * 3 / 4;
                 (raw_bytes / 750).clamp(85, 16_000)
             }
-            Content::Thinking { thinking, .. } => estimate_tokens(thinking),
+            // Same rate as images, uncapped: documents run to many pages.
+            Content::Document { data, .. } => (data.len() / 750).max(85),
+            // Size unknown until the provider fetches it.
+            Content::DocumentUrl { url, .. } => estimate_tokens_for(url, model) + 85,
+            Content::Thinking { thinking, .. } => estimate_tokens_for(thinking, model),
             Content::ToolCall {
                 name, arguments, ..
-            } => estimate_tokens(name) + estimate_tokens(&arguments.to_string()) + 8,
+            } => {
+                estimate_tokens_for(name, model)
+                    + estimate_tokens_for(&arguments.to_string(), model)
+                    + 8
+            }
+            Content::ServerToolUse { name, input, .. } => {
+                estimate_tokens_for(name, model)
+                    + estimate_tokens_for(&input.to_string(), model)
+                    + 8
+            }
+            Content::ServerToolResult { content, .. } => {
+                estimate_tokens_for(&content.to_string(), model) + 8
+            }
         })
         .sum()
 }
 
 /// Estimate total tokens for a message list
 pub fn total_tokens(messages: &[AgentMessage]) -> usize {
-    messages.iter().map(message_tokens).sum()
+    total_tokens_for(messages, None)
+}
+
+/// Estimate total tokens for a message list as seen by `model`.
+/// See [`estimate_tokens_for`].
+pub fn total_tokens_for(messages: &[AgentMessage], model: Option<&str>) -> usize {
+    messages.iter().map(|m| message_tokens_for(m, model)).sum()
 }
 
 // ---------------------------------------------------------------------------
@@ -85,6 +159,8 @@ pub struct ContextTracker {
     last_usage_tokens: Option<usize>,
     /// Index of the message that had the last usage
     last_usage_index: Option<usize>,
+    /// Model used to pick a tokenizer for trailing-message estimates
+    model: Option<String>,
 }
 
 impl ContextTracker {
@@ -92,9 +168,17 @@ impl ContextTracker {
         Self {
             last_usage_tokens: None,
             last_usage_index: None,
+            model: None,
         }
     }
 
+    /// Estimate trailing messages with `model`'s tokenizer
+    /// (see [`estimate_tokens_for`]).
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = Some(model.into());
+        self
+    }
+
     /// Record usage from an assistant response.
     ///
     /// Call this after each assistant message to update the tracker
@@ -110,15 +194,15 @@ impl ContextTracker {
     /// Estimate current context size.
     ///
     /// Uses real usage from the last assistant response as a baseline,
-    /// then adds estimates (chars/4) for any messages added since.
+    /// then adds estimates for any messages added since.
     /// Falls back to pure estimation if no usage data is available.
     pub fn estimate_context_tokens(&self, messages: &[AgentMessage]) -> usize {
+        let model = self.model.as_deref();
         match (self.last_usage_tokens, self.last_usage_index) {
             (Some(usage_tokens), Some(idx)) if idx < messages.len() => {
-                let trailing: usize = messages[idx + 1..].iter().map(message_tokens).sum();
-                usage_tokens + trailing
+                usage_tokens + total_tokens_for(&messages[idx + 1..], model)
             }
-            _ => total_tokens(messages),
+            _ => total_tokens_for(messages, model),
         }
     }
 
@@ -152,6 +236,10 @@ pub struct ContextConfig {
     pub keep_first: usize,
     /// Max lines to keep per tool output in Level 1 compaction
     pub tool_output_max_lines: usize,
+    /// Model whose tokenizer sizes messages (see [`estimate_tokens_for`]).
+    /// `None` uses the chars/4 estimate.
+    #[serde(default)]
+    pub model: Option<String>,
 }
 
 impl Default for ContextConfig {
@@ -162,6 +250,7 @@ impl Default for ContextConfig {
             keep_recent: 10,
             keep_first: 2,
             tool_output_max_lines: 50,
+            model: None,
         }
     }
 }
@@ -193,11 +282,62 @@ impl ContextConfig {
 ///
 /// See the [Custom Compaction](https://yologdev.github.io/yoagent/concepts/agent-loop.html#custom-compaction)
 /// docs for examples.
+#[async_trait::async_trait]
 pub trait CompactionStrategy: Send + Sync {
     /// Compact messages to fit within the token budget defined by `config`.
-    ///
-    /// Called before each LLM turn when `context_config` is set.
     fn compact(&self, messages: Vec<AgentMessage>, config: &ContextConfig) -> Vec<AgentMessage>;
+
+    /// What the loop calls before each LLM turn when `context_config` is
+    /// set. Defaults to [`compact`](Self::compact); override it for
+    /// strategies that need I/O, like [`LlmSummarize`], and stop early when
+    /// `cancel` fires.
+    async fn compact_async(
+        &self,
+        messages: Vec<AgentMessage>,
+        config: &ContextConfig,
+        _cancel: &CancellationToken,
+    ) -> Vec<AgentMessage> {
+        self.compact(messages, config)
+    }
+
+    /// [`compact_async`](Self::compact_async), also reporting the deepest
+    /// [`CompactionLevel`] applied, or `None` if nothing changed. The loop
+    /// calls this to fill in [`AgentEvent::ContextCompacted`].
+    ///
+    /// The built-in strategies report their level exactly. The default
+    /// guesses from the result: fewer messages is `Level3`, same count but
+    /// fewer tokens is `Level1`. Override it if your strategy knows better.
+    async fn compact_with_level(
+        &self,
+        messages: Vec<AgentMessage>,
+        config: &ContextConfig,
+        cancel: &CancellationToken,
+    ) -> (Vec<AgentMessage>, Option<CompactionLevel>) {
+        let model = config.model.as_deref();
+        let (len, tokens) = (messages.len(), total_tokens_for(&messages, model));
+        let compacted = self.compact_async(messages, config, cancel).await;
+        let level = if compacted.len() < len {
+            Some(CompactionLevel::Level3)
+        } else if total_tokens_for(&compacted, model) < tokens {
+            Some(CompactionLevel::Level1)
+        } else {
+            None
+        };
+        (compacted, level)
+    }
+}
+
+/// The tiers of [`compact_messages`], from cheapest to most lossy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompactionLevel {
+    /// Long tool outputs truncated to head + tail.
+    Level1,
+    /// Old turns replaced by one-line (or LLM-written) summaries.
+    Level2,
+    /// Middle of the history dropped, keeping the first and most recent
+    /// messages.
+    Level3,
 }
 
 /// Default 3-level compaction: truncate tool outputs → summarize turns → drop middle.
@@ -207,16 +347,415 @@ pub trait CompactionStrategy: Send + Sync {
 /// then delegate to `compact_messages()` for the actual reduction.
 pub struct DefaultCompaction;
 
+#[async_trait::async_trait]
 impl CompactionStrategy for DefaultCompaction {
     fn compact(&self, messages: Vec<AgentMessage>, config: &ContextConfig) -> Vec<AgentMessage> {
         compact_messages(messages, config)
     }
+
+    async fn compact_with_level(
+        &self,
+        messages: Vec<AgentMessage>,
+        config: &ContextConfig,
+        _cancel: &CancellationToken,
+    ) -> (Vec<AgentMessage>, Option<CompactionLevel>) {
+        compact_messages_with_level(messages, config)
+    }
+}
+
+/// Compaction strategy that never drops or rewrites anything.
+///
+/// Use it to turn compaction off while keeping `context_config` set (say, for
+/// its token estimates), or in tests that need the full history.
+pub struct NoopCompaction;
+
+#[async_trait::async_trait]
+impl CompactionStrategy for NoopCompaction {
+    fn compact(&self, messages: Vec<AgentMessage>, _config: &ContextConfig) -> Vec<AgentMessage> {
+        messages
+    }
+
+    async fn compact_with_level(
+        &self,
+        messages: Vec<AgentMessage>,
+        _config: &ContextConfig,
+        _cancel: &CancellationToken,
+    ) -> (Vec<AgentMessage>, Option<CompactionLevel>) {
+        (messages, None)
+    }
+}
+
+/// Compaction that asks an LLM to summarize the older part of the history.
+///
+/// Tool outputs are truncated first (level 1); if that isn't enough, every
+/// message before the last `keep_recent` is sent to the provider in a
+/// one-shot request and replaced by a single `[Summary] ...` user message,
+/// followed by any [pinned] messages from that span.
+/// If the provider fails or the run is cancelled, it falls back to
+/// [`compact_messages`]. The sync [`compact`](CompactionStrategy::compact)
+/// can't call the provider and always uses the fallback.
+pub struct LlmSummarize {
+    provider: Arc<dyn StreamProvider>,
+    model: String,
+    api_key: String,
+    model_config: Option<ModelConfig>,
+    max_tokens: u32,
+}
+
+const SUMMARY_PROMPT: &str = "You compress conversation history for an AI agent. Summarize the \
+transcript you are given: the user's goals, decisions made, facts learned, files or resources \
+touched, and work still pending. Be concise and factual; write only the summary.";
+
+impl LlmSummarize {
+    pub fn new(
+        provider: Arc<dyn StreamProvider>,
+        model: impl Into<String>,
+        api_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            api_key: api_key.into(),
+            model_config: None,
+            max_tokens: 1024,
+        }
+    }
+
+    pub fn with_model_config(mut self, config: ModelConfig) -> Self {
+        self.model_config = Some(config);
+        self
+    }
+
+    /// Cap on the summary's length. Defaults to 1024 tokens.
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
+
+    async fn summarize(
+        &self,
+        span: &[AgentMessage],
+        cancel: &CancellationToken,
+    ) -> Result<String, String> {
+        let mut config = StreamConfig::new(self.model.clone(), self.api_key.clone());
+        config.system_prompt = SUMMARY_PROMPT.into();
+        config.messages = vec![Message::user(render_transcript(span))];
+        config.max_tokens = Some(self.max_tokens);
+        config.model_config = self.model_config.clone();
+
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let message = self
+            .provider
+            .stream(config, tx, cancel.clone())
+            .await
+            .map_err(|e| e.to_string())?;
+        match message {
+            Message::Assistant {
+                stop_reason: StopReason::Error | StopReason::Aborted,
+                error_message,
+                ..
+            } => Err(error_message.unwrap_or_else(|| "summarization failed".into())),
+            Message::Assistant { content, .. } => {
+                let text = content
+                    .iter()
+                    .filter_map(|c| match c {
+                        Content::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                if text.trim().is_empty() {
+                    Err("empty summary".into())
+                } else {
+                    Ok(text.trim().to_string())
+                }
+            }
+            _ => Err("unexpected response".into()),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl CompactionStrategy for LlmSummarize {
+    fn compact(&self, messages: Vec<AgentMessage>, config: &ContextConfig) -> Vec<AgentMessage> {
+        compact_messages(messages, config)
+    }
+
+    async fn compact_async(
+        &self,
+        messages: Vec<AgentMessage>,
+        config: &ContextConfig,
+        cancel: &CancellationToken,
+    ) -> Vec<AgentMessage> {
+        self.compact_with_level(messages, config, cancel).await.0
+    }
+
+    async fn compact_with_level(
+        &self,
+        messages: Vec<AgentMessage>,
+        config: &ContextConfig,
+        cancel: &CancellationToken,
+    ) -> (Vec<AgentMessage>, Option<CompactionLevel>) {
+        let budget = config
+            .max_context_tokens
+            .saturating_sub(config.system_prompt_tokens);
+        let model = config.model.as_deref();
+        if total_tokens_for(&messages, model) <= budget {
+            return (messages, None);
+        }
+        let truncated = level1_truncate_tool_outputs(&messages, config.tool_output_max_lines);
+        if total_tokens_for(&truncated, model) <= budget {
+            return (truncated, Some(CompactionLevel::Level1));
+        }
+
+        let boundary = turn_start(
+            &truncated,
+            truncated.len().saturating_sub(config.keep_recent),
+        );
+        if boundary == 0 || cancel.is_cancelled() {
+            return compact_messages_with_level(messages, config);
+        }
+
+        match self.summarize(&truncated[..boundary], cancel).await {
+            Ok(summary) => {
+                let mut result = vec![AgentMessage::Llm(Message::User {
+                    content: vec![Content::Text {
+                        text: format!("[Summary] {}", summary),
+                    }],
+                    timestamp: now_ms(),
+                })];
+                // Pinned messages are summarized too, but also kept as is.
+                let pinned = pinned_flags(&truncated);
+                result.extend(
+                    truncated[..boundary]
+                        .iter()
+                        .zip(&pinned)
+                        .filter(|(_, &p)| p)
+                        .map(|(msg, _)| msg.clone()),
+                );
+                result.extend_from_slice(&truncated[boundary..]);
+                if total_tokens_for(&result, model) <= budget {
+                    (result, Some(CompactionLevel::Level2))
+                } else {
+                    (
+                        level3_drop_middle(&result, config, budget),
+                        Some(CompactionLevel::Level3),
+                    )
+                }
+            }
+            Err(e) => {
+                tracing::warn!(error = %e, "LLM summarization failed; using heuristic compaction");
+                compact_messages_with_level(messages, config)
+            }
+        }
+    }
+}
+
+/// Plain-text rendering of a history span for the summarizer.
+fn render_transcript(messages: &[AgentMessage]) -> String {
+    let mut out = String::new();
+    for msg in messages {
+        let AgentMessage::Llm(msg) = msg else {
+            continue;
+        };
+        let (speaker, content) = match msg {
+            Message::User { content, .. } => ("User", content),
+            Message::Assistant { content, .. } => ("Assistant", content),
+            Message::ToolResult {
+                tool_name, content, ..
+            } => {
+                out.push_str(&format!("Tool result ({}):", tool_name));
+                for c in content {
+                    if let Content::Text { text } = c {
+                        out.push(' ');
+                        out.push_str(text);
+                    }
+                }
+                out.push_str("\n\n");
+                continue;
+            }
+        };
+        out.push_str(speaker);
+        out.push(':');
+        for c in content {
+            match c {
+                Content::Text { text } => {
+                    out.push(' ');
+                    out.push_str(text);
+                }
+                Content::ToolCall {
+                    name, arguments, ..
+                } => out.push_str(&format!(" [called {} {}]", name, arguments)),
+                Content::Image { .. } => out.push_str(" [image]"),
+                Content::Document { filename, .. } => {
+                    out.push_str(&format!(" [document {}]", filename))
+                }
+                Content::DocumentUrl { url, .. } => out.push_str(&format!(" [document {}]", url)),
+                _ => {}
+            }
+        }
+        out.push_str("\n\n");
+    }
+    out
+}
+
+// ---------------------------------------------------------------------------
+// Pinned messages
+// ---------------------------------------------------------------------------
+
+/// [`ExtensionMessage::kind`] of a pin marker: the message right after it
+/// is pinned. See [`pinned`].
+pub const PIN_KIND: &str = "pin";
+
+/// `message` preceded by a pin marker, ready to push onto a history.
+///
+/// Compaction never drops or summarizes a pinned message, wherever it sits;
+/// a pinned assistant message keeps its tool results too, and a pinned tool
+/// result keeps the call it answers (with that call's other results). Use it
+/// for instructions that must outlive a long run:
+///
+/// ```rust
+/// use yoagent::context::pinned;
+/// use yoagent::types::{AgentMessage, Message};
+///
+/// let mut messages: Vec<AgentMessage> = Vec::new();
+/// messages.extend(pinned(Message::user("From now on, answer in French.")));
+/// ```
+///
+/// The marker is an [`AgentMessage::Extension`], so it never reaches the
+/// LLM. The pre-call safety pass on the converted request restores markers
+/// for pinned messages that conversion passed through unchanged.
+pub fn pinned(message: impl Into<AgentMessage>) -> [AgentMessage; 2] {
+    [
+        AgentMessage::Extension(ExtensionMessage::new(PIN_KIND, serde_json::Value::Null)),
+        message.into(),
+    ]
+}
+
+/// Whether `msg` is a pin marker (see [`pinned`]).
+pub fn is_pin_marker(msg: &AgentMessage) -> bool {
+    matches!(msg, AgentMessage::Extension(ext) if ext.kind == PIN_KIND)
+}
+
+/// For each message, whether compaction must keep it: pin markers, the
+/// message after each marker, the tool results of a pinned assistant
+/// message, and the call (with its other results) of a pinned tool result.
+fn pinned_flags(messages: &[AgentMessage]) -> Vec<bool> {
+    let mut flags = vec![false; messages.len()];
+    for i in 0..messages.len() {
+        if !is_pin_marker(&messages[i]) {
+            continue;
+        }
+        flags[i] = true;
+        let Some(target) = messages.get(i + 1) else {
+            break;
+        };
+        flags[i + 1] = true;
+        let call = match target {
+            AgentMessage::Llm(Message::Assistant { .. }) => Some(i + 1),
+            AgentMessage::Llm(Message::ToolResult { tool_call_id, .. }) => {
+                messages[..i].iter().rposition(|m| match m {
+                    AgentMessage::Llm(Message::Assistant { content, .. }) => content
+                        .iter()
+                        .any(|c| matches!(c, Content::ToolCall { id, .. } if id == tool_call_id)),
+                    _ => false,
+                })
+            }
+            _ => None,
+        };
+        let Some(call) = call else {
+            continue;
+        };
+        flags[call] = true;
+        // The call's results, and any pin markers among them.
+        let mut j = call + 1;
+        while let Some(msg) = messages.get(j) {
+            if !matches!(msg, AgentMessage::Llm(Message::ToolResult { .. })) && !is_pin_marker(msg)
+            {
+                break;
+            }
+            flags[j] = true;
+            j += 1;
+        }
+    }
+    flags
+}
+
+/// `converted` as [`AgentMessage`]s, with a pin marker restored before each
+/// message pinned in `original`. Pinned messages are matched by equality, in
+/// order, so pins survive a conversion that passes them through unchanged.
+pub(crate) fn repin(original: &[AgentMessage], converted: Vec<Message>) -> Vec<AgentMessage> {
+    let mut targets = original
+        .windows(2)
+        .filter(|pair| is_pin_marker(&pair[0]))
+        .filter_map(|pair| pair[1].as_llm())
+        .peekable();
+    let mut out = Vec::with_capacity(converted.len());
+    for msg in converted {
+        if targets.peek() == Some(&&msg) {
+            targets.next();
+            out.push(AgentMessage::Extension(ExtensionMessage::new(
+                PIN_KIND,
+                serde_json::Value::Null,
+            )));
+        }
+        out.push(AgentMessage::Llm(msg));
+    }
+    out
 }
 
 // ---------------------------------------------------------------------------
 // Tiered compaction
 // ---------------------------------------------------------------------------
 
+/// Move a cut point back so it doesn't split a tool call from its results:
+/// the first index at or before `at` that isn't a tool result.
+fn turn_start(messages: &[AgentMessage], mut at: usize) -> usize {
+    while at > 0
+        && matches!(
+            messages.get(at),
+            Some(AgentMessage::Llm(Message::ToolResult { .. }))
+        )
+    {
+        at -= 1;
+    }
+    at
+}
+
+/// Move the end of a kept prefix forward past the tool results of its last
+/// assistant message, so they aren't cut off from their call.
+fn turn_end(messages: &[AgentMessage], mut at: usize) -> usize {
+    while matches!(
+        messages.get(at),
+        Some(AgentMessage::Llm(Message::ToolResult { .. }))
+    ) {
+        at += 1;
+    }
+    at
+}
+
+/// Drop tool results whose call was compacted away; providers reject them
+/// and [`AgentContext::validate`] reports them.
+fn drop_orphaned_tool_results(messages: Vec<AgentMessage>) -> Vec<AgentMessage> {
+    let mut call_ids = std::collections::HashSet::new();
+    messages
+        .into_iter()
+        .filter(|msg| match msg {
+            AgentMessage::Llm(Message::Assistant { content, .. }) => {
+                call_ids.extend(content.iter().filter_map(|c| match c {
+                    Content::ToolCall { id, .. } => Some(id.clone()),
+                    _ => None,
+                }));
+                true
+            }
+            AgentMessage::Llm(Message::ToolResult { tool_call_id, .. }) => {
+                call_ids.contains(tool_call_id)
+            }
+            _ => true,
+        })
+        .collect()
+}
+
 /// Compact messages to fit within the token budget using tiered strategy.
 ///
 /// - Level 1: Truncate tool outputs (keep head + tail)
@@ -224,30 +763,44 @@ impl CompactionStrategy for DefaultCompaction {
 /// - Level 3: Drop old messages (keep first + recent only)
 ///
 /// Each level is tried in order. Returns as soon as messages fit.
+/// [Pinned](pinned) messages survive levels 2 and 3 untouched.
 pub fn compact_messages(messages: Vec<AgentMessage>, config: &ContextConfig) -> Vec<AgentMessage> {
+    compact_messages_with_level(messages, config).0
+}
+
+/// [`compact_messages`], also returning the level it stopped at (`None`
+/// if the messages already fit).
+pub fn compact_messages_with_level(
+    messages: Vec<AgentMessage>,
+    config: &ContextConfig,
+) -> (Vec<AgentMessage>, Option<CompactionLevel>) {
     let budget = config
         .max_context_tokens
         .saturating_sub(config.system_prompt_tokens);
+    let model = config.model.as_deref();
 
     // Already fits?
-    if total_tokens(&messages) <= budget {
-        return messages;
+    if total_tokens_for(&messages, model) <= budget {
+        return (messages, None);
     }
 
     // Level 1: Truncate tool outputs
     let compacted = level1_truncate_tool_outputs(&messages, config.tool_output_max_lines);
-    if total_tokens(&compacted) <= budget {
-        return compacted;
+    if total_tokens_for(&compacted, model) <= budget {
+        return (compacted, Some(CompactionLevel::Level1));
     }
 
     // Level 2: Summarize old turns (keep recent N full, summarize the rest)
     let compacted = level2_summarize_old_turns(&compacted, config.keep_recent);
-    if total_tokens(&compacted) <= budget {
-        return compacted;
+    if total_tokens_for(&compacted, model) <= budget {
+        return (compacted, Some(CompactionLevel::Level2));
     }
 
     // Level 3: Drop middle messages (keep first + recent)
-    level3_drop_middle(&compacted, config, budget)
+    (
+        level3_drop_middle(&compacted, config, budget),
+        Some(CompactionLevel::Level3),
+    )
 }
 
 /// Level 1: Truncate long tool outputs to head + tail.
@@ -317,12 +870,19 @@ fn level2_summarize_old_turns(messages: &[AgentMessage], keep_recent: usize) ->
         return messages.to_vec();
     }
 
-    let boundary = len - keep_recent;
+    // The recent section starts at a turn, not among its tool results.
+    let boundary = turn_start(messages, len - keep_recent);
+    let pinned = pinned_flags(messages);
     let mut result = Vec::new();
 
     let mut i = 0;
     while i < boundary {
         let msg = &messages[i];
+        if pinned[i] {
+            result.push(msg.clone());
+            i += 1;
+            continue;
+        }
         match msg {
             AgentMessage::Llm(Message::Assistant { content, .. }) => {
                 // Summarize: extract text content, skip tool call details
@@ -362,7 +922,7 @@ fn level2_summarize_old_turns(messages: &[AgentMessage], keep_recent: usize) ->
 
                 // Skip following tool results that belong to this turn
                 i += 1;
-                while i < boundary {
+                while i < boundary && !pinned[i] {
                     if let AgentMessage::Llm(Message::ToolResult { .. }) = &messages[i] {
                         i += 1;
                     } else {
@@ -396,17 +956,22 @@ fn level3_drop_middle(
     budget: usize,
 ) -> Vec<AgentMessage> {
     let len = messages.len();
-    let first_end = config.keep_first.min(len);
-    let recent_start = len.saturating_sub(config.keep_recent);
+    // Neither cut may separate a tool call from its results.
+    let first_end = turn_end(messages, config.keep_first.min(len));
+    let recent_start = turn_start(messages, len.saturating_sub(config.keep_recent));
 
     if first_end >= recent_start {
         // Can't split — just keep as many recent as fit
-        return keep_within_budget(messages, budget);
+        return keep_within_budget(messages, budget, config.model.as_deref());
     }
 
-    let first_msgs = &messages[..first_end];
-    let recent_msgs = &messages[recent_start..];
-    let removed = recent_start - first_end;
+    // Pinned messages from the middle stay, in order.
+    let pinned = pinned_flags(messages);
+    let kept_middle: Vec<AgentMessage> = (first_end..recent_start)
+        .filter(|&i| pinned[i])
+        .map(|i| messages[i].clone())
+        .collect();
+    let removed = recent_start - first_end - kept_middle.len();
 
     let marker = AgentMessage::Llm(Message::User {
         content: vec![Content::Text {
@@ -418,33 +983,52 @@ fn level3_drop_middle(
         timestamp: now_ms(),
     });
 
-    let mut result = first_msgs.to_vec();
+    let mut result = messages[..first_end].to_vec();
     result.push(marker);
-    result.extend_from_slice(recent_msgs);
+    result.extend(kept_middle);
+    result.extend_from_slice(&messages[recent_start..]);
 
     // If still too big, progressively drop from recent
-    if total_tokens(&result) > budget {
-        return keep_within_budget(&result, budget);
+    if total_tokens_for(&result, config.model.as_deref()) > budget {
+        return keep_within_budget(&result, budget, config.model.as_deref());
     }
 
     result
 }
 
-/// Keep as many recent messages as fit within budget.
-fn keep_within_budget(messages: &[AgentMessage], budget: usize) -> Vec<AgentMessage> {
+/// Keep as many recent messages as fit within budget, plus every pinned
+/// message (whose tokens come off the budget first).
+fn keep_within_budget(
+    messages: &[AgentMessage],
+    budget: usize,
+    model: Option<&str>,
+) -> Vec<AgentMessage> {
+    let pinned = pinned_flags(messages);
+    let pinned_tokens: usize = messages
+        .iter()
+        .zip(&pinned)
+        .filter(|(_, &p)| p)
+        .map(|(msg, _)| message_tokens_for(msg, model))
+        .sum();
     let mut result = Vec::new();
-    let mut remaining = budget;
-
-    for msg in messages.iter().rev() {
-        let tokens = message_tokens(msg);
-        if tokens > remaining {
-            break;
+    let mut remaining = budget.saturating_sub(pinned_tokens);
+    let mut full = false;
+
+    for (msg, &is_pinned) in messages.iter().zip(&pinned).rev() {
+        if !is_pinned {
+            let tokens = message_tokens_for(msg, model);
+            if full || tokens > remaining {
+                full = true;
+                continue;
+            }
+            remaining -= tokens;
         }
-        remaining -= tokens;
         result.push(msg.clone());
     }
 
     result.reverse();
+    // The oldest kept messages may be tool results of a dropped call.
+    let mut result = drop_orphaned_tool_results(result);
 
     if result.len() < messages.len() {
         let removed = messages.len() - result.len();
@@ -475,6 +1059,17 @@ pub struct ExecutionLimits {
     pub max_total_tokens: usize,
     /// Maximum wall-clock time
     pub max_duration: std::time::Duration,
+    /// Maximum time for one LLM call, retries included (with their backoff
+    /// and rate-limiter waits). A turn that runs over is recorded as an
+    /// `Aborted` assistant message and the loop moves on to the next turn.
+    /// Not checked by [`ExecutionTracker::check_limits`].
+    #[serde(default)]
+    pub turn_timeout: Option<std::time::Duration>,
+    /// Maximum estimated spend in US dollars, priced with the loop's
+    /// `ModelConfig::cost`. Turns without configured rates count as free,
+    /// so this has no effect unless pricing is known.
+    #[serde(default)]
+    pub max_cost_usd: Option<f64>,
 }
 
 impl Default for ExecutionLimits {
@@ -483,6 +1078,8 @@ impl Default for ExecutionLimits {
             max_turns: 50,
             max_total_tokens: 1_000_000,
             max_duration: std::time::Duration::from_secs(600),
+            turn_timeout: None,
+            max_cost_usd: None,
         }
     }
 }
@@ -492,6 +1089,8 @@ pub struct ExecutionTracker {
     pub limits: ExecutionLimits,
     pub turns: usize,
     pub tokens_used: usize,
+    /// Estimated spend so far, in US dollars.
+    pub cost_usd: f64,
     pub started_at: std::time::Instant,
 }
 
@@ -501,13 +1100,17 @@ impl ExecutionTracker {
             limits,
             turns: 0,
             tokens_used: 0,
+            cost_usd: 0.0,
             started_at: std::time::Instant::now(),
         }
     }
 
-    pub fn record_turn(&mut self, tokens: usize) {
+    /// Count a finished turn with its token use and estimated cost in US
+    /// dollars (0.0 when pricing is unknown).
+    pub fn record_turn(&mut self, tokens: usize, cost: f64) {
         self.turns += 1;
         self.tokens_used += tokens;
+        self.cost_usd += cost;
     }
 
     /// Check if any limit has been exceeded. Returns the reason if so.
@@ -524,6 +1127,14 @@ impl ExecutionTracker {
                 self.tokens_used, self.limits.max_total_tokens
             ));
         }
+        if let Some(max_cost) = self.limits.max_cost_usd {
+            if self.cost_usd >= max_cost {
+                return Some(format!(
+                    "Max cost reached (${:.2}/${:.2})",
+                    self.cost_usd, max_cost
+                ));
+            }
+        }
         let elapsed = self.started_at.elapsed();
         if elapsed >= self.limits.max_duration {
             return Some(format!(
@@ -536,6 +1147,183 @@ impl ExecutionTracker {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Import from OpenAI chat messages
+// ---------------------------------------------------------------------------
+
+/// A problem found by [`AgentContext::from_openai_messages`]. `index` is the
+/// position in the input array.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ImportError {
+    /// A `role` other than `system`, `developer`, `user`, `assistant` or
+    /// `tool`.
+    #[error("message {index}: unknown role '{role}'")]
+    UnknownRole { index: usize, role: String },
+    /// A `tool` message without a `tool_call_id`.
+    #[error("message {index}: tool message has no tool_call_id")]
+    MissingToolCallId { index: usize },
+    /// A message, content part or tool call that can't be converted.
+    #[error("message {index}: {reason}")]
+    MalformedContent { index: usize, reason: String },
+}
+
+impl AgentContext {
+    /// Build a context from OpenAI Chat Completions messages, such as a
+    /// ChatGPT export or the `messages` of a request body.
+    ///
+    /// `system`/`developer` messages become the system prompt (joined with
+    /// blank lines), `user` messages become [`Message::User`], `assistant`
+    /// messages become [`Message::Assistant`] with a [`Content::ToolCall`] per
+    /// `tool_calls` entry, and `tool` messages become [`Message::ToolResult`].
+    /// Text, base64 `data:` image URLs and inline files are supported as
+    /// content parts. Tools aren't part of the format, so
+    /// [`tools`](AgentContext::tools) is empty; register them separately.
+    pub fn from_openai_messages(messages: &[serde_json::Value]) -> Result<Self, ImportError> {
+        let mut system_prompt = Vec::new();
+        let mut imported = Vec::new();
+        let mut tool_names = std::collections::HashMap::new();
+        for (index, value) in messages.iter().enumerate() {
+            let malformed = |reason: &str| ImportError::MalformedContent {
+                index,
+                reason: reason.to_string(),
+            };
+            let role = value["role"]
+                .as_str()
+                .ok_or_else(|| malformed("message has no role"))?;
+            let message = match role {
+                "system" | "developer" => {
+                    let content = openai_content(&value["content"], index)?;
+                    system_prompt.extend(content.into_iter().filter_map(|c| match c {
+                        Content::Text { text } => Some(text),
+                        _ => None,
+                    }));
+                    continue;
+                }
+                "user" => Message::user_multipart(openai_content(&value["content"], index)?),
+                "assistant" => {
+                    let mut content = openai_content(&value["content"], index)?;
+                    let calls = match &value["tool_calls"] {
+                        serde_json::Value::Null => &[][..],
+                        serde_json::Value::Array(calls) => calls.as_slice(),
+                        _ => return Err(malformed("tool_calls is not an array")),
+                    };
+                    for call in calls {
+                        let (Some(id), Some(name)) =
+                            (call["id"].as_str(), call["function"]["name"].as_str())
+                        else {
+                            return Err(malformed("tool call has no id or function name"));
+                        };
+                        let arguments = match &call["function"]["arguments"] {
+                            serde_json::Value::String(s) if s.trim().is_empty() => {
+                                serde_json::json!({})
+                            }
+                            serde_json::Value::String(s) => serde_json::from_str(s)
+                                .map_err(|_| malformed("tool call arguments are not JSON"))?,
+                            serde_json::Value::Null => serde_json::json!({}),
+                            other => other.clone(),
+                        };
+                        tool_names.insert(id.to_string(), name.to_string());
+                        content.push(Content::tool_call(id, name, arguments));
+                    }
+                    let stop_reason = if calls.is_empty() {
+                        StopReason::Stop
+                    } else {
+                        StopReason::ToolUse
+                    };
+                    let model = value["model"].as_str().unwrap_or_default();
+                    Message::assistant(content, stop_reason, model, "openai", Usage::default())
+                }
+                "tool" => {
+                    let tool_call_id = value["tool_call_id"]
+                        .as_str()
+                        .ok_or(ImportError::MissingToolCallId { index })?;
+                    let tool_name = value["name"]
+                        .as_str()
+                        .or_else(|| tool_names.get(tool_call_id).map(String::as_str))
+                        .unwrap_or_default();
+                    Message::ToolResult {
+                        tool_call_id: tool_call_id.to_string(),
+                        tool_name: tool_name.to_string(),
+                        content: openai_content(&value["content"], index)?,
+                        is_error: false,
+                        timestamp: now_ms(),
+                    }
+                }
+                other => {
+                    return Err(ImportError::UnknownRole {
+                        index,
+                        role: other.to_string(),
+                    })
+                }
+            };
+            imported.push(message.into());
+        }
+        Ok(Self {
+            system_prompt: system_prompt.join("\n\n"),
+            messages: imported,
+            tools: Vec::new(),
+        })
+    }
+}
+
+/// Content blocks from an OpenAI `content` field: a string, an array of
+/// parts, or null (no content).
+fn openai_content(value: &serde_json::Value, index: usize) -> Result<Vec<Content>, ImportError> {
+    let malformed = |reason: String| ImportError::MalformedContent { index, reason };
+    let parts = match value {
+        serde_json::Value::Null => return Ok(Vec::new()),
+        serde_json::Value::String(text) => {
+            return Ok(vec![Content::Text { text: text.clone() }]);
+        }
+        serde_json::Value::Array(parts) => parts,
+        _ => return Err(malformed("content is not a string or array".into())),
+    };
+    parts
+        .iter()
+        .map(|part| match part["type"].as_str() {
+            Some("text") => part["text"]
+                .as_str()
+                .map(|text| Content::Text { text: text.into() })
+                .ok_or_else(|| malformed("text part has no text".into())),
+            Some("image_url") => {
+                let url = part["image_url"]["url"].as_str().unwrap_or_default();
+                let (mime_type, data) = split_data_url(url).ok_or_else(|| {
+                    malformed("only base64 data: URLs are supported for images".into())
+                })?;
+                Ok(Content::Image {
+                    data: data.into(),
+                    mime_type: mime_type.into(),
+                })
+            }
+            Some("file") => {
+                use base64::Engine;
+                let file = &part["file"];
+                let (mime_type, data) = file["file_data"]
+                    .as_str()
+                    .and_then(split_data_url)
+                    .ok_or_else(|| malformed("file part has no base64 file_data".into()))?;
+                let data = base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|e| malformed(format!("file data is not base64: {}", e)))?;
+                Ok(Content::Document {
+                    data,
+                    mime_type: mime_type.into(),
+                    filename: file["filename"].as_str().unwrap_or_default().into(),
+                })
+            }
+            other => Err(malformed(format!(
+                "unsupported content part type '{}'",
+                other.unwrap_or_default()
+            ))),
+        })
+        .collect()
+}
+
+/// `data:<mime>;base64,<data>` → `(mime, data)`.
+fn split_data_url(url: &str) -> Option<(&str, &str)> {
+    url.strip_prefix("data:")?.split_once(";base64,")
+}
+
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -544,6 +1332,112 @@ impl ExecutionTracker {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_noop_compaction_keeps_every_message() {
+        let messages: Vec<AgentMessage> = (0..50)
+            .map(|i| AgentMessage::Llm(Message::user(format!("{} {}", i, "x".repeat(400)))))
+            .collect();
+        let config = ContextConfig {
+            max_context_tokens: 100,
+            ..ContextConfig::default()
+        };
+
+        let result = NoopCompaction.compact(messages.clone(), &config);
+        assert_eq!(result, messages);
+    }
+
+    /// 20 user/assistant exchanges of ~100 tokens each.
+    fn long_history() -> Vec<AgentMessage> {
+        (0..20)
+            .flat_map(|i| {
+                [
+                    AgentMessage::Llm(Message::user(format!("q{} {}", i, "x".repeat(400)))),
+                    AgentMessage::Llm(Message::assistant(
+                        vec![Content::Text {
+                            text: format!("a{} {}", i, "y".repeat(400)),
+                        }],
+                        StopReason::Stop,
+                        "m",
+                        "p",
+                        Usage::default(),
+                    )),
+                ]
+            })
+            .collect()
+    }
+
+    fn summary_config() -> ContextConfig {
+        ContextConfig {
+            max_context_tokens: 1_000,
+            system_prompt_tokens: 0,
+            keep_recent: 4,
+            ..ContextConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_llm_summarize_replaces_old_span() {
+        let messages = long_history();
+        let strategy = LlmSummarize::new(
+            Arc::new(crate::provider::MockProvider::text(
+                "User asked 16 questions.",
+            )),
+            "mock",
+            "key",
+        );
+
+        let result = strategy
+            .compact_async(
+                messages.clone(),
+                &summary_config(),
+                &CancellationToken::new(),
+            )
+            .await;
+
+        assert_eq!(result.len(), 5);
+        assert!(matches!(
+            &result[0],
+            AgentMessage::Llm(Message::User { content, .. })
+                if content == &vec![Content::Text { text: "[Summary] User asked 16 questions.".into() }]
+        ));
+        assert_eq!(&result[1..], &messages[messages.len() - 4..]);
+    }
+
+    #[tokio::test]
+    async fn test_llm_summarize_falls_back_when_cancelled() {
+        let messages = long_history();
+        let config = summary_config();
+        let strategy = LlmSummarize::new(
+            Arc::new(crate::provider::MockProvider::text("unused")),
+            "mock",
+            "key",
+        );
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+
+        let result = strategy
+            .compact_async(messages.clone(), &config, &cancel)
+            .await;
+        // Compare content only: heuristic summaries carry fresh timestamps.
+        let contents = |messages: &[AgentMessage]| -> Vec<Vec<Content>> {
+            messages
+                .iter()
+                .map(|m| match m {
+                    AgentMessage::Llm(
+                        Message::User { content, .. }
+                        | Message::Assistant { content, .. }
+                        | Message::ToolResult { content, .. },
+                    ) => content.clone(),
+                    AgentMessage::Extension(_) => Vec::new(),
+                })
+                .collect()
+        };
+        assert_eq!(
+            contents(&result),
+            contents(&compact_messages(messages, &config))
+        );
+    }
+
     #[test]
     fn test_estimate_tokens() {
         assert!(estimate_tokens("hello world") > 0);
@@ -551,6 +1445,44 @@ mod tests {
         assert_eq!(estimate_tokens(""), 0);
     }
 
+    #[cfg(not(feature = "tiktoken"))]
+    #[test]
+    fn test_estimate_tokens_for_falls_back_without_tiktoken() {
+        let text = "fn main() { println!(\"hello\"); }";
+        assert_eq!(
+            estimate_tokens_for(text, Some("claude-3-5-sonnet")),
+            estimate_tokens(text)
+        );
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_estimate_tokens_for_uses_bpe() {
+        // Unknown models use cl100k_base, a close proxy for Claude on code.
+        let tokens = estimate_tokens_for(
+            "fn main() { println!(\"hello\"); }",
+            Some("claude-3-5-sonnet"),
+        );
+        assert!((9..=14).contains(&tokens), "got {tokens}");
+        assert_eq!(estimate_tokens_for("hello world", Some("gpt-4o")), 2);
+        assert_eq!(estimate_tokens_for("", Some("gpt-4o")), 0);
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_estimate_tokens_uses_cl100k() {
+        assert_eq!(estimate_tokens("hello world"), 2);
+        assert_eq!(
+            estimate_tokens("The quick brown fox jumps over the lazy dog."),
+            10
+        );
+        let code = "fn main() { println!(\"hello\"); }";
+        assert_eq!(
+            estimate_tokens(code),
+            estimate_tokens_for(code, Some("gpt-4"))
+        );
+    }
+
     #[test]
     fn test_context_config_from_context_window() {
         let config = ContextConfig::from_context_window(200_000);
@@ -623,6 +1555,174 @@ mod tests {
         assert_eq!(result.len(), 2);
     }
 
+    #[test]
+    fn test_compact_reports_the_level_it_stopped_at() {
+        let config = ContextConfig {
+            max_context_tokens: 600,
+            system_prompt_tokens: 0,
+            keep_recent: 2,
+            keep_first: 1,
+            tool_output_max_lines: 4,
+            model: None,
+        };
+        let tool_result = |lines: usize| {
+            AgentMessage::Llm(Message::ToolResult {
+                tool_call_id: "tc".into(),
+                tool_name: "bash".into(),
+                content: vec![Content::Text {
+                    text: "output line\n".repeat(lines),
+                }],
+                is_error: false,
+                timestamp: 0,
+            })
+        };
+
+        let small = vec![AgentMessage::Llm(Message::user("hi"))];
+        assert_eq!(compact_messages_with_level(small, &config).1, None);
+
+        let long_output = vec![AgentMessage::Llm(Message::user("run it")), tool_result(300)];
+        let (result, level) = compact_messages_with_level(long_output, &config);
+        assert_eq!(level, Some(CompactionLevel::Level1));
+        assert_eq!(result.len(), 2);
+
+        let long_chat: Vec<_> = (0..40)
+            .map(|i| AgentMessage::Llm(Message::user(format!("{} {}", i, "x".repeat(400)))))
+            .collect();
+        let (result, level) = compact_messages_with_level(long_chat, &config);
+        assert_eq!(level, Some(CompactionLevel::Level3));
+        assert!(result.len() < 40);
+    }
+
+    fn text_of(msg: &AgentMessage) -> &str {
+        match msg {
+            AgentMessage::Llm(Message::User { content, .. }) => match &content[0] {
+                Content::Text { text } => text,
+                _ => "",
+            },
+            _ => "",
+        }
+    }
+
+    #[test]
+    fn test_pinned_message_survives_level3() {
+        let mut messages: Vec<AgentMessage> = (0..20)
+            .map(|i| AgentMessage::Llm(Message::user(format!("filler {} {}", i, "x".repeat(400)))))
+            .collect();
+        let [marker, rule] = pinned(Message::user("RULE: answer in French"));
+        messages.splice(10..10, [marker, rule]);
+        let config = ContextConfig {
+            max_context_tokens: 700,
+            system_prompt_tokens: 0,
+            keep_recent: 3,
+            keep_first: 1,
+            tool_output_max_lines: 10,
+            model: None,
+        };
+
+        let (result, level) = compact_messages_with_level(messages, &config);
+        assert_eq!(level, Some(CompactionLevel::Level3));
+        let texts: Vec<&str> = result.iter().map(text_of).collect();
+        assert!(texts.contains(&"RULE: answer in French"), "{:?}", texts);
+        assert!(result.iter().any(is_pin_marker));
+        // Its unpinned neighbours are gone.
+        assert!(!texts.iter().any(|t| t.starts_with("filler 9 ")));
+        assert!(!texts.iter().any(|t| t.starts_with("filler 10 ")));
+        // Order is kept: first message, compaction note, pin, recent tail.
+        assert!(texts[0].starts_with("filler 0 "));
+        assert!(texts[1].starts_with("[Context compacted: 16 messages removed"));
+        assert_eq!(texts[3], "RULE: answer in French");
+        assert!(texts[4].starts_with("filler 17 "));
+    }
+
+    #[test]
+    fn test_pinned_assistant_turn_survives_level2() {
+        let call = |id: &str| {
+            AgentMessage::Llm(Message::assistant(
+                vec![Content::tool_call(id, "bash", serde_json::json!({}))],
+                StopReason::ToolUse,
+                "m",
+                "p",
+                Usage::default(),
+            ))
+        };
+        let result = |id: &str| {
+            AgentMessage::Llm(Message::ToolResult {
+                tool_call_id: id.into(),
+                tool_name: "bash".into(),
+                content: vec![Content::Text { text: "ok".into() }],
+                is_error: false,
+                timestamp: 0,
+            })
+        };
+        let mut messages = vec![
+            AgentMessage::Llm(Message::user("go")),
+            call("a"),
+            result("a"),
+        ];
+        messages.extend(pinned(call("b")));
+        messages.push(result("b"));
+        messages.extend([
+            call("c"),
+            result("c"),
+            AgentMessage::Llm(Message::user("next")),
+        ]);
+
+        let compacted = level2_summarize_old_turns(&messages, 1);
+        let tool_results: Vec<&str> = compacted
+            .iter()
+            .filter_map(|m| match m {
+                AgentMessage::Llm(Message::ToolResult { tool_call_id, .. }) => {
+                    Some(tool_call_id.as_str())
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(tool_results, ["b"]);
+        assert!(
+            compacted.contains(&messages[4]),
+            "pinned call kept verbatim"
+        );
+    }
+
+    #[test]
+    fn test_pinned_tool_result_keeps_its_call() {
+        let mut messages = parallel_tool_history(8);
+        // Pin the second result of turn 3 (call at 10, results at 11 and 12).
+        messages.insert(12, pinned(Message::user("unused"))[0].clone());
+        let config = ContextConfig {
+            max_context_tokens: 100_000,
+            system_prompt_tokens: 0,
+            keep_recent: 2,
+            keep_first: 1,
+            tool_output_max_lines: 10,
+            model: None,
+        };
+
+        let dropped = level3_drop_middle(&messages, &config, 100_000);
+        // The whole turn survives: call, first result, marker, pinned result.
+        assert!(
+            dropped.windows(4).any(|w| w == &messages[10..14]),
+            "{:#?}",
+            dropped
+        );
+        assert_valid(dropped);
+        assert_valid(keep_within_budget(&messages, 5_000, None));
+    }
+
+    #[test]
+    fn test_keep_within_budget_keeps_pinned() {
+        let big = |i: usize| AgentMessage::Llm(Message::user(format!("{} {}", i, "x".repeat(400))));
+        let mut messages = vec![];
+        messages.extend(pinned(Message::user("keep me")));
+        messages.extend((0..10).map(big));
+
+        let result = keep_within_budget(&messages, 250, None);
+        let texts: Vec<&str> = result.iter().map(text_of).collect();
+        assert!(texts.contains(&"keep me"), "{:?}", texts);
+        assert!(texts.last().unwrap().starts_with("9 "));
+        assert!(result.len() < messages.len());
+    }
+
     #[test]
     fn test_compact_drops_middle_when_needed() {
         let mut messages = Vec::new();
@@ -640,6 +1740,7 @@ mod tests {
             keep_recent: 5,
             keep_first: 2,
             tool_output_max_lines: 20,
+            model: None,
         };
 
         let result = compact_messages(messages, &config);
@@ -647,6 +1748,83 @@ mod tests {
         assert!(result.len() >= 2);
     }
 
+    /// A user prompt, then `turns` assistant turns that each call two tools
+    /// with ~1k-token results, then a user message.
+    fn parallel_tool_history(turns: usize) -> Vec<AgentMessage> {
+        let result = |id: String| {
+            AgentMessage::Llm(Message::ToolResult {
+                tool_call_id: id,
+                tool_name: "bash".into(),
+                content: vec![Content::Text {
+                    text: "x".repeat(4_000),
+                }],
+                is_error: false,
+                timestamp: 0,
+            })
+        };
+        let mut messages = vec![AgentMessage::Llm(Message::user("go"))];
+        for i in 0..turns {
+            let (a, b) = (format!("a{}", i), format!("b{}", i));
+            messages.push(AgentMessage::Llm(Message::assistant(
+                vec![
+                    Content::tool_call(a.clone(), "bash", serde_json::json!({})),
+                    Content::tool_call(b.clone(), "bash", serde_json::json!({})),
+                ],
+                StopReason::ToolUse,
+                "m",
+                "p",
+                Usage::default(),
+            )));
+            messages.push(result(a));
+            messages.push(result(b));
+        }
+        messages.push(AgentMessage::Llm(Message::user("next")));
+        messages
+    }
+
+    fn assert_valid(messages: Vec<AgentMessage>) {
+        let context = AgentContext {
+            system_prompt: String::new(),
+            messages,
+            tools: Vec::new(),
+        };
+        assert_eq!(context.validate(), Ok(()), "{:#?}", context.messages);
+    }
+
+    #[test]
+    fn test_compaction_keeps_tool_calls_with_their_results() {
+        // keep_first and keep_recent both cut between a call and its results.
+        let messages = parallel_tool_history(8);
+        let config = ContextConfig {
+            max_context_tokens: 100_000,
+            system_prompt_tokens: 0,
+            keep_recent: 2,
+            keep_first: 3,
+            tool_output_max_lines: 10,
+            model: None,
+        };
+
+        let dropped = level3_drop_middle(&messages, &config, 100_000);
+        // First turn whole, the note, then the last turn whole.
+        assert_eq!(dropped.len(), 4 + 1 + 4);
+        assert_eq!(dropped[..4], messages[..4]);
+        assert_eq!(dropped[5..], messages[messages.len() - 4..]);
+        assert_valid(dropped);
+
+        // Less than the two results Level 2 keeps, whatever the estimator.
+        let budget = message_tokens(&messages[2]) * 3 / 2;
+        assert_valid(level2_summarize_old_turns(&messages, 2));
+        assert_valid(keep_within_budget(&messages, budget, None));
+
+        let config = ContextConfig {
+            max_context_tokens: budget,
+            ..config
+        };
+        let (compacted, level) = compact_messages_with_level(messages, &config);
+        assert_eq!(level, Some(CompactionLevel::Level3));
+        assert_valid(compacted);
+    }
+
     #[test]
     fn test_context_tracker_no_usage() {
         let tracker = ContextTracker::new();
@@ -723,16 +1901,140 @@ mod tests {
             max_turns: 3,
             max_total_tokens: 1000,
             max_duration: std::time::Duration::from_secs(60),
+            turn_timeout: None,
+            max_cost_usd: None,
         };
 
         let mut tracker = ExecutionTracker::new(limits);
         assert!(tracker.check_limits().is_none());
 
-        tracker.record_turn(100);
-        tracker.record_turn(100);
+        tracker.record_turn(100, 0.0);
+        tracker.record_turn(100, 0.0);
         assert!(tracker.check_limits().is_none());
 
-        tracker.record_turn(100);
+        tracker.record_turn(100, 0.0);
         assert!(tracker.check_limits().is_some());
     }
+
+    #[test]
+    fn test_execution_limits_max_cost() {
+        let mut tracker = ExecutionTracker::new(ExecutionLimits {
+            max_cost_usd: Some(1.0),
+            ..ExecutionLimits::default()
+        });
+        tracker.record_turn(100, 0.6);
+        assert!(tracker.check_limits().is_none());
+        tracker.record_turn(100, 0.6);
+        assert_eq!(
+            tracker.check_limits().as_deref(),
+            Some("Max cost reached ($1.20/$1.00)")
+        );
+    }
+
+    #[test]
+    fn test_from_openai_messages_maps_roles() {
+        let context = AgentContext::from_openai_messages(&[
+            serde_json::json!({"role": "system", "content": "Be brief."}),
+            serde_json::json!({"role": "user", "content": "Weather in Paris?"}),
+            serde_json::json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "weather", "arguments": "{\"city\":\"Paris\"}"},
+                }],
+            }),
+            serde_json::json!({"role": "tool", "tool_call_id": "call_1", "content": "18°C"}),
+            serde_json::json!({"role": "assistant", "content": "It's 18°C."}),
+        ])
+        .unwrap();
+
+        assert_eq!(context.system_prompt, "Be brief.");
+        assert!(context.tools.is_empty());
+        let roles: Vec<_> = context
+            .messages
+            .iter()
+            .map(|m| m.as_llm().unwrap().role())
+            .collect();
+        assert_eq!(roles, ["user", "assistant", "toolResult", "assistant"]);
+        let Some(Message::Assistant {
+            content,
+            stop_reason,
+            ..
+        }) = context.messages[1].as_llm()
+        else {
+            panic!("expected assistant");
+        };
+        assert_eq!(*stop_reason, StopReason::ToolUse);
+        assert_eq!(
+            content[0],
+            Content::tool_call("call_1", "weather", serde_json::json!({"city": "Paris"}))
+        );
+        let Some(Message::ToolResult { tool_name, .. }) = context.messages[2].as_llm() else {
+            panic!("expected tool result");
+        };
+        assert_eq!(tool_name, "weather");
+    }
+
+    #[test]
+    fn test_from_openai_messages_errors() {
+        let import = |message: serde_json::Value| AgentContext::from_openai_messages(&[message]);
+        assert_eq!(
+            import(serde_json::json!({"role": "function", "content": "x"})).err(),
+            Some(ImportError::UnknownRole {
+                index: 0,
+                role: "function".into()
+            })
+        );
+        assert_eq!(
+            import(serde_json::json!({"role": "tool", "content": "x"})).err(),
+            Some(ImportError::MissingToolCallId { index: 0 })
+        );
+        assert!(matches!(
+            import(serde_json::json!({"role": "user", "content": 42})),
+            Err(ImportError::MalformedContent { index: 0, .. })
+        ));
+        assert!(matches!(
+            import(serde_json::json!({
+                "role": "user",
+                "content": [{"type": "image_url", "image_url": {"url": "https://example.com/a.png"}}],
+            })),
+            Err(ImportError::MalformedContent { index: 0, .. })
+        ));
+        assert!(matches!(
+            import(serde_json::json!({
+                "role": "assistant",
+                "tool_calls": [{"id": "c", "function": {"name": "f", "arguments": "{not json"}}],
+            })),
+            Err(ImportError::MalformedContent { index: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_document_tokens_scale_with_size() {
+        let document = |bytes: usize| {
+            AgentMessage::Llm(Message::User {
+                content: vec![Content::Document {
+                    data: vec![0; bytes],
+                    mime_type: "application/pdf".into(),
+                    filename: "report.pdf".into(),
+                }],
+                timestamp: 0,
+            })
+        };
+        // A tiny file still costs the floor; larger ones grow without a cap.
+        assert_eq!(message_tokens(&document(100)), 85 + 4);
+        assert_eq!(message_tokens(&document(750_000)), 1_000 + 4);
+        assert_eq!(message_tokens(&document(75_000_000)), 100_000 + 4);
+
+        let url = AgentMessage::Llm(Message::User {
+            content: vec![Content::DocumentUrl {
+                url: "https://example.com/report.pdf".into(),
+                mime_type: "application/pdf".into(),
+            }],
+            timestamp: 0,
+        });
+        assert!(message_tokens(&url) > 85);
+    }
 }
@@ -0,0 +1,154 @@
+//! Built-in filters for [`AgentLoopConfig::output_filters`](crate::agent_loop::AgentLoopConfig::output_filters).
+//!
+//! ```ignore
+//! use yoagent::{Agent, filters::PiiRedactionOutputFilter, provider::ModelConfig};
+//!
+//! let agent = Agent::from_config(ModelConfig::anthropic("claude-sonnet-4-20250514", "Claude Sonnet 4"))
+//!     .with_output_filter(PiiRedactionOutputFilter::new());
+//! ```
+
+use crate::types::{Content, Message, OutputFilter, OutputFilterResult};
+use regex::{Captures, Regex};
+
+/// Text that replaces each redacted span.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Redacts credit card numbers, US Social Security numbers and email
+/// addresses from the text blocks of assistant messages.
+///
+/// Card numbers are 13–19 digits, optionally grouped with spaces or dashes,
+/// that pass the Luhn check, so most other long numbers are left alone.
+/// Thinking blocks and tool call arguments are not touched.
+pub struct PiiRedactionOutputFilter {
+    card: Regex,
+    ssn: Regex,
+    email: Regex,
+}
+
+impl PiiRedactionOutputFilter {
+    pub fn new() -> Self {
+        Self {
+            card: Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").expect("valid regex"),
+            ssn: Regex::new(r"\b\d{3}-\d{2}-\d{4}\b").expect("valid regex"),
+            email: Regex::new(r"\b[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}\b")
+                .expect("valid regex"),
+        }
+    }
+
+    /// `text` with every PII span replaced by [`REDACTED`], or `None` if it
+    /// has none.
+    fn redact(&self, text: &str) -> Option<String> {
+        let mut changed = false;
+        let out = self.card.replace_all(text, |caps: &Captures| {
+            if luhn_valid(&caps[0]) {
+                changed = true;
+                REDACTED.to_string()
+            } else {
+                caps[0].to_string()
+            }
+        });
+        let mut out = out.into_owned();
+        for re in [&self.ssn, &self.email] {
+            if re.is_match(&out) {
+                changed = true;
+                out = re.replace_all(&out, REDACTED).into_owned();
+            }
+        }
+        changed.then_some(out)
+    }
+}
+
+impl Default for PiiRedactionOutputFilter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OutputFilter for PiiRedactionOutputFilter {
+    fn filter(&self, message: &Message) -> OutputFilterResult {
+        let Message::Assistant { content, .. } = message else {
+            return OutputFilterResult::Pass;
+        };
+        if !content
+            .iter()
+            .any(|c| matches!(c, Content::Text { text } if self.redact(text).is_some()))
+        {
+            return OutputFilterResult::Pass;
+        }
+        let mut redacted = message.clone();
+        if let Message::Assistant { content, .. } = &mut redacted {
+            for block in content.iter_mut() {
+                if let Content::Text { text } = block {
+                    if let Some(clean) = self.redact(text) {
+                        *text = clean;
+                    }
+                }
+            }
+        }
+        OutputFilterResult::Redact(redacted)
+    }
+}
+
+/// Luhn checksum over the digits of `number`, ignoring separators.
+fn luhn_valid(number: &str) -> bool {
+    let sum: u32 = number
+        .chars()
+        .rev()
+        .filter_map(|c| c.to_digit(10))
+        .enumerate()
+        .map(|(i, d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact(text: &str) -> String {
+        PiiRedactionOutputFilter::new()
+            .redact(text)
+            .unwrap_or_else(|| text.to_string())
+    }
+
+    #[test]
+    fn redacts_card_numbers_that_pass_luhn() {
+        assert_eq!(redact("card 4111111111111111."), "card [REDACTED].");
+        assert_eq!(redact("card 4111 1111 1111 1111"), "card [REDACTED]");
+        assert_eq!(redact("card 5500-0000-0000-0004"), "card [REDACTED]");
+        // Same length, bad checksum: an order number, not a card
+        assert_eq!(redact("order 4111111111111112"), "order 4111111111111112");
+    }
+
+    #[test]
+    fn redacts_ssns_and_emails() {
+        assert_eq!(
+            redact("SSN 123-45-6789, mail jane.doe+x@example.co.uk"),
+            "SSN [REDACTED], mail [REDACTED]"
+        );
+    }
+
+    #[test]
+    fn passes_clean_text() {
+        let filter = PiiRedactionOutputFilter::new();
+        assert!(filter
+            .redact("Call 555-1234 at 10:30 on 2024-01-02")
+            .is_none());
+        let message = Message::Assistant {
+            content: vec![Content::Text {
+                text: "nothing to hide".into(),
+            }],
+            stop_reason: crate::types::StopReason::Stop,
+            model: "m".into(),
+            provider: "p".into(),
+            usage: Default::default(),
+            timestamp: 0,
+            error_message: None,
+        };
+        assert!(matches!(filter.filter(&message), OutputFilterResult::Pass));
+    }
+}
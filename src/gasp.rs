@@ -451,8 +451,8 @@ fn summarize(text: &str) -> String {
 
 fn outcome_for(stop_reason: &StopReason) -> &'static str {
     match stop_reason {
-        StopReason::Stop | StopReason::ToolUse => "completed",
-        StopReason::Length => "truncated",
+        StopReason::Stop | StopReason::StopSequence | StopReason::ToolUse => "completed",
+        StopReason::Length | StopReason::PauseTurn => "truncated",
         StopReason::Error => "error",
         StopReason::Aborted => "aborted",
         StopReason::Refusal => "refused",
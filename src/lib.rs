@@ -29,8 +29,8 @@
 //!
 //! - **The loop** ([`agent_loop()`](agent_loop())) — a stateless free function; [`Agent`] is an
 //!   optional stateful wrapper (history, tool registry, steering queues).
-//! - **7 provider protocols, 20+ providers** ([`provider`]) — Anthropic,
-//!   OpenAI (Completions + Responses), Azure, Gemini, Vertex, Bedrock, plus
+//! - **8 provider protocols, 20+ providers** ([`provider`]) — Anthropic,
+//!   OpenAI (Completions + Responses), Azure, Gemini, Vertex, Bedrock, Cohere, plus
 //!   OpenAI-compatible gateways (Groq, DeepSeek, xAI, OpenCode, Ollama, ...)
 //!   with per-provider quirk flags.
 //! - **Tools** ([`tools`]) — bash, read/write/edit file, search; add your own
@@ -54,12 +54,16 @@
 //! - **Session trees** ([`Session`]) — branching conversation history with
 //!   fork, checkpoints, and JSONL persistence; edit an earlier turn and
 //!   re-run without losing the original branch.
+//! - **Event logs** ([`logging::ConversationLogger`]) — write the event
+//!   stream as JSONL for replay or shipping to other processes.
 //! - **Context management** ([`context`]) — token tracking and tiered
 //!   compaction so long sessions keep running.
 //! - **Skills** ([`skills`]) — load `SKILL.md` files per the
 //!   [AgentSkills](https://agentskills.io) standard.
 //! - **Telemetry** — `tracing` spans per loop/LLM-stream/tool with token and
 //!   cost fields; bridge to OpenTelemetry app-side, negligible cost otherwise.
+//!   Turn/token/tool counters go to a [`metrics::MetricsSink`]
+//!   (Prometheus exporter with feature `prometheus`).
 //!
 //! The [book](https://yologdev.github.io/yoagent/) covers concepts and
 //! provider-specific guides.
@@ -67,8 +71,12 @@
 pub mod agent;
 pub mod agent_loop;
 pub mod context;
+pub mod filters;
+pub mod logging;
 pub mod mcp;
+pub mod metrics;
 pub mod provider;
+pub mod rate_limit;
 pub mod retry;
 pub mod session;
 pub mod shared_state;
@@ -84,11 +92,20 @@ pub mod openapi;
 pub mod gasp;
 
 pub use agent::{Agent, AgentBuildError, StructuredPromptError};
-pub use agent_loop::{agent_loop, agent_loop_continue};
-pub use context::{CompactionStrategy, DefaultCompaction};
-pub use retry::RetryConfig;
+pub use agent_loop::{
+    agent_loop, agent_loop_continue, agent_loop_continue_stream, agent_loop_resume,
+    agent_loop_stream, AgentEventStream, DateInjector, EventSender,
+};
+pub use context::{
+    CompactionLevel, CompactionStrategy, DefaultCompaction, ImportError, LlmSummarize,
+    NoopCompaction,
+};
+pub use rate_limit::RateLimiter;
+pub use retry::{CircuitBreaker, RetryConfig};
 pub use session::{Session, SessionEntry, SessionError};
 pub use shared_state::SharedState;
 pub use skills::SkillSet;
-pub use sub_agent::SubAgentTool;
+#[cfg(feature = "watch")]
+pub use skills::SkillWatcher;
+pub use sub_agent::{AgentFanOut, SharedToolWrapper, SubAgentTool};
 pub use types::*;
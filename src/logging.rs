@@ -0,0 +1,240 @@
+//! Conversation logging — append the agent event stream to a JSONL file.
+//!
+//! [`ConversationLogger`] drains an [`AgentEvent`] channel on a background
+//! task and appends one event per line in the
+//! [wire format](crate::types::AgentEvent), so a run can be saved, replayed
+//! with [`ConversationLogger::replay`], or tailed by another process.
+//! [`ConversationLogger::from_writer`] writes to any [`std::io::Write`]
+//! instead, inline on the caller's task.
+//!
+//! ```no_run
+//! use futures::StreamExt;
+//! use yoagent::{Agent, logging::ConversationLogger, provider::ModelConfig};
+//!
+//! # #[tokio::main]
+//! # async fn main() -> std::io::Result<()> {
+//! let mut agent = Agent::from_config(ModelConfig::anthropic("claude-sonnet-4-20250514", "Claude Sonnet 4"));
+//! let rx = agent.prompt("Hello").await;
+//!
+//! let logger = ConversationLogger::new("run.jsonl")?.spawn(rx);
+//! agent.finish().await;
+//! logger.close().await?;
+//!
+//! let events: Vec<_> = ConversationLogger::replay("run.jsonl").collect().await;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::types::AgentEvent;
+use futures::{Stream, StreamExt};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+/// Lines that may wait for the file before new events are dropped.
+const DEFAULT_BUFFER: usize = 1024;
+
+/// Appends [`AgentEvent`]s as newline-delimited JSON to a file, or to any
+/// [`Write`](std::io::Write).
+///
+/// Logging to a file never slows the agent down: events are queued for the
+/// writer task, and once `buffer` lines are waiting (a slow disk) further
+/// events are dropped and counted instead of applying backpressure. Call
+/// [`spawn`](Self::spawn) to start logging.
+///
+/// A logger built with [`from_writer`](Self::from_writer) writes
+/// synchronously instead, flushing every line, and drops nothing — wrap slow
+/// sinks in a [`BufWriter`](std::io::BufWriter).
+pub struct ConversationLogger<W = tokio::fs::File> {
+    sink: W,
+    buffer: usize,
+}
+
+impl ConversationLogger {
+    /// Open `path` for appending, creating it if needed. A last line left
+    /// unterminated (a crashed run) is closed off first so it doesn't swallow
+    /// the next event.
+    pub fn new(path: impl AsRef<Path>) -> io::Result<Self> {
+        use std::io::{Read, Seek, Write};
+
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .read(true)
+            .open(path)?;
+        if file.metadata()?.len() > 0 {
+            let mut last = [0u8];
+            file.seek(io::SeekFrom::End(-1))?;
+            file.read_exact(&mut last)?;
+            if last[0] != b'\n' {
+                file.write_all(b"\n")?;
+            }
+        }
+        Ok(Self {
+            sink: tokio::fs::File::from_std(file),
+            buffer: DEFAULT_BUFFER,
+        })
+    }
+
+    /// Number of lines that may queue behind the file before events are
+    /// dropped. Defaults to 1024.
+    pub fn with_buffer(mut self, lines: usize) -> Self {
+        self.buffer = lines.max(1);
+        self
+    }
+
+    /// Log every event from `rx` on a background task until the channel
+    /// closes or [`LoggerHandle::close`] is called.
+    pub fn spawn(self, mut rx: mpsc::UnboundedReceiver<AgentEvent>) -> LoggerHandle {
+        let stop = CancellationToken::new();
+        let dropped = Arc::new(AtomicU64::new(0));
+        let (lines_tx, lines_rx) = mpsc::channel(self.buffer);
+        let writer = tokio::spawn(write_lines(self.sink, lines_rx));
+
+        let task = {
+            let stop = stop.clone();
+            let dropped = dropped.clone();
+            tokio::spawn(async move {
+                loop {
+                    // Drain what's queued before honouring a close.
+                    let event = tokio::select! {
+                        biased;
+                        event = rx.recv() => event,
+                        _ = stop.cancelled() => None,
+                    };
+                    let Some(event) = event else { break };
+                    let queued = serde_json::to_string(&event)
+                        .ok()
+                        .is_some_and(|line| lines_tx.try_send(line).is_ok());
+                    if !queued {
+                        dropped.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+                drop(lines_tx);
+                writer.await.map_err(io::Error::other)?
+            })
+        };
+
+        LoggerHandle {
+            stop,
+            dropped,
+            task,
+        }
+    }
+
+    /// Read a log back as a stream of events.
+    ///
+    /// Lines that don't parse — e.g. a last line cut short by a crash — are
+    /// skipped with a warning; an unreadable file yields an empty stream.
+    pub fn replay(path: impl AsRef<Path>) -> impl Stream<Item = AgentEvent> {
+        let path: PathBuf = path.as_ref().to_path_buf();
+        let open = async move {
+            match tokio::fs::File::open(&path).await {
+                Ok(file) => Some(BufReader::new(file).lines()),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "cannot open conversation log");
+                    None
+                }
+            }
+        };
+        futures::stream::once(open)
+            .filter_map(futures::future::ready)
+            .flat_map(|lines| {
+                futures::stream::unfold(lines, |mut lines| async move {
+                    loop {
+                        match lines.next_line().await {
+                            Ok(Some(line)) if line.trim().is_empty() => continue,
+                            Ok(Some(line)) => match serde_json::from_str(&line) {
+                                Ok(event) => return Some((event, lines)),
+                                Err(e) => {
+                                    tracing::warn!(error = %e, "skipping malformed log line")
+                                }
+                            },
+                            Ok(None) => return None,
+                            Err(e) => {
+                                tracing::warn!(error = %e, "stopped reading conversation log");
+                                return None;
+                            }
+                        }
+                    }
+                })
+            })
+    }
+}
+
+impl<W: std::io::Write> ConversationLogger<W> {
+    /// Log to `writer`, one flushed line per event.
+    pub fn from_writer(writer: W) -> Self {
+        Self {
+            sink: writer,
+            buffer: DEFAULT_BUFFER,
+        }
+    }
+
+    /// Write a single event as one JSON line.
+    pub fn log(&mut self, event: &AgentEvent) -> io::Result<()> {
+        serde_json::to_writer(&mut self.sink, event)?;
+        self.sink.write_all(b"\n")?;
+        self.sink.flush()
+    }
+
+    /// Log every event until the channel closes, then return the writer.
+    ///
+    /// Stops at the first write error; events still in the channel are
+    /// dropped.
+    pub async fn run(mut self, mut rx: mpsc::UnboundedReceiver<AgentEvent>) -> io::Result<W> {
+        while let Some(event) = rx.recv().await {
+            self.log(&event)?;
+        }
+        Ok(self.sink)
+    }
+
+    /// Recover the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.sink
+    }
+}
+
+async fn write_lines(file: tokio::fs::File, mut lines: mpsc::Receiver<String>) -> io::Result<u64> {
+    let mut out = BufWriter::new(file);
+    let mut written = 0;
+    while let Some(line) = lines.recv().await {
+        out.write_all(line.as_bytes()).await?;
+        out.write_all(b"\n").await?;
+        written += 1;
+        // Flush once the queue is drained so a crash loses little.
+        if lines.is_empty() {
+            out.flush().await?;
+        }
+    }
+    out.flush().await?;
+    Ok(written)
+}
+
+/// A running [`ConversationLogger`].
+///
+/// Dropping the handle leaves the logger running until its channel closes.
+pub struct LoggerHandle {
+    stop: CancellationToken,
+    dropped: Arc<AtomicU64>,
+    task: JoinHandle<io::Result<u64>>,
+}
+
+impl LoggerHandle {
+    /// Events dropped so far because the writer fell behind.
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Stop logging once the events already queued are written, and return
+    /// how many lines were written. Events sent afterwards are not logged.
+    pub async fn close(self) -> io::Result<u64> {
+        self.stop.cancel();
+        self.task.await.map_err(io::Error::other)?
+    }
+}
@@ -1,6 +1,9 @@
 //! High-level MCP client.
 
-use super::transport::{HttpTransport, McpTransport, StdioTransport};
+use super::sampling::{serve_server_requests, SamplingHandler};
+#[cfg(feature = "ws")]
+use super::transport::WebSocketTransport;
+use super::transport::{HttpTransport, McpTransport, SseTransport, StdioTransport};
 use super::types::*;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -11,6 +14,7 @@ pub struct McpClient {
     transport: Arc<Mutex<Box<dyn McpTransport>>>,
     server_info: Option<ServerInfo>,
     capabilities: Option<ServerCapabilities>,
+    sampling: Option<Arc<dyn SamplingHandler>>,
 }
 
 impl McpClient {
@@ -25,6 +29,7 @@ impl McpClient {
             transport: Arc::new(Mutex::new(Box::new(transport))),
             server_info: None,
             capabilities: None,
+            sampling: None,
         };
         client.initialize().await?;
         Ok(client)
@@ -37,6 +42,40 @@ impl McpClient {
             transport: Arc::new(Mutex::new(Box::new(transport))),
             server_info: None,
             capabilities: None,
+            sampling: None,
+        };
+        client.initialize().await?;
+        Ok(client)
+    }
+
+    /// Connect to an MCP server via the HTTP+SSE transport. `url` is the
+    /// server's SSE endpoint (often ending in `/sse`).
+    pub async fn connect_sse(url: &str) -> Result<Self, McpError> {
+        let transport = SseTransport::connect(url).await?;
+        let mut client = Self {
+            transport: Arc::new(Mutex::new(Box::new(transport))),
+            server_info: None,
+            capabilities: None,
+            sampling: None,
+        };
+        client.initialize().await?;
+        Ok(client)
+    }
+
+    /// Connect to an MCP server over a WebSocket (`ws://` or `wss://`).
+    /// `headers` are sent with the upgrade request, e.g. for auth. The
+    /// transport reconnects on its own and replays this handshake.
+    #[cfg(feature = "ws")]
+    pub async fn connect_ws(
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<Self, McpError> {
+        let transport = WebSocketTransport::connect(url, headers).await?;
+        let mut client = Self {
+            transport: Arc::new(Mutex::new(Box::new(transport))),
+            server_info: None,
+            capabilities: None,
+            sampling: None,
         };
         client.initialize().await?;
         Ok(client)
@@ -48,14 +87,35 @@ impl McpClient {
             transport: Arc::new(Mutex::new(transport)),
             server_info: None,
             capabilities: None,
+            sampling: None,
         }
     }
 
+    /// Answer the server's `sampling/createMessage` requests with `handler`.
+    ///
+    /// Must be set before [`initialize`](Self::initialize) so the `sampling`
+    /// capability is advertised; use [`from_transport`](Self::from_transport)
+    /// and call `initialize()` yourself. Requires a transport that carries
+    /// server-initiated requests ([`SseTransport`], `WebSocketTransport`);
+    /// on others the capability isn't advertised and the handler is unused.
+    pub fn with_sampling_handler(mut self, handler: impl SamplingHandler + 'static) -> Self {
+        self.sampling = Some(Arc::new(handler));
+        self
+    }
+
     /// Initialize the MCP connection (handshake).
     pub async fn initialize(&mut self) -> Result<ServerInfo, McpError> {
+        // Only offer sampling if the server's requests can reach us: on
+        // stdio a server request would be read as a response instead.
+        let server_requests = self.transport.lock().await.take_server_requests();
+        let capabilities = if self.sampling.is_some() && server_requests.is_some() {
+            serde_json::json!({ "sampling": {} })
+        } else {
+            serde_json::json!({})
+        };
         let params = serde_json::json!({
             "protocolVersion": "2024-11-05",
-            "capabilities": {},
+            "capabilities": capabilities,
             "clientInfo": ClientInfo::default()
         });
 
@@ -72,6 +132,11 @@ impl McpClient {
         // Best-effort: ignore errors on the notification
         let _ = self.send_request(notify).await;
 
+        // Start answering server-initiated requests (sampling, ping).
+        if let Some(channel) = server_requests {
+            tokio::spawn(serve_server_requests(channel, self.sampling.clone()));
+        }
+
         Ok(result.server_info)
     }
 
@@ -102,6 +167,40 @@ impl McpClient {
         Ok(result)
     }
 
+    /// List resources the server exposes.
+    ///
+    /// Returns an empty list when the server didn't advertise the
+    /// `resources` capability during initialization.
+    pub async fn list_resources(&self) -> Result<Vec<McpResource>, McpError> {
+        if self
+            .capabilities
+            .as_ref()
+            .is_some_and(|c| c.resources.is_none())
+        {
+            return Ok(Vec::new());
+        }
+
+        let request = JsonRpcRequest::new("resources/list", Some(serde_json::json!({})));
+        let response = self.send_request(request).await?;
+
+        let result: ResourcesListResult = serde_json::from_value(response)?;
+        Ok(result.resources)
+    }
+
+    /// Read a resource by URI.
+    pub async fn read_resource(&self, uri: &str) -> Result<McpResourceContent, McpError> {
+        let params = serde_json::json!({ "uri": uri });
+
+        let request = JsonRpcRequest::new("resources/read", Some(params));
+        let response = self.send_request(request).await?;
+
+        let result: ResourcesReadResult = serde_json::from_value(response)?;
+        Ok(McpResourceContent {
+            uri: uri.to_string(),
+            contents: result.contents.into_iter().map(Into::into).collect(),
+        })
+    }
+
     /// Close the connection.
     pub async fn close(&self) -> Result<(), McpError> {
         self.transport.lock().await.close().await
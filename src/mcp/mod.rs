@@ -15,11 +15,18 @@
 //! ```
 
 pub mod client;
+pub mod sampling;
 pub mod tool_adapter;
 pub mod transport;
 pub mod types;
 
 pub use client::McpClient;
-pub use tool_adapter::McpToolAdapter;
-pub use transport::{HttpTransport, McpTransport, StdioTransport};
-pub use types::{McpContent, McpError, McpToolCallResult, McpToolInfo, ServerInfo};
+pub use sampling::{ProviderSamplingHandler, SamplingHandler};
+pub use tool_adapter::{McpResourceTool, McpToolAdapter};
+#[cfg(feature = "ws")]
+pub use transport::WebSocketTransport;
+pub use transport::{HttpTransport, McpTransport, ServerRequests, SseTransport, StdioTransport};
+pub use types::{
+    CreateMessageParams, CreateMessageResult, McpContent, McpError, McpResource,
+    McpResourceContent, McpToolCallResult, McpToolInfo, SamplingMessage, ServerInfo,
+};
@@ -0,0 +1,388 @@
+//! Servicing `sampling/createMessage` — server-initiated LLM calls.
+//!
+//! An MCP server can ask the client to run a completion for it. Install a
+//! [`SamplingHandler`] with
+//! [`McpClient::with_sampling_handler`](super::McpClient::with_sampling_handler)
+//! before `initialize()`; the client then advertises the `sampling`
+//! capability and answers these requests. [`ProviderSamplingHandler`] runs
+//! them as a short, tool-less agent loop on a given provider.
+
+use super::transport::ServerRequests;
+use super::types::*;
+use crate::agent_loop::{agent_loop, AgentLoopConfig};
+use crate::provider::{ModelConfig, StreamProvider};
+use crate::types::*;
+use async_trait::async_trait;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::debug;
+
+/// Answers `sampling/createMessage` requests from a server.
+///
+/// Return an assistant [`Message`]; its first text or image block becomes
+/// the result content. Returning `McpError::JsonRpc` passes that code to
+/// the server (e.g. `-1` for "user rejected"); other errors are sent as
+/// internal errors.
+#[async_trait]
+pub trait SamplingHandler: Send + Sync {
+    async fn create_message(&self, params: CreateMessageParams) -> Result<Message, McpError>;
+}
+
+/// [`SamplingHandler`] that runs each request through [`agent_loop`] with
+/// no tools, on a fixed provider and model. The server's model preferences
+/// are advisory and not used for model selection.
+pub struct ProviderSamplingHandler {
+    provider: Arc<dyn StreamProvider>,
+    model: String,
+    api_key: String,
+    model_config: Option<ModelConfig>,
+}
+
+impl ProviderSamplingHandler {
+    pub fn new(
+        provider: Arc<dyn StreamProvider>,
+        model: impl Into<String>,
+        api_key: impl Into<String>,
+    ) -> Self {
+        Self {
+            provider,
+            model: model.into(),
+            api_key: api_key.into(),
+            model_config: None,
+        }
+    }
+
+    pub fn with_model_config(mut self, config: ModelConfig) -> Self {
+        self.model_config = Some(config);
+        self
+    }
+}
+
+#[async_trait]
+impl SamplingHandler for ProviderSamplingHandler {
+    async fn create_message(&self, params: CreateMessageParams) -> Result<Message, McpError> {
+        let mut builder = AgentLoopConfig::builder(self.provider.clone())
+            .with_model(self.model.clone())
+            .with_api_key(self.api_key.clone())
+            .with_max_tokens(params.max_tokens);
+        if let Some(config) = &self.model_config {
+            builder = builder.with_model_config(config.clone());
+        }
+        if let Some(temperature) = params.temperature {
+            builder = builder.with_temperature(temperature);
+        }
+        if let Some(stop) = params.stop_sequences {
+            builder = builder.with_stop_sequences(stop);
+        }
+        let config = builder.build();
+
+        let prompts = params
+            .messages
+            .into_iter()
+            .map(|m| AgentMessage::Llm(sampling_to_message(m)))
+            .collect();
+        let mut context = AgentContext {
+            system_prompt: params.system_prompt.unwrap_or_default(),
+            messages: Vec::new(),
+            tools: Vec::new(),
+        };
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let new_messages = agent_loop(
+            prompts,
+            &mut context,
+            &config,
+            tx,
+            tokio_util::sync::CancellationToken::new(),
+        )
+        .await;
+
+        let reply = new_messages.into_iter().rev().find_map(|m| match m {
+            AgentMessage::Llm(message @ Message::Assistant { .. }) => Some(message),
+            _ => None,
+        });
+        match reply {
+            Some(Message::Assistant {
+                stop_reason: StopReason::Error | StopReason::Aborted,
+                error_message,
+                ..
+            }) => Err(McpError::Protocol(format!(
+                "sampling failed: {}",
+                error_message.unwrap_or_else(|| "LLM call failed".into())
+            ))),
+            Some(message) => Ok(message),
+            None => Err(McpError::Protocol(
+                "sampling produced no assistant message".into(),
+            )),
+        }
+    }
+}
+
+fn sampling_to_message(m: SamplingMessage) -> Message {
+    let content = match m.content {
+        McpContent::Text { text } => Content::Text { text },
+        McpContent::Image { data, mime_type } => Content::Image { data, mime_type },
+    };
+    if m.role == "assistant" {
+        Message::assistant(vec![content], StopReason::Stop, "", "", Usage::default())
+    } else {
+        Message::User {
+            content: vec![content],
+            timestamp: now_ms(),
+        }
+    }
+}
+
+/// Convert a handler's reply into the `sampling/createMessage` result.
+fn message_to_result(message: Message) -> Result<CreateMessageResult, McpError> {
+    let Message::Assistant {
+        content,
+        stop_reason,
+        model,
+        ..
+    } = message
+    else {
+        return Err(McpError::Protocol(
+            "sampling handler must return an assistant message".into(),
+        ));
+    };
+    let content = content
+        .into_iter()
+        .find_map(|c| match c {
+            Content::Text { text } => Some(McpContent::Text { text }),
+            Content::Image { data, mime_type } => Some(McpContent::Image { data, mime_type }),
+            _ => None,
+        })
+        .unwrap_or(McpContent::Text {
+            text: String::new(),
+        });
+    let stop_reason = match stop_reason {
+        StopReason::Stop | StopReason::PauseTurn => "endTurn",
+        StopReason::Length => "maxTokens",
+        StopReason::StopSequence => "stopSequence",
+        StopReason::ToolUse => "toolUse",
+        StopReason::Refusal => "refusal",
+        StopReason::Error | StopReason::Aborted => "error",
+    };
+    Ok(CreateMessageResult {
+        role: "assistant".into(),
+        content,
+        model,
+        stop_reason: Some(stop_reason.into()),
+    })
+}
+
+/// Answer server requests until the transport closes the channel. Each
+/// request is handled on its own task so a slow sampling call doesn't hold
+/// up a `ping`.
+pub(crate) async fn serve_server_requests(
+    mut channel: ServerRequests,
+    sampling: Option<Arc<dyn SamplingHandler>>,
+) {
+    while let Some(request) = channel.requests.recv().await {
+        let sampling = sampling.clone();
+        let responses = channel.responses.clone();
+        tokio::spawn(async move {
+            let response = handle_server_request(request, sampling.as_deref()).await;
+            let _ = responses.send(response);
+        });
+    }
+}
+
+async fn handle_server_request(
+    request: JsonRpcRequest,
+    sampling: Option<&dyn SamplingHandler>,
+) -> JsonRpcResponse {
+    let result = match (request.method.as_str(), sampling) {
+        ("ping", _) => Ok(serde_json::json!({})),
+        ("sampling/createMessage", Some(handler)) => {
+            create_message(handler, request.params.unwrap_or_default()).await
+        }
+        (method, _) => {
+            debug!("MCP: unsupported server request {}", method);
+            Err(JsonRpcError {
+                code: -32601,
+                message: format!("Method not found: {}", method),
+                data: None,
+            })
+        }
+    };
+
+    let (result, error) = match result {
+        Ok(result) => (Some(result), None),
+        Err(error) => (None, Some(error)),
+    };
+    JsonRpcResponse {
+        jsonrpc: "2.0".into(),
+        id: Some(request.id),
+        result,
+        error,
+    }
+}
+
+async fn create_message(
+    handler: &dyn SamplingHandler,
+    params: serde_json::Value,
+) -> Result<serde_json::Value, JsonRpcError> {
+    let params: CreateMessageParams = serde_json::from_value(params).map_err(|e| JsonRpcError {
+        code: -32602,
+        message: format!("Invalid params: {}", e),
+        data: None,
+    })?;
+    let result = handler
+        .create_message(params)
+        .await
+        .and_then(message_to_result)
+        .map_err(|e| match e {
+            McpError::JsonRpc { code, message } => JsonRpcError {
+                code,
+                message,
+                data: None,
+            },
+            other => JsonRpcError {
+                code: -32603,
+                message: other.to_string(),
+                data: None,
+            },
+        })?;
+    Ok(serde_json::to_value(result).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+
+    fn params(json: serde_json::Value) -> CreateMessageParams {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_provider_handler_runs_the_conversation() {
+        let handler = ProviderSamplingHandler::new(
+            Arc::new(MockProvider::text("Paris")),
+            "mock-model",
+            "key",
+        );
+        let message = handler
+            .create_message(params(serde_json::json!({
+                "messages": [{"role": "user", "content": {"type": "text", "text": "Capital of France?"}}],
+                "systemPrompt": "Answer in one word.",
+                "maxTokens": 10
+            })))
+            .await
+            .unwrap();
+
+        let result = message_to_result(message).unwrap();
+        assert!(matches!(&result.content, McpContent::Text { text } if text == "Paris"));
+        assert_eq!(result.stop_reason.as_deref(), Some("endTurn"));
+    }
+
+    #[tokio::test]
+    async fn test_unknown_server_request_is_method_not_found() {
+        let request = JsonRpcRequest::new("roots/list", None);
+        let response = handle_server_request(request.clone(), None).await;
+        assert_eq!(response.id, Some(request.id));
+        assert_eq!(response.error.unwrap().code, -32601);
+
+        // Sampling without a handler is also unsupported.
+        let request = JsonRpcRequest::new("sampling/createMessage", None);
+        let response = handle_server_request(request, None).await;
+        assert_eq!(response.error.unwrap().code, -32601);
+    }
+
+    /// Transport that answers `initialize` and hands out a server-request
+    /// channel driven by the test.
+    struct ServerRequestTransport {
+        initialize_params: Arc<std::sync::Mutex<Option<serde_json::Value>>>,
+        channel: std::sync::Mutex<Option<ServerRequests>>,
+    }
+
+    #[async_trait]
+    impl super::super::McpTransport for ServerRequestTransport {
+        async fn send(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse, McpError> {
+            if request.method == "initialize" {
+                *self.initialize_params.lock().unwrap() = request.params;
+            }
+            Ok(JsonRpcResponse {
+                jsonrpc: "2.0".into(),
+                id: Some(request.id),
+                result: Some(serde_json::json!({
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": {},
+                    "serverInfo": {"name": "test"}
+                })),
+                error: None,
+            })
+        }
+
+        async fn close(&self) -> Result<(), McpError> {
+            Ok(())
+        }
+
+        fn take_server_requests(&self) -> Option<ServerRequests> {
+            self.channel.lock().unwrap().take()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_answers_sampling_requests() {
+        let (request_tx, requests) = mpsc::unbounded_channel();
+        let (responses, mut response_rx) = mpsc::unbounded_channel();
+        let initialize_params = Arc::new(std::sync::Mutex::new(None));
+        let transport = ServerRequestTransport {
+            initialize_params: initialize_params.clone(),
+            channel: std::sync::Mutex::new(Some(ServerRequests {
+                requests,
+                responses,
+            })),
+        };
+        let handler =
+            ProviderSamplingHandler::new(Arc::new(MockProvider::text("42")), "mock-model", "key");
+        let mut client = super::super::McpClient::from_transport(Box::new(transport))
+            .with_sampling_handler(handler);
+        client.initialize().await.unwrap();
+
+        let params = initialize_params.lock().unwrap().clone().unwrap();
+        assert_eq!(params["capabilities"], serde_json::json!({"sampling": {}}));
+
+        let mut request = JsonRpcRequest::new(
+            "sampling/createMessage",
+            Some(serde_json::json!({
+                "messages": [{"role": "user", "content": {"type": "text", "text": "6 * 7?"}}],
+                "modelPreferences": {"hints": [{"name": "claude"}]},
+                "maxTokens": 5
+            })),
+        );
+        request.id = 99;
+        request_tx.send(request).unwrap();
+
+        let response = response_rx.recv().await.unwrap();
+        assert_eq!(response.id, Some(99));
+        assert_eq!(
+            response.result.unwrap(),
+            serde_json::json!({
+                "role": "assistant",
+                "content": {"type": "text", "text": "42"},
+                "model": "mock",
+                "stopReason": "endTurn"
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_sampling_not_advertised_without_server_requests() {
+        let initialize_params = Arc::new(std::sync::Mutex::new(None));
+        let transport = ServerRequestTransport {
+            initialize_params: initialize_params.clone(),
+            channel: std::sync::Mutex::new(None),
+        };
+        let handler =
+            ProviderSamplingHandler::new(Arc::new(MockProvider::text("42")), "mock-model", "key");
+        let mut client = super::super::McpClient::from_transport(Box::new(transport))
+            .with_sampling_handler(handler);
+        client.initialize().await.unwrap();
+
+        let params = initialize_params.lock().unwrap().clone().unwrap();
+        assert_eq!(params["capabilities"], serde_json::json!({}));
+    }
+}
@@ -1,13 +1,14 @@
-//! Adapts MCP tools to the AgentTool trait.
+//! Adapts MCP tools and resources to the AgentTool trait.
 
 use super::client::McpClient;
-use super::types::{McpContent, McpError, McpToolInfo};
+use super::types::{McpContent, McpError, McpResource, McpToolInfo};
 use crate::types::{AgentTool, Content, ToolContext, ToolError, ToolResult};
 use async_trait::async_trait;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
 /// Wraps an MCP server tool as an `AgentTool` so it can be used by the agent.
+#[derive(Clone)]
 pub struct McpToolAdapter {
     client: Arc<Mutex<McpClient>>,
     tool: McpToolInfo,
@@ -105,20 +106,111 @@ impl AgentTool for McpToolAdapter {
             return Err(ToolError::Failed(error_text));
         }
 
-        let content: Vec<Content> = result
-            .content
-            .into_iter()
-            .map(|c| match c {
-                McpContent::Text { text } => Content::Text { text },
-                McpContent::Image { data, mime_type } => Content::Image { data, mime_type },
-            })
-            .collect();
-
         Ok(ToolResult {
-            content,
+            content: result.content.into_iter().map(to_content).collect(),
             details: serde_json::Value::Null,
         })
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+fn to_content(c: McpContent) -> Content {
+    match c {
+        McpContent::Text { text } => Content::Text { text },
+        McpContent::Image { data, mime_type } => Content::Image { data, mime_type },
+    }
+}
+
+/// A `read_resource` tool that lets the agent fetch an MCP server's
+/// resources on demand. The resources known at construction are listed in
+/// the tool description; any URI the server accepts can be read.
+#[derive(Clone)]
+pub struct McpResourceTool {
+    client: Arc<Mutex<McpClient>>,
+    description: String,
+}
+
+impl McpResourceTool {
+    /// Create a tool advertising `resources` to the model.
+    pub fn new(client: Arc<Mutex<McpClient>>, resources: &[McpResource]) -> Self {
+        let mut description =
+            "Read a resource from the MCP server by URI. Returns its contents.".to_string();
+        if !resources.is_empty() {
+            description.push_str("\n\nAvailable resources:");
+            for r in resources {
+                description.push_str(&format!("\n- {} ({})", r.uri, r.name));
+                if let Some(desc) = &r.description {
+                    description.push_str(&format!(": {}", desc));
+                }
+            }
+        }
+        Self {
+            client,
+            description,
+        }
+    }
+
+    /// Create the tool from the resources the client currently lists.
+    pub async fn from_client(client: Arc<Mutex<McpClient>>) -> Result<Self, McpError> {
+        let resources = client.lock().await.list_resources().await?;
+        Ok(Self::new(client, &resources))
+    }
+}
+
+#[async_trait]
+impl AgentTool for McpResourceTool {
+    fn name(&self) -> &str {
+        "read_resource"
+    }
+
+    fn label(&self) -> &str {
+        "Read Resource"
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "uri": {
+                    "type": "string",
+                    "description": "URI of the resource to read"
+                }
+            },
+            "required": ["uri"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let uri = params["uri"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'uri' parameter".into()))?;
+
+        let client = self.client.lock().await;
+        let resource = client
+            .read_resource(uri)
+            .await
+            .map_err(|e| ToolError::Failed(format!("MCP resource read failed: {}", e)))?;
+
+        Ok(ToolResult {
+            content: resource.contents.into_iter().map(to_content).collect(),
+            details: serde_json::json!({ "uri": uri }),
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
 }
 
 #[cfg(test)]
@@ -211,6 +303,7 @@ mod tests {
                     cancel: tokio_util::sync::CancellationToken::new(),
                     on_update: None,
                     on_progress: None,
+                    timeout: None,
                 },
             )
             .await
@@ -255,6 +348,7 @@ mod tests {
                     cancel: tokio_util::sync::CancellationToken::new(),
                     on_update: None,
                     on_progress: None,
+                    timeout: None,
                 },
             )
             .await;
@@ -282,4 +376,107 @@ mod tests {
         assert_eq!(adapters[0].name(), "tool_a");
         assert_eq!(adapters[1].name(), "tool_b");
     }
+
+    fn ctx(name: &str) -> ToolContext {
+        ToolContext {
+            tool_call_id: "tc-1".into(),
+            tool_name: name.into(),
+            cancel: tokio_util::sync::CancellationToken::new(),
+            on_update: None,
+            on_progress: None,
+            timeout: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_and_read_resources() {
+        let transport = MockTransport::new(vec![
+            ok_response(
+                1,
+                serde_json::json!({
+                    "resources": [
+                        {"uri": "file:///notes.md", "name": "notes", "description": "Team notes", "mimeType": "text/markdown"},
+                        {"uri": "db://users", "name": "users"}
+                    ]
+                }),
+            ),
+            ok_response(
+                2,
+                serde_json::json!({
+                    "contents": [{"uri": "file:///notes.md", "mimeType": "text/markdown", "text": "# Notes"}]
+                }),
+            ),
+        ]);
+        let client = McpClient::from_transport(Box::new(transport));
+
+        let resources = client.list_resources().await.unwrap();
+        assert_eq!(resources.len(), 2);
+        assert_eq!(resources[0].mime_type.as_deref(), Some("text/markdown"));
+        assert_eq!(resources[1].description, None);
+
+        let resource = client.read_resource("file:///notes.md").await.unwrap();
+        assert_eq!(resource.uri, "file:///notes.md");
+        assert!(matches!(&resource.contents[..], [McpContent::Text { text }] if text == "# Notes"));
+    }
+
+    #[tokio::test]
+    async fn test_list_resources_without_capability_is_empty() {
+        let transport = MockTransport::new(vec![
+            ok_response(
+                1,
+                serde_json::json!({
+                    "protocolVersion": "2024-11-05",
+                    "capabilities": {"tools": {}},
+                    "serverInfo": {"name": "tools-only"}
+                }),
+            ),
+            // initialized notification
+            ok_response(2, serde_json::json!({})),
+        ]);
+        let mut client = McpClient::from_transport(Box::new(transport));
+        client.initialize().await.unwrap();
+
+        // No request is sent — the mock would fail with ConnectionClosed.
+        assert!(client.list_resources().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_resource_tool_reads_on_demand() {
+        let transport = MockTransport::new(vec![
+            ok_response(
+                1,
+                serde_json::json!({
+                    "resources": [{"uri": "db://users", "name": "users", "description": "All users"}]
+                }),
+            ),
+            ok_response(
+                2,
+                serde_json::json!({
+                    "contents": [{"uri": "db://users", "text": "alice\nbob"}]
+                }),
+            ),
+        ]);
+        let client = Arc::new(Mutex::new(McpClient::from_transport(Box::new(transport))));
+
+        let tool = McpResourceTool::from_client(client).await.unwrap();
+        assert_eq!(tool.name(), "read_resource");
+        assert!(tool
+            .description()
+            .contains("- db://users (users): All users"));
+
+        let result = tool
+            .execute(
+                serde_json::json!({"uri": "db://users"}),
+                ctx("read_resource"),
+            )
+            .await
+            .unwrap();
+        assert!(matches!(&result.content[0], Content::Text { text } if text == "alice\nbob"));
+        assert_eq!(result.details["uri"], "db://users");
+
+        let missing = tool
+            .execute(serde_json::json!({}), ctx("read_resource"))
+            .await;
+        assert!(matches!(missing, Err(ToolError::InvalidArgs(_))));
+    }
 }
@@ -1,12 +1,22 @@
-//! MCP transport implementations: stdio and HTTP+SSE.
+//! MCP transport implementations: stdio, HTTP, HTTP+SSE, and WebSocket.
 
 use super::types::*;
+use crate::retry::RetryConfig;
 use async_trait::async_trait;
+#[cfg(feature = "ws")]
+use futures::SinkExt;
+use futures::StreamExt;
+use reqwest_eventsource::{Event, EventSource};
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::process::{Child, Command};
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex};
+#[cfg(feature = "ws")]
+use tokio_tungstenite::tungstenite;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, warn};
 
 /// Transport trait for MCP communication.
 #[async_trait]
@@ -15,6 +25,20 @@ pub trait McpTransport: Send + Sync {
     async fn send(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse, McpError>;
     /// Close the transport.
     async fn close(&self) -> Result<(), McpError>;
+
+    /// Take the channel of requests the server sends to the client (e.g.
+    /// `sampling/createMessage`). `None` if the transport can't receive
+    /// them or the channel was already taken.
+    fn take_server_requests(&self) -> Option<ServerRequests> {
+        None
+    }
+}
+
+/// Server-initiated requests and the channel to answer them on. Each
+/// response must carry the `id` of the request it answers.
+pub struct ServerRequests {
+    pub requests: mpsc::UnboundedReceiver<JsonRpcRequest>,
+    pub responses: mpsc::UnboundedSender<JsonRpcResponse>,
 }
 
 // ---------------------------------------------------------------------------
@@ -165,6 +189,771 @@ impl McpTransport for HttpTransport {
     }
 }
 
+// ---------------------------------------------------------------------------
+// SSE Transport
+// ---------------------------------------------------------------------------
+
+type PendingResponses = Arc<std::sync::Mutex<HashMap<u64, oneshot::Sender<JsonRpcResponse>>>>;
+
+/// Communicates with an MCP server over the HTTP+SSE transport: a
+/// long-lived `GET` event stream carries server→client messages, and
+/// requests are `POST`ed to the URL the server announces in its first
+/// `endpoint` event. Responses are matched to requests by `id`.
+///
+/// If the stream drops, the transport reconnects with exponential backoff
+/// and picks up the new endpoint; requests in flight at that moment fail
+/// with [`McpError::ConnectionClosed`]. Server notifications are available
+/// via [`subscribe_notifications`](Self::subscribe_notifications), server
+/// requests via [`take_server_requests`](McpTransport::take_server_requests).
+pub struct SseTransport {
+    client: reqwest::Client,
+    endpoint: watch::Receiver<Option<String>>,
+    pending: PendingResponses,
+    notifications: broadcast::Sender<JsonRpcNotification>,
+    server_requests: std::sync::Mutex<Option<ServerRequests>>,
+    request_timeout: Duration,
+    cancel: CancellationToken,
+}
+
+impl SseTransport {
+    /// Open the event stream and wait for the server's `endpoint` event.
+    ///
+    /// Reconnects up to 10 times in a row (500ms initial delay, doubling,
+    /// capped at 30s) before giving up.
+    pub async fn connect(url: &str) -> Result<Self, McpError> {
+        let reconnect = RetryConfig {
+            max_retries: 10,
+            initial_delay_ms: 500,
+            ..RetryConfig::default()
+        };
+        Self::connect_with_reconnect(url, reconnect).await
+    }
+
+    /// Like [`connect`](Self::connect), with a custom reconnect policy.
+    /// `max_retries` counts consecutive failed connections; a successful
+    /// one resets it.
+    pub async fn connect_with_reconnect(
+        url: &str,
+        reconnect: RetryConfig,
+    ) -> Result<Self, McpError> {
+        let url = reqwest::Url::parse(url)
+            .map_err(|e| McpError::Transport(format!("Invalid SSE URL '{}': {}", url, e)))?;
+        let client = reqwest::Client::new();
+        let (endpoint_tx, endpoint) = watch::channel(None);
+        let pending: PendingResponses = Arc::default();
+        let (notifications, _) = broadcast::channel(64);
+        let (server_request_tx, requests) = mpsc::unbounded_channel();
+        let (responses, response_rx) = mpsc::unbounded_channel();
+        let cancel = CancellationToken::new();
+
+        tokio::spawn(run_event_stream(
+            client.clone(),
+            url,
+            endpoint_tx,
+            EventRoutes {
+                pending: pending.clone(),
+                notifications: notifications.clone(),
+                server_requests: server_request_tx,
+            },
+            reconnect,
+            cancel.clone(),
+        ));
+        tokio::spawn(post_responses(
+            client.clone(),
+            endpoint.clone(),
+            response_rx,
+            cancel.clone(),
+        ));
+
+        let transport = Self {
+            client,
+            endpoint,
+            pending,
+            notifications,
+            server_requests: std::sync::Mutex::new(Some(ServerRequests {
+                requests,
+                responses,
+            })),
+            request_timeout: Duration::from_secs(60),
+            cancel,
+        };
+        transport.current_endpoint().await?;
+        Ok(transport)
+    }
+
+    /// How long to wait for the endpoint and for each response
+    /// (default: 60s).
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Receive server-initiated notifications. Only notifications sent
+    /// after subscribing are delivered; a receiver that falls more than 64
+    /// behind skips the oldest.
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<JsonRpcNotification> {
+        self.notifications.subscribe()
+    }
+
+    /// The POST URL for the current connection, waiting for a reconnect to
+    /// announce one if necessary.
+    async fn current_endpoint(&self) -> Result<String, McpError> {
+        let mut endpoint = self.endpoint.clone();
+        let wait = async move {
+            endpoint
+                .wait_for(Option::is_some)
+                .await
+                .map(|url| url.clone().unwrap_or_default())
+        };
+        match tokio::time::timeout(self.request_timeout, wait).await {
+            Ok(Ok(url)) => Ok(url),
+            Ok(Err(_)) => Err(McpError::ConnectionClosed),
+            Err(_) => Err(McpError::Transport(
+                "Timed out waiting for the SSE endpoint event".into(),
+            )),
+        }
+    }
+}
+
+#[async_trait]
+impl McpTransport for SseTransport {
+    async fn send(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse, McpError> {
+        let endpoint = self.current_endpoint().await?;
+
+        // Notifications get no response; don't wait for one.
+        let is_notification = request.method.starts_with("notifications/");
+        let response_rx = if is_notification {
+            None
+        } else {
+            let (tx, rx) = oneshot::channel();
+            self.pending.lock().unwrap().insert(request.id, tx);
+            Some(rx)
+        };
+        let forget = || {
+            self.pending.lock().unwrap().remove(&request.id);
+        };
+
+        let resp = match self.client.post(&endpoint).json(&request).send().await {
+            Ok(resp) => resp,
+            Err(e) => {
+                forget();
+                return Err(McpError::Transport(format!("HTTP error: {}", e)));
+            }
+        };
+        if !resp.status().is_success() {
+            forget();
+            return Err(McpError::Transport(format!(
+                "HTTP {} from server",
+                resp.status()
+            )));
+        }
+
+        let Some(rx) = response_rx else {
+            return Ok(JsonRpcResponse {
+                jsonrpc: "2.0".into(),
+                id: None,
+                result: Some(serde_json::Value::Null),
+                error: None,
+            });
+        };
+        match tokio::time::timeout(self.request_timeout, rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(McpError::ConnectionClosed),
+            Err(_) => {
+                forget();
+                Err(McpError::Transport(format!(
+                    "Timed out waiting for response to '{}'",
+                    request.method
+                )))
+            }
+        }
+    }
+
+    async fn close(&self) -> Result<(), McpError> {
+        self.cancel.cancel();
+        Ok(())
+    }
+
+    fn take_server_requests(&self) -> Option<ServerRequests> {
+        self.server_requests.lock().unwrap().take()
+    }
+}
+
+impl Drop for SseTransport {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+/// Keep the event stream open, reconnecting with backoff when it drops.
+/// Exits on cancellation or after `reconnect.max_retries` consecutive
+/// failures, which closes the endpoint channel and fails later sends.
+async fn run_event_stream(
+    client: reqwest::Client,
+    url: reqwest::Url,
+    endpoint_tx: watch::Sender<Option<String>>,
+    routes: EventRoutes,
+    reconnect: RetryConfig,
+    cancel: CancellationToken,
+) {
+    let mut failures = 0;
+    loop {
+        let mut es = match EventSource::new(client.get(url.clone())) {
+            Ok(es) => es,
+            Err(e) => {
+                warn!("MCP SSE: cannot open event stream: {}", e);
+                return;
+            }
+        };
+
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    es.close();
+                    return;
+                }
+                event = es.next() => match event {
+                    None => break,
+                    Some(Ok(Event::Open)) => {
+                        debug!("MCP SSE stream opened: {}", url);
+                        failures = 0;
+                    }
+                    Some(Ok(Event::Message(msg))) if msg.event == "endpoint" => {
+                        match url.join(msg.data.trim()) {
+                            Ok(endpoint) => {
+                                endpoint_tx.send_replace(Some(endpoint.to_string()));
+                            }
+                            Err(e) => warn!("MCP SSE: invalid endpoint '{}': {}", msg.data, e),
+                        }
+                    }
+                    Some(Ok(Event::Message(msg))) => routes.dispatch(&msg.data),
+                    Some(Err(e)) => {
+                        debug!("MCP SSE stream error: {}", e);
+                        break;
+                    }
+                },
+            }
+        }
+        es.close();
+
+        // The old endpoint belongs to the dropped session, and responses to
+        // requests sent on it will never arrive.
+        endpoint_tx.send_replace(None);
+        routes.pending.lock().unwrap().clear();
+
+        failures += 1;
+        if failures > reconnect.max_retries {
+            warn!(
+                "MCP SSE stream dropped; giving up after {} reconnect attempts",
+                reconnect.max_retries
+            );
+            return;
+        }
+        let delay = reconnect.delay_for_attempt(failures);
+        warn!(
+            "MCP SSE stream dropped, reconnecting in {:.1}s",
+            delay.as_secs_f64()
+        );
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            _ = tokio::time::sleep(delay) => {}
+        }
+    }
+}
+
+/// Where messages arriving from the server (SSE events, WebSocket frames)
+/// go.
+struct EventRoutes {
+    pending: PendingResponses,
+    notifications: broadcast::Sender<JsonRpcNotification>,
+    server_requests: mpsc::UnboundedSender<JsonRpcRequest>,
+}
+
+impl EventRoutes {
+    /// Route one JSON-RPC message: responses go to the waiting request,
+    /// notifications to subscribers, server requests to whoever took them.
+    fn dispatch(&self, data: &str) {
+        let value: serde_json::Value = match serde_json::from_str(data) {
+            Ok(value) => value,
+            Err(e) => {
+                warn!("MCP: ignoring malformed message: {}", e);
+                return;
+            }
+        };
+
+        if value.get("method").is_some() {
+            if value.get("id").is_some() {
+                match serde_json::from_value::<JsonRpcRequest>(value) {
+                    Ok(request) => {
+                        if self.server_requests.send(request).is_err() {
+                            debug!("MCP: no handler for server requests");
+                        }
+                    }
+                    Err(e) => warn!("MCP: ignoring malformed server request: {}", e),
+                }
+                return;
+            }
+            match serde_json::from_value::<JsonRpcNotification>(value) {
+                // No subscribers is fine — the notification is just dropped.
+                Ok(notification) => {
+                    let _ = self.notifications.send(notification);
+                }
+                Err(e) => warn!("MCP: ignoring malformed notification: {}", e),
+            }
+            return;
+        }
+
+        match serde_json::from_value::<JsonRpcResponse>(value) {
+            Ok(response) => {
+                let waiter = response
+                    .id
+                    .and_then(|id| self.pending.lock().unwrap().remove(&id));
+                match waiter {
+                    Some(tx) => {
+                        let _ = tx.send(response);
+                    }
+                    None => debug!("MCP: response for unknown id {:?}", response.id),
+                }
+            }
+            Err(e) => warn!("MCP: ignoring malformed response: {}", e),
+        }
+    }
+}
+
+/// POST responses to server requests to the current endpoint. Runs without
+/// the client's transport lock, so a server can ask for sampling while one
+/// of our requests is still waiting.
+async fn post_responses(
+    client: reqwest::Client,
+    mut endpoint: watch::Receiver<Option<String>>,
+    mut responses: mpsc::UnboundedReceiver<JsonRpcResponse>,
+    cancel: CancellationToken,
+) {
+    loop {
+        let response = tokio::select! {
+            _ = cancel.cancelled() => return,
+            response = responses.recv() => match response {
+                Some(response) => response,
+                None => return,
+            },
+        };
+        let url = match endpoint.wait_for(Option::is_some).await {
+            Ok(url) => url.clone().unwrap_or_default(),
+            Err(_) => return,
+        };
+        match client.post(&url).json(&response).send().await {
+            Ok(resp) if !resp.status().is_success() => {
+                warn!("MCP SSE: server rejected response: HTTP {}", resp.status())
+            }
+            Ok(_) => {}
+            Err(e) => warn!("MCP SSE: failed to send response: {}", e),
+        }
+    }
+}
+
+// ---------------------------------------------------------------------------
+// WebSocket Transport
+// ---------------------------------------------------------------------------
+
+#[cfg(feature = "ws")]
+type WsStream =
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>;
+
+/// Communicates with an MCP server over a WebSocket: one JSON-RPC message
+/// per text frame, responses matched to requests by `id`.
+///
+/// If the connection drops, the transport reconnects with exponential
+/// backoff and replays the last `initialize` handshake before sending
+/// anything else, so the session continues transparently. Requests in
+/// flight at the moment of the drop fail with [`McpError::ConnectionClosed`];
+/// requests sent while reconnecting wait for the new connection. Server
+/// notifications and requests are delivered as with [`SseTransport`].
+#[cfg(feature = "ws")]
+pub struct WebSocketTransport {
+    outgoing: mpsc::UnboundedSender<String>,
+    connected: watch::Receiver<bool>,
+    pending: PendingResponses,
+    notifications: broadcast::Sender<JsonRpcNotification>,
+    server_requests: std::sync::Mutex<Option<ServerRequests>>,
+    handshake: Arc<std::sync::Mutex<Option<JsonRpcRequest>>>,
+    request_timeout: Duration,
+    cancel: CancellationToken,
+}
+
+/// What's needed to (re)open the connection.
+#[cfg(feature = "ws")]
+struct WsTarget {
+    url: String,
+    headers: HashMap<String, String>,
+}
+
+#[cfg(feature = "ws")]
+impl WsTarget {
+    async fn open(&self) -> Result<WsStream, McpError> {
+        use tungstenite::client::IntoClientRequest;
+        use tungstenite::http::{HeaderName, HeaderValue};
+
+        let mut request = self.url.as_str().into_client_request().map_err(|e| {
+            McpError::Transport(format!("Invalid WebSocket URL '{}': {}", self.url, e))
+        })?;
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| McpError::Transport(format!("Invalid header '{}': {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| McpError::Transport(format!("Invalid header value: {}", e)))?;
+            request.headers_mut().insert(name, value);
+        }
+        let (ws, _) = tokio_tungstenite::connect_async(request)
+            .await
+            .map_err(|e| McpError::Transport(format!("WebSocket connect error: {}", e)))?;
+        Ok(ws)
+    }
+}
+
+#[cfg(feature = "ws")]
+impl WebSocketTransport {
+    /// Connect to `url` (`ws://` or `wss://`), sending `headers` (e.g.
+    /// `Authorization`) with the upgrade request.
+    ///
+    /// Reconnects up to 10 times in a row (500ms initial delay, doubling,
+    /// capped at 30s) before giving up. The first connection isn't retried.
+    pub async fn connect(
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+    ) -> Result<Self, McpError> {
+        let reconnect = RetryConfig {
+            max_retries: 10,
+            initial_delay_ms: 500,
+            ..RetryConfig::default()
+        };
+        Self::connect_with_reconnect(url, headers, reconnect).await
+    }
+
+    /// Like [`connect`](Self::connect), with a custom reconnect policy.
+    /// `max_retries` counts consecutive failed attempts; a successful one
+    /// resets it.
+    pub async fn connect_with_reconnect(
+        url: &str,
+        headers: Option<HashMap<String, String>>,
+        reconnect: RetryConfig,
+    ) -> Result<Self, McpError> {
+        let target = WsTarget {
+            url: url.to_string(),
+            headers: headers.unwrap_or_default(),
+        };
+        let ws = target.open().await?;
+
+        let (outgoing, outgoing_rx) = mpsc::unbounded_channel();
+        let (connected_tx, connected) = watch::channel(true);
+        let pending: PendingResponses = Arc::default();
+        let (notifications, _) = broadcast::channel(64);
+        let (server_request_tx, requests) = mpsc::unbounded_channel();
+        let (responses, response_rx) = mpsc::unbounded_channel();
+        let handshake = Arc::new(std::sync::Mutex::new(None));
+        let request_timeout = Duration::from_secs(60);
+        let cancel = CancellationToken::new();
+
+        // Responses to server requests share the outgoing queue.
+        tokio::spawn(forward_responses(
+            response_rx,
+            outgoing.clone(),
+            cancel.clone(),
+        ));
+        tokio::spawn(run_websocket(
+            target,
+            ws,
+            outgoing_rx,
+            connected_tx,
+            EventRoutes {
+                pending: pending.clone(),
+                notifications: notifications.clone(),
+                server_requests: server_request_tx,
+            },
+            handshake.clone(),
+            WsReconnect {
+                policy: reconnect,
+                handshake_timeout: request_timeout,
+            },
+            cancel.clone(),
+        ));
+
+        Ok(Self {
+            outgoing,
+            connected,
+            pending,
+            notifications,
+            server_requests: std::sync::Mutex::new(Some(ServerRequests {
+                requests,
+                responses,
+            })),
+            handshake,
+            request_timeout,
+            cancel,
+        })
+    }
+
+    /// How long to wait for each response, and for a reconnect to finish
+    /// (default: 60s).
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Receive server-initiated notifications. Only notifications sent
+    /// after subscribing are delivered; a receiver that falls more than 64
+    /// behind skips the oldest.
+    pub fn subscribe_notifications(&self) -> broadcast::Receiver<JsonRpcNotification> {
+        self.notifications.subscribe()
+    }
+
+    /// Wait until the connection is up (or a reconnect has finished).
+    async fn wait_connected(&self) -> Result<(), McpError> {
+        let mut connected = self.connected.clone();
+        let wait = async move { connected.wait_for(|up| *up).await.map(|_| ()) };
+        match tokio::time::timeout(self.request_timeout, wait).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(_)) => Err(McpError::ConnectionClosed),
+            Err(_) => Err(McpError::Transport(
+                "Timed out waiting for the WebSocket to reconnect".into(),
+            )),
+        }
+    }
+}
+
+#[cfg(feature = "ws")]
+#[async_trait]
+impl McpTransport for WebSocketTransport {
+    async fn send(&self, request: JsonRpcRequest) -> Result<JsonRpcResponse, McpError> {
+        if request.method == "initialize" {
+            *self.handshake.lock().unwrap() = Some(request.clone());
+        }
+        self.wait_connected().await?;
+        let frame = serde_json::to_string(&request)?;
+
+        // Notifications get no response; don't wait for one.
+        if request.method.starts_with("notifications/") {
+            self.outgoing
+                .send(frame)
+                .map_err(|_| McpError::ConnectionClosed)?;
+            return Ok(JsonRpcResponse {
+                jsonrpc: "2.0".into(),
+                id: None,
+                result: Some(serde_json::Value::Null),
+                error: None,
+            });
+        }
+
+        let (tx, rx) = oneshot::channel();
+        self.pending.lock().unwrap().insert(request.id, tx);
+        if self.outgoing.send(frame).is_err() {
+            self.pending.lock().unwrap().remove(&request.id);
+            return Err(McpError::ConnectionClosed);
+        }
+        match tokio::time::timeout(self.request_timeout, rx).await {
+            Ok(Ok(response)) => Ok(response),
+            Ok(Err(_)) => Err(McpError::ConnectionClosed),
+            Err(_) => {
+                self.pending.lock().unwrap().remove(&request.id);
+                Err(McpError::Transport(format!(
+                    "Timed out waiting for response to '{}'",
+                    request.method
+                )))
+            }
+        }
+    }
+
+    async fn close(&self) -> Result<(), McpError> {
+        self.cancel.cancel();
+        Ok(())
+    }
+
+    fn take_server_requests(&self) -> Option<ServerRequests> {
+        self.server_requests.lock().unwrap().take()
+    }
+}
+
+#[cfg(feature = "ws")]
+impl Drop for WebSocketTransport {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+#[cfg(feature = "ws")]
+struct WsReconnect {
+    policy: RetryConfig,
+    handshake_timeout: Duration,
+}
+
+/// Pump frames both ways, reconnecting with backoff when the socket drops.
+/// Exits on cancellation or after `max_retries` consecutive failed
+/// reconnects, which closes `connected` and fails later sends.
+#[cfg(feature = "ws")]
+#[allow(clippy::too_many_arguments)]
+async fn run_websocket(
+    target: WsTarget,
+    mut ws: WsStream,
+    mut outgoing: mpsc::UnboundedReceiver<String>,
+    connected: watch::Sender<bool>,
+    routes: EventRoutes,
+    handshake: Arc<std::sync::Mutex<Option<JsonRpcRequest>>>,
+    reconnect: WsReconnect,
+    cancel: CancellationToken,
+) {
+    use tungstenite::Message;
+
+    loop {
+        connected.send_replace(true);
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    let _ = ws.close(None).await;
+                    return;
+                }
+                frame = outgoing.recv() => {
+                    let Some(frame) = frame else { return };
+                    if let Err(e) = ws.send(Message::text(frame)).await {
+                        debug!("MCP WebSocket write error: {}", e);
+                        break;
+                    }
+                }
+                message = ws.next() => match message {
+                    Some(Ok(Message::Text(text))) => routes.dispatch(text.as_str()),
+                    Some(Ok(Message::Binary(bytes))) => match std::str::from_utf8(&bytes) {
+                        Ok(text) => routes.dispatch(text),
+                        Err(_) => warn!("MCP WebSocket: ignoring non-UTF-8 binary frame"),
+                    },
+                    Some(Ok(Message::Close(_))) | None => break,
+                    Some(Ok(_)) => {} // ping/pong are answered by tungstenite
+                    Some(Err(e)) => {
+                        debug!("MCP WebSocket read error: {}", e);
+                        break;
+                    }
+                },
+            }
+        }
+
+        // Responses to requests sent on the old socket will never arrive,
+        // and frames still queued for it would reach the new session before
+        // its handshake.
+        connected.send_replace(false);
+        routes.pending.lock().unwrap().clear();
+        while outgoing.try_recv().is_ok() {}
+
+        let mut failures = 0;
+        ws = loop {
+            failures += 1;
+            if failures > reconnect.policy.max_retries {
+                warn!(
+                    "MCP WebSocket dropped; giving up after {} reconnect attempts",
+                    reconnect.policy.max_retries
+                );
+                return;
+            }
+            let delay = reconnect.policy.delay_for_attempt(failures);
+            warn!(
+                "MCP WebSocket dropped, reconnecting in {:.1}s",
+                delay.as_secs_f64()
+            );
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = tokio::time::sleep(delay) => {}
+            }
+            let init = handshake.lock().unwrap().clone();
+            let attempt = async {
+                let mut ws = target.open().await?;
+                if let Some(init) = init {
+                    replay_handshake(&mut ws, init, &routes, reconnect.handshake_timeout).await?;
+                }
+                Ok::<_, McpError>(ws)
+            };
+            match attempt.await {
+                Ok(ws) => break ws,
+                Err(e) => debug!("MCP WebSocket reconnect failed: {}", e),
+            }
+        };
+    }
+}
+
+/// Re-run `initialize` + `notifications/initialized` on a fresh socket,
+/// routing anything else the server sends meanwhile as usual.
+#[cfg(feature = "ws")]
+async fn replay_handshake(
+    ws: &mut WsStream,
+    init: JsonRpcRequest,
+    routes: &EventRoutes,
+    timeout: Duration,
+) -> Result<(), McpError> {
+    use tungstenite::Message;
+
+    let send_error = |e: tungstenite::Error| McpError::Transport(format!("WebSocket error: {}", e));
+    let request = JsonRpcRequest::new(init.method, init.params);
+    ws.send(Message::text(serde_json::to_string(&request)?))
+        .await
+        .map_err(send_error)?;
+
+    let wait_for_response = async {
+        while let Some(message) = ws.next().await {
+            let text = match message.map_err(send_error)? {
+                Message::Text(text) => text.to_string(),
+                Message::Close(_) => break,
+                _ => continue,
+            };
+            match serde_json::from_str::<JsonRpcResponse>(&text) {
+                Ok(response) if response.id == Some(request.id) => {
+                    return match response.error {
+                        Some(error) => Err(McpError::JsonRpc {
+                            code: error.code,
+                            message: error.message,
+                        }),
+                        None => Ok(()),
+                    };
+                }
+                _ => routes.dispatch(&text),
+            }
+        }
+        Err(McpError::ConnectionClosed)
+    };
+    tokio::time::timeout(timeout, wait_for_response)
+        .await
+        .map_err(|_| McpError::Transport("Timed out replaying the MCP handshake".into()))??;
+
+    let initialized = JsonRpcNotification {
+        jsonrpc: "2.0".into(),
+        method: "notifications/initialized".into(),
+        params: None,
+    };
+    ws.send(Message::text(serde_json::to_string(&initialized)?))
+        .await
+        .map_err(send_error)
+}
+
+/// Queue responses to server requests on the socket.
+#[cfg(feature = "ws")]
+async fn forward_responses(
+    mut responses: mpsc::UnboundedReceiver<JsonRpcResponse>,
+    outgoing: mpsc::UnboundedSender<String>,
+    cancel: CancellationToken,
+) {
+    loop {
+        let response = tokio::select! {
+            _ = cancel.cancelled() => return,
+            response = responses.recv() => match response {
+                Some(response) => response,
+                None => return,
+            },
+        };
+        match serde_json::to_string(&response) {
+            Ok(frame) => {
+                if outgoing.send(frame).is_err() {
+                    return;
+                }
+            }
+            Err(e) => warn!("MCP WebSocket: cannot encode response: {}", e),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -212,4 +1001,361 @@ mod tests {
         let transport = HttpTransport::new("http://localhost:8080/mcp/").unwrap();
         assert_eq!(transport.base_url, "http://localhost:8080/mcp");
     }
+
+    /// Minimal MCP HTTP+SSE server on a raw socket. Every `GET` opens an
+    /// event stream that announces `/messages?session=N`; `POST`ed requests
+    /// are handed to the test, which answers by pushing SSE events.
+    /// `POST`ed responses (to server-initiated requests) go to `replies`.
+    struct FakeSseServer {
+        url: String,
+        posts: tokio::sync::mpsc::UnboundedReceiver<(String, JsonRpcRequest)>,
+        replies: tokio::sync::mpsc::UnboundedReceiver<JsonRpcResponse>,
+        events: tokio::sync::mpsc::UnboundedSender<String>,
+    }
+
+    impl FakeSseServer {
+        /// `drop_first`: close the first event stream before it announces
+        /// an endpoint, forcing a reconnect.
+        async fn start(drop_first: bool) -> Self {
+            use tokio::sync::mpsc::unbounded_channel;
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/sse", listener.local_addr().unwrap());
+            let (post_tx, posts) = unbounded_channel();
+            let (reply_tx, replies) = unbounded_channel();
+            let (events, event_rx) = unbounded_channel::<String>();
+            let event_rx = Arc::new(Mutex::new(event_rx));
+
+            tokio::spawn(async move {
+                let mut sessions = 0;
+                loop {
+                    let (socket, _) = listener.accept().await.unwrap();
+                    let (read, mut write) = socket.into_split();
+                    let mut reader = BufReader::new(read);
+
+                    let mut request_line = String::new();
+                    reader.read_line(&mut request_line).await.unwrap();
+                    let mut content_length = 0;
+                    loop {
+                        let mut line = String::new();
+                        reader.read_line(&mut line).await.unwrap();
+                        if line.trim().is_empty() {
+                            break;
+                        }
+                        if let Some((name, value)) = line.split_once(':') {
+                            if name.eq_ignore_ascii_case("content-length") {
+                                content_length = value.trim().parse().unwrap();
+                            }
+                        }
+                    }
+
+                    if request_line.starts_with("GET") {
+                        sessions += 1;
+                        let session = sessions;
+                        let event_rx = event_rx.clone();
+                        tokio::spawn(async move {
+                            let head = "HTTP/1.1 200 OK\r\ncontent-type: text/event-stream\r\ncache-control: no-cache\r\n\r\n";
+                            let endpoint =
+                                format!("event: endpoint\ndata: /messages?session={}\n\n", session);
+                            write.write_all(head.as_bytes()).await.unwrap();
+                            if drop_first && session == 1 {
+                                return;
+                            }
+                            write.write_all(endpoint.as_bytes()).await.unwrap();
+                            let mut event_rx = event_rx.lock().await;
+                            while let Some(event) = event_rx.recv().await {
+                                let frame = format!("event: message\ndata: {}\n\n", event);
+                                if write.write_all(frame.as_bytes()).await.is_err() {
+                                    break;
+                                }
+                            }
+                        });
+                    } else {
+                        let mut body = vec![0; content_length];
+                        tokio::io::AsyncReadExt::read_exact(&mut reader, &mut body)
+                            .await
+                            .unwrap();
+                        let path = request_line.split_whitespace().nth(1).unwrap().to_string();
+                        let message: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                        if message.get("method").is_some() {
+                            post_tx
+                                .send((path, serde_json::from_value(message).unwrap()))
+                                .unwrap();
+                        } else {
+                            reply_tx
+                                .send(serde_json::from_value(message).unwrap())
+                                .unwrap();
+                        }
+                        write
+                            .write_all(b"HTTP/1.1 202 Accepted\r\ncontent-length: 0\r\n\r\n")
+                            .await
+                            .unwrap();
+                    }
+                }
+            });
+
+            Self {
+                url,
+                posts,
+                replies,
+                events,
+            }
+        }
+
+        fn respond(&self, id: u64, result: serde_json::Value) {
+            let response = serde_json::json!({"jsonrpc": "2.0", "id": id, "result": result});
+            self.events.send(response.to_string()).unwrap();
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sse_transport_correlates_responses_by_id() {
+        let mut server = FakeSseServer::start(false).await;
+        let transport = Arc::new(SseTransport::connect(&server.url).await.unwrap());
+        let mut notifications = transport.subscribe_notifications();
+
+        let send = |method: &str| {
+            let transport = transport.clone();
+            let request = JsonRpcRequest::new(method, None);
+            tokio::spawn(async move { transport.send(request).await })
+        };
+        let first = send("tools/list");
+        let (path, first_req) = server.posts.recv().await.unwrap();
+        assert_eq!(path, "/messages?session=1");
+        let second = send("resources/list");
+        let (_, second_req) = server.posts.recv().await.unwrap();
+
+        // Answer out of order, with a notification in between.
+        server.respond(second_req.id, serde_json::json!({"which": "second"}));
+        server
+            .events
+            .send(r#"{"jsonrpc":"2.0","method":"notifications/tools/list_changed"}"#.into())
+            .unwrap();
+        server.respond(first_req.id, serde_json::json!({"which": "first"}));
+
+        let first = first.await.unwrap().unwrap();
+        let second = second.await.unwrap().unwrap();
+        assert_eq!(first.id, Some(first_req.id));
+        assert_eq!(first.result.unwrap()["which"], "first");
+        assert_eq!(second.result.unwrap()["which"], "second");
+
+        let notification = notifications.recv().await.unwrap();
+        assert_eq!(notification.method, "notifications/tools/list_changed");
+
+        transport.close().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_sse_transport_notifications_do_not_wait_for_a_response() {
+        let mut server = FakeSseServer::start(false).await;
+        let transport = SseTransport::connect(&server.url).await.unwrap();
+
+        let response = transport
+            .send(JsonRpcRequest::new("notifications/initialized", None))
+            .await
+            .unwrap();
+        assert!(response.error.is_none());
+        let (_, posted) = server.posts.recv().await.unwrap();
+        assert_eq!(posted.method, "notifications/initialized");
+    }
+
+    #[tokio::test]
+    async fn test_sse_transport_reconnects_after_stream_drop() {
+        let mut server = FakeSseServer::start(true).await;
+        let reconnect = RetryConfig {
+            max_retries: 3,
+            initial_delay_ms: 10,
+            ..RetryConfig::default()
+        };
+        let transport = Arc::new(
+            SseTransport::connect_with_reconnect(&server.url, reconnect)
+                .await
+                .unwrap(),
+        );
+
+        // The first stream dropped; requests go to the second session.
+        let request = JsonRpcRequest::new("ping", None);
+        let id = request.id;
+        let sender = transport.clone();
+        let pending = tokio::spawn(async move { sender.send(request).await });
+        let (path, _) = server.posts.recv().await.unwrap();
+        assert_eq!(path, "/messages?session=2");
+
+        server.respond(id, serde_json::json!({}));
+        assert_eq!(pending.await.unwrap().unwrap().id, Some(id));
+    }
+
+    #[tokio::test]
+    async fn test_sse_transport_forwards_server_requests() {
+        let mut server = FakeSseServer::start(false).await;
+        let transport = SseTransport::connect(&server.url).await.unwrap();
+        let mut channel = transport.take_server_requests().unwrap();
+        assert!(transport.take_server_requests().is_none());
+
+        server
+            .events
+            .send(r#"{"jsonrpc":"2.0","id":7,"method":"ping"}"#.into())
+            .unwrap();
+        let request = channel.requests.recv().await.unwrap();
+        assert_eq!((request.id, request.method.as_str()), (7, "ping"));
+
+        channel
+            .responses
+            .send(JsonRpcResponse {
+                jsonrpc: "2.0".into(),
+                id: Some(7),
+                result: Some(serde_json::json!({})),
+                error: None,
+            })
+            .unwrap();
+        let reply = server.replies.recv().await.unwrap();
+        assert_eq!(reply.id, Some(7));
+        assert_eq!(reply.result, Some(serde_json::json!({})));
+    }
+
+    /// Minimal MCP WebSocket server. Reports `(session, method)` for every
+    /// message it receives and closes session 1 after answering
+    /// `tools/list`, forcing a reconnect.
+    #[cfg(feature = "ws")]
+    struct FakeWsServer {
+        url: String,
+        received: tokio::sync::mpsc::UnboundedReceiver<(usize, String)>,
+        auth: tokio::sync::mpsc::UnboundedReceiver<Option<String>>,
+    }
+
+    #[cfg(feature = "ws")]
+    impl FakeWsServer {
+        // The handshake callback's error type is fixed by tungstenite.
+        #[allow(clippy::result_large_err)]
+        async fn start() -> Self {
+            use tokio::sync::mpsc::unbounded_channel;
+            use tungstenite::handshake::server::{Request, Response};
+            use tungstenite::Message;
+
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("ws://{}/mcp", listener.local_addr().unwrap());
+            let (received_tx, received) = unbounded_channel();
+            let (auth_tx, auth) = unbounded_channel();
+
+            tokio::spawn(async move {
+                let mut session = 0;
+                loop {
+                    let (socket, _) = listener.accept().await.unwrap();
+                    session += 1;
+                    let auth_tx = auth_tx.clone();
+                    let callback = move |request: &Request, response: Response| {
+                        let header = request
+                            .headers()
+                            .get("authorization")
+                            .map(|v| v.to_str().unwrap().to_string());
+                        auth_tx.send(header).unwrap();
+                        Ok(response)
+                    };
+                    let mut ws = tokio_tungstenite::accept_hdr_async(socket, callback)
+                        .await
+                        .unwrap();
+                    let received_tx = received_tx.clone();
+                    tokio::spawn(async move {
+                        while let Some(Ok(Message::Text(text))) = ws.next().await {
+                            let request: serde_json::Value =
+                                serde_json::from_str(text.as_str()).unwrap();
+                            let method = request["method"].as_str().unwrap().to_string();
+                            received_tx.send((session, method.clone())).unwrap();
+                            let result = match method.as_str() {
+                                "initialize" => serde_json::json!({
+                                    "protocolVersion": "2024-11-05",
+                                    "capabilities": {"tools": {}},
+                                    "serverInfo": {"name": "fake-ws"}
+                                }),
+                                "tools/list" => serde_json::json!({"tools": [
+                                    {"name": "echo", "inputSchema": {"type": "object"}}
+                                ]}),
+                                "tools/call" => serde_json::json!({"content": [
+                                    {"type": "text", "text": format!("session {}", session)}
+                                ]}),
+                                _ => continue,
+                            };
+                            let response = serde_json::json!({
+                                "jsonrpc": "2.0", "id": request["id"], "result": result
+                            });
+                            ws.send(Message::text(response.to_string())).await.unwrap();
+                            if session == 1 && method == "tools/list" {
+                                let _ = ws.close(None).await;
+                                return;
+                            }
+                        }
+                    });
+                }
+            });
+
+            Self {
+                url,
+                received,
+                auth,
+            }
+        }
+
+        /// Wait until `session` has received `method`.
+        async fn expect(&mut self, session: usize, method: &str) {
+            while let Some((s, m)) = self.received.recv().await {
+                if (s, m.as_str()) == (session, method) {
+                    return;
+                }
+            }
+            panic!("server stopped before session {} got {}", session, method);
+        }
+    }
+
+    #[cfg(feature = "ws")]
+    #[tokio::test]
+    async fn test_websocket_client_survives_reconnect() {
+        let mut server = FakeWsServer::start().await;
+        let headers = HashMap::from([("Authorization".to_string(), "Bearer t0k".to_string())]);
+        let client = crate::mcp::McpClient::connect_ws(&server.url, Some(headers))
+            .await
+            .unwrap();
+        assert_eq!(client.server_info().unwrap().name, "fake-ws");
+        assert_eq!(
+            server.auth.recv().await.unwrap().as_deref(),
+            Some("Bearer t0k")
+        );
+
+        let tools = client.list_tools().await.unwrap();
+        assert_eq!(tools[0].name, "echo");
+
+        // Session 1 is closed; the transport reconnects with the same
+        // headers and replays the handshake before anything else.
+        server.expect(2, "initialize").await;
+        assert_eq!(
+            server.auth.recv().await.unwrap().as_deref(),
+            Some("Bearer t0k")
+        );
+        server.expect(2, "notifications/initialized").await;
+
+        let result = client
+            .call_tool("echo", serde_json::json!({}))
+            .await
+            .unwrap();
+        assert!(matches!(&result.content[0], McpContent::Text { text } if text == "session 2"));
+        client.close().await.unwrap();
+    }
+
+    #[cfg(feature = "ws")]
+    #[tokio::test]
+    async fn test_websocket_transport_fails_without_server() {
+        let result = WebSocketTransport::connect("ws://127.0.0.1:9/mcp", None).await;
+        assert!(matches!(result, Err(McpError::Transport(_))));
+    }
+
+    #[tokio::test]
+    async fn test_sse_transport_fails_without_server() {
+        let reconnect = RetryConfig {
+            max_retries: 1,
+            initial_delay_ms: 1,
+            ..RetryConfig::default()
+        };
+        let result =
+            SseTransport::connect_with_reconnect("http://127.0.0.1:9/sse", reconnect).await;
+        assert!(matches!(result, Err(McpError::ConnectionClosed)));
+    }
 }
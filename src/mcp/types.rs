@@ -44,6 +44,16 @@ pub struct JsonRpcResponse {
     pub error: Option<JsonRpcError>,
 }
 
+/// A message without an `id` — no response is expected. Servers send these
+/// unprompted, e.g. `notifications/tools/list_changed`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonRpcNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_json::Value>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcError {
     pub code: i64,
@@ -141,6 +151,151 @@ pub struct McpToolCallResult {
     pub is_error: bool,
 }
 
+/// MCP resource as returned by resources/list.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpResource {
+    pub uri: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+}
+
+/// resources/list result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourcesListResult {
+    pub resources: Vec<McpResource>,
+}
+
+/// One item in a resources/read result — text or base64 `blob`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(default)]
+    pub mime_type: Option<String>,
+    #[serde(default)]
+    pub text: Option<String>,
+    #[serde(default)]
+    pub blob: Option<String>,
+}
+
+impl From<ResourceContents> for McpContent {
+    /// Text stays text and image blobs become images. Other binary blobs
+    /// can't be shown to the model, so they're replaced by a short note.
+    fn from(c: ResourceContents) -> Self {
+        let mime_type = c.mime_type.unwrap_or_default();
+        match (c.text, c.blob) {
+            (Some(text), _) => McpContent::Text { text },
+            (None, Some(data)) if mime_type.starts_with("image/") => {
+                McpContent::Image { data, mime_type }
+            }
+            (None, Some(data)) => McpContent::Text {
+                text: format!(
+                    "[binary resource {} ({}), {} bytes base64]",
+                    c.uri,
+                    if mime_type.is_empty() {
+                        "unknown type"
+                    } else {
+                        &mime_type
+                    },
+                    data.len()
+                ),
+            },
+            (None, None) => McpContent::Text {
+                text: String::new(),
+            },
+        }
+    }
+}
+
+/// resources/read result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourcesReadResult {
+    pub contents: Vec<ResourceContents>,
+}
+
+/// A resource read with [`McpClient::read_resource`](super::McpClient::read_resource).
+#[derive(Debug, Clone)]
+pub struct McpResourceContent {
+    /// The URI that was read.
+    pub uri: String,
+    /// One entry per item the server returned (a directory-like resource
+    /// may return several).
+    pub contents: Vec<McpContent>,
+}
+
+// ---------------------------------------------------------------------------
+// Sampling (server → client)
+// ---------------------------------------------------------------------------
+
+/// Params of a `sampling/createMessage` request: the server asks the client
+/// to run an LLM call on its behalf.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMessageParams {
+    pub messages: Vec<SamplingMessage>,
+    #[serde(default)]
+    pub model_preferences: Option<ModelPreferences>,
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// `"none"`, `"thisServer"` or `"allServers"` — a hint; the client
+    /// decides what context to include.
+    #[serde(default)]
+    pub include_context: Option<String>,
+    #[serde(default)]
+    pub temperature: Option<f32>,
+    pub max_tokens: u32,
+    #[serde(default)]
+    pub stop_sequences: Option<Vec<String>>,
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// One message in a sampling request or result.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SamplingMessage {
+    /// `"user"` or `"assistant"`.
+    pub role: String,
+    pub content: McpContent,
+}
+
+/// The server's model preferences. Priorities are 0.0–1.0; hints are
+/// advisory (a client may map them to an equivalent model).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPreferences {
+    #[serde(default)]
+    pub hints: Vec<ModelHint>,
+    #[serde(default)]
+    pub cost_priority: Option<f64>,
+    #[serde(default)]
+    pub speed_priority: Option<f64>,
+    #[serde(default)]
+    pub intelligence_priority: Option<f64>,
+}
+
+/// A model name or substring (e.g. `"claude-3-5-sonnet"`, `"sonnet"`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelHint {
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Result of `sampling/createMessage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CreateMessageResult {
+    pub role: String,
+    pub content: McpContent,
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<String>,
+}
+
 // ---------------------------------------------------------------------------
 // MCP Error
 // ---------------------------------------------------------------------------
@@ -225,6 +380,25 @@ mod tests {
         assert!(!result.is_error);
     }
 
+    #[test]
+    fn test_resource_contents_conversion() {
+        let json = r#"{"contents":[
+            {"uri":"file:///a.txt","mimeType":"text/plain","text":"hello"},
+            {"uri":"file:///b.png","mimeType":"image/png","blob":"iVBORw0KGgo="},
+            {"uri":"file:///c.bin","blob":"AAAA"}
+        ]}"#;
+        let result: ResourcesReadResult = serde_json::from_str(json).unwrap();
+        let content: Vec<McpContent> = result.contents.into_iter().map(Into::into).collect();
+
+        assert!(matches!(&content[0], McpContent::Text { text } if text == "hello"));
+        assert!(
+            matches!(&content[1], McpContent::Image { mime_type, .. } if mime_type == "image/png")
+        );
+        assert!(
+            matches!(&content[2], McpContent::Text { text } if text == "[binary resource file:///c.bin (unknown type), 4 bytes base64]")
+        );
+    }
+
     #[test]
     fn test_unique_request_ids() {
         let id1 = next_request_id();
@@ -0,0 +1,172 @@
+//! Metrics — count turns, tokens and tool calls of a running agent.
+//!
+//! The loop reports every LLM call and tool call to the [`MetricsSink`] set
+//! as [`AgentLoopConfig::metrics_sink`](crate::agent_loop::AgentLoopConfig::metrics_sink)
+//! (or [`Agent::with_metrics_sink`](crate::Agent::with_metrics_sink)).
+//! Implement the trait to feed any metrics backend; with the `prometheus`
+//! feature, [`PrometheusMetrics`] is a ready-made one.
+//!
+//! ```ignore
+//! use std::sync::Arc;
+//! use yoagent::{Agent, metrics::PrometheusMetrics, provider::ModelConfig};
+//!
+//! let metrics = Arc::new(PrometheusMetrics::new());
+//! let agent = Agent::from_config(ModelConfig::anthropic("claude-sonnet-4-20250514", "Claude Sonnet 4"))
+//!     .with_metrics_sink(metrics.clone());
+//!
+//! // In the /metrics handler:
+//! let body = prometheus::TextEncoder::new()
+//!     .encode_to_string(&metrics.registry().gather())
+//!     .unwrap();
+//! ```
+
+use crate::types::{ToolResult, Usage};
+use std::time::Duration;
+
+/// Receives a measurement for every LLM call and tool call the loop makes.
+///
+/// Called inline on the loop's task, so implementations should only update
+/// in-memory counters — never block or do I/O.
+pub trait MetricsSink: Send + Sync {
+    /// An LLM call finished, including failed, aborted and timed-out ones.
+    /// `duration` covers the whole streamed response, retries included.
+    fn record_turn(&self, model: &str, tokens: &Usage, duration: Duration);
+
+    /// A tool call finished. Calls served from the tool cache are recorded
+    /// (with their near-zero duration); calls denied before running are not.
+    fn record_tool_call(&self, tool_name: &str, is_error: bool, duration: Duration);
+
+    /// The same tool call with its arguments and result, for sinks that keep
+    /// a record per call rather than counters (e.g.
+    /// [`AuditLog`](crate::tools::audit::AuditLog)). Called right after
+    /// [`record_tool_call`](Self::record_tool_call). Does nothing by default.
+    fn record_tool_result(&self, call: &ToolCallRecord<'_>) {
+        let _ = call;
+    }
+}
+
+/// A finished tool call, passed to [`MetricsSink::record_tool_result`].
+#[derive(Debug, Clone, Copy)]
+pub struct ToolCallRecord<'a> {
+    pub tool_call_id: &'a str,
+    pub tool_name: &'a str,
+    /// Arguments the tool ran with, after any middleware changes.
+    pub args: &'a serde_json::Value,
+    pub result: &'a ToolResult,
+    pub is_error: bool,
+    pub duration: Duration,
+}
+
+#[cfg(feature = "prometheus")]
+pub use self::prometheus_metrics::PrometheusMetrics;
+
+#[cfg(feature = "prometheus")]
+mod prometheus_metrics {
+    use super::MetricsSink;
+    use crate::types::Usage;
+    use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry};
+    use std::time::Duration;
+
+    /// [`MetricsSink`] backed by the `prometheus` crate (feature
+    /// `prometheus`).
+    ///
+    /// Exposes:
+    /// - `yoagent_turns_total{model}` — LLM calls
+    /// - `yoagent_turn_duration_seconds{model}` — LLM call latency
+    /// - `yoagent_token_usage_total{model, type}` — tokens, with `type` one
+    ///   of `input`, `output`, `cache_read`, `cache_write`
+    /// - `yoagent_tool_call_duration_seconds{tool, status}` — tool latency,
+    ///   with `status` `ok` or `error`; its `_count` counts calls
+    pub struct PrometheusMetrics {
+        registry: Registry,
+        turns: IntCounterVec,
+        turn_duration: HistogramVec,
+        tokens: IntCounterVec,
+        tool_duration: HistogramVec,
+    }
+
+    impl PrometheusMetrics {
+        /// Register the metrics in a new, empty registry.
+        pub fn new() -> Self {
+            Self::with_registry(Registry::new()).expect("a new registry has no conflicting metrics")
+        }
+
+        /// Register the metrics in `registry`, e.g. one shared with the rest
+        /// of the application. Fails if it already holds yoagent metrics.
+        pub fn with_registry(registry: Registry) -> prometheus::Result<Self> {
+            let turns = IntCounterVec::new(
+                Opts::new("yoagent_turns_total", "LLM calls made by the agent loop"),
+                &["model"],
+            )?;
+            let turn_duration = HistogramVec::new(
+                HistogramOpts::new(
+                    "yoagent_turn_duration_seconds",
+                    "Time to stream one LLM response",
+                )
+                .buckets(vec![0.5, 1.0, 2.5, 5.0, 10.0, 20.0, 40.0, 80.0, 160.0]),
+                &["model"],
+            )?;
+            let tokens = IntCounterVec::new(
+                Opts::new(
+                    "yoagent_token_usage_total",
+                    "Tokens reported by the provider",
+                ),
+                &["model", "type"],
+            )?;
+            let tool_duration = HistogramVec::new(
+                HistogramOpts::new(
+                    "yoagent_tool_call_duration_seconds",
+                    "Time to run one tool call",
+                ),
+                &["tool", "status"],
+            )?;
+            registry.register(Box::new(turns.clone()))?;
+            registry.register(Box::new(turn_duration.clone()))?;
+            registry.register(Box::new(tokens.clone()))?;
+            registry.register(Box::new(tool_duration.clone()))?;
+            Ok(Self {
+                registry,
+                turns,
+                turn_duration,
+                tokens,
+                tool_duration,
+            })
+        }
+
+        /// The registry holding the metrics — gather it from your `/metrics`
+        /// endpoint (axum, actix, ...).
+        pub fn registry(&self) -> &Registry {
+            &self.registry
+        }
+    }
+
+    impl Default for PrometheusMetrics {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl MetricsSink for PrometheusMetrics {
+        fn record_turn(&self, model: &str, tokens: &Usage, duration: Duration) {
+            self.turns.with_label_values(&[model]).inc();
+            self.turn_duration
+                .with_label_values(&[model])
+                .observe(duration.as_secs_f64());
+            for (kind, count) in [
+                ("input", tokens.input),
+                ("output", tokens.output),
+                ("cache_read", tokens.cache_read),
+                ("cache_write", tokens.cache_write),
+            ] {
+                self.tokens.with_label_values(&[model, kind]).inc_by(count);
+            }
+        }
+
+        fn record_tool_call(&self, tool_name: &str, is_error: bool, duration: Duration) {
+            let status = if is_error { "error" } else { "ok" };
+            self.tool_duration
+                .with_label_values(&[tool_name, status])
+                .observe(duration.as_secs_f64());
+        }
+    }
+}
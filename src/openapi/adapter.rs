@@ -11,7 +11,7 @@ use std::sync::Arc;
 /// Created via factory methods that parse an OpenAPI spec and produce
 /// one adapter per operation. Each adapter makes HTTP requests to the
 /// API endpoint when executed.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct OpenApiToolAdapter {
     client: Arc<reqwest::Client>,
     config: OpenApiConfig,
@@ -318,6 +318,10 @@ impl AgentTool for OpenApiToolAdapter {
             }),
         })
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -472,29 +476,68 @@ fn resolve_parameter<'a>(
     }
 }
 
+/// Convert a schema to JSON with every `#/components/schemas/` reference
+/// inlined — including those nested in properties, array items and
+/// `allOf`/`oneOf`/`anyOf` — since the model can't follow a `$ref`.
 fn resolve_schema_to_json(
     spec: &OpenAPI,
     ref_or: &ReferenceOr<Schema>,
 ) -> Result<serde_json::Value, OpenApiError> {
-    match ref_or {
-        ReferenceOr::Item(schema) => serde_json::to_value(schema).map_err(OpenApiError::JsonError),
-        ReferenceOr::Reference { reference } => {
-            let name = reference
-                .strip_prefix("#/components/schemas/")
-                .ok_or_else(|| {
-                    OpenApiError::InvalidSpec(format!("Unsupported schema $ref: {}", reference))
-                })?;
-            let components = spec
-                .components
-                .as_ref()
-                .ok_or_else(|| OpenApiError::InvalidSpec("No components section".into()))?;
-            let schema = components
-                .schemas
-                .get(name)
-                .and_then(|r| r.as_item())
-                .ok_or_else(|| OpenApiError::InvalidSpec(format!("Schema not found: {}", name)))?;
-            serde_json::to_value(schema).map_err(OpenApiError::JsonError)
+    let mut json = serde_json::to_value(ref_or).map_err(OpenApiError::JsonError)?;
+    inline_schema_refs(spec, &mut json, &mut Vec::new())?;
+    Ok(json)
+}
+
+/// Replace `$ref` objects in `json` with the schemas they name. `seen` holds
+/// the schemas being inlined on the current path; a reference back to one of
+/// them (a recursive schema) becomes an unconstrained schema instead.
+fn inline_schema_refs(
+    spec: &OpenAPI,
+    json: &mut serde_json::Value,
+    seen: &mut Vec<String>,
+) -> Result<(), OpenApiError> {
+    match json {
+        serde_json::Value::Object(map) => {
+            if let Some(reference) = map.get("$ref").and_then(|r| r.as_str()) {
+                let name = reference
+                    .strip_prefix("#/components/schemas/")
+                    .ok_or_else(|| {
+                        OpenApiError::InvalidSpec(format!("Unsupported schema $ref: {}", reference))
+                    })?
+                    .to_string();
+                if seen.contains(&name) {
+                    *json = serde_json::json!({ "description": format!("A {} (recursive)", name) });
+                    return Ok(());
+                }
+                let components = spec
+                    .components
+                    .as_ref()
+                    .ok_or_else(|| OpenApiError::InvalidSpec("No components section".into()))?;
+                let schema = components
+                    .schemas
+                    .get(&name)
+                    .and_then(|r| r.as_item())
+                    .ok_or_else(|| {
+                        OpenApiError::InvalidSpec(format!("Schema not found: {}", name))
+                    })?;
+                *json = serde_json::to_value(schema).map_err(OpenApiError::JsonError)?;
+                seen.push(name);
+                let inlined = inline_schema_refs(spec, json, seen);
+                seen.pop();
+                return inlined;
+            }
+            for value in map.values_mut() {
+                inline_schema_refs(spec, value, seen)?;
+            }
+            Ok(())
+        }
+        serde_json::Value::Array(items) => {
+            for value in items {
+                inline_schema_refs(spec, value, seen)?;
+            }
+            Ok(())
         }
+        _ => Ok(()),
     }
 }
 
@@ -915,6 +958,59 @@ paths:
         assert!(schema["properties"]["body"].is_object());
     }
 
+    #[test]
+    fn test_ref_resolution_nested_and_recursive_schemas() {
+        let spec = r##"{
+            "openapi": "3.0.0",
+            "info": { "title": "Test", "version": "1.0.0" },
+            "servers": [{ "url": "https://api.example.com" }],
+            "paths": {
+                "/folders": {
+                    "post": {
+                        "operationId": "createFolder",
+                        "requestBody": {
+                            "content": {
+                                "application/json": {
+                                    "schema": { "$ref": "#/components/schemas/Folder" }
+                                }
+                            }
+                        },
+                        "responses": { "201": { "description": "created" } }
+                    }
+                }
+            },
+            "components": {
+                "schemas": {
+                    "Folder": {
+                        "type": "object",
+                        "properties": {
+                            "owner": { "$ref": "#/components/schemas/User" },
+                            "children": {
+                                "type": "array",
+                                "items": { "$ref": "#/components/schemas/Folder" }
+                            }
+                        }
+                    },
+                    "User": {
+                        "type": "object",
+                        "properties": { "email": { "type": "string" } }
+                    }
+                }
+            }
+        }"##;
+        let adapters =
+            OpenApiToolAdapter::from_str(spec, OpenApiConfig::default(), &OperationFilter::All)
+                .unwrap();
+        let body = &adapters[0].parameters_schema()["properties"]["body"];
+        assert_eq!(
+            body["properties"]["owner"]["properties"]["email"]["type"],
+            "string"
+        );
+        let child = &body["properties"]["children"]["items"];
+        assert!(child.get("$ref").is_none());
+        assert_eq!(child["description"], "A Folder (recursive)");
+    }
+
     #[test]
     fn test_operations_without_id_are_skipped() {
         let spec = r#"{
@@ -17,9 +17,40 @@
 //! # Ok(())
 //! # }
 //! ```
+//!
+//! For a spec already in memory as JSON, [`tools_from_openapi`] is the
+//! one-call shortcut (every operation, explicit base URL and auth).
 
 pub mod adapter;
 pub mod types;
 
 pub use adapter::OpenApiToolAdapter;
 pub use types::{OpenApiAuth, OpenApiConfig, OpenApiError, OperationFilter};
+
+use crate::types::AgentTool;
+
+/// Turn every operation of an in-memory OpenAPI 3.0 spec into boxed tools.
+///
+/// Shortcut over [`OpenApiToolAdapter::from_spec`] with
+/// [`OperationFilter::All`]. An empty `base_url` falls back to the spec's
+/// first `servers` entry.
+pub fn tools_from_openapi(
+    spec: serde_json::Value,
+    base_url: &str,
+    auth: OpenApiAuth,
+) -> Result<Vec<Box<dyn AgentTool>>, OpenApiError> {
+    let spec: openapiv3::OpenAPI = serde_json::from_value(spec)
+        .map_err(|e| OpenApiError::ParseError(format!("JSON: {}", e)))?;
+    let mut config = OpenApiConfig {
+        auth,
+        ..OpenApiConfig::default()
+    };
+    if !base_url.is_empty() {
+        config = config.with_base_url(base_url);
+    }
+    let adapters = OpenApiToolAdapter::from_spec(spec, config, &OperationFilter::All)?;
+    Ok(adapters
+        .into_iter()
+        .map(|a| Box::new(a) as Box<dyn AgentTool>)
+        .collect())
+}
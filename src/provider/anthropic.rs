@@ -3,6 +3,7 @@
 use super::traits::*;
 use crate::types::*;
 use async_trait::async_trait;
+use base64::Engine;
 use futures::StreamExt;
 use reqwest_eventsource::{Event, EventSource};
 use serde::Deserialize;
@@ -11,6 +12,9 @@ use tracing::{debug, warn};
 
 const API_URL: &str = "https://api.anthropic.com/v1/messages";
 const API_VERSION: &str = "2023-06-01";
+/// System prompt addition for JSON mode when tools rule out tool-forcing.
+const JSON_OBJECT_INSTRUCTION: &str =
+    "When you give your final answer, reply with a single JSON object and nothing else.";
 
 /// Resolve the request URL: `{base_url}/messages` when a `ModelConfig` is set
 /// (e.g. a gateway like OpenCode Zen), the official endpoint otherwise.
@@ -47,6 +51,13 @@ impl StreamProvider for AnthropicProvider {
         Some(crate::provider::ApiProtocol::AnthropicMessages)
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            max_context_tokens: Some(200_000),
+            ..ProviderCapabilities::default()
+        }
+    }
+
     async fn stream(
         &self,
         config: StreamConfig,
@@ -158,6 +169,20 @@ impl StreamProvider for AnthropicProvider {
                                                     name,
                                                 });
                                             }
+                                            // Run by Anthropic: recorded, never announced as a tool call.
+                                            AnthropicContentBlock::ServerToolUse { id, name } => {
+                                                while content.len() <= idx {
+                                                    content.push(Content::server_tool_use(
+                                                        id.clone(),
+                                                        name.clone(),
+                                                        serde_json::Value::Object(Default::default()),
+                                                    ));
+                                                }
+                                            }
+                                        }
+                                    } else if let Some((idx, block)) = server_tool_result_start(&msg.data) {
+                                        while content.len() <= idx {
+                                            content.push(block.clone());
                                         }
                                     }
                                 }
@@ -184,8 +209,13 @@ impl StreamProvider for AnthropicProvider {
                                                 });
                                             }
                                             AnthropicDelta::InputJsonDelta { partial_json } => {
+                                                let server_call = matches!(content.get(idx), Some(Content::ServerToolUse { .. }));
                                                 // Accumulate JSON into a buffer for this tool call
-                                                if let Some(Content::ToolCall { ref mut arguments, .. }) = content.get_mut(idx) {
+                                                if let Some(
+                                                    Content::ToolCall { ref mut arguments, .. }
+                                                    | Content::ServerToolUse { input: ref mut arguments, .. },
+                                                ) = content.get_mut(idx)
+                                                {
                                                     // Append to string buffer stored in arguments
                                                     // We accumulate the raw JSON string and parse it at content_block_stop
                                                     let buf = arguments
@@ -197,10 +227,12 @@ impl StreamProvider for AnthropicProvider {
                                                         obj.insert("__partial_json".into(), serde_json::Value::String(new_buf));
                                                     }
                                                 }
-                                                let _ = tx.send(StreamEvent::ToolCallDelta {
-                                                    content_index: idx,
-                                                    delta: partial_json,
-                                                });
+                                                if !server_call {
+                                                    let _ = tx.send(StreamEvent::ToolCallDelta {
+                                                        content_index: idx,
+                                                        delta: partial_json,
+                                                    });
+                                                }
                                             }
                                             AnthropicDelta::SignatureDelta { signature } => {
                                                 if let Some(Content::Thinking { signature: ref mut s, .. }) = content.get_mut(idx) {
@@ -214,7 +246,11 @@ impl StreamProvider for AnthropicProvider {
                                     if let Ok(data) = serde_json::from_str::<serde_json::Value>(&msg.data) {
                                         let idx = data["index"].as_u64().unwrap_or(0) as usize;
                                         // Parse accumulated JSON for tool calls
-                                        if let Some(Content::ToolCall { ref mut arguments, .. }) = content.get_mut(idx) {
+                                        if let Some(
+                                            Content::ToolCall { ref mut arguments, .. }
+                                            | Content::ServerToolUse { input: ref mut arguments, .. },
+                                        ) = content.get_mut(idx)
+                                        {
                                             if let Some(partial) = arguments.as_object()
                                                 .and_then(|o| o.get("__partial_json"))
                                                 .and_then(|v| v.as_str())
@@ -236,6 +272,8 @@ impl StreamProvider for AnthropicProvider {
                                         stop_reason = match data.delta.stop_reason.as_deref() {
                                             Some("tool_use") => StopReason::ToolUse,
                                             Some("max_tokens") => StopReason::Length,
+                                            Some("stop_sequence") => StopReason::StopSequence,
+                                            Some("pause_turn") => StopReason::PauseTurn,
                                             Some("model_context_window_exceeded") => {
                                                 // In-stream overflow (HTTP 200). Map to the same
                                                 // Error + overflow-phrase shape as an HTTP 400
@@ -342,8 +380,15 @@ fn build_request_body(config: &StreamConfig, is_oauth: bool) -> serde_json::Valu
                 is_error,
                 ..
             } => {
-                let result_content = if content.iter().any(|c| matches!(c, Content::Image { .. })) {
-                    // Multi-content with images: use array format
+                let result_content = if content.iter().any(|c| {
+                    matches!(
+                        c,
+                        Content::Image { .. }
+                            | Content::Document { .. }
+                            | Content::DocumentUrl { .. }
+                    )
+                }) {
+                    // Multi-content with images or documents: use array format
                     serde_json::json!(content_to_anthropic(content))
                 } else {
                     // Text-only: use string shorthand
@@ -472,8 +517,9 @@ fn build_request_body(config: &StreamConfig, is_oauth: bool) -> serde_json::Valu
         body["system"] = serde_json::json!([block]);
     }
 
-    // Breakpoint 2: last tool definition (tools are stable between turns)
-    if !config.tools.is_empty() {
+    // Breakpoint 2: last tool definition (tools are stable between turns).
+    // Server tools follow the function tools; both share the one breakpoint.
+    if !config.tools.is_empty() || !config.server_tools.is_empty() {
         let mut tools: Vec<serde_json::Value> = config
             .tools
             .iter()
@@ -484,6 +530,12 @@ fn build_request_body(config: &StreamConfig, is_oauth: bool) -> serde_json::Valu
                     "input_schema": t.parameters,
                 })
             })
+            .chain(
+                config
+                    .server_tools
+                    .iter()
+                    .map(|t| serde_json::to_value(t).unwrap_or_default()),
+            )
             .collect();
         if caching_enabled && cache_tools {
             if let Some(last_tool) = tools.last_mut() {
@@ -492,34 +544,79 @@ fn build_request_body(config: &StreamConfig, is_oauth: bool) -> serde_json::Valu
         }
         body["tools"] = serde_json::json!(tools);
     }
+    if !config.tools.is_empty() {
+        match &config.tool_choice {
+            ToolChoice::Auto => {}
+            ToolChoice::None => body["tool_choice"] = serde_json::json!({"type": "none"}),
+            ToolChoice::Required => body["tool_choice"] = serde_json::json!({"type": "any"}),
+            ToolChoice::Specific(name) => {
+                body["tool_choice"] = serde_json::json!({"type": "tool", "name": name});
+            }
+        }
+    }
+
+    // JSON mode with real tools: forcing the synthetic tool would stop the
+    // model from ever calling them, so ask for JSON in the system prompt.
+    let json_by_prompt = config.output_schema.is_none()
+        && config.response_format == ResponseFormat::JsonObject
+        && !config.tools.is_empty();
+    if json_by_prompt {
+        let instruction = serde_json::json!({
+            "type": "text",
+            "text": JSON_OBJECT_INSTRUCTION,
+        });
+        match body.get_mut("system").and_then(|v| v.as_array_mut()) {
+            Some(blocks) => blocks.push(instruction),
+            None => body["system"] = serde_json::json!([instruction]),
+        }
+    }
 
     // Structured outputs via tool-forcing: append a synthetic tool built from
     // the schema and force the model to call it. The loop unwraps the forced
-    // call back into plain text (`unwrap_structured_tool_call`).
-    if let Some(schema) = &config.output_schema {
+    // call back into plain text (`unwrap_structured_tool_call`). JSON mode has
+    // no native equivalent, so without real tools it rides the same path with
+    // an open object schema.
+    let structured = match (&config.output_schema, &config.response_format) {
+        (Some(schema), _) => Some((
+            schema.name.as_str(),
+            schema.schema.clone(),
+            "Produce the final answer in the required schema.",
+        )),
+        (None, ResponseFormat::JsonObject) if !json_by_prompt => Some((
+            JSON_OBJECT_TOOL_NAME,
+            serde_json::json!({ "type": "object" }),
+            "Produce the final answer as a JSON object.",
+        )),
+        (None, _) => None,
+    };
+    let structured_forced = structured.is_some();
+    if let Some((name, schema, description)) = structured {
         let synthetic = serde_json::json!({
-            "name": schema.name,
-            "description": "Produce the final answer in the required schema.",
-            "input_schema": schema.schema,
+            "name": name,
+            "description": description,
+            "input_schema": schema,
         });
         match body.get_mut("tools").and_then(|v| v.as_array_mut()) {
             Some(arr) => arr.push(synthetic),
             None => body["tools"] = serde_json::json!([synthetic]),
         }
-        body["tool_choice"] = serde_json::json!({ "type": "tool", "name": schema.name });
+        body["tool_choice"] = serde_json::json!({ "type": "tool", "name": name });
     }
 
     // Forced tool_choice and extended thinking are mutually exclusive at the
-    // API level — a structured-output request wins and thinking is skipped
-    // for this call (warned, not silent).
+    // API level — a structured-output request or a forcing tool choice wins
+    // and thinking is skipped for this call (warned, not silent).
     let thinking_requested = config.thinking_level != ThinkingLevel::Off;
-    if thinking_requested && config.output_schema.is_some() {
+    let tool_forced =
+        structured_forced || (!config.tools.is_empty() && config.tool_choice.forces_tool_call());
+    if thinking_requested && tool_forced {
         tracing::warn!(
-            "structured outputs force tool_choice, which Anthropic rejects with \
-             extended thinking; thinking is disabled for this request"
+            "a forced tool_choice (structured output or ToolChoice::Required/Specific) \
+             is rejected by Anthropic with extended thinking; thinking is disabled \
+             for this request"
         );
     }
-    if thinking_requested && config.output_schema.is_none() {
+    if thinking_requested && !tool_forced {
         if compat.adaptive_thinking {
             // Current generation (Claude 4.6+ / Fable 5): adaptive thinking with
             // an effort hint. Budget-based thinking is rejected with a 400.
@@ -543,6 +640,10 @@ fn build_request_body(config: &StreamConfig, is_oauth: bool) -> serde_json::Valu
         body["temperature"] = serde_json::json!(temp);
     }
 
+    if let Some(stop) = &config.stop_sequences {
+        body["stop_sequences"] = serde_json::json!(stop);
+    }
+
     body
 }
 
@@ -568,6 +669,29 @@ fn content_to_anthropic(content: &[Content]) -> Vec<serde_json::Value> {
                 "type": "image",
                 "source": {"type": "base64", "media_type": mime_type, "data": data},
             }),
+            Content::Document {
+                data,
+                mime_type,
+                filename,
+            } => {
+                let mut block = serde_json::json!({
+                    "type": "document",
+                    "source": {
+                        "type": "base64",
+                        "media_type": mime_type,
+                        "data": base64::engine::general_purpose::STANDARD.encode(data),
+                    },
+                });
+                // The title is shown to the model, e.g. when citing.
+                if !filename.is_empty() {
+                    block["title"] = serde_json::json!(filename);
+                }
+                block
+            }
+            Content::DocumentUrl { url, .. } => serde_json::json!({
+                "type": "document",
+                "source": {"type": "url", "url": url},
+            }),
             Content::Thinking {
                 thinking,
                 signature,
@@ -587,10 +711,48 @@ fn content_to_anthropic(content: &[Content]) -> Vec<serde_json::Value> {
                 "name": name,
                 "input": arguments,
             }),
+            Content::ServerToolUse {
+                id, name, input, ..
+            } => serde_json::json!({
+                "type": "server_tool_use",
+                "id": id,
+                "name": name,
+                "input": input,
+            }),
+            Content::ServerToolResult {
+                tool_use_id,
+                result_type,
+                content,
+                ..
+            } => serde_json::json!({
+                "type": result_type,
+                "tool_use_id": tool_use_id,
+                "content": content,
+            }),
         })
         .collect()
 }
 
+/// A `content_block_start` whose block is a server tool's result (e.g.
+/// `web_search_tool_result`). These arrive whole — no deltas follow — and are
+/// kept verbatim so the turn can be replayed.
+fn server_tool_result_start(data: &str) -> Option<(usize, Content)> {
+    let data: serde_json::Value = serde_json::from_str(data).ok()?;
+    let block = &data["content_block"];
+    let result_type = block["type"].as_str()?;
+    if !result_type.ends_with("_tool_result") {
+        return None;
+    }
+    Some((
+        data["index"].as_u64()? as usize,
+        Content::server_tool_result(
+            block["tool_use_id"].as_str().unwrap_or_default(),
+            result_type,
+            block["content"].clone(),
+        ),
+    ))
+}
+
 // Anthropic SSE event types
 #[derive(Deserialize)]
 struct AnthropicMessageStart {
@@ -635,6 +797,8 @@ enum AnthropicContentBlock {
     },
     #[serde(rename = "tool_use")]
     ToolUse { id: String, name: String },
+    #[serde(rename = "server_tool_use")]
+    ServerToolUse { id: String, name: String },
 }
 
 #[derive(Deserialize)]
@@ -698,6 +862,10 @@ mod tests {
             model_config: None,
             cache_config: cache,
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         }
     }
 
@@ -830,6 +998,98 @@ mod tests {
         assert_eq!(body["tool_choice"]["name"], "structured_output");
     }
 
+    #[test]
+    fn json_mode_forces_open_object_tool() {
+        let mut config = make_config(CacheConfig::default());
+        config.tools.clear();
+        config.response_format = ResponseFormat::JsonObject;
+        let body = build_request_body(&config, false);
+
+        let synthetic = body["tools"].as_array().unwrap().last().unwrap();
+        assert_eq!(synthetic["name"], JSON_OBJECT_TOOL_NAME);
+        assert_eq!(
+            synthetic["input_schema"],
+            serde_json::json!({"type": "object"})
+        );
+        assert_eq!(body["tool_choice"]["name"], JSON_OBJECT_TOOL_NAME);
+
+        // An explicit schema wins over JSON mode.
+        config.output_schema = Some(crate::provider::OutputSchema::new(
+            "structured_output",
+            serde_json::json!({"type": "object"}),
+        ));
+        let body = build_request_body(&config, false);
+        let tools = body["tools"].as_array().unwrap();
+        assert!(tools.iter().all(|t| t["name"] != JSON_OBJECT_TOOL_NAME));
+        assert_eq!(body["tool_choice"]["name"], "structured_output");
+    }
+
+    #[test]
+    fn json_mode_with_tools_asks_in_the_prompt() {
+        let mut config = make_config(CacheConfig::default());
+        config.response_format = ResponseFormat::JsonObject;
+        let body = build_request_body(&config, false);
+
+        // The real tool stays callable: nothing is forced.
+        let tools = body["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0]["name"], "bash");
+        assert!(body["tool_choice"].is_null());
+        let system = body["system"].as_array().unwrap();
+        assert_eq!(system[0]["text"], "You are helpful.");
+        assert_eq!(system[1]["text"], JSON_OBJECT_INSTRUCTION);
+    }
+
+    #[test]
+    fn test_tool_choice_serialization() {
+        let mut config = make_config(CacheConfig::default());
+        let body = build_request_body(&config, false);
+        assert!(body["tool_choice"].is_null(), "Auto sends no tool_choice");
+
+        config.tool_choice = ToolChoice::None;
+        let body = build_request_body(&config, false);
+        assert_eq!(body["tool_choice"], serde_json::json!({"type": "none"}));
+
+        config.tool_choice = ToolChoice::Required;
+        let body = build_request_body(&config, false);
+        assert_eq!(body["tool_choice"], serde_json::json!({"type": "any"}));
+
+        config.tool_choice = ToolChoice::Specific("bash".into());
+        let body = build_request_body(&config, false);
+        assert_eq!(
+            body["tool_choice"],
+            serde_json::json!({"type": "tool", "name": "bash"})
+        );
+    }
+
+    #[test]
+    fn test_stop_sequences_serialization() {
+        let mut config = make_config(CacheConfig::default());
+        let body = build_request_body(&config, false);
+        assert!(body.get("stop_sequences").is_none());
+
+        config.stop_sequences = Some(vec!["</answer>".into(), "STOP".into()]);
+        let body = build_request_body(&config, false);
+        assert_eq!(
+            body["stop_sequences"],
+            serde_json::json!(["</answer>", "STOP"])
+        );
+    }
+
+    #[test]
+    fn test_forced_tool_choice_disables_thinking() {
+        let mut config = make_config(CacheConfig::default());
+        config.thinking_level = ThinkingLevel::High;
+        config.tool_choice = ToolChoice::Required;
+        let body = build_request_body(&config, false);
+        assert!(body["thinking"].is_null(), "thinking must be skipped");
+
+        // Auto / None don't force a call, so thinking stays on.
+        config.tool_choice = ToolChoice::None;
+        let body = build_request_body(&config, false);
+        assert!(!body["thinking"].is_null());
+    }
+
     #[test]
     fn test_tool_result_with_image() {
         let config = StreamConfig {
@@ -877,6 +1137,10 @@ mod tests {
                 strategy: CacheStrategy::Disabled,
             },
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, false);
@@ -933,6 +1197,10 @@ mod tests {
                 strategy: CacheStrategy::Disabled,
             },
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, false);
@@ -942,6 +1210,73 @@ mod tests {
         assert_eq!(tool_result["content"], "hello");
     }
 
+    #[test]
+    fn test_content_to_anthropic_documents() {
+        let content = vec![
+            Content::Document {
+                data: b"%PDF-1.7".to_vec(),
+                mime_type: "application/pdf".into(),
+                filename: "spec.pdf".into(),
+            },
+            Content::DocumentUrl {
+                url: "https://example.com/spec.pdf".into(),
+                mime_type: "application/pdf".into(),
+            },
+        ];
+        let result = content_to_anthropic(&content);
+        assert_eq!(
+            result[0],
+            serde_json::json!({
+                "type": "document",
+                "source": {"type": "base64", "media_type": "application/pdf", "data": "JVBERi0xLjc="},
+                "title": "spec.pdf",
+            })
+        );
+        assert_eq!(
+            result[1],
+            serde_json::json!({
+                "type": "document",
+                "source": {"type": "url", "url": "https://example.com/spec.pdf"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_multipart_user_messages_to_anthropic() {
+        let Message::User { content, .. } =
+            Message::user_with_image("what's this?", "iVBORw0", "image/png")
+        else {
+            unreachable!()
+        };
+        assert_eq!(
+            content_to_anthropic(&content),
+            vec![
+                serde_json::json!({"type": "text", "text": "what's this?"}),
+                serde_json::json!({
+                    "type": "image",
+                    "source": {"type": "base64", "media_type": "image/png", "data": "iVBORw0"},
+                }),
+            ]
+        );
+
+        let Message::User { content, .. } = Message::user_with_document(
+            "summarize",
+            b"%PDF-1.7".to_vec(),
+            "spec.pdf",
+            "application/pdf",
+        ) else {
+            unreachable!()
+        };
+        assert_eq!(
+            content_to_anthropic(&content)[1],
+            serde_json::json!({
+                "type": "document",
+                "source": {"type": "base64", "media_type": "application/pdf", "data": "JVBERi0xLjc="},
+                "title": "spec.pdf",
+            })
+        );
+    }
+
     #[test]
     fn test_content_to_anthropic_filters_empty_text() {
         let content = vec![
@@ -987,6 +1322,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
         let body = build_request_body(&config, false);
         let msgs = body["messages"].as_array().unwrap();
@@ -1042,6 +1381,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, false);
@@ -1207,4 +1550,76 @@ mod tests {
             "https://api.anthropic.com/v1/messages"
         );
     }
+
+    #[test]
+    fn test_server_tools_follow_function_tools() {
+        let mut config = make_config(CacheConfig::default());
+        config.server_tools = vec![ServerTool::web_search().with_option("max_uses", 3)];
+        let body = build_request_body(&config, false);
+
+        let tools = body["tools"].as_array().unwrap();
+        assert_eq!(tools.len(), 2);
+        assert_eq!(tools[0]["name"], "bash");
+        assert!(tools[0].get("cache_control").is_none());
+        // The cache breakpoint moves to the last tool, which is now the server tool.
+        assert_eq!(
+            tools[1],
+            serde_json::json!({
+                "type": "web_search_20250305",
+                "name": "web_search",
+                "max_uses": 3,
+                "cache_control": {"type": "ephemeral"},
+            })
+        );
+    }
+
+    #[test]
+    fn test_server_tools_alone_send_no_tool_choice() {
+        let mut config = make_config(CacheConfig {
+            enabled: false,
+            strategy: CacheStrategy::Auto,
+        });
+        config.tools.clear();
+        config.tool_choice = ToolChoice::Required;
+        config.server_tools = vec![ServerTool::web_search()];
+        let body = build_request_body(&config, false);
+
+        assert_eq!(
+            body["tools"],
+            serde_json::json!([{"type": "web_search_20250305", "name": "web_search"}])
+        );
+        assert!(body.get("tool_choice").is_none());
+    }
+
+    #[test]
+    fn test_server_tool_blocks_are_replayed() {
+        let blocks = content_to_anthropic(&[
+            Content::server_tool_use(
+                "srvtoolu_1",
+                "web_search",
+                serde_json::json!({"query": "rust"}),
+            ),
+            Content::server_tool_result(
+                "srvtoolu_1",
+                "web_search_tool_result",
+                serde_json::json!([{"type": "web_search_result", "url": "https://rust-lang.org"}]),
+            ),
+        ]);
+        assert_eq!(
+            blocks,
+            vec![
+                serde_json::json!({
+                    "type": "server_tool_use",
+                    "id": "srvtoolu_1",
+                    "name": "web_search",
+                    "input": {"query": "rust"},
+                }),
+                serde_json::json!({
+                    "type": "web_search_tool_result",
+                    "tool_use_id": "srvtoolu_1",
+                    "content": [{"type": "web_search_result", "url": "https://rust-lang.org"}],
+                }),
+            ]
+        );
+    }
 }
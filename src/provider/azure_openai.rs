@@ -23,6 +23,13 @@ impl StreamProvider for AzureOpenAiProvider {
         Some(crate::provider::ApiProtocol::AzureOpenAiResponses)
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_server_tools: false,
+            ..ProviderCapabilities::default()
+        }
+    }
+
     async fn stream(
         &self,
         config: StreamConfig,
@@ -34,6 +41,11 @@ impl StreamProvider for AzureOpenAiProvider {
                 "structured outputs are not yet wired for the Azure OpenAI provider; output_schema will be ignored"
             );
         }
+        if config.stop_sequences.is_some() {
+            tracing::warn!(
+                "the Responses API has no stop parameter; stop_sequences will be ignored by the Azure OpenAI provider"
+            );
+        }
         let model_config = config
             .model_config
             .as_ref()
@@ -220,6 +232,9 @@ fn build_azure_request_body(config: &StreamConfig) -> serde_json::Value {
                             "type": "input_image",
                             "image_url": format!("data:{};base64,{}", mime_type, data),
                         })),
+                        Content::Document { .. } | Content::DocumentUrl { .. } => {
+                            Some(super::openai_responses::input_file(c))
+                        }
                         _ => None,
                     })
                     .collect();
@@ -271,7 +286,14 @@ fn build_azure_request_body(config: &StreamConfig) -> serde_json::Value {
                 content,
                 ..
             } => {
-                let output_val = if content.iter().any(|c| matches!(c, Content::Image { .. })) {
+                let output_val = if content.iter().any(|c| {
+                    matches!(
+                        c,
+                        Content::Image { .. }
+                            | Content::Document { .. }
+                            | Content::DocumentUrl { .. }
+                    )
+                }) {
                     let parts: Vec<serde_json::Value> = content
                         .iter()
                         .filter(|c| !matches!(c, Content::Text { text } if text.is_empty()))
@@ -284,6 +306,9 @@ fn build_azure_request_body(config: &StreamConfig) -> serde_json::Value {
                                 "type": "input_image",
                                 "image_url": format!("data:{};base64,{}", mime_type, data),
                             })),
+                            Content::Document { .. } | Content::DocumentUrl { .. } => {
+                                Some(super::openai_responses::input_file(c))
+                            }
                             _ => None,
                         })
                         .collect();
@@ -321,6 +346,10 @@ fn build_azure_request_body(config: &StreamConfig) -> serde_json::Value {
         body["max_output_tokens"] = serde_json::json!(max);
     }
 
+    if config.response_format == ResponseFormat::JsonObject && config.output_schema.is_none() {
+        body["text"] = serde_json::json!({ "format": { "type": "json_object" } });
+    }
+
     if !config.tools.is_empty() {
         let tools: Vec<serde_json::Value> = config
             .tools
@@ -335,6 +364,9 @@ fn build_azure_request_body(config: &StreamConfig) -> serde_json::Value {
             })
             .collect();
         body["tools"] = serde_json::json!(tools);
+        if let Some(choice) = super::openai_responses::responses_tool_choice(&config.tool_choice) {
+            body["tool_choice"] = choice;
+        }
     }
 
     if let Some(temp) = config.temperature {
@@ -409,6 +441,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         }
     }
 
@@ -423,4 +459,71 @@ mod tests {
         let body = build_azure_request_body(&config(ThinkingLevel::Off));
         assert!(body["reasoning"].is_null());
     }
+
+    #[test]
+    fn json_mode_sets_text_format() {
+        let mut cfg = config(ThinkingLevel::Off);
+        assert!(build_azure_request_body(&cfg).get("text").is_none());
+
+        cfg.response_format = ResponseFormat::JsonObject;
+        assert_eq!(
+            build_azure_request_body(&cfg)["text"],
+            serde_json::json!({"format": {"type": "json_object"}})
+        );
+    }
+
+    #[test]
+    fn tool_choice_uses_responses_shape() {
+        let mut cfg = config(ThinkingLevel::Off);
+        cfg.tools = vec![ToolDefinition {
+            name: "classify".into(),
+            description: "Pick a label".into(),
+            parameters: serde_json::json!({"type": "object"}),
+        }];
+        assert!(build_azure_request_body(&cfg).get("tool_choice").is_none());
+
+        cfg.tool_choice = ToolChoice::None;
+        assert_eq!(build_azure_request_body(&cfg)["tool_choice"], "none");
+
+        cfg.tool_choice = ToolChoice::Required;
+        assert_eq!(build_azure_request_body(&cfg)["tool_choice"], "required");
+
+        cfg.tool_choice = ToolChoice::Specific("classify".into());
+        assert_eq!(
+            build_azure_request_body(&cfg)["tool_choice"],
+            serde_json::json!({"type": "function", "name": "classify"})
+        );
+    }
+
+    #[test]
+    fn documents_become_input_files() {
+        let mut cfg = config(ThinkingLevel::Off);
+        cfg.messages = vec![Message::user_with_document(
+            "summarize",
+            b"%PDF-1.7".to_vec(),
+            "spec.pdf",
+            "application/pdf",
+        )];
+        let body = build_azure_request_body(&cfg);
+        assert_eq!(
+            body["input"][0]["content"][1],
+            serde_json::json!({
+                "type": "input_file",
+                "filename": "spec.pdf",
+                "file_data": "data:application/pdf;base64,JVBERi0xLjc=",
+            })
+        );
+
+        cfg.messages = vec![Message::User {
+            content: vec![Content::DocumentUrl {
+                url: "https://example.com/spec.pdf".into(),
+                mime_type: "application/pdf".into(),
+            }],
+            timestamp: 0,
+        }];
+        assert_eq!(
+            build_azure_request_body(&cfg)["input"][0]["content"][0],
+            serde_json::json!({"type": "input_file", "file_url": "https://example.com/spec.pdf"})
+        );
+    }
 }
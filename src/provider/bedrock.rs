@@ -1,20 +1,24 @@
 //! Amazon Bedrock ConverseStream provider.
 //!
 //! Uses the Bedrock ConverseStream API with AWS SigV4 request signing.
-//! For simplicity, we implement minimal SigV4 signing using the `aws-sigv4`
-//! and `aws-credential-types` crates. If those aren't available, callers
-//! can pass pre-signed requests or use an IAM proxy.
 //!
 //! The `api_key` field in StreamConfig is expected to be formatted as:
 //! `{access_key_id}:{secret_access_key}` (with optional `:{session_token}`).
 //! The `base_url` in ModelConfig should be the Bedrock endpoint, e.g.:
-//! `https://bedrock-runtime.us-east-1.amazonaws.com`
+//! `https://bedrock-runtime.us-east-1.amazonaws.com` — the signing region is
+//! taken from it. For other hosts (proxies, test servers) the region falls
+//! back to `AWS_REGION`, then `us-east-1`.
+//!
+//! Requests are not signed when `ModelConfig.headers` already carries an
+//! `authorization` header (pre-computed auth or an IAM proxy).
 
 use super::traits::*;
 use crate::types::*;
 use async_trait::async_trait;
+use base64::Engine;
 use futures::StreamExt;
 use serde::Deserialize;
+use std::collections::HashMap;
 use tokio::sync::mpsc;
 use tracing::{debug, warn};
 
@@ -26,6 +30,15 @@ impl StreamProvider for BedrockProvider {
         Some(crate::provider::ApiProtocol::BedrockConverseStream)
     }
 
+    /// JSON mode and structured outputs aren't wired for Converse yet.
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_json_mode: false,
+            supports_server_tools: false,
+            ..ProviderCapabilities::default()
+        }
+    }
+
     async fn stream(
         &self,
         config: StreamConfig,
@@ -37,66 +50,88 @@ impl StreamProvider for BedrockProvider {
                 "structured outputs are not yet wired for the Amazon Bedrock provider; output_schema will be ignored"
             );
         }
+        if config.response_format == ResponseFormat::JsonObject {
+            tracing::warn!(
+                "JSON mode is not yet wired for the Amazon Bedrock provider; response_format will be ignored"
+            );
+        }
         let model_config = config
             .model_config
             .as_ref()
             .ok_or_else(|| ProviderError::Other("ModelConfig required".into()))?;
 
         let base_url = &model_config.base_url;
-        let url = format!("{}/model/{}/converse-stream", base_url, config.model);
+        // Model ids contain ':' (and ARNs '/'), so encode them the way the
+        // AWS SDKs do — the signature covers the path as sent.
+        let url = format!(
+            "{}/model/{}/converse-stream",
+            base_url,
+            encode_path_segment(&config.model)
+        );
 
-        let body = build_bedrock_body(&config);
+        let body = serde_json::to_vec(&build_bedrock_body(&config))
+            .map_err(|e| ProviderError::Other(e.to_string()))?;
         debug!("Bedrock request: model={} url={}", config.model, url);
 
-        // Parse AWS credentials from api_key
-        let parts: Vec<&str> = config.api_key.splitn(3, ':').collect();
-        if parts.len() < 2 {
-            return Err(ProviderError::Auth(
-                "Bedrock api_key must be 'access_key:secret_key[:session_token]'".into(),
-            ));
-        }
-
         let client = reqwest::Client::new();
         let mut request = client.post(&url).header("content-type", "application/json");
 
-        // Add AWS auth headers. In a real implementation, this would use SigV4.
-        // For now, we support a simplified auth model where the caller provides
-        // pre-computed auth headers via model_config.headers, or uses an IAM proxy.
         for (k, v) in &model_config.headers {
             request = request.header(k, v);
         }
 
-        // If no auth headers provided, try basic Bearer auth as fallback
-        // (works with some Bedrock proxy configurations)
-        if !model_config.headers.contains_key("authorization") {
-            request = request.header("authorization", format!("Bearer {}", config.api_key));
+        let has_auth_header = model_config
+            .headers
+            .keys()
+            .any(|k| k.eq_ignore_ascii_case("authorization"));
+        if !has_auth_header {
+            let credentials = AwsCredentials::parse(&config.api_key)?;
+            let region = region_from_url(base_url)
+                .map(str::to_string)
+                .or_else(|| std::env::var("AWS_REGION").ok())
+                .unwrap_or_else(|| "us-east-1".into());
+            let headers = sign_request(
+                "POST",
+                &url,
+                &[("content-type", "application/json")],
+                &body,
+                &credentials,
+                &region,
+                SIGNING_SERVICE,
+                std::time::SystemTime::now(),
+            )?;
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
         }
 
         let response = request
-            .json(&body)
+            .body(body)
             .send()
             .await
             .map_err(|e| ProviderError::Network(e.to_string()))?;
 
         if !response.status().is_success() {
             let status = response.status();
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::classify(
+            return Err(ProviderError::classify_with_headers(
                 status.as_u16(),
                 &format!("Bedrock error {}: {}", status, body),
+                &headers,
             ));
         }
 
-        let mut content: Vec<Content> = Vec::new();
-        let mut usage = Usage::default();
-        let mut stop_reason = StopReason::Stop;
+        let mut state = BedrockStreamState::new();
+        let mut decoder = EventStreamDecoder::default();
 
         let _ = tx.send(StreamEvent::Start);
 
-        // Bedrock ConverseStream returns event-stream format (application/vnd.amazon.eventstream)
-        // For simplicity, we parse it as newline-delimited JSON chunks.
+        // ConverseStream responds with `application/vnd.amazon.eventstream`:
+        // binary, length-prefixed, CRC-checked frames whose `:event-type`
+        // header names the JSON payload. Frames routinely straddle chunk
+        // boundaries, so raw bytes are buffered until a frame is complete.
         let mut stream = response.bytes_stream();
-        let mut buffer = String::new();
 
         loop {
             tokio::select! {
@@ -107,113 +142,14 @@ impl StreamProvider for BedrockProvider {
                     match chunk {
                         None => break,
                         Some(Err(e)) => {
-                            warn!("Bedrock stream error: {}", e);
-                            break;
+                            let provider_err = ProviderError::Network(e.to_string());
+                            warn!("Bedrock stream error: {}", provider_err);
+                            return Err(provider_err);
                         }
                         Some(Ok(bytes)) => {
-                            buffer.push_str(&String::from_utf8_lossy(&bytes));
-
-                            // Try to parse complete JSON objects
-                            while let Some(pos) = buffer.find('\n') {
-                                let line = buffer[..pos].trim().to_string();
-                                buffer = buffer[pos + 1..].to_string();
-
-                                if line.is_empty() {
-                                    continue;
-                                }
-
-                                let event: BedrockEvent = match serde_json::from_str(&line) {
-                                    Ok(e) => e,
-                                    Err(_) => continue,
-                                };
-
-                                match event {
-                                    BedrockEvent::ContentBlockDelta { delta, .. } => {
-                                        if let Some(text) = delta.text {
-                                            let text_idx = content.iter().position(|c| matches!(c, Content::Text { .. }));
-                                            let idx = match text_idx {
-                                                Some(i) => i,
-                                                None => {
-                                                    content.push(Content::Text { text: String::new() });
-                                                    content.len() - 1
-                                                }
-                                            };
-                                            if let Some(Content::Text { text: t }) = content.get_mut(idx) {
-                                                t.push_str(&text);
-                                            }
-                                            let _ = tx.send(StreamEvent::TextDelta {
-                                                content_index: idx,
-                                                delta: text,
-                                            });
-                                        }
-                                        if let Some(tool_use) = delta.tool_use {
-                                            let _ = tx.send(StreamEvent::ToolCallDelta {
-                                                content_index: content.len(),
-                                                delta: tool_use.input,
-                                            });
-                                        }
-                                        if let Some(reasoning) = delta.reasoning_content {
-                                            let think_idx = content.iter().position(|c| matches!(c, Content::Thinking { .. }));
-                                            let idx = match think_idx {
-                                                Some(i) => i,
-                                                None => {
-                                                    content.push(Content::thinking(String::new()));
-                                                    content.len() - 1
-                                                }
-                                            };
-                                            if let Some(Content::Thinking { thinking, signature }) = content.get_mut(idx) {
-                                                if let Some(text) = reasoning.text {
-                                                    thinking.push_str(&text);
-                                                    let _ = tx.send(StreamEvent::ThinkingDelta {
-                                                        content_index: idx,
-                                                        delta: text,
-                                                    });
-                                                }
-                                                if reasoning.signature.is_some() {
-                                                    *signature = reasoning.signature;
-                                                }
-                                            }
-                                        }
-                                    }
-                                    BedrockEvent::ContentBlockStart { start, .. } => {
-                                        if let Some(tool_use) = start.tool_use {
-                                            let idx = content.len();
-                                            content.push(Content::ToolCall { provider_metadata: None,
-                                                id: tool_use.tool_use_id.clone(),
-                                                name: tool_use.name.clone(),
-                                                arguments: serde_json::Value::Object(Default::default()),
-                                            });
-                                            let _ = tx.send(StreamEvent::ToolCallStart {
-                                                content_index: idx,
-                                                id: tool_use.tool_use_id,
-                                                name: tool_use.name,
-                                            });
-                                        }
-                                    }
-                                    BedrockEvent::ContentBlockStop { .. } => {
-                                        if content.iter().any(|c| matches!(c, Content::ToolCall { .. })) {
-                                            let _ = tx.send(StreamEvent::ToolCallEnd {
-                                                content_index: content.len() - 1,
-                                            });
-                                        }
-                                    }
-                                    BedrockEvent::MessageStop { stop_reason: sr } => {
-                                        stop_reason = match sr.as_deref() {
-                                            Some("end_turn") => StopReason::Stop,
-                                            Some("max_tokens") => StopReason::Length,
-                                            Some("tool_use") => StopReason::ToolUse,
-                                            _ => StopReason::Stop,
-                                        };
-                                    }
-                                    BedrockEvent::Metadata { usage: u } => {
-                                        if let Some(u) = u {
-                                            usage.input = u.input_tokens;
-                                            usage.output = u.output_tokens;
-                                            usage.total_tokens = u.input_tokens + u.output_tokens;
-                                        }
-                                    }
-                                    BedrockEvent::Unknown => {}
-                                }
+                            decoder.push(&bytes);
+                            while let Some(frame) = decoder.next_frame()? {
+                                state.handle_frame(&frame, &tx)?;
                             }
                         }
                     }
@@ -221,15 +157,7 @@ impl StreamProvider for BedrockProvider {
             }
         }
 
-        let message = Message::Assistant {
-            content,
-            stop_reason,
-            model: config.model.clone(),
-            provider: model_config.provider.clone(),
-            usage,
-            timestamp: now_ms(),
-            error_message: None,
-        };
+        let message = state.into_message(&config.model, &model_config.provider);
 
         let _ = tx.send(StreamEvent::Done {
             message: message.clone(),
@@ -238,6 +166,576 @@ impl StreamProvider for BedrockProvider {
     }
 }
 
+// ---------------------------------------------------------------------------
+// SigV4 signing
+// ---------------------------------------------------------------------------
+
+const SIGNING_SERVICE: &str = "bedrock";
+
+/// Static AWS credentials parsed from `api_key`.
+struct AwsCredentials<'a> {
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+    session_token: Option<&'a str>,
+}
+
+impl<'a> AwsCredentials<'a> {
+    /// Parse `access_key:secret_key[:session_token]`.
+    fn parse(api_key: &'a str) -> Result<Self, ProviderError> {
+        let mut parts = api_key.splitn(3, ':');
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(access_key_id), Some(secret_access_key), session_token)
+                if !access_key_id.is_empty() && !secret_access_key.is_empty() =>
+            {
+                Ok(Self {
+                    access_key_id,
+                    secret_access_key,
+                    session_token: session_token.filter(|t| !t.is_empty()),
+                })
+            }
+            _ => Err(ProviderError::Auth(
+                "Bedrock api_key must be 'access_key:secret_key[:session_token]'".into(),
+            )),
+        }
+    }
+}
+
+/// Region from a Bedrock endpoint host: `us-east-1` from
+/// `bedrock-runtime.us-east-1.amazonaws.com` (also the `-fips` and VPC
+/// endpoint forms).
+fn region_from_url(url: &str) -> Option<&str> {
+    let host = url.split("://").nth(1).unwrap_or(url);
+    let host = host.split(['/', ':']).next()?;
+    let mut labels = host.split('.');
+    labels.find(|l| l.starts_with("bedrock-runtime"))?;
+    labels.next().filter(|r| !r.is_empty())
+}
+
+/// Percent-encode everything except RFC 3986 unreserved characters.
+fn encode_path_segment(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// SigV4-sign a request and return the headers to add: `authorization`,
+/// `x-amz-date` and, for temporary credentials, `x-amz-security-token`.
+/// `headers` are the request headers to include in the signature; `host` is
+/// always signed. `url` must already be percent-encoded.
+#[allow(clippy::too_many_arguments)]
+fn sign_request(
+    method: &str,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    credentials: &AwsCredentials<'_>,
+    region: &str,
+    service: &str,
+    time: std::time::SystemTime,
+) -> Result<Vec<(String, String)>, ProviderError> {
+    let parsed = reqwest::Url::parse(url)
+        .map_err(|e| ProviderError::Other(format!("invalid Bedrock URL {}: {}", url, e)))?;
+    let host = match parsed.port() {
+        Some(port) => format!("{}:{}", parsed.host_str().unwrap_or_default(), port),
+        None => parsed.host_str().unwrap_or_default().to_string(),
+    };
+
+    let (date, amz_date) = amz_timestamp(time);
+    let mut out = vec![("x-amz-date".to_string(), amz_date.clone())];
+    if let Some(token) = credentials.session_token {
+        out.push(("x-amz-security-token".to_string(), token.to_string()));
+    }
+
+    let mut signed: Vec<(String, String)> = headers
+        .iter()
+        .map(|(k, v)| (k.to_ascii_lowercase(), v.trim().to_string()))
+        .chain(std::iter::once(("host".to_string(), host)))
+        .chain(out.iter().cloned())
+        .collect();
+    signed.sort();
+    let signed_headers = signed
+        .iter()
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    // Non-S3 services encode the (already encoded) path a second time.
+    let canonical_uri = match parsed.path() {
+        "" => "/".to_string(),
+        path => path
+            .split('/')
+            .map(encode_path_segment)
+            .collect::<Vec<_>>()
+            .join("/"),
+    };
+    let mut query: Vec<&str> = parsed
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|p| !p.is_empty())
+        .collect();
+    query.sort_unstable();
+    let canonical_query = query
+        .iter()
+        .map(|p| {
+            if p.contains('=') {
+                p.to_string()
+            } else {
+                format!("{}=", p)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    let canonical_headers: String = signed
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v))
+        .collect();
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        method,
+        canonical_uri,
+        canonical_query,
+        canonical_headers,
+        signed_headers,
+        hex(&sha256(body))
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        hex(&sha256(canonical_request.as_bytes()))
+    );
+
+    let mut key = hmac_sha256(
+        format!("AWS4{}", credentials.secret_access_key).as_bytes(),
+        date.as_bytes(),
+    );
+    for part in [region, service, "aws4_request"] {
+        key = hmac_sha256(&key, part.as_bytes());
+    }
+    let signature = hex(&hmac_sha256(&key, string_to_sign.as_bytes()));
+
+    out.push((
+        "authorization".to_string(),
+        format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            credentials.access_key_id, scope, signed_headers, signature
+        ),
+    ));
+    Ok(out)
+}
+
+fn sha256(data: &[u8]) -> Vec<u8> {
+    use sha2::Digest;
+    sha2::Sha256::digest(data).to_vec()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    use hmac::Mac;
+    let mut mac =
+        hmac::Hmac::<sha2::Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// `(YYYYMMDD, YYYYMMDDTHHMMSSZ)` in UTC.
+fn amz_timestamp(time: std::time::SystemTime) -> (String, String) {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+
+    let (year, month, day) = civil_from_days(days as i64);
+
+    let date = format!("{:04}{:02}{:02}", year, month, day);
+    let amz_date = format!(
+        "{}T{:02}{:02}{:02}Z",
+        date,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60
+    );
+    (date, amz_date)
+}
+
+// ---------------------------------------------------------------------------
+// Event-stream framing
+// ---------------------------------------------------------------------------
+
+/// Prelude: total length (4) + headers length (4) + prelude CRC (4).
+const PRELUDE_LEN: usize = 12;
+/// Trailing CRC over the whole frame.
+const MESSAGE_CRC_LEN: usize = 4;
+/// AWS caps event-stream frames at 16 MiB; anything larger is garbage, and
+/// must not make us buffer indefinitely.
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// One decoded event-stream frame. Only string-valued headers are kept —
+/// the ones Bedrock uses (`:message-type`, `:event-type`,
+/// `:exception-type`, `:content-type`) are all strings.
+struct EventFrame {
+    headers: HashMap<String, String>,
+    payload: Vec<u8>,
+}
+
+/// Incremental decoder for `application/vnd.amazon.eventstream`.
+#[derive(Default)]
+struct EventStreamDecoder {
+    buffer: Vec<u8>,
+}
+
+impl EventStreamDecoder {
+    fn push(&mut self, bytes: &[u8]) {
+        self.buffer.extend_from_slice(bytes);
+    }
+
+    /// Pop the next complete frame. `Ok(None)` means more bytes are needed;
+    /// a checksum or length violation is an error (the stream is
+    /// unrecoverable once framing is lost).
+    fn next_frame(&mut self) -> Result<Option<EventFrame>, ProviderError> {
+        if self.buffer.len() < PRELUDE_LEN {
+            return Ok(None);
+        }
+        let total_len = be_u32(&self.buffer[0..4]) as usize;
+        let headers_len = be_u32(&self.buffer[4..8]) as usize;
+        if crc32(&self.buffer[0..8]) != be_u32(&self.buffer[8..12]) {
+            return Err(malformed_frame("prelude checksum mismatch"));
+        }
+        if total_len > MAX_FRAME_LEN || total_len < PRELUDE_LEN + headers_len + MESSAGE_CRC_LEN {
+            return Err(malformed_frame("invalid frame length"));
+        }
+        if self.buffer.len() < total_len {
+            return Ok(None);
+        }
+
+        let frame: Vec<u8> = self.buffer.drain(..total_len).collect();
+        let crc_start = total_len - MESSAGE_CRC_LEN;
+        if crc32(&frame[..crc_start]) != be_u32(&frame[crc_start..]) {
+            return Err(malformed_frame("message checksum mismatch"));
+        }
+        let headers_end = PRELUDE_LEN + headers_len;
+        Ok(Some(EventFrame {
+            headers: parse_frame_headers(&frame[PRELUDE_LEN..headers_end])?,
+            payload: frame[headers_end..crc_start].to_vec(),
+        }))
+    }
+}
+
+fn malformed_frame(reason: &str) -> ProviderError {
+    ProviderError::Other(format!("Malformed Bedrock event-stream frame: {}", reason))
+}
+
+fn be_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
+
+/// Parse the header block: `name_len:u8, name, type:u8, value` repeated.
+fn parse_frame_headers(mut bytes: &[u8]) -> Result<HashMap<String, String>, ProviderError> {
+    fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], ProviderError> {
+        if bytes.len() < n {
+            return Err(malformed_frame("truncated header"));
+        }
+        let (head, rest) = bytes.split_at(n);
+        *bytes = rest;
+        Ok(head)
+    }
+
+    let mut headers = HashMap::new();
+    while !bytes.is_empty() {
+        let name_len = take(&mut bytes, 1)?[0] as usize;
+        let name = String::from_utf8_lossy(take(&mut bytes, name_len)?).into_owned();
+        let value_type = take(&mut bytes, 1)?[0];
+        let fixed_len = match value_type {
+            // bool true / bool false carry no value bytes
+            0 | 1 => 0,
+            2 => 1,
+            3 => 2,
+            4 => 4,
+            // int64 / timestamp
+            5 | 8 => 8,
+            // uuid
+            9 => 16,
+            // byte array / string: u16 length prefix
+            6 | 7 => {
+                let len_bytes = take(&mut bytes, 2)?;
+                let len = u16::from_be_bytes([len_bytes[0], len_bytes[1]]) as usize;
+                let value = take(&mut bytes, len)?;
+                if value_type == 7 {
+                    headers.insert(name, String::from_utf8_lossy(value).into_owned());
+                }
+                continue;
+            }
+            other => {
+                return Err(malformed_frame(&format!("unknown header type {}", other)));
+            }
+        };
+        take(&mut bytes, fixed_len)?;
+    }
+    Ok(headers)
+}
+
+/// CRC-32 (IEEE 802.3), as used by the event-stream prelude and trailer.
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// Decode a frame payload to JSON. ConverseStream sends the event body
+/// directly; InvokeModelWithResponseStream-style payloads wrap it as
+/// `{"bytes": "<base64 JSON>"}` — both are accepted.
+fn decode_payload(payload: &[u8]) -> Option<serde_json::Value> {
+    let value: serde_json::Value = serde_json::from_slice(payload).ok()?;
+    match value.get("bytes").and_then(|b| b.as_str()) {
+        Some(encoded) => {
+            let decoded = base64::engine::general_purpose::STANDARD
+                .decode(encoded)
+                .ok()?;
+            serde_json::from_slice(&decoded).ok()
+        }
+        None => Some(value),
+    }
+}
+
+// ---------------------------------------------------------------------------
+// ConverseStream event handling
+// ---------------------------------------------------------------------------
+
+/// Accumulates one ConverseStream response into an assistant message,
+/// emitting [`StreamEvent`]s as frames arrive.
+struct BedrockStreamState {
+    content: Vec<Content>,
+    /// Bedrock `contentBlockIndex` → index into `content`.
+    blocks: HashMap<u64, usize>,
+    /// Streamed JSON argument fragments, keyed by `content` index.
+    tool_args: HashMap<usize, String>,
+    usage: Usage,
+    stop_reason: StopReason,
+}
+
+impl BedrockStreamState {
+    fn new() -> Self {
+        Self {
+            content: Vec::new(),
+            blocks: HashMap::new(),
+            tool_args: HashMap::new(),
+            usage: Usage::default(),
+            stop_reason: StopReason::Stop,
+        }
+    }
+
+    fn handle_frame(
+        &mut self,
+        frame: &EventFrame,
+        tx: &mpsc::UnboundedSender<StreamEvent>,
+    ) -> Result<(), ProviderError> {
+        let message_type = frame
+            .headers
+            .get(":message-type")
+            .map(String::as_str)
+            .unwrap_or("event");
+        if message_type != "event" {
+            return Err(classify_exception_frame(frame));
+        }
+
+        let Some(event_type) = frame.headers.get(":event-type") else {
+            return Ok(());
+        };
+        let Some(payload) = decode_payload(&frame.payload) else {
+            warn!("Failed to decode Bedrock {} payload", event_type);
+            return Ok(());
+        };
+
+        match event_type.as_str() {
+            "contentBlockStart" => {
+                let Ok(event) = serde_json::from_value::<BedrockBlockStartEvent>(payload) else {
+                    return Ok(());
+                };
+                if let Some(tool_use) = event.start.and_then(|s| s.tool_use) {
+                    let idx = self.content.len();
+                    self.content.push(Content::tool_call(
+                        tool_use.tool_use_id.clone(),
+                        tool_use.name.clone(),
+                        serde_json::Value::Object(Default::default()),
+                    ));
+                    self.blocks.insert(event.content_block_index, idx);
+                    self.tool_args.insert(idx, String::new());
+                    let _ = tx.send(StreamEvent::ToolCallStart {
+                        content_index: idx,
+                        id: tool_use.tool_use_id,
+                        name: tool_use.name,
+                    });
+                }
+            }
+            "contentBlockDelta" => {
+                let Ok(event) = serde_json::from_value::<BedrockBlockDeltaEvent>(payload) else {
+                    return Ok(());
+                };
+                let block = event.content_block_index;
+                let delta = event.delta;
+                if let Some(text) = delta.text {
+                    let idx = self.block(block, || Content::Text {
+                        text: String::new(),
+                    });
+                    if let Some(Content::Text { text: t }) = self.content.get_mut(idx) {
+                        t.push_str(&text);
+                    }
+                    let _ = tx.send(StreamEvent::TextDelta {
+                        content_index: idx,
+                        delta: text,
+                    });
+                }
+                if let Some(tool_use) = delta.tool_use {
+                    if let Some(&idx) = self.blocks.get(&block) {
+                        if let Some(args) = self.tool_args.get_mut(&idx) {
+                            args.push_str(&tool_use.input);
+                        }
+                        let _ = tx.send(StreamEvent::ToolCallDelta {
+                            content_index: idx,
+                            delta: tool_use.input,
+                        });
+                    }
+                }
+                if let Some(reasoning) = delta.reasoning_content {
+                    let idx = self.block(block, || Content::thinking(String::new()));
+                    if let Some(Content::Thinking {
+                        thinking,
+                        signature,
+                    }) = self.content.get_mut(idx)
+                    {
+                        if let Some(text) = reasoning.text {
+                            thinking.push_str(&text);
+                            let _ = tx.send(StreamEvent::ThinkingDelta {
+                                content_index: idx,
+                                delta: text,
+                            });
+                        }
+                        if reasoning.signature.is_some() {
+                            *signature = reasoning.signature;
+                        }
+                    }
+                }
+            }
+            "contentBlockStop" => {
+                let Ok(event) = serde_json::from_value::<BedrockBlockStopEvent>(payload) else {
+                    return Ok(());
+                };
+                let Some(&idx) = self.blocks.get(&event.content_block_index) else {
+                    return Ok(());
+                };
+                if let Some(raw) = self.tool_args.remove(&idx) {
+                    let parsed = if raw.trim().is_empty() {
+                        serde_json::Value::Object(Default::default())
+                    } else {
+                        serde_json::from_str(&raw).unwrap_or_else(|e| {
+                            warn!("Failed to parse Bedrock tool input: {}", e);
+                            serde_json::Value::Object(Default::default())
+                        })
+                    };
+                    if let Some(Content::ToolCall { arguments, .. }) = self.content.get_mut(idx) {
+                        *arguments = parsed;
+                    }
+                    let _ = tx.send(StreamEvent::ToolCallEnd { content_index: idx });
+                }
+            }
+            "messageStop" => {
+                if let Ok(event) = serde_json::from_value::<BedrockMessageStopEvent>(payload) {
+                    self.stop_reason = match event.stop_reason.as_deref() {
+                        Some("end_turn") => StopReason::Stop,
+                        Some("max_tokens") => StopReason::Length,
+                        Some("tool_use") => StopReason::ToolUse,
+                        Some("stop_sequence") => StopReason::StopSequence,
+                        _ => StopReason::Stop,
+                    };
+                }
+            }
+            "metadata" => {
+                if let Ok(event) = serde_json::from_value::<BedrockMetadataEvent>(payload) {
+                    if let Some(u) = event.usage {
+                        self.usage.input = u.input_tokens;
+                        self.usage.output = u.output_tokens;
+                        self.usage.cache_read = u.cache_read_input_tokens;
+                        self.usage.cache_write = u.cache_write_input_tokens;
+                        self.usage.total_tokens = if u.total_tokens > 0 {
+                            u.total_tokens
+                        } else {
+                            u.input_tokens + u.output_tokens
+                        };
+                    }
+                }
+            }
+            // messageStart carries only the role.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Content index for a Bedrock block, creating it on first sight.
+    fn block(&mut self, block: u64, make: impl FnOnce() -> Content) -> usize {
+        if let Some(&idx) = self.blocks.get(&block) {
+            return idx;
+        }
+        self.content.push(make());
+        let idx = self.content.len() - 1;
+        self.blocks.insert(block, idx);
+        idx
+    }
+
+    fn into_message(self, model: &str, provider: &str) -> Message {
+        Message::Assistant {
+            content: self.content,
+            stop_reason: self.stop_reason,
+            model: model.to_string(),
+            provider: provider.to_string(),
+            usage: self.usage,
+            timestamp: now_ms(),
+            error_message: None,
+        }
+    }
+}
+
+/// Map an `exception` frame (`:exception-type` header, `{"message"}` body)
+/// to a [`ProviderError`]. Throttling is retryable; the rest are classified
+/// like any in-stream error (context overflow is detected by message).
+fn classify_exception_frame(frame: &EventFrame) -> ProviderError {
+    let kind = frame
+        .headers
+        .get(":exception-type")
+        .or_else(|| frame.headers.get(":error-code"))
+        .map(String::as_str)
+        .unwrap_or("unknownException");
+    let message = serde_json::from_slice::<serde_json::Value>(&frame.payload)
+        .ok()
+        .and_then(|v| v.get("message").and_then(|m| m.as_str()).map(String::from))
+        .unwrap_or_else(|| String::from_utf8_lossy(&frame.payload).into_owned());
+    match kind {
+        "throttlingException" => ProviderError::RateLimited {
+            retry_after_ms: None,
+        },
+        _ => classify_sse_error_event(&format!("Bedrock {}: {}", kind, message)),
+    }
+}
+
 /// Budget for Bedrock's Anthropic-style thinking per level (matches the
 /// legacy Anthropic budget mapping).
 fn bedrock_thinking_budget(level: ThinkingLevel) -> u32 {
@@ -317,6 +815,9 @@ fn build_bedrock_body(config: &StreamConfig) -> serde_json::Value {
     if let Some(temp) = config.temperature {
         inference_config["temperature"] = serde_json::json!(temp);
     }
+    if let Some(stop) = &config.stop_sequences {
+        inference_config["stopSequences"] = serde_json::json!(stop);
+    }
     if inference_config != serde_json::json!({}) {
         body["inferenceConfig"] = inference_config;
     }
@@ -348,6 +849,20 @@ fn build_bedrock_body(config: &StreamConfig) -> serde_json::Value {
             })
             .collect();
         body["toolConfig"] = serde_json::json!({"tools": tools});
+
+        // Converse has no "none" choice; the tools stay available.
+        match &config.tool_choice {
+            ToolChoice::Auto => {}
+            ToolChoice::None => {
+                warn!("ToolChoice::None is not supported by Bedrock Converse; using auto")
+            }
+            ToolChoice::Required => {
+                body["toolConfig"]["toolChoice"] = serde_json::json!({"any": {}});
+            }
+            ToolChoice::Specific(name) => {
+                body["toolConfig"]["toolChoice"] = serde_json::json!({"tool": {"name": name}});
+            }
+        }
     }
 
     body
@@ -357,22 +872,37 @@ fn content_to_bedrock(content: &[Content]) -> Vec<serde_json::Value> {
     content
         .iter()
         .filter(|c| !matches!(c, Content::Text { text } if text.is_empty()))
-        .map(|c| match c {
-            Content::Text { text } => serde_json::json!({"text": text}),
-            Content::Image { data, mime_type } => serde_json::json!({
+        .filter_map(|c| match c {
+            Content::Text { text } => Some(serde_json::json!({"text": text})),
+            Content::Image { data, mime_type } => Some(serde_json::json!({
                 "image": {
                     "format": mime_type.split('/').nth(1).unwrap_or("png"),
                     "source": {"bytes": data},
                 }
-            }),
+            })),
+            Content::Document {
+                data,
+                mime_type,
+                filename,
+            } => Some(serde_json::json!({
+                "document": {
+                    "format": document_format(filename, mime_type),
+                    "name": document_name(filename),
+                    "source": {"bytes": base64::engine::general_purpose::STANDARD.encode(data)},
+                }
+            })),
+            // Converse only reads documents from bytes or S3.
+            Content::DocumentUrl { url, .. } => Some(serde_json::json!({
+                "text": format!("[document: {}]", url),
+            })),
             Content::ToolCall {
                 id,
                 name,
                 arguments,
                 ..
-            } => serde_json::json!({
+            } => Some(serde_json::json!({
                 "toolUse": {"toolUseId": id, "name": name, "input": arguments},
-            }),
+            })),
             // Replay reasoning blocks: Anthropic-on-Bedrock requires the
             // thinking block (with signature) to accompany a replayed
             // assistant message in multi-turn tool use — dropping it causes a
@@ -380,44 +910,87 @@ fn content_to_bedrock(content: &[Content]) -> Vec<serde_json::Value> {
             Content::Thinking {
                 thinking,
                 signature,
-            } => serde_json::json!({
+            } => Some(serde_json::json!({
                 "reasoningContent": {
                     "reasoningText": {
                         "text": thinking,
                         "signature": signature.clone().unwrap_or_default(),
                     }
                 }
-            }),
+            })),
+            // Server tools are Anthropic-only; Converse has no equivalent block.
+            Content::ServerToolUse { .. } | Content::ServerToolResult { .. } => None,
         })
         .collect()
 }
 
-// Bedrock event types
+/// Converse's document `format`: the file extension, falling back to the
+/// MIME subtype.
+fn document_format(filename: &str, mime_type: &str) -> String {
+    std::path::Path::new(filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_else(|| mime_type.rsplit('/').next().unwrap_or("txt"))
+        .to_lowercase()
+}
+
+/// Converse document names may only hold alphanumerics, single spaces,
+/// hyphens, parentheses and square brackets.
+fn document_name(filename: &str) -> String {
+    let stem = std::path::Path::new(filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("document");
+    let name: String = stem
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '(' | ')' | '[' | ']') {
+                c
+            } else {
+                ' '
+            }
+        })
+        .collect();
+    let name = name.split_whitespace().collect::<Vec<_>>().join(" ");
+    if name.is_empty() {
+        "document".into()
+    } else {
+        name
+    }
+}
+
+// ConverseStream event payloads (the event name comes from `:event-type`)
+#[derive(Deserialize)]
+struct BedrockBlockStartEvent {
+    #[serde(default, rename = "contentBlockIndex")]
+    content_block_index: u64,
+    #[serde(default)]
+    start: Option<BedrockBlockStart>,
+}
+
+#[derive(Deserialize)]
+struct BedrockBlockDeltaEvent {
+    #[serde(default, rename = "contentBlockIndex")]
+    content_block_index: u64,
+    delta: BedrockDelta,
+}
+
+#[derive(Deserialize)]
+struct BedrockBlockStopEvent {
+    #[serde(default, rename = "contentBlockIndex")]
+    content_block_index: u64,
+}
+
 #[derive(Deserialize)]
-#[serde(untagged)]
-enum BedrockEvent {
-    ContentBlockDelta {
-        #[serde(rename = "contentBlockDelta")]
-        delta: BedrockDelta,
-    },
-    ContentBlockStart {
-        #[serde(rename = "contentBlockStart")]
-        start: BedrockBlockStart,
-    },
-    ContentBlockStop {
-        #[serde(rename = "contentBlockStop")]
-        #[allow(dead_code)]
-        stop: serde_json::Value,
-    },
-    MessageStop {
-        #[serde(rename = "messageStop")]
-        stop_reason: Option<String>,
-    },
-    Metadata {
-        #[serde(rename = "metadata")]
-        usage: Option<BedrockUsage>,
-    },
-    Unknown,
+struct BedrockMessageStopEvent {
+    #[serde(default, rename = "stopReason")]
+    stop_reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct BedrockMetadataEvent {
+    #[serde(default)]
+    usage: Option<BedrockUsage>,
 }
 
 #[derive(Deserialize)]
@@ -462,6 +1035,12 @@ struct BedrockUsage {
     input_tokens: u64,
     #[serde(default, rename = "outputTokens")]
     output_tokens: u64,
+    #[serde(default, rename = "totalTokens")]
+    total_tokens: u64,
+    #[serde(default, rename = "cacheReadInputTokens")]
+    cache_read_input_tokens: u64,
+    #[serde(default, rename = "cacheWriteInputTokens")]
+    cache_write_input_tokens: u64,
 }
 
 #[cfg(test)]
@@ -518,6 +1097,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
         let body = build_bedrock_body(&config);
         let thinking = &body["additionalModelRequestFields"]["thinking"];
@@ -539,11 +1122,143 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
         let body = build_bedrock_body(&config);
         assert!(body["additionalModelRequestFields"].is_null());
     }
 
+    fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+        headers
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    #[test]
+    fn test_sigv4_matches_aws_test_suite() {
+        // `get-vanilla` from the AWS SigV4 test suite.
+        let credentials =
+            AwsCredentials::parse("AKIDEXAMPLE:wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY").unwrap();
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_440_938_160); // 2015-08-30T12:36:00Z
+        let headers = sign_request(
+            "GET",
+            "https://example.amazonaws.com/",
+            &[],
+            &[],
+            &credentials,
+            "us-east-1",
+            "service",
+            time,
+        )
+        .unwrap();
+
+        assert_eq!(header(&headers, "x-amz-date"), Some("20150830T123600Z"));
+        assert_eq!(
+            header(&headers, "authorization"),
+            Some(
+                "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/service/aws4_request, \
+                 SignedHeaders=host;x-amz-date, \
+                 Signature=5fa00fa31553b73ebf1942676e86291e8372ff2a2260956d9b8aae1d763fbf31"
+            )
+        );
+        assert_eq!(header(&headers, "x-amz-security-token"), None);
+    }
+
+    #[test]
+    fn test_sigv4_signs_body_and_session_token() {
+        let credentials = AwsCredentials::parse("AKID:SECRET:TOKEN").unwrap();
+        let sign = |body: &[u8]| {
+            sign_request(
+                "POST",
+                "https://bedrock-runtime.us-west-2.amazonaws.com/model/m/converse-stream",
+                &[("content-type", "application/json")],
+                body,
+                &credentials,
+                "us-west-2",
+                SIGNING_SERVICE,
+                std::time::UNIX_EPOCH,
+            )
+            .unwrap()
+        };
+        let headers = sign(b"{}");
+
+        assert_eq!(header(&headers, "x-amz-security-token"), Some("TOKEN"));
+        let auth = header(&headers, "authorization").unwrap();
+        assert!(
+            auth.starts_with("AWS4-HMAC-SHA256 Credential=AKID/19700101/us-west-2/bedrock/aws4_request, SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, Signature="),
+            "{auth}"
+        );
+        assert_ne!(
+            header(&sign(b"{\"x\":1}"), "authorization"),
+            Some(auth),
+            "signature must cover the body"
+        );
+    }
+
+    #[test]
+    fn test_amz_timestamp() {
+        let at = |secs| amz_timestamp(std::time::UNIX_EPOCH + std::time::Duration::from_secs(secs));
+        assert_eq!(at(0), ("19700101".into(), "19700101T000000Z".into()));
+        assert_eq!(
+            at(951_782_400), // leap day
+            ("20000229".into(), "20000229T000000Z".into())
+        );
+        assert_eq!(
+            at(1_767_225_599),
+            ("20251231".into(), "20251231T235959Z".into())
+        );
+    }
+
+    #[test]
+    fn test_parse_credentials() {
+        let c = AwsCredentials::parse("AKID:SECRET").unwrap();
+        assert_eq!((c.access_key_id, c.secret_access_key), ("AKID", "SECRET"));
+        assert_eq!(c.session_token, None);
+
+        // Session tokens may themselves contain ':'.
+        let c = AwsCredentials::parse("AKID:SECRET:tok:en").unwrap();
+        assert_eq!(c.session_token, Some("tok:en"));
+
+        assert!(matches!(
+            AwsCredentials::parse("just-a-key"),
+            Err(ProviderError::Auth(_))
+        ));
+        assert!(AwsCredentials::parse(":SECRET").is_err());
+    }
+
+    #[test]
+    fn test_region_from_url() {
+        assert_eq!(
+            region_from_url("https://bedrock-runtime.us-east-1.amazonaws.com"),
+            Some("us-east-1")
+        );
+        assert_eq!(
+            region_from_url("https://bedrock-runtime-fips.us-gov-west-1.amazonaws.com/"),
+            Some("us-gov-west-1")
+        );
+        assert_eq!(
+            region_from_url("https://vpce-0abc.bedrock-runtime.eu-west-1.vpce.amazonaws.com:443/x"),
+            Some("eu-west-1")
+        );
+        assert_eq!(region_from_url("http://127.0.0.1:8080"), None);
+    }
+
+    #[test]
+    fn test_encode_path_segment() {
+        assert_eq!(
+            encode_path_segment("anthropic.claude-3-sonnet-20240229-v1:0"),
+            "anthropic.claude-3-sonnet-20240229-v1%3A0"
+        );
+        assert_eq!(
+            encode_path_segment("arn:aws:bedrock:us-east-1::foundation-model/x"),
+            "arn%3Aaws%3Abedrock%3Aus-east-1%3A%3Afoundation-model%2Fx"
+        );
+    }
+
     #[test]
     fn test_build_bedrock_body() {
         let config = StreamConfig {
@@ -558,6 +1273,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_bedrock_body(&config);
@@ -567,6 +1286,46 @@ mod tests {
         assert_eq!(body["inferenceConfig"]["maxTokens"], 1024);
     }
 
+    #[test]
+    fn stop_sequences_go_in_inference_config() {
+        let mut config = StreamConfig::new("anthropic.claude-sonnet", "a:b");
+        config.messages = vec![Message::user("hi")];
+        config.stop_sequences = Some(vec!["END".into()]);
+        let body = build_bedrock_body(&config);
+        assert_eq!(
+            body["inferenceConfig"]["stopSequences"],
+            serde_json::json!(["END"])
+        );
+    }
+
+    #[test]
+    fn tool_choice_sets_converse_tool_choice() {
+        let mut config = StreamConfig::new("anthropic.claude-sonnet", "a:b");
+        config.messages = vec![Message::user("classify")];
+        config.tools = vec![ToolDefinition {
+            name: "classify".into(),
+            description: "Pick a label".into(),
+            parameters: serde_json::json!({"type": "object"}),
+        }];
+        assert!(build_bedrock_body(&config)["toolConfig"]["toolChoice"].is_null());
+
+        config.tool_choice = ToolChoice::Required;
+        assert_eq!(
+            build_bedrock_body(&config)["toolConfig"]["toolChoice"],
+            serde_json::json!({"any": {}})
+        );
+
+        config.tool_choice = ToolChoice::Specific("classify".into());
+        assert_eq!(
+            build_bedrock_body(&config)["toolConfig"]["toolChoice"],
+            serde_json::json!({"tool": {"name": "classify"}})
+        );
+
+        // Unsupported by Converse: falls back to the default (auto).
+        config.tool_choice = ToolChoice::None;
+        assert!(build_bedrock_body(&config)["toolConfig"]["toolChoice"].is_null());
+    }
+
     #[test]
     fn test_content_to_bedrock_filters_empty_text() {
         let content = vec![
@@ -599,4 +1358,220 @@ mod tests {
         assert_eq!(blocks[0]["text"], "hello");
         assert_eq!(blocks[1]["toolUse"]["name"], "bash");
     }
+
+    // -- Event-stream framing --
+
+    /// Encode a frame the way Bedrock does (string headers only).
+    fn encode_frame(headers: &[(&str, &str)], payload: &[u8]) -> Vec<u8> {
+        let mut header_bytes = Vec::new();
+        for (name, value) in headers {
+            header_bytes.push(name.len() as u8);
+            header_bytes.extend_from_slice(name.as_bytes());
+            header_bytes.push(7);
+            header_bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            header_bytes.extend_from_slice(value.as_bytes());
+        }
+        let total = PRELUDE_LEN + header_bytes.len() + payload.len() + MESSAGE_CRC_LEN;
+        let mut frame = Vec::new();
+        frame.extend_from_slice(&(total as u32).to_be_bytes());
+        frame.extend_from_slice(&(header_bytes.len() as u32).to_be_bytes());
+        let prelude_crc = crc32(&frame);
+        frame.extend_from_slice(&prelude_crc.to_be_bytes());
+        frame.extend_from_slice(&header_bytes);
+        frame.extend_from_slice(payload);
+        let message_crc = crc32(&frame);
+        frame.extend_from_slice(&message_crc.to_be_bytes());
+        frame
+    }
+
+    fn event(event_type: &str, payload: serde_json::Value) -> Vec<u8> {
+        encode_frame(
+            &[
+                (":event-type", event_type),
+                (":content-type", "application/json"),
+                (":message-type", "event"),
+            ],
+            payload.to_string().as_bytes(),
+        )
+    }
+
+    #[test]
+    fn crc32_matches_ieee_check_value() {
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn decodes_frames_split_across_chunks() {
+        let mut bytes = event("messageStart", serde_json::json!({"role": "assistant"}));
+        bytes.extend(event(
+            "contentBlockDelta",
+            serde_json::json!({"contentBlockIndex": 0, "delta": {"text": "héllo"}}),
+        ));
+
+        // Feed one byte at a time — the worst case for chunk boundaries.
+        let mut decoder = EventStreamDecoder::default();
+        let mut frames = Vec::new();
+        for b in &bytes {
+            decoder.push(std::slice::from_ref(b));
+            while let Some(frame) = decoder.next_frame().unwrap() {
+                frames.push(frame);
+            }
+        }
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0].headers[":event-type"], "messageStart");
+        assert_eq!(frames[1].headers[":event-type"], "contentBlockDelta");
+        let payload: serde_json::Value = serde_json::from_slice(&frames[1].payload).unwrap();
+        assert_eq!(payload["delta"]["text"], "héllo");
+    }
+
+    #[test]
+    fn corrupt_frame_is_an_error() {
+        let mut bytes = event("messageStart", serde_json::json!({"role": "assistant"}));
+        let last = bytes.len() - 6;
+        bytes[last] ^= 0xFF; // flip a payload byte; message CRC no longer matches
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&bytes);
+        assert!(decoder.next_frame().is_err());
+    }
+
+    #[test]
+    fn base64_bytes_payload_is_unwrapped() {
+        use base64::Engine;
+        let inner = serde_json::json!({"stopReason": "max_tokens"}).to_string();
+        let wrapped = serde_json::json!({
+            "bytes": base64::engine::general_purpose::STANDARD.encode(inner),
+        });
+        assert_eq!(
+            decode_payload(wrapped.to_string().as_bytes()).unwrap()["stopReason"],
+            "max_tokens"
+        );
+    }
+
+    #[test]
+    fn converse_stream_frames_emit_stream_events() {
+        let frames = [
+            event("messageStart", serde_json::json!({"role": "assistant"})),
+            event(
+                "contentBlockDelta",
+                serde_json::json!({"contentBlockIndex": 0, "delta": {"text": "Let me check."}}),
+            ),
+            event("contentBlockStop", serde_json::json!({"contentBlockIndex": 0})),
+            event(
+                "contentBlockStart",
+                serde_json::json!({"contentBlockIndex": 1, "start": {"toolUse": {"toolUseId": "tu-1", "name": "bash"}}}),
+            ),
+            event(
+                "contentBlockDelta",
+                serde_json::json!({"contentBlockIndex": 1, "delta": {"toolUse": {"input": "{\"command\":"}}}),
+            ),
+            event(
+                "contentBlockDelta",
+                serde_json::json!({"contentBlockIndex": 1, "delta": {"toolUse": {"input": "\"ls\"}"}}}),
+            ),
+            event("contentBlockStop", serde_json::json!({"contentBlockIndex": 1})),
+            event("messageStop", serde_json::json!({"stopReason": "tool_use"})),
+            event(
+                "metadata",
+                serde_json::json!({"usage": {"inputTokens": 12, "outputTokens": 7, "totalTokens": 19}, "metrics": {"latencyMs": 100}}),
+            ),
+        ]
+        .concat();
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut decoder = EventStreamDecoder::default();
+        let mut state = BedrockStreamState::new();
+        // Arbitrary chunking that splits frames mid-header and mid-payload.
+        for chunk in frames.chunks(37) {
+            decoder.push(chunk);
+            while let Some(frame) = decoder.next_frame().unwrap() {
+                state.handle_frame(&frame, &tx).unwrap();
+            }
+        }
+        let message = state.into_message("m", "amazon-bedrock");
+
+        let mut events = Vec::new();
+        while let Ok(e) = rx.try_recv() {
+            events.push(e);
+        }
+        assert!(
+            matches!(&events[0], StreamEvent::TextDelta { content_index: 0, delta } if delta == "Let me check.")
+        );
+        assert!(
+            matches!(&events[1], StreamEvent::ToolCallStart { content_index: 1, id, name } if id == "tu-1" && name == "bash")
+        );
+        assert!(matches!(
+            &events[2],
+            StreamEvent::ToolCallDelta {
+                content_index: 1,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &events[3],
+            StreamEvent::ToolCallDelta {
+                content_index: 1,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &events[4],
+            StreamEvent::ToolCallEnd { content_index: 1 }
+        ));
+        assert_eq!(events.len(), 5);
+
+        let Message::Assistant {
+            content,
+            stop_reason,
+            usage,
+            ..
+        } = message
+        else {
+            panic!("expected assistant message");
+        };
+        assert_eq!(stop_reason, StopReason::ToolUse);
+        assert_eq!(usage.input, 12);
+        assert_eq!(usage.output, 7);
+        assert_eq!(usage.total_tokens, 19);
+        assert_eq!(
+            content[1],
+            Content::tool_call("tu-1", "bash", serde_json::json!({"command": "ls"}))
+        );
+    }
+
+    #[test]
+    fn stop_sequence_stop_reason_is_mapped() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&event(
+            "messageStop",
+            serde_json::json!({"stopReason": "stop_sequence"}),
+        ));
+        let mut state = BedrockStreamState::new();
+        let frame = decoder.next_frame().unwrap().unwrap();
+        state.handle_frame(&frame, &tx).unwrap();
+        let Message::Assistant { stop_reason, .. } = state.into_message("m", "amazon-bedrock")
+        else {
+            panic!("expected assistant message");
+        };
+        assert_eq!(stop_reason, StopReason::StopSequence);
+    }
+
+    #[test]
+    fn exception_frame_maps_to_provider_error() {
+        let frame = encode_frame(
+            &[
+                (":exception-type", "throttlingException"),
+                (":message-type", "exception"),
+            ],
+            br#"{"message":"Too many requests"}"#,
+        );
+        let mut decoder = EventStreamDecoder::default();
+        decoder.push(&frame);
+        let frame = decoder.next_frame().unwrap().unwrap();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let err = BedrockStreamState::new()
+            .handle_frame(&frame, &tx)
+            .unwrap_err();
+        assert!(matches!(err, ProviderError::RateLimited { .. }));
+    }
 }
@@ -0,0 +1,812 @@
+//! Cohere Chat API provider (Command R / R+).
+//!
+//! Uses the v1 `/chat` endpoint. Unlike OpenAI, the request carries the
+//! newest turn in `message` (or `tool_results`) and everything before it in
+//! `chat_history`; the stream is one JSON event per line, tagged by
+//! `event_type`.
+
+use super::traits::*;
+use crate::types::*;
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+pub struct CohereProvider;
+
+#[async_trait]
+impl StreamProvider for CohereProvider {
+    fn protocol(&self) -> Option<crate::provider::ApiProtocol> {
+        Some(crate::provider::ApiProtocol::CohereChat)
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_vision: false,
+            supports_thinking: false,
+            supports_server_tools: false,
+            max_context_tokens: Some(128_000),
+            ..ProviderCapabilities::default()
+        }
+    }
+
+    async fn stream(
+        &self,
+        config: StreamConfig,
+        tx: mpsc::UnboundedSender<StreamEvent>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<Message, ProviderError> {
+        if config.tool_choice != ToolChoice::Auto && !config.tools.is_empty() {
+            warn!(
+                "Cohere's Chat API has no tool_choice; {:?} will be ignored by the Cohere provider",
+                config.tool_choice
+            );
+        }
+        let model_config = config
+            .model_config
+            .as_ref()
+            .ok_or_else(|| ProviderError::Other("ModelConfig required".into()))?;
+
+        let url = format!("{}/chat", model_config.base_url);
+        let body = build_request_body(&config);
+        debug!("Cohere request: model={} url={}", config.model, url);
+
+        let client = reqwest::Client::new();
+        let mut request = client
+            .post(&url)
+            .header("content-type", "application/json")
+            .header("accept", "application/json")
+            .header("authorization", format!("Bearer {}", config.api_key));
+
+        for (k, v) in &model_config.headers {
+            request = request.header(k, v);
+        }
+
+        let response = request
+            .json(&body)
+            .send()
+            .await
+            .map_err(|e| ProviderError::Network(e.to_string()))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ProviderError::classify_with_headers(
+                status.as_u16(),
+                &format!("Cohere API error {}: {}", status, body),
+                &headers,
+            ));
+        }
+
+        let mut state = StreamState::new();
+        let _ = tx.send(StreamEvent::Start);
+
+        let mut stream = response.bytes_stream();
+        let mut buffer = String::new();
+        'read: loop {
+            tokio::select! {
+                _ = cancel.cancelled() => {
+                    return Err(ProviderError::Cancelled);
+                }
+                chunk = stream.next() => {
+                    let end_of_body = match chunk {
+                        None => {
+                            // A final event without a trailing newline.
+                            buffer.push('\n');
+                            true
+                        }
+                        Some(Err(e)) => {
+                            let provider_err = ProviderError::Network(e.to_string());
+                            warn!("Cohere stream error: {}", provider_err);
+                            return Err(provider_err);
+                        }
+                        Some(Ok(bytes)) => {
+                            buffer.push_str(&String::from_utf8_lossy(&bytes));
+                            false
+                        }
+                    };
+                    while let Some(data) = next_event_line(&mut buffer) {
+                        let event: CohereEvent = match serde_json::from_str(&data) {
+                            Ok(e) => e,
+                            Err(e) => {
+                                warn!("Failed to parse Cohere event: {} data={}", e, data);
+                                continue;
+                            }
+                        };
+                        if state.handle(event, &tx)? {
+                            break 'read;
+                        }
+                    }
+                    if end_of_body {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if state.stop_reason.is_none() {
+            // The body closed without `stream-end`: the turn was truncated.
+            return Err(ProviderError::Network(
+                "Cohere stream ended before stream-end".into(),
+            ));
+        }
+
+        let message = state.finish(&config.model, &model_config.provider, &tx);
+        let _ = tx.send(StreamEvent::Done {
+            message: message.clone(),
+        });
+        Ok(message)
+    }
+}
+
+/// Pop the next complete line from `buffer` and return its JSON payload.
+/// Lines may carry an SSE `data:` prefix; blank lines and other SSE fields
+/// (`event:`, comments) yield nothing and are skipped. Returns `None` until
+/// a complete JSON line is buffered.
+fn next_event_line(buffer: &mut String) -> Option<String> {
+    while let Some(pos) = buffer.find('\n') {
+        let line: String = buffer.drain(..=pos).collect();
+        let line = line.trim();
+        let data = line.strip_prefix("data:").map_or(line, str::trim_start);
+        if data.starts_with('{') {
+            return Some(data.to_string());
+        }
+    }
+    None
+}
+
+/// Assistant message assembled from the event stream.
+struct StreamState {
+    content: Vec<Content>,
+    usage: Usage,
+    stop_reason: Option<StopReason>,
+    error_message: Option<String>,
+    tool_call_buffers: Vec<ToolCallBuffer>,
+    /// Unique to this stream; prefixes its synthesized tool call ids.
+    call_id_prefix: String,
+}
+
+#[derive(Default)]
+struct ToolCallBuffer {
+    name: String,
+    arguments: String,
+}
+
+impl StreamState {
+    fn new() -> Self {
+        Self {
+            content: Vec::new(),
+            usage: Usage::default(),
+            stop_reason: None,
+            error_message: None,
+            tool_call_buffers: Vec::new(),
+            call_id_prefix: call_id_prefix(),
+        }
+    }
+
+    /// Apply one stream event. Returns `true` once the stream has ended.
+    fn handle(
+        &mut self,
+        event: CohereEvent,
+        tx: &mpsc::UnboundedSender<StreamEvent>,
+    ) -> Result<bool, ProviderError> {
+        match event.event_type.as_str() {
+            // `tool-calls-chunk` carries the model's tool plan as `text`
+            // deltas alongside (or instead of) the call itself.
+            "text-generation" | "tool-calls-chunk" => {
+                if let Some(text) = event.text.filter(|t| !t.is_empty()) {
+                    self.push_text(text, tx);
+                }
+                if let Some(delta) = event.tool_call_delta {
+                    // Without an index, a name opens the next call and
+                    // parameters continue the current one.
+                    let index = delta.index.unwrap_or(match delta.name {
+                        Some(_) => self.tool_call_buffers.len(),
+                        None => self.tool_call_buffers.len().saturating_sub(1),
+                    });
+                    if let Some(name) = delta.name {
+                        self.start_tool_call(index, name, tx);
+                    }
+                    if let Some(args) = delta.parameters {
+                        if let Some(buf) = self.tool_call_buffers.get_mut(index) {
+                            buf.arguments.push_str(&args);
+                            let _ = tx.send(StreamEvent::ToolCallDelta {
+                                content_index: self.content.len() + index,
+                                delta: args,
+                            });
+                        }
+                    }
+                }
+            }
+            // The complete calls, after the chunks: authoritative for the
+            // arguments, which some models stream only partially.
+            "tool-calls-generation" => {
+                for (index, call) in event.tool_calls.unwrap_or_default().into_iter().enumerate() {
+                    if self.tool_call_buffers.len() <= index {
+                        self.start_tool_call(index, call.name.clone(), tx);
+                    }
+                    let buf = &mut self.tool_call_buffers[index];
+                    buf.name = call.name;
+                    buf.arguments = call.parameters.to_string();
+                }
+            }
+            "stream-end" => {
+                let reason = event.finish_reason.unwrap_or_default();
+                self.stop_reason = Some(match reason.as_str() {
+                    "COMPLETE" => StopReason::Stop,
+                    "STOP_SEQUENCE" => StopReason::StopSequence,
+                    "MAX_TOKENS" => StopReason::Length,
+                    "TOOL_CALL" => StopReason::ToolUse,
+                    "ERROR_LIMIT" => {
+                        return Err(ProviderError::ContextOverflow {
+                            message: "Cohere stream ended with ERROR_LIMIT".into(),
+                        });
+                    }
+                    "ERROR_TOXIC" => {
+                        self.error_message =
+                            Some("Response blocked by Cohere (finish_reason: ERROR_TOXIC)".into());
+                        StopReason::Refusal
+                    }
+                    _ => {
+                        warn!("Cohere stream ended with finish_reason={}", reason);
+                        self.error_message = Some(format!("Cohere stream ended with {}", reason));
+                        StopReason::Error
+                    }
+                });
+                if let Some(meta) = event.response.and_then(|r| r.meta) {
+                    if let Some(tokens) = meta.tokens.or(meta.billed_units) {
+                        self.usage.input = tokens.input_tokens as u64;
+                        self.usage.output = tokens.output_tokens as u64;
+                        self.usage.total_tokens = self.usage.input + self.usage.output;
+                    }
+                }
+                return Ok(true);
+            }
+            // `stream-start`, and `search-results` / `citation-generation`
+            // from connectors, which tool-driven agents don't use.
+            other => debug!("Ignoring Cohere event: {}", other),
+        }
+        Ok(false)
+    }
+
+    fn push_text(&mut self, delta: String, tx: &mpsc::UnboundedSender<StreamEvent>) {
+        let text_idx = self
+            .content
+            .iter()
+            .position(|c| matches!(c, Content::Text { .. }));
+        let idx = match text_idx {
+            Some(i) => i,
+            None => {
+                self.content.push(Content::Text {
+                    text: String::new(),
+                });
+                self.content.len() - 1
+            }
+        };
+        if let Some(Content::Text { text }) = self.content.get_mut(idx) {
+            text.push_str(&delta);
+        }
+        let _ = tx.send(StreamEvent::TextDelta {
+            content_index: idx,
+            delta,
+        });
+    }
+
+    fn start_tool_call(
+        &mut self,
+        index: usize,
+        name: String,
+        tx: &mpsc::UnboundedSender<StreamEvent>,
+    ) {
+        while self.tool_call_buffers.len() <= index {
+            self.tool_call_buffers.push(ToolCallBuffer::default());
+        }
+        self.tool_call_buffers[index].name.clone_from(&name);
+        let _ = tx.send(StreamEvent::ToolCallStart {
+            content_index: self.content.len() + index,
+            id: format!("{}-{}", self.call_id_prefix, index),
+            name,
+        });
+    }
+
+    fn finish(
+        mut self,
+        model: &str,
+        provider: &str,
+        tx: &mpsc::UnboundedSender<StreamEvent>,
+    ) -> Message {
+        let has_tool_calls = !self.tool_call_buffers.is_empty();
+        for (index, buf) in self.tool_call_buffers.into_iter().enumerate() {
+            let args = serde_json::from_str(&buf.arguments).unwrap_or_else(|e| {
+                if !buf.arguments.is_empty() {
+                    warn!(
+                        tool = %buf.name,
+                        len = buf.arguments.len(),
+                        "tool-call arguments failed to parse ({e}); using empty object"
+                    );
+                }
+                serde_json::Value::Object(Default::default())
+            });
+            self.content.push(Content::ToolCall {
+                provider_metadata: None,
+                id: format!("{}-{}", self.call_id_prefix, index),
+                name: buf.name,
+                arguments: args,
+            });
+            let _ = tx.send(StreamEvent::ToolCallEnd {
+                content_index: self.content.len() - 1,
+            });
+        }
+
+        // v1 reports COMPLETE even when the turn ends in tool calls.
+        let stop_reason = match self.stop_reason {
+            Some(StopReason::Stop) if has_tool_calls => StopReason::ToolUse,
+            Some(reason) => reason,
+            None => StopReason::Stop,
+        };
+
+        Message::Assistant {
+            content: self.content,
+            stop_reason,
+            model: model.to_string(),
+            provider: provider.to_string(),
+            usage: self.usage,
+            timestamp: now_ms(),
+            error_message: self.error_message,
+        }
+    }
+}
+
+/// Streams started by this process, so two in the same millisecond still
+/// get distinct call ids.
+static STREAM_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+/// Cohere calls carry no id (results are matched by name and parameters),
+/// so ids are synthesized as this prefix plus the call's position in the
+/// turn. The prefix differs per stream: positions repeat every turn, and the
+/// agent loop and compaction need ids unique across the conversation.
+fn call_id_prefix() -> String {
+    format!(
+        "cohere-call-{}-{}",
+        now_ms(),
+        STREAM_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+fn build_request_body(config: &StreamConfig) -> serde_json::Value {
+    let mut chat_history: Vec<serde_json::Value> = Vec::new();
+    // Tool results must restate their call, so remember each call by id.
+    let mut calls: HashMap<&str, &serde_json::Value> = HashMap::new();
+
+    for msg in &config.messages {
+        match msg {
+            Message::User { content, .. } => {
+                chat_history.push(serde_json::json!({
+                    "role": "USER",
+                    "message": text_of(content),
+                }));
+            }
+            Message::Assistant { content, .. } => {
+                let mut turn = serde_json::json!({
+                    "role": "CHATBOT",
+                    "message": text_of(content),
+                });
+                let tool_calls: Vec<serde_json::Value> = content
+                    .iter()
+                    .filter_map(|c| match c {
+                        Content::ToolCall {
+                            id,
+                            name,
+                            arguments,
+                            ..
+                        } => {
+                            calls.insert(id, arguments);
+                            Some(serde_json::json!({"name": name, "parameters": arguments}))
+                        }
+                        _ => None,
+                    })
+                    .collect();
+                if !tool_calls.is_empty() {
+                    turn["tool_calls"] = serde_json::json!(tool_calls);
+                }
+                chat_history.push(turn);
+            }
+            Message::ToolResult {
+                tool_call_id,
+                tool_name,
+                content,
+                is_error,
+                ..
+            } => {
+                let output = if *is_error {
+                    serde_json::json!({"error": text_of(content)})
+                } else {
+                    serde_json::json!({"result": text_of(content)})
+                };
+                let parameters = calls
+                    .get(tool_call_id.as_str())
+                    .map_or_else(|| serde_json::json!({}), |args| (*args).clone());
+                let result = serde_json::json!({
+                    "call": {"name": tool_name, "parameters": parameters},
+                    "outputs": [output],
+                });
+                // Results for one assistant turn share a TOOL entry.
+                match chat_history.last_mut() {
+                    Some(turn) if turn["role"] == "TOOL" => {
+                        if let Some(results) = turn["tool_results"].as_array_mut() {
+                            results.push(result);
+                        }
+                    }
+                    _ => chat_history.push(serde_json::json!({
+                        "role": "TOOL",
+                        "tool_results": [result],
+                    })),
+                }
+            }
+        }
+    }
+
+    let mut body = serde_json::json!({
+        "model": config.model,
+        "stream": true,
+        "message": "",
+    });
+
+    // The newest turn is sent outside the history: a user prompt as
+    // `message`, answers to the last tool calls as `tool_results`.
+    match chat_history.pop() {
+        Some(mut turn) if turn["role"] == "USER" => body["message"] = turn["message"].take(),
+        Some(mut turn) if turn["role"] == "TOOL" => {
+            body["tool_results"] = turn["tool_results"].take();
+        }
+        Some(turn) => chat_history.push(turn),
+        None => {}
+    }
+    if !chat_history.is_empty() {
+        body["chat_history"] = serde_json::json!(chat_history);
+    }
+
+    if !config.system_prompt.is_empty() {
+        body["preamble"] = serde_json::json!(config.system_prompt);
+    }
+    if let Some(max) = config.max_tokens {
+        body["max_tokens"] = serde_json::json!(max);
+    }
+    if let Some(temp) = config.temperature {
+        body["temperature"] = serde_json::json!(temp);
+    }
+    if let Some(stop) = &config.stop_sequences {
+        body["stop_sequences"] = serde_json::json!(stop);
+    }
+    if let Some(schema) = &config.output_schema {
+        body["response_format"] = serde_json::json!({
+            "type": "json_object",
+            "schema": schema.schema,
+        });
+    } else if config.response_format == ResponseFormat::JsonObject {
+        body["response_format"] = serde_json::json!({"type": "json_object"});
+    }
+
+    if !config.tools.is_empty() {
+        let tools: Vec<serde_json::Value> = config
+            .tools
+            .iter()
+            .map(|t| {
+                serde_json::json!({
+                    "name": t.name,
+                    "description": t.description,
+                    "parameter_definitions": parameter_definitions(&t.parameters),
+                })
+            })
+            .collect();
+        body["tools"] = serde_json::json!(tools);
+    }
+
+    body
+}
+
+/// The text parts of a message, one per line. Cohere v1 messages are plain
+/// strings, so images and thinking are dropped, and documents with a warning.
+fn text_of(content: &[Content]) -> String {
+    content
+        .iter()
+        .filter_map(|c| match c {
+            Content::Text { text } if !text.is_empty() => Some(text.as_str()),
+            Content::Document { filename, .. } => {
+                warn!("Cohere does not accept documents; dropping {}", filename);
+                None
+            }
+            Content::DocumentUrl { url, .. } => {
+                warn!("Cohere does not accept documents; dropping {}", url);
+                None
+            }
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Cohere's flat `parameter_definitions` for a tool's JSON Schema: one
+/// entry per top-level property with a Python-style type name. Nested
+/// object structure is not expressible and collapses to `Dict`.
+fn parameter_definitions(schema: &serde_json::Value) -> serde_json::Value {
+    let required: Vec<&str> = schema["required"]
+        .as_array()
+        .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let definitions: serde_json::Map<String, serde_json::Value> = schema["properties"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, prop)| {
+            let mut definition = serde_json::json!({
+                "type": python_type(prop),
+                "required": required.contains(&name.as_str()),
+            });
+            if let Some(description) = prop["description"].as_str() {
+                definition["description"] = serde_json::json!(description);
+            }
+            (name.clone(), definition)
+        })
+        .collect();
+    serde_json::Value::Object(definitions)
+}
+
+fn python_type(schema: &serde_json::Value) -> String {
+    match schema["type"].as_str() {
+        Some("integer") => "int".into(),
+        Some("number") => "float".into(),
+        Some("boolean") => "bool".into(),
+        Some("array") => format!("List[{}]", python_type(&schema["items"])),
+        Some("object") => "Dict".into(),
+        _ => "str".into(),
+    }
+}
+
+// Cohere stream event types
+#[derive(Deserialize)]
+struct CohereEvent {
+    event_type: String,
+    #[serde(default)]
+    text: Option<String>,
+    #[serde(default)]
+    tool_call_delta: Option<CohereToolCallDelta>,
+    #[serde(default)]
+    tool_calls: Option<Vec<CohereToolCall>>,
+    #[serde(default)]
+    finish_reason: Option<String>,
+    #[serde(default)]
+    response: Option<CohereResponse>,
+}
+
+#[derive(Deserialize)]
+struct CohereToolCallDelta {
+    #[serde(default)]
+    index: Option<usize>,
+    #[serde(default)]
+    name: Option<String>,
+    /// A fragment of the arguments' JSON text.
+    #[serde(default)]
+    parameters: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CohereToolCall {
+    name: String,
+    #[serde(default)]
+    parameters: serde_json::Value,
+}
+
+#[derive(Deserialize)]
+struct CohereResponse {
+    #[serde(default)]
+    meta: Option<CohereMeta>,
+}
+
+#[derive(Deserialize)]
+struct CohereMeta {
+    #[serde(default)]
+    tokens: Option<CohereTokens>,
+    #[serde(default)]
+    billed_units: Option<CohereTokens>,
+}
+
+/// Token counts; Cohere sends them as JSON numbers that may be floats.
+#[derive(Deserialize)]
+struct CohereTokens {
+    #[serde(default)]
+    input_tokens: f64,
+    #[serde(default)]
+    output_tokens: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool_turns() -> Vec<Message> {
+        vec![
+            Message::user("weather in Paris and Rome?"),
+            Message::assistant(
+                vec![
+                    Content::Text {
+                        text: "I will check both.".into(),
+                    },
+                    Content::tool_call(
+                        "cohere-call-0",
+                        "get_weather",
+                        serde_json::json!({"city": "Paris"}),
+                    ),
+                    Content::tool_call(
+                        "cohere-call-1",
+                        "get_weather",
+                        serde_json::json!({"city": "Rome"}),
+                    ),
+                ],
+                StopReason::ToolUse,
+                "command-r-plus",
+                "cohere",
+                Usage::default(),
+            ),
+            Message::ToolResult {
+                tool_call_id: "cohere-call-0".into(),
+                tool_name: "get_weather".into(),
+                content: vec![Content::Text { text: "22C".into() }],
+                is_error: false,
+                timestamp: 0,
+            },
+            Message::ToolResult {
+                tool_call_id: "cohere-call-1".into(),
+                tool_name: "get_weather".into(),
+                content: vec![Content::Text {
+                    text: "unknown city".into(),
+                }],
+                is_error: true,
+                timestamp: 0,
+            },
+        ]
+    }
+
+    #[test]
+    fn last_user_turn_goes_in_message() {
+        let mut config = StreamConfig::new("command-r-plus", "k");
+        config.system_prompt = "Be brief".into();
+        config.messages = vec![
+            Message::user("hi"),
+            Message::assistant(
+                vec![Content::Text {
+                    text: "hello".into(),
+                }],
+                StopReason::Stop,
+                "command-r-plus",
+                "cohere",
+                Usage::default(),
+            ),
+            Message::user("how are you?"),
+        ];
+        let body = build_request_body(&config);
+        assert_eq!(body["message"], "how are you?");
+        assert_eq!(body["preamble"], "Be brief");
+        assert_eq!(
+            body["chat_history"],
+            serde_json::json!([
+                {"role": "USER", "message": "hi"},
+                {"role": "CHATBOT", "message": "hello"},
+            ])
+        );
+        assert!(body.get("tool_results").is_none());
+    }
+
+    #[test]
+    fn trailing_tool_results_go_in_tool_results() {
+        let mut config = StreamConfig::new("command-r-plus", "k");
+        config.messages = tool_turns();
+        let body = build_request_body(&config);
+
+        assert_eq!(body["message"], "");
+        assert_eq!(
+            body["tool_results"],
+            serde_json::json!([
+                {
+                    "call": {"name": "get_weather", "parameters": {"city": "Paris"}},
+                    "outputs": [{"result": "22C"}],
+                },
+                {
+                    "call": {"name": "get_weather", "parameters": {"city": "Rome"}},
+                    "outputs": [{"error": "unknown city"}],
+                },
+            ])
+        );
+        let history = body["chat_history"].as_array().unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[1]["role"], "CHATBOT");
+        assert_eq!(history[1]["message"], "I will check both.");
+        assert_eq!(
+            history[1]["tool_calls"][1],
+            serde_json::json!({"name": "get_weather", "parameters": {"city": "Rome"}})
+        );
+    }
+
+    #[test]
+    fn earlier_tool_results_stay_in_history() {
+        let mut config = StreamConfig::new("command-r-plus", "k");
+        config.messages = tool_turns();
+        config.messages.push(Message::user("thanks"));
+        let body = build_request_body(&config);
+
+        assert_eq!(body["message"], "thanks");
+        assert!(body.get("tool_results").is_none());
+        let history = body["chat_history"].as_array().unwrap();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history[2]["role"], "TOOL");
+        assert_eq!(history[2]["tool_results"].as_array().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn tools_map_to_parameter_definitions() {
+        let mut config = StreamConfig::new("command-r-plus", "k");
+        config.messages = vec![Message::user("hi")];
+        config.tools = vec![ToolDefinition {
+            name: "search".into(),
+            description: "Search notes".into(),
+            parameters: serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "What to find"},
+                    "limit": {"type": "integer"},
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                    "filters": {"type": "object"},
+                },
+                "required": ["query"],
+            }),
+        }];
+        let body = build_request_body(&config);
+        assert_eq!(
+            body["tools"],
+            serde_json::json!([{
+                "name": "search",
+                "description": "Search notes",
+                "parameter_definitions": {
+                    "query": {"type": "str", "required": true, "description": "What to find"},
+                    "limit": {"type": "int", "required": false},
+                    "tags": {"type": "List[str]", "required": false},
+                    "filters": {"type": "Dict", "required": false},
+                },
+            }])
+        );
+    }
+
+    #[test]
+    fn generation_options_and_json_mode() {
+        let mut config = StreamConfig::new("command-r-plus", "k");
+        config.messages = vec![Message::user("hi")];
+        config.max_tokens = Some(512);
+        config.stop_sequences = Some(vec!["END".into()]);
+        config.response_format = ResponseFormat::JsonObject;
+        let body = build_request_body(&config);
+        assert_eq!(body["max_tokens"], 512);
+        assert_eq!(body["stop_sequences"], serde_json::json!(["END"]));
+        assert_eq!(
+            body["response_format"],
+            serde_json::json!({"type": "json_object"})
+        );
+    }
+
+    #[test]
+    fn event_lines_accept_plain_and_sse_framing() {
+        let mut buf =
+            "{\"event_type\":\"stream-start\"}\nevent: text-generation\ndata: {\"a\":1}\n\n{\"b\""
+                .to_string();
+        assert_eq!(
+            next_event_line(&mut buf).as_deref(),
+            Some("{\"event_type\":\"stream-start\"}")
+        );
+        assert_eq!(next_event_line(&mut buf).as_deref(), Some("{\"a\":1}"));
+        assert_eq!(next_event_line(&mut buf), None, "incomplete line waits");
+        assert_eq!(buf, "{\"b\"");
+    }
+}
@@ -0,0 +1,333 @@
+//! Fallback provider — routes around provider-level failures.
+//!
+//! [`RetryConfig`](crate::retry::RetryConfig) retries the *same* provider on
+//! transient errors. `FallbackProvider` tries a *different* backend (say,
+//! OpenAI after Anthropic reports it is overloaded), each with its own
+//! model, API key and [`ModelConfig`](super::ModelConfig).
+
+use super::traits::*;
+use crate::types::*;
+use async_trait::async_trait;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+impl ProviderError {
+    /// Whether a [`FallbackProvider`] should move on to the next backend.
+    ///
+    /// True for network failures, rate limits, API errors (overloaded, 5xx)
+    /// and unclassified errors. False for auth failures and cancellation,
+    /// and for context overflow, which the agent loop handles itself.
+    pub fn is_fallback_eligible(&self) -> bool {
+        matches!(
+            self,
+            Self::Network(_) | Self::RateLimited { .. } | Self::Api(_) | Self::Other(_)
+        )
+    }
+}
+
+/// A [`StreamProvider`] that tries each backend in order and returns the
+/// first success.
+///
+/// Each entry pairs a provider with a [`StreamConfig`] whose `model`,
+/// `api_key` and `model_config` replace the incoming request's; everything
+/// else (prompt, messages, tools, ...) is passed through unchanged.
+///
+/// Backends whose [`capabilities`](StreamProvider::capabilities) can't
+/// serve the request (say, JSON mode on a backend without it) are skipped.
+///
+/// Events are forwarded as each backend streams, so a backend that fails
+/// mid-stream may already have emitted partial deltas — the same caveat as
+/// retries.
+///
+/// ```rust,no_run
+/// use yoagent::provider::{AnthropicProvider, FallbackProvider, OpenAiCompatProvider, StreamConfig};
+///
+/// let provider = FallbackProvider::new(vec![
+///     (
+///         Box::new(AnthropicProvider),
+///         StreamConfig::new("claude-sonnet-4-20250514", "sk-ant-..."),
+///     ),
+///     (
+///         Box::new(OpenAiCompatProvider),
+///         StreamConfig::new("gpt-4o", "sk-..."),
+///     ),
+/// ]);
+/// ```
+pub struct FallbackProvider {
+    providers: Vec<(Box<dyn StreamProvider>, StreamConfig)>,
+}
+
+impl FallbackProvider {
+    /// Create a fallback chain. Backends are tried in order.
+    pub fn new(providers: Vec<(Box<dyn StreamProvider>, StreamConfig)>) -> Self {
+        Self { providers }
+    }
+}
+
+#[async_trait]
+impl StreamProvider for FallbackProvider {
+    /// What at least one backend supports; the largest known context size.
+    fn capabilities(&self) -> ProviderCapabilities {
+        let all = self.providers.iter().map(|(p, _)| p.capabilities());
+        all.fold(
+            ProviderCapabilities {
+                supports_vision: false,
+                supports_tools: false,
+                supports_thinking: false,
+                supports_streaming: false,
+                supports_json_mode: false,
+                supports_system_prompt: false,
+                supports_server_tools: false,
+                max_context_tokens: None,
+            },
+            |acc, c| ProviderCapabilities {
+                supports_vision: acc.supports_vision || c.supports_vision,
+                supports_tools: acc.supports_tools || c.supports_tools,
+                supports_thinking: acc.supports_thinking || c.supports_thinking,
+                supports_streaming: acc.supports_streaming || c.supports_streaming,
+                supports_json_mode: acc.supports_json_mode || c.supports_json_mode,
+                supports_system_prompt: acc.supports_system_prompt || c.supports_system_prompt,
+                supports_server_tools: acc.supports_server_tools || c.supports_server_tools,
+                max_context_tokens: acc.max_context_tokens.max(c.max_context_tokens),
+            },
+        )
+    }
+
+    async fn stream(
+        &self,
+        config: StreamConfig,
+        tx: mpsc::UnboundedSender<StreamEvent>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<Message, ProviderError> {
+        let mut last_error = ProviderError::Other("FallbackProvider has no providers".into());
+
+        for (index, (provider, overrides)) in self.providers.iter().enumerate() {
+            if cancel.is_cancelled() {
+                return Err(ProviderError::Cancelled);
+            }
+
+            let attempt = StreamConfig {
+                model: overrides.model.clone(),
+                api_key: overrides.api_key.clone(),
+                model_config: overrides.model_config.clone(),
+                ..config.clone()
+            };
+
+            let missing = provider.capabilities().unsupported_features(&attempt);
+            if !missing.is_empty() {
+                debug!(
+                    "Skipping provider for model {}: no support for {}",
+                    overrides.model,
+                    missing.join(", ")
+                );
+                last_error = ProviderError::Other(format!(
+                    "provider for model {} does not support {}",
+                    overrides.model,
+                    missing.join(", ")
+                ));
+                continue;
+            }
+
+            match provider.stream(attempt, tx.clone(), cancel.clone()).await {
+                Ok(message) => return Ok(message),
+                Err(e) if e.is_fallback_eligible() => {
+                    if index + 1 < self.providers.len() {
+                        warn!(
+                            "Provider for model {} failed, falling back: {}",
+                            overrides.model, e
+                        );
+                    }
+                    last_error = e;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(last_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+    use std::sync::{Arc, Mutex};
+
+    /// Fails with a fixed error and records the config it was called with.
+    struct FailingProvider {
+        error: fn() -> ProviderError,
+        seen: Arc<Mutex<Vec<StreamConfig>>>,
+    }
+
+    #[async_trait]
+    impl StreamProvider for FailingProvider {
+        async fn stream(
+            &self,
+            config: StreamConfig,
+            _tx: mpsc::UnboundedSender<StreamEvent>,
+            _cancel: tokio_util::sync::CancellationToken,
+        ) -> Result<Message, ProviderError> {
+            self.seen.lock().unwrap().push(config);
+            Err((self.error)())
+        }
+    }
+
+    fn failing(
+        error: fn() -> ProviderError,
+    ) -> (Box<dyn StreamProvider>, Arc<Mutex<Vec<StreamConfig>>>) {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let provider = FailingProvider {
+            error,
+            seen: seen.clone(),
+        };
+        (Box::new(provider), seen)
+    }
+
+    async fn run(provider: &FallbackProvider) -> Result<Message, ProviderError> {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut config = StreamConfig::new("request-model", "request-key");
+        config.system_prompt = "be brief".into();
+        provider
+            .stream(config, tx, tokio_util::sync::CancellationToken::new())
+            .await
+    }
+
+    #[test]
+    fn test_fallback_eligibility() {
+        assert!(ProviderError::Network("reset".into()).is_fallback_eligible());
+        assert!(ProviderError::RateLimited {
+            retry_after_ms: None
+        }
+        .is_fallback_eligible());
+        assert!(ProviderError::Api("overloaded".into()).is_fallback_eligible());
+        assert!(ProviderError::Other("bad frame".into()).is_fallback_eligible());
+        assert!(!ProviderError::Auth("bad key".into()).is_fallback_eligible());
+        assert!(!ProviderError::Cancelled.is_fallback_eligible());
+        assert!(!ProviderError::ContextOverflow {
+            message: "too long".into()
+        }
+        .is_fallback_eligible());
+    }
+
+    #[tokio::test]
+    async fn test_falls_through_to_backup_with_its_config() {
+        let (primary, primary_seen) = failing(|| ProviderError::Api("overloaded".into()));
+        let provider = FallbackProvider::new(vec![
+            (primary, StreamConfig::new("claude-primary", "key-a")),
+            (
+                Box::new(MockProvider::text("from backup")),
+                StreamConfig::new("gpt-4o", "key-b"),
+            ),
+        ]);
+
+        let message = run(&provider).await.unwrap();
+        match message {
+            Message::Assistant { content, .. } => {
+                assert!(matches!(&content[0], Content::Text { text } if text == "from backup"))
+            }
+            other => panic!("expected assistant message, got {other:?}"),
+        }
+
+        let seen = primary_seen.lock().unwrap();
+        assert_eq!(seen[0].model, "claude-primary");
+        assert_eq!(seen[0].api_key, "key-a");
+        assert_eq!(seen[0].system_prompt, "be brief");
+    }
+
+    #[tokio::test]
+    async fn test_auth_error_stops_the_chain() {
+        let (primary, _) = failing(|| ProviderError::Auth("bad key".into()));
+        let (backup, backup_seen) = failing(|| ProviderError::Other("unreachable".into()));
+        let provider = FallbackProvider::new(vec![
+            (primary, StreamConfig::new("a", "key-a")),
+            (backup, StreamConfig::new("b", "key-b")),
+        ]);
+
+        assert!(matches!(run(&provider).await, Err(ProviderError::Auth(_))));
+        assert!(backup_seen.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_all_failing_returns_last_error() {
+        let (primary, _) = failing(|| ProviderError::Network("reset".into()));
+        let (backup, _) = failing(|| ProviderError::RateLimited {
+            retry_after_ms: Some(500),
+        });
+        let provider = FallbackProvider::new(vec![
+            (primary, StreamConfig::new("a", "key-a")),
+            (backup, StreamConfig::new("b", "key-b")),
+        ]);
+
+        assert!(matches!(
+            run(&provider).await,
+            Err(ProviderError::RateLimited {
+                retry_after_ms: Some(500)
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_empty_chain_is_an_error() {
+        let provider = FallbackProvider::new(Vec::new());
+        assert!(matches!(run(&provider).await, Err(ProviderError::Other(_))));
+    }
+
+    #[tokio::test]
+    async fn test_skips_backends_without_required_capability() {
+        use crate::provider::{BedrockProvider, ResponseFormat};
+
+        let (backup, backup_seen) = failing(|| ProviderError::Other("reached".into()));
+        let provider = FallbackProvider::new(vec![
+            (
+                Box::new(BedrockProvider),
+                StreamConfig::new("bedrock-model", "key-a"),
+            ),
+            (backup, StreamConfig::new("b", "key-b")),
+        ]);
+
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut config = StreamConfig::new("request-model", "request-key");
+        config.response_format = ResponseFormat::JsonObject;
+        let result = provider
+            .stream(config, tx, tokio_util::sync::CancellationToken::new())
+            .await;
+
+        // Bedrock can't do JSON mode, so the backup is the only one called.
+        assert!(matches!(result, Err(ProviderError::Other(msg)) if msg == "reached"));
+        assert_eq!(backup_seen.lock().unwrap().len(), 1);
+
+        let only_bedrock = FallbackProvider::new(vec![(
+            Box::new(BedrockProvider),
+            StreamConfig::new("bedrock-model", "key-a"),
+        )]);
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut config = StreamConfig::new("request-model", "request-key");
+        config.response_format = ResponseFormat::JsonObject;
+        let result = only_bedrock
+            .stream(config, tx, tokio_util::sync::CancellationToken::new())
+            .await;
+        assert!(
+            matches!(&result, Err(ProviderError::Other(msg)) if msg.contains("does not support json_mode")),
+            "got {result:?}"
+        );
+    }
+
+    #[test]
+    fn test_capabilities_are_the_union_of_backends() {
+        use crate::provider::{AnthropicProvider, BedrockProvider};
+
+        let provider = FallbackProvider::new(vec![
+            (Box::new(BedrockProvider), StreamConfig::new("a", "key-a")),
+            (Box::new(AnthropicProvider), StreamConfig::new("b", "key-b")),
+        ]);
+        let caps = provider.capabilities();
+        assert!(caps.supports_json_mode);
+        assert_eq!(caps.max_context_tokens, Some(200_000));
+
+        let bedrock_only = FallbackProvider::new(vec![(
+            Box::new(BedrockProvider),
+            StreamConfig::new("a", "key-a"),
+        )]);
+        assert!(!bedrock_only.capabilities().supports_json_mode);
+    }
+}
@@ -6,6 +6,7 @@
 use super::traits::*;
 use crate::types::*;
 use async_trait::async_trait;
+use base64::Engine;
 use futures::StreamExt;
 use serde::Deserialize;
 use tokio::sync::mpsc;
@@ -19,6 +20,14 @@ impl StreamProvider for GoogleProvider {
         Some(crate::provider::ApiProtocol::GoogleGenerativeAi)
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_server_tools: false,
+            max_context_tokens: Some(1_048_576),
+            ..ProviderCapabilities::default()
+        }
+    }
+
     async fn stream(
         &self,
         config: StreamConfig,
@@ -56,188 +65,217 @@ impl StreamProvider for GoogleProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::classify(
+            return Err(ProviderError::classify_with_headers(
                 status.as_u16(),
                 &format!("Google API error {}: {}", status, body),
+                &headers,
             ));
         }
 
-        let mut content: Vec<Content> = Vec::new();
-        let mut usage = Usage::default();
-        let mut stop_reason = StopReason::Stop;
-        let mut error_message: Option<String> = None;
+        parse_google_sse_response(response, &config, &model_config.provider, tx, cancel).await
+    }
+}
 
-        let _ = tx.send(StreamEvent::Start);
+/// Parse a Gemini-format SSE response stream into an assistant message.
+///
+/// Shared by [`GoogleProvider`] and
+/// [`GoogleVertexProvider`](super::GoogleVertexProvider): both endpoints
+/// stream the same `streamGenerateContent?alt=sse` payloads and differ only
+/// in URL and auth.
+pub(super) async fn parse_google_sse_response(
+    response: reqwest::Response,
+    config: &StreamConfig,
+    provider_name: &str,
+    tx: mpsc::UnboundedSender<StreamEvent>,
+    cancel: tokio_util::sync::CancellationToken,
+) -> Result<Message, ProviderError> {
+    let mut content: Vec<Content> = Vec::new();
+    let mut usage = Usage::default();
+    let mut stop_reason = StopReason::Stop;
+    let mut error_message: Option<String> = None;
+
+    let _ = tx.send(StreamEvent::Start);
+
+    // Parse SSE stream
+    let mut stream = response.bytes_stream();
+    let mut buffer = String::new();
+
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                return Err(ProviderError::Cancelled);
+            }
+            chunk = stream.next() => {
+                match chunk {
+                    None => break,
+                    Some(Err(e)) => {
+                        // Match the other providers: a transport failure is an
+                        // error (and retryable), not a silently truncated turn.
+                        let provider_err = ProviderError::Network(e.to_string());
+                        warn!("Google stream error: {}", provider_err);
+                        return Err(provider_err);
+                    }
+                    Some(Ok(bytes)) => {
+                        buffer.push_str(&String::from_utf8_lossy(&bytes));
 
-        // Parse SSE stream
-        let mut stream = response.bytes_stream();
-        let mut buffer = String::new();
+                        // Process complete SSE events (handle both \n\n and \r\n\r\n)
+                        while let Some(data) = next_sse_data(&mut buffer) {
+                            if data.is_empty() {
+                                continue;
+                            }
 
-        loop {
-            tokio::select! {
-                _ = cancel.cancelled() => {
-                    return Err(ProviderError::Cancelled);
-                }
-                chunk = stream.next() => {
-                    match chunk {
-                        None => break,
-                        Some(Err(e)) => {
-                            // Match the other providers: a transport failure is an
-                            // error (and retryable), not a silently truncated turn.
-                            let provider_err = ProviderError::Network(e.to_string());
-                            warn!("Google stream error: {}", provider_err);
-                            return Err(provider_err);
-                        }
-                        Some(Ok(bytes)) => {
-                            buffer.push_str(&String::from_utf8_lossy(&bytes));
+                            // Google reports mid-stream failures as
+                            // {"error": {...}} payloads, which would otherwise
+                            // deserialize into an empty chunk and vanish.
+                            if is_error_payload(&data) {
+                                let provider_err = classify_sse_error_event(&data);
+                                warn!("Google in-stream error: {}", provider_err);
+                                return Err(provider_err);
+                            }
 
-                            // Process complete SSE events (handle both \n\n and \r\n\r\n)
-                            while let Some(data) = next_sse_data(&mut buffer) {
-                                if data.is_empty() {
+                            let chunk: GoogleChunk = match serde_json::from_str(&data) {
+                                Ok(c) => c,
+                                Err(e) => {
+                                    warn!("Failed to parse Google chunk: {}", e);
                                     continue;
                                 }
-
-                                // Google reports mid-stream failures as
-                                // {"error": {...}} payloads, which would otherwise
-                                // deserialize into an empty chunk and vanish.
-                                if is_error_payload(&data) {
-                                    let provider_err = classify_sse_error_event(&data);
-                                    warn!("Google in-stream error: {}", provider_err);
-                                    return Err(provider_err);
-                                }
-
-                                let chunk: GoogleChunk = match serde_json::from_str(&data) {
-                                    Ok(c) => c,
-                                    Err(e) => {
-                                        warn!("Failed to parse Google chunk: {}", e);
-                                        continue;
-                                    }
-                                };
-
-                                // Process candidates
-                                for candidate in &chunk.candidates.unwrap_or_default() {
-                                    if let Some(c) = &candidate.content {
-                                        for part in &c.parts {
-                                            if let Some(text) = part_text(part) {
-                                                if part.thought.unwrap_or(false) {
-                                                    // Thought summary part → Thinking content.
-                                                    let think_idx = content.iter().position(|c| matches!(c, Content::Thinking { .. }));
-                                                    let idx = match think_idx {
-                                                        Some(i) => i,
-                                                        None => {
-                                                            content.push(Content::thinking(String::new()));
-                                                            content.len() - 1
-                                                        }
-                                                    };
-                                                    if let Some(Content::Thinking { thinking, .. }) = content.get_mut(idx) {
-                                                        thinking.push_str(text);
-                                                    }
-                                                    let _ = tx.send(StreamEvent::ThinkingDelta {
-                                                        content_index: idx,
-                                                        delta: text.to_string(),
-                                                    });
-                                                    continue;
-                                                }
-                                                let text_idx = content.iter().position(|c| matches!(c, Content::Text { .. }));
-                                                let idx = match text_idx {
+                            };
+
+                            // Only candidate 0 is surfaced: with
+                            // candidateCount > 1, other candidates are
+                            // alternatives, not continuations, and would
+                            // otherwise interleave into one message. A chunk
+                            // may carry only a later candidate, so match on
+                            // `index` (omitted when there's just one).
+                            for candidate in chunk
+                                .candidates
+                                .unwrap_or_default()
+                                .iter()
+                                .filter(|c| c.index.unwrap_or(0) == 0)
+                                .take(1)
+                            {
+                                if let Some(c) = &candidate.content {
+                                    for part in &c.parts {
+                                        if let Some(text) = part_text(part) {
+                                            if part.thought.unwrap_or(false) {
+                                                // Thought summary part → Thinking content.
+                                                let think_idx = content.iter().position(|c| matches!(c, Content::Thinking { .. }));
+                                                let idx = match think_idx {
                                                     Some(i) => i,
                                                     None => {
-                                                        content.push(Content::Text { text: String::new() });
+                                                        content.push(Content::thinking(String::new()));
                                                         content.len() - 1
                                                     }
                                                 };
-                                                if let Some(Content::Text { text: t }) = content.get_mut(idx) {
-                                                    t.push_str(text);
+                                                if let Some(Content::Thinking { thinking, .. }) = content.get_mut(idx) {
+                                                    thinking.push_str(text);
                                                 }
-                                                let _ = tx.send(StreamEvent::TextDelta {
+                                                let _ = tx.send(StreamEvent::ThinkingDelta {
                                                     content_index: idx,
                                                     delta: text.to_string(),
                                                 });
+                                                continue;
                                             }
-                                            if let Some(fc) = &part.function_call {
-                                                let id = fc.id.clone().unwrap_or_else(|| format!("google-fc-{}", content.len()));
-                                                let args = fc.args.clone().unwrap_or(serde_json::Value::Object(Default::default()));
-                                                let metadata = part.thought_signature.as_ref().map(|sig| {
-                                                    serde_json::json!({"thought_signature": sig})
-                                                });
-                                                let idx = content.len();
-                                                content.push(Content::ToolCall {
-                                                    id: id.clone(),
-                                                    name: fc.name.clone(),
-                                                    arguments: args,
-                                                    provider_metadata: metadata,
-                                                });
-                                                let _ = tx.send(StreamEvent::ToolCallStart {
-                                                    content_index: idx,
-                                                    id,
-                                                    name: fc.name.clone(),
-                                                });
-                                                let _ = tx.send(StreamEvent::ToolCallEnd { content_index: idx });
-                                                stop_reason = StopReason::ToolUse;
-                                            }
-                                        }
-                                    }
-                                    if let Some(reason) = &candidate.finish_reason {
-                                        // Don't override ToolUse -- Gemini returns "STOP"
-                                        // even when it emits function calls
-                                        if stop_reason != StopReason::ToolUse {
-                                            stop_reason = match reason.as_str() {
-                                                "STOP" => StopReason::Stop,
-                                                "MAX_TOKENS" | "RECITATION" => StopReason::Length,
-                                                "SAFETY" | "PROHIBITED_CONTENT" | "BLOCKLIST"
-                                                | "SPII" => {
-                                                    warn!(
-                                                        "Gemini blocked the response (finishReason={})",
-                                                        reason
-                                                    );
-                                                    error_message = Some(format!(
-                                                        "Response blocked by Gemini safety filters (finishReason: {})",
-                                                        reason
-                                                    ));
-                                                    StopReason::Refusal
+                                            let text_idx = content.iter().position(|c| matches!(c, Content::Text { .. }));
+                                            let idx = match text_idx {
+                                                Some(i) => i,
+                                                None => {
+                                                    content.push(Content::Text { text: String::new() });
+                                                    content.len() - 1
                                                 }
-                                                _ => StopReason::Stop,
                                             };
+                                            if let Some(Content::Text { text: t }) = content.get_mut(idx) {
+                                                t.push_str(text);
+                                            }
+                                            let _ = tx.send(StreamEvent::TextDelta {
+                                                content_index: idx,
+                                                delta: text.to_string(),
+                                            });
+                                        }
+                                        if let Some(fc) = &part.function_call {
+                                            let id = fc.id.clone().unwrap_or_else(|| format!("google-fc-{}", content.len()));
+                                            let args = fc.args.clone().unwrap_or(serde_json::Value::Object(Default::default()));
+                                            let metadata = part.thought_signature.as_ref().map(|sig| {
+                                                serde_json::json!({"thought_signature": sig})
+                                            });
+                                            let idx = content.len();
+                                            content.push(Content::ToolCall {
+                                                id: id.clone(),
+                                                name: fc.name.clone(),
+                                                arguments: args,
+                                                provider_metadata: metadata,
+                                            });
+                                            let _ = tx.send(StreamEvent::ToolCallStart {
+                                                content_index: idx,
+                                                id,
+                                                name: fc.name.clone(),
+                                            });
+                                            let _ = tx.send(StreamEvent::ToolCallEnd { content_index: idx });
+                                            stop_reason = StopReason::ToolUse;
                                         }
                                     }
                                 }
-
-                                // Process usage
-                                if let Some(u) = &chunk.usage_metadata {
-                                    // promptTokenCount includes cached tokens;
-                                    // keep `input` as the uncached remainder so
-                                    // downstream sums don't double-count.
-                                    usage.input = u
-                                        .prompt_token_count
-                                        .unwrap_or(0)
-                                        .saturating_sub(u.cached_content_token_count.unwrap_or(0));
-                                    usage.output = u.candidates_token_count.unwrap_or(0);
-                                    usage.total_tokens = u.total_token_count.unwrap_or(0);
-                                    usage.cache_read = u.cached_content_token_count.unwrap_or(0);
+                                if let Some(reason) = &candidate.finish_reason {
+                                    // Don't override ToolUse -- Gemini returns "STOP"
+                                    // even when it emits function calls
+                                    if stop_reason != StopReason::ToolUse {
+                                        stop_reason = match reason.as_str() {
+                                            "STOP" => StopReason::Stop,
+                                            "MAX_TOKENS" | "RECITATION" => StopReason::Length,
+                                            "SAFETY" | "PROHIBITED_CONTENT" | "BLOCKLIST"
+                                            | "SPII" => {
+                                                warn!(
+                                                    "Gemini blocked the response (finishReason={})",
+                                                    reason
+                                                );
+                                                error_message = Some(format!(
+                                                    "Response blocked by Gemini safety filters (finishReason: {})",
+                                                    reason
+                                                ));
+                                                StopReason::Refusal
+                                            }
+                                            _ => StopReason::Stop,
+                                        };
+                                    }
                                 }
                             }
+
+                            // Process usage
+                            if let Some(u) = &chunk.usage_metadata {
+                                // promptTokenCount includes cached tokens;
+                                // keep `input` as the uncached remainder so
+                                // downstream sums don't double-count.
+                                usage.input = u
+                                    .prompt_token_count
+                                    .unwrap_or(0)
+                                    .saturating_sub(u.cached_content_token_count.unwrap_or(0));
+                                usage.output = u.candidates_token_count.unwrap_or(0);
+                                usage.total_tokens = u.total_token_count.unwrap_or(0);
+                                usage.cache_read = u.cached_content_token_count.unwrap_or(0);
+                            }
                         }
                     }
                 }
             }
         }
+    }
 
-        let message = Message::Assistant {
-            content,
-            stop_reason,
-            model: config.model.clone(),
-            provider: model_config.provider.clone(),
-            usage,
-            timestamp: now_ms(),
-            error_message,
-        };
+    let message = Message::Assistant {
+        content,
+        stop_reason,
+        model: config.model.clone(),
+        provider: provider_name.to_string(),
+        usage,
+        timestamp: now_ms(),
+        error_message,
+    };
 
-        let _ = tx.send(StreamEvent::Done {
-            message: message.clone(),
-        });
-        Ok(message)
-    }
+    let _ = tx.send(StreamEvent::Done {
+        message: message.clone(),
+    });
+    Ok(message)
 }
 
 /// Pop the next complete SSE event from `buffer` and return its `data:`
@@ -365,11 +403,16 @@ fn build_request_body(config: &StreamConfig) -> serde_json::Value {
     if let Some(temp) = config.temperature {
         generation_config["temperature"] = serde_json::json!(temp);
     }
+    if let Some(stop) = &config.stop_sequences {
+        generation_config["stopSequences"] = serde_json::json!(stop);
+    }
     // Structured outputs: native responseSchema (Gemini's OpenAPI-style
     // schema dialect — pass the caller's schema through as given).
     if let Some(schema) = &config.output_schema {
         generation_config["responseMimeType"] = serde_json::json!("application/json");
         generation_config["responseSchema"] = schema.schema.clone();
+    } else if config.response_format == ResponseFormat::JsonObject {
+        generation_config["responseMimeType"] = serde_json::json!("application/json");
     }
 
     // Thinking: Gemini 2.5's thinkingConfig. Budget scales with the level;
@@ -400,11 +443,29 @@ fn build_request_body(config: &StreamConfig) -> serde_json::Value {
         body["tools"] = serde_json::json!([{
             "functionDeclarations": declarations,
         }]);
+        if let Some(tool_config) = gemini_tool_config(&config.tool_choice) {
+            body["toolConfig"] = tool_config;
+        }
     }
 
     body
 }
 
+/// Gemini `toolConfig` for a [`ToolChoice`] (`None` for `Auto`, the API
+/// default). Shared with the Vertex AI provider.
+pub(super) fn gemini_tool_config(choice: &ToolChoice) -> Option<serde_json::Value> {
+    let config = match choice {
+        ToolChoice::Auto => return None,
+        ToolChoice::None => serde_json::json!({"mode": "NONE"}),
+        ToolChoice::Required => serde_json::json!({"mode": "ANY"}),
+        ToolChoice::Specific(name) => serde_json::json!({
+            "mode": "ANY",
+            "allowedFunctionNames": [name],
+        }),
+    };
+    Some(serde_json::json!({ "functionCallingConfig": config }))
+}
+
 fn content_to_google_parts(content: &[Content]) -> Vec<serde_json::Value> {
     content
         .iter()
@@ -414,6 +475,17 @@ fn content_to_google_parts(content: &[Content]) -> Vec<serde_json::Value> {
             Content::Image { data, mime_type } => Some(serde_json::json!({
                 "inlineData": {"mimeType": mime_type, "data": data},
             })),
+            Content::Document {
+                data, mime_type, ..
+            } => Some(serde_json::json!({
+                "inlineData": {
+                    "mimeType": mime_type,
+                    "data": base64::engine::general_purpose::STANDARD.encode(data),
+                },
+            })),
+            Content::DocumentUrl { url, mime_type } => Some(serde_json::json!({
+                "fileData": {"mimeType": mime_type, "fileUri": url},
+            })),
             Content::ToolCall {
                 id,
                 name,
@@ -434,7 +506,10 @@ fn content_to_google_parts(content: &[Content]) -> Vec<serde_json::Value> {
                 }
                 Some(part)
             }
-            Content::Thinking { .. } => None,
+            // Server tools are Anthropic-only; their blocks can't be replayed here.
+            Content::Thinking { .. }
+            | Content::ServerToolUse { .. }
+            | Content::ServerToolResult { .. } => None,
         })
         .collect()
 }
@@ -452,6 +527,8 @@ struct GoogleChunk {
 struct GoogleCandidate {
     #[serde(default)]
     content: Option<GoogleContent>,
+    #[serde(default)]
+    index: Option<u32>,
     #[serde(default, rename = "finishReason")]
     finish_reason: Option<String>,
 }
@@ -526,6 +603,64 @@ mod tests {
         assert_eq!(parts[0]["text"], "answer");
     }
 
+    #[test]
+    fn stop_sequences_go_in_generation_config() {
+        let mut config = StreamConfig::new("gemini-2.5-pro", "k");
+        config.messages = vec![Message::user("hi")];
+        config.stop_sequences = Some(vec!["END".into()]);
+        let body = build_request_body(&config);
+        assert_eq!(
+            body["generationConfig"]["stopSequences"],
+            serde_json::json!(["END"])
+        );
+    }
+
+    #[test]
+    fn json_mode_sets_response_mime_type() {
+        let mut config = StreamConfig::new("gemini-2.5-pro", "k");
+        config.messages = vec![Message::user("hi")];
+        config.response_format = ResponseFormat::JsonObject;
+        let body = build_request_body(&config);
+        assert_eq!(
+            body["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+        assert!(body["generationConfig"]["responseSchema"].is_null());
+    }
+
+    #[test]
+    fn tool_choice_sets_function_calling_config() {
+        let mut config = StreamConfig::new("gemini-2.5-pro", "k");
+        config.messages = vec![Message::user("classify")];
+        config.tools = vec![ToolDefinition {
+            name: "classify".into(),
+            description: "Pick a label".into(),
+            parameters: serde_json::json!({"type": "object"}),
+        }];
+        assert!(build_request_body(&config).get("toolConfig").is_none());
+
+        config.tool_choice = ToolChoice::None;
+        assert_eq!(
+            build_request_body(&config)["toolConfig"],
+            serde_json::json!({"functionCallingConfig": {"mode": "NONE"}})
+        );
+
+        config.tool_choice = ToolChoice::Required;
+        assert_eq!(
+            build_request_body(&config)["toolConfig"],
+            serde_json::json!({"functionCallingConfig": {"mode": "ANY"}})
+        );
+
+        config.tool_choice = ToolChoice::Specific("classify".into());
+        assert_eq!(
+            build_request_body(&config)["toolConfig"],
+            serde_json::json!({"functionCallingConfig": {
+                "mode": "ANY",
+                "allowedFunctionNames": ["classify"],
+            }})
+        );
+    }
+
     #[test]
     fn thinking_level_sets_thinking_config() {
         let config = StreamConfig {
@@ -540,6 +675,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
         let body = build_request_body(&config);
         assert_eq!(
@@ -566,6 +705,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
         let body = build_request_body(&config);
         assert!(body["generationConfig"]["thinkingConfig"].is_null());
@@ -588,6 +731,10 @@ mod tests {
                 "structured_output",
                 serde_json::json!({"type": "object", "properties": {"x": {"type": "number"}}}),
             )),
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
         let body = build_request_body(&config);
         assert_eq!(
@@ -614,6 +761,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config);
@@ -820,6 +971,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config);
@@ -868,6 +1023,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config);
@@ -31,6 +31,14 @@ impl StreamProvider for GoogleVertexProvider {
         Some(crate::provider::ApiProtocol::GoogleVertex)
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_server_tools: false,
+            max_context_tokens: Some(1_048_576),
+            ..ProviderCapabilities::default()
+        }
+    }
+
     async fn stream(
         &self,
         config: StreamConfig,
@@ -81,243 +89,27 @@ impl StreamProvider for GoogleVertexProvider {
 
         if !response.status().is_success() {
             let status = response.status();
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            return Err(ProviderError::classify(
+            return Err(ProviderError::classify_with_headers(
                 status.as_u16(),
                 &format!("Vertex AI error {}: {}", status, body),
+                &headers,
             ));
         }
 
-        // Delegate SSE parsing to the Google provider's streaming logic.
-        // Since the response format is identical, we reuse GoogleProvider.
-        // However, we already have the response, so we'll parse it inline.
-        // For simplicity, delegate fully to GoogleProvider with modified config.
-        let mut modified_config = config.clone();
-        modified_config.model_config = Some(vertex_model);
-
-        // Actually, let's just delegate to GoogleProvider. The key difference
-        // is auth (Bearer vs API key in URL). We handle that by using a modified
-        // model config. But GoogleProvider builds its own URL... so let's just
-        // use GoogleProvider with a trick: empty api_key and auth in headers.
-        // We can't easily reuse GoogleProvider because it constructs its own URL.
-        // Instead, parse the SSE response directly (same format as Google GenAI).
-        parse_google_sse_response(response, &config, &model_config.provider, tx, cancel).await
-    }
-}
-
-/// Parse a Google-format SSE response stream. Shared between Google and Vertex.
-async fn parse_google_sse_response(
-    response: reqwest::Response,
-    config: &StreamConfig,
-    provider_name: &str,
-    tx: mpsc::UnboundedSender<StreamEvent>,
-    cancel: tokio_util::sync::CancellationToken,
-) -> Result<Message, ProviderError> {
-    use futures::StreamExt;
-    use serde::Deserialize;
-    use tracing::{debug, warn};
-
-    let mut content: Vec<Content> = Vec::new();
-    let mut usage = Usage::default();
-    let mut stop_reason = StopReason::Stop;
-
-    let _ = tx.send(StreamEvent::Start);
-
-    let mut stream = response.bytes_stream();
-    let mut buffer = String::new();
-
-    loop {
-        tokio::select! {
-            _ = cancel.cancelled() => {
-                return Err(ProviderError::Cancelled);
-            }
-            chunk = stream.next() => {
-                match chunk {
-                    None => break,
-                    Some(Err(e)) => {
-                        warn!("Vertex stream error: {}", e);
-                        break;
-                    }
-                    Some(Ok(bytes)) => {
-                        buffer.push_str(&String::from_utf8_lossy(&bytes));
-
-                        while let Some(pos) = buffer.find("\n\n") {
-                            let event_str = buffer[..pos].to_string();
-                            buffer = buffer[pos + 2..].to_string();
-
-                            let data = event_str
-                                .lines()
-                                .find(|l| l.starts_with("data: "))
-                                .map(|l| &l[6..])
-                                .unwrap_or("");
-
-                            if data.is_empty() {
-                                continue;
-                            }
-
-                            #[derive(Deserialize)]
-                            struct Chunk {
-                                #[serde(default)]
-                                candidates: Option<Vec<Candidate>>,
-                                #[serde(default, rename = "usageMetadata")]
-                                usage_metadata: Option<UsageMeta>,
-                            }
-                            #[derive(Deserialize)]
-                            struct Candidate {
-                                #[serde(default)]
-                                content: Option<CContent>,
-                                #[serde(default, rename = "finishReason")]
-                                finish_reason: Option<String>,
-                            }
-                            #[derive(Deserialize)]
-                            struct CContent {
-                                #[serde(default)]
-                                parts: Vec<Part>,
-                            }
-                            #[derive(Deserialize)]
-                            struct Part {
-                                #[serde(default)]
-                                text: Option<String>,
-                                #[serde(default)]
-                                thought: Option<bool>,
-                                #[serde(default, rename = "functionCall")]
-                                function_call: Option<FCall>,
-                                #[serde(default, rename = "thoughtSignature")]
-                                thought_signature: Option<String>,
-                            }
-                            #[derive(Deserialize)]
-                            struct FCall {
-                                name: String,
-                                #[serde(default)]
-                                args: Option<serde_json::Value>,
-                            }
-                            #[derive(Deserialize)]
-                            struct UsageMeta {
-                                #[serde(default, rename = "promptTokenCount")]
-                                prompt_token_count: Option<u64>,
-                                #[serde(default, rename = "candidatesTokenCount")]
-                                candidates_token_count: Option<u64>,
-                                #[serde(default, rename = "totalTokenCount")]
-                                total_token_count: Option<u64>,
-                                #[serde(default, rename = "cachedContentTokenCount")]
-                                cached_content_token_count: Option<u64>,
-                            }
-
-                            let parsed: Chunk = match serde_json::from_str(data) {
-                                Ok(c) => c,
-                                Err(e) => {
-                                    debug!("Failed to parse Vertex chunk: {}", e);
-                                    continue;
-                                }
-                            };
-
-                            for candidate in parsed.candidates.unwrap_or_default() {
-                                if let Some(c) = candidate.content {
-                                    for part in c.parts {
-                                        if let Some(text) = part.text {
-                                            if part.thought.unwrap_or(false) {
-                                                let think_idx = content.iter().position(|c| matches!(c, Content::Thinking { .. }));
-                                                let idx = match think_idx {
-                                                    Some(i) => i,
-                                                    None => {
-                                                        content.push(Content::thinking(String::new()));
-                                                        content.len() - 1
-                                                    }
-                                                };
-                                                if let Some(Content::Thinking { thinking, .. }) = content.get_mut(idx) {
-                                                    thinking.push_str(&text);
-                                                }
-                                                let _ = tx.send(StreamEvent::ThinkingDelta {
-                                                    content_index: idx,
-                                                    delta: text,
-                                                });
-                                                continue;
-                                            }
-                                            let idx = content.iter().position(|c| matches!(c, Content::Text { .. }));
-                                            let idx = match idx {
-                                                Some(i) => i,
-                                                None => {
-                                                    content.push(Content::Text { text: String::new() });
-                                                    content.len() - 1
-                                                }
-                                            };
-                                            if let Some(Content::Text { text: t }) = content.get_mut(idx) {
-                                                t.push_str(&text);
-                                            }
-                                            let _ = tx.send(StreamEvent::TextDelta {
-                                                content_index: idx,
-                                                delta: text,
-                                            });
-                                        }
-                                        if let Some(fc) = part.function_call {
-                                            let id = format!("vertex-fc-{}", content.len());
-                                            let args = fc.args.unwrap_or(serde_json::Value::Object(Default::default()));
-                                            // Gemini thinking conversations require the
-                                            // thought signature replayed on function calls.
-                                            let metadata = part.thought_signature.as_ref().map(|sig| {
-                                                serde_json::json!({"thought_signature": sig})
-                                            });
-                                            let idx = content.len();
-                                            content.push(Content::ToolCall { provider_metadata: metadata,
-                                                id: id.clone(),
-                                                name: fc.name.clone(),
-                                                arguments: args,
-                                            });
-                                            let _ = tx.send(StreamEvent::ToolCallStart {
-                                                content_index: idx,
-                                                id,
-                                                name: fc.name,
-                                            });
-                                            let _ = tx.send(StreamEvent::ToolCallEnd { content_index: idx });
-                                            stop_reason = StopReason::ToolUse;
-                                        }
-                                    }
-                                }
-                                if let Some(reason) = candidate.finish_reason {
-                                    stop_reason = match reason.as_str() {
-                                        "STOP" => StopReason::Stop,
-                                        "MAX_TOKENS" => StopReason::Length,
-                                        _ => StopReason::Stop,
-                                    };
-                                }
-                            }
-
-                            if let Some(u) = parsed.usage_metadata {
-                                // promptTokenCount includes cached tokens;
-                                // keep `input` as the uncached remainder so
-                                // downstream sums don't double-count.
-                                usage.input = u
-                                    .prompt_token_count
-                                    .unwrap_or(0)
-                                    .saturating_sub(u.cached_content_token_count.unwrap_or(0));
-                                usage.output = u.candidates_token_count.unwrap_or(0);
-                                usage.cache_read = u.cached_content_token_count.unwrap_or(0);
-                                usage.total_tokens = u.total_token_count.unwrap_or(0);
-                            }
-                        }
-                    }
-                }
-            }
-        }
+        // Same SSE payload format as the Gemini API — only URL and auth differ.
+        super::google::parse_google_sse_response(
+            response,
+            &config,
+            &model_config.provider,
+            tx,
+            cancel,
+        )
+        .await
     }
-
-    let message = Message::Assistant {
-        content,
-        stop_reason,
-        model: config.model.clone(),
-        provider: provider_name.to_string(),
-        usage,
-        timestamp: now_ms(),
-        error_message: None,
-    };
-
-    let _ = tx.send(StreamEvent::Done {
-        message: message.clone(),
-    });
-    Ok(message)
 }
 
-/// Build the request body for Vertex AI (same format as Google GenAI).
 /// Token budget for Vertex's thinkingConfig per level (same scale as Gemini).
 fn vertex_thinking_budget(level: ThinkingLevel) -> u32 {
     match level {
@@ -421,6 +213,12 @@ fn build_vertex_request_body(config: &StreamConfig) -> serde_json::Value {
     if let Some(temp) = config.temperature {
         gen_config["temperature"] = serde_json::json!(temp);
     }
+    if let Some(stop) = &config.stop_sequences {
+        gen_config["stopSequences"] = serde_json::json!(stop);
+    }
+    if config.response_format == ResponseFormat::JsonObject {
+        gen_config["responseMimeType"] = serde_json::json!("application/json");
+    }
     // Thinking: same thinkingConfig as the Gemini API.
     if config.thinking_level != ThinkingLevel::Off {
         gen_config["thinkingConfig"] = serde_json::json!({
@@ -445,6 +243,9 @@ fn build_vertex_request_body(config: &StreamConfig) -> serde_json::Value {
             })
             .collect();
         body["tools"] = serde_json::json!([{"functionDeclarations": declarations}]);
+        if let Some(tool_config) = super::google::gemini_tool_config(&config.tool_choice) {
+            body["toolConfig"] = tool_config;
+        }
     }
 
     body
@@ -467,6 +268,10 @@ mod tests {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         }
     }
 
@@ -479,7 +284,7 @@ mod tests {
             Message::user("go"),
             Message::assistant(
                 vec![Content::ToolCall {
-                    id: "vertex-fc-0".into(),
+                    id: "google-fc-0".into(),
                     name: "get_weather".into(),
                     arguments: serde_json::json!({"city": "Paris"}),
                     provider_metadata: Some(serde_json::json!({"thought_signature": "sig-9"})),
@@ -496,6 +301,34 @@ mod tests {
         assert_eq!(part["thoughtSignature"], "sig-9");
     }
 
+    #[test]
+    fn json_mode_sets_response_mime_type() {
+        let mut c = config(ThinkingLevel::Off);
+        c.response_format = ResponseFormat::JsonObject;
+        let body = build_vertex_request_body(&c);
+        assert_eq!(
+            body["generationConfig"]["responseMimeType"],
+            "application/json"
+        );
+    }
+
+    #[test]
+    fn tool_choice_sets_function_calling_config() {
+        let mut c = config(ThinkingLevel::Off);
+        c.tools = vec![ToolDefinition {
+            name: "classify".into(),
+            description: "Pick a label".into(),
+            parameters: serde_json::json!({"type": "object"}),
+        }];
+        c.tool_choice = ToolChoice::Specific("classify".into());
+        let body = build_vertex_request_body(&c);
+        assert_eq!(body["toolConfig"]["functionCallingConfig"]["mode"], "ANY");
+        assert_eq!(
+            body["toolConfig"]["functionCallingConfig"]["allowedFunctionNames"],
+            serde_json::json!(["classify"])
+        );
+    }
+
     #[test]
     fn thinking_level_sets_thinking_config() {
         let body = build_vertex_request_body(&config(ThinkingLevel::High));
@@ -0,0 +1,330 @@
+//! Provider middleware — hooks around every `stream()` call.
+//!
+//! [`MiddlewareProvider`] wraps any [`StreamProvider`] and runs a chain of
+//! [`ProviderMiddleware`] before and after each call: logging, latency
+//! metrics, fault injection in tests. Unlike a one-off wrapper such as
+//! [`RecordingProvider`](super::RecordingProvider), middleware compose —
+//! stack as many as you like around one provider.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use yoagent::provider::{
+//!     AnthropicProvider, LatencyMiddleware, LoggingMiddleware, MiddlewareProvider,
+//! };
+//!
+//! let latency = Arc::new(LatencyMiddleware::new());
+//! let provider = MiddlewareProvider::new(
+//!     Box::new(AnthropicProvider),
+//!     vec![Box::new(LoggingMiddleware), Box::new(latency.clone())],
+//! );
+//! // ... run the agent with `provider`, then:
+//! for (model, stats) in latency.stats() {
+//!     println!("{model}: {} calls, mean {:?}", stats.calls, stats.mean());
+//! }
+//! ```
+
+use super::traits::*;
+use crate::types::*;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+/// Hooks run by [`MiddlewareProvider`] around each provider call.
+///
+/// Both methods default to doing nothing, so implement only what you need.
+/// `after_stream` sees the same `config` that was passed to `before_stream`.
+/// It doesn't run if the call is dropped midway (e.g. by a turn timeout).
+#[async_trait]
+pub trait ProviderMiddleware: Send + Sync {
+    /// Called before the request goes to the provider.
+    async fn before_stream(&self, _config: &StreamConfig) {}
+
+    /// Called with the provider's result once the call finishes.
+    async fn after_stream(&self, _config: &StreamConfig, _result: &Result<Message, ProviderError>) {
+    }
+}
+
+#[async_trait]
+impl<T: ProviderMiddleware + ?Sized> ProviderMiddleware for Arc<T> {
+    async fn before_stream(&self, config: &StreamConfig) {
+        (**self).before_stream(config).await
+    }
+
+    async fn after_stream(&self, config: &StreamConfig, result: &Result<Message, ProviderError>) {
+        (**self).after_stream(config, result).await
+    }
+}
+
+/// A [`StreamProvider`] that runs middleware around an inner provider.
+///
+/// `before_stream` hooks run in order, `after_stream` hooks in reverse
+/// order, so the first middleware wraps all the others. Pass an
+/// `Arc<impl ProviderMiddleware>` to keep a handle on middleware that
+/// collects data, such as [`LatencyMiddleware`].
+pub struct MiddlewareProvider {
+    inner: Box<dyn StreamProvider>,
+    middleware: Vec<Box<dyn ProviderMiddleware>>,
+}
+
+impl MiddlewareProvider {
+    pub fn new(
+        inner: Box<dyn StreamProvider>,
+        middleware: Vec<Box<dyn ProviderMiddleware>>,
+    ) -> Self {
+        Self { inner, middleware }
+    }
+
+    /// Append a middleware; it runs innermost, after those already added.
+    pub fn with_middleware(mut self, middleware: impl ProviderMiddleware + 'static) -> Self {
+        self.middleware.push(Box::new(middleware));
+        self
+    }
+}
+
+#[async_trait]
+impl StreamProvider for MiddlewareProvider {
+    async fn stream(
+        &self,
+        config: StreamConfig,
+        tx: mpsc::UnboundedSender<StreamEvent>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<Message, ProviderError> {
+        for middleware in &self.middleware {
+            middleware.before_stream(&config).await;
+        }
+        let result = self.inner.stream(config.clone(), tx, cancel).await;
+        for middleware in self.middleware.iter().rev() {
+            middleware.after_stream(&config, &result).await;
+        }
+        result
+    }
+
+    fn protocol(&self) -> Option<crate::provider::ApiProtocol> {
+        self.inner.protocol()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+/// Logs each call with `tracing`: model and message count before, stop
+/// reason (or error) after.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMiddleware;
+
+#[async_trait]
+impl ProviderMiddleware for LoggingMiddleware {
+    async fn before_stream(&self, config: &StreamConfig) {
+        tracing::debug!(
+            model = %config.model,
+            messages = config.messages.len(),
+            "provider request"
+        );
+    }
+
+    async fn after_stream(&self, config: &StreamConfig, result: &Result<Message, ProviderError>) {
+        match result {
+            Ok(Message::Assistant { stop_reason, .. }) => {
+                tracing::debug!(model = %config.model, %stop_reason, "provider response")
+            }
+            Ok(_) => tracing::debug!(model = %config.model, "provider response"),
+            Err(e) => tracing::debug!(model = %config.model, error = %e, "provider error"),
+        }
+    }
+}
+
+/// Call latency for one model, as collected by [`LatencyMiddleware`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LatencyStats {
+    /// Finished calls, successful or not.
+    pub calls: u32,
+    /// How many of those returned an error.
+    pub errors: u32,
+    pub total: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl LatencyStats {
+    /// Average latency per call; zero before the first call.
+    pub fn mean(&self) -> Duration {
+        self.total.checked_div(self.calls).unwrap_or_default()
+    }
+
+    fn record(&mut self, latency: Duration, is_error: bool) {
+        self.min = if self.calls == 0 {
+            latency
+        } else {
+            self.min.min(latency)
+        };
+        self.max = self.max.max(latency);
+        self.total += latency;
+        self.calls += 1;
+        self.errors += is_error as u32;
+    }
+}
+
+/// Measures how long each provider call takes, per model
+/// (`StreamConfig::model`).
+///
+/// Latency runs from `before_stream` to `after_stream`, so it includes any
+/// middleware further in. Calls that are dropped midway aren't counted.
+#[derive(Debug, Default)]
+pub struct LatencyMiddleware {
+    /// Start times of calls in flight, keyed by the address of the call's
+    /// `StreamConfig` — the same for both hooks of one call and distinct
+    /// between concurrent calls.
+    started: Mutex<HashMap<usize, Instant>>,
+    stats: Mutex<HashMap<String, LatencyStats>>,
+}
+
+impl LatencyMiddleware {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Latency so far, by model.
+    pub fn stats(&self) -> HashMap<String, LatencyStats> {
+        self.stats.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+fn call_key(config: &StreamConfig) -> usize {
+    config as *const StreamConfig as usize
+}
+
+#[async_trait]
+impl ProviderMiddleware for LatencyMiddleware {
+    async fn before_stream(&self, config: &StreamConfig) {
+        self.started
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(call_key(config), Instant::now());
+    }
+
+    async fn after_stream(&self, config: &StreamConfig, result: &Result<Message, ProviderError>) {
+        let started = self
+            .started
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(&call_key(config));
+        let Some(started) = started else { return };
+        self.stats
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .entry(config.model.clone())
+            .or_default()
+            .record(started.elapsed(), result.is_err());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+
+    /// Records `"<name>:before"` / `"<name>:after"` into a shared log.
+    struct Tracer {
+        name: &'static str,
+        log: Arc<Mutex<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl ProviderMiddleware for Tracer {
+        async fn before_stream(&self, _config: &StreamConfig) {
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}:before", self.name));
+        }
+
+        async fn after_stream(
+            &self,
+            _config: &StreamConfig,
+            result: &Result<Message, ProviderError>,
+        ) {
+            let outcome = if result.is_ok() { "ok" } else { "err" };
+            self.log
+                .lock()
+                .unwrap()
+                .push(format!("{}:after:{}", self.name, outcome));
+        }
+    }
+
+    /// Always fails with a network error.
+    struct Down;
+
+    #[async_trait]
+    impl StreamProvider for Down {
+        async fn stream(
+            &self,
+            _config: StreamConfig,
+            _tx: mpsc::UnboundedSender<StreamEvent>,
+            _cancel: tokio_util::sync::CancellationToken,
+        ) -> Result<Message, ProviderError> {
+            Err(ProviderError::Network("connection reset".into()))
+        }
+    }
+
+    async fn call(provider: &MiddlewareProvider, model: &str) -> Result<Message, ProviderError> {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        provider
+            .stream(
+                StreamConfig::new(model, "key"),
+                tx,
+                tokio_util::sync::CancellationToken::new(),
+            )
+            .await
+    }
+
+    #[tokio::test]
+    async fn test_before_in_order_after_in_reverse() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let tracer = |name| Tracer {
+            name,
+            log: log.clone(),
+        };
+        let provider = MiddlewareProvider::new(
+            Box::new(MockProvider::text("hi")),
+            vec![Box::new(tracer("outer")), Box::new(LoggingMiddleware)],
+        )
+        .with_middleware(tracer("inner"));
+
+        assert!(call(&provider, "m").await.is_ok());
+        assert_eq!(
+            *log.lock().unwrap(),
+            [
+                "outer:before",
+                "inner:before",
+                "inner:after:ok",
+                "outer:after:ok"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_latency_is_recorded_per_model() {
+        let latency = Arc::new(LatencyMiddleware::new());
+        let ok = MiddlewareProvider::new(
+            Box::new(MockProvider::texts(vec!["a", "b"])),
+            vec![Box::new(latency.clone())],
+        );
+        let down = MiddlewareProvider::new(Box::new(Down), vec![Box::new(latency.clone())]);
+
+        call(&ok, "fast").await.unwrap();
+        call(&ok, "fast").await.unwrap();
+        assert!(call(&down, "flaky").await.is_err());
+
+        let stats = latency.stats();
+        assert_eq!(stats["fast"].calls, 2);
+        assert_eq!(stats["fast"].errors, 0);
+        assert!(stats["fast"].min <= stats["fast"].max);
+        assert!(stats["fast"].mean() <= stats["fast"].max);
+        assert_eq!(stats["flaky"].calls, 1);
+        assert_eq!(stats["flaky"].errors, 1);
+        assert!(latency.started.lock().unwrap().is_empty());
+    }
+}
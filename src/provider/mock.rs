@@ -1,15 +1,21 @@
 //! Mock provider for testing. No real API calls.
 
+use super::recording::RecordedCall;
 use super::traits::*;
 use crate::types::*;
 use async_trait::async_trait;
+use std::io;
+use std::path::Path;
 use tokio::sync::mpsc;
 
-/// A mock response: either plain text or tool calls
+/// A mock response: plain text, tool calls, or a call captured by
+/// [`RecordingProvider`](super::RecordingProvider)
 #[derive(Debug, Clone)]
 pub enum MockResponse {
     Text(String),
     ToolCalls(Vec<MockToolCall>),
+    /// Re-send the recorded stream events and return the recorded message.
+    Recorded(Box<RecordedCall>),
 }
 
 #[derive(Debug, Clone)]
@@ -23,15 +29,36 @@ pub struct MockToolCall {
 /// Mock LLM provider for tests. Supply a sequence of responses.
 pub struct MockProvider {
     responses: std::sync::Mutex<Vec<MockResponse>>,
+    recorded_timing: bool,
 }
 
 impl MockProvider {
     pub fn new(responses: Vec<MockResponse>) -> Self {
         Self {
             responses: std::sync::Mutex::new(responses),
+            recorded_timing: false,
         }
     }
 
+    /// Replay a file written by [`RecordingProvider`](super::RecordingProvider),
+    /// one recorded call per request, in order.
+    pub fn from_recording(path: impl AsRef<Path>) -> io::Result<Self> {
+        let calls = super::recording::read_recording(path)?;
+        Ok(Self::new(
+            calls
+                .into_iter()
+                .map(|call| MockResponse::Recorded(Box::new(call)))
+                .collect(),
+        ))
+    }
+
+    /// Send recorded events at the pace they were recorded instead of all
+    /// at once. Off by default.
+    pub fn with_recorded_timing(mut self, enabled: bool) -> Self {
+        self.recorded_timing = enabled;
+        self
+    }
+
     /// Convenience: provider that always returns the same text
     pub fn text(text: impl Into<String>) -> Self {
         Self::new(vec![MockResponse::Text(text.into())])
@@ -69,6 +96,10 @@ impl StreamProvider for MockProvider {
             return Err(ProviderError::Cancelled);
         }
 
+        if let MockResponse::Recorded(call) = response {
+            return self.replay(*call, tx, cancel).await;
+        }
+
         let _ = tx.send(StreamEvent::Start);
 
         let message = match response {
@@ -118,6 +149,7 @@ impl StreamProvider for MockProvider {
                     error_message: None,
                 }
             }
+            MockResponse::Recorded(_) => unreachable!("replayed above"),
         };
 
         let _ = tx.send(StreamEvent::Done {
@@ -126,3 +158,25 @@ impl StreamProvider for MockProvider {
         Ok(message)
     }
 }
+
+impl MockProvider {
+    async fn replay(
+        &self,
+        call: RecordedCall,
+        tx: mpsc::UnboundedSender<StreamEvent>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<Message, ProviderError> {
+        let started = tokio::time::Instant::now();
+        for recorded in call.events {
+            if self.recorded_timing {
+                let at = started + std::time::Duration::from_millis(recorded.at_ms);
+                tokio::select! {
+                    _ = cancel.cancelled() => return Err(ProviderError::Cancelled),
+                    _ = tokio::time::sleep_until(at) => {}
+                }
+            }
+            let _ = tx.send(recorded.event);
+        }
+        Ok(call.message)
+    }
+}
@@ -1,12 +1,16 @@
 pub mod anthropic;
 pub mod azure_openai;
 pub mod bedrock;
+pub mod cohere;
+pub mod fallback;
 pub mod google;
 pub mod google_vertex;
+pub mod middleware;
 pub mod mock;
 pub mod model;
 pub mod openai_compat;
 pub mod openai_responses;
+pub mod recording;
 pub mod registry;
 pub mod sse;
 pub mod traits;
@@ -14,12 +18,18 @@ pub mod traits;
 pub use anthropic::AnthropicProvider;
 pub use azure_openai::AzureOpenAiProvider;
 pub use bedrock::BedrockProvider;
+pub use cohere::CohereProvider;
+pub use fallback::FallbackProvider;
 pub use google::GoogleProvider;
 pub use google_vertex::GoogleVertexProvider;
+pub use middleware::{
+    LatencyMiddleware, LatencyStats, LoggingMiddleware, MiddlewareProvider, ProviderMiddleware,
+};
 pub use mock::MockProvider;
 pub use model::{AnthropicCompat, ApiProtocol, CostConfig, ModelConfig, OpenAiCompat};
 pub use openai_compat::OpenAiCompatProvider;
 pub use openai_responses::OpenAiResponsesProvider;
+pub use recording::{RecordedCall, RecordedEvent, RecordingProvider};
 pub(crate) use registry::resolve_api_key_or_warn;
 pub use registry::{resolve_api_key, ProviderRegistry};
 pub use traits::*;
@@ -14,6 +14,7 @@ pub enum ApiProtocol {
     GoogleGenerativeAi,
     GoogleVertex,
     BedrockConverseStream,
+    CohereChat,
 }
 
 impl std::fmt::Display for ApiProtocol {
@@ -26,6 +27,7 @@ impl std::fmt::Display for ApiProtocol {
             Self::GoogleGenerativeAi => write!(f, "google_generative_ai"),
             Self::GoogleVertex => write!(f, "google_vertex"),
             Self::BedrockConverseStream => write!(f, "bedrock_converse_stream"),
+            Self::CohereChat => write!(f, "cohere_chat"),
         }
     }
 }
@@ -62,6 +64,54 @@ impl CostConfig {
             + usage.cache_write as f64 * self.cache_write_per_million)
             / 1_000_000.0
     }
+
+    /// Published list pricing for a known model id, or `None` if unknown.
+    ///
+    /// Matches the longest known id that `model` starts with, so dated
+    /// snapshots (`claude-sonnet-4-20250514`, `gpt-4o-2024-08-06`) resolve to
+    /// their family. A `provider/` prefix (OpenRouter style) is ignored.
+    /// Preset models use the same rates as their [`ModelConfig`]
+    /// constructor. Prices drift; set [`ModelConfig::cost`] directly when you
+    /// need them exact.
+    pub fn for_model(model: &str) -> Option<Self> {
+        let id = model
+            .rsplit('/')
+            .next()
+            .unwrap_or(model)
+            .to_ascii_lowercase();
+        fn rates(input: f64, output: f64, cache_read: f64, cache_write: f64) -> CostConfig {
+            CostConfig {
+                input_per_million: input,
+                output_per_million: output,
+                cache_read_per_million: cache_read,
+                cache_write_per_million: cache_write,
+            }
+        }
+        let known = [
+            ("claude-fable-5", ModelConfig::claude_fable_5().cost),
+            ("claude-opus-5", ModelConfig::claude_opus_5().cost),
+            ("claude-opus-4-8", ModelConfig::claude_opus_4_8().cost),
+            ("claude-sonnet-5", ModelConfig::claude_sonnet_5().cost),
+            ("claude-haiku-4-5", ModelConfig::claude_haiku_4_5().cost),
+            ("gpt-5.5", ModelConfig::gpt_5_5().cost),
+            ("claude-sonnet-4", rates(3.0, 15.0, 0.3, 3.75)),
+            ("claude-3-7-sonnet", rates(3.0, 15.0, 0.3, 3.75)),
+            ("claude-3-5-sonnet", rates(3.0, 15.0, 0.3, 3.75)),
+            ("claude-3-5-haiku", rates(0.8, 4.0, 0.08, 1.0)),
+            ("gpt-4o", rates(2.5, 10.0, 1.25, 0.0)),
+            ("gpt-4o-mini", rates(0.15, 0.6, 0.075, 0.0)),
+            ("gpt-4.1", rates(2.0, 8.0, 0.5, 0.0)),
+            ("gpt-4.1-mini", rates(0.4, 1.6, 0.1, 0.0)),
+        ];
+        known
+            .into_iter()
+            .filter(|(prefix, _)| {
+                id.strip_prefix(*prefix)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('-'))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, cost)| cost)
+    }
 }
 
 impl Default for CostConfig {
@@ -118,8 +168,22 @@ pub struct OpenAiCompat {
     pub requires_assistant_after_tool_result: bool,
     /// How thinking/reasoning content is formatted in streaming.
     pub thinking_format: ThinkingFormat,
+    /// Azure OpenAI deployment name. When set, requests go to
+    /// `{base_url}/openai/deployments/{deployment}/chat/completions`, auth
+    /// uses the `api-key` header instead of `Bearer`, and the body omits
+    /// `model` (Azure infers it from the deployment).
+    #[serde(default)]
+    pub azure_deployment: Option<String>,
+    /// Azure `api-version` query parameter. Only used with
+    /// `azure_deployment`; defaults to [`AZURE_DEFAULT_API_VERSION`].
+    #[serde(default)]
+    pub api_version: Option<String>,
 }
 
+/// Azure OpenAI chat-completions API version used when
+/// [`OpenAiCompat::api_version`] is unset.
+pub const AZURE_DEFAULT_API_VERSION: &str = "2024-10-21";
+
 impl Default for OpenAiCompat {
     fn default() -> Self {
         Self {
@@ -132,6 +196,8 @@ impl Default for OpenAiCompat {
             requires_tool_result_name: false,
             requires_assistant_after_tool_result: false,
             thinking_format: ThinkingFormat::OpenAi,
+            azure_deployment: None,
+            api_version: None,
         }
     }
 }
@@ -149,6 +215,16 @@ impl OpenAiCompat {
         }
     }
 
+    /// Compat flags for an Azure OpenAI chat-completions deployment.
+    ///
+    /// Pair with a `base_url` of `https://{resource}.openai.azure.com`.
+    pub fn azure(deployment: impl Into<String>) -> Self {
+        Self {
+            azure_deployment: Some(deployment.into()),
+            ..Self::openai()
+        }
+    }
+
     /// Compat flags for the Meta Model API (Muse Spark).
     ///
     /// OpenAI-compatible chat completions. Meta documents `reasoning_effort`
@@ -893,6 +969,24 @@ impl ModelConfig {
             compat: None,
         }
     }
+
+    /// Create a new Cohere (Command R / R+) model config.
+    pub fn cohere(id: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            api: ApiProtocol::CohereChat,
+            provider: "cohere".into(),
+            base_url: "https://api.cohere.com/v1".into(),
+            reasoning: false,
+            context_window: 128_000,
+            max_tokens: 4000,
+            cost: CostConfig::default(),
+            headers: HashMap::new(),
+            anthropic: None,
+            compat: None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -952,6 +1046,59 @@ mod tests {
         assert!((cost.cost_usd(&usage) - 6.6).abs() < 1e-9);
         // zero rates (default) => zero cost
         assert_eq!(CostConfig::default().cost_usd(&usage), 0.0);
+        assert_eq!(usage.cost(&cost), cost.cost_usd(&usage));
+    }
+
+    #[test]
+    fn test_cache_reads_cost_less_than_fresh_input() {
+        let cost = CostConfig::for_model("claude-sonnet-5").unwrap();
+        let fresh = crate::types::Usage {
+            input: 100_000,
+            ..Default::default()
+        };
+        let cached = crate::types::Usage {
+            input: 0,
+            cache_read: 100_000,
+            ..Default::default()
+        };
+        // $3.00 vs $0.30 per million.
+        assert!((fresh.cost(&cost) - 0.3).abs() < 1e-9);
+        assert!((cached.cost(&cost) - 0.03).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_cost_config_for_model() {
+        let sonnet = CostConfig::for_model("claude-sonnet-4-20250514").unwrap();
+        assert_eq!(sonnet.input_per_million, 3.0);
+        assert_eq!(sonnet.cache_read_per_million, 0.3);
+        let sonnet_3_5 = CostConfig::for_model("claude-3-5-sonnet-20241022").unwrap();
+        assert_eq!(sonnet_3_5.output_per_million, 15.0);
+        assert_eq!(sonnet_3_5.cache_write_per_million, 3.75);
+
+        // Longest match wins: gpt-4o-mini is not priced as gpt-4o.
+        let mini = CostConfig::for_model("openai/gpt-4o-mini-2024-07-18").unwrap();
+        assert_eq!(mini.input_per_million, 0.15);
+        assert_eq!(
+            CostConfig::for_model("gpt-4o").unwrap().input_per_million,
+            2.5
+        );
+        let million_in = crate::types::Usage {
+            input: 1_000_000,
+            ..Default::default()
+        };
+        assert!((million_in.cost(&CostConfig::for_model("gpt-4o").unwrap()) - 2.5).abs() < 1e-9);
+
+        // Presets and the registry agree.
+        assert_eq!(
+            CostConfig::for_model("claude-opus-5")
+                .unwrap()
+                .output_per_million,
+            ModelConfig::claude_opus_5().cost.output_per_million
+        );
+
+        // Only whole id segments match.
+        assert!(CostConfig::for_model("gpt-4oops").is_none());
+        assert!(CostConfig::for_model("llama3.1:8b").is_none());
     }
 
     #[test]
@@ -10,6 +10,7 @@ use super::model::{MaxTokensField, ModelConfig, OpenAiCompat, ThinkingFormat};
 use super::traits::*;
 use crate::types::*;
 use async_trait::async_trait;
+use base64::Engine;
 use futures::StreamExt;
 use reqwest_eventsource::EventSource;
 use serde::Deserialize;
@@ -24,6 +25,16 @@ impl StreamProvider for OpenAiCompatProvider {
         Some(crate::provider::ApiProtocol::OpenAiCompletions)
     }
 
+    /// Reasoning streams and image input depend on the backend behind the
+    /// endpoint; the API shape supports both, so they're reported as
+    /// available. Context size varies too much to report.
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_server_tools: false,
+            ..ProviderCapabilities::default()
+        }
+    }
+
     async fn stream(
         &self,
         config: StreamConfig,
@@ -35,8 +46,8 @@ impl StreamProvider for OpenAiCompatProvider {
         })?;
         let compat = model_config.compat.as_ref().cloned().unwrap_or_default();
 
-        let base_url = &model_config.base_url;
-        let url = format!("{}/chat/completions", base_url);
+        let url = chat_completions_url(&model_config.base_url, &compat);
+        let (auth_name, auth_value) = auth_header(&compat, &config.api_key);
 
         let body = build_request_body(&config, model_config, &compat);
         debug!("OpenAI compat request: model={} url={}", config.model, url);
@@ -45,7 +56,7 @@ impl StreamProvider for OpenAiCompatProvider {
         let mut request = client
             .post(&url)
             .header("content-type", "application/json")
-            .header("authorization", format!("Bearer {}", config.api_key));
+            .header(auth_name, auth_value);
 
         // Add any extra headers from model config
         for (k, v) in &model_config.headers {
@@ -266,6 +277,33 @@ struct ToolCallBuffer {
     arguments: String,
 }
 
+/// Chat-completions endpoint: `{base_url}/chat/completions`, or Azure's
+/// deployment route when `compat.azure_deployment` is set.
+fn chat_completions_url(base_url: &str, compat: &OpenAiCompat) -> String {
+    match &compat.azure_deployment {
+        Some(deployment) => format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            base_url,
+            deployment,
+            compat
+                .api_version
+                .as_deref()
+                .unwrap_or(super::model::AZURE_DEFAULT_API_VERSION)
+        ),
+        None => format!("{}/chat/completions", base_url),
+    }
+}
+
+/// Auth header name and value: Azure deployments take a raw `api-key`,
+/// everything else a `Bearer` token.
+fn auth_header(compat: &OpenAiCompat, api_key: &str) -> (&'static str, String) {
+    if compat.azure_deployment.is_some() {
+        ("api-key", api_key.to_string())
+    } else {
+        ("authorization", format!("Bearer {}", api_key))
+    }
+}
+
 fn build_request_body(
     config: &StreamConfig,
     model_config: &ModelConfig,
@@ -339,8 +377,11 @@ fn build_request_body(
                 content,
                 ..
             } => {
-                let content_val = if content.iter().any(|c| matches!(c, Content::Image { .. })) {
-                    // Images present: use array format for multimodal tool results
+                let content_val = if content
+                    .iter()
+                    .any(|c| matches!(c, Content::Image { .. } | Content::Document { .. }))
+                {
+                    // Images or documents present: use array format for multimodal tool results
                     content_to_openai(content)
                 } else {
                     // Text-only: use plain string for maximum compat
@@ -375,6 +416,12 @@ fn build_request_body(
         "stream_options": {"include_usage": true},
         "messages": messages,
     });
+    // Azure routes by deployment; the deployment fixes the model.
+    if compat.azure_deployment.is_some() {
+        if let Some(obj) = body.as_object_mut() {
+            obj.remove("model");
+        }
+    }
 
     match compat.max_tokens_field {
         MaxTokensField::MaxCompletionTokens => {
@@ -410,6 +457,18 @@ fn build_request_body(
             })
             .collect();
         body["tools"] = serde_json::json!(tools);
+
+        match &config.tool_choice {
+            ToolChoice::Auto => {}
+            ToolChoice::None => body["tool_choice"] = serde_json::json!("none"),
+            ToolChoice::Required => body["tool_choice"] = serde_json::json!("required"),
+            ToolChoice::Specific(name) => {
+                body["tool_choice"] = serde_json::json!({
+                    "type": "function",
+                    "function": {"name": name},
+                });
+            }
+        }
     }
 
     // Structured outputs: native json_schema response format.
@@ -419,9 +478,11 @@ fn build_request_body(
             "json_schema": {
                 "name": schema.name,
                 "schema": schema.schema,
-                "strict": true,
+                "strict": schema.strict,
             },
         });
+    } else if config.response_format == ResponseFormat::JsonObject {
+        body["response_format"] = serde_json::json!({ "type": "json_object" });
     }
 
     if config.thinking_level != ThinkingLevel::Off && compat.supports_reasoning_effort {
@@ -438,6 +499,10 @@ fn build_request_body(
         body["temperature"] = serde_json::json!(temp);
     }
 
+    if let Some(stop) = &config.stop_sequences {
+        body["stop"] = serde_json::json!(stop);
+    }
+
     body
 }
 
@@ -479,6 +544,26 @@ fn content_to_openai(content: &[Content]) -> serde_json::Value {
                 "type": "image_url",
                 "image_url": {"url": format!("data:{};base64,{}", mime_type, data)},
             })),
+            Content::Document {
+                data,
+                mime_type,
+                filename,
+            } => Some(serde_json::json!({
+                "type": "file",
+                "file": {
+                    "filename": filename,
+                    "file_data": format!(
+                        "data:{};base64,{}",
+                        mime_type,
+                        base64::engine::general_purpose::STANDARD.encode(data)
+                    ),
+                },
+            })),
+            // Chat Completions can't fetch files by URL.
+            Content::DocumentUrl { url, .. } => Some(serde_json::json!({
+                "type": "text",
+                "text": format!("[document: {}]", url),
+            })),
             _ => None,
         })
         .collect();
@@ -576,6 +661,10 @@ mod tests {
                 "structured_output",
                 serde_json::json!({"type": "object"}),
             )),
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
         let body = build_request_body(&config, &mc, &OpenAiCompat::openai());
         assert_eq!(body["response_format"]["type"], "json_schema");
@@ -590,6 +679,18 @@ mod tests {
         );
     }
 
+    #[test]
+    fn structured_output_strict_can_be_disabled() {
+        let mc = ModelConfig::openai("gpt-5.5", "GPT-5.5");
+        let mut config = StreamConfig::new("gpt-5.5", "test");
+        config.output_schema = Some(
+            crate::provider::OutputSchema::new("loose", serde_json::json!({"type": "object"}))
+                .with_strict(false),
+        );
+        let body = build_request_body(&config, &mc, &OpenAiCompat::openai());
+        assert_eq!(body["response_format"]["json_schema"]["strict"], false);
+    }
+
     #[test]
     fn test_build_request_body_basic() {
         let model_config = ModelConfig::openai("gpt-4o", "GPT-4o");
@@ -605,6 +706,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &OpenAiCompat::openai());
@@ -637,6 +742,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -645,6 +754,118 @@ mod tests {
         assert_eq!(body["temperature"], 0.5);
     }
 
+    #[test]
+    fn test_build_request_body_stop_sequences() {
+        let model_config = ModelConfig::openai("gpt-4o", "GPT-4o");
+        let compat = OpenAiCompat::openai();
+        let mut config = StreamConfig::new("gpt-4o", "test");
+        config.messages = vec![Message::user("Hello")];
+        let body = build_request_body(&config, &model_config, &compat);
+        assert!(body.get("stop").is_none());
+
+        config.stop_sequences = Some(vec!["\n\n".into()]);
+        let body = build_request_body(&config, &model_config, &compat);
+        assert_eq!(body["stop"], serde_json::json!(["\n\n"]));
+    }
+
+    #[test]
+    fn test_azure_deployment_routing() {
+        let plain = OpenAiCompat::openai();
+        assert_eq!(
+            chat_completions_url("https://api.openai.com/v1", &plain),
+            "https://api.openai.com/v1/chat/completions"
+        );
+        assert_eq!(
+            auth_header(&plain, "sk-1"),
+            ("authorization", "Bearer sk-1".to_string())
+        );
+
+        let mut azure = OpenAiCompat::azure("gpt4o-prod");
+        assert_eq!(
+            chat_completions_url("https://res.openai.azure.com", &azure),
+            "https://res.openai.azure.com/openai/deployments/gpt4o-prod/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(auth_header(&azure, "az-1"), ("api-key", "az-1".to_string()));
+
+        azure.api_version = Some("2025-01-01-preview".into());
+        assert!(chat_completions_url("https://res.openai.azure.com", &azure)
+            .ends_with("?api-version=2025-01-01-preview"));
+    }
+
+    #[test]
+    fn test_build_request_body_omits_model_for_azure() {
+        let model_config = ModelConfig::openai("gpt-4o", "GPT-4o");
+        let mut config = StreamConfig::new("gpt-4o", "test");
+        config.messages = vec![Message::user("Hello")];
+
+        let body = build_request_body(&config, &model_config, &OpenAiCompat::openai());
+        assert_eq!(body["model"], "gpt-4o");
+
+        let body = build_request_body(&config, &model_config, &OpenAiCompat::azure("prod"));
+        assert!(body.get("model").is_none());
+        assert_eq!(body["stream"], true);
+    }
+
+    #[test]
+    fn test_build_request_body_json_mode() {
+        let model_config = ModelConfig::openai("gpt-4o", "GPT-4o");
+        let compat = OpenAiCompat::openai();
+        let mut config = StreamConfig::new("gpt-4o", "test");
+        config.messages = vec![Message::user("Hello")];
+        config.response_format = ResponseFormat::JsonObject;
+        let body = build_request_body(&config, &model_config, &compat);
+        assert_eq!(
+            body["response_format"],
+            serde_json::json!({"type": "json_object"})
+        );
+
+        config.output_schema = Some(crate::provider::OutputSchema::new(
+            "structured_output",
+            serde_json::json!({"type": "object"}),
+        ));
+        let body = build_request_body(&config, &model_config, &compat);
+        assert_eq!(body["response_format"]["type"], "json_schema");
+    }
+
+    #[test]
+    fn test_build_request_body_tool_choice() {
+        let model_config = ModelConfig::openai("gpt-4o", "GPT-4o");
+        let compat = OpenAiCompat::openai();
+        let mut config = StreamConfig::new("gpt-4o", "test");
+        config.messages = vec![Message::user("Classify this")];
+        config.tools = vec![ToolDefinition {
+            name: "classify".into(),
+            description: "Pick a label".into(),
+            parameters: serde_json::json!({"type": "object"}),
+        }];
+
+        let body = build_request_body(&config, &model_config, &compat);
+        assert!(
+            body.get("tool_choice").is_none(),
+            "Auto sends no tool_choice"
+        );
+
+        config.tool_choice = ToolChoice::None;
+        let body = build_request_body(&config, &model_config, &compat);
+        assert_eq!(body["tool_choice"], "none");
+
+        config.tool_choice = ToolChoice::Required;
+        let body = build_request_body(&config, &model_config, &compat);
+        assert_eq!(body["tool_choice"], "required");
+
+        config.tool_choice = ToolChoice::Specific("classify".into());
+        let body = build_request_body(&config, &model_config, &compat);
+        assert_eq!(
+            body["tool_choice"],
+            serde_json::json!({"type": "function", "function": {"name": "classify"}})
+        );
+
+        // Without tools there is nothing to choose from — never sent.
+        config.tools.clear();
+        let body = build_request_body(&config, &model_config, &compat);
+        assert!(body.get("tool_choice").is_none());
+    }
+
     #[test]
     fn test_build_request_body_deepseek_off_uses_current_api_shape() {
         let model_config = ModelConfig::deepseek("deepseek-v4-flash", "DeepSeek V4 Flash");
@@ -661,6 +882,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -688,6 +913,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -712,6 +941,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -748,6 +981,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -832,6 +1069,71 @@ mod tests {
         assert_eq!(result[1]["type"], "image_url");
     }
 
+    #[test]
+    fn test_multipart_user_messages_to_openai() {
+        let Message::User { content, .. } = Message::user_multipart(vec![
+            Content::Text {
+                text: "compare".into(),
+            },
+            Content::Image {
+                data: "AAAA".into(),
+                mime_type: "image/jpeg".into(),
+            },
+            Content::Image {
+                data: "BBBB".into(),
+                mime_type: "image/png".into(),
+            },
+        ]) else {
+            unreachable!()
+        };
+        assert_eq!(
+            content_to_openai(&content),
+            serde_json::json!([
+                {"type": "text", "text": "compare"},
+                {"type": "image_url", "image_url": {"url": "data:image/jpeg;base64,AAAA"}},
+                {"type": "image_url", "image_url": {"url": "data:image/png;base64,BBBB"}},
+            ])
+        );
+
+        let Message::User { content, .. } = Message::user_with_document(
+            "summarize",
+            b"%PDF-1.7".to_vec(),
+            "spec.pdf",
+            "application/pdf",
+        ) else {
+            unreachable!()
+        };
+        assert_eq!(
+            content_to_openai(&content)[1]["file"]["file_data"],
+            "data:application/pdf;base64,JVBERi0xLjc="
+        );
+    }
+
+    #[test]
+    fn test_content_to_openai_document_as_data_url() {
+        let content = vec![
+            Content::Text {
+                text: "summarize".into(),
+            },
+            Content::Document {
+                data: b"%PDF-1.7".to_vec(),
+                mime_type: "application/pdf".into(),
+                filename: "spec.pdf".into(),
+            },
+        ];
+        let result = content_to_openai(&content);
+        assert_eq!(
+            result[1],
+            serde_json::json!({
+                "type": "file",
+                "file": {
+                    "filename": "spec.pdf",
+                    "file_data": "data:application/pdf;base64,JVBERi0xLjc=",
+                },
+            })
+        );
+    }
+
     #[test]
     fn test_tool_result_with_image() {
         let model_config = ModelConfig::openai("gpt-4o", "GPT-4o");
@@ -873,6 +1175,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -913,6 +1219,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -968,6 +1278,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -1010,6 +1324,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -1062,6 +1380,10 @@ mod tests {
             model_config: Some(model_config.clone()),
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         };
 
         let body = build_request_body(&config, &model_config, &compat);
@@ -1072,4 +1394,56 @@ mod tests {
         assert_eq!(msgs[1]["content"][0]["text"], "The file contains a.");
         assert_eq!(msgs[2]["role"], "user");
     }
+
+    #[test]
+    fn test_request_messages_import_back_into_context() {
+        // Exported assistant messages carry no model, so the originals don't either.
+        let assistant = |content, stop_reason| {
+            Message::assistant(content, stop_reason, "", "openai", Usage::default())
+        };
+        let original = vec![
+            Message::user_with_image("What's this?", "AAAA", "image/png"),
+            assistant(
+                vec![
+                    Content::Text {
+                        text: "Let me check.".into(),
+                    },
+                    Content::tool_call("call_1", "lookup", serde_json::json!({"q": "cat"})),
+                ],
+                StopReason::ToolUse,
+            ),
+            Message::ToolResult {
+                tool_call_id: "call_1".into(),
+                tool_name: "lookup".into(),
+                content: vec![Content::Text {
+                    text: "A cat.".into(),
+                }],
+                is_error: false,
+                timestamp: 0,
+            },
+            assistant(
+                vec![Content::Text {
+                    text: "It's a cat.".into(),
+                }],
+                StopReason::Stop,
+            ),
+            Message::user_with_document("And this?", b"%PDF".to_vec(), "a.pdf", "application/pdf"),
+        ];
+        let mut config = StreamConfig::new("gpt-4o", "test");
+        config.system_prompt = "You are helpful.".into();
+        config.messages = original.clone();
+        let mc = ModelConfig::openai("gpt-4o", "GPT-4o");
+        let body = build_request_body(&config, &mc, &OpenAiCompat::openai());
+
+        let context =
+            AgentContext::from_openai_messages(body["messages"].as_array().unwrap()).unwrap();
+        assert_eq!(context.system_prompt, "You are helpful.");
+        let imported: Vec<Message> = context
+            .messages
+            .into_iter()
+            .map(|m| m.as_llm().unwrap().clone().with_timestamp(0))
+            .collect();
+        let original: Vec<Message> = original.into_iter().map(|m| m.with_timestamp(0)).collect();
+        assert_eq!(imported, original);
+    }
 }
@@ -7,6 +7,7 @@ use super::model::ModelConfig;
 use super::traits::*;
 use crate::types::*;
 use async_trait::async_trait;
+use base64::Engine;
 use futures::StreamExt;
 use reqwest_eventsource::EventSource;
 use serde::Deserialize;
@@ -21,6 +22,13 @@ impl StreamProvider for OpenAiResponsesProvider {
         Some(crate::provider::ApiProtocol::OpenAiResponses)
     }
 
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities {
+            supports_server_tools: false,
+            ..ProviderCapabilities::default()
+        }
+    }
+
     async fn stream(
         &self,
         config: StreamConfig,
@@ -32,6 +40,11 @@ impl StreamProvider for OpenAiResponsesProvider {
                 "structured outputs are not yet wired for the OpenAI Responses provider; output_schema will be ignored"
             );
         }
+        if config.stop_sequences.is_some() {
+            tracing::warn!(
+                "the Responses API has no stop parameter; stop_sequences will be ignored by the OpenAI Responses provider"
+            );
+        }
         let model_config = config
             .model_config
             .as_ref()
@@ -224,6 +237,42 @@ struct ToolCallBuffer {
     arguments: String,
 }
 
+/// Responses API `tool_choice` for a [`ToolChoice`] (`None` for `Auto`, which
+/// is the API default). Shared with the Azure OpenAI provider.
+pub(super) fn responses_tool_choice(choice: &ToolChoice) -> Option<serde_json::Value> {
+    match choice {
+        ToolChoice::Auto => None,
+        ToolChoice::None => Some(serde_json::json!("none")),
+        ToolChoice::Required => Some(serde_json::json!("required")),
+        ToolChoice::Specific(name) => Some(serde_json::json!({"type": "function", "name": name})),
+    }
+}
+
+/// A Responses API `input_file` part for a document, inline or by URL.
+/// Shared with the Azure provider, which speaks the same format.
+pub(super) fn input_file(content: &Content) -> serde_json::Value {
+    match content {
+        Content::Document {
+            data,
+            mime_type,
+            filename,
+        } => serde_json::json!({
+            "type": "input_file",
+            "filename": filename,
+            "file_data": format!(
+                "data:{};base64,{}",
+                mime_type,
+                base64::engine::general_purpose::STANDARD.encode(data)
+            ),
+        }),
+        Content::DocumentUrl { url, .. } => serde_json::json!({
+            "type": "input_file",
+            "file_url": url,
+        }),
+        _ => serde_json::Value::Null,
+    }
+}
+
 fn build_request_body(config: &StreamConfig, _model_config: &ModelConfig) -> serde_json::Value {
     let mut input: Vec<serde_json::Value> = Vec::new();
 
@@ -243,6 +292,9 @@ fn build_request_body(config: &StreamConfig, _model_config: &ModelConfig) -> ser
                             "type": "input_image",
                             "image_url": format!("data:{};base64,{}", mime_type, data),
                         })),
+                        Content::Document { .. } | Content::DocumentUrl { .. } => {
+                            Some(input_file(c))
+                        }
                         _ => None,
                     })
                     .collect();
@@ -294,7 +346,14 @@ fn build_request_body(config: &StreamConfig, _model_config: &ModelConfig) -> ser
                 content,
                 ..
             } => {
-                let output_val = if content.iter().any(|c| matches!(c, Content::Image { .. })) {
+                let output_val = if content.iter().any(|c| {
+                    matches!(
+                        c,
+                        Content::Image { .. }
+                            | Content::Document { .. }
+                            | Content::DocumentUrl { .. }
+                    )
+                }) {
                     // Images present: build content array
                     let parts: Vec<serde_json::Value> = content
                         .iter()
@@ -308,6 +367,9 @@ fn build_request_body(config: &StreamConfig, _model_config: &ModelConfig) -> ser
                                 "type": "input_image",
                                 "image_url": format!("data:{};base64,{}", mime_type, data),
                             })),
+                            Content::Document { .. } | Content::DocumentUrl { .. } => {
+                                Some(input_file(c))
+                            }
                             _ => None,
                         })
                         .collect();
@@ -345,6 +407,10 @@ fn build_request_body(config: &StreamConfig, _model_config: &ModelConfig) -> ser
         body["max_output_tokens"] = serde_json::json!(max);
     }
 
+    if config.response_format == ResponseFormat::JsonObject && config.output_schema.is_none() {
+        body["text"] = serde_json::json!({ "format": { "type": "json_object" } });
+    }
+
     if !config.tools.is_empty() {
         let tools: Vec<serde_json::Value> = config
             .tools
@@ -359,6 +425,9 @@ fn build_request_body(config: &StreamConfig, _model_config: &ModelConfig) -> ser
             })
             .collect();
         body["tools"] = serde_json::json!(tools);
+        if let Some(choice) = responses_tool_choice(&config.tool_choice) {
+            body["tool_choice"] = choice;
+        }
     }
 
     if config.thinking_level != ThinkingLevel::Off {
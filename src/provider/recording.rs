@@ -0,0 +1,273 @@
+//! Recording provider — capture real provider calls as replayable fixtures.
+//!
+//! [`RecordingProvider`] passes calls through to a real provider and appends
+//! each one (request, timed stream events, final message) as a line of JSON.
+//! [`MockProvider::from_recording`](super::MockProvider::from_recording)
+//! turns the file back into a provider that replays the same events, so a
+//! conversation recorded once against Anthropic or OpenAI can run in CI
+//! without network access or API keys.
+//!
+//! ```rust,no_run
+//! use yoagent::provider::{AnthropicProvider, MockProvider, RecordingProvider};
+//!
+//! # fn main() -> std::io::Result<()> {
+//! // Once, with a real key:
+//! let provider = RecordingProvider::new(Box::new(AnthropicProvider), "fixtures/chat.jsonl")?;
+//! // ... run the agent with `provider` ...
+//!
+//! // In tests:
+//! let provider = MockProvider::from_recording("fixtures/chat.jsonl")?;
+//! # Ok(())
+//! # }
+//! ```
+
+use super::traits::*;
+use crate::types::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::sync::mpsc;
+
+/// One provider call as stored by [`RecordingProvider`] — a line of the
+/// recording file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedCall {
+    /// When the call started (Unix ms).
+    pub timestamp: u64,
+    /// The request, minus the API key; `model_config` header values are
+    /// replaced with `"<redacted>"`.
+    pub config: serde_json::Value,
+    /// Stream events in the order they were sent.
+    pub events: Vec<RecordedEvent>,
+    /// The message the call returned.
+    pub message: Message,
+    /// How long the call took.
+    pub duration_ms: u64,
+}
+
+/// A [`StreamEvent`] and when it arrived, relative to the start of the call.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordedEvent {
+    pub at_ms: u64,
+    pub event: StreamEvent,
+}
+
+/// Read a recording written by [`RecordingProvider`]. Blank lines are
+/// skipped; a line that doesn't parse is an `InvalidData` error naming it.
+pub fn read_recording(path: impl AsRef<Path>) -> io::Result<Vec<RecordedCall>> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{} line {}: {}", path.display(), i + 1, e),
+                )
+            })
+        })
+        .collect()
+}
+
+/// A [`StreamProvider`] that records every successful call of the provider
+/// it wraps to a JSON Lines file of [`RecordedCall`]s.
+///
+/// Events are forwarded live, so the wrapped provider behaves exactly as
+/// without recording. Failed calls are not recorded: a replay sees only the
+/// attempt that succeeded, as if retries had never been needed.
+pub struct RecordingProvider {
+    inner: Box<dyn StreamProvider>,
+    file: Mutex<std::fs::File>,
+}
+
+impl RecordingProvider {
+    /// Wrap `inner`, recording to `path`. The file is created, or truncated
+    /// if it exists.
+    pub fn new(inner: Box<dyn StreamProvider>, path: impl AsRef<Path>) -> io::Result<Self> {
+        if let Some(parent) = path.as_ref().parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        Ok(Self {
+            inner,
+            file: Mutex::new(std::fs::File::create(path)?),
+        })
+    }
+
+    fn append(&self, call: &RecordedCall) -> io::Result<()> {
+        let mut line = serde_json::to_string(call)?;
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(line.as_bytes())?;
+        file.flush()
+    }
+}
+
+/// The request as recorded: no API key, header values blanked out.
+fn redacted_config(config: &StreamConfig) -> serde_json::Value {
+    let mut config = config.clone();
+    if let Some(model_config) = config.model_config.as_mut() {
+        for value in model_config.headers.values_mut() {
+            *value = "<redacted>".into();
+        }
+    }
+    serde_json::to_value(&config).unwrap_or(serde_json::Value::Null)
+}
+
+#[async_trait]
+impl StreamProvider for RecordingProvider {
+    async fn stream(
+        &self,
+        config: StreamConfig,
+        tx: mpsc::UnboundedSender<StreamEvent>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<Message, ProviderError> {
+        let timestamp = now_ms();
+        let started = Instant::now();
+        let recorded_config = redacted_config(&config);
+
+        let (inner_tx, mut inner_rx) = mpsc::unbounded_channel();
+        let call = self.inner.stream(config, inner_tx, cancel);
+        let forward = async {
+            let mut events = Vec::new();
+            while let Some(event) = inner_rx.recv().await {
+                events.push(RecordedEvent {
+                    at_ms: started.elapsed().as_millis() as u64,
+                    event: event.clone(),
+                });
+                let _ = tx.send(event);
+            }
+            events
+        };
+        let (result, events) = tokio::join!(call, forward);
+
+        if let Ok(message) = &result {
+            let call = RecordedCall {
+                timestamp,
+                config: recorded_config,
+                events,
+                message: message.clone(),
+                duration_ms: started.elapsed().as_millis() as u64,
+            };
+            if let Err(e) = self.append(&call) {
+                tracing::warn!(error = %e, "failed to record provider call");
+            }
+        }
+        result
+    }
+
+    fn protocol(&self) -> Option<crate::provider::ApiProtocol> {
+        self.inner.protocol()
+    }
+
+    fn capabilities(&self) -> ProviderCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{MockProvider, ModelConfig};
+
+    async fn call(provider: &dyn StreamProvider, config: StreamConfig) -> Vec<StreamEvent> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        provider
+            .stream(config, tx, tokio_util::sync::CancellationToken::new())
+            .await
+            .unwrap();
+        let mut events = Vec::new();
+        while let Ok(event) = rx.try_recv() {
+            events.push(event);
+        }
+        events
+    }
+
+    #[tokio::test]
+    async fn test_recording_omits_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("calls.jsonl");
+        let recorder = RecordingProvider::new(Box::new(MockProvider::text("hi")), &path).unwrap();
+
+        let mut config = StreamConfig::new("mock", "sk-secret");
+        let mut model_config = ModelConfig::mock();
+        model_config
+            .headers
+            .insert("x-api-key".into(), "sk-header".into());
+        config.model_config = Some(model_config);
+        let live = call(&recorder, config).await;
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("sk-secret") && !text.contains("sk-header"));
+        let calls = read_recording(&path).unwrap();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(
+            calls[0].config["modelConfig"]["headers"]["x-api-key"],
+            "<redacted>"
+        );
+        let recorded: Vec<StreamEvent> = calls[0].events.iter().map(|e| e.event.clone()).collect();
+        assert_eq!(recorded, live);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_with_recorded_timing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("calls.jsonl");
+        let message = Message::assistant(
+            vec![Content::Text { text: "ok".into() }],
+            StopReason::Stop,
+            "mock",
+            "mock",
+            Usage::default(),
+        );
+        let recorded = RecordedCall {
+            timestamp: 0,
+            config: serde_json::Value::Null,
+            events: vec![
+                RecordedEvent {
+                    at_ms: 0,
+                    event: StreamEvent::Start,
+                },
+                RecordedEvent {
+                    at_ms: 400,
+                    event: StreamEvent::Done {
+                        message: message.clone(),
+                    },
+                },
+            ],
+            message,
+            duration_ms: 400,
+        };
+        std::fs::write(&path, serde_json::to_string(&recorded).unwrap() + "\n\n").unwrap();
+
+        let started = tokio::time::Instant::now();
+        let instant = MockProvider::from_recording(&path).unwrap();
+        call(&instant, StreamConfig::new("mock", "")).await;
+        assert_eq!(started.elapsed(), std::time::Duration::ZERO);
+
+        let paced = MockProvider::from_recording(&path)
+            .unwrap()
+            .with_recorded_timing(true);
+        let events = call(&paced, StreamConfig::new("mock", "")).await;
+        assert_eq!(started.elapsed(), std::time::Duration::from_millis(400));
+        assert_eq!(events.len(), 2);
+    }
+
+    #[test]
+    fn test_read_recording_reports_bad_line() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("calls.jsonl");
+        std::fs::write(&path, "{not json}\n").unwrap();
+        let err = read_recording(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("line 1"));
+    }
+}
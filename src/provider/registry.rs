@@ -74,8 +74,8 @@ impl Default for ProviderRegistry {
     /// Create a registry with all built-in providers registered.
     fn default() -> Self {
         use crate::provider::{
-            AnthropicProvider, AzureOpenAiProvider, BedrockProvider, GoogleProvider,
-            GoogleVertexProvider, OpenAiCompatProvider, OpenAiResponsesProvider,
+            AnthropicProvider, AzureOpenAiProvider, BedrockProvider, CohereProvider,
+            GoogleProvider, GoogleVertexProvider, OpenAiCompatProvider, OpenAiResponsesProvider,
         };
 
         let mut registry = Self::new();
@@ -86,6 +86,7 @@ impl Default for ProviderRegistry {
         registry.register(ApiProtocol::GoogleVertex, GoogleVertexProvider);
         registry.register(ApiProtocol::BedrockConverseStream, BedrockProvider);
         registry.register(ApiProtocol::AzureOpenAiResponses, AzureOpenAiProvider);
+        registry.register(ApiProtocol::CohereChat, CohereProvider);
 
         registry
     }
@@ -106,13 +107,14 @@ mod tests {
         assert!(registry.has(&ApiProtocol::GoogleVertex));
         assert!(registry.has(&ApiProtocol::BedrockConverseStream));
         assert!(registry.has(&ApiProtocol::AzureOpenAiResponses));
+        assert!(registry.has(&ApiProtocol::CohereChat));
     }
 
     #[test]
     fn test_registry_protocols() {
         let registry = ProviderRegistry::default();
         let protocols = registry.protocols();
-        assert_eq!(protocols.len(), 7);
+        assert_eq!(protocols.len(), 8);
     }
 
     #[test]
@@ -144,6 +146,7 @@ mod tests {
 /// | `meta` | `META_API_KEY`, then `MODEL_API_KEY` |
 /// | `opencode-zen` / `opencode-go` | `OPENCODE_API_KEY` |
 /// | `azure` | `AZURE_OPENAI_API_KEY` |
+/// | `cohere` | `COHERE_API_KEY`, `CO_API_KEY` |
 /// | `bedrock` | `AWS_ACCESS_KEY_ID` + `AWS_SECRET_ACCESS_KEY` (+ `AWS_SESSION_TOKEN`), composed as `access:secret[:token]` |
 /// | `vertex` | none — pass a short-lived OAuth token via `with_api_key` |
 /// | `local` / `ollama` | no key needed (empty) |
@@ -175,6 +178,7 @@ pub fn resolve_api_key(provider: &str) -> Option<String> {
         "qwen" => first(&["DASHSCOPE_API_KEY"]),
         "opencode-zen" | "opencode-go" => first(&["OPENCODE_API_KEY"]),
         "azure" => first(&["AZURE_OPENAI_API_KEY"]),
+        "cohere" => first(&["COHERE_API_KEY", "CO_API_KEY"]),
         "bedrock" => {
             let access = var("AWS_ACCESS_KEY_ID").ok()?;
             let secret = var("AWS_SECRET_ACCESS_KEY").ok()?;
@@ -227,6 +231,7 @@ fn api_key_env_hint(provider: &str) -> &'static str {
         "qwen" => "set DASHSCOPE_API_KEY or call .with_api_key(...)",
         "opencode-zen" | "opencode-go" => "set OPENCODE_API_KEY or call .with_api_key(...)",
         "azure" => "set AZURE_OPENAI_API_KEY or call .with_api_key(...)",
+        "cohere" => "set COHERE_API_KEY (or CO_API_KEY) or call .with_api_key(...)",
         "bedrock" => {
             "set AWS_ACCESS_KEY_ID + AWS_SECRET_ACCESS_KEY (+ AWS_SESSION_TOKEN) \
              or call .with_api_key(\"access:secret[:token]\")"
@@ -294,7 +299,8 @@ mod registry_tests {
                 | ApiProtocol::AzureOpenAiResponses
                 | ApiProtocol::GoogleGenerativeAi
                 | ApiProtocol::GoogleVertex
-                | ApiProtocol::BedrockConverseStream => {}
+                | ApiProtocol::BedrockConverseStream
+                | ApiProtocol::CohereChat => {}
             }
         }
         vec![
@@ -305,6 +311,7 @@ mod registry_tests {
             ApiProtocol::GoogleGenerativeAi,
             ApiProtocol::GoogleVertex,
             ApiProtocol::BedrockConverseStream,
+            ApiProtocol::CohereChat,
         ]
     }
 
@@ -1,11 +1,17 @@
 use crate::types::*;
 use async_trait::async_trait;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::mpsc;
 
 use super::model::ModelConfig;
 
 /// Events emitted during LLM streaming
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
 pub enum StreamEvent {
     /// Stream started, partial assistant message
     Start,
@@ -40,7 +46,11 @@ pub enum StreamEvent {
 /// let mut config = StreamConfig::new("claude-sonnet-5", "sk-key");
 /// config.system_prompt = "be brief".into();
 /// ```
-#[derive(Debug, Clone)]
+///
+/// Serializes (for [`RecordingProvider`](super::RecordingProvider)) without
+/// `api_key`, which deserializes as empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 #[non_exhaustive]
 pub struct StreamConfig {
     pub model: String,
@@ -48,6 +58,7 @@ pub struct StreamConfig {
     pub messages: Vec<Message>,
     pub tools: Vec<ToolDefinition>,
     pub thinking_level: ThinkingLevel,
+    #[serde(skip_serializing, default)]
     pub api_key: String,
     pub max_tokens: Option<u32>,
     pub temperature: Option<f32>,
@@ -61,6 +72,18 @@ pub struct StreamConfig {
     /// response format; Gemini: `responseSchema`). Providers without support
     /// log a warning and ignore it.
     pub output_schema: Option<OutputSchema>,
+    /// How the model may use `tools`. Default [`ToolChoice::Auto`] sends
+    /// nothing, leaving the provider's own default in place.
+    pub tool_choice: ToolChoice,
+    /// Strings that end generation when the model emits them.
+    pub stop_sequences: Option<Vec<String>>,
+    /// Reply format without a schema (e.g. JSON mode). Ignored when
+    /// [`output_schema`](Self::output_schema) is set.
+    pub response_format: ResponseFormat,
+    /// Tools the provider runs itself (see [`ServerTool`]), offered next to
+    /// `tools`.
+    #[serde(default)]
+    pub server_tools: Vec<ServerTool>,
 }
 
 impl StreamConfig {
@@ -79,6 +102,10 @@ impl StreamConfig {
             model_config: None,
             cache_config: CacheConfig::default(),
             output_schema: None,
+            tool_choice: ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: ResponseFormat::Text,
+            server_tools: Vec::new(),
         }
     }
 }
@@ -94,6 +121,15 @@ pub struct OutputSchema {
     pub name: String,
     /// The JSON Schema the model's reply must satisfy.
     pub schema: serde_json::Value,
+    /// Ask for strict schema adherence where the provider offers it (OpenAI
+    /// `strict: true`, which restricts the schema dialect). Defaults to
+    /// `true`.
+    #[serde(default = "default_strict")]
+    pub strict: bool,
+}
+
+fn default_strict() -> bool {
+    true
 }
 
 impl OutputSchema {
@@ -101,8 +137,67 @@ impl OutputSchema {
         Self {
             name: name.into(),
             schema,
+            strict: true,
         }
     }
+
+    /// Turn strict mode off, e.g. for schemas using keywords OpenAI's
+    /// strict dialect rejects.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+}
+
+/// Format of the model's reply when no [`OutputSchema`] is given.
+///
+/// `JsonObject` asks for a single JSON object of any shape: OpenAI
+/// `response_format: {type: "json_object"}` (Responses API:
+/// `text.format`), Gemini `responseMimeType: application/json`, and on
+/// Anthropic a forced tool call whose input is unwrapped back into text, as
+/// with structured outputs. Bedrock logs a warning and ignores it. For a
+/// specific shape use [`StreamConfig::output_schema`], which takes
+/// precedence. Parse the reply with [`Message::parse_json`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Free text (the provider's default).
+    #[default]
+    Text,
+    /// A single JSON object.
+    JsonObject,
+}
+
+/// Name of the synthetic tool that carries [`ResponseFormat::JsonObject`]
+/// replies on tool-forcing providers (Anthropic).
+pub(crate) const JSON_OBJECT_TOOL_NAME: &str = "json_object";
+
+/// How the model may use the tools offered in a request.
+///
+/// Providers translate this into their native field (Anthropic/OpenAI
+/// `tool_choice`, Gemini `toolConfig.functionCallingConfig`, Bedrock
+/// `toolConfig.toolChoice`). It only applies when tools are offered. An
+/// [`output_schema`](StreamConfig::output_schema) takes precedence on
+/// providers that implement structured output by forcing a tool.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolChoice {
+    /// The model decides whether to call a tool.
+    #[default]
+    Auto,
+    /// Tools are visible but must not be called.
+    None,
+    /// The model must call at least one tool.
+    Required,
+    /// The model must call the named tool.
+    Specific(String),
+}
+
+impl ToolChoice {
+    /// Whether this choice obliges the model to call a tool.
+    pub fn forces_tool_call(&self) -> bool {
+        matches!(self, Self::Required | Self::Specific(_))
+    }
 }
 
 /// Tool definition sent to the LLM (schema only, no execute fn)
@@ -113,8 +208,140 @@ pub struct ToolDefinition {
     pub parameters: serde_json::Value,
 }
 
+/// A tool the provider executes itself, such as Anthropic's web search.
+///
+/// The model calls it and receives the result within the same response; the
+/// agent loop runs nothing. The call and its result come back as
+/// [`Content::ServerToolUse`] and [`Content::ServerToolResult`] blocks, which
+/// are replayed on later turns. Only Anthropic supports server tools (see
+/// [`ProviderCapabilities::supports_server_tools`]).
+///
+/// ```
+/// # use yoagent::provider::ServerTool;
+/// let search = ServerTool::web_search().with_option("max_uses", 5);
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct ServerTool {
+    /// Versioned tool type, e.g. `web_search_20250305`.
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    /// Name the model calls the tool by, e.g. `web_search`.
+    pub name: String,
+    /// Tool-specific settings sent alongside `type` and `name` (e.g.
+    /// `max_uses`, `allowed_domains`).
+    #[serde(flatten)]
+    pub options: serde_json::Map<String, serde_json::Value>,
+}
+
+impl ServerTool {
+    pub fn new(tool_type: impl Into<String>, name: impl Into<String>) -> Self {
+        Self {
+            tool_type: tool_type.into(),
+            name: name.into(),
+            options: serde_json::Map::new(),
+        }
+    }
+
+    /// Anthropic's web search (`web_search_20250305`).
+    pub fn web_search() -> Self {
+        Self::new("web_search_20250305", "web_search")
+    }
+
+    /// Set a tool-specific field, e.g. `("max_uses", 5)`.
+    pub fn with_option(
+        mut self,
+        key: impl Into<String>,
+        value: impl Into<serde_json::Value>,
+    ) -> Self {
+        self.options.insert(key.into(), value.into());
+        self
+    }
+}
+
 use serde::{Deserialize, Serialize};
 
+/// What a [`StreamProvider`] can handle, reported by
+/// [`StreamProvider::capabilities`].
+///
+/// Values describe the backend's API, not a particular model: a provider
+/// that supports vision may still be pointed at a text-only model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProviderCapabilities {
+    /// Image content in user messages and tool results.
+    pub supports_vision: bool,
+    /// Tool definitions and tool calls.
+    pub supports_tools: bool,
+    /// Extended thinking / reasoning via `thinking_level`.
+    pub supports_thinking: bool,
+    /// Incremental deltas while the response is generated.
+    pub supports_streaming: bool,
+    /// [`ResponseFormat::JsonObject`] (and `output_schema`).
+    pub supports_json_mode: bool,
+    /// A system prompt.
+    pub supports_system_prompt: bool,
+    /// Provider-executed [`ServerTool`]s.
+    pub supports_server_tools: bool,
+    /// Largest context window the backend serves, when known.
+    pub max_context_tokens: Option<usize>,
+}
+
+impl Default for ProviderCapabilities {
+    /// Everything supported, context size unknown.
+    fn default() -> Self {
+        Self {
+            supports_vision: true,
+            supports_tools: true,
+            supports_thinking: true,
+            supports_streaming: true,
+            supports_json_mode: true,
+            supports_system_prompt: true,
+            supports_server_tools: true,
+            max_context_tokens: None,
+        }
+    }
+}
+
+impl ProviderCapabilities {
+    /// Features `config` uses that these capabilities lack, by name
+    /// (`"tools"`, `"thinking"`, `"json_mode"`, `"vision"`, `"system_prompt"`,
+    /// `"server_tools"`).
+    /// Empty when the request fits.
+    pub fn unsupported_features(&self, config: &StreamConfig) -> Vec<&'static str> {
+        let mut missing = Vec::new();
+        if !self.supports_tools && !config.tools.is_empty() {
+            missing.push("tools");
+        }
+        if !self.supports_thinking && config.thinking_level != ThinkingLevel::Off {
+            missing.push("thinking");
+        }
+        if !self.supports_json_mode
+            && (config.response_format == ResponseFormat::JsonObject
+                || config.output_schema.is_some())
+        {
+            missing.push("json_mode");
+        }
+        if !self.supports_vision && config.messages.iter().any(has_image) {
+            missing.push("vision");
+        }
+        if !self.supports_system_prompt && !config.system_prompt.is_empty() {
+            missing.push("system_prompt");
+        }
+        if !self.supports_server_tools && !config.server_tools.is_empty() {
+            missing.push("server_tools");
+        }
+        missing
+    }
+}
+
+fn has_image(message: &Message) -> bool {
+    let content = match message {
+        Message::User { content, .. } | Message::ToolResult { content, .. } => content,
+        Message::Assistant { .. } => return false,
+    };
+    content.iter().any(|c| matches!(c, Content::Image { .. }))
+}
+
 /// The core provider trait. Implement this for each LLM backend.
 #[async_trait]
 pub trait StreamProvider: Send + Sync {
@@ -139,6 +366,12 @@ pub trait StreamProvider: Send + Sync {
     fn protocol(&self) -> Option<crate::provider::ApiProtocol> {
         None
     }
+
+    /// Features this provider supports. The default claims everything with
+    /// an unknown context size; built-in providers report accurate values.
+    fn capabilities(&self) -> ProviderCapabilities {
+        ProviderCapabilities::default()
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -168,6 +401,17 @@ impl ProviderError {
         Self::classify_with_retry_after(status, message, None)
     }
 
+    /// Like [`classify`](Self::classify), reading the retry delay for a
+    /// `RateLimited` error from the response headers: `Retry-After` (seconds
+    /// or HTTP-date), else the provider's rate-limit reset headers.
+    pub fn classify_with_headers(
+        status: u16,
+        message: &str,
+        headers: &reqwest::header::HeaderMap,
+    ) -> Self {
+        Self::classify_with_retry_after(status, message, parse_retry_after(headers))
+    }
+
     /// Like [`classify`](Self::classify), carrying a parsed `Retry-After`
     /// value (milliseconds) into the `RateLimited` variant when present.
     pub fn classify_with_retry_after(
@@ -206,9 +450,9 @@ pub async fn classify_eventsource_error(error: reqwest_eventsource::Error) -> Pr
     match error {
         reqwest_eventsource::Error::InvalidStatusCode(status, response) => {
             let status_code = status.as_u16();
-            let retry_after_ms = parse_retry_after(response.headers());
+            let headers = response.headers().clone();
             let body = response.text().await.unwrap_or_default();
-            ProviderError::classify_with_retry_after(
+            ProviderError::classify_with_headers(
                 status_code,
                 &format!(
                     "HTTP {} {}: {}",
@@ -216,7 +460,7 @@ pub async fn classify_eventsource_error(error: reqwest_eventsource::Error) -> Pr
                     status.canonical_reason().unwrap_or(""),
                     body
                 ),
-                retry_after_ms,
+                &headers,
             )
         }
         reqwest_eventsource::Error::Transport(e) => ProviderError::Network(format!("{:?}", e)),
@@ -224,17 +468,176 @@ pub async fn classify_eventsource_error(error: reqwest_eventsource::Error) -> Pr
     }
 }
 
-/// Parse a `Retry-After` header (seconds form) into milliseconds.
+/// Rate-limit reset headers, consulted when there is no `Retry-After`.
+/// When several are present the longest wait wins.
+const RESET_HEADERS: &[&str] = &[
+    // Anthropic: RFC 3339 timestamps
+    "anthropic-ratelimit-requests-reset",
+    "anthropic-ratelimit-tokens-reset",
+    // OpenAI and compatibles: durations such as "6m0s" or "20ms"
+    "x-ratelimit-reset-requests",
+    "x-ratelimit-reset-tokens",
+    // Others: delta seconds or a Unix timestamp
+    "x-ratelimit-reset",
+];
+
+/// How long the server asks us to wait, in milliseconds.
+///
+/// Reads `Retry-After` (delta-seconds or HTTP-date), falling back to the
+/// provider-specific reset headers in [`RESET_HEADERS`]. A time already in
+/// the past means "retry now" (`Some(0)`).
 pub(crate) fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<u64> {
-    headers
-        .get(reqwest::header::RETRY_AFTER)?
-        .to_str()
-        .ok()?
-        .trim()
-        .parse::<f64>()
-        .ok()
-        .filter(|s| *s >= 0.0)
-        .map(|secs| (secs * 1000.0) as u64)
+    parse_retry_after_at(headers, SystemTime::now())
+}
+
+fn parse_retry_after_at(headers: &reqwest::header::HeaderMap, now: SystemTime) -> Option<u64> {
+    let header = |name: &str| headers.get(name)?.to_str().ok().map(str::trim);
+    if let Some(value) = header(reqwest::header::RETRY_AFTER.as_str()) {
+        return match value.parse::<f64>() {
+            Ok(secs) => (secs.is_finite() && secs >= 0.0).then_some((secs * 1000.0) as u64),
+            Err(_) => httpdate::parse_http_date(value)
+                .ok()
+                .map(|at| millis_until(at, now)),
+        };
+    }
+    RESET_HEADERS
+        .iter()
+        .filter_map(|name| parse_reset(header(name)?, now))
+        .max()
+}
+
+/// One reset header value: seconds (a Unix timestamp when large enough to
+/// be one), a Go-style duration, an RFC 3339 timestamp or an HTTP-date.
+fn parse_reset(value: &str, now: SystemTime) -> Option<u64> {
+    if let Ok(n) = value.parse::<f64>() {
+        if !n.is_finite() || n < 0.0 {
+            return None;
+        }
+        // Anything past 2001-09-09 is an epoch timestamp, not a delay.
+        if n >= 1e9 {
+            let at = UNIX_EPOCH.checked_add(Duration::try_from_secs_f64(n).ok()?)?;
+            return Some(millis_until(at, now));
+        }
+        return Some((n * 1000.0) as u64);
+    }
+    parse_go_duration(value)
+        .map(|d| d.as_millis() as u64)
+        .or_else(|| parse_rfc3339(value).map(|at| millis_until(at, now)))
+        .or_else(|| {
+            httpdate::parse_http_date(value)
+                .ok()
+                .map(|at| millis_until(at, now))
+        })
+}
+
+fn millis_until(at: SystemTime, now: SystemTime) -> u64 {
+    at.duration_since(now).map_or(0, |d| d.as_millis() as u64)
+}
+
+/// Go `time.Duration` strings as OpenAI sends them: `"1h2m3.5s"`, `"20ms"`.
+fn parse_go_duration(value: &str) -> Option<Duration> {
+    let mut rest = value;
+    let mut total = 0.0;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .filter(|&i| i > 0)?;
+        let n: f64 = rest[..split].parse().ok()?;
+        rest = &rest[split..];
+        let unit_len = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        total += n * match &rest[..unit_len] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" => 1.0,
+            "ms" => 1e-3,
+            "us" | "µs" => 1e-6,
+            "ns" => 1e-9,
+            _ => return None,
+        };
+        rest = &rest[unit_len..];
+    }
+    if value.is_empty() {
+        return None;
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
+
+/// An RFC 3339 timestamp such as `2025-01-01T00:00:30Z` or
+/// `2025-01-01T01:00:30.5+01:00`.
+fn parse_rfc3339(value: &str) -> Option<SystemTime> {
+    let b = value.as_bytes();
+    if b.len() < 20 || b[4] != b'-' || b[7] != b'-' || !matches!(b[10], b'T' | b't' | b' ') {
+        return None;
+    }
+    let num = |range: std::ops::Range<usize>| -> Option<i64> {
+        let s = value.get(range)?;
+        s.bytes()
+            .all(|c| c.is_ascii_digit())
+            .then(|| s.parse().ok())?
+    };
+    let (year, month, day) = (num(0..4)?, num(5..7)?, num(8..10)?);
+    let (hour, minute, second) = (num(11..13)?, num(14..16)?, num(17..19)?);
+    if b[13] != b':' || b[16] != b':' || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut rest = &value[19..];
+    let mut nanos = 0u32;
+    if let Some(frac) = rest.strip_prefix('.') {
+        let digits = frac
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(frac.len());
+        let padded = format!("{:0<9}", &frac[..digits.min(9)]);
+        nanos = padded.parse().ok()?;
+        rest = &frac[digits..];
+    }
+    let offset_secs = match rest {
+        "Z" | "z" => 0,
+        _ if rest.len() == 6 && rest.as_bytes()[3] == b':' => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let h: i64 = rest[1..3].parse().ok()?;
+            let m: i64 = rest[4..6].parse().ok()?;
+            sign * (h * 3600 + m * 60)
+        }
+        _ => return None,
+    };
+
+    let secs = days_from_civil(year, month, day) * 86_400 + hour * 3600 + minute * 60 + second
+        - offset_secs;
+    let secs = u64::try_from(secs).ok()?;
+    Some(UNIX_EPOCH + Duration::new(secs, nanos))
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date (Howard Hinnant's
+/// `days_from_civil`). The inverse of [`civil_from_days`].
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+/// Proleptic Gregorian `(year, month, day)` for a count of days since
+/// 1970-01-01 (Howard Hinnant's `civil_from_days`).
+pub(crate) fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
 }
 
 /// Classify an SSE-embedded error event message into a [`ProviderError`].
@@ -322,14 +725,109 @@ mod tests {
         assert_eq!(parse_retry_after(&headers_with_retry_after("-1")), None);
     }
 
+    /// 2015-10-21T07:28:00Z
+    fn now() -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(1_445_412_480)
+    }
+
+    fn headers(pairs: &[(&'static str, &str)]) -> reqwest::header::HeaderMap {
+        let mut h = reqwest::header::HeaderMap::new();
+        for (name, value) in pairs {
+            h.insert(*name, value.parse().unwrap());
+        }
+        h
+    }
+
+    #[test]
+    fn parse_retry_after_http_date() {
+        let h = headers_with_retry_after("Wed, 21 Oct 2015 07:28:30 GMT");
+        assert_eq!(parse_retry_after_at(&h, now()), Some(30_000));
+        // A date already passed means retry now, not a huge delay.
+        let h = headers_with_retry_after("Wed, 21 Oct 2015 07:27:00 GMT");
+        assert_eq!(parse_retry_after_at(&h, now()), Some(0));
+        assert_eq!(parse_retry_after(&headers_with_retry_after("soon")), None);
+    }
+
+    #[test]
+    fn parse_anthropic_reset_timestamp() {
+        let h = headers(&[
+            ("anthropic-ratelimit-requests-reset", "2015-10-21T07:28:12Z"),
+            ("anthropic-ratelimit-tokens-reset", "2015-10-21T07:28:45.5Z"),
+        ]);
+        assert_eq!(parse_retry_after_at(&h, now()), Some(45_500));
+        let h = headers(&[(
+            "anthropic-ratelimit-requests-reset",
+            "2015-10-21T09:28:10+02:00",
+        )]);
+        assert_eq!(parse_retry_after_at(&h, now()), Some(10_000));
+    }
+
+    #[test]
+    fn parse_openai_reset_durations() {
+        let h = headers(&[
+            ("x-ratelimit-reset-requests", "1m30.5s"),
+            ("x-ratelimit-reset-tokens", "20ms"),
+        ]);
+        assert_eq!(parse_retry_after_at(&h, now()), Some(90_500));
+        let h = headers(&[("x-ratelimit-reset-requests", "6m0s")]);
+        assert_eq!(parse_retry_after_at(&h, now()), Some(360_000));
+    }
+
+    #[test]
+    fn parse_x_ratelimit_reset_seconds_and_epoch() {
+        let h = headers(&[("x-ratelimit-reset", "12")]);
+        assert_eq!(parse_retry_after_at(&h, now()), Some(12_000));
+        let h = headers(&[("x-ratelimit-reset", "1445412500")]);
+        assert_eq!(parse_retry_after_at(&h, now()), Some(20_000));
+        let h = headers(&[("x-ratelimit-reset", "tomorrow")]);
+        assert_eq!(parse_retry_after_at(&h, now()), None);
+    }
+
+    #[test]
+    fn retry_after_takes_precedence_over_reset_headers() {
+        let h = headers(&[
+            ("retry-after", "3"),
+            ("anthropic-ratelimit-requests-reset", "2015-10-21T07:29:00Z"),
+        ]);
+        assert_eq!(parse_retry_after_at(&h, now()), Some(3_000));
+    }
+
+    #[test]
+    fn classify_with_headers_fills_retry_after() {
+        let h = headers(&[("retry-after", "2")]);
+        let err = ProviderError::classify_with_headers(429, "slow down", &h);
+        assert!(matches!(
+            err,
+            ProviderError::RateLimited {
+                retry_after_ms: Some(2000)
+            }
+        ));
+    }
+
     #[test]
-    fn parse_retry_after_rejects_http_date() {
-        // The HTTP-date form of Retry-After is not supported; must not
-        // misparse as a huge delay.
+    fn rfc3339_matches_known_epochs() {
+        assert_eq!(parse_rfc3339("1970-01-01T00:00:00Z"), Some(UNIX_EPOCH));
         assert_eq!(
-            parse_retry_after(&headers_with_retry_after("Wed, 21 Oct 2015 07:28:00 GMT")),
-            None
+            parse_rfc3339("2000-03-01T00:00:00Z"),
+            Some(UNIX_EPOCH + Duration::from_secs(951_868_800))
         );
+        assert_eq!(parse_rfc3339("2015-10-21 07:28:00"), None);
+    }
+
+    #[test]
+    fn reset_headers_reject_unrepresentable_values() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        for value in ["inf", "NaN", "-inf"] {
+            assert_eq!(parse_reset(value, now), None, "{value}");
+            assert_eq!(
+                parse_retry_after_at(&headers_with_retry_after(value), now),
+                None,
+                "{value}"
+            );
+        }
+        assert_eq!(parse_reset("1e30", now), None);
+        assert_eq!(parse_go_duration("99999999999999999999h"), None);
+        assert_eq!(parse_reset("99999999999999999999h", now), None);
     }
 
     #[test]
@@ -426,4 +924,53 @@ mod tests {
         assert!(!is_context_overflow_message("internal server error"));
         assert!(!is_context_overflow_message(""));
     }
+
+    #[test]
+    fn unsupported_features_lists_what_the_request_needs() {
+        let mut config = StreamConfig::new("m", "k");
+        config.system_prompt = "be brief".into();
+        config.thinking_level = ThinkingLevel::High;
+        config.messages = vec![Message::User {
+            content: vec![Content::Image {
+                data: "aGk=".into(),
+                mime_type: "image/png".into(),
+            }],
+            timestamp: 0,
+        }];
+
+        assert!(ProviderCapabilities::default()
+            .unsupported_features(&config)
+            .is_empty());
+
+        let text_only = ProviderCapabilities {
+            supports_vision: false,
+            supports_thinking: false,
+            supports_json_mode: false,
+            ..ProviderCapabilities::default()
+        };
+        // No JSON mode requested, so only thinking and vision are missing.
+        assert_eq!(
+            text_only.unsupported_features(&config),
+            vec!["thinking", "vision"]
+        );
+
+        config.server_tools = vec![ServerTool::web_search()];
+        let no_server_tools = ProviderCapabilities {
+            supports_server_tools: false,
+            ..ProviderCapabilities::default()
+        };
+        assert_eq!(
+            no_server_tools.unsupported_features(&config),
+            vec!["server_tools"]
+        );
+    }
+
+    #[test]
+    fn civil_from_days_inverts_days_from_civil() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        for (y, m, d) in [(2000, 2, 29), (2024, 3, 1), (2100, 12, 31), (1601, 1, 1)] {
+            assert_eq!(civil_from_days(days_from_civil(y, m, d)), (y, m, d));
+        }
+    }
 }
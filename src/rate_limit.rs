@@ -0,0 +1,203 @@
+//! Client-side rate limiting for provider calls.
+//!
+//! A [`RateLimiter`] keeps requests-per-minute and tokens-per-minute token
+//! buckets. The agent loop awaits it before every provider call, retries
+//! included, so several agents sharing one limiter (via `Arc`) stay under a
+//! common budget instead of each discovering the limit through 429s.
+//!
+//! ```rust
+//! use std::sync::Arc;
+//! use yoagent::rate_limit::RateLimiter;
+//!
+//! let limiter = Arc::new(
+//!     RateLimiter::new()
+//!         .with_requests_per_minute(50)
+//!         .with_tokens_per_minute(40_000),
+//! );
+//! // AgentLoopConfig { rate_limiter: Some(limiter.clone()), .. }
+//! // or Agent::with_rate_limiter(limiter.clone())
+//! ```
+
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+/// Token-bucket limiter for requests and estimated input tokens per minute.
+///
+/// Each bucket holds up to one minute's budget and starts full, so a burst
+/// up to the limit goes out at once and later calls are spaced at the
+/// refill rate (`60s / rpm` per request). A request estimated at more tokens
+/// than the whole per-minute budget waits for a full bucket and drains it.
+///
+/// With no limits set, [`acquire`](Self::acquire) never waits.
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    requests: Option<Mutex<Bucket>>,
+    tokens: Option<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    /// A limiter with no limits; add them with the `with_*` methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow at most `rpm` provider calls per minute.
+    pub fn with_requests_per_minute(mut self, rpm: u32) -> Self {
+        self.requests = Some(Mutex::new(Bucket::per_minute(rpm as f64)));
+        self
+    }
+
+    /// Allow at most `tpm` estimated input tokens per minute.
+    pub fn with_tokens_per_minute(mut self, tpm: u64) -> Self {
+        self.tokens = Some(Mutex::new(Bucket::per_minute(tpm as f64)));
+        self
+    }
+
+    /// Wait until a request costing `tokens` fits in both buckets, then take
+    /// it. Returns `false`, taking nothing, if `cancel` fires first.
+    pub async fn acquire(&self, tokens: usize, cancel: &CancellationToken) -> bool {
+        loop {
+            let wait = match self.try_acquire(tokens) {
+                Ok(()) => return true,
+                Err(wait) => wait,
+            };
+            tokio::select! {
+                _ = cancel.cancelled() => return false,
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    }
+
+    /// Take a request costing `tokens` if it fits now; otherwise how long
+    /// until it might.
+    pub fn try_acquire(&self, tokens: usize) -> Result<(), Duration> {
+        let now = Instant::now();
+        // Lock order is fixed (requests, then tokens), so no deadlock.
+        let mut requests = self.requests.as_ref().map(lock);
+        let mut token_bucket = self.tokens.as_ref().map(lock);
+        let wait = [
+            requests.as_deref_mut().map(|b| b.wait_for(1.0, now)),
+            token_bucket
+                .as_deref_mut()
+                .map(|b| b.wait_for(tokens as f64, now)),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or_default();
+        if !wait.is_zero() {
+            return Err(wait);
+        }
+        if let Some(b) = requests.as_deref_mut() {
+            b.take(1.0);
+        }
+        if let Some(b) = token_bucket.as_deref_mut() {
+            b.take(tokens as f64);
+        }
+        Ok(())
+    }
+}
+
+fn lock(bucket: &Mutex<Bucket>) -> std::sync::MutexGuard<'_, Bucket> {
+    bucket.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[derive(Debug)]
+struct Bucket {
+    capacity: f64,
+    per_sec: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn per_minute(limit: f64) -> Self {
+        let capacity = limit.max(1.0);
+        Self {
+            capacity,
+            per_sec: capacity / 60.0,
+            available: capacity,
+            updated: Instant::now(),
+        }
+    }
+
+    /// Refill up to `now`, then how long until `cost` is available.
+    fn wait_for(&mut self, cost: f64, now: Instant) -> Duration {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.per_sec).min(self.capacity);
+        self.updated = now;
+        let short = cost.min(self.capacity) - self.available;
+        if short <= 0.0 {
+            Duration::ZERO
+        } else {
+            // Round up so the retry after sleeping doesn't fall just short.
+            Duration::from_secs_f64(short / self.per_sec) + Duration::from_millis(1)
+        }
+    }
+
+    fn take(&mut self, cost: f64) {
+        self.available = (self.available - cost.min(self.capacity)).max(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn requests_are_spaced_after_the_burst() {
+        let limiter = RateLimiter::new().with_requests_per_minute(6);
+        let cancel = CancellationToken::new();
+        let start = Instant::now();
+        for _ in 0..6 {
+            assert!(limiter.acquire(0, &cancel).await);
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        assert!(limiter.acquire(0, &cancel).await);
+        let elapsed = start.elapsed();
+        assert!(
+            (Duration::from_secs(10)..Duration::from_millis(10_100)).contains(&elapsed),
+            "{:?}",
+            elapsed
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn token_budget_waits_for_refill() {
+        let limiter = RateLimiter::new().with_tokens_per_minute(1_000);
+        let cancel = CancellationToken::new();
+        let start = Instant::now();
+        assert!(limiter.acquire(800, &cancel).await);
+        assert_eq!(limiter.try_acquire(400).map_err(|_| ()), Err(()));
+        // 200 left; 200 more at 1000/min take 12s.
+        assert!(limiter.acquire(400, &cancel).await);
+        let elapsed = start.elapsed();
+        assert!(
+            (Duration::from_secs(12)..Duration::from_millis(12_100)).contains(&elapsed),
+            "{:?}",
+            elapsed
+        );
+        // Larger than the whole budget: waits for a full bucket, then goes.
+        assert!(limiter.acquire(5_000, &cancel).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn cancellation_stops_waiting_without_taking() {
+        let limiter = RateLimiter::new().with_requests_per_minute(1);
+        let cancel = CancellationToken::new();
+        assert!(limiter.acquire(0, &cancel).await);
+        let waiting = limiter.acquire(0, &cancel);
+        cancel.cancel();
+        assert!(!waiting.await);
+    }
+
+    #[test]
+    fn no_limits_never_wait() {
+        let limiter = RateLimiter::new();
+        for _ in 0..1000 {
+            assert!(limiter.try_acquire(1_000_000).is_ok());
+        }
+    }
+}
@@ -1,13 +1,18 @@
-//! Retry with exponential backoff and jitter for provider calls.
+//! Retry with exponential backoff and jitter for provider calls, and a
+//! circuit breaker that stops calling a provider that keeps failing.
 
 use crate::provider::ProviderError;
+use std::sync::Mutex;
 use std::time::Duration;
+use tokio::time::Instant;
 use tracing::warn;
 
 /// Configuration for automatic retry of transient provider errors.
 ///
-/// Defaults: 3 retries, 1s initial delay, 2x backoff, 30s max delay.
-/// Use `RetryConfig::none()` to disable retries entirely.
+/// Defaults: 3 retries, 1s initial delay, 2x backoff, 30s max delay, no
+/// jitter (so delays are deterministic). Use `RetryConfig::none()` to disable
+/// retries entirely, and `RetryConfig::full_jitter()` or `jitter_factor` to
+/// spread retries out.
 #[derive(Debug, Clone)]
 pub struct RetryConfig {
     /// Maximum number of retry attempts (0 = no retries).
@@ -18,6 +23,11 @@ pub struct RetryConfig {
     pub backoff_multiplier: f64,
     /// Maximum delay between retries (milliseconds).
     pub max_delay_ms: u64,
+    /// Fraction of each delay (0.0–1.0) that is randomized. The delay is
+    /// drawn uniformly from `[delay × (1 − jitter_factor), delay]`, so many
+    /// clients failing at once don't retry in lockstep. 0.0 (the default) is
+    /// deterministic.
+    pub jitter_factor: f64,
 }
 
 impl Default for RetryConfig {
@@ -27,6 +37,7 @@ impl Default for RetryConfig {
             initial_delay_ms: 1000,
             backoff_multiplier: 2.0,
             max_delay_ms: 30_000,
+            jitter_factor: 0.0,
         }
     }
 }
@@ -40,16 +51,25 @@ impl RetryConfig {
         }
     }
 
+    /// Default backoff with full jitter: each delay is uniform between zero
+    /// and the backoff value, which spreads a burst of clients the most.
+    pub fn full_jitter() -> Self {
+        Self {
+            jitter_factor: 1.0,
+            ..Default::default()
+        }
+    }
+
     /// Calculate the delay for a given attempt (1-indexed).
-    /// Uses exponential backoff with ±20% jitter.
+    /// Uses exponential backoff capped at `max_delay_ms`, minus up to
+    /// `jitter_factor` of it at random.
     pub fn delay_for_attempt(&self, attempt: usize) -> Duration {
         let base_ms =
             self.initial_delay_ms as f64 * self.backoff_multiplier.powi((attempt - 1) as i32);
         let capped_ms = base_ms.min(self.max_delay_ms as f64);
 
-        // Jitter: ±20% (multiply by 0.8–1.2)
-        let jitter = 0.8 + rand::random::<f64>() * 0.4;
-        Duration::from_millis((capped_ms * jitter) as u64)
+        let jitter = self.jitter_factor.clamp(0.0, 1.0) * rand::random::<f64>();
+        Duration::from_millis((capped_ms * (1.0 - jitter)) as u64)
     }
 }
 
@@ -73,7 +93,158 @@ impl ProviderError {
     }
 }
 
-/// Log a retry attempt.
+/// Stops calling a provider that keeps failing.
+///
+/// After `failure_threshold` consecutive failed provider calls the breaker
+/// *opens*: for `cooldown`, the agent loop doesn't call the provider at all
+/// and ends the turn with an error message instead of burning through its
+/// retries. Once the cooldown has passed the breaker is *half-open* and lets
+/// a single trial call through — success closes it again, failure reopens it
+/// for another cooldown.
+///
+/// Failures are the errors [`ProviderError::is_fallback_eligible`] accepts
+/// (network, rate limit, API errors); auth errors, context overflow and
+/// cancellation neither count nor reset the streak. Every retry attempt is a
+/// call, so one turn's retries can trip the breaker.
+///
+/// Share one breaker (via `Arc`) between every agent talking to the same
+/// provider so an outage seen by one spares the others:
+///
+/// ```rust
+/// use std::{sync::Arc, time::Duration};
+/// use yoagent::retry::CircuitBreaker;
+///
+/// let breaker = Arc::new(CircuitBreaker::new(5, Duration::from_secs(60)));
+/// // AgentLoopConfig { circuit_breaker: Some(breaker.clone()), .. }
+/// // or Agent::with_circuit_breaker(breaker.clone())
+/// ```
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: usize,
+    cooldown: Duration,
+    state: Mutex<BreakerState>,
+}
+
+/// Observable state of a [`CircuitBreaker`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Calls go through; consecutive failures are being counted.
+    Closed,
+    /// Calls are refused until the cooldown ends.
+    Open,
+    /// The cooldown has ended; the next call is a trial.
+    HalfOpen,
+}
+
+#[derive(Debug)]
+enum BreakerState {
+    Closed { failures: usize },
+    Open { until: Instant },
+    HalfOpen { trial_in_flight: bool },
+}
+
+impl CircuitBreaker {
+    /// Open after `failure_threshold` consecutive failures (at least 1) and
+    /// stay open for `cooldown`.
+    pub fn new(failure_threshold: usize, cooldown: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            state: Mutex::new(BreakerState::Closed { failures: 0 }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        match *self.lock() {
+            BreakerState::Closed { .. } => CircuitState::Closed,
+            BreakerState::Open { until } if Instant::now() < until => CircuitState::Open,
+            BreakerState::Open { .. } | BreakerState::HalfOpen { .. } => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Ask to make a call. `Err` carries how long until the breaker will
+    /// allow one; while a half-open trial is in flight that is the full
+    /// cooldown, since the trial may reopen it.
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut state = self.lock();
+        match *state {
+            BreakerState::Closed { .. } => Ok(()),
+            BreakerState::Open { until } => {
+                let now = Instant::now();
+                if now < until {
+                    return Err(until - now);
+                }
+                *state = BreakerState::HalfOpen {
+                    trial_in_flight: true,
+                };
+                Ok(())
+            }
+            BreakerState::HalfOpen { trial_in_flight } => {
+                if trial_in_flight {
+                    return Err(self.cooldown);
+                }
+                *state = BreakerState::HalfOpen {
+                    trial_in_flight: true,
+                };
+                Ok(())
+            }
+        }
+    }
+
+    /// A call succeeded: close the breaker and reset the failure count.
+    pub fn record_success(&self) {
+        *self.lock() = BreakerState::Closed { failures: 0 };
+    }
+
+    /// A call failed: count it, opening the breaker at the threshold. A
+    /// failed half-open trial reopens it straight away.
+    pub fn record_failure(&self) {
+        let mut state = self.lock();
+        let failures = match *state {
+            BreakerState::Closed { failures } => failures + 1,
+            // Already open: a call that started before it opened.
+            BreakerState::Open { .. } => return,
+            BreakerState::HalfOpen { .. } => self.failure_threshold,
+        };
+        if failures >= self.failure_threshold {
+            warn!(
+                "Circuit breaker open after {} consecutive provider failures; pausing calls for {:.1}s",
+                failures,
+                self.cooldown.as_secs_f64()
+            );
+            *state = BreakerState::Open {
+                until: Instant::now() + self.cooldown,
+            };
+        } else {
+            *state = BreakerState::Closed { failures };
+        }
+    }
+
+    /// Record the outcome of a provider call acquired with
+    /// [`try_acquire`](Self::try_acquire).
+    pub(crate) fn record<T>(&self, result: &Result<T, ProviderError>) {
+        match result {
+            Ok(_) => self.record_success(),
+            Err(e) if e.is_fallback_eligible() => self.record_failure(),
+            Err(_) => {
+                // Says nothing about the provider's health; free the trial
+                // slot so the next call can probe instead.
+                let mut state = self.lock();
+                if let BreakerState::HalfOpen { .. } = *state {
+                    *state = BreakerState::HalfOpen {
+                        trial_in_flight: false,
+                    };
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BreakerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Log a retry attempt. `delay` is the wait actually used, after jitter.
 pub(crate) fn log_retry(attempt: usize, max: usize, delay: &Duration, error: &ProviderError) {
     warn!(
         "Provider error (attempt {}/{}), retrying in {:.1}s: {}",
@@ -83,3 +254,95 @@ pub(crate) fn log_retry(attempt: usize, max: usize, delay: &Duration, error: &Pr
         error
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_has_no_jitter() {
+        let config = RetryConfig::default();
+        assert_eq!(config.delay_for_attempt(1), Duration::from_millis(1000));
+        assert_eq!(config.delay_for_attempt(3), Duration::from_millis(4000));
+        assert_eq!(config.delay_for_attempt(10), Duration::from_millis(30_000));
+    }
+
+    #[test]
+    fn partial_jitter_stays_within_bounds() {
+        let config = RetryConfig {
+            jitter_factor: 0.2,
+            ..Default::default()
+        };
+        for _ in 0..50 {
+            let ms = config.delay_for_attempt(2).as_millis();
+            assert!((1600..=2000).contains(&ms), "{}ms", ms);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn breaker_opens_after_threshold_and_recovers() {
+        let breaker = CircuitBreaker::new(3, Duration::from_secs(30));
+        for _ in 0..2 {
+            breaker.try_acquire().unwrap();
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(breaker.try_acquire(), Err(Duration::from_secs(30)));
+
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(breaker.try_acquire(), Err(Duration::from_secs(20)));
+
+        // Half-open: exactly one trial at a time.
+        tokio::time::advance(Duration::from_secs(20)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.try_acquire().is_ok());
+        assert!(breaker.try_acquire().is_err());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert!(breaker.try_acquire().is_ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failed_trial_reopens_the_breaker() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(5));
+        breaker.record_failure();
+        breaker.record_failure();
+        tokio::time::advance(Duration::from_secs(5)).await;
+        breaker.try_acquire().unwrap();
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(breaker.try_acquire(), Err(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn success_resets_the_streak_and_neutral_errors_do_not_count() {
+        let breaker = CircuitBreaker::new(2, Duration::from_secs(5));
+        breaker.record::<()>(&Err(ProviderError::Network("reset".into())));
+        breaker.record::<()>(&Ok(()));
+        breaker.record::<()>(&Err(ProviderError::Network("reset".into())));
+        breaker.record::<()>(&Err(ProviderError::Auth("bad key".into())));
+        breaker.record::<()>(&Err(ProviderError::Cancelled));
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        breaker.record::<()>(&Err(ProviderError::Api("503".into())));
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[test]
+    fn full_jitter_spreads_delays_uniformly() {
+        let config = RetryConfig::full_jitter();
+        let mut buckets = [0usize; 5];
+        let mut distinct = std::collections::HashSet::new();
+        for _ in 0..50 {
+            let ms = config.delay_for_attempt(1).as_millis() as usize;
+            assert!(ms <= 1000, "{}ms", ms);
+            buckets[(ms / 200).min(4)] += 1;
+            distinct.insert(ms);
+        }
+        // Expect ~10 per fifth of the range; a spike at one value would leave
+        // most buckets empty. P(any bucket < 2) is well under 0.1%.
+        assert!(buckets.iter().all(|&n| n >= 2), "{:?}", buckets);
+        assert!(distinct.len() > 40, "{} distinct delays", distinct.len());
+    }
+}
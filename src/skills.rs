@@ -9,6 +9,10 @@
 //! 2. **Instructions** (<5k tokens) — SKILL.md body, loaded by the agent when activated
 //! 3. **Resources** (unlimited) — scripts/, references/, assets/, loaded on demand
 //!
+//! [`Skill::load_instructions`] and [`Skill::list_resources`] read levels 2
+//! and 3 directly; [`SkillSet::as_tools`] exposes each skill as a tool the
+//! agent calls to activate it.
+//!
 //! The agent decides when to activate a skill based on the description. No trigger
 //! engine needed — the LLM is smart enough.
 //!
@@ -22,9 +26,21 @@
 //! // Inject into system prompt via Agent::with_skills()
 //! ```
 
+use crate::types::{AgentTool, Content, ToolContext, ToolError, ToolResult};
+use async_trait::async_trait;
+use serde::Deserialize;
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+#[cfg(feature = "watch")]
+use std::sync::mpsc;
+#[cfg(feature = "watch")]
+use std::time::Duration;
+
+/// Quiet period after a filesystem event before [`SkillSet::watch`] reloads,
+/// so an editor's burst of writes triggers one reload.
+#[cfg(feature = "watch")]
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(100);
 
 /// A loaded skill with its metadata.
 #[derive(Debug, Clone)]
@@ -39,6 +55,157 @@ pub struct Skill {
     pub base_dir: PathBuf,
     /// Where this skill was loaded from (e.g. "workspace", "global", or a custom label)
     pub source: String,
+    /// Tools the skill may use, from the `allowed-tools` frontmatter field.
+    /// `None` when the field is absent: no restriction.
+    pub allowed_tools: Option<Vec<String>>,
+    /// Frontmatter fields other than the ones above (e.g. `license`,
+    /// `metadata`), converted to JSON values.
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Subdirectory of a skill that a [`SkillResource`] was found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ResourceKind {
+    /// `scripts/` — executable helpers.
+    Script,
+    /// `references/` — documentation loaded on demand.
+    Reference,
+    /// `assets/` — templates, images and other static files.
+    Asset,
+    /// `examples/` — sample inputs and outputs.
+    Example,
+}
+
+impl ResourceKind {
+    const ALL: [ResourceKind; 4] = [
+        ResourceKind::Script,
+        ResourceKind::Reference,
+        ResourceKind::Asset,
+        ResourceKind::Example,
+    ];
+
+    /// The subdirectory name, e.g. `"scripts"`.
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            ResourceKind::Script => "scripts",
+            ResourceKind::Reference => "references",
+            ResourceKind::Asset => "assets",
+            ResourceKind::Example => "examples",
+        }
+    }
+}
+
+/// A file bundled with a skill.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SkillResource {
+    /// File name, without directories. Pass to [`Skill::read_resource`].
+    pub name: String,
+    /// Absolute path to the file
+    pub path: PathBuf,
+    pub kind: ResourceKind,
+}
+
+impl Skill {
+    /// Whether the skill may use the tool named `name`. Names compare
+    /// case-insensitively, so `Bash` allows `bash`.
+    pub fn allows_tool(&self, name: &str) -> bool {
+        self.allowed_tools
+            .as_ref()
+            .is_none_or(|allowed| allowed.iter().any(|a| a.eq_ignore_ascii_case(name)))
+    }
+
+    /// Keep only the tools the skill may use, in their original order, e.g.
+    /// to build a sub-agent that runs the skill:
+    ///
+    /// ```rust,ignore
+    /// let sub = SubAgentTool::from_config("git", model)
+    ///     .with_tools(skill.filter_tools(shared_tools));
+    /// ```
+    ///
+    /// Works with both `Box<dyn AgentTool>` and `Arc<dyn AgentTool>`. Without
+    /// `allowed-tools`, every tool is kept.
+    pub fn filter_tools<T>(&self, tools: Vec<T>) -> Vec<T>
+    where
+        T: std::ops::Deref<Target = dyn AgentTool>,
+    {
+        tools
+            .into_iter()
+            .filter(|tool| self.allows_tool(tool.name()))
+            .collect()
+    }
+
+    /// The SKILL.md body: everything after the closing `---` of the
+    /// frontmatter. Read from disk on each call, so edits are picked up.
+    pub fn load_instructions(&self) -> Result<String, SkillError> {
+        let content = fs::read_to_string(&self.file_path).map_err(|e| SkillError::Io {
+            path: self.file_path.clone(),
+            source: e,
+        })?;
+        let (_, body) = split_frontmatter(&content, &self.file_path)?;
+        Ok(body.trim_start_matches(['\r', '\n']).to_string())
+    }
+
+    /// Files under the skill's `scripts/`, `references/`, `assets/` and
+    /// `examples/` directories (recursively), grouped by kind and sorted by
+    /// path. Missing or unreadable directories are skipped.
+    pub fn list_resources(&self) -> Vec<SkillResource> {
+        let mut resources = Vec::new();
+        for kind in ResourceKind::ALL {
+            let mut files = Vec::new();
+            collect_files(&self.base_dir.join(kind.dir_name()), &mut files);
+            files.sort();
+            resources.extend(files.into_iter().map(|path| {
+                SkillResource {
+                    name: path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    path,
+                    kind,
+                }
+            }));
+        }
+        resources
+    }
+
+    /// Contents of the resource whose file name is `name`. Only files
+    /// returned by [`list_resources`](Self::list_resources) can be read; if
+    /// two share a name, the first listed wins.
+    pub fn read_resource(&self, name: &str) -> Result<String, SkillError> {
+        let resource = self
+            .list_resources()
+            .into_iter()
+            .find(|r| r.name == name)
+            .ok_or_else(|| SkillError::ResourceNotFound {
+                skill: self.name.clone(),
+                name: name.to_string(),
+            })?;
+        fs::read_to_string(&resource.path).map_err(|e| SkillError::Io {
+            path: resource.path,
+            source: e,
+        })
+    }
+}
+
+fn collect_files(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        // `file_type` doesn't follow symlinks, so a linked directory (and
+        // with it any link cycle) is never descended into. Links to files
+        // are still listed.
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            collect_files(&path, out);
+        } else if path.is_file() {
+            out.push(path);
+        }
+    }
 }
 
 /// A collection of loaded skills.
@@ -59,6 +226,14 @@ pub enum SkillError {
     MissingField { path: PathBuf, field: &'static str },
     #[error("SKILL.md in {path} has invalid frontmatter: {detail}")]
     InvalidFrontmatter { path: PathBuf, detail: String },
+    #[error("skill {skill} has no resource named {name}")]
+    ResourceNotFound { skill: String, name: String },
+    #[cfg(feature = "watch")]
+    #[error("cannot watch {path}: {source}")]
+    Watch {
+        path: PathBuf,
+        source: notify::Error,
+    },
 }
 
 impl SkillSet {
@@ -84,6 +259,40 @@ impl SkillSet {
         Ok(Self { skills })
     }
 
+    /// Reload the skills in `dirs` whenever a file under them is created,
+    /// modified or deleted, and pass the fresh set to `callback`.
+    ///
+    /// Events within 100 ms of each other are coalesced into one reload.
+    /// `callback` runs on a dedicated thread, never on the watcher's own. A
+    /// reload that fails (e.g. a half-written `SKILL.md`) is logged and
+    /// skipped; the next save retries. Directories that don't exist yet are
+    /// not watched, as with [`load`](Self::load). Watching stops when the
+    /// returned [`SkillWatcher`] is dropped.
+    #[cfg(feature = "watch")]
+    pub fn watch(
+        dirs: Vec<PathBuf>,
+        callback: impl Fn(SkillSet) + Send + 'static,
+    ) -> Result<SkillWatcher, SkillError> {
+        use notify::Watcher;
+
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(|source| SkillError::Watch {
+            path: dirs.first().cloned().unwrap_or_default(),
+            source,
+        })?;
+        for dir in dirs.iter().filter(|d| d.exists()) {
+            watcher
+                .watch(dir, notify::RecursiveMode::Recursive)
+                .map_err(|source| SkillError::Watch {
+                    path: dir.clone(),
+                    source,
+                })?;
+        }
+
+        std::thread::spawn(move || reload_on_change(rx, dirs, callback));
+        Ok(SkillWatcher { _watcher: watcher })
+    }
+
     /// Load skills from a single directory with a custom source label.
     pub fn load_dir(dir: impl AsRef<Path>, source: &str) -> Result<Self, SkillError> {
         let skills = load_skills_from_dir(dir.as_ref(), source)?;
@@ -121,6 +330,34 @@ impl SkillSet {
         self.skills.is_empty()
     }
 
+    /// One tool per skill, named `skill_<name>` and described by the skill's
+    /// description. Calling it returns the skill's instructions followed by
+    /// its resource listing, so the agent can activate a skill on demand
+    /// without file tools.
+    pub fn as_tools(&self) -> Vec<Box<dyn AgentTool>> {
+        self.skills
+            .iter()
+            .map(|skill| {
+                Box::new(SkillTool {
+                    tool_name: format!("skill_{}", skill.name),
+                    skill: skill.clone(),
+                }) as Box<dyn AgentTool>
+            })
+            .collect()
+    }
+
+    /// A single `activate_skill` tool covering every skill in the set. It
+    /// takes a skill `name` and returns the same text as that skill's
+    /// [`as_tools`](Self::as_tools) tool. Use it instead of `as_tools` when
+    /// there are many skills, so the tool list stays short; the skills index
+    /// from [`format_for_prompt`](Self::format_for_prompt) tells the model
+    /// which names exist.
+    pub fn activate_tool(&self) -> Box<dyn AgentTool> {
+        Box::new(ActivateSkillTool {
+            skills: self.skills.clone(),
+        })
+    }
+
     /// Format skills for inclusion in a system prompt.
     ///
     /// Uses XML format per the [AgentSkills standard](https://agentskills.io/integrate-skills):
@@ -159,6 +396,182 @@ impl SkillSet {
     }
 }
 
+/// Handle to a running [`SkillSet::watch`]. Dropping it stops the watcher;
+/// the reload thread exits once any in-flight callback returns.
+#[cfg(feature = "watch")]
+pub struct SkillWatcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(feature = "watch")]
+impl std::fmt::Debug for SkillWatcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SkillWatcher").finish_non_exhaustive()
+    }
+}
+
+/// Body of the [`SkillSet::watch`] thread. Ends when the watcher is dropped,
+/// which closes the event channel.
+#[cfg(feature = "watch")]
+fn reload_on_change(
+    rx: mpsc::Receiver<notify::Result<notify::Event>>,
+    dirs: Vec<PathBuf>,
+    callback: impl Fn(SkillSet),
+) {
+    let is_change = |event: notify::Result<notify::Event>| match event {
+        Ok(event) => matches!(
+            event.kind,
+            notify::EventKind::Create(_)
+                | notify::EventKind::Modify(_)
+                | notify::EventKind::Remove(_)
+        ),
+        Err(e) => {
+            tracing::warn!(error = %e, "skill watcher error");
+            false
+        }
+    };
+
+    while let Ok(event) = rx.recv() {
+        let mut changed = is_change(event);
+        loop {
+            match rx.recv_timeout(WATCH_DEBOUNCE) {
+                Ok(event) => changed |= is_change(event),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+        }
+        if !changed {
+            continue;
+        }
+        match SkillSet::load(&dirs) {
+            Ok(skills) => callback(skills),
+            Err(e) => tracing::warn!(error = %e, "skill reload failed"),
+        }
+    }
+}
+
+/// Activates one skill; see [`SkillSet::as_tools`].
+#[derive(Clone)]
+struct SkillTool {
+    tool_name: String,
+    skill: Skill,
+}
+
+#[async_trait]
+impl AgentTool for SkillTool {
+    fn name(&self) -> &str {
+        &self.tool_name
+    }
+
+    fn label(&self) -> &str {
+        &self.skill.name
+    }
+
+    fn description(&self) -> &str {
+        &self.skill.description
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({"type": "object", "properties": {}})
+    }
+
+    async fn execute(
+        &self,
+        _params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        activate(&self.skill)
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+/// Activates any skill by name; see [`SkillSet::activate_tool`].
+#[derive(Clone)]
+struct ActivateSkillTool {
+    skills: Vec<Skill>,
+}
+
+#[async_trait]
+impl AgentTool for ActivateSkillTool {
+    fn name(&self) -> &str {
+        "activate_skill"
+    }
+
+    fn label(&self) -> &str {
+        "Activate skill"
+    }
+
+    fn description(&self) -> &str {
+        "Load a skill's full instructions and list its resource files. \
+         The available skills are listed in the system prompt."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        let names: Vec<&str> = self.skills.iter().map(|s| s.name.as_str()).collect();
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "name": {
+                    "type": "string",
+                    "description": "Name of the skill to activate",
+                    "enum": names,
+                }
+            },
+            "required": ["name"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let name = params["name"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'name' parameter".into()))?;
+        let skill = self.skills.iter().find(|s| s.name == name).ok_or_else(|| {
+            let names: Vec<&str> = self.skills.iter().map(|s| s.name.as_str()).collect();
+            ToolError::InvalidArgs(format!(
+                "unknown skill '{}'; available: {}",
+                name,
+                names.join(", ")
+            ))
+        })?;
+        activate(skill)
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+/// The skill's instructions followed by its resource listing.
+fn activate(skill: &Skill) -> Result<ToolResult, ToolError> {
+    let instructions = skill
+        .load_instructions()
+        .map_err(|e| ToolError::Failed(e.to_string()))?;
+    let resources = skill.list_resources();
+
+    let mut text = instructions;
+    if !resources.is_empty() {
+        text.push_str("\n\n## Resources\n");
+        for r in &resources {
+            text.push_str(&format!("- {}\n", r.path.display()));
+        }
+    }
+    Ok(ToolResult {
+        content: vec![Content::Text { text }],
+        details: serde_json::json!({
+            "skill": skill.name,
+            "base_dir": skill.base_dir,
+            "resources": resources.len(),
+        }),
+    })
+}
+
 /// Scan a directory for skills. Looks for:
 /// - `<dir>/<name>/SKILL.md` (standard layout)
 fn load_skills_from_dir(dir: &Path, source: &str) -> Result<Vec<Skill>, SkillError> {
@@ -189,7 +602,8 @@ fn load_skills_from_dir(dir: &Path, source: &str) -> Result<Vec<Skill>, SkillErr
             source: e,
         })?;
 
-        let (name, description) = parse_frontmatter(&content, &skill_md)?;
+        let frontmatter = parse_frontmatter(&content, &skill_md)?;
+        let name = frontmatter.name;
 
         // Validate name matches directory
         let dir_name = path
@@ -206,10 +620,12 @@ fn load_skills_from_dir(dir: &Path, source: &str) -> Result<Vec<Skill>, SkillErr
 
         skills.push(Skill {
             name,
-            description,
+            description: frontmatter.description,
             file_path,
             base_dir,
             source: source.to_string(),
+            allowed_tools: frontmatter.allowed_tools.map(ToolList::into_names),
+            extra: frontmatter.extra,
         });
     }
 
@@ -217,67 +633,151 @@ fn load_skills_from_dir(dir: &Path, source: &str) -> Result<Vec<Skill>, SkillErr
     Ok(skills)
 }
 
-/// Parse YAML frontmatter from SKILL.md content.
+/// SKILL.md frontmatter. Missing `name`/`description` deserialize as empty
+/// and are rejected by [`parse_frontmatter`].
+#[derive(Debug, Default, Deserialize)]
+struct SkillFrontmatter {
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default, rename = "allowed-tools")]
+    allowed_tools: Option<ToolList>,
+    /// Every other field, e.g. `license` or `metadata`.
+    #[serde(flatten)]
+    extra: serde_json::Map<String, serde_json::Value>,
+}
+
+/// `allowed-tools` as a YAML list or a comma/space-separated string.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ToolList {
+    List(Vec<String>),
+    Inline(String),
+}
+
+impl ToolList {
+    fn into_names(self) -> Vec<String> {
+        match self {
+            Self::List(names) => names,
+            Self::Inline(value) => parse_tool_list(&value),
+        }
+    }
+}
+
+/// Parse the frontmatter of SKILL.md content.
 /// Expects `---\n...\n---` block at the start.
-fn parse_frontmatter(content: &str, path: &Path) -> Result<(String, String), SkillError> {
-    let trimmed = content.trim_start();
-    if !trimmed.starts_with("---") {
-        return Err(SkillError::InvalidFrontmatter {
+///
+/// The block is read as YAML. Many published skills have frontmatter that
+/// isn't valid YAML, most often an unquoted description containing `: `;
+/// those fall back to a line-by-line `key: value` reading.
+fn parse_frontmatter(content: &str, path: &Path) -> Result<SkillFrontmatter, SkillError> {
+    let (yaml_block, _) = split_frontmatter(content, path)?;
+
+    let mut frontmatter = if yaml_block.trim().is_empty() {
+        SkillFrontmatter::default()
+    } else {
+        serde_yaml_ng::from_str(yaml_block).unwrap_or_else(|_| scan_frontmatter(yaml_block))
+    };
+    frontmatter.name = frontmatter.name.trim().to_string();
+    frontmatter.description = frontmatter.description.trim().to_string();
+
+    if frontmatter.name.is_empty() {
+        return Err(SkillError::MissingField {
             path: path.to_path_buf(),
-            detail: "missing opening ---".into(),
+            field: "name",
         });
     }
-
-    let after_open = &trimmed[3..];
-    let end = after_open
-        .find("\n---")
-        .ok_or(SkillError::InvalidFrontmatter {
+    if frontmatter.description.is_empty() {
+        return Err(SkillError::MissingField {
             path: path.to_path_buf(),
-            detail: "missing closing ---".into(),
-        })?;
+            field: "description",
+        });
+    }
 
-    let yaml_block = &after_open[..end];
+    Ok(frontmatter)
+}
 
-    let mut name = None;
-    let mut description = None;
+/// Lenient fallback for frontmatter that isn't valid YAML: top-level
+/// `name:`, `description:` and `allowed-tools:` lines, one value per line.
+fn scan_frontmatter(yaml_block: &str) -> SkillFrontmatter {
+    let mut frontmatter = SkillFrontmatter::default();
+    let mut tools: Option<Vec<String>> = None;
+    // Inside an `allowed-tools:` block list (`- item` lines).
+    let mut in_tool_list = false;
 
     for line in yaml_block.lines() {
         let line = line.trim();
+        if in_tool_list {
+            if let Some(item) = line.strip_prefix('-') {
+                tools
+                    .get_or_insert_with(Vec::new)
+                    .push(unquote(item.trim()));
+                continue;
+            }
+            in_tool_list = line.is_empty();
+        }
         if let Some(rest) = line.strip_prefix("name:") {
-            name = Some(unquote(rest.trim()));
+            frontmatter.name = unquote(rest.trim());
         } else if let Some(rest) = line.strip_prefix("description:") {
-            description = Some(unquote(rest.trim()));
+            frontmatter.description = unquote(rest.trim());
+        } else if let Some(rest) = line.strip_prefix("allowed-tools:") {
+            in_tool_list = rest.trim().is_empty();
+            tools = Some(parse_tool_list(rest));
         }
     }
 
-    let name = name.ok_or(SkillError::MissingField {
-        path: path.to_path_buf(),
-        field: "name",
-    })?;
-    let description = description.ok_or(SkillError::MissingField {
-        path: path.to_path_buf(),
-        field: "description",
-    })?;
+    frontmatter.allowed_tools = tools.map(ToolList::List);
+    frontmatter
+}
 
-    if name.is_empty() {
-        return Err(SkillError::MissingField {
+/// An inline tool list: `[bash, read_file]`, `bash, read_file` or
+/// `bash read_file`.
+fn parse_tool_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .map(unquote)
+        .filter(|tool| !tool.is_empty())
+        .collect()
+}
+
+/// Split SKILL.md content into the frontmatter block and the body after the
+/// closing `---` line.
+fn split_frontmatter<'a>(content: &'a str, path: &Path) -> Result<(&'a str, &'a str), SkillError> {
+    let trimmed = content.trim_start();
+    if !trimmed.starts_with("---") {
+        return Err(SkillError::InvalidFrontmatter {
             path: path.to_path_buf(),
-            field: "name",
+            detail: "missing opening ---".into(),
         });
     }
-    if description.is_empty() {
-        return Err(SkillError::MissingField {
+
+    let after_open = &trimmed[3..];
+    let end = after_open
+        .find("\n---")
+        .ok_or(SkillError::InvalidFrontmatter {
             path: path.to_path_buf(),
-            field: "description",
-        });
-    }
+            detail: "missing closing ---".into(),
+        })?;
 
-    Ok((name, description))
+    // The body starts on the line after the closing fence.
+    let after_close = &after_open[end + 4..];
+    let body = after_close
+        .find('\n')
+        .map_or("", |newline| &after_close[newline + 1..]);
+    Ok((&after_open[..end], body))
 }
 
 /// Remove surrounding quotes from a YAML value.
 fn unquote(s: &str) -> String {
-    if (s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')) {
+    let quoted =
+        (s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\''));
+    if quoted && s.len() >= 2 {
         s[1..s.len() - 1].to_string()
     } else {
         s.to_string()
@@ -426,6 +926,89 @@ mod tests {
         let skills = SkillSet::load(&[tmp.path()]).unwrap();
         assert_eq!(skills.skills()[0].name, "quoted");
         assert_eq!(skills.skills()[0].description, "A quoted description.");
+        assert_eq!(skills.skills()[0].allowed_tools, None);
+    }
+
+    #[test]
+    fn allowed_tools_frontmatter_forms() {
+        let tmp = TempDir::new().unwrap();
+        for (name, field) in [
+            ("inline", "allowed-tools: [bash, \"read_file\"]"),
+            ("commas", "allowed-tools: bash, read_file"),
+            ("spaces", "allowed-tools: Bash Read_File"),
+            ("block", "allowed-tools:\n  - bash\n  - 'read_file'"),
+        ] {
+            let skill_dir = tmp.path().join(name);
+            fs::create_dir_all(&skill_dir).unwrap();
+            fs::write(
+                skill_dir.join("SKILL.md"),
+                format!("---\nname: {name}\n{field}\ndescription: Test.\n---\n"),
+            )
+            .unwrap();
+        }
+
+        let skills = SkillSet::load(&[tmp.path()]).unwrap();
+        for skill in skills.skills() {
+            let allowed: Vec<String> = skill
+                .allowed_tools
+                .clone()
+                .unwrap()
+                .iter()
+                .map(|t| t.to_lowercase())
+                .collect();
+            assert_eq!(allowed, ["bash", "read_file"], "{}", skill.name);
+            assert_eq!(skill.description, "Test.", "{}", skill.name);
+        }
+    }
+
+    #[test]
+    fn allowed_tools_with_stray_quote() {
+        let tmp = TempDir::new().unwrap();
+        let skill_dir = tmp.path().join("stray");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(
+            skill_dir.join("SKILL.md"),
+            "---\nname: stray\ndescription: Test.\nallowed-tools: Bash, Read \"\n---\n",
+        )
+        .unwrap();
+
+        let skills = SkillSet::load(&[tmp.path()]).unwrap();
+        assert_eq!(
+            skills.skills()[0].allowed_tools.clone().unwrap(),
+            ["Bash", "Read", "\""]
+        );
+    }
+
+    #[test]
+    fn filter_tools_keeps_allowed_tools() {
+        let tmp = TempDir::new().unwrap();
+        let skill_dir = tmp.path().join("git");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(
+            skill_dir.join("SKILL.md"),
+            "---\nname: git\ndescription: Git.\nallowed-tools: [bash, read_file]\n---\n",
+        )
+        .unwrap();
+        create_skill(tmp.path(), "open", "No restriction.");
+
+        let skills = SkillSet::load(&[tmp.path()]).unwrap();
+        let (git, open) = (&skills.skills()[0], &skills.skills()[1]);
+
+        let names = |tools: &[Box<dyn AgentTool>]| -> Vec<String> {
+            tools.iter().map(|t| t.name().to_string()).collect()
+        };
+        let filtered = git.filter_tools(crate::tools::default_tools());
+        assert_eq!(names(&filtered), ["bash", "read_file"]);
+        assert!(!git.allows_tool("write_file"));
+
+        let all = crate::tools::default_tools().len();
+        assert_eq!(open.filter_tools(crate::tools::default_tools()).len(), all);
+
+        let shared: Vec<std::sync::Arc<dyn AgentTool>> = crate::tools::default_tools()
+            .into_iter()
+            .map(std::sync::Arc::from)
+            .collect();
+        assert_eq!(git.filter_tools(shared).len(), 2);
     }
 
     #[test]
@@ -473,9 +1056,192 @@ mod tests {
         );
     }
 
+    #[test]
+    fn load_instructions_returns_body() {
+        let tmp = TempDir::new().unwrap();
+        create_skill(tmp.path(), "weather", "Get weather.");
+        let skills = SkillSet::load(&[tmp.path()]).unwrap();
+
+        let instructions = skills.skills()[0].load_instructions().unwrap();
+        assert_eq!(instructions, "# weather\n\nInstructions here.\n");
+    }
+
+    #[test]
+    fn list_and_read_resources() {
+        let tmp = TempDir::new().unwrap();
+        create_skill(tmp.path(), "deploy", "Deploy the app.");
+        let base = tmp.path().join("deploy");
+        fs::create_dir_all(base.join("scripts")).unwrap();
+        fs::create_dir_all(base.join("references/api")).unwrap();
+        fs::write(base.join("scripts/run.sh"), "echo deploy").unwrap();
+        fs::write(base.join("references/api/endpoints.md"), "# Endpoints").unwrap();
+        fs::write(base.join("notes.txt"), "not a resource").unwrap();
+
+        let skill = SkillSet::load(&[tmp.path()]).unwrap().skills()[0].clone();
+        let resources = skill.list_resources();
+        let listed: Vec<_> = resources
+            .iter()
+            .map(|r| (r.name.as_str(), r.kind))
+            .collect();
+        assert_eq!(
+            listed,
+            vec![
+                ("run.sh", ResourceKind::Script),
+                ("endpoints.md", ResourceKind::Reference)
+            ]
+        );
+
+        assert_eq!(skill.read_resource("endpoints.md").unwrap(), "# Endpoints");
+        assert!(matches!(
+            skill.read_resource("notes.txt"),
+            Err(SkillError::ResourceNotFound { .. })
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn resource_listing_does_not_follow_symlinked_dirs() {
+        let tmp = TempDir::new().unwrap();
+        create_skill(tmp.path(), "deploy", "Deploy the app.");
+        let scripts = tmp.path().join("deploy/scripts");
+        fs::create_dir_all(&scripts).unwrap();
+        fs::write(scripts.join("run.sh"), "echo deploy").unwrap();
+        // scripts/loop -> scripts: a cycle if followed.
+        std::os::unix::fs::symlink(&scripts, scripts.join("loop")).unwrap();
+        std::os::unix::fs::symlink(scripts.join("run.sh"), scripts.join("alias.sh")).unwrap();
+
+        let skill = SkillSet::load(&[tmp.path()]).unwrap().skills()[0].clone();
+        let mut names: Vec<_> = skill.list_resources().into_iter().map(|r| r.name).collect();
+        names.sort();
+        assert_eq!(names, ["alias.sh", "run.sh"]);
+        assert_eq!(skill.read_resource("run.sh").unwrap(), "echo deploy");
+    }
+
+    #[tokio::test]
+    async fn skill_tools_return_instructions_and_resources() {
+        let tmp = TempDir::new().unwrap();
+        create_skill(tmp.path(), "deploy", "Deploy the app.");
+        fs::create_dir_all(tmp.path().join("deploy/scripts")).unwrap();
+        fs::write(tmp.path().join("deploy/scripts/run.sh"), "echo deploy").unwrap();
+
+        let tools = SkillSet::load(&[tmp.path()]).unwrap().as_tools();
+        assert_eq!(tools.len(), 1);
+        assert_eq!(tools[0].name(), "skill_deploy");
+        assert_eq!(tools[0].description(), "Deploy the app.");
+
+        let ctx = ToolContext {
+            tool_call_id: "tc-1".into(),
+            tool_name: "skill_deploy".into(),
+            cancel: tokio_util::sync::CancellationToken::new(),
+            on_update: None,
+            on_progress: None,
+            timeout: None,
+        };
+        let result = tools[0].execute(serde_json::json!({}), ctx).await.unwrap();
+        let Content::Text { text } = &result.content[0] else {
+            panic!("expected text");
+        };
+        assert!(text.starts_with("# deploy\n\nInstructions here."));
+        assert!(text.contains("## Resources\n"));
+        assert!(text.contains("scripts/run.sh"));
+    }
+
+    #[tokio::test]
+    async fn activate_skill_tool_loads_skill_by_name() {
+        let tmp = TempDir::new().unwrap();
+        create_skill(tmp.path(), "deploy", "Deploy the app.");
+        create_skill(tmp.path(), "review", "Review a change.");
+        fs::create_dir_all(tmp.path().join("deploy/scripts")).unwrap();
+        fs::write(tmp.path().join("deploy/scripts/run.sh"), "echo deploy").unwrap();
+
+        let tool = SkillSet::load(&[tmp.path()]).unwrap().activate_tool();
+        assert_eq!(tool.name(), "activate_skill");
+        let mut names: Vec<_> = tool.parameters_schema()["properties"]["name"]["enum"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|n| n.as_str().unwrap().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["deploy", "review"]);
+
+        let ctx = || ToolContext {
+            tool_call_id: "tc-1".into(),
+            tool_name: "activate_skill".into(),
+            cancel: tokio_util::sync::CancellationToken::new(),
+            on_update: None,
+            on_progress: None,
+            timeout: None,
+        };
+        let result = tool
+            .execute(serde_json::json!({"name": "deploy"}), ctx())
+            .await
+            .unwrap();
+        let Content::Text { text } = &result.content[0] else {
+            panic!("expected text");
+        };
+        assert!(text.starts_with("# deploy\n\nInstructions here."));
+        assert!(text.contains("## Resources\n"), "{}", text);
+        assert!(text.contains("scripts/run.sh"), "{}", text);
+        assert_eq!(result.details["skill"], "deploy");
+        assert_eq!(result.details["resources"], 1);
+
+        let err = tool
+            .execute(serde_json::json!({"name": "missing"}), ctx())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::InvalidArgs(_)), "{}", err);
+        assert!(err.to_string().contains("deploy"), "{}", err);
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn watch_reloads_on_change() {
+        let tmp = TempDir::new().unwrap();
+        create_skill(tmp.path(), "weather", "Get weather.");
+
+        let (tx, rx) = mpsc::channel();
+        let _watcher = SkillSet::watch(vec![tmp.path().to_path_buf()], move |skills| {
+            let _ = tx.send(skills);
+        })
+        .unwrap();
+
+        create_skill(tmp.path(), "weather", "Get weather and forecasts.");
+
+        let deadline = std::time::Instant::now() + Duration::from_millis(500);
+        loop {
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            let skills = rx.recv_timeout(remaining).expect("no reload within 500 ms");
+            if skills.skills()[0].description == "Get weather and forecasts." {
+                break;
+            }
+        }
+    }
+
+    #[cfg(feature = "watch")]
+    #[test]
+    fn watch_stops_when_dropped() {
+        let tmp = TempDir::new().unwrap();
+        create_skill(tmp.path(), "weather", "Get weather.");
+
+        let (tx, rx) = mpsc::channel();
+        let watcher = SkillSet::watch(vec![tmp.path().to_path_buf()], move |skills| {
+            let _ = tx.send(skills);
+        })
+        .unwrap();
+        drop(watcher);
+
+        create_skill(tmp.path(), "weather", "Get weather and forecasts.");
+        // The reload thread exits, dropping the callback and its sender.
+        assert!(matches!(
+            rx.recv_timeout(Duration::from_millis(500)),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        ));
+    }
+
     #[test]
     fn load_real_agentskills_format() {
-        // Test with metadata field (should be ignored, we only parse name+description)
+        // Nested metadata in flow style, kept in `extra`.
         let tmp = TempDir::new().unwrap();
         let skill_dir = tmp.path().join("nano-banana-pro");
         fs::create_dir_all(&skill_dir).unwrap();
@@ -508,5 +1274,74 @@ Use the bundled script to generate images.
             skills.skills()[0].description,
             "Generate or edit images via Gemini 3 Pro Image."
         );
+        assert_eq!(
+            skills.skills()[0].extra["metadata"]["openclaw"]["requires"]["bins"],
+            serde_json::json!(["uv"])
+        );
+    }
+
+    #[test]
+    fn yaml_block_scalars_and_nested_fields() {
+        let tmp = TempDir::new().unwrap();
+        let skill_dir = tmp.path().join("release");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(
+            skill_dir.join("SKILL.md"),
+            r#"---
+name: release
+description: >
+  Cut a release: bump the version,
+  tag it and publish.
+metadata:
+  name: not-the-skill-name
+  tags:
+    - ops
+    - git
+allowed-tools: [
+  bash,
+  read_file
+]
+license: MIT
+---
+"#,
+        )
+        .unwrap();
+
+        let skills = SkillSet::load(&[tmp.path()]).unwrap();
+        let skill = &skills.skills()[0];
+        assert_eq!(skill.name, "release");
+        assert_eq!(
+            skill.description,
+            "Cut a release: bump the version, tag it and publish."
+        );
+        assert_eq!(
+            skill.allowed_tools,
+            Some(vec!["bash".to_string(), "read_file".to_string()])
+        );
+        assert_eq!(skill.extra["metadata"]["name"], "not-the-skill-name");
+        assert_eq!(
+            skill.extra["metadata"]["tags"],
+            serde_json::json!(["ops", "git"])
+        );
+        assert_eq!(skill.extra["license"], "MIT");
+    }
+
+    #[test]
+    fn literal_block_description() {
+        let tmp = TempDir::new().unwrap();
+        let skill_dir = tmp.path().join("notes");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(
+            skill_dir.join("SKILL.md"),
+            "---\nname: notes\ndescription: |\n  Take notes.\n  Use for meetings.\n---\n",
+        )
+        .unwrap();
+
+        let skills = SkillSet::load(&[tmp.path()]).unwrap();
+        assert_eq!(
+            skills.skills()[0].description,
+            "Take notes.\nUse for meetings."
+        );
+        assert!(skills.skills()[0].extra.is_empty());
     }
 }
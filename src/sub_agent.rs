@@ -6,10 +6,18 @@
 //!
 //! # Design
 //!
-//! - **Context isolation**: each invocation starts a fresh conversation
+//! - **Context isolation**: each invocation starts a fresh conversation,
+//!   unless a shared context is attached with `with_shared_context()`
 //! - **Nesting supported**: sub-agents can contain other SubAgentTools for recursive delegation (use `with_max_turns()` to bound depth)
+//! - **Committed answers**: `with_final_answer_tool(true)` gives the sub-agent
+//!   a `final_answer` tool whose payload becomes the result
+//! - **Budgets**: `with_execution_limits()` caps tokens, time and cost; the
+//!   sub-agent's usage is reported in `details.usage`
+//! - **History**: `with_include_history(true)` returns the sub-agent's
+//!   messages in `details.conversation`
 //! - **Cancellation propagation**: the parent's cancel token is forwarded
-//! - **Event forwarding**: sub-agent events stream to the parent via `on_update`
+//! - **Event forwarding**: sub-agent events stream to the parent via `on_update`;
+//!   `with_event_forwarding(true)` adds structured tool and turn events
 //!
 //! # Example
 //!
@@ -44,6 +52,7 @@ const DEFAULT_MAX_TURNS: usize = 10;
 /// When the parent LLM calls this tool, it spawns a fresh `agent_loop()` with
 /// its own system prompt, tools, and provider. The sub-agent runs to completion
 /// and its final text output is returned as the tool result.
+#[derive(Clone)]
 pub struct SubAgentTool {
     tool_name: String,
     tool_description: String,
@@ -59,11 +68,18 @@ pub struct SubAgentTool {
     cache_config: CacheConfig,
     tool_execution: ToolExecutionStrategy,
     retry_config: crate::retry::RetryConfig,
-    max_turns: usize,
+    circuit_breaker: Option<Arc<crate::retry::CircuitBreaker>>,
+    rate_limiter: Option<Arc<crate::rate_limit::RateLimiter>>,
+    execution_limits: ExecutionLimits,
     shared_state: Option<SharedState>,
     turn_delay: Option<std::time::Duration>,
     model_config: Option<ModelConfig>,
     tool_middleware: Vec<Arc<dyn ToolMiddleware>>,
+    tool_timeout: Option<std::time::Duration>,
+    shared_context: Option<Arc<tokio::sync::Mutex<AgentContext>>>,
+    forward_events: bool,
+    final_answer_tool: bool,
+    include_history: bool,
 }
 
 impl SubAgentTool {
@@ -97,11 +113,23 @@ impl SubAgentTool {
             cache_config: CacheConfig::default(),
             tool_execution: ToolExecutionStrategy::default(),
             retry_config: crate::retry::RetryConfig::default(),
-            max_turns: DEFAULT_MAX_TURNS,
+            circuit_breaker: None,
+            rate_limiter: None,
+            execution_limits: ExecutionLimits {
+                max_turns: DEFAULT_MAX_TURNS,
+                // Generous token/duration limits — turn limit is the primary guard
+                max_duration: std::time::Duration::from_secs(300),
+                ..ExecutionLimits::default()
+            },
             shared_state: None,
             turn_delay: None,
             model_config: None,
             tool_middleware: Vec::new(),
+            tool_timeout: None,
+            shared_context: None,
+            forward_events: false,
+            final_answer_tool: false,
+            include_history: false,
         }
     }
 
@@ -203,6 +231,15 @@ impl SubAgentTool {
         self
     }
 
+    /// Add tools that are also used elsewhere, typically by the parent
+    /// agent. Calls go to the same instances, so state such as a REPL
+    /// process or a cache is shared rather than duplicated. Unlike
+    /// [`with_tools`](Self::with_tools) this appends.
+    pub fn with_shared_tools(mut self, tools: Vec<Arc<dyn AgentTool>>) -> Self {
+        self.tools.extend(tools);
+        self
+    }
+
     /// Add a tool middleware for the sub-agent's own tool calls. Mirrors
     /// [`Agent::with_tool_middleware`](crate::Agent::with_tool_middleware).
     pub fn with_tool_middleware(mut self, middleware: impl ToolMiddleware + 'static) -> Self {
@@ -210,6 +247,13 @@ impl SubAgentTool {
         self
     }
 
+    /// Limit how long any of the sub-agent's own tool calls may run. Mirrors
+    /// [`Agent::with_tool_timeout`](crate::Agent::with_tool_timeout).
+    pub fn with_tool_timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
     pub fn with_thinking(mut self, level: ThinkingLevel) -> Self {
         self.thinking_level = level;
         self
@@ -243,8 +287,31 @@ impl SubAgentTool {
         self
     }
 
+    /// Share a [`CircuitBreaker`](crate::retry::CircuitBreaker) with the
+    /// parent (or other sub-agents) on the same provider.
+    pub fn with_circuit_breaker(mut self, breaker: Arc<crate::retry::CircuitBreaker>) -> Self {
+        self.circuit_breaker = Some(breaker);
+        self
+    }
+
+    /// Share a [`RateLimiter`](crate::rate_limit::RateLimiter) with the
+    /// parent so sub-agent calls count against the same budget.
+    pub fn with_rate_limiter(mut self, limiter: Arc<crate::rate_limit::RateLimiter>) -> Self {
+        self.rate_limiter = Some(limiter);
+        self
+    }
+
     pub fn with_max_turns(mut self, max: usize) -> Self {
-        self.max_turns = max;
+        self.execution_limits.max_turns = max;
+        self
+    }
+
+    /// Replace the sub-agent's execution limits — turns, total tokens,
+    /// duration, cost — which otherwise default to 10 turns, 1M tokens and
+    /// 5 minutes. When a limit stops the sub-agent, the reason is appended
+    /// to the returned text and set as `details.limit_reached`.
+    pub fn with_execution_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.execution_limits = limits;
         self
     }
 
@@ -256,6 +323,46 @@ impl SubAgentTool {
         self
     }
 
+    /// Keep the sub-agent's conversation across calls. Each call appends its
+    /// task and the sub-agent's work to `context.messages` and sees
+    /// everything earlier calls left there, so follow-up tasks can build on
+    /// what the sub-agent already read. The tool gains a `reset` parameter
+    /// that clears the history before the task runs.
+    ///
+    /// The context's system prompt and tools are set from this sub-agent on
+    /// every call; only the messages carry over. Calls sharing one context
+    /// run one at a time.
+    pub fn with_shared_context(mut self, context: Arc<tokio::sync::Mutex<AgentContext>>) -> Self {
+        self.shared_context = Some(context);
+        self
+    }
+
+    /// Forward the sub-agent's turn boundaries and tool executions to the
+    /// parent through `on_update`, so a UI can show what it is doing
+    /// ("researcher is running bash").
+    ///
+    /// Each forwarded event is a partial result whose text is a short status
+    /// line and whose `details` are `{"sub_agent": <name>, "event": {...}}`.
+    /// `event` follows the [`AgentEvent`] wire format, trimmed to what a
+    /// status display needs: `turnStart`, `turnEnd`, `toolExecutionStart`
+    /// (`toolCallId`, `toolName`, `args`) and `toolExecutionEnd`
+    /// (`toolCallId`, `toolName`, `isError`). Off by default.
+    pub fn with_event_forwarding(mut self, enabled: bool) -> Self {
+        self.forward_events = enabled;
+        self
+    }
+
+    /// Give the sub-agent a `final_answer` tool for committing its answer.
+    /// Calling it ends the run after that turn, and its `answer` becomes the
+    /// returned content instead of the last assistant text, keeping the
+    /// sub-agent's intermediate reasoning out of the parent's result. If the
+    /// tool is never called, the last assistant text is returned as usual.
+    /// `details.final_answer` says which happened.
+    pub fn with_final_answer_tool(mut self, enabled: bool) -> Self {
+        self.final_answer_tool = enabled;
+        self
+    }
+
     /// Add an inter-turn delay to throttle API requests.
     /// Useful when using OAuth tokens or providers with low rate limits.
     /// The delay is applied before each turn except the first.
@@ -264,6 +371,17 @@ impl SubAgentTool {
         self
     }
 
+    /// Add the sub-agent's messages from this call to the result as
+    /// `details.conversation` (the [`AgentMessage`] wire format), with the
+    /// summed token usage as `details.total_tokens` and the names of the
+    /// tools it called, in order, as `details.tools_called`. Lets an
+    /// orchestrating parent audit how an answer was reached. Off by default,
+    /// since the history can be large.
+    pub fn with_include_history(mut self, enabled: bool) -> Self {
+        self.include_history = enabled;
+        self
+    }
+
     /// Set the model configuration for multi-provider support.
     /// Required for non-Anthropic providers (OpenAI-compat, Google, etc.)
     /// to specify base URL, compat flags, and other provider-specific settings.
@@ -278,12 +396,14 @@ impl SubAgentTool {
     }
 }
 
-/// Thin adapter: wraps `Arc<dyn AgentTool>` so it can be placed in a
-/// `Vec<Box<dyn AgentTool>>` (required by `AgentContext`).
-struct ArcToolWrapper(Arc<dyn AgentTool>);
+/// Adapter that places a shared `Arc<dyn AgentTool>` where a
+/// `Box<dyn AgentTool>` is required (`AgentContext`, `Agent::with_tools`).
+/// Every call, including `timeout` and `validate_args`, forwards to the
+/// shared tool.
+pub struct SharedToolWrapper(pub Arc<dyn AgentTool>);
 
 #[async_trait::async_trait]
-impl AgentTool for ArcToolWrapper {
+impl AgentTool for SharedToolWrapper {
     fn name(&self) -> &str {
         self.0.name()
     }
@@ -303,6 +423,18 @@ impl AgentTool for ArcToolWrapper {
     ) -> Result<ToolResult, ToolError> {
         self.0.execute(params, ctx).await
     }
+    fn timeout(&self) -> Option<std::time::Duration> {
+        self.0.timeout()
+    }
+    fn validate_args(&self) -> bool {
+        self.0.validate_args()
+    }
+    fn cacheable(&self) -> bool {
+        self.0.cacheable()
+    }
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(SharedToolWrapper(Arc::clone(&self.0)))
+    }
 }
 
 #[async_trait::async_trait]
@@ -320,7 +452,7 @@ impl AgentTool for SubAgentTool {
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
-        serde_json::json!({
+        let mut schema = serde_json::json!({
             "type": "object",
             "properties": {
                 "task": {
@@ -329,7 +461,14 @@ impl AgentTool for SubAgentTool {
                 }
             },
             "required": ["task"]
-        })
+        });
+        if self.shared_context.is_some() {
+            schema["properties"]["reset"] = serde_json::json!({
+                "type": "boolean",
+                "description": "Clear the sub-agent's conversation from earlier calls before this task"
+            });
+        }
+        schema
     }
 
     async fn execute(
@@ -337,21 +476,50 @@ impl AgentTool for SubAgentTool {
         params: serde_json::Value,
         ctx: ToolContext,
     ) -> Result<ToolResult, ToolError> {
-        let cancel = ctx.cancel;
-        let on_update = ctx.on_update;
-        let on_progress = ctx.on_progress;
         // Extract the task parameter
         let task = params
             .get("task")
             .and_then(|v| v.as_str())
             .ok_or_else(|| ToolError::InvalidArgs("Missing required 'task' parameter".into()))?
             .to_string();
+        let reset = params
+            .get("reset")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false);
+        self.run_task(task, reset, ctx.cancel, ctx.on_update, ctx.on_progress)
+            .await
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
 
+impl SubAgentTool {
+    /// Run the sub-agent on `task` directly, without going through a parent
+    /// agent loop. This is what [`execute`](AgentTool::execute) does; the
+    /// returned future can be awaited alongside others (see [`AgentFanOut`]).
+    pub async fn run(
+        &self,
+        task: impl Into<String>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<ToolResult, ToolError> {
+        self.run_task(task.into(), false, cancel, None, None).await
+    }
+
+    async fn run_task(
+        &self,
+        task: String,
+        reset: bool,
+        cancel: tokio_util::sync::CancellationToken,
+        on_update: Option<ToolUpdateFn>,
+        on_progress: Option<ProgressFn>,
+    ) -> Result<ToolResult, ToolError> {
         // Build tool list from Arc wrappers
         let mut tools: Vec<Box<dyn AgentTool>> = self
             .tools
             .iter()
-            .map(|t| Box::new(ArcToolWrapper(Arc::clone(t))) as Box<dyn AgentTool>)
+            .map(|t| Box::new(SharedToolWrapper(Arc::clone(t))) as Box<dyn AgentTool>)
             .collect();
 
         // Append the skills index (if any) so the sub-agent can discover skills.
@@ -374,11 +542,45 @@ impl AgentTool for SubAgentTool {
             ));
         }
 
-        // Fresh context for the sub-agent
-        let mut context = AgentContext {
-            system_prompt,
-            messages: Vec::new(),
-            tools,
+        // The final_answer tool fills `final_answer`; the loop stops once it has.
+        let final_answer: Arc<std::sync::Mutex<Option<String>>> = Arc::default();
+        if self.final_answer_tool {
+            tools.push(Box::new(FinalAnswerTool {
+                answer: final_answer.clone(),
+            }));
+            system_prompt.push_str(
+                "\n\nWhen you have the answer, call the `final_answer` tool with it. Only that answer is returned.",
+            );
+        }
+        let stop_if: Option<crate::agent_loop::StopIfFn> = self.final_answer_tool.then(|| {
+            let answer = final_answer.clone();
+            Arc::new(move |_: &[AgentMessage]| {
+                answer.lock().unwrap_or_else(|e| e.into_inner()).is_some()
+            }) as crate::agent_loop::StopIfFn
+        });
+
+        // Fresh context for the sub-agent, or the shared one held for the
+        // whole run.
+        let mut fresh_context;
+        let mut shared_guard;
+        let context = match &self.shared_context {
+            Some(shared) => {
+                shared_guard = shared.lock().await;
+                if reset {
+                    shared_guard.messages.clear();
+                }
+                shared_guard.system_prompt = system_prompt;
+                shared_guard.tools = tools;
+                &mut *shared_guard
+            }
+            None => {
+                fresh_context = AgentContext {
+                    system_prompt,
+                    messages: Vec::new(),
+                    tools,
+                };
+                &mut fresh_context
+            }
         };
 
         // Config with Arc'd provider
@@ -405,21 +607,35 @@ impl AgentTool for SubAgentTool {
             get_follow_up_messages: None,
             context_config: None,
             compaction_strategy: None,
-            execution_limits: Some(ExecutionLimits {
-                max_turns: self.max_turns,
-                // Generous token/duration limits — turn limit is the primary guard
-                max_total_tokens: 1_000_000,
-                max_duration: std::time::Duration::from_secs(300),
-            }),
+            execution_limits: Some(self.execution_limits.clone()),
             cache_config: self.cache_config.clone(),
             tool_execution: self.tool_execution.clone(),
             retry_config: self.retry_config.clone(),
+            circuit_breaker: self.circuit_breaker.clone(),
+            rate_limiter: self.rate_limiter.clone(),
             before_turn: None,
+            prepare_turn: None,
             after_turn: None,
+            checkpoint: None,
+            stop_if,
+            on_context_compact: None,
+            on_message: None,
+            event_buffer_size: None,
+            system_prompt_hook: None,
             on_error: None,
             input_filters: vec![],
+            output_filters: vec![],
             tool_middleware: self.tool_middleware.clone(),
+            tool_timeout: self.tool_timeout,
+            tool_cache: None,
+            metrics_sink: None,
+            approve_tool: None,
+            on_tool_call: None,
             output_schema: None,
+            tool_choice: crate::provider::ToolChoice::Auto,
+            stop_sequences: None,
+            response_format: crate::provider::ResponseFormat::Text,
+            server_tools: Vec::new(),
             turn_delay: self.turn_delay,
         };
 
@@ -429,6 +645,7 @@ impl AgentTool for SubAgentTool {
         // Forward sub-agent events to parent via on_update and on_progress callbacks
         let forward_handle = if on_update.is_some() || on_progress.is_some() {
             let tool_name = self.tool_name.clone();
+            let forward_events = self.forward_events;
             Some(tokio::spawn(async move {
                 while let Some(event) = rx.recv().await {
                     // Forward progress messages via on_progress
@@ -440,6 +657,18 @@ impl AgentTool for SubAgentTool {
 
                     // Convert interesting events to ToolResult updates for the parent
                     if let Some(ref on_update) = on_update {
+                        if forward_events {
+                            if let Some((text, event)) = nested_event(&tool_name, &event) {
+                                on_update(ToolResult {
+                                    content: vec![Content::Text { text }],
+                                    details: serde_json::json!({
+                                        "sub_agent": tool_name,
+                                        "event": event,
+                                    }),
+                                });
+                                continue;
+                            }
+                        }
                         let update_text = match &event {
                             AgentEvent::MessageUpdate {
                                 delta: StreamDelta::Text { delta },
@@ -466,7 +695,7 @@ impl AgentTool for SubAgentTool {
 
         // Run the sub-agent loop
         let prompt = AgentMessage::Llm(Message::user(task));
-        let new_messages = agent_loop(vec![prompt], &mut context, &config, tx, cancel).await;
+        let new_messages = agent_loop(vec![prompt], context, &config, tx, cancel).await;
 
         // Wait for event forwarding to complete
         if let Some(handle) = forward_handle {
@@ -482,13 +711,31 @@ impl AgentTool for SubAgentTool {
         }
 
         // Extract final assistant text from the returned messages
-        let result_text = extract_final_text(&new_messages);
+        let committed = final_answer
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        let from_final_answer = committed.is_some();
+        let mut result_text = committed.unwrap_or_else(|| extract_final_text(&new_messages));
+        let limit_reached = extract_limit_reason(&new_messages);
+        if let Some(reason) = &limit_reached {
+            result_text.push_str(&format!("\n\n[Agent stopped: {}]", reason));
+        }
 
-        // Include full sub-agent conversation in details for debugging
-        let details = serde_json::json!({
+        // Summary details; the full conversation only when asked for
+        let mut details = serde_json::json!({
             "sub_agent": self.tool_name,
             "turns": new_messages.len(),
+            "usage": total_usage(&new_messages),
+            "limit_reached": limit_reached,
+            "final_answer": from_final_answer,
         });
+        if self.include_history {
+            details["total_tokens"] = serde_json::json!(total_usage(&new_messages));
+            details["tools_called"] = serde_json::json!(tools_called(&new_messages));
+            details["conversation"] = serde_json::to_value(&new_messages)
+                .unwrap_or_else(|e| serde_json::json!({ "error": e.to_string() }));
+        }
 
         Ok(ToolResult {
             content: vec![Content::Text { text: result_text }],
@@ -497,6 +744,171 @@ impl AgentTool for SubAgentTool {
     }
 }
 
+/// The `final_answer` tool injected by [`SubAgentTool::with_final_answer_tool`].
+struct FinalAnswerTool {
+    answer: Arc<std::sync::Mutex<Option<String>>>,
+}
+
+#[async_trait::async_trait]
+impl AgentTool for FinalAnswerTool {
+    fn name(&self) -> &str {
+        "final_answer"
+    }
+
+    fn label(&self) -> &str {
+        "Final Answer"
+    }
+
+    fn description(&self) -> &str {
+        "Commit your final answer to the task. This ends your work; the answer is returned as your result, so make it complete."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "answer": {
+                    "type": "string",
+                    "description": "The complete final answer"
+                }
+            },
+            "required": ["answer"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let answer = params["answer"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'answer' parameter".into()))?;
+        *self.answer.lock().unwrap_or_else(|e| e.into_inner()) = Some(answer.to_string());
+        Ok(ToolResult {
+            content: vec![Content::Text {
+                text: "Answer recorded.".into(),
+            }],
+            details: serde_json::Value::Null,
+        })
+    }
+}
+
+/// Runs several sub-agents concurrently and collects their results in the
+/// order they were given.
+///
+/// Each entry pairs a sub-agent with its tool arguments (`{"task": ...}`).
+/// At most `max_concurrent` run at once (default: all). A failing sub-agent
+/// doesn't stop the others; its slot holds the error.
+///
+/// ```rust,no_run
+/// # use yoagent::sub_agent::{AgentFanOut, SubAgentTool};
+/// # use yoagent::provider::ModelConfig;
+/// # async fn demo() {
+/// let reviewer = |name: &str| SubAgentTool::from_config(name, ModelConfig::claude_haiku_4_5());
+/// let results = AgentFanOut::new(vec![
+///     (reviewer("api"), serde_json::json!({"task": "Review src/api.rs"})),
+///     (reviewer("db"), serde_json::json!({"task": "Review src/db.rs"})),
+/// ])
+/// .with_max_concurrent(4)
+/// .run(tokio_util::sync::CancellationToken::new())
+/// .await;
+/// # }
+/// ```
+pub struct AgentFanOut {
+    jobs: Vec<(SubAgentTool, serde_json::Value)>,
+    max_concurrent: usize,
+}
+
+impl AgentFanOut {
+    pub fn new(jobs: Vec<(SubAgentTool, serde_json::Value)>) -> Self {
+        Self {
+            max_concurrent: jobs.len().max(1),
+            jobs,
+        }
+    }
+
+    /// Cap how many sub-agents run at the same time.
+    pub fn with_max_concurrent(mut self, max: usize) -> Self {
+        self.max_concurrent = max.max(1);
+        self
+    }
+
+    /// Run every sub-agent and return one result per entry, in input order.
+    /// `cancel` is forwarded to all of them.
+    pub async fn run(
+        self,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Vec<Result<ToolResult, ToolError>> {
+        use futures::StreamExt;
+
+        futures::stream::iter(self.jobs.into_iter().enumerate())
+            .map(|(i, (agent, params))| {
+                let ctx = ToolContext {
+                    tool_call_id: format!("fan-out-{}", i),
+                    tool_name: agent.tool_name.clone(),
+                    cancel: cancel.clone(),
+                    on_update: None,
+                    on_progress: None,
+                    timeout: None,
+                };
+                async move { agent.execute(params, ctx).await }
+            })
+            // `buffered` keeps output in input order.
+            .buffered(self.max_concurrent)
+            .collect()
+            .await
+    }
+}
+
+/// A status line and a compact wire-format copy of `event`, for the events
+/// [`SubAgentTool::with_event_forwarding`] passes on.
+fn nested_event(sub_agent: &str, event: &AgentEvent) -> Option<(String, serde_json::Value)> {
+    match event {
+        AgentEvent::TurnStart => Some((
+            format!("[{}] turn started", sub_agent),
+            serde_json::json!({ "type": "turnStart" }),
+        )),
+        AgentEvent::TurnEnd { .. } => Some((
+            format!("[{}] turn ended", sub_agent),
+            serde_json::json!({ "type": "turnEnd" }),
+        )),
+        AgentEvent::ToolExecutionStart {
+            tool_call_id,
+            tool_name,
+            args,
+        } => Some((
+            format!("[{}] running {}", sub_agent, tool_name),
+            serde_json::json!({
+                "type": "toolExecutionStart",
+                "toolCallId": tool_call_id,
+                "toolName": tool_name,
+                "args": args,
+            }),
+        )),
+        AgentEvent::ToolExecutionEnd {
+            tool_call_id,
+            tool_name,
+            is_error,
+            ..
+        } => Some((
+            format!(
+                "[{}] {} {}",
+                sub_agent,
+                tool_name,
+                if *is_error { "failed" } else { "finished" }
+            ),
+            serde_json::json!({
+                "type": "toolExecutionEnd",
+                "toolCallId": tool_call_id,
+                "toolName": tool_name,
+                "isError": is_error,
+            }),
+        )),
+        _ => None,
+    }
+}
+
 /// Check if the last assistant message was an error, return the error message.
 fn extract_error(messages: &[AgentMessage]) -> Option<String> {
     for msg in messages.iter().rev() {
@@ -518,6 +930,52 @@ fn extract_error(messages: &[AgentMessage]) -> Option<String> {
     None
 }
 
+/// The reason given by the loop's `[Agent stopped: ...]` note, if an
+/// execution limit ended the run.
+fn extract_limit_reason(messages: &[AgentMessage]) -> Option<String> {
+    let Some(AgentMessage::Llm(Message::User { content, .. })) = messages.last() else {
+        return None;
+    };
+    content.iter().find_map(|c| match c {
+        Content::Text { text } => text
+            .strip_prefix("[Agent stopped: ")
+            .and_then(|rest| rest.strip_suffix(']'))
+            .map(str::to_string),
+        _ => None,
+    })
+}
+
+/// Token usage summed over the sub-agent's assistant messages.
+fn total_usage(messages: &[AgentMessage]) -> Usage {
+    let mut total = Usage::default();
+    for msg in messages {
+        if let AgentMessage::Llm(Message::Assistant { usage, .. }) = msg {
+            total.input += usage.input;
+            total.output += usage.output;
+            total.cache_read += usage.cache_read;
+            total.cache_write += usage.cache_write;
+            total.total_tokens += usage.total_tokens;
+        }
+    }
+    total
+}
+
+/// Names of the tools the assistant called, in call order.
+fn tools_called(messages: &[AgentMessage]) -> Vec<&str> {
+    messages
+        .iter()
+        .filter_map(|msg| match msg {
+            AgentMessage::Llm(Message::Assistant { content, .. }) => Some(content),
+            _ => None,
+        })
+        .flatten()
+        .filter_map(|c| match c {
+            Content::ToolCall { name, .. } => Some(name.as_str()),
+            _ => None,
+        })
+        .collect()
+}
+
 /// Extract the final assistant text from agent messages.
 /// Collects text from the last assistant message, or returns a fallback.
 fn extract_final_text(messages: &[AgentMessage]) -> String {
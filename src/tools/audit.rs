@@ -0,0 +1,237 @@
+//! Audit log — an append-only JSONL record of every tool call.
+//!
+//! [`AuditLog`] is a [`MetricsSink`]: set it as the loop's metrics sink and
+//! each finished tool call is appended to the file as one [`AuditEntry`]
+//! line — tool, arguments, a summary of the result, error flag and timing.
+//! [`AuditLog::tail`] follows the file for live monitoring.
+//!
+//! ```rust,no_run
+//! use std::sync::Arc;
+//! use yoagent::{Agent, provider::ModelConfig, tools::audit::AuditLog};
+//!
+//! # async fn example() -> std::io::Result<()> {
+//! let audit = Arc::new(AuditLog::new("audit.jsonl")?.with_session_id("session-42"));
+//! let agent = Agent::from_config(ModelConfig::anthropic("claude-sonnet-4-20250514", "Claude Sonnet 4"))
+//!     .with_metrics_sink(audit.clone());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::metrics::{MetricsSink, ToolCallRecord};
+use crate::types::{Content, ToolResult, Usage};
+use futures::Stream;
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::sync::{mpsc, oneshot};
+
+/// Longest `result_summary` kept, in characters.
+const MAX_SUMMARY_CHARS: usize = 2000;
+
+/// How often [`AuditLog::tail`] checks for new lines at end of file.
+const TAIL_POLL: Duration = Duration::from_millis(100);
+
+/// One tool call, as written to the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    /// When the call finished, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u64,
+    pub session_id: String,
+    pub tool_call_id: String,
+    pub tool_name: String,
+    pub args: serde_json::Value,
+    /// The result's text, truncated; non-text blocks appear as placeholders
+    /// such as `[image]`.
+    pub result_summary: String,
+    pub is_error: bool,
+    pub duration_ms: u64,
+}
+
+/// Appends [`AuditEntry`] lines to a JSONL file.
+///
+/// Writes happen in order on a background task, so [`record`](Self::record)
+/// never blocks the agent loop. Call [`flush`](Self::flush) to wait until
+/// everything recorded so far is on disk. Write errors are logged with
+/// `tracing` and don't stop the agent.
+pub struct AuditLog {
+    session_id: String,
+    writes: mpsc::UnboundedSender<Write>,
+}
+
+enum Write {
+    Line(String),
+    Flush(oneshot::Sender<io::Result<()>>),
+}
+
+impl AuditLog {
+    /// Open `path` for appending, creating it if needed. Entries get a random
+    /// session id; set one with [`with_session_id`](Self::with_session_id).
+    ///
+    /// Must be called from within a Tokio runtime.
+    pub fn new(path: impl AsRef<Path>) -> Result<Self, io::Error> {
+        let runtime = tokio::runtime::Handle::try_current()
+            .map_err(|e| io::Error::other(format!("AuditLog needs a Tokio runtime: {}", e)))?;
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path.as_ref())?;
+        let file = tokio::fs::File::from_std(file);
+        let (writes, rx) = mpsc::unbounded_channel();
+        runtime.spawn(write_entries(file, rx, path.as_ref().to_path_buf()));
+        Ok(Self {
+            session_id: uuid::Uuid::new_v4().to_string(),
+            writes,
+        })
+    }
+
+    pub fn with_session_id(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = session_id.into();
+        self
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    /// Append `entry` to the log.
+    pub fn record(&self, entry: AuditEntry) {
+        match serde_json::to_string(&entry) {
+            Ok(mut line) => {
+                line.push('\n');
+                self.writes.send(Write::Line(line)).ok();
+            }
+            Err(e) => tracing::warn!("could not serialize audit entry: {}", e),
+        }
+    }
+
+    /// Wait until every entry recorded so far has been written and synced
+    /// to disk.
+    pub async fn flush(&self) -> io::Result<()> {
+        let (done, result) = oneshot::channel();
+        self.writes
+            .send(Write::Flush(done))
+            .map_err(|_| io::Error::other("audit log writer has stopped"))?;
+        result
+            .await
+            .map_err(|_| io::Error::other("audit log writer has stopped"))?
+    }
+
+    /// Follow the audit log at `path`, yielding every entry with
+    /// `timestamp_ms >= since_ms`: first those already written, then new
+    /// ones as they are appended. The stream doesn't end on its own; drop it
+    /// to stop. Waits for the file if it doesn't exist yet, and skips lines
+    /// that aren't valid entries.
+    pub fn tail(path: impl AsRef<Path>, since_ms: u64) -> impl Stream<Item = AuditEntry> {
+        let state = Tail {
+            path: path.as_ref().to_path_buf(),
+            reader: None,
+            line: String::new(),
+        };
+        futures::stream::unfold(state, move |mut state| async move {
+            loop {
+                let reader = match &mut state.reader {
+                    Some(reader) => reader,
+                    None => match tokio::fs::File::open(&state.path).await {
+                        Ok(file) => state.reader.insert(BufReader::new(file)),
+                        Err(_) => {
+                            tokio::time::sleep(TAIL_POLL).await;
+                            continue;
+                        }
+                    },
+                };
+                match reader.read_line(&mut state.line).await {
+                    // At the end for now; a partial line stays buffered.
+                    Ok(0) => tokio::time::sleep(TAIL_POLL).await,
+                    Ok(_) if state.line.ends_with('\n') => {
+                        let line = std::mem::take(&mut state.line);
+                        if let Ok(entry) = serde_json::from_str::<AuditEntry>(&line) {
+                            if entry.timestamp_ms >= since_ms {
+                                return Some((entry, state));
+                            }
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        tracing::warn!("stopped tailing {}: {}", state.path.display(), e);
+                        return None;
+                    }
+                }
+            }
+        })
+    }
+}
+
+struct Tail {
+    path: PathBuf,
+    reader: Option<BufReader<tokio::fs::File>>,
+    line: String,
+}
+
+async fn write_entries(
+    mut file: tokio::fs::File,
+    mut rx: mpsc::UnboundedReceiver<Write>,
+    path: PathBuf,
+) {
+    while let Some(write) = rx.recv().await {
+        match write {
+            Write::Line(line) => {
+                // Flush each entry so tail readers see it straight away.
+                let written = match file.write_all(line.as_bytes()).await {
+                    Ok(()) => file.flush().await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = written {
+                    tracing::warn!("could not write to audit log {}: {}", path.display(), e);
+                }
+            }
+            Write::Flush(done) => {
+                let synced = match file.flush().await {
+                    Ok(()) => file.sync_data().await,
+                    Err(e) => Err(e),
+                };
+                done.send(synced).ok();
+            }
+        }
+    }
+}
+
+impl MetricsSink for AuditLog {
+    fn record_turn(&self, _model: &str, _tokens: &Usage, _duration: Duration) {}
+
+    fn record_tool_call(&self, _tool_name: &str, _is_error: bool, _duration: Duration) {}
+
+    fn record_tool_result(&self, call: &ToolCallRecord<'_>) {
+        self.record(AuditEntry {
+            timestamp_ms: crate::types::now_ms(),
+            session_id: self.session_id.clone(),
+            tool_call_id: call.tool_call_id.to_string(),
+            tool_name: call.tool_name.to_string(),
+            args: call.args.clone(),
+            result_summary: summarize(call.result),
+            is_error: call.is_error,
+            duration_ms: call.duration.as_millis() as u64,
+        });
+    }
+}
+
+fn summarize(result: &ToolResult) -> String {
+    let text = result
+        .content
+        .iter()
+        .map(|c| match c {
+            Content::Text { text } => text.as_str(),
+            Content::Image { .. } => "[image]",
+            Content::Document { .. } | Content::DocumentUrl { .. } => "[document]",
+            Content::Thinking { .. } => "[thinking]",
+            Content::ToolCall { .. } => "[tool call]",
+            Content::ServerToolUse { .. } | Content::ServerToolResult { .. } => "[server tool]",
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    match text.char_indices().nth(MAX_SUMMARY_CHARS) {
+        Some((cut, _)) => format!("{}… [truncated]", &text[..cut]),
+        None => text,
+    }
+}
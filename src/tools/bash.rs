@@ -3,31 +3,49 @@
 use crate::types::*;
 
 /// Type alias for command confirmation callback.
-pub type ConfirmFn = Box<dyn Fn(&str) -> bool + Send + Sync>;
+pub type ConfirmFn = std::sync::Arc<dyn Fn(&str) -> bool + Send + Sync>;
 use async_trait::async_trait;
+use std::collections::VecDeque;
 use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
 use tokio::process::Command;
 
 /// Execute shell commands. Captures stdout + stderr.
+#[derive(Clone)]
 pub struct BashTool {
-    /// Working directory for commands
+    /// Working directory for commands. A per-call `cwd` must stay inside it
+    /// (or inside the process working directory when unset).
     pub cwd: Option<String>,
+    /// Extra environment variables for every command, applied after the
+    /// inherited environment
+    pub env: Vec<(String, String)>,
+    /// Pass this process's environment on to commands (default: true).
+    /// When off, commands see only `env` — set `PATH` there if needed.
+    pub inherit_env: bool,
     /// Max execution time per command
     pub timeout: Duration,
-    /// Max output bytes to capture (prevents OOM on huge outputs)
+    /// Max bytes of stdout (and, separately, stderr) to keep. Longer output
+    /// keeps its head and tail with a `[... N bytes truncated ...]` marker
+    /// in between; the rest is never held in memory.
     pub max_output_bytes: usize,
     /// Commands/patterns that are always blocked (e.g., "rm -rf /")
     pub deny_patterns: Vec<String>,
     /// Optional callback for confirming dangerous commands
     pub confirm_fn: Option<ConfirmFn>,
+    /// Report each new line of output through `ToolContext::on_update` while
+    /// the command runs (surfaces as `ToolExecutionUpdate` events). Updates
+    /// are deltas, flagged `details.delta`; join them for the output so far
+    pub streaming: bool,
 }
 
 impl Default for BashTool {
     fn default() -> Self {
         Self {
             cwd: None,
+            env: Vec::new(),
+            inherit_env: true,
             timeout: Duration::from_secs(120),
-            max_output_bytes: 256 * 1024, // 256KB
+            max_output_bytes: 30 * 1024, // 30KB
             deny_patterns: vec![
                 "rm -rf /".into(),
                 "rm -rf /*".into(),
@@ -36,6 +54,7 @@ impl Default for BashTool {
                 ":(){:|:&};:".into(), // fork bomb
             ],
             confirm_fn: None,
+            streaming: false,
         }
     }
 }
@@ -50,20 +69,224 @@ impl BashTool {
         self
     }
 
+    /// Add environment variables for every command.
+    pub fn with_env<K: Into<String>, V: Into<String>>(
+        mut self,
+        vars: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        self.env
+            .extend(vars.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    pub fn with_inherit_env(mut self, inherit: bool) -> Self {
+        self.inherit_env = inherit;
+        self
+    }
+
     pub fn with_timeout(mut self, timeout: Duration) -> Self {
         self.timeout = timeout;
         self
     }
 
+    /// Cap on the stdout and stderr returned to the model, in bytes each.
+    pub fn with_max_output_bytes(mut self, max: usize) -> Self {
+        self.max_output_bytes = max;
+        self
+    }
+
     pub fn with_deny_patterns(mut self, patterns: Vec<String>) -> Self {
         self.deny_patterns = patterns;
         self
     }
 
     pub fn with_confirm(mut self, f: impl Fn(&str) -> bool + Send + Sync + 'static) -> Self {
-        self.confirm_fn = Some(Box::new(f));
+        self.confirm_fn = Some(std::sync::Arc::new(f));
         self
     }
+
+    /// Stream output through `on_update` as it arrives instead of only
+    /// returning it when the command exits. The final result is unchanged.
+    pub fn with_streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+}
+
+impl BashTool {
+    /// Resolve a per-call `cwd` against the configured one and check it
+    /// doesn't leave it. Symlinks are resolved first, so they can't be used
+    /// to escape. `cd` inside a command needs no guard: every call starts a
+    /// fresh shell here.
+    fn resolve_cwd(&self, dir: &str) -> Result<std::path::PathBuf, ToolError> {
+        let root = match &self.cwd {
+            Some(cwd) => std::path::PathBuf::from(cwd),
+            None => std::env::current_dir().map_err(|e| {
+                ToolError::Failed(format!("Cannot determine working directory: {}", e))
+            })?,
+        };
+        let root = root
+            .canonicalize()
+            .map_err(|e| ToolError::Failed(format!("Cannot access {}: {}", root.display(), e)))?;
+        let resolved = root.join(dir).canonicalize().map_err(|e| {
+            ToolError::InvalidArgs(format!("cwd '{}' is not accessible: {}", dir, e))
+        })?;
+        if !resolved.starts_with(&root) {
+            return Err(ToolError::InvalidArgs(format!(
+                "cwd '{}' is outside the allowed directory {}",
+                dir,
+                root.display()
+            )));
+        }
+        if !resolved.is_dir() {
+            return Err(ToolError::InvalidArgs(format!(
+                "cwd '{}' is not a directory",
+                dir
+            )));
+        }
+        Ok(resolved)
+    }
+}
+
+/// One output stream, bounded to `max` bytes: the first half is kept as it
+/// arrives, the second half as a rolling window over the end, and what
+/// falls between is only counted. The same head/tail split that
+/// [`compact_messages`](crate::context::compact_messages) applies to long
+/// tool output, by bytes instead of lines.
+struct Capture {
+    max: usize,
+    head: Vec<u8>,
+    tail: VecDeque<u8>,
+    total: usize,
+}
+
+impl Capture {
+    fn new(max: usize) -> Self {
+        Self {
+            max,
+            head: Vec::new(),
+            tail: VecDeque::new(),
+            total: 0,
+        }
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        self.total += bytes.len();
+        let head_max = self.max / 2;
+        let n = head_max.saturating_sub(self.head.len()).min(bytes.len());
+        self.head.extend_from_slice(&bytes[..n]);
+        self.tail.extend(&bytes[n..]);
+        let tail_max = self.max - head_max;
+        if self.tail.len() > tail_max {
+            self.tail.drain(..self.tail.len() - tail_max);
+        }
+    }
+
+    /// Bytes dropped from the middle.
+    fn truncated(&self) -> usize {
+        self.total - self.head.len() - self.tail.len()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.total == 0
+    }
+
+    fn text(&self) -> String {
+        let (front, back) = self.tail.as_slices();
+        if self.truncated() == 0 {
+            return String::from_utf8_lossy(&[&self.head[..], front, back].concat()).into_owned();
+        }
+        format!(
+            "{}\n\n[... {} bytes truncated ...]\n\n{}",
+            String::from_utf8_lossy(&self.head),
+            self.truncated(),
+            String::from_utf8_lossy(&[front, back].concat())
+        )
+    }
+}
+
+/// Output captured from a finished command: exit code, stdout, stderr.
+type CommandOutput = (i32, Capture, Capture);
+
+async fn read_into(
+    mut reader: impl AsyncRead + Unpin,
+    capture: &mut Capture,
+) -> std::io::Result<()> {
+    let mut buf = [0u8; 8192];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        capture.push(&buf[..n]);
+    }
+}
+
+/// Spawn `cmd` and collect its stdout/stderr, keeping at most `max_bytes`
+/// of each.
+async fn run_captured(mut cmd: Command, max_bytes: usize) -> std::io::Result<CommandOutput> {
+    cmd.kill_on_drop(true);
+    let mut child = cmd.spawn()?;
+    let (mut stdout, mut stderr) = (Capture::new(max_bytes), Capture::new(max_bytes));
+    let stdout_pipe = child.stdout.take().expect("stdout is piped");
+    let stderr_pipe = child.stderr.take().expect("stderr is piped");
+    tokio::try_join!(
+        read_into(stdout_pipe, &mut stdout),
+        read_into(stderr_pipe, &mut stderr)
+    )?;
+    let status = child.wait().await?;
+    Ok((status.code().unwrap_or(-1), stdout, stderr))
+}
+
+/// Spawn `cmd` and read stdout/stderr line by line, reporting each new line
+/// through `on_update` as it arrives. Updates carry only that line, not the
+/// output so far: re-sending up to `max_bytes` per line would make long
+/// output quadratic. `details` says so (`"delta": true`) and names the
+/// stream. Each stream keeps at most `max_bytes`, head and tail.
+async fn run_streaming(
+    mut cmd: Command,
+    on_update: ToolUpdateFn,
+    max_bytes: usize,
+) -> std::io::Result<CommandOutput> {
+    // Timeout/cancellation drop this future; take the process down with it.
+    cmd.kill_on_drop(true);
+    let mut child = cmd.spawn()?;
+    let mut stdout_pipe = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let mut stderr_pipe = BufReader::new(child.stderr.take().expect("stderr is piped"));
+    let (mut stdout, mut stderr) = (Capture::new(max_bytes), Capture::new(max_bytes));
+    let (mut stdout_line, mut stderr_line) = (Vec::new(), Vec::new());
+    let (mut stdout_open, mut stderr_open) = (true, true);
+
+    while stdout_open || stderr_open {
+        // `read_until` keeps the newline, so an unterminated last line is
+        // reported (and captured) exactly as the process wrote it. When the
+        // other branch wins, bytes read so far stay in the line buffer, so
+        // EOF can arrive with `n == 0` and a partial line still buffered.
+        let (stream, capture, line) = tokio::select! {
+            n = stdout_pipe.read_until(b'\n', &mut stdout_line), if stdout_open => {
+                stdout_open = n? > 0;
+                ("stdout", &mut stdout, &mut stdout_line)
+            },
+            n = stderr_pipe.read_until(b'\n', &mut stderr_line), if stderr_open => {
+                stderr_open = n? > 0;
+                ("stderr", &mut stderr, &mut stderr_line)
+            },
+        };
+        if line.is_empty() {
+            continue;
+        }
+        capture.push(line);
+        on_update(ToolResult {
+            content: vec![Content::Text {
+                text: String::from_utf8_lossy(line).into_owned(),
+            }],
+            details: serde_json::json!({ "stream": stream, "delta": true }),
+        });
+        line.clear();
+    }
+
+    let status = child.wait().await?;
+    Ok((status.code().unwrap_or(-1), stdout, stderr))
 }
 
 #[async_trait]
@@ -87,6 +310,15 @@ impl AgentTool for BashTool {
                 "command": {
                     "type": "string",
                     "description": "The bash command to execute"
+                },
+                "cwd": {
+                    "type": "string",
+                    "description": "Directory to run in (optional). Relative paths resolve against the default working directory; must stay inside it."
+                },
+                "timeout_ms": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Optional time limit in milliseconds. Can only shorten the tool's configured limit, never extend it."
                 }
             },
             "required": ["command"]
@@ -125,61 +357,77 @@ impl AgentTool for BashTool {
         let mut cmd = Command::new("bash");
         cmd.arg("-c").arg(command);
 
-        if let Some(ref cwd) = self.cwd {
-            cmd.current_dir(cwd);
+        match params["cwd"].as_str() {
+            Some(dir) => {
+                cmd.current_dir(self.resolve_cwd(dir)?);
+            }
+            None => {
+                if let Some(ref cwd) = self.cwd {
+                    cmd.current_dir(cwd);
+                }
+            }
         }
+        if !self.inherit_env {
+            cmd.env_clear();
+        }
+        cmd.envs(self.env.iter().map(|(k, v)| (k, v)));
 
         // Capture output
         cmd.stdout(std::process::Stdio::piped());
         cmd.stderr(std::process::Stdio::piped());
 
-        let timeout = self.timeout;
+        // The LLM may ask for a tighter limit, never a looser one.
+        let timeout = match params["timeout_ms"].as_u64() {
+            Some(ms) => self.timeout.min(Duration::from_millis(ms)),
+            None => self.timeout,
+        };
         let max_bytes = self.max_output_bytes;
 
+        let run = async {
+            match (self.streaming, ctx.on_update) {
+                (true, Some(on_update)) => run_streaming(cmd, on_update, max_bytes).await,
+                _ => run_captured(cmd, max_bytes).await,
+            }
+        };
+
         // Run with timeout and cancellation
-        let result = tokio::select! {
+        let (exit_code, stdout, stderr) = tokio::select! {
             _ = cancel.cancelled() => {
                 return Err(ToolError::Cancelled);
             }
             _ = tokio::time::sleep(timeout) => {
-                return Err(ToolError::Failed(format!(
-                    "Command timed out after {}s",
-                    timeout.as_secs()
-                )));
+                return Err(ToolError::Timeout(timeout));
             }
-            result = cmd.output() => {
+            result = run => {
                 result.map_err(|e| ToolError::Failed(format!("Failed to execute: {}", e)))?
             }
         };
 
-        let mut stdout = String::from_utf8_lossy(&result.stdout).to_string();
-        let mut stderr = String::from_utf8_lossy(&result.stderr).to_string();
-
-        // Truncate if too large
-        if stdout.len() > max_bytes {
-            stdout.truncate(max_bytes);
-            stdout.push_str("\n... (output truncated)");
-        }
-        if stderr.len() > max_bytes {
-            stderr.truncate(max_bytes);
-            stderr.push_str("\n... (output truncated)");
-        }
-
-        let exit_code = result.status.code().unwrap_or(-1);
-
         let output = if stderr.is_empty() {
-            format!("Exit code: {}\n{}", exit_code, stdout)
+            format!("Exit code: {}\n{}", exit_code, stdout.text())
         } else {
             format!(
                 "Exit code: {}\nSTDOUT:\n{}\nSTDERR:\n{}",
-                exit_code, stdout, stderr
+                exit_code,
+                stdout.text(),
+                stderr.text()
             )
         };
 
         // Return output even on failure — LLMs need error output to self-correct
         Ok(ToolResult {
             content: vec![Content::Text { text: output }],
-            details: serde_json::json!({ "exit_code": exit_code, "success": exit_code == 0 }),
+            details: serde_json::json!({
+                "exit_code": exit_code,
+                "success": exit_code == 0,
+                "stdout_bytes": stdout.total,
+                "stderr_bytes": stderr.total,
+                "truncated_bytes": stdout.truncated() + stderr.truncated(),
+            }),
         })
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
 }
@@ -0,0 +1,149 @@
+//! Tool result caching — skip re-running identical tool calls.
+
+use crate::types::ToolResult;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Cache of successful tool results, keyed by tool name and arguments.
+///
+/// Consulted by the agent loop before each call to a tool whose
+/// [`AgentTool::cacheable`](crate::types::AgentTool::cacheable) is `true`
+/// (after middleware, so a denied call is never served from cache). Only
+/// successful results are inserted; errors always re-run. Running any other
+/// tool clears the cache, since it may have changed what a cached call would
+/// now return.
+pub trait ToolCache: Send + Sync {
+    /// Look up a previous result for this exact call.
+    fn get(&self, name: &str, args: &serde_json::Value) -> Option<ToolResult>;
+    /// Store the result of a successful call.
+    fn insert(&self, name: &str, args: &serde_json::Value, result: ToolResult);
+    /// Drop every cached result.
+    fn clear(&self);
+}
+
+/// In-memory [`ToolCache`]. Keys are the tool name plus the JSON-serialised
+/// arguments; with `max_entries` set, the oldest entry is evicted first.
+#[derive(Default)]
+pub struct InMemoryToolCache {
+    max_entries: Option<usize>,
+    inner: Mutex<CacheEntries>,
+}
+
+#[derive(Default)]
+struct CacheEntries {
+    results: HashMap<(String, String), ToolResult>,
+    order: VecDeque<(String, String)>,
+}
+
+impl InMemoryToolCache {
+    /// Unbounded cache.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `max` entries, evicting the oldest insertion.
+    pub fn with_max_entries(mut self, max: usize) -> Self {
+        self.max_entries = Some(max);
+        self
+    }
+
+    /// Number of cached results.
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+fn key(name: &str, args: &serde_json::Value) -> (String, String) {
+    (name.to_string(), args.to_string())
+}
+
+impl ToolCache for InMemoryToolCache {
+    fn get(&self, name: &str, args: &serde_json::Value) -> Option<ToolResult> {
+        self.inner
+            .lock()
+            .unwrap()
+            .results
+            .get(&key(name, args))
+            .cloned()
+    }
+
+    fn insert(&self, name: &str, args: &serde_json::Value, result: ToolResult) {
+        if self.max_entries == Some(0) {
+            return;
+        }
+        let key = key(name, args);
+        let mut entries = self.inner.lock().unwrap();
+        if entries.results.insert(key.clone(), result).is_none() {
+            entries.order.push_back(key);
+        }
+        if let Some(max) = self.max_entries {
+            while entries.results.len() > max {
+                let Some(oldest) = entries.order.pop_front() else {
+                    break;
+                };
+                entries.results.remove(&oldest);
+            }
+        }
+    }
+
+    fn clear(&self) {
+        let mut entries = self.inner.lock().unwrap();
+        entries.results.clear();
+        entries.order.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Content;
+
+    fn result(text: &str) -> ToolResult {
+        ToolResult {
+            content: vec![Content::Text { text: text.into() }],
+            details: serde_json::Value::Null,
+        }
+    }
+
+    #[test]
+    fn test_keys_on_name_and_args() {
+        let cache = InMemoryToolCache::new();
+        let args = serde_json::json!({"path": "a.rs"});
+        cache.insert("read_file", &args, result("contents"));
+
+        assert_eq!(cache.get("read_file", &args), Some(result("contents")));
+        assert_eq!(
+            cache.get("read_file", &serde_json::json!({"path": "b.rs"})),
+            None
+        );
+        assert_eq!(cache.get("list_files", &args), None);
+    }
+
+    #[test]
+    fn test_evicts_oldest_beyond_max_entries() {
+        let cache = InMemoryToolCache::new().with_max_entries(2);
+        for i in 0..3 {
+            cache.insert("t", &serde_json::json!(i), result(&i.to_string()));
+        }
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("t", &serde_json::json!(0)), None);
+        assert_eq!(cache.get("t", &serde_json::json!(2)), Some(result("2")));
+    }
+
+    #[test]
+    fn test_reinserting_a_key_does_not_grow_the_cache() {
+        let cache = InMemoryToolCache::new().with_max_entries(2);
+        let args = serde_json::json!({});
+        cache.insert("t", &args, result("old"));
+        cache.insert("t", &args, result("new"));
+        cache.insert("u", &args, result("other"));
+
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("t", &args), Some(result("new")));
+    }
+}
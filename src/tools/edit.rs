@@ -7,8 +7,23 @@
 use crate::types::*;
 use async_trait::async_trait;
 
-/// Surgical file editing via exact text search/replace.
-pub struct EditFileTool;
+/// Surgical file editing via text search/replace.
+///
+/// `old_text` is matched exactly first. If it isn't found, the match is
+/// retried line by line ignoring each line's leading and trailing
+/// whitespace — the usual way a model's copy of the file drifts. Such a
+/// match must be unique and is reported in the result.
+///
+/// Built with [`new_multi`](Self::new_multi), the tool also takes an `edits`
+/// array and applies it in one call. Unlike [`MultiEditTool`], a batch is
+/// best-effort: an edit that doesn't match is reported in
+/// `details["edit_errors"]` and the rest still apply.
+#[derive(Clone)]
+pub struct EditFileTool {
+    /// Accept an `edits` array of `old_text`/`new_text` pairs as well as
+    /// a single pair at the top level.
+    pub multi_edit: bool,
+}
 
 impl Default for EditFileTool {
     fn default() -> Self {
@@ -18,7 +33,14 @@ impl Default for EditFileTool {
 
 impl EditFileTool {
     pub fn new() -> Self {
-        Self
+        Self { multi_edit: false }
+    }
+
+    /// An `edit_file` tool that also accepts an `edits` array of
+    /// `{old_text, new_text}` pairs, applied in order with a single write.
+    /// The item fields match [`MultiEditTool`]'s.
+    pub fn new_multi() -> Self {
+        Self { multi_edit: true }
     }
 }
 
@@ -33,11 +55,11 @@ impl AgentTool for EditFileTool {
     }
 
     fn description(&self) -> &str {
-        "Make a surgical edit to a file by specifying exact text to find and replace. The old_text must match exactly (including whitespace and indentation). For creating new files, use write_file instead."
+        "Make a surgical edit to a file by specifying exact text to find and replace. The old_text should match exactly (including whitespace and indentation); if it doesn't, a unique match of whole lines ignoring leading/trailing whitespace is used instead. For creating new files, use write_file instead."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
-        serde_json::json!({
+        let mut schema = serde_json::json!({
             "type": "object",
             "properties": {
                 "path": {
@@ -54,7 +76,24 @@ impl AgentTool for EditFileTool {
                 }
             },
             "required": ["path", "old_text", "new_text"]
-        })
+        });
+        if self.multi_edit {
+            schema["properties"]["edits"] = serde_json::json!({
+                "type": "array",
+                "description": "Several edits to apply in order, each to the result of the previous one, instead of old_text/new_text. Edits that don't match are reported and skipped.",
+                "minItems": 1,
+                "items": {
+                    "type": "object",
+                    "properties": {
+                        "old_text": { "type": "string", "description": "Exact text to find" },
+                        "new_text": { "type": "string", "description": "Text to replace it with" }
+                    },
+                    "required": ["old_text", "new_text"]
+                }
+            });
+            schema["required"] = serde_json::json!(["path"]);
+        }
+        schema
     }
 
     async fn execute(
@@ -66,6 +105,18 @@ impl AgentTool for EditFileTool {
         let path = params["path"]
             .as_str()
             .ok_or_else(|| ToolError::InvalidArgs("missing 'path' parameter".into()))?;
+        if self.multi_edit && !params["edits"].is_null() {
+            let edits = params["edits"]
+                .as_array()
+                .filter(|edits| !edits.is_empty())
+                .ok_or_else(|| {
+                    ToolError::InvalidArgs("'edits' must be a non-empty array".into())
+                })?;
+            if cancel.is_cancelled() {
+                return Err(ToolError::Cancelled);
+            }
+            return edit_batch(path, edits).await;
+        }
         let old_text = params["old_text"]
             .as_str()
             .ok_or_else(|| ToolError::InvalidArgs("missing 'old_text' parameter".into()))?;
@@ -86,31 +137,10 @@ impl AgentTool for EditFileTool {
         })?;
 
         // Find the old text
-        let match_count = content.matches(old_text).count();
-
-        if match_count == 0 {
-            // Provide helpful error with context
-            let suggestion = find_similar_text(&content, old_text);
-            let hint = if let Some(similar) = suggestion {
-                format!(
-                    "\n\nDid you mean:\n```\n{}\n```\nMake sure old_text matches exactly, including whitespace and indentation.",
-                    similar
-                )
-            } else {
-                "\n\nTip: Use read_file to see the current file contents, then copy the exact text you want to replace.".into()
-            };
-
-            return Err(ToolError::Failed(format!(
-                "old_text not found in {}.{}",
-                path, hint
-            )));
-        }
-
-        if match_count > 1 {
-            return Err(ToolError::Failed(format!(
-                "old_text matches {} locations in {}. Include more surrounding context to make the match unique.",
-                match_count, path
-            )));
+        if let Located::Normalized(m) =
+            locate(&content, old_text, path, "old_text").map_err(ToolError::Failed)?
+        {
+            return replace_normalized(path, &content, m, old_text, new_text).await;
         }
 
         // Perform the replacement
@@ -145,6 +175,389 @@ impl AgentTool for EditFileTool {
             }),
         })
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+/// Where `old_text` is in a file.
+enum Located {
+    /// Exactly once, at this byte offset.
+    Exact(usize),
+    /// Only once whitespace is ignored.
+    Normalized(NormalizedMatch),
+}
+
+/// Find the single place `old_text` matches in `content`: exactly, or else
+/// ignoring whitespace. Errors explain a missing or ambiguous match, naming
+/// the parameter it came from as `field`.
+fn locate(content: &str, old_text: &str, path: &str, field: &str) -> Result<Located, String> {
+    let match_count = content.matches(old_text).count();
+    if match_count > 1 {
+        return Err(format!(
+            "{} matches {} locations in {}. Include more surrounding context to make the match unique.",
+            field, match_count, path
+        ));
+    }
+    if let Some(at) = content.find(old_text) {
+        return Ok(Located::Exact(at));
+    }
+
+    let matches = find_normalized(content, old_text);
+    if matches.len() > 1 {
+        let lines: Vec<String> = matches.iter().map(|m| m.first_line.to_string()).collect();
+        return Err(format!(
+            "{} is ambiguous in {}: ignoring whitespace it matches {} locations, starting at lines {}. Include more surrounding context, or copy the exact text with read_file.",
+            field,
+            path,
+            matches.len(),
+            lines.join(", ")
+        ));
+    }
+    if let Some(m) = matches.into_iter().next() {
+        return Ok(Located::Normalized(m));
+    }
+
+    // Provide helpful error with context
+    let hint = match find_similar_text(content, old_text) {
+        Some(similar) => format!(
+            "\n\nDid you mean:\n```\n{}\n```\nMake sure {} matches exactly, including whitespace and indentation.",
+            similar, field
+        ),
+        None => "\n\nTip: Use read_file to see the current file contents, then copy the exact text you want to replace.".into(),
+    };
+    Err(format!("{} not found in {}.{}", field, path, hint))
+}
+
+/// Apply `edits` in order to the file's content and write it once. Edits
+/// that fail are collected rather than stopping the batch; the call fails
+/// only if none applied.
+async fn edit_batch(path: &str, edits: &[serde_json::Value]) -> Result<ToolResult, ToolError> {
+    let original = tokio::fs::read_to_string(path).await.map_err(|e| {
+        ToolError::Failed(format!(
+            "Cannot read {}: {}. Use write_file to create new files.",
+            path, e
+        ))
+    })?;
+
+    let mut content = original.clone();
+    let mut applied = 0;
+    let mut errors = Vec::new();
+    for (i, edit) in edits.iter().enumerate() {
+        let (Some(old_text), Some(new_text)) =
+            (edit["old_text"].as_str(), edit["new_text"].as_str())
+        else {
+            errors.push((i, "missing 'old_text' or 'new_text'".to_string()));
+            continue;
+        };
+        let range = match locate(&content, old_text, path, "old_text") {
+            Ok(Located::Exact(at)) => at..at + old_text.len(),
+            Ok(Located::Normalized(m)) => m.range,
+            Err(e) => {
+                errors.push((i, e));
+                continue;
+            }
+        };
+        content.replace_range(range, new_text);
+        applied += 1;
+    }
+
+    let error_lines: Vec<String> = errors
+        .iter()
+        .map(|(i, e)| format!("edits[{}] failed: {}", i, e))
+        .collect();
+    if applied == 0 {
+        return Err(ToolError::Failed(format!(
+            "None of the {} edits applied to {}; nothing was written.\n{}",
+            edits.len(),
+            path,
+            error_lines.join("\n")
+        )));
+    }
+    if content != original {
+        tokio::fs::write(path, &content)
+            .await
+            .map_err(|e| ToolError::Failed(format!("Cannot write {}: {}", path, e)))?;
+    }
+
+    let mut text = format!("Applied {} of {} edits to {}", applied, edits.len(), path);
+    for line in &error_lines {
+        text.push('\n');
+        text.push_str(line);
+    }
+    Ok(ToolResult {
+        content: vec![Content::Text { text }],
+        details: serde_json::json!({
+            "path": path,
+            "edits": edits.len(),
+            "applied": applied,
+            "edit_errors": errors
+                .iter()
+                .map(|(i, e)| serde_json::json!({ "index": i, "error": e }))
+                .collect::<Vec<_>>(),
+        }),
+    })
+}
+
+/// A whitespace-normalized match: the byte range of whole lines it covers.
+struct NormalizedMatch {
+    range: std::ops::Range<usize>,
+    first_line: usize,
+    last_line: usize,
+}
+
+/// Find every run of lines in `content` equal to `target`'s lines once each
+/// line's leading and trailing whitespace is ignored. A trailing newline on
+/// `target` is kept in the match so the replacement lines up.
+fn find_normalized(content: &str, target: &str) -> Vec<NormalizedMatch> {
+    let wanted: Vec<&str> = target.lines().map(str::trim).collect();
+    if wanted.iter().all(|line| line.is_empty()) {
+        return Vec::new();
+    }
+
+    // (start, end without newline, end with newline) of each line.
+    let mut spans = Vec::new();
+    let mut start = 0;
+    for line in content.split_inclusive('\n') {
+        let end = start + line.len();
+        spans.push((
+            start,
+            start + line.trim_end_matches(['\n', '\r']).len(),
+            end,
+        ));
+        start = end;
+    }
+
+    let keep_newline = target.ends_with('\n');
+    spans
+        .windows(wanted.len())
+        .enumerate()
+        .filter(|(_, window)| {
+            window
+                .iter()
+                .zip(&wanted)
+                .all(|(&(s, e, _), want)| content[s..e].trim() == *want)
+        })
+        .map(|(i, window)| {
+            let last = window[window.len() - 1];
+            NormalizedMatch {
+                range: window[0].0..if keep_newline { last.2 } else { last.1 },
+                first_line: i + 1,
+                last_line: i + window.len(),
+            }
+        })
+        .collect()
+}
+
+/// Apply an edit found by [`find_normalized`] and say which lines it hit.
+async fn replace_normalized(
+    path: &str,
+    content: &str,
+    m: NormalizedMatch,
+    old_text: &str,
+    new_text: &str,
+) -> Result<ToolResult, ToolError> {
+    let matched = &content[m.range.clone()];
+    let mut new_content = String::with_capacity(content.len() + new_text.len());
+    new_content.push_str(&content[..m.range.start]);
+    new_content.push_str(new_text);
+    new_content.push_str(&content[m.range.end..]);
+
+    tokio::fs::write(path, &new_content)
+        .await
+        .map_err(|e| ToolError::Failed(format!("Cannot write {}: {}", path, e)))?;
+
+    let old_lines = old_text.lines().count();
+    let new_lines = new_text.lines().count();
+    Ok(ToolResult {
+        content: vec![Content::Text {
+            text: format!(
+                "old_text matched lines {}-{} of {} only after ignoring whitespace; replaced them with {} line{}. The original lines were:\n```\n{}\n```",
+                m.first_line,
+                m.last_line,
+                path,
+                new_lines,
+                if new_lines == 1 { "" } else { "s" },
+                matched.trim_end_matches('\n')
+            ),
+        }],
+        details: serde_json::json!({
+            "path": path,
+            "old_lines": old_lines,
+            "new_lines": new_lines,
+            "whitespace_normalized": true,
+            "matched_lines": [m.first_line, m.last_line],
+        }),
+    })
+}
+
+/// Several search/replace edits to one file, applied all-or-nothing.
+///
+/// Edits run in order against the in-memory content, so later edits see the
+/// results of earlier ones. The file is written only if every edit matched;
+/// otherwise the call fails naming the first failing edit and the file is
+/// left untouched.
+#[derive(Clone)]
+pub struct MultiEditTool;
+
+impl Default for MultiEditTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MultiEditTool {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl AgentTool for MultiEditTool {
+    fn name(&self) -> &str {
+        "multi_edit"
+    }
+
+    fn label(&self) -> &str {
+        "Multi Edit"
+    }
+
+    fn description(&self) -> &str {
+        "Make several find/replace edits to one file in a single call. Edits apply in order, each to the result of the previous one. Either all edits succeed or the file is left unchanged. Each old_text must match exactly once unless replace_all is set."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File path to edit"
+                },
+                "edits": {
+                    "type": "array",
+                    "description": "Edits to apply, in order",
+                    "minItems": 1,
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "old_text": {
+                                "type": "string",
+                                "description": "Exact text to find (must match exactly, including whitespace)"
+                            },
+                            "new_text": {
+                                "type": "string",
+                                "description": "Text to replace it with"
+                            },
+                            "replace_all": {
+                                "type": "boolean",
+                                "description": "Replace every occurrence instead of requiring a unique match (default: false)"
+                            }
+                        },
+                        "required": ["old_text", "new_text"]
+                    }
+                }
+            },
+            "required": ["path", "edits"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let cancel = ctx.cancel;
+        let path = params["path"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'path' parameter".into()))?;
+        let edits = params["edits"]
+            .as_array()
+            .filter(|edits| !edits.is_empty())
+            .ok_or_else(|| ToolError::InvalidArgs("'edits' must be a non-empty array".into()))?;
+
+        if cancel.is_cancelled() {
+            return Err(ToolError::Cancelled);
+        }
+
+        let original = tokio::fs::read_to_string(path).await.map_err(|e| {
+            ToolError::Failed(format!(
+                "Cannot read {}: {}. Use write_file to create new files.",
+                path, e
+            ))
+        })?;
+
+        let mut content = original.clone();
+        let mut replacements = 0;
+        for (i, edit) in edits.iter().enumerate() {
+            let old = edit["old_text"].as_str().ok_or_else(|| {
+                ToolError::InvalidArgs(format!("edits[{}]: missing 'old_text'", i))
+            })?;
+            let new = edit["new_text"].as_str().ok_or_else(|| {
+                ToolError::InvalidArgs(format!("edits[{}]: missing 'new_text'", i))
+            })?;
+            let replace_all = edit["replace_all"].as_bool().unwrap_or(false);
+
+            if old.is_empty() {
+                return Err(ToolError::InvalidArgs(format!(
+                    "edits[{}]: old_text must not be empty",
+                    i
+                )));
+            }
+            let match_count = content.matches(old).count();
+            if match_count == 0 {
+                let hint = match find_similar_text(&content, old) {
+                    Some(similar) => format!(
+                        "\n\nDid you mean:\n```\n{}\n```\nMake sure old_text matches exactly, including whitespace and indentation.",
+                        similar
+                    ),
+                    None => String::new(),
+                };
+                return Err(ToolError::Failed(format!(
+                    "edits[{}]: old_text not found in {} (after applying the edits before it). No changes were written.{}",
+                    i, path, hint
+                )));
+            }
+            if match_count > 1 && !replace_all {
+                return Err(ToolError::Failed(format!(
+                    "edits[{}]: old_text matches {} locations in {}. Include more surrounding context, or set replace_all. No changes were written.",
+                    i, match_count, path
+                )));
+            }
+
+            content = content.replace(old, new);
+            replacements += match_count;
+        }
+
+        if content != original {
+            tokio::fs::write(path, &content)
+                .await
+                .map_err(|e| ToolError::Failed(format!("Cannot write {}: {}", path, e)))?;
+        }
+
+        Ok(ToolResult {
+            content: vec![Content::Text {
+                text: format!(
+                    "Applied {} edit{} ({} replacement{}) to {}",
+                    edits.len(),
+                    if edits.len() == 1 { "" } else { "s" },
+                    replacements,
+                    if replacements == 1 { "" } else { "s" },
+                    path
+                ),
+            }],
+            details: serde_json::json!({
+                "path": path,
+                "edits": edits.len(),
+                "replacements": replacements,
+            }),
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
 }
 
 /// Try to find similar text in the file (fuzzy match for better error messages).
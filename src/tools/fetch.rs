@@ -0,0 +1,425 @@
+//! Fetch tool — read a web page or API response as text.
+
+use super::http::{build_request, read_body, request_error};
+use crate::types::*;
+use async_trait::async_trait;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// Fetch a URL and return its body as text: HTML pages are reduced to
+/// readable text, anything else is returned as-is.
+///
+/// Unlike [`HttpTool`](super::HttpTool), which shows the model the raw
+/// exchange (status line, headers, body), this returns only the content;
+/// status and content type go in `details`. Only `http` and `https` URLs are
+/// fetched. Not part of [`default_tools`](super::default_tools) — network
+/// access is opt-in.
+///
+/// With [`with_block_private_ips`](Self::with_block_private_ips), requests
+/// to loopback, private, link-local and other non-public addresses are
+/// refused — including hosts that resolve to one, and redirects to one —
+/// so a model can't be steered into probing the internal network.
+#[derive(Clone)]
+pub struct FetchTool {
+    client: reqwest::Client,
+    /// Request timeout
+    pub timeout: Duration,
+    /// Max response body bytes to read (a call may ask for fewer)
+    pub max_bytes: usize,
+    block_private_ips: bool,
+}
+
+impl Default for FetchTool {
+    fn default() -> Self {
+        Self {
+            client: build_client(false),
+            timeout: Duration::from_secs(30),
+            max_bytes: 64 * 1024, // 64KB
+            block_private_ips: false,
+        }
+    }
+}
+
+impl FetchTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_bytes(mut self, max: usize) -> Self {
+        self.max_bytes = max;
+        self
+    }
+
+    /// Refuse URLs whose host is, or resolves to, a non-public address.
+    /// System proxy settings are ignored while this is on, since a proxy
+    /// would do the resolving.
+    pub fn with_block_private_ips(mut self, block: bool) -> Self {
+        self.block_private_ips = block;
+        self.client = build_client(block);
+        self
+    }
+}
+
+fn build_client(block_private_ips: bool) -> reqwest::Client {
+    let builder = reqwest::Client::builder();
+    let builder = if block_private_ips {
+        builder
+            .no_proxy()
+            .dns_resolver(PublicOnlyResolver)
+            .redirect(reqwest::redirect::Policy::custom(|attempt| {
+                // Hosts given as IP literals never reach the resolver.
+                if attempt.previous().len() >= 10 {
+                    attempt.error("too many redirects")
+                } else if let Some(ip) = private_literal(attempt.url()) {
+                    let message = format!("redirect to non-public address {}", ip);
+                    attempt.error(message)
+                } else {
+                    attempt.follow()
+                }
+            }))
+    } else {
+        builder
+    };
+    builder.build().expect("reqwest client builds")
+}
+
+#[async_trait]
+impl AgentTool for FetchTool {
+    fn name(&self) -> &str {
+        "fetch"
+    }
+
+    fn label(&self) -> &str {
+        "Fetch URL"
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a URL and return its content as text. HTML pages are converted to readable text. Use to read web pages, documentation, or API responses."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "http or https URL to fetch"
+                },
+                "method": {
+                    "type": "string",
+                    "enum": ["GET", "POST", "PUT", "DELETE", "PATCH"],
+                    "description": "HTTP method (default: GET)"
+                },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Request headers (optional)"
+                },
+                "body": {
+                    "type": ["string", "object"],
+                    "description": "Request body. Objects are sent as JSON; strings are sent as-is (optional)"
+                },
+                "max_bytes": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Maximum bytes of the response to read (optional)"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let cancel = ctx.cancel;
+        let url = params["url"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'url' parameter".into()))?;
+        let parsed = reqwest::Url::parse(url)
+            .map_err(|e| ToolError::InvalidArgs(format!("invalid URL '{}': {}", url, e)))?;
+        if !matches!(parsed.scheme(), "http" | "https") {
+            return Err(ToolError::InvalidArgs(format!(
+                "unsupported URL scheme '{}': only http and https URLs can be fetched",
+                parsed.scheme()
+            )));
+        }
+        if self.block_private_ips {
+            if let Some(ip) = private_literal(&parsed) {
+                return Err(ToolError::Failed(format!(
+                    "Refusing to fetch {}: {} is not a public address",
+                    url, ip
+                )));
+            }
+        }
+        let max_bytes = params["max_bytes"]
+            .as_u64()
+            .map_or(self.max_bytes, |n| (n as usize).clamp(1, self.max_bytes));
+
+        let req = build_request(&self.client, url, &params, self.timeout)?;
+        let mut response = tokio::select! {
+            _ = cancel.cancelled() => return Err(ToolError::Cancelled),
+            result = req.send() => result.map_err(|e| request_error(e, self.timeout))?,
+        };
+
+        let status = response.status();
+        let final_url = response.url().to_string();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let (body, truncated) = read_body(&mut response, max_bytes, self.timeout, &cancel).await?;
+
+        let body = String::from_utf8_lossy(&body);
+        let is_html = content_type.as_deref().is_some_and(|ct| {
+            let mime = ct.split(';').next().unwrap_or("").trim();
+            mime.eq_ignore_ascii_case("text/html")
+                || mime.eq_ignore_ascii_case("application/xhtml+xml")
+        });
+        let mut text = if is_html {
+            html_to_text(&body)
+        } else {
+            body.into_owned()
+        };
+        if !status.is_success() {
+            text = format!("HTTP {}\n\n{}", status, text);
+        }
+        if truncated {
+            text.push_str(&format!(
+                "\n\n... [truncated: response exceeded {} bytes]",
+                max_bytes
+            ));
+        }
+
+        Ok(ToolResult {
+            content: vec![Content::Text { text }],
+            details: serde_json::json!({
+                "url": final_url,
+                "status": status.as_u16(),
+                "content_type": content_type,
+                "html_converted": is_html,
+                "truncated": truncated,
+            }),
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+/// Resolves names with the system resolver, failing if any address the name
+/// resolves to isn't public.
+struct PublicOnlyResolver;
+
+impl reqwest::dns::Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+                return Err(
+                    format!("{} resolves to non-public address {}", host, addr.ip()).into(),
+                );
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// The URL's host, if it is an IP literal that isn't public.
+fn private_literal(url: &reqwest::Url) -> Option<IpAddr> {
+    let host = url.host_str()?;
+    let ip: IpAddr = host.trim_matches(['[', ']']).parse().ok()?;
+    (!is_public(ip)).then_some(ip)
+}
+
+/// Whether `ip` is a globally routable address: not loopback, private,
+/// link-local, shared (CGNAT), unspecified, broadcast or multicast.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    || ip.is_unique_local()
+                    || ip.is_unicast_link_local())
+            }
+        },
+    }
+}
+
+/// Reduce an HTML page to readable text: drop `<script>`, `<style>` and
+/// comments, turn block-level tags into line breaks, strip the remaining
+/// tags, decode common entities, and collapse whitespace and blank lines.
+pub(super) fn html_to_text(html: &str) -> String {
+    const BLOCK_TAGS: &[&str] = &[
+        "p",
+        "br",
+        "div",
+        "li",
+        "ul",
+        "ol",
+        "tr",
+        "table",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "pre",
+        "blockquote",
+        "section",
+        "article",
+        "header",
+        "footer",
+        "title",
+        "hr",
+    ];
+
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+    while let Some(start) = rest.find('<') {
+        text.push_str(&rest[..start]);
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        let name: String = tag
+            .trim_start_matches('/')
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if (name == "script" || name == "style") && !tag.starts_with('/') {
+            let close = format!("</{}", name);
+            let lower = rest.to_ascii_lowercase();
+            rest = match lower.find(&close) {
+                Some(at) => rest[at..].find('>').map_or("", |gt| &rest[at + gt + 1..]),
+                None => "",
+            };
+            continue;
+        }
+        if BLOCK_TAGS.contains(&name.as_str()) {
+            text.push('\n');
+        }
+    }
+    text.push_str(rest);
+
+    decode_entities(&text)
+        .lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let entity = rest[1..]
+            .find(';')
+            .filter(|&len| len <= 10)
+            .map(|len| &rest[1..len + 1]);
+        let decoded = entity.and_then(|entity| match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => {
+                let code = entity.strip_prefix('#')?;
+                let code = match code.strip_prefix(['x', 'X']) {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => code.parse().ok()?,
+                };
+                char::from_u32(code)
+            }
+        });
+        match (entity, decoded) {
+            (Some(entity), Some(c)) => {
+                out.push(c);
+                rest = &rest[entity.len() + 2..];
+            }
+            _ => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn html_is_reduced_to_text() {
+        let html = "<html><head><title>Docs</title><style>p { color: red }</style>\
+                    <script>alert('<p>')</script></head>\
+                    <body><!-- nav --><h1>Install</h1><p>Run <code>cargo add</code> &amp; go.</p>\
+                    <ul><li>One</li><li>Two&nbsp;&#x2713;</li></ul></body></html>";
+        assert_eq!(
+            html_to_text(html),
+            "Docs\nInstall\nRun cargo add & go.\nOne\nTwo ✓"
+        );
+    }
+
+    #[test]
+    fn private_addresses_are_not_public() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public(ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["93.184.216.34", "2606:4700::1111"] {
+            assert!(is_public(ip.parse().unwrap()), "{}", ip);
+        }
+    }
+}
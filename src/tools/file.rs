@@ -18,6 +18,31 @@ fn is_image_file(path: &Path) -> bool {
     )
 }
 
+/// 32 MB limit for documents (Anthropic's request size limit)
+const MAX_DOCUMENT_SIZE_BYTES: u64 = 32 * 1024 * 1024;
+
+/// Documents `return_binary` can send as `Content::Document`.
+fn get_document_mime_type(path: &Path) -> Option<&'static str> {
+    match path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_lowercase())
+        .as_deref()
+    {
+        Some("pdf") => Some("application/pdf"),
+        Some("doc") => Some("application/msword"),
+        Some("docx") => {
+            Some("application/vnd.openxmlformats-officedocument.wordprocessingml.document")
+        }
+        Some("xlsx") => Some("application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"),
+        Some("pptx") => {
+            Some("application/vnd.openxmlformats-officedocument.presentationml.presentation")
+        }
+        Some("odt") => Some("application/vnd.oasis.opendocument.text"),
+        _ => None,
+    }
+}
+
 fn get_image_mime_type(path: &Path) -> Option<&'static str> {
     match path
         .extension()
@@ -34,10 +59,18 @@ fn get_image_mime_type(path: &Path) -> Option<&'static str> {
     }
 }
 
-/// Read a file's contents. Supports line range for large files.
+/// Read a file's contents. Supports line and byte ranges for large files.
+///
+/// Text is streamed, so a range can be read from a file of any size. Without
+/// a `limit`, at most `max_lines` lines are returned, with a note saying where
+/// to continue; a result never carries more than `max_bytes` of file content.
+#[derive(Clone)]
 pub struct ReadFileTool {
-    /// Max file size to read (prevents OOM)
+    /// Max bytes of file content returned by one call (prevents OOM and
+    /// context blowups)
     pub max_bytes: usize,
+    /// Lines returned when the call gives no `limit`
+    pub max_lines: usize,
     /// Allowed directory roots (empty = no restriction)
     pub allowed_paths: Vec<String>,
 }
@@ -46,6 +79,7 @@ impl Default for ReadFileTool {
     fn default() -> Self {
         Self {
             max_bytes: 1024 * 1024, // 1MB
+            max_lines: 2000,
             allowed_paths: Vec::new(),
         }
     }
@@ -57,6 +91,39 @@ impl ReadFileTool {
     }
 }
 
+/// Read a whole document for `return_binary`.
+async fn read_document(path: &str, size: u64) -> Result<ToolResult, ToolError> {
+    let file_path = Path::new(path);
+    let mime_type = get_document_mime_type(file_path).ok_or_else(|| {
+        ToolError::InvalidArgs(format!(
+            "return_binary supports PDF and Office documents; {} is not one. Omit it to read the file as text.",
+            path
+        ))
+    })?;
+    if size > MAX_DOCUMENT_SIZE_BYTES {
+        return Err(ToolError::Failed(format!(
+            "Document too large ({}MB, max 32MB)",
+            size / (1024 * 1024)
+        )));
+    }
+    let data = tokio::fs::read(path)
+        .await
+        .map_err(|e| ToolError::Failed(format!("Cannot read {}: {}", path, e)))?;
+    let filename = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string());
+    let bytes = data.len();
+    Ok(ToolResult {
+        content: vec![Content::Document {
+            data,
+            mime_type: mime_type.to_string(),
+            filename,
+        }],
+        details: serde_json::json!({ "path": path, "bytes": bytes }),
+    })
+}
+
 #[async_trait]
 impl AgentTool for ReadFileTool {
     fn name(&self) -> &str {
@@ -68,7 +135,7 @@ impl AgentTool for ReadFileTool {
     }
 
     fn description(&self) -> &str {
-        "Read a file's contents. Supports text files with optional offset/limit, and image files (jpg, png, webp, gif, bmp) which are returned as base64-encoded images."
+        "Read a file's contents. Supports text files with optional offset/limit or an inclusive start_line/end_line range (long files are truncated; the result says how to read on), a byte range for binary files, image files (jpg, png, webp, gif, bmp) which are returned as base64-encoded images, and documents (pdf, docx, ...) which are attached as-is when return_binary is set."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -86,12 +153,37 @@ impl AgentTool for ReadFileTool {
                 "limit": {
                     "type": "integer",
                     "description": "Maximum number of lines to return (optional)"
+                },
+                "start_line": {
+                    "type": "integer",
+                    "description": "First line of a range to read (1-indexed, inclusive, optional). Use instead of offset/limit."
+                },
+                "end_line": {
+                    "type": "integer",
+                    "description": "Last line of the range (inclusive, at least start_line; optional, default: end of file)"
+                },
+                "bytes": {
+                    "type": "object",
+                    "description": "Byte range to read instead of lines, for binary files (optional). Non-text content is returned as a hex dump.",
+                    "properties": {
+                        "start": { "type": "integer", "description": "First byte (0-indexed)" },
+                        "end": { "type": "integer", "description": "End of the range, exclusive (optional)" }
+                    },
+                    "required": ["start"]
+                },
+                "return_binary": {
+                    "type": "boolean",
+                    "description": "Attach a PDF or Office document as a document instead of reading it as text (optional)"
                 }
             },
             "required": ["path"]
         })
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     async fn execute(
         &self,
         params: serde_json::Value,
@@ -110,6 +202,10 @@ impl AgentTool for ReadFileTool {
             .await
             .map_err(|e| ToolError::Failed(format!("Cannot access {}: {}", path, e)))?;
 
+        if let Some(range) = params.get("bytes").filter(|v| !v.is_null()) {
+            return self.read_bytes(path, range, metadata.len()).await;
+        }
+
         // Handle image files: read as binary, return base64-encoded Content::Image
         let file_path = Path::new(path);
         if is_image_file(file_path) {
@@ -134,40 +230,54 @@ impl AgentTool for ReadFileTool {
             });
         }
 
-        // Text files: check size limit and apply line offset/limit
-        if metadata.len() as usize > self.max_bytes {
-            return Err(ToolError::Failed(format!(
-                "File too large ({} bytes, max {}). Use offset/limit for partial reads.",
-                metadata.len(),
-                self.max_bytes
-            )));
+        if params["return_binary"].as_bool() == Some(true) {
+            return read_document(path, metadata.len()).await;
         }
 
-        let content = tokio::fs::read_to_string(path)
-            .await
-            .map_err(|e| ToolError::Failed(format!("Cannot read {}: {}", path, e)))?;
-
-        let offset = params["offset"].as_u64().map(|v| v.max(1) as usize);
-        let limit = params["limit"].as_u64().map(|v| v as usize);
-
-        // Always show line numbers — helps agent reference exact lines for edit_file
-        let lines: Vec<&str> = content.lines().collect();
-        let total = lines.len();
-
-        let (start, end) = match (offset, limit) {
-            (Some(off), Some(lim)) => {
-                let s = (off - 1).min(total);
-                (s, (s + lim).min(total))
-            }
-            (Some(off), None) => {
-                let s = (off - 1).min(total);
-                (s, total)
+        // `start_line`/`end_line` is an inclusive range; out-of-bounds
+        // ranges are clamped to the file rather than rejected, but a
+        // backwards one is an error.
+        let start_line = params["start_line"].as_u64().map(|v| v.max(1) as usize);
+        let end_line = params["end_line"].as_u64().map(|v| v as usize);
+        let ranged = start_line.is_some() || end_line.is_some();
+        let (offset, limit) = if ranged {
+            let first = start_line.unwrap_or(1);
+            if end_line.is_some_and(|last| last < first) {
+                return Err(ToolError::InvalidArgs(
+                    "end_line must be >= start_line".into(),
+                ));
             }
-            (None, Some(lim)) => (0, lim.min(total)),
-            (None, None) => (0, total),
+            (Some(first), end_line.map(|last| last - first + 1))
+        } else {
+            (
+                params["offset"].as_u64().map(|v| v.max(1) as usize),
+                params["limit"].as_u64().map(|v| v as usize),
+            )
         };
+        let mut skip = offset.map_or(0, |off| off - 1);
+        let take = limit.unwrap_or(self.max_lines);
 
-        let numbered: Vec<String> = lines[start..end]
+        let mut window = read_lines(path, skip, take, self.max_bytes, &ctx.cancel).await?;
+        let total = window.total;
+        if skip > 0 && skip >= total {
+            if !ranged || total == 0 {
+                return Err(ToolError::Failed(format!(
+                    "Offset {} is past the end of {} ({} lines).",
+                    skip + 1,
+                    path,
+                    total
+                )));
+            }
+            // The range starts past the end: return the last line.
+            skip = total - 1;
+            window = read_lines(path, skip, 1, self.max_bytes, &ctx.cancel).await?;
+        }
+
+        // Always show line numbers — helps agent reference exact lines for edit_file
+        let start = skip;
+        let end = skip + window.lines.len();
+        let numbered: Vec<String> = window
+            .lines
             .iter()
             .enumerate()
             .map(|(i, line)| format!("{:>4} | {}", start + i + 1, line))
@@ -179,18 +289,234 @@ impl AgentTool for ReadFileTool {
             format!("[{} lines]", total)
         };
 
-        let output = format!("{}\n{}", header, numbered.join("\n"));
+        let mut output = format!("{}\n{}", header, numbered.join("\n"));
+        // Cut short by us rather than by the caller's limit: say how to go on.
+        let truncated = end < total && (limit.is_none() || window.byte_capped);
+        if truncated {
+            output.push_str(&format!(
+                "\n\n... [truncated: {} more lines. Use offset={} to continue.]",
+                total - end,
+                end + 1
+            ));
+        }
 
         Ok(ToolResult {
             content: vec![Content::Text { text: output }],
-            details: serde_json::json!({ "path": path }),
+            details: serde_json::json!({
+                "path": path,
+                "start_line": start + 1,
+                "end_line": end,
+                "lines_read": end - start,
+                "total_lines": total,
+                "truncated": truncated,
+            }),
         })
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+impl ReadFileTool {
+    /// Read `range` (`{start, end?}`, end exclusive) as text, or as a hex
+    /// dump when it isn't UTF-8.
+    async fn read_bytes(
+        &self,
+        path: &str,
+        range: &serde_json::Value,
+        len: u64,
+    ) -> Result<ToolResult, ToolError> {
+        use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+        let start = range["start"]
+            .as_u64()
+            .ok_or_else(|| ToolError::InvalidArgs("'bytes' needs a 'start' offset".into()))?;
+        let end = range["end"].as_u64().unwrap_or(len).min(len);
+        if start >= len && len > 0 {
+            return Err(ToolError::Failed(format!(
+                "Byte offset {} is past the end of {} ({} bytes).",
+                start, path, len
+            )));
+        }
+        if end < start {
+            return Err(ToolError::InvalidArgs(format!(
+                "'bytes' end ({}) is before start ({})",
+                end, start
+            )));
+        }
+
+        let mut file = tokio::fs::File::open(path)
+            .await
+            .map_err(|e| ToolError::Failed(format!("Cannot read {}: {}", path, e)))?;
+        file.seek(std::io::SeekFrom::Start(start))
+            .await
+            .map_err(|e| ToolError::Failed(format!("Cannot read {}: {}", path, e)))?;
+        let wanted = (end - start).min(self.max_bytes as u64);
+        let mut buf = Vec::with_capacity(wanted as usize);
+        file.take(wanted)
+            .read_to_end(&mut buf)
+            .await
+            .map_err(|e| ToolError::Failed(format!("Cannot read {}: {}", path, e)))?;
+
+        let text = as_text(&buf).map(str::to_string);
+        let (body, hex) = match text {
+            Some(text) => {
+                // Drop a character cut off by the range so `end` is exact.
+                buf.truncate(text.len());
+                (text, false)
+            }
+            None => {
+                // A hex dump is ~4x the input; keep it within max_bytes.
+                buf.truncate((self.max_bytes / 4).max(16));
+                (hex_dump(&buf, start), true)
+            }
+        };
+        let shown_end = start + buf.len() as u64;
+        let mut output = format!("[Bytes {}-{} of {}]\n{}", start, shown_end, len, body);
+        let truncated = shown_end < end;
+        if truncated {
+            output.push_str(&format!(
+                "\n\n... [truncated: {} more bytes in range. Use bytes.start={} to continue.]",
+                end - shown_end,
+                shown_end
+            ));
+        }
+
+        Ok(ToolResult {
+            content: vec![Content::Text { text: output }],
+            details: serde_json::json!({
+                "path": path,
+                "start_byte": start,
+                "end_byte": shown_end,
+                "total_bytes": len,
+                "hex": hex,
+                "truncated": truncated,
+            }),
+        })
+    }
+}
+
+/// The lines a text read returned, and how many the file has in total.
+struct LineWindow {
+    lines: Vec<String>,
+    total: usize,
+    /// Stopped early because `max_bytes` of content was collected.
+    byte_capped: bool,
+}
+
+/// Stream `path`, keeping up to `take` lines after the first `skip` and at
+/// most `max_bytes` of content, while counting every line.
+async fn read_lines(
+    path: &str,
+    skip: usize,
+    take: usize,
+    max_bytes: usize,
+    cancel: &tokio_util::sync::CancellationToken,
+) -> Result<LineWindow, ToolError> {
+    use tokio::io::AsyncBufReadExt;
+
+    let read_err = |e: std::io::Error| ToolError::Failed(format!("Cannot read {}: {}", path, e));
+    let file = tokio::fs::File::open(path).await.map_err(read_err)?;
+    let mut reader = tokio::io::BufReader::new(file);
+    let mut window = LineWindow {
+        lines: Vec::new(),
+        total: 0,
+        byte_capped: false,
+    };
+    let mut used = 0;
+    let mut buf = Vec::new();
+    loop {
+        buf.clear();
+        if reader.read_until(b'\n', &mut buf).await.map_err(read_err)? == 0 {
+            break;
+        }
+        window.total += 1;
+        if window.total % 4096 == 0 && cancel.is_cancelled() {
+            return Err(ToolError::Cancelled);
+        }
+        let index = window.total - 1;
+        if index < skip || window.lines.len() >= take || window.byte_capped {
+            continue;
+        }
+
+        let line = std::str::from_utf8(&buf).map_err(|_| {
+            let hint = if get_document_mime_type(Path::new(path)).is_some() {
+                "Use return_binary to attach it as a document, or 'bytes' to read it raw."
+            } else {
+                "Use 'bytes' to read it raw."
+            };
+            ToolError::Failed(format!(
+                "{} is not UTF-8 text (line {}). {}",
+                path, window.total, hint
+            ))
+        })?;
+        let mut line = line
+            .trim_end_matches('\n')
+            .trim_end_matches('\r')
+            .to_string();
+        if used + line.len() > max_bytes {
+            window.byte_capped = true;
+            if !window.lines.is_empty() {
+                continue;
+            }
+            // A single huge line: return what fits of it.
+            let mut cut = max_bytes;
+            while !line.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            line.truncate(cut);
+            line.push_str(" ... [line truncated]");
+        }
+        used += line.len() + 1;
+        window.lines.push(line);
+    }
+    Ok(window)
+}
+
+/// `bytes` as text if it is UTF-8 (ignoring a character cut off at the end)
+/// without NULs.
+fn as_text(bytes: &[u8]) -> Option<&str> {
+    let text = match std::str::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) if e.error_len().is_none() => std::str::from_utf8(&bytes[..e.valid_up_to()]).ok()?,
+        Err(_) => return None,
+    };
+    (!text.contains('\0')).then_some(text)
+}
+
+/// `xxd`-style dump: offset, 16 hex bytes, printable ASCII.
+fn hex_dump(bytes: &[u8], base: u64) -> String {
+    bytes
+        .chunks(16)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!(
+                "{:08x}  {:<47}  |{}|",
+                base + i as u64 * 16,
+                hex.join(" "),
+                ascii
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 // ---------------------------------------------------------------------------
 
 /// Write content to a file. Creates parent directories if needed.
+#[derive(Clone)]
 pub struct WriteFileTool;
 
 impl Default for WriteFileTool {
@@ -273,4 +599,8 @@ impl AgentTool for WriteFileTool {
             details: serde_json::json!({ "path": path, "bytes": bytes }),
         })
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
 }
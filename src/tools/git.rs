@@ -0,0 +1,502 @@
+//! Git tool — status, diff, log, add, commit, branch and checkout through
+//! libgit2 (feature `git`).
+
+use crate::types::*;
+use async_trait::async_trait;
+use git2::{
+    BranchType, DiffFormat, DiffOptions, IndexAddOption, Repository, Sort, Status, StatusOptions,
+};
+
+/// Run common git operations on a repository without a `git` binary.
+///
+/// Backed by `git2` (libgit2), so it works wherever the crate builds —
+/// Windows included — and never spawns a process. Local operations only:
+/// no fetch, pull or push. Commits use the repository's configured
+/// `user.name`/`user.email`.
+#[derive(Clone)]
+pub struct GitTool {
+    /// Directory to discover the repository from (default: the process
+    /// working directory)
+    pub root: Option<String>,
+    /// Max commits `log` returns (a call may ask for fewer)
+    pub max_log_entries: usize,
+    /// Max bytes of `diff` output; longer diffs are cut with a note
+    pub max_output_bytes: usize,
+}
+
+impl Default for GitTool {
+    fn default() -> Self {
+        Self {
+            root: None,
+            max_log_entries: 20,
+            max_output_bytes: 30 * 1024,
+        }
+    }
+}
+
+impl GitTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_root(mut self, root: impl Into<String>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+}
+
+#[async_trait]
+impl AgentTool for GitTool {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    fn label(&self) -> &str {
+        "Git"
+    }
+
+    fn description(&self) -> &str {
+        "Run a git operation on the repository: status (changed files), diff (unified diff of unstaged changes, or staged ones with staged=true), log (recent commits), add (stage files), commit, branch (list, or create with name), checkout (switch to an existing branch)."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "operation": {
+                    "type": "string",
+                    "enum": ["status", "diff", "log", "add", "commit", "branch", "checkout"],
+                    "description": "Git operation to run"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "diff/add: limit to this file, directory or pathspec (add defaults to everything)"
+                },
+                "staged": {
+                    "type": "boolean",
+                    "description": "diff: show staged changes (index vs HEAD) instead of unstaged ones (default: false)"
+                },
+                "message": {
+                    "type": "string",
+                    "description": "commit: the commit message (required)"
+                },
+                "add_all": {
+                    "type": "boolean",
+                    "description": "commit: stage all changes, new files included, before committing (default: false)"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "branch: name of a branch to create at HEAD; checkout: branch to switch to (required)"
+                },
+                "limit": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "log: number of commits to show (optional)"
+                }
+            },
+            "required": ["operation"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let operation = params["operation"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'operation' parameter".into()))?
+            .to_string();
+        let root = self.root.clone().unwrap_or_else(|| ".".into());
+        let max_log_entries = self.max_log_entries;
+        let max_output_bytes = self.max_output_bytes;
+
+        if ctx.cancel.is_cancelled() {
+            return Err(ToolError::Cancelled);
+        }
+
+        // libgit2 is blocking; keep it off the async runtime.
+        let run = tokio::task::spawn_blocking(move || {
+            let repo = Repository::discover(&root).map_err(git_error)?;
+            match operation.as_str() {
+                "status" => status(&repo),
+                "diff" => diff(
+                    &repo,
+                    params["path"].as_str(),
+                    params["staged"].as_bool().unwrap_or(false),
+                    max_output_bytes,
+                ),
+                "log" => {
+                    let limit = params["limit"]
+                        .as_u64()
+                        .map_or(max_log_entries, |n| (n as usize).clamp(1, max_log_entries));
+                    log(&repo, limit)
+                }
+                "add" => add(&repo, params["path"].as_str().unwrap_or(".")),
+                "commit" => {
+                    let message = params["message"].as_str().ok_or_else(|| {
+                        ToolError::InvalidArgs("commit needs a 'message' parameter".into())
+                    })?;
+                    commit(&repo, message, params["add_all"].as_bool().unwrap_or(false))
+                }
+                "branch" => branch(&repo, params["name"].as_str()),
+                "checkout" => {
+                    let name = params["name"].as_str().ok_or_else(|| {
+                        ToolError::InvalidArgs("checkout needs a 'name' parameter".into())
+                    })?;
+                    checkout(&repo, name)
+                }
+                other => Err(ToolError::InvalidArgs(format!(
+                    "unknown operation '{}'. Use status, diff, log, add, commit, branch or checkout.",
+                    other
+                ))),
+            }
+        });
+        let (text, details) = tokio::select! {
+            _ = ctx.cancel.cancelled() => return Err(ToolError::Cancelled),
+            result = run => result.map_err(|e| ToolError::Failed(format!("git failed: {}", e)))??,
+        };
+
+        Ok(ToolResult {
+            content: vec![Content::Text { text }],
+            details,
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+type GitOutput = Result<(String, serde_json::Value), ToolError>;
+
+fn git_error(e: git2::Error) -> ToolError {
+    ToolError::Failed(format!("git: {}", e.message()))
+}
+
+/// Current branch name, or `HEAD (detached at abc1234)`.
+fn head_name(repo: &Repository) -> String {
+    match repo.head() {
+        Ok(head) if head.is_branch() => head.shorthand().unwrap_or("HEAD").to_string(),
+        Ok(head) => match head.target() {
+            Some(oid) => format!("HEAD (detached at {})", short_id(oid)),
+            None => "HEAD (detached)".into(),
+        },
+        // Unborn branch: HEAD names a branch with no commits yet.
+        Err(_) => repo
+            .find_reference("HEAD")
+            .ok()
+            .and_then(|r| r.symbolic_target().map(String::from))
+            .map(|target| target.trim_start_matches("refs/heads/").to_string())
+            .unwrap_or_else(|| "HEAD".into()),
+    }
+}
+
+fn short_id(oid: git2::Oid) -> String {
+    oid.to_string()[..7].to_string()
+}
+
+/// `git status --short`-style listing: index column, worktree column, path.
+fn status(repo: &Repository) -> GitOutput {
+    let mut opts = StatusOptions::new();
+    opts.include_untracked(true)
+        .recurse_untracked_dirs(true)
+        .renames_head_to_index(true);
+    let statuses = repo.statuses(Some(&mut opts)).map_err(git_error)?;
+
+    let branch = head_name(repo);
+    let mut lines = vec![format!("## {}", branch)];
+    let mut files = Vec::new();
+    for entry in statuses.iter() {
+        let s = entry.status();
+        if s.contains(Status::IGNORED) {
+            continue;
+        }
+        let path = entry.path().unwrap_or("?").to_string();
+        let (index, worktree) = if s.contains(Status::CONFLICTED) {
+            ('U', 'U')
+        } else if s.contains(Status::WT_NEW) && !s.intersects(index_flags()) {
+            ('?', '?')
+        } else {
+            (index_code(s), worktree_code(s))
+        };
+        lines.push(format!("{}{} {}", index, worktree, path));
+        files.push(serde_json::json!({
+            "path": path,
+            "index": index.to_string().trim(),
+            "worktree": worktree.to_string().trim(),
+        }));
+    }
+    if files.is_empty() {
+        lines.push("nothing to commit, working tree clean".into());
+    }
+    Ok((
+        lines.join("\n"),
+        serde_json::json!({ "branch": branch, "files": files }),
+    ))
+}
+
+fn index_flags() -> Status {
+    Status::INDEX_NEW
+        | Status::INDEX_MODIFIED
+        | Status::INDEX_DELETED
+        | Status::INDEX_RENAMED
+        | Status::INDEX_TYPECHANGE
+}
+
+fn index_code(s: Status) -> char {
+    if s.contains(Status::INDEX_NEW) {
+        'A'
+    } else if s.contains(Status::INDEX_MODIFIED) {
+        'M'
+    } else if s.contains(Status::INDEX_DELETED) {
+        'D'
+    } else if s.contains(Status::INDEX_RENAMED) {
+        'R'
+    } else if s.contains(Status::INDEX_TYPECHANGE) {
+        'T'
+    } else {
+        ' '
+    }
+}
+
+fn worktree_code(s: Status) -> char {
+    if s.contains(Status::WT_MODIFIED) {
+        'M'
+    } else if s.contains(Status::WT_DELETED) {
+        'D'
+    } else if s.contains(Status::WT_RENAMED) {
+        'R'
+    } else if s.contains(Status::WT_TYPECHANGE) {
+        'T'
+    } else if s.contains(Status::WT_NEW) {
+        '?'
+    } else {
+        ' '
+    }
+}
+
+/// Unified diff of the worktree against the index, or (`staged`) of the
+/// index against HEAD.
+fn diff(repo: &Repository, path: Option<&str>, staged: bool, max_bytes: usize) -> GitOutput {
+    let mut opts = DiffOptions::new();
+    if let Some(path) = path {
+        opts.pathspec(path);
+    }
+    let diff = if staged {
+        // An unborn HEAD has no tree: everything staged is new.
+        let head_tree = repo.head().ok().and_then(|h| h.peel_to_tree().ok());
+        repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))
+    } else {
+        repo.diff_index_to_workdir(None, Some(&mut opts))
+    }
+    .map_err(git_error)?;
+    let stats = diff.stats().map_err(git_error)?;
+
+    let mut text = String::new();
+    let mut truncated = false;
+    diff.print(DiffFormat::Patch, |_delta, _hunk, line| {
+        let mut chunk = String::new();
+        if matches!(line.origin(), '+' | '-' | ' ') {
+            chunk.push(line.origin());
+        }
+        chunk.push_str(&String::from_utf8_lossy(line.content()));
+        if text.len() + chunk.len() > max_bytes {
+            truncated = true;
+            return false;
+        }
+        text.push_str(&chunk);
+        true
+    })
+    // Stopping the walk early is reported as an error; only a real one counts.
+    .or_else(|e| if truncated { Ok(()) } else { Err(e) })
+    .map_err(git_error)?;
+
+    if stats.files_changed() == 0 {
+        let what = if staged { "staged" } else { "unstaged" };
+        text = format!("No {} changes", what);
+    } else if truncated {
+        text.push_str(&format!(
+            "\n... (diff truncated at {} bytes; pass a path to see the rest)",
+            max_bytes
+        ));
+    }
+    Ok((
+        text.trim_end().to_string(),
+        serde_json::json!({
+            "files_changed": stats.files_changed(),
+            "insertions": stats.insertions(),
+            "deletions": stats.deletions(),
+            "truncated": truncated,
+        }),
+    ))
+}
+
+/// One line per commit, newest first: `abc1234 Summary (Author)`.
+fn log(repo: &Repository, limit: usize) -> GitOutput {
+    if repo.head().is_err() {
+        return Ok(("No commits yet".into(), serde_json::json!({ "commits": 0 })));
+    }
+    let mut walk = repo.revwalk().map_err(git_error)?;
+    walk.push_head().map_err(git_error)?;
+    walk.set_sorting(Sort::TIME).map_err(git_error)?;
+
+    let mut lines = Vec::new();
+    for oid in walk.take(limit) {
+        let commit = repo
+            .find_commit(oid.map_err(git_error)?)
+            .map_err(git_error)?;
+        lines.push(format!(
+            "{} {} ({})",
+            short_id(commit.id()),
+            commit.summary().unwrap_or(""),
+            commit.author().name().unwrap_or("unknown")
+        ));
+    }
+    Ok((
+        lines.join("\n"),
+        serde_json::json!({ "commits": lines.len() }),
+    ))
+}
+
+/// Stage new, modified and deleted files matching `pathspec`.
+fn stage(repo: &Repository, pathspec: &str) -> Result<(), ToolError> {
+    let mut index = repo.index().map_err(git_error)?;
+    index
+        .add_all([pathspec], IndexAddOption::DEFAULT, None)
+        .map_err(git_error)?;
+    index.update_all([pathspec], None).map_err(git_error)?;
+    index.write().map_err(git_error)
+}
+
+fn add(repo: &Repository, pathspec: &str) -> GitOutput {
+    stage(repo, pathspec)?;
+    let (text, details) = status(repo)?;
+    Ok((format!("Staged {}\n{}", pathspec, text), details))
+}
+
+fn commit(repo: &Repository, message: &str, add_all: bool) -> GitOutput {
+    if add_all {
+        stage(repo, ".")?;
+    }
+    let mut index = repo.index().map_err(git_error)?;
+    let tree = repo
+        .find_tree(index.write_tree().map_err(git_error)?)
+        .map_err(git_error)?;
+    let parent = repo.head().ok().and_then(|h| h.peel_to_commit().ok());
+    if parent.as_ref().is_some_and(|p| p.tree_id() == tree.id()) {
+        return Err(ToolError::Failed(
+            "nothing to commit: no staged changes. Stage files with add, or pass add_all=true."
+                .into(),
+        ));
+    }
+
+    let signature = repo.signature().map_err(git_error)?;
+    let parents: Vec<&git2::Commit> = parent.iter().collect();
+    let oid = repo
+        .commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parents,
+        )
+        .map_err(git_error)?;
+
+    let diff = repo
+        .diff_tree_to_tree(
+            parent
+                .map(|p| p.tree())
+                .transpose()
+                .map_err(git_error)?
+                .as_ref(),
+            Some(&tree),
+            None,
+        )
+        .map_err(git_error)?;
+    let stats = diff.stats().map_err(git_error)?;
+    let summary = message.lines().next().unwrap_or("");
+    Ok((
+        format!(
+            "[{} {}] {}\n{} file{} changed, {} insertion{}(+), {} deletion{}(-)",
+            head_name(repo),
+            short_id(oid),
+            summary,
+            stats.files_changed(),
+            plural(stats.files_changed()),
+            stats.insertions(),
+            plural(stats.insertions()),
+            stats.deletions(),
+            plural(stats.deletions()),
+        ),
+        serde_json::json!({
+            "commit": oid.to_string(),
+            "files_changed": stats.files_changed(),
+        }),
+    ))
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// List local branches, or create `name` at HEAD.
+fn branch(repo: &Repository, name: Option<&str>) -> GitOutput {
+    if let Some(name) = name {
+        let head = repo
+            .head()
+            .and_then(|h| h.peel_to_commit())
+            .map_err(git_error)?;
+        repo.branch(name, &head, false).map_err(git_error)?;
+        return Ok((
+            format!("Created branch {} at {}", name, short_id(head.id())),
+            serde_json::json!({ "created": name }),
+        ));
+    }
+
+    let mut names = Vec::new();
+    let mut lines = Vec::new();
+    for entry in repo.branches(Some(BranchType::Local)).map_err(git_error)? {
+        let (branch, _) = entry.map_err(git_error)?;
+        let name = branch.name().map_err(git_error)?.unwrap_or("?").to_string();
+        let marker = if branch.is_head() { '*' } else { ' ' };
+        lines.push(format!("{} {}", marker, name));
+        names.push(name);
+    }
+    if lines.is_empty() {
+        lines.push(format!("No branches yet (on {})", head_name(repo)));
+    }
+    Ok((
+        lines.join("\n"),
+        serde_json::json!({ "branches": names, "current": head_name(repo) }),
+    ))
+}
+
+/// Switch to an existing local branch. Refuses if local changes would be
+/// overwritten.
+fn checkout(repo: &Repository, name: &str) -> GitOutput {
+    let branch = repo
+        .find_branch(name, BranchType::Local)
+        .map_err(git_error)?;
+    let refname = branch
+        .get()
+        .name()
+        .ok_or_else(|| ToolError::Failed(format!("branch '{}' has a non-UTF-8 name", name)))?
+        .to_string();
+    let target = branch.get().peel_to_commit().map_err(git_error)?;
+    repo.checkout_tree(
+        target.as_object(),
+        Some(git2::build::CheckoutBuilder::new().safe()),
+    )
+    .map_err(git_error)?;
+    repo.set_head(&refname).map_err(git_error)?;
+    Ok((
+        format!("Switched to branch '{}'", name),
+        serde_json::json!({ "branch": name }),
+    ))
+}
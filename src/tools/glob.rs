@@ -0,0 +1,241 @@
+//! Glob tool — find files by path pattern (`src/**/*.rs`).
+
+use crate::types::*;
+use async_trait::async_trait;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// Find files whose path matches a glob pattern, newest first.
+///
+/// Supports `*`, `?`, `[abc]`, `**` (any number of directories) and `{a,b}`
+/// alternation. `*` does not cross `/`. Relative patterns match paths
+/// relative to the search directory; absolute patterns walk from their
+/// longest literal prefix. `.gitignore`/`.ignore` files are honoured unless
+/// `respect_gitignore` is off; `.git` itself is never searched.
+#[derive(Clone)]
+pub struct GlobTool {
+    /// Directory relative patterns are resolved against when the call gives
+    /// no `cwd` (default: the process working directory)
+    pub root: Option<String>,
+    /// Max paths to return
+    pub max_results: usize,
+    /// Skip files excluded by `.gitignore`, `.ignore` and git's exclude files
+    pub respect_gitignore: bool,
+}
+
+impl Default for GlobTool {
+    fn default() -> Self {
+        Self {
+            root: None,
+            max_results: 100,
+            respect_gitignore: true,
+        }
+    }
+}
+
+impl GlobTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_root(mut self, root: impl Into<String>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+}
+
+#[async_trait]
+impl AgentTool for GlobTool {
+    fn name(&self) -> &str {
+        "glob"
+    }
+
+    fn label(&self) -> &str {
+        "Find Files"
+    }
+
+    fn description(&self) -> &str {
+        "Find files by glob pattern, e.g. 'src/**/*.rs' or '**/*.{ts,tsx}'. Returns matching paths, most recently modified first. Skips files ignored by .gitignore."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "pattern": {
+                    "type": "string",
+                    "description": "Glob pattern. '**' matches any number of directories, '{a,b}' either alternative"
+                },
+                "cwd": {
+                    "type": "string",
+                    "description": "Directory to search from (optional, defaults to working directory)"
+                }
+            },
+            "required": ["pattern"]
+        })
+    }
+
+    fn cacheable(&self) -> bool {
+        true
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let cancel = ctx.cancel;
+        let pattern = params["pattern"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'pattern' parameter".into()))?;
+        // Matched paths carry no `./`, so drop it from the pattern too.
+        let pattern = pattern.strip_prefix("./").unwrap_or(pattern).to_string();
+        let cwd = params["cwd"]
+            .as_str()
+            .map(|s| s.to_string())
+            .or_else(|| self.root.clone())
+            .unwrap_or_else(|| ".".into());
+
+        let matcher = globset::GlobBuilder::new(&pattern)
+            .literal_separator(true)
+            .build()
+            .map_err(|e| ToolError::InvalidArgs(format!("invalid glob pattern: {}", e)))?
+            .compile_matcher();
+
+        // Absolute patterns are matched against absolute paths, walking from
+        // the directory before the first wildcard.
+        let absolute = Path::new(&pattern).is_absolute();
+        let walk_root = if absolute {
+            literal_prefix(&pattern)
+        } else {
+            PathBuf::from(&cwd)
+        };
+        if absolute && !walk_root.exists() {
+            return Ok(no_matches(&pattern, &walk_root));
+        }
+        if !absolute && !walk_root.is_dir() {
+            return Err(ToolError::Failed(format!(
+                "Directory not found: {}. Check the path and try again.",
+                cwd
+            )));
+        }
+
+        if cancel.is_cancelled() {
+            return Err(ToolError::Cancelled);
+        }
+
+        let respect_gitignore = self.respect_gitignore;
+        let walk = {
+            let walk_root = walk_root.clone();
+            let cancel = cancel.clone();
+            tokio::task::spawn_blocking(move || {
+                find_matches(&walk_root, &matcher, absolute, respect_gitignore, &cancel)
+            })
+        };
+        let mut matches = tokio::select! {
+            _ = cancel.cancelled() => return Err(ToolError::Cancelled),
+            result = walk => result.map_err(|e| ToolError::Failed(format!("Glob failed: {}", e)))?,
+        };
+
+        if matches.is_empty() {
+            return Ok(no_matches(&pattern, &walk_root));
+        }
+
+        // Newest first; ties (and unreadable mtimes) by path.
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let total = matches.len();
+        let truncated = total > self.max_results;
+        matches.truncate(self.max_results);
+
+        let paths: Vec<String> = matches
+            .iter()
+            .map(|(path, _)| path.display().to_string())
+            .collect();
+        let text = if truncated {
+            format!(
+                "{}\n\n... ({} files, showing the {} most recently modified; narrow the pattern to see the rest)",
+                paths.join("\n"),
+                total,
+                self.max_results
+            )
+        } else {
+            format!("{}\n\n({} files)", paths.join("\n"), total)
+        };
+
+        Ok(ToolResult {
+            content: vec![Content::Text { text }],
+            details: serde_json::json!({ "total": total, "truncated": truncated }),
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+fn no_matches(pattern: &str, dir: &Path) -> ToolResult {
+    ToolResult {
+        content: vec![Content::Text {
+            text: format!("No files match '{}' in {}", pattern, dir.display()),
+        }],
+        details: serde_json::json!({ "total": 0, "truncated": false }),
+    }
+}
+
+/// The leading components of `pattern` that contain no glob syntax.
+fn literal_prefix(pattern: &str) -> PathBuf {
+    Path::new(pattern)
+        .components()
+        .take_while(|c| match c {
+            Component::Normal(part) => !part
+                .to_string_lossy()
+                .contains(['*', '?', '[', ']', '{', '}']),
+            _ => true,
+        })
+        .collect()
+}
+
+/// Walk `root` and return matching files with their modification times.
+/// Paths are absolute when `absolute` is set, otherwise relative to `root`.
+fn find_matches(
+    root: &Path,
+    matcher: &globset::GlobMatcher,
+    absolute: bool,
+    respect_gitignore: bool,
+    cancel: &tokio_util::sync::CancellationToken,
+) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let walker = ignore::WalkBuilder::new(root)
+        .hidden(false)
+        .ignore(respect_gitignore)
+        .git_ignore(respect_gitignore)
+        .git_global(respect_gitignore)
+        .git_exclude(respect_gitignore)
+        .parents(respect_gitignore)
+        // Honour .gitignore files even outside a git checkout.
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .build();
+
+    let mut matches = Vec::new();
+    for entry in walker.flatten() {
+        if cancel.is_cancelled() {
+            break;
+        }
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let path = if absolute {
+            entry.path().to_path_buf()
+        } else {
+            match entry.path().strip_prefix(root) {
+                Ok(relative) => relative.to_path_buf(),
+                Err(_) => continue,
+            }
+        };
+        if matcher.is_match(&path) {
+            let modified = entry.metadata().ok().and_then(|m| m.modified().ok());
+            matches.push((path, modified));
+        }
+    }
+    matches
+}
@@ -0,0 +1,252 @@
+//! HTTP tool — call REST APIs, webhooks, and health endpoints.
+
+use crate::types::*;
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Send an HTTP request and return the status line, headers, and body.
+///
+/// Not part of [`default_tools`](super::default_tools) — network access is
+/// opt-in.
+#[derive(Clone)]
+pub struct HttpTool {
+    /// Shared client, so repeated calls reuse connections
+    client: Arc<reqwest::Client>,
+    /// Request timeout when the call doesn't pass `timeout_ms`
+    pub timeout: Duration,
+    /// Max response body bytes to return (the rest is dropped with a note)
+    pub max_response_bytes: usize,
+    /// Return `ToolError::Failed` for non-2xx responses instead of
+    /// reporting the status to the model as a normal result
+    pub fail_on_error: bool,
+}
+
+impl Default for HttpTool {
+    fn default() -> Self {
+        Self {
+            client: Arc::new(reqwest::Client::new()),
+            timeout: Duration::from_secs(30),
+            max_response_bytes: 64 * 1024, // 64KB
+            fail_on_error: false,
+        }
+    }
+}
+
+impl HttpTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use an existing client (proxies, TLS settings, or sharing a pool
+    /// with the rest of the application).
+    pub fn with_client(mut self, client: Arc<reqwest::Client>) -> Self {
+        self.client = client;
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn with_max_response_bytes(mut self, max: usize) -> Self {
+        self.max_response_bytes = max;
+        self
+    }
+
+    pub fn with_fail_on_error(mut self, fail_on_error: bool) -> Self {
+        self.fail_on_error = fail_on_error;
+        self
+    }
+}
+
+#[async_trait]
+impl AgentTool for HttpTool {
+    fn name(&self) -> &str {
+        "http_request"
+    }
+
+    fn label(&self) -> &str {
+        "HTTP Request"
+    }
+
+    fn description(&self) -> &str {
+        "Send an HTTP request and return the response status, headers, and body. Use for REST APIs, webhooks, and health checks."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "url": {
+                    "type": "string",
+                    "description": "Absolute URL to request"
+                },
+                "method": {
+                    "type": "string",
+                    "enum": ["GET", "POST", "PUT", "DELETE", "PATCH"],
+                    "description": "HTTP method (default: GET)"
+                },
+                "headers": {
+                    "type": "object",
+                    "additionalProperties": { "type": "string" },
+                    "description": "Request headers (optional)"
+                },
+                "body": {
+                    "type": ["string", "object"],
+                    "description": "Request body. Objects are sent as JSON; strings are sent as-is (optional)"
+                },
+                "timeout_ms": {
+                    "type": "integer",
+                    "description": "Request timeout in milliseconds (optional)"
+                }
+            },
+            "required": ["url"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let cancel = ctx.cancel;
+        let url = params["url"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'url' parameter".into()))?;
+        let timeout = params["timeout_ms"]
+            .as_u64()
+            .map(Duration::from_millis)
+            .unwrap_or(self.timeout);
+
+        let req = build_request(&self.client, url, &params, timeout)?;
+        let mut response = tokio::select! {
+            _ = cancel.cancelled() => return Err(ToolError::Cancelled),
+            result = req.send() => result.map_err(|e| request_error(e, timeout))?,
+        };
+
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let mut text = format!("{:?} {}\n", response.version(), status);
+        for (name, value) in response.headers() {
+            text.push_str(&format!(
+                "{}: {}\n",
+                name,
+                String::from_utf8_lossy(value.as_bytes())
+            ));
+        }
+
+        let (body, truncated) =
+            read_body(&mut response, self.max_response_bytes, timeout, &cancel).await?;
+
+        text.push('\n');
+        text.push_str(&String::from_utf8_lossy(&body));
+        if truncated {
+            text.push_str(&format!(
+                "\n\n... [truncated: response exceeded {} bytes]",
+                self.max_response_bytes
+            ));
+        }
+
+        if self.fail_on_error && !status.is_success() {
+            return Err(ToolError::Failed(text));
+        }
+
+        Ok(ToolResult {
+            content: vec![Content::Text { text }],
+            details: serde_json::json!({
+                "status": status.as_u16(),
+                "content_type": content_type,
+                "truncated": truncated,
+            }),
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+/// A request from the `method`, `headers` and `body` parameters, which
+/// [`HttpTool`] and [`FetchTool`](super::FetchTool) share.
+pub(super) fn build_request(
+    client: &reqwest::Client,
+    url: &str,
+    params: &serde_json::Value,
+    timeout: Duration,
+) -> Result<reqwest::RequestBuilder, ToolError> {
+    let method = match params["method"].as_str().unwrap_or("GET") {
+        "GET" => reqwest::Method::GET,
+        "POST" => reqwest::Method::POST,
+        "PUT" => reqwest::Method::PUT,
+        "DELETE" => reqwest::Method::DELETE,
+        "PATCH" => reqwest::Method::PATCH,
+        other => {
+            return Err(ToolError::InvalidArgs(format!(
+                "unsupported method '{}': use GET, POST, PUT, DELETE, or PATCH",
+                other
+            )))
+        }
+    };
+
+    let mut req = client.request(method, url).timeout(timeout);
+    if let Some(headers) = params["headers"].as_object() {
+        for (name, value) in headers {
+            let value = match value {
+                serde_json::Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            req = req.header(name, value);
+        }
+    }
+    match &params["body"] {
+        serde_json::Value::Null => {}
+        serde_json::Value::String(s) => req = req.body(s.clone()),
+        other => req = req.json(other),
+    }
+    Ok(req)
+}
+
+pub(super) fn request_error(e: reqwest::Error, timeout: Duration) -> ToolError {
+    if e.is_timeout() {
+        return ToolError::Timeout(timeout);
+    }
+    // reqwest's message alone is often just "error sending request".
+    let mut message = format!("HTTP request failed: {}", e);
+    let mut source = std::error::Error::source(&e);
+    while let Some(cause) = source {
+        message.push_str(&format!(": {}", cause));
+        source = cause.source();
+    }
+    ToolError::Failed(message)
+}
+
+/// Up to `max` bytes of the body, and whether more was left. Reads chunk by
+/// chunk so an oversized body is never fully buffered.
+pub(super) async fn read_body(
+    response: &mut reqwest::Response,
+    max: usize,
+    timeout: Duration,
+    cancel: &tokio_util::sync::CancellationToken,
+) -> Result<(Vec<u8>, bool), ToolError> {
+    let mut body = Vec::new();
+    loop {
+        let chunk = tokio::select! {
+            _ = cancel.cancelled() => return Err(ToolError::Cancelled),
+            chunk = response.chunk() => chunk.map_err(|e| request_error(e, timeout))?,
+        };
+        let Some(chunk) = chunk else { break };
+        let room = max - body.len();
+        if chunk.len() > room {
+            body.extend_from_slice(&chunk[..room]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
+}
@@ -6,6 +6,7 @@ use std::time::Duration;
 use tokio::process::Command;
 
 /// List files and directories. Uses `find` or `fd` for efficient traversal.
+#[derive(Clone)]
 pub struct ListFilesTool {
     pub max_results: usize,
     pub timeout: Duration,
@@ -60,6 +61,10 @@ impl AgentTool for ListFilesTool {
         })
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     async fn execute(
         &self,
         params: serde_json::Value,
@@ -137,4 +142,8 @@ impl AgentTool for ListFilesTool {
             details: serde_json::json!({ "total": total, "truncated": truncated }),
         })
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
 }
@@ -1,16 +1,37 @@
+pub mod audit;
 pub mod bash;
+pub mod cache;
 pub mod edit;
+pub mod fetch;
 pub mod file;
+#[cfg(feature = "git")]
+pub mod git;
+pub mod glob;
+pub mod http;
 pub mod list;
+pub mod patch;
+/// OpenAPI-generated tools, also reachable here next to the built-ins.
+#[cfg(feature = "openapi")]
+pub use crate::openapi;
 pub mod search;
 pub mod shared_state_tool;
+pub mod web_search;
 
+pub use audit::{AuditEntry, AuditLog};
 pub use bash::BashTool;
-pub use edit::EditFileTool;
+pub use cache::{InMemoryToolCache, ToolCache};
+pub use edit::{EditFileTool, MultiEditTool};
+pub use fetch::FetchTool;
 pub use file::{ReadFileTool, WriteFileTool};
+#[cfg(feature = "git")]
+pub use git::GitTool;
+pub use glob::GlobTool;
+pub use http::HttpTool;
 pub use list::ListFilesTool;
+pub use patch::ApplyPatchTool;
 pub use search::SearchTool;
 pub use shared_state_tool::SharedStateTool;
+pub use web_search::{BraveSearch, SearchBackend, WebSearchResult, WebSearchTool};
 
 use crate::types::AgentTool;
 
@@ -22,6 +43,7 @@ pub fn default_tools() -> Vec<Box<dyn AgentTool>> {
         Box::new(WriteFileTool::new()),
         Box::new(EditFileTool::new()),
         Box::new(ListFilesTool::default()),
+        Box::new(GlobTool::default()),
         Box::new(SearchTool::default()),
     ]
 }
@@ -0,0 +1,697 @@
+//! Patch tool — apply a unified diff to one or more files.
+//!
+//! For large changes models are often better at writing a diff than many
+//! search/replace pairs. Hunks are located by their context and removed
+//! lines, not trusted line numbers, so a patch made against a slightly
+//! different version of the file still applies. Every file is patched in
+//! memory first; a hunk that can't be placed fails the whole call and
+//! leaves all files untouched. Writes are staged in temp files and renamed
+//! into place, so a failed write doesn't leave the patch half-applied.
+
+use crate::types::*;
+use async_trait::async_trait;
+
+/// Lines of file content shown around a hunk that didn't match.
+const CONFLICT_CONTEXT: usize = 3;
+
+/// Apply a unified diff (`@@ -a,b +c,d @@` hunks) to one or more files.
+///
+/// Each hunk is matched at its stated line first, then at the nearest
+/// position within `max_drift` lines, first exactly and then ignoring
+/// trailing whitespace. A `--- /dev/null` header creates the file; a
+/// `+++ /dev/null` header deletes it once every hunk has applied.
+///
+/// With a `path` argument the diff must cover one file and its header names
+/// are ignored. Without one, each `---`/`+++` section patches the file its
+/// headers name (git's `a/` and `b/` prefixes are dropped), resolved against
+/// `base_path` when given. `dry_run` checks that everything applies and
+/// reports the result without writing.
+#[derive(Clone)]
+pub struct ApplyPatchTool {
+    /// How far (in lines) a hunk may be found from where its header says
+    pub max_drift: usize,
+}
+
+impl Default for ApplyPatchTool {
+    fn default() -> Self {
+        Self { max_drift: 200 }
+    }
+}
+
+impl ApplyPatchTool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// One file's patch, applied in memory and waiting to be written.
+struct Planned<'a> {
+    path: String,
+    patch: &'a Patch,
+    /// The content before the patch, to roll back to.
+    original: String,
+    applied: Applied,
+}
+
+#[async_trait]
+impl AgentTool for ApplyPatchTool {
+    fn name(&self) -> &str {
+        "apply_patch"
+    }
+
+    fn label(&self) -> &str {
+        "Apply Patch"
+    }
+
+    fn description(&self) -> &str {
+        "Apply a unified diff to one or more files. Include a few unchanged context lines around each change; line numbers in @@ headers may be approximate. Without 'path', the ---/+++ headers name the files (a/ and b/ prefixes are dropped). Use '--- /dev/null' to create a file and '+++ /dev/null' to delete one. If any hunk doesn't match, nothing is written and the current file content around it is returned. Set dry_run to check a patch without writing."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "patch": {
+                    "type": "string",
+                    "description": "Unified diff: ---/+++ headers then @@ hunks, for one or more files"
+                },
+                "path": {
+                    "type": "string",
+                    "description": "File to patch when the diff covers a single file; overrides the header names (headers are then optional)"
+                },
+                "base_path": {
+                    "type": "string",
+                    "description": "Directory that relative paths are resolved against"
+                },
+                "dry_run": {
+                    "type": "boolean",
+                    "description": "Check that the patch applies and report the result without writing (default: false)"
+                }
+            },
+            "required": ["patch"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let cancel = ctx.cancel;
+        let patch = params["patch"]
+            .as_str()
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'patch' parameter".into()))?;
+        let base_path = params["base_path"].as_str();
+        let dry_run = params["dry_run"].as_bool().unwrap_or(false);
+        let patches = parse_patch(patch).map_err(ToolError::InvalidArgs)?;
+
+        let targets: Vec<String> = match params["path"].as_str() {
+            Some(_) if patches.len() > 1 => {
+                return Err(ToolError::InvalidArgs(format!(
+                    "the patch touches {} files but 'path' names one; omit 'path' to use the file names in the headers",
+                    patches.len()
+                )))
+            }
+            Some(path) => vec![path.to_string()],
+            None => patches
+                .iter()
+                .map(|p| {
+                    p.path.clone().ok_or_else(|| {
+                        ToolError::InvalidArgs(
+                            "hunks without ---/+++ headers need a 'path' parameter".into(),
+                        )
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+        };
+
+        let mut planned: Vec<Planned> = Vec::new();
+        for (patch, target) in patches.iter().zip(targets) {
+            if cancel.is_cancelled() {
+                return Err(ToolError::Cancelled);
+            }
+            let path = resolve(base_path, target);
+            if planned.iter().any(|p| p.path == path) {
+                return Err(ToolError::InvalidArgs(format!(
+                    "the patch touches {} more than once; merge its hunks into one section",
+                    path
+                )));
+            }
+
+            let original = if patch.creates {
+                if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+                    return Err(ToolError::Failed(format!(
+                        "{} already exists but the patch creates it (--- /dev/null). Use a patch against the current content instead.",
+                        path
+                    )));
+                }
+                String::new()
+            } else {
+                tokio::fs::read_to_string(&path)
+                    .await
+                    .map_err(|e| ToolError::Failed(format!("Cannot read {}: {}", path, e)))?
+            };
+
+            let applied = apply_hunks(&original, patch, self.max_drift)
+                .map_err(|conflict| ToolError::Failed(conflict.describe(&path)))?;
+            if patch.deletes && !applied.content.is_empty() {
+                return Err(ToolError::Failed(format!(
+                    "The patch deletes {} (+++ /dev/null) but its hunks leave content behind. No changes were written.",
+                    path
+                )));
+            }
+            planned.push(Planned {
+                path,
+                patch,
+                original,
+                applied,
+            });
+        }
+
+        if !dry_run {
+            write_all(&planned).await?;
+        }
+
+        let mut summary = Vec::new();
+        let mut files = Vec::new();
+        for Planned {
+            path,
+            patch,
+            applied,
+            ..
+        } in &planned
+        {
+            let hunks = patch.hunks.len();
+            if patch.deletes {
+                summary.push(format!(
+                    "{} {}",
+                    if dry_run { "Would delete" } else { "Deleted" },
+                    path
+                ));
+            } else {
+                summary.push(format!(
+                    "{} {} hunk{} to {}",
+                    if dry_run { "Would apply" } else { "Applied" },
+                    hunks,
+                    if hunks == 1 { "" } else { "s" },
+                    path
+                ));
+                for (i, offset) in applied.offsets.iter().enumerate() {
+                    if *offset != 0 {
+                        summary.push(format!("Hunk {} applied at offset {:+}", i + 1, offset));
+                    }
+                }
+            }
+            let mut file = serde_json::json!({
+                "path": path,
+                "hunks": hunks,
+                "offsets": applied.offsets,
+                "created": patch.creates,
+                "deleted": patch.deletes,
+            });
+            if dry_run && !patch.deletes {
+                file["content"] = serde_json::json!(applied.content);
+            }
+            files.push(file);
+        }
+
+        Ok(ToolResult {
+            content: vec![Content::Text {
+                text: summary.join("\n"),
+            }],
+            details: serde_json::json!({ "files": files, "dry_run": dry_run }),
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+/// Write every planned file, or none of them.
+///
+/// New contents are staged in temp files next to their targets first, so a
+/// failure there (disk full, permissions) leaves every file untouched. The
+/// temp files are then renamed into place and deletions done; if one of
+/// those steps fails, the files already changed are restored.
+async fn write_all(planned: &[Planned<'_>]) -> Result<(), ToolError> {
+    let mut staged: Vec<Option<String>> = Vec::new();
+    for plan in planned {
+        match stage(plan).await {
+            Ok(temp) => staged.push(temp),
+            Err(e) => {
+                for temp in staged.iter().flatten() {
+                    let _ = tokio::fs::remove_file(temp).await;
+                }
+                return Err(e);
+            }
+        }
+    }
+
+    for (done, (plan, temp)) in planned.iter().zip(&staged).enumerate() {
+        let result = match temp {
+            Some(temp) => tokio::fs::rename(temp, &plan.path)
+                .await
+                .map_err(|e| format!("Cannot write {}: {}", plan.path, e)),
+            None => tokio::fs::remove_file(&plan.path)
+                .await
+                .map_err(|e| format!("Cannot delete {}: {}", plan.path, e)),
+        };
+        if let Err(e) = result {
+            for temp in staged[done..].iter().flatten() {
+                let _ = tokio::fs::remove_file(temp).await;
+            }
+            for plan in &planned[..done] {
+                let _ = if plan.patch.creates {
+                    tokio::fs::remove_file(&plan.path).await
+                } else {
+                    tokio::fs::write(&plan.path, &plan.original).await
+                };
+            }
+            return Err(ToolError::Failed(format!(
+                "{}. Files already patched were restored.",
+                e
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Write `plan`'s new content to a temp file beside its target and return
+/// the temp path; `None` for a deletion, which has nothing to stage.
+async fn stage(plan: &Planned<'_>) -> Result<Option<String>, ToolError> {
+    if plan.patch.deletes {
+        return Ok(None);
+    }
+    let path = std::path::Path::new(&plan.path);
+    if let Some(parent) = path.parent() {
+        if plan.patch.creates && !parent.as_os_str().is_empty() {
+            tokio::fs::create_dir_all(parent).await.map_err(|e| {
+                ToolError::Failed(format!("Cannot create {}: {}", parent.display(), e))
+            })?;
+        }
+    }
+    let name = path
+        .file_name()
+        .map_or_else(|| "patch".into(), |n| n.to_string_lossy());
+    let temp = path
+        .with_file_name(format!(".{}.apply_patch.tmp", name))
+        .to_string_lossy()
+        .into_owned();
+    let mut written = tokio::fs::write(&temp, &plan.applied.content).await;
+    if written.is_ok() && !plan.patch.creates {
+        // Keep the file's mode (e.g. executable scripts) across the rename.
+        if let Ok(meta) = tokio::fs::metadata(path).await {
+            written = tokio::fs::set_permissions(&temp, meta.permissions()).await;
+        }
+    }
+    if let Err(e) = written {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(ToolError::Failed(format!(
+            "Cannot write {}: {}. No changes were written.",
+            plan.path, e
+        )));
+    }
+    Ok(Some(temp))
+}
+
+/// `path` joined to `base_path` unless it is already absolute.
+fn resolve(base_path: Option<&str>, path: String) -> String {
+    match base_path {
+        Some(base) if std::path::Path::new(&path).is_relative() => std::path::Path::new(base)
+            .join(path)
+            .to_string_lossy()
+            .into_owned(),
+        _ => path,
+    }
+}
+
+/// A parsed unified diff for one file.
+#[derive(Debug, Default)]
+struct Patch {
+    /// The file named by the headers (`+++`, or `---` for a deletion), if
+    /// there were any.
+    path: Option<String>,
+    hunks: Vec<Hunk>,
+    creates: bool,
+    deletes: bool,
+    /// `\ No newline at end of file` after a line of the new version.
+    new_missing_newline: bool,
+    /// `\ No newline at end of file` after a removed line.
+    old_missing_newline: bool,
+}
+
+#[derive(Debug)]
+struct Hunk {
+    header: String,
+    /// 1-based start line in the old file, from the header.
+    old_start: usize,
+    lines: Vec<HunkLine>,
+}
+
+#[derive(Debug)]
+enum HunkLine {
+    Context(String),
+    Remove(String),
+    Add(String),
+}
+
+impl Hunk {
+    /// Lines the hunk expects to find (context and removed).
+    fn old_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Remove(s) => Some(s.as_str()),
+                HunkLine::Add(_) => None,
+            })
+            .collect()
+    }
+
+    /// Lines the hunk leaves in their place (context and added).
+    fn new_lines(&self) -> Vec<&str> {
+        self.lines
+            .iter()
+            .filter_map(|l| match l {
+                HunkLine::Context(s) | HunkLine::Add(s) => Some(s.as_str()),
+                HunkLine::Remove(_) => None,
+            })
+            .collect()
+    }
+}
+
+/// Split a diff into per-file patches at each `---`/`+++` header pair.
+fn parse_patch(text: &str) -> Result<Vec<Patch>, String> {
+    let mut patches = Vec::new();
+    let mut patch = Patch::default();
+    let mut seen_header = false;
+    // Between files: `diff --git`, `index`, mode lines until the next header.
+    let mut in_preamble = false;
+    // Old and new lines the current hunk's header says are still to come.
+    // Until they are used up, `---`/`+++` lines are hunk body, not headers.
+    let mut remaining = (0, 0);
+    let lines: Vec<&str> = text.lines().collect();
+    let mut i = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        // A file header is a `---` line directly followed by `+++`, outside
+        // a hunk; anything else starting with `---` is a removed `--` line.
+        if remaining == (0, 0)
+            && line.starts_with("--- ")
+            && lines.get(i + 1).is_some_and(|l| l.starts_with("+++ "))
+        {
+            if seen_header || !patch.hunks.is_empty() {
+                patches.push(std::mem::take(&mut patch));
+            }
+            seen_header = true;
+            in_preamble = false;
+            let (old, new) = (header_path(line), header_path(lines[i + 1]));
+            patch.creates = old == "/dev/null";
+            patch.deletes = new == "/dev/null";
+            let named = if patch.deletes { old } else { new };
+            if named != "/dev/null" {
+                patch.path = Some(strip_git_prefix(named).to_string());
+            }
+            i += 2;
+            continue;
+        }
+        if line.starts_with("@@") {
+            in_preamble = false;
+            remaining = parse_counts(line).unwrap_or((0, 0));
+            patch.hunks.push(Hunk {
+                header: line.to_string(),
+                old_start: parse_old_start(line)
+                    .ok_or_else(|| format!("malformed hunk header: {}", line))?,
+                lines: Vec::new(),
+            });
+            i += 1;
+            continue;
+        }
+        if line.starts_with("diff ") {
+            in_preamble = true;
+            remaining = (0, 0);
+        }
+        let Some(hunk) = patch.hunks.last_mut().filter(|_| !in_preamble) else {
+            // Preamble before the first hunk (`diff --git`, `index ...`).
+            i += 1;
+            continue;
+        };
+        let (old, new) = &mut remaining;
+        if let Some(rest) = line.strip_prefix('+') {
+            hunk.lines.push(HunkLine::Add(rest.to_string()));
+            *new = new.saturating_sub(1);
+        } else if let Some(rest) = line.strip_prefix('-') {
+            hunk.lines.push(HunkLine::Remove(rest.to_string()));
+            *old = old.saturating_sub(1);
+        } else if let Some(rest) = line.strip_prefix(' ') {
+            hunk.lines.push(HunkLine::Context(rest.to_string()));
+            (*old, *new) = (old.saturating_sub(1), new.saturating_sub(1));
+        } else if line.is_empty() {
+            // Editors and models often strip the space from blank context lines.
+            hunk.lines.push(HunkLine::Context(String::new()));
+            (*old, *new) = (old.saturating_sub(1), new.saturating_sub(1));
+        } else if line.starts_with('\\') {
+            match hunk.lines.last() {
+                Some(HunkLine::Remove(_)) => patch.old_missing_newline = true,
+                Some(_) => patch.new_missing_newline = true,
+                None => {}
+            }
+        } else {
+            return Err(format!(
+                "malformed line in hunk {}: {:?} (lines must start with ' ', '+' or '-')",
+                patch.hunks.len(),
+                line
+            ));
+        }
+        i += 1;
+    }
+    patches.push(patch);
+
+    for patch in &mut patches {
+        if patch.hunks.is_empty() {
+            return Err(match &patch.path {
+                Some(path) => format!("the patch has no @@ hunks for {}", path),
+                None => "the patch has no @@ hunks".into(),
+            });
+        }
+        // Trailing blank lines are usually an artifact of how the diff was
+        // quoted, not blank context.
+        for hunk in &mut patch.hunks {
+            while matches!(hunk.lines.last(), Some(HunkLine::Context(s)) if s.is_empty()) {
+                hunk.lines.pop();
+            }
+        }
+    }
+    Ok(patches)
+}
+
+fn header_path(line: &str) -> &str {
+    let path = line[4..].trim_end();
+    // Drop a trailing tab-separated timestamp.
+    path.split('\t').next().unwrap_or(path)
+}
+
+/// `a/src/lib.rs` → `src/lib.rs`, as `git apply -p1` would.
+fn strip_git_prefix(path: &str) -> &str {
+    path.strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path)
+}
+
+/// `@@ -12,5 +12,7 @@` → 12.
+fn parse_old_start(header: &str) -> Option<usize> {
+    let old = header.strip_prefix("@@")?.trim_start().strip_prefix('-')?;
+    let start = old.split([',', ' ']).next()?;
+    start.parse().ok()
+}
+
+/// `@@ -12,5 +12,7 @@` → (5, 7). An omitted count is 1.
+fn parse_counts(header: &str) -> Option<(usize, usize)> {
+    let mut ranges = header.strip_prefix("@@")?.split_whitespace();
+    let count = |range: Option<&str>, sign: char| -> Option<usize> {
+        let range = range?.strip_prefix(sign)?;
+        match range.split_once(',') {
+            Some((_, count)) => count.parse().ok(),
+            None => Some(1),
+        }
+    };
+    Some((count(ranges.next(), '-')?, count(ranges.next(), '+')?))
+}
+
+struct Applied {
+    content: String,
+    /// Per hunk: actual position minus the header's, in lines.
+    offsets: Vec<i64>,
+}
+
+/// A hunk that couldn't be placed.
+struct Conflict<'a> {
+    number: usize,
+    hunk: &'a Hunk,
+    /// The file lines around where the hunk was expected.
+    nearby: Vec<(usize, String)>,
+}
+
+impl Conflict<'_> {
+    fn describe(&self, path: &str) -> String {
+        let mut out = format!(
+            "Hunk {} ({}) does not match {}. No changes were written.\n\nThe hunk expects:\n",
+            self.number, self.hunk.header, path
+        );
+        for line in self.hunk.old_lines() {
+            out.push_str(&format!("  {}\n", line));
+        }
+        out.push_str("\nCurrent content near that location:\n");
+        for (number, line) in &self.nearby {
+            out.push_str(&format!("{:>4} | {}\n", number, line));
+        }
+        out.push_str("\nRe-read the file and regenerate the hunk against its current content.");
+        out
+    }
+}
+
+fn apply_hunks<'a>(
+    original: &str,
+    patch: &'a Patch,
+    max_drift: usize,
+) -> Result<Applied, Conflict<'a>> {
+    let mut lines: Vec<String> = original.lines().map(str::to_string).collect();
+    let mut offsets = Vec::new();
+    // Net lines added by earlier hunks, and where the next hunk may start.
+    let mut shift: i64 = 0;
+    let mut floor = 0;
+
+    for (index, hunk) in patch.hunks.iter().enumerate() {
+        let old = hunk.old_lines();
+        let new = hunk.new_lines();
+        // `-0,0` (insert into an empty file) and 1-based starts.
+        let stated = hunk.old_start.saturating_sub(1) as i64;
+        let expected = (stated + shift).clamp(floor as i64, lines.len() as i64) as usize;
+
+        let at = find_hunk(&lines, &old, expected, floor, max_drift).ok_or_else(|| {
+            let start = expected.saturating_sub(CONFLICT_CONTEXT);
+            let end = (expected + old.len() + CONFLICT_CONTEXT).min(lines.len());
+            Conflict {
+                number: index + 1,
+                hunk,
+                nearby: (start..end).map(|i| (i + 1, lines[i].clone())).collect(),
+            }
+        })?;
+
+        offsets.push(at as i64 - (stated + shift));
+        lines.splice(at..at + old.len(), new.iter().map(|s| s.to_string()));
+        shift += new.len() as i64 - old.len() as i64;
+        floor = at + new.len();
+    }
+
+    let mut content = lines.join("\n");
+    let trailing_newline = if patch.new_missing_newline {
+        false
+    } else if patch.old_missing_newline || original.is_empty() {
+        true
+    } else {
+        original.ends_with('\n')
+    };
+    if trailing_newline && !content.is_empty() {
+        content.push('\n');
+    }
+    Ok(Applied { content, offsets })
+}
+
+/// Where `old` occurs in `lines`, searching outward from `expected` no
+/// further than `max_drift` and never before `floor`. Exact matches win over
+/// matches that differ only in trailing whitespace.
+fn find_hunk(
+    lines: &[String],
+    old: &[&str],
+    expected: usize,
+    floor: usize,
+    max_drift: usize,
+) -> Option<usize> {
+    if old.is_empty() {
+        return Some(expected);
+    }
+    let last_start = lines.len().checked_sub(old.len())?;
+    let matches_at = |at: usize, loose: bool| {
+        at >= floor
+            && at <= last_start
+            && old.iter().zip(&lines[at..]).all(|(want, have)| {
+                if loose {
+                    want.trim_end() == have.trim_end()
+                } else {
+                    *want == have.as_str()
+                }
+            })
+    };
+    for loose in [false, true] {
+        for drift in 0..=max_drift {
+            let below = expected.checked_sub(drift);
+            let above = expected + drift;
+            if let Some(at) = below.filter(|at| matches_at(*at, loose)) {
+                return Some(at);
+            }
+            if drift > 0 && matches_at(above, loose) {
+                return Some(above);
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_headers_and_hunks() {
+        let patch =
+            parse_patch("diff --git a/x b/x\n--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n-a\n+b\n c\n\n")
+                .unwrap()
+                .remove(0);
+        assert_eq!(patch.path.as_deref(), Some("x"));
+        assert!(!patch.creates && !patch.deletes);
+        assert_eq!(patch.hunks.len(), 1);
+        assert_eq!(patch.hunks[0].old_start, 1);
+        assert_eq!(patch.hunks[0].old_lines(), vec!["a", "c"]);
+        assert_eq!(patch.hunks[0].new_lines(), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn removed_line_starting_with_dashes_is_not_a_header() {
+        let patch = parse_patch("@@ -1,2 +1,1 @@\n--- old rule\n keep\n")
+            .unwrap()
+            .remove(0);
+        assert_eq!(patch.hunks[0].old_lines(), vec!["-- old rule", "keep"]);
+    }
+
+    #[test]
+    fn header_lines_inside_a_hunk_are_body() {
+        let patches =
+            parse_patch("--- a/x\n+++ b/x\n@@ -1,2 +1,2 @@\n--- a/y\n+++ b/y\n keep\n").unwrap();
+        assert_eq!(patches.len(), 1);
+        assert_eq!(patches[0].hunks[0].old_lines(), vec!["-- a/y", "keep"]);
+        assert_eq!(patches[0].hunks[0].new_lines(), vec!["++ b/y", "keep"]);
+    }
+
+    #[test]
+    fn missing_newline_marker() {
+        let patch = parse_patch("@@ -1 +1 @@\n-a\n+b\n\\ No newline at end of file\n")
+            .unwrap()
+            .remove(0);
+        let applied = apply_hunks("a\n", &patch, 10).ok().unwrap();
+        assert_eq!(applied.content, "b");
+    }
+
+    #[test]
+    fn splits_multi_file_diffs() {
+        let patches = parse_patch(
+            "diff --git a/src/a.rs b/src/a.rs\nindex 1..2 100644\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -1 +1 @@\n-a\n+b\ndiff --git a/old.txt b/old.txt\ndeleted file mode 100644\n--- a/old.txt\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n",
+        )
+        .unwrap();
+        assert_eq!(patches.len(), 2);
+        assert_eq!(patches[0].path.as_deref(), Some("src/a.rs"));
+        assert_eq!(patches[0].hunks[0].new_lines(), vec!["b"]);
+        assert_eq!(patches[1].path.as_deref(), Some("old.txt"));
+        assert!(patches[1].deletes);
+    }
+}
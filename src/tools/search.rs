@@ -1,16 +1,30 @@
-//! Search tool — grep/ripgrep-style search across files.
+//! Search tool — ripgrep-style regex search across files.
 
 use crate::types::*;
 use async_trait::async_trait;
+use grep_matcher::Matcher;
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkMatch};
+use std::path::Path;
 use std::time::Duration;
-use tokio::process::Command;
 
-/// Search files using grep (or ripgrep if available).
+/// Search file contents with a regex, ripgrep style.
+///
+/// Runs ripgrep's own search engine in-process (the `grep-*` crates), so no
+/// `rg` binary is needed. Files excluded by `.gitignore`/`.ignore` are
+/// skipped, as are binary files and `.git`. Results are rendered like
+/// `rg --column`: `path:line:column:text` for matches, `path-line-text` for
+/// context lines, `--` between groups.
+#[derive(Clone)]
 pub struct SearchTool {
     /// Root directory to search in
     pub root: Option<String>,
-    /// Max results to return
+    /// Max matching lines to return (a call may ask for fewer)
     pub max_results: usize,
+    /// Max bytes of rendered output; longer results are cut with a note
+    pub max_output_bytes: usize,
+    /// Skip files excluded by `.gitignore`, `.ignore` and git's exclude files
+    pub respect_gitignore: bool,
     /// Timeout
     pub timeout: Duration,
 }
@@ -20,6 +34,8 @@ impl Default for SearchTool {
         Self {
             root: None,
             max_results: 50,
+            max_output_bytes: 30 * 1024,
+            respect_gitignore: true,
             timeout: Duration::from_secs(30),
         }
     }
@@ -36,6 +52,14 @@ impl SearchTool {
     }
 }
 
+/// What to search for and how to render it, resolved from the call.
+struct Query {
+    matcher: RegexMatcher,
+    context_lines: usize,
+    max_results: usize,
+    max_bytes: usize,
+}
+
 #[async_trait]
 impl AgentTool for SearchTool {
     fn name(&self) -> &str {
@@ -47,7 +71,7 @@ impl AgentTool for SearchTool {
     }
 
     fn description(&self) -> &str {
-        "Search for a pattern across files using grep. Returns matching lines with file paths and line numbers. Supports regex patterns."
+        "Search file contents with a regex (ripgrep syntax). Returns path:line:column:text for each matching line, optionally with surrounding context lines. Filter files by glob or type. Skips files ignored by .gitignore."
     }
 
     fn parameters_schema(&self) -> serde_json::Value {
@@ -56,25 +80,48 @@ impl AgentTool for SearchTool {
             "properties": {
                 "pattern": {
                     "type": "string",
-                    "description": "Search pattern (regex supported)"
+                    "description": "Regular expression to search for (Rust regex / ripgrep syntax)"
                 },
                 "path": {
                     "type": "string",
                     "description": "Directory or file to search in (optional, defaults to working directory)"
                 },
-                "include": {
+                "glob": {
                     "type": "string",
-                    "description": "File glob pattern to include, e.g. '*.rs' (optional)"
+                    "description": "Only search files matching this glob, e.g. '*.rs' or 'src/**/*.ts' (optional)"
                 },
-                "case_sensitive": {
+                "type": {
+                    "type": "string",
+                    "description": "Only search files of this ripgrep type, e.g. 'rust', 'py', 'js', 'md' (optional)"
+                },
+                "file_types": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Only search files with these extensions or ripgrep types, e.g. ['rs', 'toml'] (optional)"
+                },
+                "case_insensitive": {
                     "type": "boolean",
-                    "description": "Case sensitive search (default: false)"
+                    "description": "Ignore case (default: false)"
+                },
+                "context_lines": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Lines of context to show before and after each match (default: 0)"
+                },
+                "max_results": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "description": "Maximum matching lines to return (optional)"
                 }
             },
             "required": ["pattern"]
         })
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     async fn execute(
         &self,
         params: serde_json::Value,
@@ -90,143 +137,332 @@ impl AgentTool for SearchTool {
             .map(|s| s.to_string())
             .or_else(|| self.root.clone())
             .unwrap_or_else(|| ".".into());
+        // `include` and `case_sensitive` are the pre-regex-engine names.
+        let glob = params["glob"].as_str().or(params["include"].as_str());
+        let file_type = params["type"].as_str();
+        let file_types: Vec<&str> = match &params["file_types"] {
+            serde_json::Value::Null => Vec::new(),
+            serde_json::Value::Array(items) => items
+                .iter()
+                .map(|item| {
+                    item.as_str().ok_or_else(|| {
+                        ToolError::InvalidArgs("'file_types' must be an array of strings".into())
+                    })
+                })
+                .collect::<Result<_, _>>()?,
+            _ => {
+                return Err(ToolError::InvalidArgs(
+                    "'file_types' must be an array of strings".into(),
+                ))
+            }
+        };
+        let case_insensitive = params["case_insensitive"]
+            .as_bool()
+            .or(params["case_sensitive"]
+                .as_bool()
+                .map(|sensitive| !sensitive))
+            .unwrap_or(false);
+
+        let matcher = RegexMatcherBuilder::new()
+            .case_insensitive(case_insensitive)
+            .build(pattern)
+            .map_err(|e| ToolError::InvalidArgs(format!("invalid regex: {}", e)))?;
+        let query = Query {
+            matcher,
+            context_lines: params["context_lines"].as_u64().unwrap_or(0) as usize,
+            max_results: params["max_results"]
+                .as_u64()
+                .map_or(self.max_results, |n| {
+                    (n as usize).clamp(1, self.max_results)
+                }),
+            max_bytes: self.max_output_bytes,
+        };
 
-        let include = params["include"].as_str();
-        let case_sensitive = params["case_sensitive"].as_bool().unwrap_or(false);
+        if !Path::new(&search_path).exists() {
+            return Err(ToolError::Failed(format!(
+                "Path not found: {}. Check the path and try again.",
+                search_path
+            )));
+        }
+        let walker = build_walker(
+            &search_path,
+            glob,
+            file_type,
+            &file_types,
+            self.respect_gitignore,
+        )?;
 
         if cancel.is_cancelled() {
             return Err(ToolError::Cancelled);
         }
 
-        // Try ripgrep first, fall back to grep
-        let (cmd_name, args) = if which_exists("rg") {
-            build_rg_args(
-                pattern,
-                &search_path,
-                include,
-                case_sensitive,
-                self.max_results,
-            )
-        } else {
-            build_grep_args(
-                pattern,
-                &search_path,
-                include,
-                case_sensitive,
-                self.max_results,
-            )
+        let search_cancel = cancel.child_token();
+        let search = {
+            let cancel = search_cancel.clone();
+            tokio::task::spawn_blocking(move || search_files(walker, &query, &cancel))
         };
-
-        let mut cmd = Command::new(&cmd_name);
-        cmd.args(&args);
-        cmd.stdout(std::process::Stdio::piped());
-        cmd.stderr(std::process::Stdio::piped());
-
-        let timeout = self.timeout;
-
-        let result = tokio::select! {
-            _ = cancel.cancelled() => {
-                return Err(ToolError::Cancelled);
-            }
-            _ = tokio::time::sleep(timeout) => {
+        let output = tokio::select! {
+            _ = cancel.cancelled() => return Err(ToolError::Cancelled),
+            _ = tokio::time::sleep(self.timeout) => {
+                search_cancel.cancel();
                 return Err(ToolError::Failed("Search timed out".into()));
             }
-            result = cmd.output() => {
-                result.map_err(|e| ToolError::Failed(format!("Search failed: {}", e)))?
-            }
+            result = search => result.map_err(|e| ToolError::Failed(format!("Search failed: {}", e)))?,
         };
 
-        let stdout = String::from_utf8_lossy(&result.stdout).to_string();
-        let stderr = String::from_utf8_lossy(&result.stderr).to_string();
-
-        // grep returns exit code 1 for "no matches" — that's not an error
-        if result.status.code() == Some(2)
-            || (!stderr.is_empty() && result.status.code() != Some(1))
-        {
-            return Err(ToolError::Failed(format!("Search error: {}", stderr)));
-        }
-
-        if stdout.trim().is_empty() {
+        if output.matches == 0 {
             return Ok(ToolResult {
                 content: vec![Content::Text {
                     text: format!("No matches found for '{}'", pattern),
                 }],
-                details: serde_json::json!({ "matches": 0 }),
+                details: serde_json::json!({ "matches": 0, "files": 0, "truncated": false }),
             });
         }
 
-        let match_count = stdout.lines().count();
-        let text = if match_count >= self.max_results {
-            format!(
-                "{}\n... (showing first {} matches)",
-                stdout.trim(),
-                self.max_results
-            )
+        let mut text = output.lines.join("\n");
+        if output.byte_capped {
+            text.push_str(&format!(
+                "\n... (output truncated after {} matches; narrow the search to see the rest)",
+                output.matches
+            ));
+        } else if output.more_matches {
+            text.push_str(&format!(
+                "\n... (showing first {} matches; narrow the search to see the rest)",
+                output.matches
+            ));
         } else {
-            format!("{}\n({} matches)", stdout.trim(), match_count)
-        };
+            text.push_str(&format!(
+                "\n({} match{} in {} file{})",
+                output.matches,
+                if output.matches == 1 { "" } else { "es" },
+                output.files,
+                if output.files == 1 { "" } else { "s" }
+            ));
+        }
 
         Ok(ToolResult {
             content: vec![Content::Text { text }],
-            details: serde_json::json!({ "matches": match_count }),
+            details: serde_json::json!({
+                "matches": output.matches,
+                "files": output.files,
+                "truncated": output.byte_capped || output.more_matches,
+            }),
         })
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
 }
 
-fn which_exists(name: &str) -> bool {
-    std::process::Command::new("which")
-        .arg(name)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
+/// A gitignore-aware walk over `root`, restricted by `glob`, `file_type` and
+/// `file_types`. Files matching any of the types are searched.
+fn build_walker(
+    root: &str,
+    glob: Option<&str>,
+    file_type: Option<&str>,
+    file_types: &[&str],
+    respect_gitignore: bool,
+) -> Result<ignore::Walk, ToolError> {
+    let mut builder = ignore::WalkBuilder::new(root);
+    builder
+        .hidden(false)
+        .ignore(respect_gitignore)
+        .git_ignore(respect_gitignore)
+        .git_global(respect_gitignore)
+        .git_exclude(respect_gitignore)
+        .parents(respect_gitignore)
+        // Honour .gitignore files even outside a git checkout.
+        .require_git(false)
+        .filter_entry(|entry| entry.file_name() != ".git")
+        .sort_by_file_name(|a, b| a.cmp(b));
+
+    if let Some(glob) = glob {
+        let overrides = ignore::overrides::OverrideBuilder::new(root)
+            .add(glob)
+            .and_then(|b| b.build())
+            .map_err(|e| ToolError::InvalidArgs(format!("invalid glob '{}': {}", glob, e)))?;
+        builder.overrides(overrides);
+    }
+    if file_type.is_some() || !file_types.is_empty() {
+        let mut types = ignore::types::TypesBuilder::new();
+        types.add_defaults();
+        let known = |types: &ignore::types::TypesBuilder, name: &str| {
+            types.definitions().iter().any(|def| def.name() == name)
+        };
+        if let Some(name) = file_type {
+            if !known(&types, name) {
+                return Err(ToolError::InvalidArgs(format!(
+                    "unknown file type '{}'. Use a ripgrep type name such as 'rust', 'py', 'js' or 'md', or a glob instead.",
+                    name
+                )));
+            }
+            types.select(name);
+        }
+        // Anything that isn't a ripgrep type name is a file extension,
+        // collected under one custom type (type names must be alphanumeric).
+        const EXTENSIONS: &str = "yoagentextensions";
+        for name in file_types {
+            if known(&types, name) {
+                types.select(name);
+                continue;
+            }
+            let ext = name.trim_start_matches("*.").trim_start_matches('.');
+            types.add(EXTENSIONS, &format!("*.{}", ext)).map_err(|e| {
+                ToolError::InvalidArgs(format!("invalid file type '{}': {}", name, e))
+            })?;
+            types.select(EXTENSIONS);
+        }
+        let types = types
+            .build()
+            .map_err(|e| ToolError::InvalidArgs(format!("invalid file type: {}", e)))?;
+        builder.types(types);
+    }
+    Ok(builder.build())
 }
 
-fn build_rg_args(
-    pattern: &str,
-    path: &str,
-    include: Option<&str>,
-    case_sensitive: bool,
-    max_results: usize,
-) -> (String, Vec<String>) {
-    let mut args = vec![
-        "--line-number".into(),
-        "--no-heading".into(),
-        format!("--max-count={}", max_results),
-    ];
+/// Rendered search results.
+#[derive(Default)]
+struct Output {
+    lines: Vec<String>,
+    bytes: usize,
+    matches: usize,
+    files: usize,
+    /// A match past `max_results` was seen
+    more_matches: bool,
+    /// `max_bytes` cut the output short
+    byte_capped: bool,
+}
 
-    if !case_sensitive {
-        args.push("--ignore-case".into());
+impl Output {
+    fn done(&self) -> bool {
+        self.more_matches || self.byte_capped
     }
 
-    if let Some(glob) = include {
-        args.push(format!("--glob={}", glob));
+    fn push(&mut self, line: String, max_bytes: usize) {
+        if self.bytes + line.len() > max_bytes {
+            self.byte_capped = true;
+            return;
+        }
+        self.bytes += line.len() + 1;
+        self.lines.push(line);
+    }
+}
+
+fn search_files(
+    walker: ignore::Walk,
+    query: &Query,
+    cancel: &tokio_util::sync::CancellationToken,
+) -> Output {
+    let mut searcher = SearcherBuilder::new()
+        .line_number(true)
+        .before_context(query.context_lines)
+        .after_context(query.context_lines)
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .build();
+    let mut output = Output::default();
+
+    for entry in walker.flatten() {
+        if cancel.is_cancelled() || output.done() {
+            break;
+        }
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+        let path = entry.path();
+        let display = path
+            .strip_prefix("./")
+            .unwrap_or(path)
+            .display()
+            .to_string();
+        let mut sink = FileSink {
+            query,
+            path: &display,
+            output: &mut output,
+            started: false,
+        };
+        // Unreadable files are skipped, as rg does (it reports them on stderr).
+        let _ = searcher.search_path(&query.matcher, path, &mut sink);
+        if sink.started {
+            output.files += 1;
+        }
     }
+    output
+}
 
-    args.push(pattern.into());
-    args.push(path.into());
+/// Renders one file's matches into the shared [`Output`].
+struct FileSink<'a> {
+    query: &'a Query,
+    path: &'a str,
+    output: &'a mut Output,
+    /// Whether this file has produced any output yet
+    started: bool,
+}
 
-    ("rg".into(), args)
+impl FileSink<'_> {
+    fn push(&mut self, line: String) {
+        // Separate files' context groups like rg does.
+        if !self.started && self.query.context_lines > 0 && !self.output.lines.is_empty() {
+            self.output.push("--".into(), self.query.max_bytes);
+        }
+        self.started = true;
+        self.output.push(line, self.query.max_bytes);
+    }
 }
 
-fn build_grep_args(
-    pattern: &str,
-    path: &str,
-    include: Option<&str>,
-    case_sensitive: bool,
-    max_results: usize,
-) -> (String, Vec<String>) {
-    let mut args = vec!["-r".into(), "-n".into(), format!("-m{}", max_results)];
+impl Sink for FileSink<'_> {
+    type Error = std::io::Error;
 
-    if !case_sensitive {
-        args.push("-i".into());
+    fn matched(&mut self, _searcher: &Searcher, m: &SinkMatch<'_>) -> Result<bool, Self::Error> {
+        if self.output.matches >= self.query.max_results {
+            self.output.more_matches = true;
+            return Ok(false);
+        }
+        let line = m.bytes();
+        let column = self
+            .query
+            .matcher
+            .find(line)
+            .ok()
+            .flatten()
+            .map_or(1, |found| found.start() + 1);
+        self.output.matches += 1;
+        self.push(format!(
+            "{}:{}:{}:{}",
+            self.path,
+            m.line_number().unwrap_or(0),
+            column,
+            trim_line(line)
+        ));
+        Ok(!self.output.byte_capped)
     }
 
-    if let Some(glob) = include {
-        args.push(format!("--include={}", glob));
+    fn context(&mut self, _searcher: &Searcher, c: &SinkContext<'_>) -> Result<bool, Self::Error> {
+        // Once the match limit is hit, only the last match's trailing context
+        // is still wanted.
+        if self.output.matches < self.query.max_results
+            || matches!(c.kind(), grep_searcher::SinkContextKind::After)
+        {
+            self.push(format!(
+                "{}-{}-{}",
+                self.path,
+                c.line_number().unwrap_or(0),
+                trim_line(c.bytes())
+            ));
+        }
+        Ok(!self.output.byte_capped)
     }
 
-    args.push(pattern.into());
-    args.push(path.into());
+    fn context_break(&mut self, _searcher: &Searcher) -> Result<bool, Self::Error> {
+        if self.output.matches < self.query.max_results {
+            self.push("--".into());
+        }
+        Ok(!self.output.byte_capped)
+    }
+}
 
-    ("grep".into(), args)
+fn trim_line(line: &[u8]) -> String {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    let line = line.strip_suffix(b"\r").unwrap_or(line);
+    String::from_utf8_lossy(line).into_owned()
 }
@@ -7,6 +7,7 @@ use crate::shared_state::SharedState;
 use crate::types::*;
 
 /// A tool that lets an LLM read/write a [`SharedState`] store.
+#[derive(Clone)]
 pub struct SharedStateTool {
     state: SharedState,
 }
@@ -126,6 +127,10 @@ impl AgentTool for SharedStateTool {
             ))),
         }
     }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
 }
 
 fn require_key(params: &serde_json::Value) -> Result<String, ToolError> {
@@ -148,6 +153,7 @@ mod tests {
             cancel: CancellationToken::new(),
             on_update: None,
             on_progress: None,
+            timeout: None,
         }
     }
 
@@ -0,0 +1,261 @@
+//! Web search tool — query a search engine through a pluggable backend.
+
+use super::fetch::html_to_text;
+use super::http::request_error;
+use crate::types::*;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Results returned when the call gives no `count`.
+const DEFAULT_COUNT: usize = 5;
+
+/// One search hit.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebSearchResult {
+    pub title: String,
+    pub url: String,
+    /// Short plain-text excerpt from the page.
+    pub snippet: String,
+}
+
+/// A search engine that [`WebSearchTool`] can query. Implement it to plug
+/// in any provider; [`BraveSearch`] is built in.
+#[async_trait]
+pub trait SearchBackend: Send + Sync {
+    /// Up to `count` results for `query`, best first.
+    async fn search(&self, query: &str, count: usize) -> Result<Vec<WebSearchResult>, ToolError>;
+}
+
+/// Search the web and return a compact list of results (title, URL,
+/// snippet) so the model can ground its answers and pick pages to
+/// [`fetch`](super::FetchTool).
+///
+/// The search itself is done by a [`SearchBackend`]. The full results, with
+/// snippets untruncated, are in `details["results"]`. Not part of
+/// [`default_tools`](super::default_tools) — it needs a backend and usually
+/// an API key.
+///
+/// ```rust,no_run
+/// use yoagent::tools::{BraveSearch, WebSearchTool};
+///
+/// let search = WebSearchTool::new(BraveSearch::new(std::env::var("BRAVE_API_KEY").unwrap()))
+///     .with_max_results(8);
+/// ```
+#[derive(Clone)]
+pub struct WebSearchTool {
+    backend: Arc<dyn SearchBackend>,
+    /// Max results per call (a call may ask for fewer)
+    pub max_results: usize,
+    /// Snippets longer than this many characters are cut in the text shown
+    /// to the model
+    pub max_snippet_chars: usize,
+}
+
+impl WebSearchTool {
+    pub fn new(backend: impl SearchBackend + 'static) -> Self {
+        Self {
+            backend: Arc::new(backend),
+            max_results: 10,
+            max_snippet_chars: 300,
+        }
+    }
+
+    pub fn with_max_results(mut self, max: usize) -> Self {
+        self.max_results = max.max(1);
+        self
+    }
+
+    pub fn with_max_snippet_chars(mut self, max: usize) -> Self {
+        self.max_snippet_chars = max;
+        self
+    }
+}
+
+#[async_trait]
+impl AgentTool for WebSearchTool {
+    fn name(&self) -> &str {
+        "web_search"
+    }
+
+    fn label(&self) -> &str {
+        "Web Search"
+    }
+
+    fn description(&self) -> &str {
+        "Search the web. Returns a numbered list of results with title, URL and a short snippet. Use to find current information or sources; fetch a result's URL to read the page."
+    }
+
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {
+                "query": {
+                    "type": "string",
+                    "description": "Search query"
+                },
+                "count": {
+                    "type": "integer",
+                    "minimum": 1,
+                    "maximum": self.max_results,
+                    "description": format!(
+                        "Number of results to return (default: {})",
+                        DEFAULT_COUNT.min(self.max_results)
+                    )
+                }
+            },
+            "required": ["query"]
+        })
+    }
+
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let query = params["query"]
+            .as_str()
+            .map(str::trim)
+            .filter(|q| !q.is_empty())
+            .ok_or_else(|| ToolError::InvalidArgs("missing 'query' parameter".into()))?;
+        let count = params["count"]
+            .as_u64()
+            .map_or(DEFAULT_COUNT, |n| n as usize)
+            .clamp(1, self.max_results);
+
+        let mut results = tokio::select! {
+            _ = ctx.cancel.cancelled() => return Err(ToolError::Cancelled),
+            results = self.backend.search(query, count) => results?,
+        };
+        results.truncate(count);
+
+        let text = if results.is_empty() {
+            format!("No results for \"{}\"", query)
+        } else {
+            results
+                .iter()
+                .enumerate()
+                .map(|(i, r)| {
+                    let mut entry = format!("{}. {}\n   {}", i + 1, r.title, r.url);
+                    if !r.snippet.is_empty() {
+                        entry.push_str("\n   ");
+                        entry.push_str(&truncate_chars(&r.snippet, self.max_snippet_chars));
+                    }
+                    entry
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+
+        Ok(ToolResult {
+            content: vec![Content::Text { text }],
+            details: serde_json::json!({
+                "query": query,
+                "results": results,
+            }),
+        })
+    }
+
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        Box::new(self.clone())
+    }
+}
+
+fn truncate_chars(text: &str, max: usize) -> String {
+    match text.char_indices().nth(max) {
+        Some((cut, _)) => format!("{}…", text[..cut].trim_end()),
+        None => text.to_string(),
+    }
+}
+
+/// [`SearchBackend`] for the [Brave Search API](https://brave.com/search/api/).
+#[derive(Clone)]
+pub struct BraveSearch {
+    client: reqwest::Client,
+    api_key: String,
+    endpoint: String,
+    /// Request timeout
+    pub timeout: Duration,
+}
+
+impl BraveSearch {
+    pub const ENDPOINT: &'static str = "https://api.search.brave.com/res/v1/web/search";
+
+    /// Results Brave returns per request at most.
+    const MAX_COUNT: usize = 20;
+
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            api_key: api_key.into(),
+            endpoint: Self::ENDPOINT.to_string(),
+            timeout: Duration::from_secs(15),
+        }
+    }
+
+    /// Send requests to another URL, e.g. a proxy or a mock server.
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+#[async_trait]
+impl SearchBackend for BraveSearch {
+    async fn search(&self, query: &str, count: usize) -> Result<Vec<WebSearchResult>, ToolError> {
+        let mut url = reqwest::Url::parse(&self.endpoint).map_err(|e| {
+            ToolError::Failed(format!(
+                "invalid search endpoint '{}': {}",
+                self.endpoint, e
+            ))
+        })?;
+        url.query_pairs_mut()
+            .append_pair("q", query)
+            .append_pair("count", &count.min(Self::MAX_COUNT).to_string());
+
+        let response = self
+            .client
+            .get(url)
+            .header(reqwest::header::ACCEPT, "application/json")
+            .header("X-Subscription-Token", &self.api_key)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .map_err(|e| request_error(e, self.timeout))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(ToolError::Failed(format!(
+                "Brave search failed: HTTP {}: {}",
+                status,
+                truncate_chars(body.trim(), 500)
+            )));
+        }
+        let body: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| ToolError::Failed(format!("invalid Brave search response: {}", e)))?;
+
+        // Titles and descriptions carry highlighting markup (`<strong>`).
+        let field = |r: &serde_json::Value, key: &str| html_to_text(r[key].as_str().unwrap_or(""));
+        Ok(body["web"]["results"]
+            .as_array()
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|r| {
+                Some(WebSearchResult {
+                    title: field(r, "title"),
+                    url: r["url"].as_str()?.to_string(),
+                    snippet: field(r, "description"),
+                })
+            })
+            .collect())
+    }
+}
@@ -11,12 +11,12 @@ use std::sync::Arc;
 /// Exhaustiveness policy (two separate levers):
 /// - The **enum** is `#[non_exhaustive]`: new content kinds may be added in
 ///   minor releases, so downstream `match` arms need a wildcard.
-/// - The `ToolCall` and `Thinking` **variants** are separately
-///   `#[non_exhaustive]`: their fields grow with provider features (PR #32
-///   added `provider_metadata`), so downstream constructs them via the
-///   `Content::tool_call*` / `Content::thinking*` constructors and uses `..`
-///   in patterns. `Text` and `Image` stay literally constructible — they are
-///   user-facing shapes that do not grow.
+/// - The `ToolCall`, `Thinking`, `ServerToolUse` and `ServerToolResult`
+///   **variants** are separately `#[non_exhaustive]`: their fields grow with
+///   provider features (PR #32 added `provider_metadata`), so downstream
+///   constructs them via the `Content::tool_call*` / `Content::thinking*` /
+///   `Content::server_tool_*` constructors and uses `..` in patterns. `Text`, `Image`, `Document` and `DocumentUrl` stay
+///   literally constructible — they are user-facing shapes that do not grow.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 #[non_exhaustive]
@@ -29,6 +29,22 @@ pub enum Content {
         #[serde(rename = "mimeType")]
         mime_type: String,
     },
+    /// A file such as a PDF or DOCX, sent inline. Serialized as base64.
+    #[serde(rename = "document")]
+    Document {
+        #[serde(with = "base64_bytes")]
+        data: Vec<u8>,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+        filename: String,
+    },
+    /// A document the provider fetches itself.
+    #[serde(rename = "documentUrl")]
+    DocumentUrl {
+        url: String,
+        #[serde(rename = "mimeType")]
+        mime_type: String,
+    },
     #[serde(rename = "thinking")]
     #[non_exhaustive]
     Thinking {
@@ -53,6 +69,29 @@ pub enum Content {
         )]
         provider_metadata: Option<serde_json::Value>,
     },
+    /// A call to a [`ServerTool`](crate::provider::ServerTool) that the
+    /// provider executed itself. The agent loop does not run it; it is kept
+    /// so the turn can be replayed.
+    #[serde(rename = "serverToolUse")]
+    #[non_exhaustive]
+    ServerToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    /// The provider's result for a [`Content::ServerToolUse`], kept verbatim
+    /// so it can be sent back (e.g. Anthropic `web_search_tool_result`, whose
+    /// `content` lists the pages found).
+    #[serde(rename = "serverToolResult")]
+    #[non_exhaustive]
+    ServerToolResult {
+        #[serde(rename = "toolUseId")]
+        tool_use_id: String,
+        /// The provider's block type, e.g. `web_search_tool_result`.
+        #[serde(rename = "resultType")]
+        result_type: String,
+        content: serde_json::Value,
+    },
 }
 
 impl Content {
@@ -90,6 +129,32 @@ impl Content {
         }
     }
 
+    /// Construct a server-tool call block.
+    pub fn server_tool_use(
+        id: impl Into<String>,
+        name: impl Into<String>,
+        input: serde_json::Value,
+    ) -> Self {
+        Self::ServerToolUse {
+            id: id.into(),
+            name: name.into(),
+            input,
+        }
+    }
+
+    /// Construct a server-tool result block.
+    pub fn server_tool_result(
+        tool_use_id: impl Into<String>,
+        result_type: impl Into<String>,
+        content: serde_json::Value,
+    ) -> Self {
+        Self::ServerToolResult {
+            tool_use_id: tool_use_id.into(),
+            result_type: result_type.into(),
+            content,
+        }
+    }
+
     /// Construct a tool-call content block carrying provider metadata
     /// (e.g. a Gemini thought signature).
     pub fn tool_call_with_metadata(
@@ -107,6 +172,23 @@ impl Content {
     }
 }
 
+/// `Vec<u8>` as a base64 string, matching how `Image` data is stored.
+mod base64_bytes {
+    use base64::Engine;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(&base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(d)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Messages
 // ---------------------------------------------------------------------------
@@ -151,12 +233,52 @@ pub enum Message {
 
 impl Message {
     pub fn user(text: impl Into<String>) -> Self {
+        Self::user_multipart(vec![Content::Text { text: text.into() }])
+    }
+
+    /// A user message with any mix of content blocks (text, images,
+    /// documents).
+    pub fn user_multipart(parts: Vec<Content>) -> Self {
         Self::User {
-            content: vec![Content::Text { text: text.into() }],
+            content: parts,
             timestamp: now_ms(),
         }
     }
 
+    /// A user message with text followed by one image. `image_data` is
+    /// base64-encoded, as in [`Content::Image`].
+    pub fn user_with_image(
+        text: impl Into<String>,
+        image_data: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) -> Self {
+        Self::user_multipart(vec![
+            Content::Text { text: text.into() },
+            Content::Image {
+                data: image_data.into(),
+                mime_type: mime_type.into(),
+            },
+        ])
+    }
+
+    /// A user message with text followed by one inline document (raw bytes,
+    /// e.g. a PDF).
+    pub fn user_with_document(
+        text: impl Into<String>,
+        data: Vec<u8>,
+        filename: impl Into<String>,
+        mime_type: impl Into<String>,
+    ) -> Self {
+        Self::user_multipart(vec![
+            Content::Text { text: text.into() },
+            Content::Document {
+                data,
+                mime_type: mime_type.into(),
+                filename: filename.into(),
+            },
+        ])
+    }
+
     /// Construct an assistant message.
     ///
     /// The `Assistant` variant is `#[non_exhaustive]` — its fields grow with
@@ -209,6 +331,14 @@ impl Message {
         }
     }
 
+    pub fn content(&self) -> &[Content] {
+        match self {
+            Self::User { content, .. }
+            | Self::Assistant { content, .. }
+            | Self::ToolResult { content, .. } => content,
+        }
+    }
+
     /// Check if this assistant message represents a context overflow error.
     ///
     /// Some providers (SSE-based: Anthropic, OpenAI) return overflow as a
@@ -224,6 +354,38 @@ impl Message {
             _ => false,
         }
     }
+
+    /// Deserialize the assistant's reply as JSON.
+    ///
+    /// Concatenates the message's text blocks (markdown code fences are
+    /// stripped) and parses the result. Pair with
+    /// [`ResponseFormat::JsonObject`](crate::provider::ResponseFormat) or an
+    /// output schema. Errors on non-assistant messages.
+    pub fn parse_json<T: serde::de::DeserializeOwned>(&self) -> Result<T, serde_json::Error> {
+        let Self::Assistant { content, .. } = self else {
+            return Err(serde::de::Error::custom(format!(
+                "expected an assistant message, got {}",
+                self.role()
+            )));
+        };
+        let text: String = content
+            .iter()
+            .filter_map(|c| match c {
+                Content::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        serde_json::from_str(strip_json_fences(&text))
+    }
+}
+
+/// Trim whitespace and any surrounding markdown code fence from a JSON reply.
+pub(crate) fn strip_json_fences(text: &str) -> &str {
+    text.trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```")
+        .trim()
 }
 
 // ---------------------------------------------------------------------------
@@ -303,6 +465,16 @@ pub enum StopReason {
     /// Fable 5). The agent loop does not special-case it (the turn ends like a
     /// normal `Stop`); callers can match on it to retry on a fallback model.
     Refusal,
+    /// Generation hit one of the request's `stop_sequences`. Only reported
+    /// by providers that distinguish it (Anthropic, Bedrock, Cohere); OpenAI and
+    /// Gemini report a plain `Stop`. The loop treats it like `Stop`.
+    StopSequence,
+    /// The provider paused a long-running turn and expects the conversation
+    /// back, paused assistant message included, so the model can pick up
+    /// where it left off. Anthropic sends it (`stop_reason: pause_turn`)
+    /// while [server tools](crate::provider::ServerTool) are still working.
+    /// The agent loop makes that follow-up call itself.
+    PauseTurn,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
@@ -329,6 +501,13 @@ impl Usage {
         }
         self.cache_read as f64 / total_input as f64
     }
+
+    /// Dollar cost of this usage at `pricing`'s rates. Cache reads and
+    /// writes are charged at their own rates rather than as fresh input.
+    /// Look up rates with [`CostConfig::for_model`](crate::provider::CostConfig::for_model).
+    pub fn cost(&self, pricing: &crate::provider::CostConfig) -> f64 {
+        pricing.cost_usd(self)
+    }
 }
 
 // ---------------------------------------------------------------------------
@@ -380,6 +559,10 @@ pub enum ToolExecutionStrategy {
     /// Run in batches of N, check steering between batches.
     /// Balances speed with human-in-the-loop control.
     Batched { size: usize },
+    /// Run concurrently, but at most `limit` at once: a new call starts as
+    /// soon as a running one finishes. Check steering after all complete.
+    /// Use when a model may emit dozens of calls at a time.
+    ThrottledParallel { limit: usize },
 }
 
 /// Strategy for placing cache breakpoints (Anthropic-specific; other providers
@@ -452,6 +635,10 @@ pub struct ToolContext {
     pub on_update: Option<ToolUpdateFn>,
     /// Optional callback for emitting user-facing progress messages.
     pub on_progress: Option<ProgressFn>,
+    /// Deadline the loop enforces on this call, if any. The loop cancels
+    /// `cancel` and reports [`ToolError::Timeout`] when it expires; tools may
+    /// read it to size their own internal waits.
+    pub timeout: Option<std::time::Duration>,
 }
 
 impl Clone for ToolContext {
@@ -462,6 +649,7 @@ impl Clone for ToolContext {
             cancel: self.cancel.clone(),
             on_update: self.on_update.clone(),
             on_progress: self.on_progress.clone(),
+            timeout: self.timeout,
         }
     }
 }
@@ -477,6 +665,7 @@ impl std::fmt::Debug for ToolContext {
                 "on_progress",
                 &self.on_progress.as_ref().map(|_| "<callback>"),
             )
+            .field("timeout", &self.timeout)
             .finish()
     }
 }
@@ -499,11 +688,59 @@ pub trait AgentTool: Send + Sync {
     /// - `ctx.cancel` — cancellation token; check `is_cancelled()` in long-running tools
     /// - `ctx.on_update` — optional callback for streaming partial `ToolResult`s (UI/logging only)
     /// - `ctx.on_progress` — optional callback for user-facing progress text (`ProgressMessage`)
+    /// - `ctx.timeout` — deadline enforced by the loop, if one is configured
     async fn execute(
         &self,
         params: serde_json::Value,
         ctx: ToolContext,
     ) -> Result<ToolResult, ToolError>;
+
+    /// Deadline for calls to this tool, overriding
+    /// `AgentLoopConfig::tool_timeout` (longer or shorter). `None` (the
+    /// default) uses the loop-wide setting.
+    fn timeout(&self) -> Option<std::time::Duration> {
+        None
+    }
+
+    /// Whether the loop checks call arguments against
+    /// [`parameters_schema`](Self::parameters_schema) before `execute`. A
+    /// call that doesn't match fails with [`ToolError::InvalidArgs`] and the
+    /// tool never runs. Return `false` for tools with deliberately loose
+    /// schemas.
+    fn validate_args(&self) -> bool {
+        true
+    }
+
+    /// Whether a [`ToolCache`](crate::tools::ToolCache) may serve repeated
+    /// identical calls from a stored result. Only read-only tools whose
+    /// output depends on nothing but their arguments and the workspace
+    /// should return `true`; the default is `false`.
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    /// Share this tool, e.g. between a parent agent and a
+    /// [`SubAgentTool`](crate::SubAgentTool), without copying its state. Wrap
+    /// the result in [`SharedToolWrapper`](crate::SharedToolWrapper) where a
+    /// `Box<dyn AgentTool>` is needed.
+    fn as_shared(self: Arc<Self>) -> Arc<dyn AgentTool>
+    where
+        Self: Sized + 'static,
+    {
+        self
+    }
+
+    /// An independent copy of this tool. Most tools hold state that can't be
+    /// duplicated, so the default panics; tools that are cheap to copy
+    /// override it. To use one tool in several places, share it with
+    /// [`as_shared`](Self::as_shared) instead.
+    fn clone_box(&self) -> Box<dyn AgentTool> {
+        panic!(
+            "tool `{}` does not implement clone_box; share it with \
+             Arc<dyn AgentTool> (see AgentTool::as_shared) instead",
+            self.name()
+        )
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -523,6 +760,9 @@ pub enum ToolError {
     InvalidArgs(String),
     #[error("Cancelled")]
     Cancelled,
+    /// The call ran past its deadline and was cancelled.
+    #[error("Tool timed out after {0:?}")]
+    Timeout(std::time::Duration),
 }
 
 // ---------------------------------------------------------------------------
@@ -572,6 +812,20 @@ pub enum AgentEvent {
     MessageEnd {
         message: AgentMessage,
     },
+    /// Tool-call arguments assembled so far while the model is still
+    /// streaming them. `partial_args` is the accumulated buffer parsed as
+    /// JSON, with unterminated strings and brackets closed.
+    ToolCallArgsUpdate {
+        tool_call_id: String,
+        tool_name: String,
+        partial_args: serde_json::Value,
+    },
+    /// The loop is about to ask `approve_tool` whether this call may run.
+    ToolApprovalRequired {
+        tool_call_id: String,
+        tool_name: String,
+        args: serde_json::Value,
+    },
     ToolExecutionStart {
         tool_call_id: String,
         tool_name: String,
@@ -596,6 +850,17 @@ pub enum AgentEvent {
     InputRejected {
         reason: String,
     },
+    /// Compaction shrank the history before a turn, or — if the converted
+    /// request was still over `max_context_tokens` (e.g. after
+    /// `transform_context` grew it) — shrank the request just before the
+    /// provider call. `level` is the deepest tier applied.
+    ContextCompacted {
+        messages_before: usize,
+        messages_after: usize,
+        tokens_before: usize,
+        tokens_after: usize,
+        level: crate::context::CompactionLevel,
+    },
 }
 
 /// Incremental content delta carried by [`AgentEvent::MessageUpdate`].
@@ -624,6 +889,302 @@ pub struct AgentContext {
     pub tools: Vec<Box<dyn AgentTool>>,
 }
 
+/// Copies the messages and clones each tool with
+/// [`AgentTool::clone_box`], so a run can be branched: clone the context at
+/// some point and continue each copy with a different prompt.
+///
+/// # Panics
+///
+/// If a tool doesn't implement `clone_box` (the built-in tools all do).
+/// Wrap such a tool in [`SharedToolWrapper`](crate::SharedToolWrapper) to
+/// let the branches share it instead.
+impl Clone for AgentContext {
+    fn clone(&self) -> Self {
+        Self {
+            system_prompt: self.system_prompt.clone(),
+            messages: self.messages.clone(),
+            tools: self.tools.iter().map(|t| t.clone_box()).collect(),
+        }
+    }
+}
+
+impl AgentContext {
+    /// Add a tool, replacing any existing tool with the same name. Takes
+    /// effect on the next LLM call and for every tool call after this.
+    pub fn add_tool(&mut self, tool: Box<dyn AgentTool>) {
+        self.remove_tool(tool.name());
+        self.tools.push(tool);
+    }
+
+    /// Remove the tool named `name`. Returns whether it was present.
+    pub fn remove_tool(&mut self, name: &str) -> bool {
+        let before = self.tools.len();
+        self.tools.retain(|t| t.name() != name);
+        self.tools.len() != before
+    }
+
+    pub fn has_tool(&self, name: &str) -> bool {
+        self.tools.iter().any(|t| t.name() == name)
+    }
+
+    /// Tool names, in registration order.
+    pub fn tool_names(&self) -> Vec<&str> {
+        self.tools.iter().map(|t| t.name()).collect()
+    }
+
+    /// Serialize the system prompt and messages. Tools are not serializable
+    /// and are left out; pass them back to [`from_json`](Self::from_json).
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(&ContextSnapshot {
+            system_prompt: &self.system_prompt,
+            messages: &self.messages,
+        })
+    }
+
+    /// Rebuild a context from [`to_json`](Self::to_json) output (or a
+    /// serialized [`ConversationSession`]) plus the tools to run with.
+    pub fn from_json(
+        json: &str,
+        tools: Vec<Box<dyn AgentTool>>,
+    ) -> Result<Self, serde_json::Error> {
+        let session: ConversationSession = serde_json::from_str(json)?;
+        Ok(session.into_context(tools))
+    }
+
+    /// The message history as a JSON array, for storing alongside other
+    /// state. Restore it with [`load_messages`](Self::load_messages).
+    pub fn save_messages(&self) -> serde_json::Value {
+        serde_json::to_value(&self.messages).expect("messages serialize to JSON")
+    }
+
+    /// Replace the message history with [`save_messages`](Self::save_messages)
+    /// output. The system prompt and tools are left as they are. On error
+    /// the history is unchanged.
+    pub fn load_messages(&mut self, value: serde_json::Value) -> Result<(), serde_json::Error> {
+        self.messages = serde_json::from_value(value)?;
+        Ok(())
+    }
+
+    /// Check the history is a sequence providers accept and
+    /// [`agent_loop_continue`](crate::agent_loop::agent_loop_continue) can
+    /// resume from. Returns every violation found, in message order.
+    ///
+    /// Assistant messages from failed turns (`Error`/`Aborted`) are
+    /// placeholders rather than replies: they may be empty and don't count
+    /// as a previous assistant message. Extension messages are ignored.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        let mut call_ids = std::collections::HashSet::new();
+        let mut previous_assistant = false;
+        for (index, message) in self.messages.iter().enumerate() {
+            let AgentMessage::Llm(message) = message else {
+                continue;
+            };
+            if message.content().is_empty() && !is_failed_turn(message) {
+                errors.push(ValidationError::EmptyContent {
+                    index,
+                    role: message.role().to_string(),
+                });
+            }
+            match message {
+                Message::Assistant { content, .. } => {
+                    if is_failed_turn(message) {
+                        continue;
+                    }
+                    if previous_assistant {
+                        errors.push(ValidationError::ConsecutiveAssistant { index });
+                    }
+                    call_ids.extend(content.iter().filter_map(|c| match c {
+                        Content::ToolCall { id, .. } => Some(id.as_str()),
+                        _ => None,
+                    }));
+                }
+                Message::ToolResult { tool_call_id, .. } => {
+                    if !call_ids.contains(tool_call_id.as_str()) {
+                        errors.push(ValidationError::OrphanedToolResult {
+                            index,
+                            tool_call_id: tool_call_id.clone(),
+                        });
+                    }
+                }
+                Message::User { .. } => {}
+            }
+            previous_assistant = matches!(message, Message::Assistant { .. });
+        }
+        if let Some(index) = self
+            .messages
+            .iter()
+            .rposition(|m| matches!(m, AgentMessage::Llm(_)))
+        {
+            if self.messages[index].role() == "assistant" {
+                errors.push(ValidationError::EndsWithAssistant { index });
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Fix what [`validate`](Self::validate) can fix without inventing
+    /// content: orphaned tool results and empty user/assistant messages are
+    /// dropped, and consecutive assistant messages are merged into one
+    /// (content concatenated, usage summed, the later stop reason kept).
+    /// A trailing assistant message and empty tool results are left as is.
+    pub fn repair(mut self) -> Self {
+        let mut repaired: Vec<AgentMessage> = Vec::with_capacity(self.messages.len());
+        let mut call_ids = std::collections::HashSet::new();
+        // Position in `repaired` of the latest assistant reply, until a user
+        // message or tool result follows it.
+        let mut last_assistant: Option<usize> = None;
+        for message in self.messages.drain(..) {
+            let AgentMessage::Llm(llm) = message else {
+                repaired.push(message);
+                continue;
+            };
+            let failed = is_failed_turn(&llm);
+            let is_tool_result = matches!(llm, Message::ToolResult { .. });
+            if llm.content().is_empty() && !failed && !is_tool_result {
+                continue;
+            }
+            match &llm {
+                Message::Assistant { content, .. } if !failed => {
+                    call_ids.extend(content.iter().filter_map(|c| match c {
+                        Content::ToolCall { id, .. } => Some(id.clone()),
+                        _ => None,
+                    }));
+                    if let Some(at) = last_assistant {
+                        merge_assistant(&mut repaired[at], llm);
+                        continue;
+                    }
+                    last_assistant = Some(repaired.len());
+                }
+                Message::Assistant { .. } => {}
+                Message::ToolResult { tool_call_id, .. } if !call_ids.contains(tool_call_id) => {
+                    continue;
+                }
+                Message::ToolResult { .. } | Message::User { .. } => last_assistant = None,
+            }
+            repaired.push(AgentMessage::Llm(llm));
+        }
+        self.messages = repaired;
+        self
+    }
+}
+
+/// Append assistant message `next` to assistant message `into`.
+fn merge_assistant(into: &mut AgentMessage, next: Message) {
+    let (
+        AgentMessage::Llm(Message::Assistant {
+            content,
+            stop_reason,
+            usage,
+            ..
+        }),
+        Message::Assistant {
+            content: next_content,
+            stop_reason: next_stop,
+            usage: next_usage,
+            ..
+        },
+    ) = (into, next)
+    else {
+        return;
+    };
+    content.extend(next_content);
+    *stop_reason = next_stop;
+    usage.input += next_usage.input;
+    usage.output += next_usage.output;
+    usage.cache_read += next_usage.cache_read;
+    usage.cache_write += next_usage.cache_write;
+    usage.total_tokens += next_usage.total_tokens;
+}
+
+/// An assistant message recording a failed turn rather than a reply.
+fn is_failed_turn(message: &Message) -> bool {
+    matches!(
+        message,
+        Message::Assistant {
+            stop_reason: StopReason::Error | StopReason::Aborted,
+            ..
+        }
+    )
+}
+
+/// A problem found by [`AgentContext::validate`]. `index` is the position in
+/// `AgentContext::messages`.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ValidationError {
+    /// An assistant message directly follows another one.
+    #[error("message {index}: assistant message follows another assistant message")]
+    ConsecutiveAssistant { index: usize },
+    /// A tool result whose id matches no earlier tool call.
+    #[error("message {index}: tool result for unknown tool call '{tool_call_id}'")]
+    OrphanedToolResult { index: usize, tool_call_id: String },
+    /// The history ends with an assistant message, so there is nothing for
+    /// the model to respond to.
+    #[error("message {index}: history ends with an assistant message")]
+    EndsWithAssistant { index: usize },
+    /// A message with no content blocks.
+    #[error("message {index}: {role} message has no content")]
+    EmptyContent { index: usize, role: String },
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ContextSnapshot<'a> {
+    system_prompt: &'a str,
+    messages: &'a [AgentMessage],
+}
+
+/// A persisted conversation: an [`AgentContext`] minus its tools, plus
+/// session metadata. This is the type to save and reload between processes;
+/// tools are supplied again on [`into_context`](Self::into_context).
+///
+/// For branching history (fork, checkpoints) see [`Session`](crate::Session).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConversationSession {
+    /// Unique id (UUID v4 for new sessions).
+    #[serde(default)]
+    pub session_id: String,
+    /// Creation time, ms since the Unix epoch.
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub system_prompt: String,
+    #[serde(default)]
+    pub messages: Vec<AgentMessage>,
+}
+
+impl ConversationSession {
+    /// A new session with a fresh id and the current time.
+    pub fn new(system_prompt: impl Into<String>, messages: Vec<AgentMessage>) -> Self {
+        Self {
+            session_id: uuid::Uuid::new_v4().to_string(),
+            created_at: now_ms(),
+            system_prompt: system_prompt.into(),
+            messages,
+        }
+    }
+
+    /// Snapshot a context's prompt and messages into a new session.
+    pub fn from_context(context: &AgentContext) -> Self {
+        Self::new(context.system_prompt.clone(), context.messages.clone())
+    }
+
+    /// Turn the session back into a runnable context with `tools`.
+    pub fn into_context(self, tools: Vec<Box<dyn AgentTool>>) -> AgentContext {
+        AgentContext {
+            system_prompt: self.system_prompt,
+            messages: self.messages,
+            tools,
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // Input filtering
 // ---------------------------------------------------------------------------
@@ -642,12 +1203,55 @@ pub enum FilterResult {
 /// Synchronous filter applied to user input before the LLM call.
 ///
 /// Implement this for injection detection, content moderation, PII redaction, etc.
-/// Filters run in the hot path and must be fast — use `before_turn` callbacks
-/// for async moderation (external API calls).
+/// Filters that need I/O (a moderation API, a database denylist) implement
+/// [`AsyncInputFilter`] instead.
 pub trait InputFilter: Send + Sync {
     fn filter(&self, text: &str) -> FilterResult;
 }
 
+/// Async filter applied to user input before the LLM call.
+///
+/// This is what the loop stores and awaits. Every [`InputFilter`] gets it
+/// through a blanket impl, so sync and async filters mix freely in
+/// `input_filters`.
+#[async_trait::async_trait]
+pub trait AsyncInputFilter: Send + Sync {
+    async fn filter(&self, text: &str) -> FilterResult;
+}
+
+#[async_trait::async_trait]
+impl<T: InputFilter> AsyncInputFilter for T {
+    async fn filter(&self, text: &str) -> FilterResult {
+        InputFilter::filter(self, text)
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Output filtering
+// ---------------------------------------------------------------------------
+
+/// Result of applying an output filter to an assistant message.
+#[derive(Debug, Clone)]
+pub enum OutputFilterResult {
+    /// Message passes unchanged.
+    Pass,
+    /// Replace the message, e.g. with sensitive text masked. Later filters
+    /// see the replacement.
+    Redact(Message),
+    /// Suppress the message: its content, tool calls included, is replaced
+    /// by this text. Later filters are skipped.
+    Block(String),
+}
+
+/// Filter applied to each assistant message before it is added to context
+/// and reported in [`AgentEvent::MessageEnd`].
+///
+/// Streaming deltas are not filtered — consumers that must never see the
+/// raw text should render from `MessageEnd` instead of `MessageUpdate`.
+pub trait OutputFilter: Send + Sync {
+    fn filter(&self, message: &Message) -> OutputFilterResult;
+}
+
 // ---------------------------------------------------------------------------
 // Tool middleware (permissions)
 // ---------------------------------------------------------------------------
@@ -703,6 +1307,21 @@ pub struct ToolCallRequest<'a> {
     pub args: &'a serde_json::Value,
 }
 
+/// Answer from an `approve_tool` callback (see
+/// [`AgentLoopConfig::approve_tool`](crate::agent_loop::AgentLoopConfig::approve_tool)).
+///
+/// Not `#[non_exhaustive]`, for the same reason as [`ToolDecision`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolApproval {
+    /// Run this call.
+    Allow,
+    /// Skip the call; the reason goes back to the LLM as an error result.
+    Deny(String),
+    /// Run this call and every later call to the same tool in this run
+    /// without asking again.
+    AlwaysAllow,
+}
+
 #[async_trait::async_trait]
 pub trait ToolMiddleware: Send + Sync {
     async fn before_tool(&self, call: &ToolCallRequest<'_>) -> ToolDecision;
@@ -728,6 +1347,8 @@ impl fmt::Display for StopReason {
             Self::Error => write!(f, "error"),
             Self::Aborted => write!(f, "aborted"),
             Self::Refusal => write!(f, "refusal"),
+            Self::StopSequence => write!(f, "stopSequence"),
+            Self::PauseTurn => write!(f, "pauseTurn"),
         }
     }
 }
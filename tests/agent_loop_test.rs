@@ -5,10 +5,10 @@ use tokio_util::sync::CancellationToken;
 use yoagent::agent_loop::{agent_loop, agent_loop_continue, AgentLoopConfig};
 use yoagent::context::ExecutionLimits;
 use yoagent::provider::mock::*;
-use yoagent::provider::MockProvider;
+use yoagent::provider::{MockProvider, ResponseFormat, ToolChoice};
 use yoagent::*;
 
-fn make_config(provider: MockProvider) -> AgentLoopConfig {
+fn make_config(provider: impl StreamProvider + 'static) -> AgentLoopConfig {
     AgentLoopConfig {
         provider: std::sync::Arc::new(provider),
         model: "mock".into(),
@@ -26,13 +26,32 @@ fn make_config(provider: MockProvider) -> AgentLoopConfig {
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::default(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     }
 }
@@ -76,11 +95,14 @@ async fn test_simple_text_response() {
             AgentEvent::MessageStart { .. } => "MessageStart",
             AgentEvent::MessageEnd { .. } => "MessageEnd",
             AgentEvent::MessageUpdate { .. } => "MessageUpdate",
+            AgentEvent::ToolCallArgsUpdate { .. } => "ToolCallArgsUpdate",
+            AgentEvent::ToolApprovalRequired { .. } => "ToolApprovalRequired",
             AgentEvent::ToolExecutionStart { .. } => "ToolExecStart",
             AgentEvent::ToolExecutionUpdate { .. } => "ToolExecUpdate",
             AgentEvent::ToolExecutionEnd { .. } => "ToolExecEnd",
             AgentEvent::ProgressMessage { .. } => "ProgressMessage",
             AgentEvent::InputRejected { .. } => "InputRejected",
+            AgentEvent::ContextCompacted { .. } => "ContextCompacted",
         })
         .collect();
 
@@ -172,11 +194,14 @@ async fn test_tool_call_and_response() {
             AgentEvent::MessageStart { .. } => "MessageStart",
             AgentEvent::MessageEnd { .. } => "MessageEnd",
             AgentEvent::MessageUpdate { .. } => "MessageUpdate",
+            AgentEvent::ToolCallArgsUpdate { .. } => "ToolCallArgsUpdate",
+            AgentEvent::ToolApprovalRequired { .. } => "ToolApprovalRequired",
             AgentEvent::ToolExecutionStart { .. } => "ToolExecStart",
             AgentEvent::ToolExecutionUpdate { .. } => "ToolExecUpdate",
             AgentEvent::ToolExecutionEnd { .. } => "ToolExecEnd",
             AgentEvent::ProgressMessage { .. } => "ProgressMessage",
             AgentEvent::InputRejected { .. } => "InputRejected",
+            AgentEvent::ContextCompacted { .. } => "ContextCompacted",
         })
         .collect();
 
@@ -227,6 +252,17 @@ async fn test_continue_from_tool_result() {
         system_prompt: "test".into(),
         messages: vec![
             AgentMessage::Llm(Message::user("do something")),
+            AgentMessage::Llm(Message::assistant(
+                vec![Content::tool_call(
+                    "tc-1",
+                    "test_tool",
+                    serde_json::json!({}),
+                )],
+                StopReason::ToolUse,
+                "mock",
+                "mock",
+                Usage::default(),
+            )),
             AgentMessage::Llm(Message::ToolResult {
                 tool_call_id: "tc-1".into(),
                 tool_name: "test_tool".into(),
@@ -249,6 +285,86 @@ async fn test_continue_from_tool_result() {
     assert_eq!(new_messages[0].role(), "assistant");
 }
 
+#[tokio::test]
+async fn test_checkpoint_and_resume_after_crash() {
+    use std::sync::{Arc, Mutex};
+    use yoagent::agent_loop::agent_loop_resume;
+
+    let step = |n: u32| MockToolCall {
+        provider_metadata: None,
+        name: "step".into(),
+        arguments: serde_json::json!({ "n": n }),
+    };
+    let tools = || -> Vec<Box<dyn AgentTool>> {
+        vec![Box::new(TimedTool {
+            name: "step".into(),
+            delay_ms: 0,
+        })]
+    };
+
+    // First process: checkpoints after each turn, "crashes" after turn 1.
+    let saved: Arc<Mutex<Option<(String, usize)>>> = Arc::default();
+    let crash = CancellationToken::new();
+    let mut config = make_config(MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![step(1)]),
+        MockResponse::ToolCalls(vec![step(2)]),
+        MockResponse::Text("all done".into()),
+    ]));
+    config.checkpoint = Some({
+        let saved = saved.clone();
+        let crash = crash.clone();
+        Arc::new(move |context: &AgentContext, turn: usize| {
+            let json = serde_json::to_string(&context.messages).unwrap();
+            *saved.lock().unwrap() = Some((json, turn));
+            crash.cancel();
+        })
+    });
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: tools(),
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        crash,
+    )
+    .await;
+    drop(context);
+
+    // Second process: restore the checkpoint and finish the run. Only the
+    // work after turn 1 is left for the provider.
+    let (json, turn) = saved.lock().unwrap().take().unwrap();
+    assert_eq!(turn, 1);
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: serde_json::from_str(&json).unwrap(),
+        tools: tools(),
+    };
+    assert_eq!(context.messages.len(), 3, "prompt, tool call, tool result");
+
+    let turns = Arc::new(Mutex::new(Vec::new()));
+    let mut config = make_config(MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![step(2)]),
+        MockResponse::Text("all done".into()),
+    ]));
+    config.checkpoint = Some({
+        let turns = turns.clone();
+        Arc::new(move |_: &AgentContext, turn: usize| turns.lock().unwrap().push(turn))
+    });
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages =
+        agent_loop_resume(&mut context, turn, &config, tx, CancellationToken::new()).await;
+
+    assert_eq!(*turns.lock().unwrap(), vec![2, 3]);
+    assert_eq!(new_messages.len(), 3, "tool call, tool result, reply");
+    assert_eq!(context.messages.len(), 6);
+    assert_eq!(reply_text(context.messages.last().unwrap()), "all done");
+}
+
 #[tokio::test]
 async fn test_tool_error_is_reported() {
     let provider = MockProvider::new(vec![
@@ -597,6 +713,177 @@ async fn test_batched_tool_execution() {
     );
 }
 
+#[tokio::test]
+async fn test_throttled_parallel_tool_execution() {
+    // 6 tools, at most 2 at a time: three waves of 50ms
+    let names = ["tool_a", "tool_b", "tool_c", "tool_d", "tool_e", "tool_f"];
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(
+            names
+                .iter()
+                .map(|name| MockToolCall {
+                    provider_metadata: None,
+                    name: name.to_string(),
+                    arguments: serde_json::json!({}),
+                })
+                .collect(),
+        ),
+        MockResponse::Text("All done.".into()),
+    ]);
+
+    let mut config = make_config(provider);
+    config.tool_execution = ToolExecutionStrategy::ThrottledParallel { limit: 2 };
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: names
+            .iter()
+            .map(|name| {
+                Box::new(TimedTool {
+                    name: name.to_string(),
+                    delay_ms: 50,
+                }) as Box<dyn AgentTool>
+            })
+            .collect(),
+    };
+
+    let prompt = AgentMessage::Llm(Message::user("Run all tools"));
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let cancel = CancellationToken::new();
+
+    let start = std::time::Instant::now();
+    let new_messages = agent_loop(vec![prompt], &mut context, &config, tx, cancel).await;
+    let elapsed = start.elapsed();
+
+    // All 6 results present, in call order
+    let tool_names: Vec<_> = new_messages
+        .iter()
+        .filter_map(|m| match m {
+            AgentMessage::Llm(Message::ToolResult { tool_name, .. }) => Some(tool_name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(tool_names, names);
+
+    // 3 waves × 50ms = ~150ms (not 300ms sequential, not 50ms unbounded)
+    assert!(
+        elapsed.as_millis() >= 140 && elapsed.as_millis() < 250,
+        "Throttled execution took {}ms, expected 140-250ms",
+        elapsed.as_millis()
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Output filter tests
+// ---------------------------------------------------------------------------
+
+fn reply_text(message: &AgentMessage) -> String {
+    match message {
+        AgentMessage::Llm(Message::Assistant { content, .. }) => content
+            .iter()
+            .filter_map(|c| match c {
+                Content::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect(),
+        other => panic!("expected an assistant message, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_output_filter_redacts_before_context_and_message_end() {
+    let provider = MockProvider::text("Your card is 4111111111111111, email a@b.io.");
+    let mut config = make_config(provider);
+    config.output_filters.push(std::sync::Arc::new(
+        yoagent::filters::PiiRedactionOutputFilter::new(),
+    ));
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("What's my card?"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let expected = "Your card is [REDACTED], email [REDACTED].";
+    assert_eq!(reply_text(&context.messages[1]), expected);
+    let ends: Vec<_> = collect_events(rx)
+        .into_iter()
+        .filter_map(|e| match e {
+            AgentEvent::MessageEnd { message } => Some(message),
+            _ => None,
+        })
+        .collect();
+    // The user prompt's MessageEnd, then exactly one for the reply
+    assert_eq!(ends.len(), 2);
+    assert_eq!(reply_text(&ends[1]), expected);
+}
+
+#[tokio::test]
+async fn test_output_filter_block_replaces_tool_calls() {
+    struct BlockTools;
+    impl OutputFilter for BlockTools {
+        fn filter(&self, message: &Message) -> OutputFilterResult {
+            match message {
+                Message::Assistant { content, .. }
+                    if content
+                        .iter()
+                        .any(|c| matches!(c, Content::ToolCall { .. })) =>
+                {
+                    OutputFilterResult::Block("Tool use is disabled.".into())
+                }
+                _ => OutputFilterResult::Pass,
+            }
+        }
+    }
+
+    let provider = MockProvider::new(vec![MockResponse::ToolCalls(vec![MockToolCall {
+        provider_metadata: None,
+        name: "tool_a".into(),
+        arguments: serde_json::json!({}),
+    }])]);
+    let mut config = make_config(provider);
+    config.output_filters.push(std::sync::Arc::new(BlockTools));
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(TimedTool {
+            name: "tool_a".into(),
+            delay_ms: 0,
+        })],
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("Run it"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    // The blocked call never runs; the turn ends with the substitute text
+    assert_eq!(new_messages.len(), 2);
+    assert_eq!(reply_text(&new_messages[1]), "Tool use is disabled.");
+    assert!(matches!(
+        &new_messages[1],
+        AgentMessage::Llm(Message::Assistant {
+            stop_reason: StopReason::Stop,
+            ..
+        })
+    ));
+}
+
 // ---------------------------------------------------------------------------
 // Streaming tool output (on_update callback) tests
 // ---------------------------------------------------------------------------
@@ -768,16 +1055,37 @@ async fn test_execution_limit_counts_cached_tokens() {
             max_turns: 50,
             max_total_tokens: 100,
             max_duration: std::time::Duration::from_secs(60),
+            turn_timeout: None,
+            max_cost_usd: None,
         }),
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::default(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     };
 
@@ -815,6 +1123,59 @@ async fn test_execution_limit_counts_cached_tokens() {
     }));
 }
 
+#[tokio::test]
+async fn test_execution_limit_stops_at_max_cost() {
+    let provider = std::sync::Arc::new(UsageProvider {
+        usage: Usage {
+            input: 1_000_000,
+            total_tokens: 1_000_000,
+            ..Default::default()
+        },
+        calls: std::sync::atomic::AtomicUsize::new(0),
+    });
+    let mut config = make_config(MockProvider::text("unused"));
+    config.provider = provider.clone();
+    // $2.50 per million input tokens: each turn costs $2.50.
+    let mut model_config = yoagent::provider::ModelConfig::openai("gpt-4o", "GPT-4o");
+    model_config.cost = yoagent::provider::CostConfig::for_model("gpt-4o").unwrap();
+    config.model_config = Some(model_config);
+    config.get_follow_up_messages = Some(Box::new(|| {
+        vec![AgentMessage::Llm(Message::user("follow up"))]
+    }));
+    config.execution_limits = Some(ExecutionLimits {
+        max_total_tokens: usize::MAX,
+        max_cost_usd: Some(5.0),
+        ..ExecutionLimits::default()
+    });
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("start"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(provider.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    assert!(new_messages.iter().any(|msg| {
+        matches!(
+            msg,
+            AgentMessage::Llm(Message::User { content, .. })
+                if content.iter().any(|c| matches!(
+                    c,
+                    Content::Text { text } if text == "[Agent stopped: Max cost reached ($5.00/$5.00)]"
+                ))
+        )
+    }));
+}
+
 #[async_trait::async_trait]
 impl StreamProvider for FailThenSucceedProvider {
     async fn stream(
@@ -869,18 +1230,38 @@ async fn test_retry_on_rate_limit_succeeds() {
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
+        circuit_breaker: None,
+        rate_limiter: None,
         retry_config: yoagent::RetryConfig {
             max_retries: 3,
             initial_delay_ms: 10,
             backoff_multiplier: 2.0,
             max_delay_ms: 100,
+            jitter_factor: 0.2,
         },
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     };
 
@@ -939,18 +1320,38 @@ async fn test_retry_exhausted_returns_error() {
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
+        circuit_breaker: None,
+        rate_limiter: None,
         retry_config: yoagent::RetryConfig {
             max_retries: 2,
             initial_delay_ms: 10,
             backoff_multiplier: 2.0,
             max_delay_ms: 100,
+            jitter_factor: 0.2,
         },
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     };
 
@@ -1016,13 +1417,32 @@ async fn test_no_retry_on_auth_error() {
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::default(), // 3 retries, but auth is not retryable
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     };
 
@@ -1076,13 +1496,32 @@ async fn test_retry_none_disables_retries() {
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::none(), // disabled
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     };
 
@@ -1107,6 +1546,111 @@ async fn test_retry_none_disables_retries() {
     );
 }
 
+#[tokio::test(start_paused = true)]
+async fn test_rate_limiter_spaces_turns_at_configured_rpm() {
+    let limiter = std::sync::Arc::new(RateLimiter::new().with_requests_per_minute(4));
+    let mut config = make_config(MockProvider::texts(vec!["ok"; 7]));
+    config.rate_limiter = Some(limiter);
+
+    let start = tokio::time::Instant::now();
+    let mut finished = Vec::new();
+    for _ in 0..7 {
+        let mut context = AgentContext {
+            system_prompt: "test".into(),
+            messages: Vec::new(),
+            tools: Vec::new(),
+        };
+        let (tx, _rx) = mpsc::unbounded_channel();
+        agent_loop(
+            vec![AgentMessage::Llm(Message::user("hi"))],
+            &mut context,
+            &config,
+            tx,
+            CancellationToken::new(),
+        )
+        .await;
+        finished.push(start.elapsed().as_secs_f64());
+    }
+
+    // A burst of 4, then one call every 60s / 4 = 15s.
+    let expected = [0.0, 0.0, 0.0, 0.0, 15.0, 30.0, 45.0];
+    for (at, want) in finished.iter().zip(expected) {
+        assert!((at - want).abs() < 0.1, "finished at {:?}", finished);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_circuit_breaker_short_circuits_and_recovers() {
+    let provider = std::sync::Arc::new(FailThenSucceedProvider {
+        fail_count: std::sync::atomic::AtomicUsize::new(0),
+        max_failures: 3,
+        error: ProviderError::Network("connection refused".into()),
+        inner: MockProvider::text("back up"),
+    });
+    let breaker = std::sync::Arc::new(CircuitBreaker::new(3, std::time::Duration::from_secs(60)));
+    let mut config = make_config(MockProvider::text("unused"));
+    config.provider = provider.clone();
+    config.circuit_breaker = Some(breaker.clone());
+    config.retry_config = yoagent::RetryConfig {
+        max_retries: 5,
+        initial_delay_ms: 10,
+        backoff_multiplier: 1.0,
+        max_delay_ms: 10,
+        jitter_factor: 0.0,
+    };
+    let calls = || {
+        provider
+            .fail_count
+            .load(std::sync::atomic::Ordering::SeqCst)
+    };
+    async fn run(config: &AgentLoopConfig) -> (StopReason, Option<String>, Vec<Content>) {
+        let mut context = AgentContext {
+            system_prompt: "test".into(),
+            messages: Vec::new(),
+            tools: Vec::new(),
+        };
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let messages = agent_loop(
+            vec![AgentMessage::Llm(Message::user("hi"))],
+            &mut context,
+            config,
+            tx,
+            CancellationToken::new(),
+        )
+        .await;
+        match messages.last() {
+            Some(AgentMessage::Llm(Message::Assistant {
+                stop_reason,
+                error_message,
+                content,
+                ..
+            })) => (stop_reason.clone(), error_message.clone(), content.clone()),
+            other => panic!("expected an assistant message, got {:?}", other),
+        }
+    }
+
+    // Three failures trip the breaker; the remaining retries are skipped.
+    let (stop, error, _) = run(&config).await;
+    assert_eq!(stop, StopReason::Error);
+    assert!(error.unwrap().contains("Circuit breaker open"));
+    assert_eq!(calls(), 3);
+    assert_eq!(breaker.state(), yoagent::retry::CircuitState::Open);
+
+    // While open, turns fail without touching the provider.
+    let (stop, error, _) = run(&config).await;
+    assert_eq!(stop, StopReason::Error);
+    assert!(error.unwrap().contains("Circuit breaker open"));
+    assert_eq!(calls(), 3);
+
+    // After the cooldown, one trial call goes through and closes it.
+    tokio::time::advance(std::time::Duration::from_secs(60)).await;
+    let (stop, _, content) = run(&config).await;
+    assert_eq!(stop, StopReason::Stop);
+    assert!(matches!(&content[0], Content::Text { text } if text == "back up"));
+    assert_eq!(calls(), 4);
+    assert_eq!(breaker.state(), yoagent::retry::CircuitState::Closed);
+}
+
 // ---------------------------------------------------------------------------
 // Event streaming bug fix test
 // ---------------------------------------------------------------------------
@@ -1243,6 +1787,69 @@ async fn test_before_turn_can_abort() {
     assert_eq!(assistant_count, 2);
 }
 
+#[tokio::test]
+async fn test_stop_if_ends_the_run_after_the_matching_turn() {
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "progress_tool".into(),
+            arguments: serde_json::json!({}),
+        }]),
+        MockResponse::Text("All DONE.".into()),
+        // Only reached if the follow-up below gets through.
+        MockResponse::Text("Still here.".into()),
+    ]);
+
+    let mut config = make_config(provider);
+    // Without stop_if, this follow-up would start a third turn.
+    config.get_follow_up_messages = Some(Box::new(|| {
+        vec![AgentMessage::Llm(Message::user("Anything else?"))]
+    }));
+    config.stop_if = Some(std::sync::Arc::new(|messages: &[AgentMessage]| {
+        messages.iter().any(|m| match m {
+            AgentMessage::Llm(Message::Assistant { content, .. }) => content
+                .iter()
+                .any(|c| matches!(c, Content::Text { text } if text.contains("DONE"))),
+            _ => false,
+        })
+    }));
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(ProgressTool)],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let events = collect_events(rx);
+    let turn_ends = events
+        .iter()
+        .filter(|e| matches!(e, AgentEvent::TurnEnd { .. }))
+        .count();
+    assert_eq!(turn_ends, 2);
+    assert!(matches!(
+        &events[events.len() - 2..],
+        [AgentEvent::TurnEnd { .. }, AgentEvent::AgentEnd { .. }]
+    ));
+    let last = new_messages.last().unwrap();
+    assert_eq!(reply_text(last), "All DONE.");
+    assert_eq!(
+        new_messages
+            .iter()
+            .filter(|m| m.role() == "assistant")
+            .count(),
+        2
+    );
+}
+
 #[tokio::test]
 async fn test_after_turn_receives_messages() {
     let provider = MockProvider::new(vec![
@@ -1312,15 +1919,34 @@ async fn test_on_error_fires_on_provider_error() {
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::none(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: Some(std::sync::Arc::new(move |err| {
             error_msgs_clone.lock().unwrap().push(err.to_string());
         })),
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     };
 
@@ -1778,6 +2404,57 @@ async fn test_filter_reject_returns_empty() {
         .any(|e| matches!(e, AgentEvent::AgentEnd { messages } if messages.is_empty())));
 }
 
+struct AsyncRejectFilter {
+    reason: String,
+}
+#[async_trait::async_trait]
+impl AsyncInputFilter for AsyncRejectFilter {
+    async fn filter(&self, _text: &str) -> FilterResult {
+        tokio::time::sleep(std::time::Duration::from_millis(1)).await;
+        FilterResult::Reject(self.reason.clone())
+    }
+}
+
+#[tokio::test]
+async fn test_async_filter_reject_returns_empty() {
+    let provider = MockProvider::text("Should not reach");
+    let mut config = make_config(provider);
+    // Sync and async filters share the chain; the sync one runs first.
+    config.input_filters = vec![
+        Arc::new(PassFilter),
+        Arc::new(AsyncRejectFilter {
+            reason: "moderation api: flagged".into(),
+        }),
+    ];
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+
+    let prompt = AgentMessage::Llm(Message::user("Bad input"));
+    let (tx, rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![prompt],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+    let events = collect_events(rx);
+
+    assert!(new_messages.is_empty());
+    assert!(context.messages.is_empty());
+    assert!(events.iter().any(
+        |e| matches!(e, AgentEvent::InputRejected { reason } if reason == "moderation api: flagged")
+    ));
+    assert!(!events
+        .iter()
+        .any(|e| matches!(e, AgentEvent::MessageStart { .. })));
+}
+
 #[tokio::test]
 async fn test_filter_chain_first_reject_wins() {
     let provider = MockProvider::text("Should not reach");
@@ -1952,6 +2629,7 @@ async fn test_default_compaction_matches_compact_messages() {
         keep_recent: 5,
         keep_first: 2,
         tool_output_max_lines: 20,
+        model: None,
     };
 
     let result_direct = compact_messages(messages.clone(), &config);
@@ -2015,18 +2693,38 @@ async fn test_custom_compaction_strategy_is_called() {
             keep_recent: 1,
             keep_first: 1,
             tool_output_max_lines: 10,
+            model: None,
         }),
         compaction_strategy: Some(std::sync::Arc::new(MarkerCompaction)),
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::none(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     };
 
@@ -2093,18 +2791,38 @@ async fn test_none_compaction_strategy_uses_default() {
             keep_recent: 1,
             keep_first: 1,
             tool_output_max_lines: 10,
+            model: None,
         }),
         compaction_strategy: None, // Should fall back to DefaultCompaction
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::none(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     };
 
@@ -2128,18 +2846,345 @@ async fn test_none_compaction_strategy_uses_default() {
     );
 }
 
-/// Tool calls with provider_metadata (e.g. Gemini thought signatures)
-/// must still be executed by the agent loop.
-#[tokio::test]
-async fn test_tool_call_with_provider_metadata_executes() {
-    let provider = MockProvider::new(vec![
-        MockResponse::ToolCalls(vec![MockToolCall {
-            name: "echo_tool".into(),
-            arguments: serde_json::json!({"text": "hello"}),
-            provider_metadata: Some(serde_json::json!({"thought_signature": "SIG_DATA"})),
-        }]),
-        MockResponse::Text("done".into()),
-    ]);
+/// 30 alternating user/assistant messages of ~1k tokens each.
+fn oversized_context() -> AgentContext {
+    AgentContext {
+        system_prompt: String::new(),
+        messages: (0..30)
+            .map(|i| {
+                let text = format!("{} {}", i, "x".repeat(4_000));
+                AgentMessage::Llm(if i % 2 == 0 {
+                    Message::user(text)
+                } else {
+                    Message::assistant(
+                        vec![Content::Text { text }],
+                        StopReason::Stop,
+                        "mock",
+                        "mock",
+                        Usage::default(),
+                    )
+                })
+            })
+            .collect(),
+        tools: vec![],
+    }
+}
+
+/// Every `ContextCompacted` event received so far, in order.
+fn compaction_events(
+    rx: &mut mpsc::UnboundedReceiver<AgentEvent>,
+) -> Vec<(usize, usize, usize, usize, CompactionLevel)> {
+    std::iter::from_fn(|| rx.try_recv().ok())
+        .filter_map(|e| match e {
+            AgentEvent::ContextCompacted {
+                messages_before,
+                messages_after,
+                tokens_before,
+                tokens_after,
+                level,
+            } => Some((
+                messages_before,
+                messages_after,
+                tokens_before,
+                tokens_after,
+                level,
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_compaction_before_a_turn_is_reported() {
+    use yoagent::context::ContextConfig;
+
+    let reported = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let config = yoagent::agent_loop::AgentLoopConfigBuilder::new(
+        Arc::new(MockProvider::text("Done.")),
+        "mock",
+        "test",
+    )
+    .with_context_config(ContextConfig {
+        max_context_tokens: 5_000,
+        system_prompt_tokens: 0,
+        keep_recent: 4,
+        keep_first: 1,
+        tool_output_max_lines: 10,
+        model: None,
+    })
+    .with_on_context_compact({
+        let reported = reported.clone();
+        move |before, after| reported.lock().unwrap().push((before, after))
+    })
+    .build();
+
+    let mut context = oversized_context();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("next"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    // No tool outputs to truncate, and one-line summaries of plain text
+    // turns aren't enough: the middle of the history goes.
+    let events = compaction_events(&mut rx);
+    assert_eq!(events.len(), 1, "{:?}", events);
+    let (messages_before, messages_after, tokens_before, tokens_after, level) = events[0];
+    assert_eq!(messages_before, 31);
+    // keep_first + "[Context compacted: ...]" marker + keep_recent.
+    assert_eq!(messages_after, 1 + 1 + 4);
+    assert!(tokens_before > 5_000 && tokens_after <= 5_000);
+    assert_eq!(level, CompactionLevel::Level3);
+    assert_eq!(
+        *reported.lock().unwrap(),
+        vec![(tokens_before, tokens_after)]
+    );
+    // The stored history was compacted, then the reply appended.
+    assert_eq!(context.messages.len(), messages_after + 1);
+}
+
+#[tokio::test]
+async fn test_oversized_request_is_compacted_before_the_call() {
+    use yoagent::context::ContextConfig;
+
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let provider = ConfigRecordingProvider {
+        inner: MockProvider::text("Done."),
+        seen: seen.clone(),
+    };
+    // The stored history is small; `transform_context` runs after the
+    // loop's compaction and grows the request to ~30k tokens, so only the
+    // final safety pass stands between it and a 5k window.
+    let config =
+        yoagent::agent_loop::AgentLoopConfigBuilder::new(Arc::new(provider), "mock", "test")
+            .with_context_config(ContextConfig {
+                max_context_tokens: 5_000,
+                system_prompt_tokens: 0,
+                keep_recent: 4,
+                keep_first: 1,
+                tool_output_max_lines: 10,
+                model: None,
+            })
+            .with_transform_context(|messages| {
+                let mut grown = oversized_context().messages;
+                grown.extend(messages);
+                grown
+            })
+            .build();
+
+    let mut context = AgentContext {
+        system_prompt: String::new(),
+        messages: vec![],
+        tools: vec![],
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("next"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let events = compaction_events(&mut rx);
+    assert_eq!(events.len(), 1, "{:?}", events);
+    let (_, _, before, after, _) = events[0];
+    assert!(before > 5_000 && after <= 5_000, "{} -> {}", before, after);
+
+    // Only the request shrank; the stored history is intact.
+    let sent = seen.lock().unwrap()[0].messages.len();
+    assert!(sent < 31, "sent {} messages", sent);
+    assert_eq!(context.messages.len(), 2);
+}
+
+#[tokio::test]
+async fn test_safety_pass_keeps_pinned_messages() {
+    use yoagent::context::{pinned, ContextConfig};
+
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let provider = ConfigRecordingProvider {
+        inner: MockProvider::text("Done."),
+        seen: seen.clone(),
+    };
+    // The pinned rule is only added by `transform_context`, in the middle
+    // of a request that the safety pass has to cut down.
+    let config =
+        yoagent::agent_loop::AgentLoopConfigBuilder::new(Arc::new(provider), "mock", "test")
+            .with_context_config(ContextConfig {
+                max_context_tokens: 5_000,
+                system_prompt_tokens: 0,
+                keep_recent: 4,
+                keep_first: 1,
+                tool_output_max_lines: 10,
+                model: None,
+            })
+            .with_transform_context(|messages| {
+                let mut grown = oversized_context().messages;
+                grown.splice(15..15, pinned(Message::user("RULE: answer in French")));
+                grown.extend(messages);
+                grown
+            })
+            .build();
+
+    let mut context = AgentContext {
+        system_prompt: String::new(),
+        messages: vec![],
+        tools: vec![],
+    };
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("next"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(compaction_events(&mut rx)[0].4, CompactionLevel::Level3);
+    let sent = &seen.lock().unwrap()[0].messages;
+    assert!(sent.len() < 31, "sent {} messages", sent.len());
+    assert!(
+        sent.iter().any(|m| matches!(
+            m.content(),
+            [Content::Text { text }] if text == "RULE: answer in French"
+        )),
+        "{:#?}",
+        sent
+    );
+}
+
+#[tokio::test]
+async fn test_safety_pass_uses_the_configured_strategy() {
+    use yoagent::context::ContextConfig;
+
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let provider = ConfigRecordingProvider {
+        inner: MockProvider::text("Done."),
+        seen: seen.clone(),
+    };
+    // NoopCompaction turns compaction off, the safety pass included.
+    let config =
+        yoagent::agent_loop::AgentLoopConfigBuilder::new(Arc::new(provider), "mock", "test")
+            .with_context_config(ContextConfig {
+                max_context_tokens: 5_000,
+                system_prompt_tokens: 0,
+                keep_recent: 4,
+                keep_first: 1,
+                tool_output_max_lines: 10,
+                model: None,
+            })
+            .with_compaction_strategy(yoagent::NoopCompaction)
+            .build();
+
+    let mut context = oversized_context();
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("next"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert!(compaction_events(&mut rx).is_empty());
+    assert_eq!(seen.lock().unwrap()[0].messages.len(), 31);
+    assert_eq!(context.messages.len(), 32);
+}
+
+#[tokio::test]
+async fn test_continue_after_level3_compaction_of_tool_turns() {
+    use yoagent::context::{message_tokens, ContextConfig};
+
+    // Turns of two parallel tool calls with ~1k-token results: both cut
+    // points fall between a call and its results.
+    let mut messages = vec![AgentMessage::Llm(Message::user("go"))];
+    for i in 0..8 {
+        let ids = [format!("a{}", i), format!("b{}", i)];
+        messages.push(AgentMessage::Llm(Message::assistant(
+            ids.iter()
+                .map(|id| Content::tool_call(id.as_str(), "bash", serde_json::json!({})))
+                .collect(),
+            StopReason::ToolUse,
+            "mock",
+            "mock",
+            Usage::default(),
+        )));
+        messages.extend(ids.into_iter().map(|id| {
+            AgentMessage::Llm(Message::ToolResult {
+                tool_call_id: id,
+                tool_name: "bash".into(),
+                content: vec![Content::Text {
+                    text: "x".repeat(4_000),
+                }],
+                is_error: false,
+                timestamp: 0,
+            })
+        }));
+    }
+    messages.push(AgentMessage::Llm(Message::user("next")));
+
+    // Less than the two results Level 2 keeps, whatever the estimator.
+    let config = yoagent::agent_loop::AgentLoopConfigBuilder::new(
+        Arc::new(MockProvider::texts(vec!["First.", "Second."])),
+        "mock",
+        "test",
+    )
+    .with_context_config(ContextConfig {
+        max_context_tokens: message_tokens(&messages[2]) * 3 / 2,
+        system_prompt_tokens: 0,
+        keep_recent: 2,
+        keep_first: 3,
+        tool_output_max_lines: 10,
+        model: None,
+    })
+    .build();
+    let mut context = AgentContext {
+        system_prompt: String::new(),
+        messages,
+        tools: vec![],
+    };
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    agent_loop_continue(&mut context, &config, tx, CancellationToken::new()).await;
+    let events = compaction_events(&mut rx);
+    assert_eq!(events[0].4, CompactionLevel::Level3, "{:?}", events);
+
+    // The compacted history the loop stored is one it can continue from.
+    context
+        .messages
+        .push(AgentMessage::Llm(Message::user("again")));
+    assert_eq!(context.validate(), Ok(()));
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages =
+        agent_loop_continue(&mut context, &config, tx, CancellationToken::new()).await;
+    assert_eq!(
+        new_messages.last().unwrap().as_llm().unwrap().content(),
+        [Content::Text {
+            text: "Second.".into()
+        }]
+    );
+}
+
+/// Tool calls with provider_metadata (e.g. Gemini thought signatures)
+/// must still be executed by the agent loop.
+#[tokio::test]
+async fn test_tool_call_with_provider_metadata_executes() {
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            name: "echo_tool".into(),
+            arguments: serde_json::json!({"text": "hello"}),
+            provider_metadata: Some(serde_json::json!({"thought_signature": "SIG_DATA"})),
+        }]),
+        MockResponse::Text("done".into()),
+    ]);
 
     struct EchoTool;
 
@@ -2271,22 +3316,44 @@ fn calibration_config(
             keep_recent: 1,
             keep_first: 1,
             tool_output_max_lines: 10,
+            model: None,
         }),
         compaction_strategy: Some(strategy),
         execution_limits: Some(ExecutionLimits {
             max_turns: 2,
             max_total_tokens: 1_000_000,
             max_duration: std::time::Duration::from_secs(60),
+            turn_timeout: None,
+            max_cost_usd: None,
         }),
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::none(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     }
 }
@@ -2358,3 +3425,2258 @@ async fn test_calibration_floor_prevents_budget_collapse() {
     assert_eq!(calls[0], (4000, 500));
     assert_eq!(calls[1], (400, 0));
 }
+
+// ---------------------------------------------------------------------------
+// AgentLoopConfigBuilder
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_config_builder_defaults_match_struct_literal() {
+    use yoagent::agent_loop::AgentLoopConfigBuilder;
+
+    let built = AgentLoopConfigBuilder::new(
+        std::sync::Arc::new(MockProvider::text("hi")),
+        "mock",
+        "test",
+    )
+    .build();
+    let manual = make_config(MockProvider::text("hi"));
+
+    assert_eq!(built.model, manual.model);
+    assert_eq!(built.api_key, manual.api_key);
+    assert_eq!(built.thinking_level, manual.thinking_level);
+    assert_eq!(built.max_tokens, manual.max_tokens);
+    assert_eq!(built.temperature, manual.temperature);
+    assert!(built.model_config.is_none());
+    assert!(built.convert_to_llm.is_none());
+    assert!(built.transform_context.is_none());
+    assert!(built.get_steering_messages.is_none());
+    assert!(built.get_follow_up_messages.is_none());
+    assert!(built.context_config.is_none());
+    assert!(built.compaction_strategy.is_none());
+    assert!(built.execution_limits.is_none());
+    assert_eq!(built.cache_config, manual.cache_config);
+    assert_eq!(built.tool_execution, manual.tool_execution);
+    assert!(built.tool_middleware.is_empty());
+    assert!(built.output_schema.is_none());
+    assert_eq!(
+        built.retry_config.max_retries,
+        manual.retry_config.max_retries
+    );
+    assert!(built.before_turn.is_none());
+    assert!(built.after_turn.is_none());
+    assert!(built.on_error.is_none());
+    assert!(built.input_filters.is_empty());
+    assert!(built.turn_delay.is_none());
+}
+
+#[tokio::test]
+async fn test_config_builder_wires_closures() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use yoagent::agent_loop::AgentLoopConfigBuilder;
+
+    let after_calls = Arc::new(AtomicUsize::new(0));
+    let after_calls_clone = after_calls.clone();
+    let transformed = Arc::new(AtomicUsize::new(0));
+    let transformed_clone = transformed.clone();
+
+    let config =
+        AgentLoopConfigBuilder::new(Arc::new(MockProvider::text("Hello!")), "mock", "test")
+            .with_thinking_level(ThinkingLevel::Medium)
+            .with_max_tokens(512)
+            .with_tool_execution(ToolExecutionStrategy::Sequential)
+            .with_transform_context(move |msgs| {
+                transformed_clone.fetch_add(1, Ordering::SeqCst);
+                msgs
+            })
+            .with_after_turn(move |_msgs, _usage| {
+                after_calls_clone.fetch_add(1, Ordering::SeqCst);
+            })
+            .build();
+
+    assert_eq!(config.thinking_level, ThinkingLevel::Medium);
+    assert_eq!(config.max_tokens, Some(512));
+    assert_eq!(config.tool_execution, ToolExecutionStrategy::Sequential);
+
+    let mut context = AgentContext {
+        system_prompt: String::new(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("Hi"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(new_messages.len(), 2);
+    assert_eq!(transformed.load(Ordering::SeqCst), 1);
+    assert_eq!(after_calls.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_config_builder_from_provider_with_model_and_key() {
+    let config = AgentLoopConfig::builder(std::sync::Arc::new(MockProvider::text("Hello!")))
+        .with_model("mock")
+        .with_api_key("test")
+        .build();
+
+    assert_eq!(config.model, "mock");
+    assert_eq!(config.api_key, "test");
+    assert_eq!(config.thinking_level, ThinkingLevel::Off);
+    assert!(config.input_filters.is_empty());
+
+    let mut context = AgentContext {
+        system_prompt: String::new(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("Hi"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(new_messages.len(), 2);
+    assert_eq!(new_messages[1].role(), "assistant");
+    let events = collect_events(rx);
+    assert!(matches!(events.last(), Some(AgentEvent::AgentEnd { .. })));
+}
+
+// ---------------------------------------------------------------------------
+// Tool timeout tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_tool_timeout_reports_error_and_loop_continues() {
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "slow".into(),
+            arguments: serde_json::json!({}),
+        }]),
+        MockResponse::Text("Gave up on it.".into()),
+    ]);
+
+    let mut config = make_config(provider);
+    config.tool_timeout = Some(std::time::Duration::from_millis(50));
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(TimedTool {
+            name: "slow".into(),
+            delay_ms: 5_000,
+        })],
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let start = std::time::Instant::now();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("Run it"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+    let elapsed = start.elapsed();
+
+    assert!(
+        elapsed.as_millis() < 1_000,
+        "timed-out tool held the loop for {}ms",
+        elapsed.as_millis()
+    );
+
+    let events = collect_events(rx);
+    let end = events
+        .iter()
+        .find_map(|e| match e {
+            AgentEvent::ToolExecutionEnd {
+                result, is_error, ..
+            } => Some((result.clone(), *is_error)),
+            _ => None,
+        })
+        .expect("ToolExecutionEnd should still fire on timeout");
+    assert!(end.1);
+    assert_eq!(
+        end.0.content,
+        vec![Content::Text {
+            text: "Tool 'slow' timed out after 0.05s".into()
+        }]
+    );
+
+    // The LLM got the error result and the loop finished normally.
+    let last = new_messages.last().unwrap();
+    assert_eq!(last.role(), "assistant");
+}
+
+/// Sleeps for `delay_ms` and declares its own deadline.
+struct DeadlineTool {
+    delay_ms: u64,
+    timeout: Option<std::time::Duration>,
+}
+
+#[async_trait::async_trait]
+impl AgentTool for DeadlineTool {
+    fn name(&self) -> &str {
+        "deadline"
+    }
+    fn label(&self) -> &str {
+        "deadline"
+    }
+    fn description(&self) -> &str {
+        "Sleeps, with a per-tool timeout"
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({})
+    }
+    async fn execute(
+        &self,
+        _params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        tokio::time::sleep(std::time::Duration::from_millis(self.delay_ms)).await;
+        Ok(ToolResult {
+            content: vec![Content::Text {
+                text: "finished".into(),
+            }],
+            details: serde_json::Value::Null,
+        })
+    }
+    fn timeout(&self) -> Option<std::time::Duration> {
+        self.timeout
+    }
+}
+
+async fn run_deadline_tool(
+    tool: DeadlineTool,
+    loop_timeout: Option<std::time::Duration>,
+) -> (ToolResult, bool) {
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "deadline".into(),
+            arguments: serde_json::json!({}),
+        }]),
+        MockResponse::Text("ok".into()),
+    ]);
+    let mut config = make_config(provider);
+    config.tool_timeout = loop_timeout;
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(tool)],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("Run it"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    collect_events(rx)
+        .into_iter()
+        .find_map(|e| match e {
+            AgentEvent::ToolExecutionEnd {
+                result, is_error, ..
+            } => Some((result, is_error)),
+            _ => None,
+        })
+        .expect("ToolExecutionEnd")
+}
+
+#[tokio::test]
+async fn test_per_tool_timeout_applies_without_loop_timeout() {
+    let (result, is_error) = run_deadline_tool(
+        DeadlineTool {
+            delay_ms: 5_000,
+            timeout: Some(std::time::Duration::from_millis(50)),
+        },
+        None,
+    )
+    .await;
+
+    assert!(is_error);
+    assert_eq!(
+        result.content,
+        vec![Content::Text {
+            text: "Tool 'deadline' timed out after 0.05s".into()
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_per_tool_timeout_overrides_loop_timeout() {
+    // The loop-wide 50ms would cut this off; the tool asks for 5s.
+    let (result, is_error) = run_deadline_tool(
+        DeadlineTool {
+            delay_ms: 150,
+            timeout: Some(std::time::Duration::from_secs(5)),
+        },
+        Some(std::time::Duration::from_millis(50)),
+    )
+    .await;
+
+    assert!(!is_error);
+    assert_eq!(
+        result.content,
+        vec![Content::Text {
+            text: "finished".into()
+        }]
+    );
+}
+
+/// Fails at once with a timeout of its own, like bash hitting its limit.
+struct SelfTimingOutTool;
+
+#[async_trait::async_trait]
+impl AgentTool for SelfTimingOutTool {
+    fn name(&self) -> &str {
+        "self_timeout"
+    }
+    fn label(&self) -> &str {
+        "self_timeout"
+    }
+    fn description(&self) -> &str {
+        "Reports its own timeout"
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({})
+    }
+    async fn execute(
+        &self,
+        _params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        Err(ToolError::Timeout(std::time::Duration::from_secs(120)))
+    }
+}
+
+#[tokio::test]
+async fn test_tool_reported_timeout_keeps_its_message() {
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "self_timeout".into(),
+            arguments: serde_json::json!({}),
+        }]),
+        MockResponse::Text("ok".into()),
+    ]);
+    let mut config = make_config(provider);
+    config.tool_timeout = Some(std::time::Duration::from_secs(5));
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(SelfTimingOutTool)],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("Run it"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    // The tool's own limit, not the loop's 5s deadline.
+    let result = collect_events(rx)
+        .into_iter()
+        .find_map(|e| match e {
+            AgentEvent::ToolExecutionEnd { result, .. } => Some(result),
+            _ => None,
+        })
+        .expect("ToolExecutionEnd");
+    assert_eq!(
+        result.content,
+        vec![Content::Text {
+            text: ToolError::Timeout(std::time::Duration::from_secs(120)).to_string()
+        }]
+    );
+}
+
+/// Stashes its context so the test can inspect it after the call is dropped.
+struct ContextCapturingTool {
+    seen: Arc<std::sync::Mutex<Option<ToolContext>>>,
+}
+
+#[async_trait::async_trait]
+impl AgentTool for ContextCapturingTool {
+    fn name(&self) -> &str {
+        "capture"
+    }
+    fn label(&self) -> &str {
+        "capture"
+    }
+    fn description(&self) -> &str {
+        "Captures its context, then hangs"
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({})
+    }
+    async fn execute(
+        &self,
+        _params: serde_json::Value,
+        ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        *self.seen.lock().unwrap() = Some(ctx);
+        std::future::pending().await
+    }
+}
+
+#[tokio::test]
+async fn test_tool_timeout_is_visible_to_tool_and_cancels_its_token() {
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "capture".into(),
+            arguments: serde_json::json!({}),
+        }]),
+        MockResponse::Text("ok".into()),
+    ]);
+    let config =
+        yoagent::agent_loop::AgentLoopConfigBuilder::new(Arc::new(provider), "mock", "test")
+            .with_tool_timeout(std::time::Duration::from_millis(30))
+            .build();
+
+    let seen = Arc::new(std::sync::Mutex::new(None));
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(ContextCapturingTool { seen: seen.clone() })],
+    };
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let parent = CancellationToken::new();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        parent.clone(),
+    )
+    .await;
+
+    let ctx = seen.lock().unwrap().take().expect("tool should have run");
+    assert_eq!(ctx.timeout, Some(std::time::Duration::from_millis(30)));
+    assert!(ctx.cancel.is_cancelled());
+    // Only the tool's child token fires, not the whole run.
+    assert!(!parent.is_cancelled());
+}
+
+// ---------------------------------------------------------------------------
+// Request options (tool choice, stop sequences)
+// ---------------------------------------------------------------------------
+
+/// Records the `StreamConfig` of every request, then delegates to a mock.
+struct ConfigRecordingProvider {
+    inner: MockProvider,
+    seen: Arc<std::sync::Mutex<Vec<StreamConfig>>>,
+}
+
+#[async_trait::async_trait]
+impl StreamProvider for ConfigRecordingProvider {
+    async fn stream(
+        &self,
+        config: StreamConfig,
+        tx: mpsc::UnboundedSender<StreamEvent>,
+        cancel: CancellationToken,
+    ) -> Result<Message, ProviderError> {
+        self.seen.lock().unwrap().push(config.clone());
+        self.inner.stream(config, tx, cancel).await
+    }
+}
+
+#[tokio::test]
+async fn test_forced_tool_choice_relaxes_after_tool_call() {
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let provider = ConfigRecordingProvider {
+        inner: MockProvider::new(vec![
+            MockResponse::ToolCalls(vec![MockToolCall {
+                provider_metadata: None,
+                name: "classify".into(),
+                arguments: serde_json::json!({}),
+            }]),
+            MockResponse::Text("Labelled.".into()),
+        ]),
+        seen: seen.clone(),
+    };
+    let config =
+        yoagent::agent_loop::AgentLoopConfigBuilder::new(Arc::new(provider), "mock", "test")
+            .with_tool_choice(ToolChoice::Specific("classify".into()))
+            .build();
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(TimedTool {
+            name: "classify".into(),
+            delay_ms: 0,
+        })],
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("Route this"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    // Forced on the first request only; the follow-up turn may answer freely.
+    let choices: Vec<ToolChoice> = seen
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|c| c.tool_choice.clone())
+        .collect();
+    assert_eq!(
+        choices,
+        vec![ToolChoice::Specific("classify".into()), ToolChoice::Auto]
+    );
+}
+
+#[tokio::test]
+async fn test_stop_sequences_reach_every_request() {
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let provider = ConfigRecordingProvider {
+        inner: MockProvider::new(vec![
+            MockResponse::ToolCalls(vec![MockToolCall {
+                provider_metadata: None,
+                name: "tool_a".into(),
+                arguments: serde_json::json!({}),
+            }]),
+            MockResponse::Text("done".into()),
+        ]),
+        seen: seen.clone(),
+    };
+    let mut config = make_config(MockProvider::text("unused"));
+    config.provider = Arc::new(provider);
+    config.stop_sequences = Some(vec!["</answer>".into()]);
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(TimedTool {
+            name: "tool_a".into(),
+            delay_ms: 0,
+        })],
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2);
+    for request in seen.iter() {
+        assert_eq!(request.stop_sequences, Some(vec!["</answer>".to_string()]));
+    }
+}
+
+#[tokio::test]
+async fn test_json_mode_tool_call_is_unwrapped_into_text() {
+    // Tool-forcing providers deliver JSON mode as a call to the synthetic
+    // `json_object` tool; the loop turns it back into a text reply.
+    #[derive(serde::Deserialize)]
+    struct Verdict {
+        label: String,
+        score: f64,
+    }
+
+    let provider = MockProvider::new(vec![MockResponse::ToolCalls(vec![MockToolCall {
+        provider_metadata: None,
+        name: "json_object".into(),
+        arguments: serde_json::json!({"label": "spam", "score": 0.9}),
+    }])]);
+    let mut config = make_config(provider);
+    config.response_format = ResponseFormat::JsonObject;
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("classify"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    // user + assistant only: nothing was executed as a tool.
+    assert_eq!(new_messages.len(), 2);
+    let AgentMessage::Llm(reply) = &new_messages[1] else {
+        panic!("expected an LLM message");
+    };
+    match reply {
+        Message::Assistant { stop_reason, .. } => assert_eq!(*stop_reason, StopReason::Stop),
+        other => panic!("expected assistant message, got {other:?}"),
+    }
+    let verdict: Verdict = reply.parse_json().unwrap();
+    assert_eq!(verdict.label, "spam");
+    assert!((verdict.score - 0.9).abs() < f64::EPSILON);
+}
+
+#[tokio::test]
+async fn test_streaming_bash_emits_updates_before_end() {
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "bash".into(),
+            arguments: serde_json::json!({"command": "echo one; echo two; echo three"}),
+        }]),
+        MockResponse::Text("done".into()),
+    ]);
+    let config = make_config(provider);
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(
+            yoagent::tools::BashTool::new().with_streaming(true),
+        )],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("run it"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let events = collect_events(rx);
+    let end = events
+        .iter()
+        .position(|e| matches!(e, AgentEvent::ToolExecutionEnd { .. }))
+        .expect("tool execution end");
+    let first_update = events
+        .iter()
+        .position(|e| matches!(e, AgentEvent::ToolExecutionUpdate { .. }))
+        .expect("at least one tool execution update");
+    assert!(first_update < end);
+}
+
+/// Streams one tool call whose arguments arrive in several fragments, then
+/// defers to `inner` for the follow-up turn.
+struct FragmentedToolCallProvider {
+    fragments: Vec<&'static str>,
+    calls: std::sync::atomic::AtomicUsize,
+    inner: MockProvider,
+}
+
+#[async_trait::async_trait]
+impl StreamProvider for FragmentedToolCallProvider {
+    async fn stream(
+        &self,
+        config: StreamConfig,
+        tx: tokio::sync::mpsc::UnboundedSender<StreamEvent>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<yoagent::Message, ProviderError> {
+        if self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) > 0 {
+            return self.inner.stream(config, tx, cancel).await;
+        }
+
+        let _ = tx.send(StreamEvent::Start);
+        let _ = tx.send(StreamEvent::ToolCallStart {
+            content_index: 0,
+            id: "tc-frag".into(),
+            name: "lookup".into(),
+        });
+        for fragment in &self.fragments {
+            let _ = tx.send(StreamEvent::ToolCallDelta {
+                content_index: 0,
+                delta: fragment.to_string(),
+            });
+        }
+        let _ = tx.send(StreamEvent::ToolCallEnd { content_index: 0 });
+
+        let arguments: serde_json::Value = serde_json::from_str(&self.fragments.concat()).unwrap();
+        let message = Message::assistant(
+            vec![Content::tool_call("tc-frag", "lookup", arguments)],
+            StopReason::ToolUse,
+            "mock",
+            "mock",
+            Usage::default(),
+        );
+        let _ = tx.send(StreamEvent::Done {
+            message: message.clone(),
+        });
+        Ok(message)
+    }
+}
+
+#[tokio::test]
+async fn test_tool_call_args_update_tracks_partial_json() {
+    let provider = FragmentedToolCallProvider {
+        fragments: vec![
+            r#"{"query": "ru"#,
+            r#"st async", "#,
+            r#""tags": ["a", "b"#,
+            r#""], "limit": 1"#,
+            r#"0}"#,
+        ],
+        calls: std::sync::atomic::AtomicUsize::new(0),
+        inner: MockProvider::text("done"),
+    };
+    let config = make_config(provider);
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("search"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let events = collect_events(rx);
+    let updates: Vec<serde_json::Value> = events
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::ToolCallArgsUpdate {
+                tool_call_id,
+                tool_name,
+                partial_args,
+            } => {
+                assert_eq!(tool_call_id, "tc-frag");
+                assert_eq!(tool_name, "lookup");
+                Some(partial_args.clone())
+            }
+            _ => None,
+        })
+        .collect();
+
+    assert_eq!(
+        updates,
+        vec![
+            serde_json::json!({"query": "ru"}),
+            serde_json::json!({"query": "rust async"}),
+            serde_json::json!({"query": "rust async", "tags": ["a", "b"]}),
+            serde_json::json!({"query": "rust async", "tags": ["a", "b"], "limit": 1}),
+            serde_json::json!({"query": "rust async", "tags": ["a", "b"], "limit": 10}),
+        ]
+    );
+
+    // Raw deltas are still forwarded alongside the parsed view.
+    let raw_deltas = events
+        .iter()
+        .filter(|e| {
+            matches!(
+                e,
+                AgentEvent::MessageUpdate {
+                    delta: StreamDelta::ToolCallDelta { .. },
+                    ..
+                }
+            )
+        })
+        .count();
+    assert_eq!(raw_deltas, 5);
+
+    // Every update arrives before the tool starts executing.
+    let last_update = events
+        .iter()
+        .rposition(|e| matches!(e, AgentEvent::ToolCallArgsUpdate { .. }))
+        .unwrap();
+    let exec_start = events
+        .iter()
+        .position(|e| matches!(e, AgentEvent::ToolExecutionStart { .. }))
+        .unwrap();
+    assert!(last_update < exec_start);
+}
+
+// ---------------------------------------------------------------------------
+// Stream API
+// ---------------------------------------------------------------------------
+
+fn tool_then_text_provider() -> MockProvider {
+    MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "silent_tool".into(),
+            arguments: serde_json::json!({}),
+        }]),
+        MockResponse::Text("done".into()),
+    ])
+}
+
+fn event_tag(event: &AgentEvent) -> String {
+    serde_json::to_value(event).unwrap()["type"]
+        .as_str()
+        .unwrap()
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_agent_loop_stream_matches_channel_ordering() {
+    use futures::StreamExt;
+    use yoagent::agent_loop::agent_loop_stream;
+
+    let prompt = || vec![AgentMessage::Llm(Message::user("run the tool"))];
+    let context = || AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(SilentTool) as Box<dyn AgentTool>],
+    };
+
+    // Channel version.
+    let mut channel_context = context();
+    let (tx, rx) = mpsc::unbounded_channel();
+    let channel_messages = agent_loop(
+        prompt(),
+        &mut channel_context,
+        &make_config(tool_then_text_provider()),
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+    let channel_tags: Vec<String> = collect_events(rx).iter().map(event_tag).collect();
+
+    // Stream version.
+    let mut stream = agent_loop_stream(
+        prompt(),
+        context(),
+        make_config(tool_then_text_provider()),
+        CancellationToken::new(),
+    );
+    let stream_events: Vec<AgentEvent> = (&mut stream).collect().await;
+    let stream_tags: Vec<String> = stream_events.iter().map(event_tag).collect();
+
+    assert_eq!(stream_tags, channel_tags);
+    assert!(stream_tags.contains(&"toolExecutionEnd".to_string()));
+    match stream_events.last() {
+        Some(AgentEvent::AgentEnd { messages }) => {
+            assert_eq!(messages.len(), channel_messages.len())
+        }
+        other => panic!("expected terminal AgentEnd, got {:?}", other),
+    }
+
+    let (final_context, new_messages) = stream.finish().await;
+    assert_eq!(new_messages.len(), channel_messages.len());
+    assert_eq!(final_context.messages.len(), channel_context.messages.len());
+    assert_eq!(final_context.tools.len(), 1);
+}
+
+#[tokio::test]
+async fn test_agent_loop_continue_stream_yields_events() {
+    use futures::StreamExt;
+    use yoagent::agent_loop::agent_loop_continue_stream;
+
+    let context = AgentContext {
+        system_prompt: "test".into(),
+        messages: vec![AgentMessage::Llm(Message::user("Hi"))],
+        tools: Vec::new(),
+    };
+    let mut stream = agent_loop_continue_stream(
+        context,
+        make_config(MockProvider::text("Hello again")),
+        CancellationToken::new(),
+    );
+    let tags: Vec<String> = (&mut stream).map(|e| event_tag(&e)).collect().await;
+    assert_eq!(tags.first().map(String::as_str), Some("agentStart"));
+    assert_eq!(tags.last().map(String::as_str), Some("agentEnd"));
+
+    let (context, new_messages) = stream.finish().await;
+    assert_eq!(new_messages.len(), 1);
+    assert_eq!(context.messages.len(), 2);
+}
+
+#[tokio::test]
+async fn test_cloned_context_branches_independently() {
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![
+            Box::new(yoagent::tools::ReadFileTool::new()),
+            Box::new(yoagent::tools::BashTool::new().with_confirm(|_| true)),
+        ],
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("Hi"))],
+        &mut context,
+        &make_config(MockProvider::text("Hello")),
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+    assert_eq!(context.messages.len(), 2);
+
+    let mut branches = Vec::new();
+    for reply in ["Option A", "Option B"] {
+        let mut branch = context.clone();
+        assert_eq!(branch.tools.len(), 2);
+        assert_eq!(branch.tools[1].name(), "bash");
+        let (tx, _rx) = mpsc::unbounded_channel();
+        agent_loop(
+            vec![AgentMessage::Llm(Message::user(format!("Try {reply}")))],
+            &mut branch,
+            &make_config(MockProvider::text(reply)),
+            tx,
+            CancellationToken::new(),
+        )
+        .await;
+        branches.push(branch);
+    }
+
+    // The branches share the first exchange and diverge after it.
+    for (branch, reply) in branches.iter().zip(["Option A", "Option B"]) {
+        assert_eq!(branch.messages.len(), 4);
+        match branch.messages.last() {
+            Some(AgentMessage::Llm(Message::Assistant { content, .. })) => {
+                assert!(matches!(&content[0], Content::Text { text } if text == reply));
+            }
+            other => panic!("expected assistant reply, got {:?}", other),
+        }
+    }
+    assert_eq!(context.messages.len(), 2);
+    assert_eq!(context.tools.len(), 2);
+}
+
+/// Counts `WARN` events from the agent loop.
+struct WarnCounter(Arc<std::sync::atomic::AtomicUsize>);
+
+impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for WarnCounter {
+    fn on_event(
+        &self,
+        event: &tracing::Event<'_>,
+        _ctx: tracing_subscriber::layer::Context<'_, S>,
+    ) {
+        let meta = event.metadata();
+        if *meta.level() == tracing::Level::WARN && meta.target() == "yoagent::agent_loop" {
+            self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_bounded_event_buffer_drops_events_for_slow_consumer() {
+    use futures::StreamExt;
+    use std::sync::atomic::Ordering;
+    use tracing_subscriber::layer::SubscriberExt;
+    use yoagent::agent_loop::agent_loop_stream;
+
+    let warnings = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let subscriber = tracing_subscriber::registry().with(WarnCounter(warnings.clone()));
+    // The test runtime is single-threaded, so the spawned loop logs here too.
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let context = || AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(SilentTool) as Box<dyn AgentTool>],
+    };
+    let prompt = || vec![AgentMessage::Llm(Message::user("run the tool"))];
+
+    let unbounded: Vec<AgentEvent> = agent_loop_stream(
+        prompt(),
+        context(),
+        make_config(tool_then_text_provider()),
+        CancellationToken::new(),
+    )
+    .collect()
+    .await;
+    assert_eq!(warnings.load(Ordering::SeqCst), 0);
+
+    let mut config = make_config(tool_then_text_provider());
+    config.event_buffer_size = Some(1);
+    let mut stream = agent_loop_stream(prompt(), context(), config, CancellationToken::new());
+    let mut received = Vec::new();
+    while let Some(event) = stream.next().await {
+        received.push(event);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    // The run completes in full; only the consumer's view is lossy.
+    let (final_context, new_messages) = stream.finish().await;
+    assert_eq!(new_messages.len(), 4);
+    assert_eq!(final_context.messages.len(), 4);
+    assert!(matches!(received.first(), Some(AgentEvent::AgentStart)));
+    assert!(matches!(received.last(), Some(AgentEvent::AgentEnd { .. })));
+    assert!(received.len() < unbounded.len());
+    assert_eq!(
+        warnings.load(Ordering::SeqCst),
+        unbounded.len() - received.len()
+    );
+}
+
+#[tokio::test]
+async fn test_event_buffer_size_zero_is_one() {
+    use futures::StreamExt;
+
+    let config = yoagent::agent_loop::AgentLoopConfigBuilder::new(
+        Arc::new(tool_then_text_provider()),
+        "mock",
+        "test",
+    )
+    .with_event_buffer_size(0)
+    .build();
+    let context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(SilentTool) as Box<dyn AgentTool>],
+    };
+
+    let mut stream = agent_loop_stream(
+        vec![AgentMessage::Llm(Message::user("run the tool"))],
+        context,
+        config,
+        CancellationToken::new(),
+    );
+    let mut received = Vec::new();
+    while let Some(event) = stream.next().await {
+        received.push(event);
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+    }
+
+    let (_, new_messages) = stream.finish().await;
+    assert_eq!(new_messages.len(), 4);
+    assert!(matches!(received.last(), Some(AgentEvent::AgentEnd { .. })));
+}
+
+// ---------------------------------------------------------------------------
+// Tool cache
+// ---------------------------------------------------------------------------
+
+/// Counts executions; fails when asked to.
+struct CountingTool {
+    calls: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl AgentTool for CountingTool {
+    fn name(&self) -> &str {
+        "count"
+    }
+    fn label(&self) -> &str {
+        "count"
+    }
+    fn description(&self) -> &str {
+        "Counts its executions"
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({"type": "object", "properties": {"fail": {"type": "boolean"}}})
+    }
+    fn cacheable(&self) -> bool {
+        true
+    }
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let n = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        if params["fail"].as_bool() == Some(true) {
+            return Err(ToolError::Failed("asked to fail".into()));
+        }
+        Ok(ToolResult {
+            content: vec![Content::Text {
+                text: format!("run {}", n),
+            }],
+            details: serde_json::json!({"run": n}),
+        })
+    }
+}
+
+fn count_call(args: serde_json::Value) -> MockResponse {
+    MockResponse::ToolCalls(vec![MockToolCall {
+        provider_metadata: None,
+        name: "count".into(),
+        arguments: args,
+    }])
+}
+
+#[tokio::test]
+async fn test_tool_cache_serves_repeated_calls() {
+    use yoagent::tools::InMemoryToolCache;
+
+    let provider = MockProvider::new(vec![
+        count_call(serde_json::json!({"fail": false})),
+        count_call(serde_json::json!({"fail": false})),
+        MockResponse::Text("done".into()),
+    ]);
+    let mut config = make_config(provider);
+    config.tool_cache = Some(Arc::new(InMemoryToolCache::new()));
+
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(CountingTool {
+            calls: calls.clone(),
+        })],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("count twice"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    let events = collect_events(rx);
+    let starts = events
+        .iter()
+        .filter(|e| matches!(e, AgentEvent::ToolExecutionStart { .. }))
+        .count();
+    assert_eq!(starts, 2);
+    let ends: Vec<(ToolResult, bool)> = events
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::ToolExecutionEnd {
+                result, is_error, ..
+            } => Some((result.clone(), *is_error)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ends.len(), 2);
+    assert_eq!(ends[0].0.details, serde_json::json!({"run": 1}));
+    assert!(!ends[1].1);
+    assert_eq!(ends[1].0.content, ends[0].0.content);
+    assert_eq!(
+        ends[1].0.details,
+        serde_json::json!({"run": 1, "from_cache": true})
+    );
+}
+
+#[tokio::test]
+async fn test_tool_cache_never_stores_errors() {
+    use yoagent::tools::InMemoryToolCache;
+
+    let provider = MockProvider::new(vec![
+        count_call(serde_json::json!({"fail": true})),
+        count_call(serde_json::json!({"fail": true})),
+        MockResponse::Text("done".into()),
+    ]);
+    let cache = Arc::new(InMemoryToolCache::new());
+    let mut config = make_config(provider);
+    config.tool_cache = Some(cache.clone());
+
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(CountingTool {
+            calls: calls.clone(),
+        })],
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("fail twice"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    assert!(cache.is_empty());
+}
+
+#[tokio::test]
+async fn test_tool_cache_skips_writes_and_clears_after_them() {
+    use yoagent::tools::{InMemoryToolCache, ReadFileTool, WriteFileTool};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("notes.txt");
+    std::fs::write(&path, "one").unwrap();
+    let path = path.display().to_string();
+    let call = |name: &str, arguments: serde_json::Value| {
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: name.into(),
+            arguments,
+        }])
+    };
+    let read = serde_json::json!({"path": path});
+    let write = serde_json::json!({"path": path, "content": "two"});
+    let provider = MockProvider::new(vec![
+        call("read_file", read.clone()),
+        call("write_file", write.clone()),
+        call("write_file", write),
+        call("read_file", read),
+        MockResponse::Text("done".into()),
+    ]);
+    let cache = Arc::new(InMemoryToolCache::new());
+    let mut config = make_config(provider);
+    config.tool_cache = Some(cache.clone());
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![
+            Box::new(ReadFileTool::new()),
+            Box::new(WriteFileTool::new()),
+        ],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("edit the notes"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let ends: Vec<ToolResult> = collect_events(rx)
+        .into_iter()
+        .filter_map(|e| match e {
+            AgentEvent::ToolExecutionEnd { result, .. } => Some(result),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ends.len(), 4);
+    for result in &ends {
+        assert!(result.details.get("from_cache").is_none(), "{result:?}");
+    }
+    let text = |result: &ToolResult| match &result.content[0] {
+        Content::Text { text } => text.clone(),
+        other => panic!("unexpected content: {other:?}"),
+    };
+    assert!(text(&ends[0]).contains("one"));
+    assert!(text(&ends[3]).contains("two"));
+    // Only the last read is left in the cache.
+    assert_eq!(cache.len(), 1);
+}
+
+// ---------------------------------------------------------------------------
+// Tool approval
+// ---------------------------------------------------------------------------
+
+/// Runs two turns of `count` calls under `approve`, returning how often the
+/// tool ran, how often approval was asked, and the events.
+async fn run_with_approval(
+    approve: impl Fn(&str, &str, &serde_json::Value) -> ToolApproval + Send + Sync + 'static,
+) -> (usize, usize, Vec<AgentEvent>) {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let provider = MockProvider::new(vec![
+        count_call(serde_json::json!({"fail": false})),
+        count_call(serde_json::json!({"fail": false})),
+        MockResponse::Text("done".into()),
+    ]);
+    let asked = Arc::new(AtomicUsize::new(0));
+    let asked_clone = asked.clone();
+    let config =
+        yoagent::agent_loop::AgentLoopConfigBuilder::new(Arc::new(provider), "mock", "test")
+            .with_approve_tool(move |id, name, args| {
+                asked_clone.fetch_add(1, Ordering::SeqCst);
+                approve(id, name, args)
+            })
+            .build();
+
+    let calls = Arc::new(AtomicUsize::new(0));
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(CountingTool {
+            calls: calls.clone(),
+        })],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("count"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    (
+        calls.load(Ordering::SeqCst),
+        asked.load(Ordering::SeqCst),
+        collect_events(rx),
+    )
+}
+
+#[tokio::test]
+async fn test_approval_allow_runs_tool_and_asks_each_time() {
+    let (calls, asked, events) = run_with_approval(|id, name, args| {
+        assert!(id.starts_with("mock-tool-"));
+        assert_eq!(name, "count");
+        assert_eq!(args, &serde_json::json!({"fail": false}));
+        ToolApproval::Allow
+    })
+    .await;
+
+    assert_eq!(calls, 2);
+    assert_eq!(asked, 2);
+    let required: Vec<usize> = events
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| matches!(e, AgentEvent::ToolApprovalRequired { tool_name, .. } if tool_name == "count"))
+        .map(|(i, _)| i)
+        .collect();
+    let first_start = events
+        .iter()
+        .position(|e| matches!(e, AgentEvent::ToolExecutionStart { .. }))
+        .unwrap();
+    assert_eq!(required.len(), 2);
+    assert!(required[0] < first_start);
+}
+
+#[tokio::test]
+async fn test_approval_deny_skips_tool_with_error_result() {
+    let (calls, asked, events) =
+        run_with_approval(|_, _, _| ToolApproval::Deny("user said no".into())).await;
+
+    assert_eq!(calls, 0);
+    assert_eq!(asked, 2);
+    let ends: Vec<(ToolResult, bool)> = events
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::ToolExecutionEnd {
+                result, is_error, ..
+            } => Some((result.clone(), *is_error)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(ends.len(), 2);
+    assert!(ends.iter().all(|(_, is_error)| *is_error));
+    assert_eq!(
+        ends[0].0.content,
+        vec![Content::Text {
+            text: "Tool call denied: user said no".into()
+        }]
+    );
+}
+
+#[tokio::test]
+async fn test_approval_always_allow_is_remembered_for_the_run() {
+    let (calls, asked, events) = run_with_approval(|_, _, _| ToolApproval::AlwaysAllow).await;
+
+    assert_eq!(calls, 2);
+    assert_eq!(asked, 1);
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e, AgentEvent::ToolApprovalRequired { .. }))
+            .count(),
+        1
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Dynamic tools (prepare_turn)
+// ---------------------------------------------------------------------------
+
+#[test]
+fn test_agent_context_tool_registry() {
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(SilentTool)],
+    };
+
+    context.add_tool(Box::new(CountingTool {
+        calls: calls.clone(),
+    }));
+    assert_eq!(context.tool_names(), vec!["silent_tool", "count"]);
+
+    // Same name replaces rather than duplicates.
+    context.add_tool(Box::new(CountingTool { calls }));
+    assert_eq!(context.tools.len(), 2);
+
+    assert!(context.remove_tool("silent_tool"));
+    assert!(!context.remove_tool("silent_tool"));
+    assert!(!context.has_tool("silent_tool"));
+    assert!(context.has_tool("count"));
+}
+
+#[tokio::test]
+async fn test_prepare_turn_adds_tool_for_later_turns() {
+    let provider = MockProvider::new(vec![
+        count_call(serde_json::json!({"fail": false})),
+        count_call(serde_json::json!({"fail": false})),
+        MockResponse::Text("done".into()),
+    ]);
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let registered = calls.clone();
+    let mut config = make_config(provider);
+    config.prepare_turn = Some(Arc::new(move |context: &mut AgentContext, turn| {
+        if turn == 1 {
+            context.add_tool(Box::new(CountingTool {
+                calls: registered.clone(),
+            }));
+        }
+    }));
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("count"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    // Turn 1: not registered yet. Turn 2: registered and executed.
+    let outcomes: Vec<bool> = collect_events(rx)
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::ToolExecutionEnd { is_error, .. } => Some(*is_error),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(outcomes, vec![true, false]);
+    assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    assert_eq!(context.tool_names(), vec!["count"]);
+}
+
+#[tokio::test]
+async fn test_system_prompt_hook_rewrites_each_turn() {
+    let seen = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let provider = ConfigRecordingProvider {
+        inner: MockProvider::new(vec![
+            count_call(serde_json::json!({"fail": false})),
+            count_call(serde_json::json!({"fail": false})),
+            MockResponse::Text("done".into()),
+        ]),
+        seen: seen.clone(),
+    };
+    let turns = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let hook_turns = turns.clone();
+    let mut config = make_config(provider);
+    config.system_prompt_hook = Some(Arc::new(move |prompt: &str, turn| {
+        hook_turns.lock().unwrap().push(turn);
+        format!("{} (turn {})", prompt, turn)
+    }));
+
+    let mut context = AgentContext {
+        system_prompt: "base".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(CountingTool {
+            calls: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
+        })],
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("count"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(*turns.lock().unwrap(), vec![0, 1, 2]);
+    let prompts: Vec<String> = seen
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|c| c.system_prompt.clone())
+        .collect();
+    assert_eq!(prompts, ["base (turn 0)", "base (turn 1)", "base (turn 2)"]);
+    assert_eq!(context.system_prompt, "base");
+}
+
+#[test]
+fn test_date_injector_prepends_iso_date() {
+    let at = std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_709_210_096); // 2024-02-29
+    assert_eq!(
+        DateInjector::inject("You are helpful.", at),
+        "Current date: 2024-02-29\n\nYou are helpful."
+    );
+    assert_eq!(
+        DateInjector::inject("", std::time::UNIX_EPOCH),
+        "Current date: 1970-01-01"
+    );
+    assert!(DateInjector::hook("x", 0).starts_with("Current date: "));
+}
+
+// ---------------------------------------------------------------------------
+// Argument validation
+// ---------------------------------------------------------------------------
+
+/// Requires `path: string`; counts executions. `validate` feeds
+/// `AgentTool::validate_args`.
+struct PathTool {
+    validate: bool,
+    calls: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl AgentTool for PathTool {
+    fn name(&self) -> &str {
+        "read_path"
+    }
+    fn label(&self) -> &str {
+        "read_path"
+    }
+    fn description(&self) -> &str {
+        "Reads a path"
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({
+            "type": "object",
+            "properties": {"path": {"type": "string"}},
+            "required": ["path"]
+        })
+    }
+    fn validate_args(&self) -> bool {
+        self.validate
+    }
+    async fn execute(
+        &self,
+        _params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        Ok(ToolResult {
+            content: vec![Content::Text { text: "ok".into() }],
+            details: serde_json::Value::Null,
+        })
+    }
+}
+
+/// Run one `read_path` call with `path: 42`; returns the `ToolExecutionEnd`
+/// outcome and how many times the tool ran.
+async fn run_bad_path_call(validate: bool) -> (String, bool, usize) {
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "read_path".into(),
+            arguments: serde_json::json!({"path": 42}),
+        }]),
+        MockResponse::Text("done".into()),
+    ]);
+    let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(PathTool {
+            validate,
+            calls: calls.clone(),
+        })],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("read"))],
+        &mut context,
+        &make_config(provider),
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let (text, is_error) = collect_events(rx)
+        .into_iter()
+        .find_map(|e| match e {
+            AgentEvent::ToolExecutionEnd {
+                result, is_error, ..
+            } => match &result.content[0] {
+                Content::Text { text } => Some((text.clone(), is_error)),
+                _ => None,
+            },
+            _ => None,
+        })
+        .expect("tool should finish");
+    (
+        text,
+        is_error,
+        calls.load(std::sync::atomic::Ordering::SeqCst),
+    )
+}
+
+#[tokio::test]
+async fn test_schema_violation_fails_without_running_tool() {
+    let (text, is_error, calls) = run_bad_path_call(true).await;
+
+    assert!(is_error);
+    assert_eq!(calls, 0);
+    assert!(text.starts_with("Invalid arguments: "), "{text}");
+    assert!(text.contains("/path"), "{text}");
+    assert!(text.contains("\"string\""), "{text}");
+}
+
+#[tokio::test]
+async fn test_validate_args_false_skips_validation() {
+    let (text, is_error, calls) = run_bad_path_call(false).await;
+
+    assert!(!is_error);
+    assert_eq!(calls, 1);
+    assert_eq!(text, "ok");
+}
+
+// ---------------------------------------------------------------------------
+// ExecutionLimits::turn_timeout
+// ---------------------------------------------------------------------------
+
+/// Sleeps `delay` on its first `slow_calls` calls (never finishing them in
+/// time), then answers from `inner`.
+struct SlowProvider {
+    delay: std::time::Duration,
+    slow_calls: usize,
+    calls: std::sync::atomic::AtomicUsize,
+    cancelled: Arc<std::sync::atomic::AtomicBool>,
+    inner: MockProvider,
+}
+
+#[async_trait::async_trait]
+impl StreamProvider for SlowProvider {
+    async fn stream(
+        &self,
+        config: StreamConfig,
+        tx: tokio::sync::mpsc::UnboundedSender<StreamEvent>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<yoagent::Message, ProviderError> {
+        let call = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        if call < self.slow_calls {
+            // Record the cancel even though the call is dropped right after.
+            let cancelled = self.cancelled.clone();
+            let watch = cancel.clone();
+            tokio::spawn(async move {
+                watch.cancelled().await;
+                cancelled.store(true, std::sync::atomic::Ordering::SeqCst);
+            });
+            tokio::time::sleep(self.delay).await;
+        }
+        self.inner.stream(config, tx, cancel).await
+    }
+}
+
+fn slow_provider(slow_calls: usize) -> SlowProvider {
+    SlowProvider {
+        delay: std::time::Duration::from_millis(200),
+        slow_calls,
+        calls: std::sync::atomic::AtomicUsize::new(0),
+        cancelled: Arc::new(std::sync::atomic::AtomicBool::new(false)),
+        inner: MockProvider::text("done"),
+    }
+}
+
+fn turn_timeout_limits() -> ExecutionLimits {
+    ExecutionLimits {
+        turn_timeout: Some(std::time::Duration::from_millis(50)),
+        ..ExecutionLimits::default()
+    }
+}
+
+fn aborted_messages(messages: &[AgentMessage]) -> Vec<String> {
+    messages
+        .iter()
+        .filter_map(|m| match m {
+            AgentMessage::Llm(Message::Assistant {
+                stop_reason: StopReason::Aborted,
+                error_message,
+                ..
+            }) => Some(error_message.clone().unwrap_or_default()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn test_turn_timeout_aborts_turn_and_continues() {
+    let provider = Arc::new(slow_provider(1));
+    let config = yoagent::agent_loop::AgentLoopConfigBuilder::new(provider.clone(), "mock", "test")
+        .with_execution_limits(turn_timeout_limits())
+        .build();
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+
+    let (tx, rx) = mpsc::unbounded_channel();
+    let session = CancellationToken::new();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        session.clone(),
+    )
+    .await;
+
+    assert_eq!(
+        aborted_messages(&new_messages),
+        vec!["Turn timeout after 0.05s".to_string()]
+    );
+    // The loop went on to a second turn that completed normally.
+    assert_eq!(new_messages.len(), 3);
+    assert!(matches!(
+        new_messages.last(),
+        Some(AgentMessage::Llm(Message::Assistant {
+            stop_reason: StopReason::Stop,
+            ..
+        }))
+    ));
+    assert_eq!(provider.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    // Only the provider's token was cancelled, not the session's.
+    tokio::task::yield_now().await;
+    assert!(provider.cancelled.load(std::sync::atomic::Ordering::SeqCst));
+    assert!(!session.is_cancelled());
+
+    let events = collect_events(rx);
+    let turn_ends = events
+        .iter()
+        .filter(|e| matches!(e, AgentEvent::TurnEnd { .. }))
+        .count();
+    assert_eq!(turn_ends, 2);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        AgentEvent::MessageEnd {
+            message: AgentMessage::Llm(Message::Assistant {
+                stop_reason: StopReason::Aborted,
+                ..
+            })
+        }
+    )));
+}
+
+#[tokio::test]
+async fn test_turn_timeout_respects_before_turn() {
+    let provider = Arc::new(slow_provider(usize::MAX));
+    let config = yoagent::agent_loop::AgentLoopConfigBuilder::new(provider.clone(), "mock", "test")
+        .with_execution_limits(turn_timeout_limits())
+        .with_before_turn(|_, turn| turn < 1)
+        .build();
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(aborted_messages(&new_messages).len(), 1);
+    assert_eq!(provider.calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_turn_timeout_cuts_short_a_retry_delay() {
+    let provider = Arc::new(FailThenSucceedProvider {
+        fail_count: std::sync::atomic::AtomicUsize::new(0),
+        max_failures: 1,
+        error: ProviderError::RateLimited {
+            retry_after_ms: Some(10_000),
+        },
+        inner: MockProvider::text("too late"),
+    });
+    let config = yoagent::agent_loop::AgentLoopConfigBuilder::new(provider.clone(), "mock", "test")
+        .with_execution_limits(turn_timeout_limits())
+        .with_retry_config(yoagent::RetryConfig {
+            max_retries: 3,
+            max_delay_ms: 30_000,
+            ..yoagent::RetryConfig::default()
+        })
+        .with_before_turn(|_, turn| turn < 1)
+        .build();
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+
+    let started = std::time::Instant::now();
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!(
+        aborted_messages(&new_messages),
+        vec!["Turn timeout after 0.05s".to_string()]
+    );
+    // The retry never went out.
+    assert_eq!(
+        provider
+            .fail_count
+            .load(std::sync::atomic::Ordering::SeqCst),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_turn_timeout_cuts_short_a_rate_limiter_wait() {
+    let provider = Arc::new(slow_provider(0));
+    let limiter = Arc::new(yoagent::RateLimiter::new().with_requests_per_minute(1));
+    // Drain the single request so the loop's first call has to wait ~60s.
+    limiter.try_acquire(0).unwrap();
+    let config = yoagent::agent_loop::AgentLoopConfigBuilder::new(provider.clone(), "mock", "test")
+        .with_execution_limits(turn_timeout_limits())
+        .with_rate_limiter(limiter)
+        .with_before_turn(|_, turn| turn < 1)
+        .build();
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+
+    let started = std::time::Instant::now();
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert!(started.elapsed() < std::time::Duration::from_secs(5));
+    assert_eq!(aborted_messages(&new_messages).len(), 1);
+    assert_eq!(provider.calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+}
+
+// ---------------------------------------------------------------------------
+// AgentContext::validate / repair
+// ---------------------------------------------------------------------------
+
+fn assistant_text(text: &str) -> AgentMessage {
+    AgentMessage::Llm(Message::assistant(
+        vec![Content::Text { text: text.into() }],
+        StopReason::Stop,
+        "mock",
+        "mock",
+        Usage {
+            output: 10,
+            ..Usage::default()
+        },
+    ))
+}
+
+fn assistant_call(id: &str) -> AgentMessage {
+    AgentMessage::Llm(Message::assistant(
+        vec![Content::tool_call(id, "count", serde_json::json!({}))],
+        StopReason::ToolUse,
+        "mock",
+        "mock",
+        Usage::default(),
+    ))
+}
+
+fn tool_result(id: &str) -> AgentMessage {
+    AgentMessage::Llm(Message::ToolResult {
+        tool_call_id: id.into(),
+        tool_name: "count".into(),
+        content: vec![Content::Text { text: "1".into() }],
+        is_error: false,
+        timestamp: 0,
+    })
+}
+
+fn context_with(messages: Vec<AgentMessage>) -> AgentContext {
+    AgentContext {
+        system_prompt: "test".into(),
+        messages,
+        tools: Vec::new(),
+    }
+}
+
+#[test]
+fn test_validate_accepts_well_formed_history() {
+    let failed = AgentMessage::Llm(
+        Message::assistant(
+            vec![],
+            StopReason::Aborted,
+            "mock",
+            "mock",
+            Usage::default(),
+        )
+        .with_error_message("Turn timeout after 30s"),
+    );
+    let context = context_with(vec![
+        AgentMessage::Llm(Message::user("go")),
+        assistant_call("call_1"),
+        tool_result("call_1"),
+        // A failed turn may be empty and doesn't count as a reply.
+        failed,
+        assistant_text("done"),
+        AgentMessage::Llm(Message::user("again")),
+    ]);
+    assert_eq!(context.validate(), Ok(()));
+}
+
+#[test]
+fn test_validate_reports_each_violation() {
+    let context = context_with(vec![
+        AgentMessage::Llm(Message::user("go")),
+        tool_result("call_0"),
+        assistant_text("one"),
+        assistant_text("two"),
+        AgentMessage::Llm(Message::User {
+            content: vec![],
+            timestamp: 0,
+        }),
+        assistant_text("last"),
+    ]);
+    let errors = context.validate().unwrap_err();
+    assert_eq!(
+        errors,
+        vec![
+            ValidationError::OrphanedToolResult {
+                index: 1,
+                tool_call_id: "call_0".into()
+            },
+            ValidationError::ConsecutiveAssistant { index: 3 },
+            ValidationError::EmptyContent {
+                index: 4,
+                role: "user".into()
+            },
+            ValidationError::EndsWithAssistant { index: 5 },
+        ]
+    );
+    assert_eq!(
+        errors[0].to_string(),
+        "message 1: tool result for unknown tool call 'call_0'"
+    );
+}
+
+#[test]
+fn test_repair_fixes_repairable_violations() {
+    let context = context_with(vec![
+        AgentMessage::Llm(Message::user("go")),
+        tool_result("call_0"),
+        assistant_text("one"),
+        assistant_call("call_1"),
+        tool_result("call_1"),
+        AgentMessage::Llm(Message::User {
+            content: vec![],
+            timestamp: 0,
+        }),
+        AgentMessage::Llm(Message::user("next")),
+    ])
+    .repair();
+
+    assert_eq!(context.validate(), Ok(()));
+    assert_eq!(context.messages.len(), 4);
+    let AgentMessage::Llm(Message::Assistant {
+        content,
+        stop_reason,
+        usage,
+        ..
+    }) = &context.messages[1]
+    else {
+        panic!("expected merged assistant message");
+    };
+    assert_eq!(content.len(), 2);
+    assert_eq!(*stop_reason, StopReason::ToolUse);
+    assert_eq!(usage.output, 10);
+    assert_eq!(context.messages[2], tool_result("call_1"));
+
+    // A trailing assistant message isn't something repair can fix.
+    let context = context_with(vec![
+        AgentMessage::Llm(Message::user("go")),
+        assistant_text("done"),
+    ])
+    .repair();
+    assert_eq!(
+        context.validate(),
+        Err(vec![ValidationError::EndsWithAssistant { index: 1 }])
+    );
+}
+
+#[tokio::test]
+#[should_panic(expected = "tool result for unknown tool call 'call_9'")]
+async fn test_continue_rejects_invalid_history() {
+    let config = make_config(MockProvider::text("unused"));
+    let mut context = context_with(vec![
+        AgentMessage::Llm(Message::user("go")),
+        tool_result("call_9"),
+    ]);
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop_continue(&mut context, &config, tx, CancellationToken::new()).await;
+}
+
+// ---------------------------------------------------------------------------
+// RecordingProvider / MockProvider::from_recording
+// ---------------------------------------------------------------------------
+
+/// Events with timestamps (which differ between runs) zeroed.
+fn normalized_events(rx: mpsc::UnboundedReceiver<AgentEvent>) -> Vec<serde_json::Value> {
+    fn zero_timestamps(value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    if key == "timestamp" {
+                        *v = serde_json::json!(0);
+                    } else {
+                        zero_timestamps(v);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(zero_timestamps),
+            _ => {}
+        }
+    }
+    collect_events(rx)
+        .iter()
+        .map(|e| {
+            let mut value = serde_json::to_value(e).unwrap();
+            zero_timestamps(&mut value);
+            value
+        })
+        .collect()
+}
+
+async fn run_with_provider(provider: impl StreamProvider + 'static) -> Vec<serde_json::Value> {
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(SilentTool) as Box<dyn AgentTool>],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("run the tool"))],
+        &mut context,
+        &make_config(provider),
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+    normalized_events(rx)
+}
+
+#[tokio::test]
+async fn test_recorded_conversation_replays_identically() {
+    use yoagent::provider::RecordingProvider;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("fixtures/conversation.jsonl");
+
+    let recorder = RecordingProvider::new(Box::new(tool_then_text_provider()), &path).unwrap();
+    let recorded = run_with_provider(recorder).await;
+
+    // Two turns: the tool call, then the final text.
+    let lines = std::fs::read_to_string(&path).unwrap();
+    assert_eq!(lines.lines().count(), 2);
+
+    let replayed = run_with_provider(MockProvider::from_recording(&path).unwrap()).await;
+    assert_eq!(replayed, recorded);
+}
+
+// ---------------------------------------------------------------------------
+// on_tool_call intercept hook
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn test_on_tool_call_intercepts_bash_without_spawning() {
+    let dir = tempfile::tempdir().unwrap();
+    let marker = dir.path().join("ran");
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![
+            MockToolCall {
+                provider_metadata: None,
+                name: "bash".into(),
+                arguments: serde_json::json!({
+                    "command": format!("touch {}", marker.display())
+                }),
+            },
+            MockToolCall {
+                provider_metadata: None,
+                name: "tool_a".into(),
+                arguments: serde_json::json!({}),
+            },
+        ]),
+        MockResponse::Text("Done.".into()),
+    ]);
+    let mut config = make_config(provider);
+    config.on_tool_call = Some(std::sync::Arc::new(|_id: &str, name: &str, _args: &_| {
+        (name == "bash").then(|| ToolResult {
+            content: vec![Content::Text {
+                text: "canned output".into(),
+            }],
+            details: serde_json::Value::Null,
+        })
+    }));
+
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![
+            Box::new(yoagent::tools::BashTool::new()),
+            Box::new(TimedTool {
+                name: "tool_a".into(),
+                delay_ms: 0,
+            }),
+        ],
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("Run it"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert!(!marker.exists(), "bash was spawned");
+    let results: Vec<_> = new_messages
+        .iter()
+        .filter_map(|m| match m {
+            AgentMessage::Llm(Message::ToolResult {
+                tool_name, content, ..
+            }) => Some((tool_name.as_str(), content.clone())),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(results.len(), 2);
+    assert_eq!(
+        results[0],
+        (
+            "bash",
+            vec![Content::Text {
+                text: "canned output".into()
+            }]
+        )
+    );
+    assert_eq!(results[1].0, "tool_a");
+
+    let events = collect_events(rx);
+    let starts: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::ToolExecutionStart { tool_name, .. } => Some(tool_name.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(starts, ["bash", "tool_a"]);
+    let intercepted: Vec<_> = events
+        .iter()
+        .filter_map(|e| match e {
+            AgentEvent::ToolExecutionEnd {
+                tool_name, result, ..
+            } => Some((tool_name.as_str(), result.details["intercepted"] == true)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(intercepted, [("bash", true), ("tool_a", false)]);
+}
+
+#[tokio::test]
+async fn test_on_message_sees_every_message_once() {
+    struct Echo;
+
+    #[async_trait::async_trait]
+    impl AgentTool for Echo {
+        fn name(&self) -> &str {
+            "echo"
+        }
+        fn label(&self) -> &str {
+            "Echo"
+        }
+        fn description(&self) -> &str {
+            "Echo the text back"
+        }
+        fn parameters_schema(&self) -> serde_json::Value {
+            serde_json::json!({"type": "object", "properties": {"text": {"type": "string"}}})
+        }
+        async fn execute(
+            &self,
+            params: serde_json::Value,
+            _ctx: ToolContext,
+        ) -> Result<ToolResult, ToolError> {
+            Ok(ToolResult {
+                content: vec![Content::Text {
+                    text: params["text"].as_str().unwrap_or_default().into(),
+                }],
+                details: serde_json::Value::Null,
+            })
+        }
+    }
+
+    let call = |text: &str| MockToolCall {
+        provider_metadata: None,
+        name: "echo".into(),
+        arguments: serde_json::json!({ "text": text }),
+    };
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![call("a"), call("b"), call("c")]),
+        MockResponse::Text("done".into()),
+    ]);
+
+    let seen = std::sync::Arc::new(std::sync::Mutex::new(Vec::<AgentMessage>::new()));
+    let mut config = make_config(provider);
+    let sink = seen.clone();
+    config.on_message = Some(std::sync::Arc::new(move |m: &AgentMessage| {
+        sink.lock().unwrap().push(m.clone());
+    }));
+
+    let mut context = AgentContext {
+        system_prompt: "You are helpful.".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(Echo)],
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("echo three times"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let seen = seen.lock().unwrap();
+    let count = |role: &str| seen.iter().filter(|m| m.role() == role).count();
+    assert_eq!(seen.len(), 6);
+    assert_eq!(
+        (count("user"), count("assistant"), count("toolResult")),
+        (1, 2, 3)
+    );
+    // Called in the order messages enter the context, once each.
+    assert_eq!(*seen, new_messages);
+}
@@ -95,6 +95,110 @@ async fn test_agent_with_tools() {
     assert_eq!(agent.messages().len(), 4);
 }
 
+#[tokio::test]
+async fn test_agent_with_skill_tools() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("deploy")).unwrap();
+    std::fs::write(
+        dir.path().join("deploy/SKILL.md"),
+        "---\nname: deploy\ndescription: Deploy the app.\n---\n\nRun make deploy.\n",
+    )
+    .unwrap();
+    let skills = yoagent::skills::SkillSet::load(&[dir.path()]).unwrap();
+
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "skill_deploy".into(),
+            arguments: serde_json::json!({"activate": true}),
+        }]),
+        MockResponse::Text("Deploying".into()),
+    ]);
+    let mut agent = Agent::from_provider(provider, ModelConfig::mock())
+        .with_system_prompt("test")
+        .with_skill_tools(skills);
+    assert!(agent
+        .system_prompt
+        .starts_with("test\n\n<available_skills>"));
+
+    let mut rx = agent.prompt("Deploy it").await;
+    while rx.recv().await.is_some() {}
+    agent.finish().await;
+
+    let AgentMessage::Llm(Message::ToolResult {
+        content, is_error, ..
+    }) = &agent.messages()[2]
+    else {
+        panic!("expected a tool result, got {:?}", agent.messages()[2]);
+    };
+    assert!(!is_error);
+    assert!(
+        matches!(&content[0], Content::Text { text } if text.contains("Run make deploy.")),
+        "{:?}",
+        content
+    );
+}
+
+/// Records the system prompt of each request, then answers from the mock.
+struct PromptCapturingProvider {
+    inner: MockProvider,
+    captured: Arc<std::sync::Mutex<Vec<String>>>,
+}
+
+#[async_trait::async_trait]
+impl yoagent::provider::StreamProvider for PromptCapturingProvider {
+    async fn stream(
+        &self,
+        config: yoagent::provider::StreamConfig,
+        tx: mpsc::UnboundedSender<yoagent::provider::StreamEvent>,
+        cancel: tokio_util::sync::CancellationToken,
+    ) -> Result<Message, yoagent::provider::ProviderError> {
+        self.captured
+            .lock()
+            .unwrap()
+            .push(config.system_prompt.clone());
+        self.inner.stream(config, tx, cancel).await
+    }
+}
+
+#[tokio::test]
+async fn test_run_streams_events_with_skills_in_system_prompt() {
+    use futures::StreamExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("deploy")).unwrap();
+    std::fs::write(
+        dir.path().join("deploy/SKILL.md"),
+        "---\nname: deploy\ndescription: Deploy the app.\n---\n\nRun make deploy.\n",
+    )
+    .unwrap();
+    let skills = yoagent::skills::SkillSet::load(&[dir.path()]).unwrap();
+
+    let captured = Arc::new(std::sync::Mutex::new(Vec::new()));
+    let provider = PromptCapturingProvider {
+        inner: MockProvider::text("On it."),
+        captured: captured.clone(),
+    };
+    let mut agent = Agent::from_provider(provider, ModelConfig::mock())
+        .with_system_prompt("You deploy things.")
+        .with_skills(skills);
+
+    let events: Vec<AgentEvent> = agent.run("Deploy it").await.collect().await;
+    assert!(matches!(events.first(), Some(AgentEvent::AgentStart)));
+    assert!(matches!(events.last(), Some(AgentEvent::AgentEnd { .. })));
+    agent.finish().await;
+    assert_eq!(agent.messages().len(), 2);
+
+    let prompts = captured.lock().unwrap();
+    assert_eq!(prompts.len(), 1);
+    assert!(
+        prompts[0].starts_with("You deploy things.\n\n<available_skills>"),
+        "{}",
+        prompts[0]
+    );
+    assert!(prompts[0].contains("<name>deploy</name>"), "{}", prompts[0]);
+}
+
 // Deliberately exercises the deprecated builder chain (`new` + `with_model` +
 // `with_api_key`) to keep coverage of that still-present API until 1.0.
 #[tokio::test]
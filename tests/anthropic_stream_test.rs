@@ -9,7 +9,8 @@ use tokio_util::sync::CancellationToken;
 use wiremock::matchers::{header, method, path};
 use wiremock::{Mock, MockServer, Request, ResponseTemplate};
 use yoagent::provider::{
-    AnthropicCompat, AnthropicProvider, ModelConfig, StreamConfig, StreamProvider,
+    AnthropicCompat, AnthropicProvider, ModelConfig, ServerTool, StreamConfig, StreamEvent,
+    StreamProvider,
 };
 use yoagent::types::*;
 
@@ -85,6 +86,31 @@ async fn refusal_stop_reason_maps_to_refusal_with_error_message() {
     );
 }
 
+#[tokio::test]
+async fn stop_sequence_is_sent_and_mapped_to_stop_sequence() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/messages"))
+        .and(wiremock::matchers::body_partial_json(
+            serde_json::json!({"stop_sequences": ["END"]}),
+        ))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_raw(sse_empty_with_stop("stop_sequence"), "text/event-stream"),
+        )
+        .mount(&server)
+        .await;
+
+    let mut config = stream_config(&server.uri(), None);
+    config.stop_sequences = Some(vec!["END".into()]);
+    let message = run_stream(config).await.expect("stream should succeed");
+
+    let Message::Assistant { stop_reason, .. } = &message else {
+        panic!("expected assistant message");
+    };
+    assert_eq!(*stop_reason, StopReason::StopSequence);
+}
+
 #[tokio::test]
 async fn context_window_exceeded_maps_to_overflow_error() {
     let server = MockServer::start().await;
@@ -202,3 +228,223 @@ async fn rate_limit_carries_retry_after_from_header() {
         other => panic!("expected RateLimited, got: {:?}", other),
     }
 }
+
+/// Recorded web search turn: the model calls `web_search`, Anthropic returns
+/// the results in the same stream, and the model answers.
+fn web_search_sse() -> String {
+    [
+        ("message_start", r#"{"type":"message_start","message":{"usage":{"input_tokens":2100,"output_tokens":3}}}"#),
+        ("content_block_start", r#"{"type":"content_block_start","index":0,"content_block":{"type":"server_tool_use","id":"srvtoolu_01","name":"web_search","input":{}}}"#),
+        ("content_block_delta", r#"{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"query\": \"rust 2024"}}"#),
+        ("content_block_delta", r#"{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":" edition\"}"}}"#),
+        ("content_block_stop", r#"{"type":"content_block_stop","index":0}"#),
+        ("content_block_start", r#"{"type":"content_block_start","index":1,"content_block":{"type":"web_search_tool_result","tool_use_id":"srvtoolu_01","content":[{"type":"web_search_result","title":"Rust 2024","url":"https://doc.rust-lang.org/edition-guide/rust-2024/","encrypted_content":"abc","page_age":null}]}}"#),
+        ("content_block_stop", r#"{"type":"content_block_stop","index":1}"#),
+        ("content_block_start", r#"{"type":"content_block_start","index":2,"content_block":{"type":"text","text":""}}"#),
+        ("content_block_delta", r#"{"type":"content_block_delta","index":2,"delta":{"type":"text_delta","text":"Rust 2024 shipped in 1.85."}}"#),
+        ("content_block_stop", r#"{"type":"content_block_stop","index":2}"#),
+        ("message_delta", r#"{"type":"message_delta","delta":{"stop_reason":"end_turn"},"usage":{"output_tokens":42,"server_tool_use":{"web_search_requests":1}}}"#),
+        ("message_stop", r#"{"type":"message_stop"}"#),
+    ]
+    .iter()
+    .map(|(event, data)| format!("event: {event}\ndata: {data}\n\n"))
+    .collect()
+}
+
+#[tokio::test]
+async fn server_tool_blocks_are_parsed_and_not_run_as_tool_calls() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/messages"))
+        .and(wiremock::matchers::body_partial_json(serde_json::json!({
+            "tools": [{"type": "web_search_20250305", "name": "web_search", "max_uses": 1}],
+        })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(web_search_sse(), "text/event-stream"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut config = stream_config(&server.uri(), None);
+    config.server_tools = vec![ServerTool::web_search().with_option("max_uses", 1)];
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let message = AnthropicProvider
+        .stream(config, tx, CancellationToken::new())
+        .await
+        .expect("stream should succeed");
+
+    let Message::Assistant {
+        content,
+        stop_reason,
+        ..
+    } = &message
+    else {
+        panic!("expected assistant message");
+    };
+    // The provider already ran the search: nothing is left for the loop.
+    assert_eq!(*stop_reason, StopReason::Stop);
+    assert_eq!(
+        content,
+        &vec![
+            Content::server_tool_use(
+                "srvtoolu_01",
+                "web_search",
+                serde_json::json!({"query": "rust 2024 edition"}),
+            ),
+            Content::server_tool_result(
+                "srvtoolu_01",
+                "web_search_tool_result",
+                serde_json::json!([{
+                    "type": "web_search_result",
+                    "title": "Rust 2024",
+                    "url": "https://doc.rust-lang.org/edition-guide/rust-2024/",
+                    "encrypted_content": "abc",
+                    "page_age": null,
+                }]),
+            ),
+            Content::Text {
+                text: "Rust 2024 shipped in 1.85.".into()
+            },
+        ]
+    );
+
+    while let Ok(event) = rx.try_recv() {
+        assert!(
+            !matches!(
+                event,
+                StreamEvent::ToolCallStart { .. } | StreamEvent::ToolCallDelta { .. }
+            ),
+            "server tool calls must not surface as client tool calls: {event:?}"
+        );
+    }
+
+    // Blocks survive a serde round trip, e.g. through a session store.
+    let json = serde_json::to_string(&message).unwrap();
+    assert!(json.contains(r#""type":"serverToolUse""#));
+    assert_eq!(serde_json::from_str::<Message>(&json).unwrap(), message);
+}
+
+/// Recorded turn that Anthropic paused while its web search was still
+/// running: the search call and results so far, then `pause_turn`.
+fn pause_turn_sse() -> String {
+    [
+        ("message_start", r#"{"type":"message_start","message":{"usage":{"input_tokens":2100,"output_tokens":3}}}"#),
+        ("content_block_start", r#"{"type":"content_block_start","index":0,"content_block":{"type":"server_tool_use","id":"srvtoolu_01","name":"web_search","input":{}}}"#),
+        ("content_block_delta", r#"{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"query\": \"rust 2024 edition\"}"}}"#),
+        ("content_block_stop", r#"{"type":"content_block_stop","index":0}"#),
+        ("content_block_start", r#"{"type":"content_block_start","index":1,"content_block":{"type":"web_search_tool_result","tool_use_id":"srvtoolu_01","content":[]}}"#),
+        ("content_block_stop", r#"{"type":"content_block_stop","index":1}"#),
+        ("message_delta", r#"{"type":"message_delta","delta":{"stop_reason":"pause_turn"},"usage":{"output_tokens":20,"server_tool_use":{"web_search_requests":1}}}"#),
+        ("message_stop", r#"{"type":"message_stop"}"#),
+    ]
+    .iter()
+    .map(|(event, data)| format!("event: {event}\ndata: {data}\n\n"))
+    .collect()
+}
+
+#[tokio::test]
+async fn pause_turn_maps_to_pause_turn_and_keeps_partial_content() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/messages"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_raw(pause_turn_sse(), "text/event-stream"),
+        )
+        .mount(&server)
+        .await;
+
+    let message = run_stream(stream_config(&server.uri(), None))
+        .await
+        .expect("stream should succeed");
+    let Message::Assistant {
+        content,
+        stop_reason,
+        ..
+    } = &message
+    else {
+        panic!("expected assistant message");
+    };
+    assert_eq!(*stop_reason, StopReason::PauseTurn);
+    assert_eq!(
+        content,
+        &vec![
+            Content::server_tool_use(
+                "srvtoolu_01",
+                "web_search",
+                serde_json::json!({"query": "rust 2024 edition"}),
+            ),
+            Content::server_tool_result(
+                "srvtoolu_01",
+                "web_search_tool_result",
+                serde_json::json!([]),
+            ),
+        ]
+    );
+}
+
+/// Answers the first request with [`pause_turn_sse`] and every later one
+/// with the finished web search turn.
+struct PauseThenFinish(std::sync::atomic::AtomicUsize);
+
+impl wiremock::Respond for PauseThenFinish {
+    fn respond(&self, _request: &Request) -> ResponseTemplate {
+        let body = match self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst) {
+            0 => pause_turn_sse(),
+            _ => web_search_sse(),
+        };
+        ResponseTemplate::new(200).set_body_raw(body, "text/event-stream")
+    }
+}
+
+#[tokio::test]
+async fn agent_loop_resumes_a_paused_turn() {
+    use std::sync::Arc;
+    use yoagent::agent_loop::{agent_loop, AgentLoopConfigBuilder};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/messages"))
+        .respond_with(PauseThenFinish(Default::default()))
+        .expect(2)
+        .mount(&server)
+        .await;
+
+    let mut mc = ModelConfig::anthropic("claude-sonnet-5", "Claude Sonnet 5");
+    mc.base_url = server.uri();
+    let config = AgentLoopConfigBuilder::new(Arc::new(AnthropicProvider), "claude-sonnet-5", "k")
+        .with_model_config(mc)
+        .with_server_tools(vec![ServerTool::web_search()])
+        .build();
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let new_messages = agent_loop(
+        vec![AgentMessage::Llm(Message::user("what's new in rust 2024?"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    let stop_reasons: Vec<StopReason> = new_messages
+        .iter()
+        .filter_map(|m| match m {
+            AgentMessage::Llm(Message::Assistant { stop_reason, .. }) => Some(stop_reason.clone()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(stop_reasons, [StopReason::PauseTurn, StopReason::Stop]);
+
+    // The follow-up call sends the paused assistant turn back verbatim.
+    let requests = server.received_requests().await.unwrap();
+    let body: serde_json::Value = serde_json::from_slice(&requests[1].body).unwrap();
+    let last = body["messages"].as_array().unwrap().last().unwrap().clone();
+    assert_eq!(last["role"], "assistant");
+    assert_eq!(last["content"][0]["type"], "server_tool_use");
+    assert_eq!(last["content"][1]["type"], "web_search_tool_result");
+}
@@ -0,0 +1,97 @@
+//! Request tests for `BedrockProvider` against a local mock server: the
+//! SigV4 headers and encoded path actually sent on the wire.
+
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use yoagent::provider::{ApiProtocol, BedrockProvider, ModelConfig, StreamConfig, StreamProvider};
+use yoagent::types::*;
+
+const MODEL: &str = "anthropic.claude-v2:1";
+
+fn stream_config(base_url: &str, api_key: &str) -> StreamConfig {
+    let mc = ModelConfig::custom(
+        ApiProtocol::BedrockConverseStream,
+        "bedrock",
+        base_url,
+        MODEL,
+        "Claude",
+    );
+    let mut config = StreamConfig::new(MODEL, api_key);
+    config.messages = vec![Message::user("hi")];
+    config.max_tokens = Some(256);
+    config.model_config = Some(mc);
+    config
+}
+
+async fn run_stream(config: StreamConfig) -> Result<Message, yoagent::provider::ProviderError> {
+    let (tx, _rx) = mpsc::unbounded_channel();
+    BedrockProvider
+        .stream(config, tx, CancellationToken::new())
+        .await
+}
+
+#[tokio::test]
+async fn request_is_sigv4_signed_with_session_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/model/anthropic.claude-v2%3A1/converse-stream"))
+        .and(header("x-amz-security-token", "session-token"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    run_stream(stream_config(&server.uri(), "AKID:secret:session-token"))
+        .await
+        .expect("stream should succeed");
+
+    let requests = server.received_requests().await.unwrap();
+    let headers = &requests[0].headers;
+    let auth = headers["authorization"].to_str().unwrap();
+    assert!(
+        auth.starts_with("AWS4-HMAC-SHA256 Credential=AKID/"),
+        "{auth}"
+    );
+    assert!(auth.contains("/bedrock/aws4_request, "), "{auth}");
+    assert!(
+        auth.contains(
+            "SignedHeaders=content-type;host;x-amz-date;x-amz-security-token, Signature="
+        ),
+        "{auth}"
+    );
+    assert!(headers.contains_key("x-amz-date"));
+}
+
+#[tokio::test]
+async fn caller_authorization_header_skips_signing() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("authorization", "Bearer proxy-token"))
+        .respond_with(ResponseTemplate::new(200))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mut config = stream_config(&server.uri(), "unused");
+    config
+        .model_config
+        .as_mut()
+        .unwrap()
+        .headers
+        .insert("authorization".into(), "Bearer proxy-token".into());
+    run_stream(config).await.expect("stream should succeed");
+
+    let requests = server.received_requests().await.unwrap();
+    assert!(!requests[0].headers.contains_key("x-amz-date"));
+}
+
+#[tokio::test]
+async fn malformed_credentials_are_an_auth_error() {
+    let result = run_stream(stream_config("http://127.0.0.1:9", "not-a-keypair")).await;
+    assert!(matches!(
+        result,
+        Err(yoagent::provider::ProviderError::Auth(_))
+    ));
+}
@@ -0,0 +1,319 @@
+//! Behavioral tests for `CohereProvider` against a local mock server.
+
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use wiremock::matchers::{header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+use yoagent::provider::{
+    CohereProvider, ModelConfig, ProviderError, StreamConfig, StreamEvent, StreamProvider,
+};
+use yoagent::types::*;
+
+const MODEL: &str = "command-r-plus";
+
+/// Cohere's v1 stream: one JSON event per line.
+fn events(lines: &[&str]) -> String {
+    lines.iter().map(|line| format!("{}\n", line)).collect()
+}
+
+fn stream_config(base_url: &str, messages: Vec<Message>) -> StreamConfig {
+    let mut mc = ModelConfig::cohere(MODEL, "Command R+");
+    mc.base_url = base_url.to_string();
+    let mut config = StreamConfig::new(MODEL, "test-key");
+    config.system_prompt = "test".into();
+    config.messages = messages;
+    config.model_config = Some(mc);
+    config
+}
+
+async fn mount(server: &MockServer, body: String) {
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .and(header("authorization", "Bearer test-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(body, "application/stream+json"))
+        .mount(server)
+        .await;
+}
+
+async fn run_stream(config: StreamConfig) -> (Result<Message, ProviderError>, Vec<StreamEvent>) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let result = CohereProvider
+        .stream(config, tx, CancellationToken::new())
+        .await;
+    let mut events = Vec::new();
+    while let Ok(event) = rx.try_recv() {
+        events.push(event);
+    }
+    (result, events)
+}
+
+#[tokio::test]
+async fn text_generation_streams_text_deltas() {
+    let server = MockServer::start().await;
+    mount(
+        &server,
+        events(&[
+            r#"{"is_finished":false,"event_type":"stream-start","generation_id":"g1"}"#,
+            r#"{"is_finished":false,"event_type":"text-generation","text":"Hello"}"#,
+            r#"{"is_finished":false,"event_type":"text-generation","text":", world"}"#,
+            r#"{"is_finished":true,"event_type":"stream-end","finish_reason":"COMPLETE","response":{"text":"Hello, world","meta":{"billed_units":{"input_tokens":12,"output_tokens":4},"tokens":{"input_tokens":80,"output_tokens":4}}}}"#,
+        ]),
+    )
+    .await;
+
+    let (result, events) =
+        run_stream(stream_config(&server.uri(), vec![Message::user("hi")])).await;
+    let Message::Assistant {
+        content,
+        stop_reason,
+        usage,
+        provider,
+        ..
+    } = result.expect("stream should succeed")
+    else {
+        panic!("expected assistant message");
+    };
+    assert_eq!(stop_reason, StopReason::Stop);
+    assert_eq!(provider, "cohere");
+    assert_eq!(
+        content,
+        vec![Content::Text {
+            text: "Hello, world".into()
+        }]
+    );
+    // `tokens` (the full prompt) wins over `billed_units`.
+    assert_eq!((usage.input, usage.output, usage.total_tokens), (80, 4, 84));
+
+    let deltas: Vec<&str> = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::TextDelta { delta, .. } => Some(delta.as_str()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(deltas, ["Hello", ", world"]);
+    assert!(matches!(events.last(), Some(StreamEvent::Done { .. })));
+}
+
+#[tokio::test]
+async fn tool_call_chunks_become_tool_calls() {
+    let server = MockServer::start().await;
+    mount(
+        &server,
+        events(&[
+            r#"{"event_type":"stream-start","generation_id":"g2"}"#,
+            r#"{"event_type":"tool-calls-chunk","text":"I will look up the weather."}"#,
+            r#"{"event_type":"tool-calls-chunk","tool_call_delta":{"index":0,"name":"get_weather"}}"#,
+            r#"{"event_type":"tool-calls-chunk","tool_call_delta":{"index":0,"parameters":"{\"city\": "}}"#,
+            r#"{"event_type":"tool-calls-chunk","tool_call_delta":{"index":0,"parameters":"\"Paris\"}"}}"#,
+            r#"{"event_type":"tool-calls-generation","text":"I will look up the weather.","tool_calls":[{"name":"get_weather","parameters":{"city":"Paris"}}]}"#,
+            r#"{"event_type":"stream-end","finish_reason":"COMPLETE","response":{"meta":{"tokens":{"input_tokens":30,"output_tokens":9}}}}"#,
+        ]),
+    )
+    .await;
+
+    let (result, events) = run_stream(stream_config(
+        &server.uri(),
+        vec![Message::user("weather?")],
+    ))
+    .await;
+    let Message::Assistant {
+        content,
+        stop_reason,
+        ..
+    } = result.expect("stream should succeed")
+    else {
+        panic!("expected assistant message");
+    };
+    assert_eq!(stop_reason, StopReason::ToolUse, "tool calls imply ToolUse");
+    let Content::ToolCall { id, .. } = &content[1] else {
+        panic!("expected a tool call, got {:?}", content[1]);
+    };
+    assert!(
+        id.starts_with("cohere-call-") && id.ends_with("-0"),
+        "{}",
+        id
+    );
+    assert_eq!(
+        content,
+        vec![
+            Content::Text {
+                text: "I will look up the weather.".into()
+            },
+            Content::tool_call(id, "get_weather", serde_json::json!({"city": "Paris"})),
+        ]
+    );
+
+    let args: String = events
+        .iter()
+        .filter_map(|e| match e {
+            StreamEvent::ToolCallDelta {
+                content_index,
+                delta,
+            } => {
+                assert_eq!(*content_index, 1);
+                Some(delta.as_str())
+            }
+            _ => None,
+        })
+        .collect();
+    assert_eq!(args, r#"{"city": "Paris"}"#);
+    assert!(events.iter().any(|e| matches!(
+        e,
+        StreamEvent::ToolCallStart { content_index: 1, id: start_id, name }
+            if name == "get_weather" && start_id == id
+    )));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, StreamEvent::ToolCallEnd { content_index: 1 })));
+}
+
+#[tokio::test]
+async fn tool_call_ids_are_unique_across_turns() {
+    let server = MockServer::start().await;
+    mount(
+        &server,
+        events(&[
+            r#"{"event_type":"tool-calls-generation","tool_calls":[{"name":"get_weather","parameters":{"city":"Paris"}}]}"#,
+            r#"{"event_type":"stream-end","finish_reason":"COMPLETE"}"#,
+        ]),
+    )
+    .await;
+
+    let mut ids = Vec::new();
+    for _ in 0..2 {
+        let (result, _) = run_stream(stream_config(
+            &server.uri(),
+            vec![Message::user("weather?")],
+        ))
+        .await;
+        let Message::Assistant { content, .. } = result.expect("stream should succeed") else {
+            panic!("expected assistant message");
+        };
+        ids.extend(content.into_iter().filter_map(|c| match c {
+            Content::ToolCall { id, .. } => Some(id),
+            _ => None,
+        }));
+    }
+    assert_eq!(ids.len(), 2);
+    assert_ne!(ids[0], ids[1], "each turn's first call needs its own id");
+}
+
+#[tokio::test]
+async fn tool_results_are_sent_with_their_call() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .and(wiremock::matchers::body_partial_json(serde_json::json!({
+            "message": "",
+            "chat_history": [
+                {"role": "USER", "message": "weather?"},
+                {"role": "CHATBOT", "tool_calls": [
+                    {"name": "get_weather", "parameters": {"city": "Paris"}}
+                ]},
+            ],
+            "tool_results": [{
+                "call": {"name": "get_weather", "parameters": {"city": "Paris"}},
+                "outputs": [{"result": "22C"}],
+            }],
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            events(&[
+                r#"{"event_type":"text-generation","text":"It is 22C."}"#,
+                r#"{"event_type":"stream-end","finish_reason":"COMPLETE"}"#,
+            ]),
+            "application/stream+json",
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let history = vec![
+        Message::user("weather?"),
+        Message::assistant(
+            vec![Content::tool_call(
+                "cohere-call-0",
+                "get_weather",
+                serde_json::json!({"city": "Paris"}),
+            )],
+            StopReason::ToolUse,
+            MODEL,
+            "cohere",
+            Usage::default(),
+        ),
+        Message::ToolResult {
+            tool_call_id: "cohere-call-0".into(),
+            tool_name: "get_weather".into(),
+            content: vec![Content::Text { text: "22C".into() }],
+            is_error: false,
+            timestamp: 0,
+        },
+    ];
+    let (result, _) = run_stream(stream_config(&server.uri(), history)).await;
+    let message = result.expect("stream should succeed");
+    assert_eq!(
+        message.content(),
+        [Content::Text {
+            text: "It is 22C.".into()
+        }]
+    );
+}
+
+#[tokio::test]
+async fn finish_reasons_map_to_stop_reasons() {
+    for (reason, expected) in [
+        ("MAX_TOKENS", StopReason::Length),
+        ("TOOL_CALL", StopReason::ToolUse),
+        ("STOP_SEQUENCE", StopReason::StopSequence),
+        ("ERROR", StopReason::Error),
+    ] {
+        let server = MockServer::start().await;
+        let end = format!(
+            r#"{{"event_type":"stream-end","finish_reason":"{}"}}"#,
+            reason
+        );
+        mount(
+            &server,
+            events(&[r#"{"event_type":"text-generation","text":"partial"}"#, &end]),
+        )
+        .await;
+
+        let (result, _) = run_stream(stream_config(&server.uri(), vec![Message::user("hi")])).await;
+        let Message::Assistant {
+            stop_reason,
+            error_message,
+            ..
+        } = result.expect("stream should succeed")
+        else {
+            panic!("expected assistant message");
+        };
+        assert_eq!(stop_reason, expected, "{reason}");
+        assert_eq!(error_message.is_some(), reason == "ERROR", "{reason}");
+    }
+}
+
+#[tokio::test]
+async fn truncated_stream_and_http_errors_fail() {
+    let server = MockServer::start().await;
+    mount(
+        &server,
+        events(&[r#"{"event_type":"text-generation","text":"cut off"}"#]),
+    )
+    .await;
+    let (result, _) = run_stream(stream_config(&server.uri(), vec![Message::user("hi")])).await;
+    assert!(
+        matches!(result, Err(ProviderError::Network(_))),
+        "{result:?}"
+    );
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/chat"))
+        .respond_with(
+            ResponseTemplate::new(401).set_body_string(r#"{"message":"invalid api token"}"#),
+        )
+        .mount(&server)
+        .await;
+    let (result, _) = run_stream(stream_config(&server.uri(), vec![Message::user("hi")])).await;
+    assert!(matches!(result, Err(ProviderError::Auth(_))), "{result:?}");
+}
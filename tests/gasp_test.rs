@@ -545,11 +545,14 @@ async fn tee_delivers_the_complete_event_stream() {
             AgentEvent::MessageStart { .. } => "MessageStart",
             AgentEvent::MessageUpdate { .. } => "MessageUpdate",
             AgentEvent::MessageEnd { .. } => "MessageEnd",
+            AgentEvent::ToolCallArgsUpdate { .. } => "ToolCallArgsUpdate",
+            AgentEvent::ToolApprovalRequired { .. } => "ToolApprovalRequired",
             AgentEvent::ToolExecutionStart { .. } => "ToolExecutionStart",
             AgentEvent::ToolExecutionUpdate { .. } => "ToolExecutionUpdate",
             AgentEvent::ToolExecutionEnd { .. } => "ToolExecutionEnd",
             AgentEvent::ProgressMessage { .. } => "ProgressMessage",
             AgentEvent::InputRejected { .. } => "InputRejected",
+            AgentEvent::ContextCompacted { .. } => "ContextCompacted",
         }
     }
 
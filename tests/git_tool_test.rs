@@ -0,0 +1,195 @@
+#![cfg(feature = "git")]
+
+//! GitTool against real repositories in temp dirs.
+
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+use yoagent::tools::GitTool;
+use yoagent::types::*;
+
+fn ctx() -> ToolContext {
+    ToolContext {
+        tool_call_id: "t1".into(),
+        tool_name: "git".into(),
+        cancel: CancellationToken::new(),
+        on_update: None,
+        on_progress: None,
+        timeout: None,
+    }
+}
+
+/// `git init` with an identity, one committed file and a `main` branch.
+async fn init_repo() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let repo = git2::Repository::init(dir.path()).unwrap();
+    repo.set_head("refs/heads/main").unwrap();
+    let mut config = repo.config().unwrap();
+    config.set_str("user.name", "Test User").unwrap();
+    config.set_str("user.email", "test@example.com").unwrap();
+    std::fs::write(dir.path().join("README.md"), "hello\n").unwrap();
+    run(
+        &tool(dir.path()),
+        serde_json::json!({"operation": "commit", "message": "Initial commit", "add_all": true}),
+    )
+    .await;
+    dir
+}
+
+async fn run(tool: &GitTool, params: serde_json::Value) -> (String, serde_json::Value) {
+    let result = tool.execute(params, ctx()).await.unwrap();
+    let Content::Text { text } = &result.content[0] else {
+        panic!("expected text");
+    };
+    (text.clone(), result.details)
+}
+
+fn tool(dir: &Path) -> GitTool {
+    GitTool::new().with_root(dir.to_str().unwrap())
+}
+
+#[tokio::test]
+async fn test_git_status_lists_changed_files() {
+    let dir = init_repo().await;
+    let git = tool(dir.path());
+
+    let (text, _) = run(&git, serde_json::json!({"operation": "status"})).await;
+    assert_eq!(text, "## main\nnothing to commit, working tree clean");
+
+    std::fs::write(dir.path().join("README.md"), "hello\nworld\n").unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(dir.path().join("src/new.rs"), "fn main() {}\n").unwrap();
+    let (text, details) = run(&git, serde_json::json!({"operation": "status"})).await;
+    assert_eq!(text, "## main\n M README.md\n?? src/new.rs");
+    assert_eq!(details["files"][0]["path"], "README.md");
+    assert_eq!(details["files"][0]["worktree"], "M");
+
+    let (text, _) = run(&git, serde_json::json!({"operation": "add", "path": "src"})).await;
+    assert!(
+        text.ends_with("## main\n M README.md\nA  src/new.rs"),
+        "{}",
+        text
+    );
+}
+
+#[tokio::test]
+async fn test_git_diff_returns_unified_diff() {
+    let dir = init_repo().await;
+    let git = tool(dir.path());
+
+    let (text, _) = run(&git, serde_json::json!({"operation": "diff"})).await;
+    assert_eq!(text, "No unstaged changes");
+
+    std::fs::write(dir.path().join("README.md"), "hello\nworld\n").unwrap();
+    let (text, details) = run(&git, serde_json::json!({"operation": "diff"})).await;
+    assert!(
+        text.starts_with("diff --git a/README.md b/README.md\n"),
+        "{}",
+        text
+    );
+    assert!(text.contains("@@ -1 +1,2 @@\n hello\n+world"), "{}", text);
+    assert_eq!(details["insertions"], 1);
+
+    // Staged view is empty until the change is added.
+    let (text, _) = run(
+        &git,
+        serde_json::json!({"operation": "diff", "staged": true}),
+    )
+    .await;
+    assert_eq!(text, "No staged changes");
+    run(&git, serde_json::json!({"operation": "add"})).await;
+    let (text, _) = run(
+        &git,
+        serde_json::json!({"operation": "diff", "staged": true}),
+    )
+    .await;
+    assert!(text.contains("+world"), "{}", text);
+
+    // A path limits the diff.
+    let (text, _) = run(
+        &git,
+        serde_json::json!({"operation": "diff", "staged": true, "path": "other.txt"}),
+    )
+    .await;
+    assert_eq!(text, "No staged changes");
+}
+
+#[tokio::test]
+async fn test_git_commit_and_log() {
+    let dir = init_repo().await;
+    let git = tool(dir.path());
+
+    let err = git
+        .execute(
+            serde_json::json!({"operation": "commit", "message": "Empty"}),
+            ctx(),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("nothing to commit"), "{}", err);
+
+    std::fs::write(dir.path().join("notes.txt"), "a\nb\n").unwrap();
+    let (text, details) = run(
+        &git,
+        serde_json::json!({"operation": "commit", "message": "Add notes\n\nLonger body.", "add_all": true}),
+    )
+    .await;
+    let id = details["commit"].as_str().unwrap();
+    assert_eq!(
+        text,
+        format!(
+            "[main {}] Add notes\n1 file changed, 2 insertions(+), 0 deletions(-)",
+            &id[..7]
+        )
+    );
+
+    let (text, _) = run(&git, serde_json::json!({"operation": "log"})).await;
+    let lines: Vec<_> = text.lines().collect();
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0], format!("{} Add notes (Test User)", &id[..7]));
+    assert!(lines[1].ends_with(" Initial commit (Test User)"));
+
+    let (text, _) = run(&git, serde_json::json!({"operation": "log", "limit": 1})).await;
+    assert_eq!(text.lines().count(), 1);
+}
+
+#[tokio::test]
+async fn test_git_branch_and_checkout() {
+    let dir = init_repo().await;
+    let git = tool(dir.path());
+
+    let (text, _) = run(
+        &git,
+        serde_json::json!({"operation": "branch", "name": "feature"}),
+    )
+    .await;
+    assert!(text.starts_with("Created branch feature at "), "{}", text);
+
+    let (text, _) = run(
+        &git,
+        serde_json::json!({"operation": "checkout", "name": "feature"}),
+    )
+    .await;
+    assert_eq!(text, "Switched to branch 'feature'");
+    let (text, details) = run(&git, serde_json::json!({"operation": "branch"})).await;
+    assert_eq!(text, "* feature\n  main");
+    assert_eq!(details["current"], "feature");
+
+    let err = git
+        .execute(
+            serde_json::json!({"operation": "checkout", "name": "missing"}),
+            ctx(),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::Failed(_)), "{}", err);
+}
+
+#[tokio::test]
+async fn test_git_outside_a_repository_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let err = tool(dir.path())
+        .execute(serde_json::json!({"operation": "status"}), ctx())
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::Failed(_)), "{}", err);
+}
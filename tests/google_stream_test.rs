@@ -9,7 +9,9 @@ use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use wiremock::matchers::{method, path};
 use wiremock::{Mock, MockServer, ResponseTemplate};
-use yoagent::provider::{GoogleProvider, ModelConfig, StreamConfig, StreamProvider};
+use yoagent::provider::{
+    ApiProtocol, GoogleProvider, GoogleVertexProvider, ModelConfig, StreamConfig, StreamProvider,
+};
 use yoagent::types::*;
 
 const MODEL: &str = "gemini-2.5-flash";
@@ -382,3 +384,126 @@ async fn thought_parts_map_to_thinking_content() {
         .expect("answer text");
     assert_eq!(text, "The answer is 4.");
 }
+
+/// With candidateCount > 1 Gemini streams alternatives side by side, in any
+/// order and sometimes alone in a chunk; only candidate 0 may land in the
+/// message.
+#[tokio::test]
+async fn only_candidate_zero_is_used() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!(
+            "/v1beta/models/{}:streamGenerateContent",
+            MODEL
+        )))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            sse(&[
+                r#"{"candidates":[{"content":{"parts":[{"text":"second, "}],"role":"model"},"index":1}]}"#,
+                r#"{"candidates":[{"content":{"parts":[{"text":"second"}],"role":"model"},"finishReason":"MAX_TOKENS","index":1},{"content":{"parts":[{"text":"first"}],"role":"model"},"finishReason":"STOP","index":0}]}"#,
+            ]),
+            "text/event-stream",
+        ))
+        .mount(&server)
+        .await;
+
+    let message = run_stream(stream_config(&server.uri(), vec![Message::user("hi")])).await;
+
+    let Message::Assistant {
+        content,
+        stop_reason,
+        ..
+    } = &message
+    else {
+        panic!("expected assistant message");
+    };
+    assert_eq!(
+        content,
+        &vec![Content::Text {
+            text: "first".into()
+        }]
+    );
+    assert_eq!(*stop_reason, StopReason::Stop);
+}
+
+/// Vertex streams the same payloads as the Gemini API and shares its parser,
+/// so safety blocks and thought parts map identically.
+#[tokio::test]
+async fn vertex_shares_gemini_stream_parsing() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!("/{}:streamGenerateContent", MODEL)))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            sse(&[
+                r#"{"candidates":[{"content":{"parts":[{"text":"Hmm...","thought":true}],"role":"model"},"index":0}]}"#,
+                r#"{"candidates":[{"content":{"parts":[],"role":"model"},"finishReason":"SAFETY","index":0}]}"#,
+            ]),
+            "text/event-stream",
+        ))
+        .mount(&server)
+        .await;
+
+    let mut mc = ModelConfig::google(MODEL, "Gemini 2.5 Flash");
+    mc.api = ApiProtocol::GoogleVertex;
+    mc.provider = "google_vertex".into();
+    mc.base_url = server.uri();
+    let mut config = StreamConfig::new(MODEL, "oauth-token");
+    config.messages = vec![Message::user("hi")];
+    config.model_config = Some(mc);
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let message = GoogleVertexProvider
+        .stream(config, tx, CancellationToken::new())
+        .await
+        .expect("stream should succeed");
+
+    let Message::Assistant {
+        content,
+        stop_reason,
+        provider,
+        ..
+    } = &message
+    else {
+        panic!("expected assistant message");
+    };
+    assert_eq!(*stop_reason, StopReason::Refusal);
+    assert_eq!(provider, "google_vertex");
+    assert!(matches!(&content[0], Content::Thinking { thinking, .. } if thinking == "Hmm..."));
+}
+
+/// A 429 from the Gemini API must carry the Retry-After delay (this path
+/// reads the response directly rather than through the SSE client).
+#[tokio::test]
+async fn rate_limit_reads_retry_after_header() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path(format!(
+            "/v1beta/models/{}:streamGenerateContent",
+            MODEL
+        )))
+        .respond_with(
+            ResponseTemplate::new(429)
+                .insert_header("retry-after", "12")
+                .set_body_string(r#"{"error":{"code":429,"status":"RESOURCE_EXHAUSTED"}}"#),
+        )
+        .mount(&server)
+        .await;
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    let err = GoogleProvider
+        .stream(
+            stream_config(&server.uri(), vec![Message::user("hi")]),
+            tx,
+            CancellationToken::new(),
+        )
+        .await
+        .expect_err("429 must surface as an error");
+    assert!(
+        matches!(
+            err,
+            yoagent::provider::ProviderError::RateLimited {
+                retry_after_ms: Some(12_000)
+            }
+        ),
+        "got: {err:?}"
+    );
+}
@@ -4,7 +4,7 @@
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use yoagent::agent_loop::{agent_loop, AgentLoopConfig};
-use yoagent::provider::{GoogleProvider, ModelConfig};
+use yoagent::provider::{GoogleProvider, ModelConfig, ResponseFormat, ToolChoice};
 use yoagent::tools;
 use yoagent::types::*;
 
@@ -34,13 +34,32 @@ fn make_config(model: &str) -> AgentLoopConfig {
             strategy: CacheStrategy::Disabled,
         },
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::default(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     }
 }
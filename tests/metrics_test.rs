@@ -0,0 +1,300 @@
+//! Tests that the loop reports turns and tool calls to a `MetricsSink`.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
+use yoagent::agent_loop::{agent_loop, AgentLoopConfig, AgentLoopConfigBuilder};
+use yoagent::metrics::MetricsSink;
+use yoagent::provider::mock::*;
+use yoagent::provider::{MockProvider, ProviderError, StreamConfig, StreamEvent, StreamProvider};
+use yoagent::*;
+
+/// Reports fixed usage on every response it forwards from a `MockProvider`.
+struct UsageProvider(MockProvider);
+
+#[async_trait::async_trait]
+impl StreamProvider for UsageProvider {
+    async fn stream(
+        &self,
+        config: StreamConfig,
+        tx: mpsc::UnboundedSender<StreamEvent>,
+        cancel: CancellationToken,
+    ) -> Result<Message, ProviderError> {
+        let mut message = self.0.stream(config, tx, cancel).await?;
+        if let Message::Assistant { usage, .. } = &mut message {
+            *usage = Usage {
+                input: 100,
+                output: 20,
+                cache_read: 50,
+                cache_write: 0,
+                total_tokens: 170,
+            };
+        }
+        Ok(message)
+    }
+}
+
+struct FlakyTool;
+
+#[async_trait::async_trait]
+impl AgentTool for FlakyTool {
+    fn name(&self) -> &str {
+        "flaky"
+    }
+    fn label(&self) -> &str {
+        "Flaky"
+    }
+    fn description(&self) -> &str {
+        "fails when asked to"
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({"type": "object"})
+    }
+    async fn execute(
+        &self,
+        params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        if params["fail"].as_bool() == Some(true) {
+            return Err(ToolError::Failed("asked to fail".into()));
+        }
+        Ok(ToolResult {
+            content: vec![Content::Text { text: "ok".into() }],
+            details: serde_json::Value::Null,
+        })
+    }
+}
+
+fn flaky_call(fail: bool) -> MockToolCall {
+    MockToolCall {
+        provider_metadata: None,
+        name: "flaky".into(),
+        arguments: serde_json::json!({ "fail": fail }),
+    }
+}
+
+/// Two tool turns (one call ok, then one ok and one failing) and a final
+/// text turn.
+fn config_with(sink: Arc<dyn MetricsSink>) -> AgentLoopConfig {
+    let provider = UsageProvider(MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![flaky_call(false)]),
+        MockResponse::ToolCalls(vec![flaky_call(false), flaky_call(true)]),
+        MockResponse::Text("done".into()),
+    ]));
+    AgentLoopConfigBuilder::new(Arc::new(provider), "mock-model", "key")
+        .with_metrics_sink(sink)
+        .build()
+}
+
+async fn run(config: &AgentLoopConfig) {
+    let mut context = AgentContext {
+        system_prompt: "test".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(FlakyTool)],
+    };
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+}
+
+#[derive(Default)]
+struct RecordingSink {
+    turns: Mutex<Vec<(String, u64)>>,
+    tool_calls: Mutex<Vec<(String, bool)>>,
+}
+
+impl MetricsSink for RecordingSink {
+    fn record_turn(&self, model: &str, tokens: &Usage, _duration: Duration) {
+        self.turns
+            .lock()
+            .unwrap()
+            .push((model.to_string(), tokens.input));
+    }
+
+    fn record_tool_call(&self, tool_name: &str, is_error: bool, _duration: Duration) {
+        self.tool_calls
+            .lock()
+            .unwrap()
+            .push((tool_name.to_string(), is_error));
+    }
+}
+
+#[tokio::test]
+async fn test_loop_reports_turns_and_tool_calls() {
+    let sink = Arc::new(RecordingSink::default());
+    run(&config_with(sink.clone())).await;
+
+    assert_eq!(
+        *sink.turns.lock().unwrap(),
+        vec![("mock-model".to_string(), 100); 3]
+    );
+    let mut tool_calls = sink.tool_calls.lock().unwrap().clone();
+    tool_calls.sort();
+    assert_eq!(
+        tool_calls,
+        vec![
+            ("flaky".to_string(), false),
+            ("flaky".to_string(), false),
+            ("flaky".to_string(), true),
+        ]
+    );
+}
+
+#[cfg(feature = "prometheus")]
+#[tokio::test]
+async fn test_prometheus_metrics_count_turns_tokens_and_tools() {
+    use yoagent::metrics::PrometheusMetrics;
+
+    let metrics = Arc::new(PrometheusMetrics::new());
+    run(&config_with(metrics.clone())).await;
+
+    let families = metrics.registry().gather();
+    let family = |name: &str| {
+        families
+            .iter()
+            .find(|f| f.name() == name)
+            .unwrap_or_else(|| panic!("{} not registered", name))
+    };
+    let label = |m: &prometheus::proto::Metric, name: &str| {
+        m.get_label()
+            .iter()
+            .find(|l| l.name() == name)
+            .map(|l| l.value().to_string())
+            .unwrap()
+    };
+
+    let turns = family("yoagent_turns_total").get_metric();
+    assert_eq!(turns.len(), 1);
+    assert_eq!(label(&turns[0], "model"), "mock-model");
+    assert_eq!(turns[0].get_counter().get_value(), 3.0);
+
+    let tokens: Vec<(String, f64)> = family("yoagent_token_usage_total")
+        .get_metric()
+        .iter()
+        .map(|m| (label(m, "type"), m.get_counter().get_value()))
+        .collect();
+    for (kind, value) in [("input", 300.0), ("output", 60.0), ("cache_read", 150.0)] {
+        assert!(
+            tokens.contains(&(kind.to_string(), value)),
+            "{}: {:?}",
+            kind,
+            tokens
+        );
+    }
+
+    let tools: Vec<(String, String, u64)> = family("yoagent_tool_call_duration_seconds")
+        .get_metric()
+        .iter()
+        .map(|m| {
+            (
+                label(m, "tool"),
+                label(m, "status"),
+                m.get_histogram().get_sample_count(),
+            )
+        })
+        .collect();
+    assert_eq!(tools.len(), 2);
+    assert!(
+        tools.contains(&("flaky".into(), "ok".into(), 2)),
+        "{:?}",
+        tools
+    );
+    assert!(
+        tools.contains(&("flaky".into(), "error".into(), 1)),
+        "{:?}",
+        tools
+    );
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn test_prometheus_metrics_reject_double_registration() {
+    use yoagent::metrics::PrometheusMetrics;
+
+    let first = PrometheusMetrics::new();
+    assert!(PrometheusMetrics::with_registry(first.registry().clone()).is_err());
+}
+
+#[tokio::test]
+async fn test_audit_log_records_every_tool_call() {
+    use yoagent::tools::{AuditEntry, AuditLog};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let audit = Arc::new(AuditLog::new(&path).unwrap().with_session_id("s-1"));
+    run(&config_with(audit.clone())).await;
+    audit.flush().await.unwrap();
+
+    let entries: Vec<AuditEntry> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(entries.len(), 3);
+    assert!(entries
+        .iter()
+        .all(|e| e.session_id == "s-1" && e.tool_name == "flaky" && e.timestamp_ms > 0));
+    assert_eq!(entries[0].args, serde_json::json!({ "fail": false }));
+    assert_eq!(entries[0].result_summary, "ok");
+    assert!(!entries[0].is_error);
+
+    let failed: Vec<_> = entries.iter().filter(|e| e.is_error).collect();
+    assert_eq!(failed.len(), 1);
+    assert_eq!(failed[0].args, serde_json::json!({ "fail": true }));
+    assert!(failed[0].result_summary.contains("asked to fail"));
+
+    // Reopening appends rather than truncating.
+    let again = AuditLog::new(&path).unwrap();
+    again.record(entries[0].clone());
+    again.flush().await.unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), 4);
+}
+
+#[tokio::test]
+async fn test_audit_log_tail_follows_new_entries() {
+    use futures::StreamExt;
+    use yoagent::tools::{AuditEntry, AuditLog};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let audit = AuditLog::new(&path).unwrap();
+    let entry = |id: &str, timestamp_ms: u64| AuditEntry {
+        timestamp_ms,
+        session_id: audit.session_id().to_string(),
+        tool_call_id: id.into(),
+        tool_name: "bash".into(),
+        args: serde_json::json!({ "command": "ls" }),
+        result_summary: "Cargo.toml".into(),
+        is_error: false,
+        duration_ms: 3,
+    };
+    audit.record(entry("old", 1_000));
+    audit.record(entry("kept", 2_000));
+    audit.flush().await.unwrap();
+
+    let mut tail = Box::pin(AuditLog::tail(&path, 1_500));
+    assert_eq!(
+        tokio::time::timeout(Duration::from_secs(5), tail.next())
+            .await
+            .unwrap()
+            .unwrap(),
+        entry("kept", 2_000)
+    );
+
+    // Appended after the tail caught up.
+    audit.record(entry("live", 3_000));
+    assert_eq!(
+        tokio::time::timeout(Duration::from_secs(5), tail.next())
+            .await
+            .unwrap()
+            .unwrap(),
+        entry("live", 3_000)
+    );
+}
@@ -9,9 +9,11 @@
 
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
-use wiremock::matchers::{method, path};
+use wiremock::matchers::{header, method, path, query_param};
 use wiremock::{Mock, MockServer, ResponseTemplate};
-use yoagent::provider::{ModelConfig, OpenAiCompatProvider, StreamConfig, StreamProvider};
+use yoagent::provider::{
+    ModelConfig, OpenAiCompat, OpenAiCompatProvider, StreamConfig, StreamProvider,
+};
 use yoagent::types::*;
 
 fn chunk(json: &str) -> String {
@@ -134,3 +136,48 @@ async fn test_usage_chunk_after_finish_reason_survives_doneless_close() {
     );
     assert_eq!(usage.output, 3);
 }
+
+/// Azure deployment routing: deployment path, api-version query and an
+/// `api-key` header instead of Bearer auth.
+#[tokio::test]
+async fn test_azure_deployment_url_and_auth_header() {
+    let server = MockServer::start().await;
+    let body = [
+        chunk(r#"{"choices":[{"delta":{"content":"Hi"},"index":0}]}"#),
+        chunk(r#"{"choices":[{"delta":{},"finish_reason":"stop","index":0}]}"#),
+        "data: [DONE]\n\n".to_string(),
+    ]
+    .concat();
+    Mock::given(method("POST"))
+        .and(path("/openai/deployments/gpt4o-prod/chat/completions"))
+        .and(query_param("api-version", "2024-10-21"))
+        .and(header("api-key", "azure-key"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(body, "text/event-stream"),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let mc = ModelConfig::openai_compat(
+        server.uri(),
+        "gpt-4o",
+        "azure",
+        OpenAiCompat::azure("gpt4o-prod"),
+    );
+    let mut config = StreamConfig::new("gpt-4o", "azure-key");
+    config.messages = vec![Message::user("hi")];
+    config.model_config = Some(mc);
+
+    let message = run_stream(config)
+        .await
+        .expect("azure request should match");
+    match message {
+        Message::Assistant { content, .. } => {
+            assert!(matches!(&content[0], Content::Text { text } if text == "Hi"));
+        }
+        other => panic!("expected assistant message, got {other:?}"),
+    }
+}
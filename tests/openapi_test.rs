@@ -94,6 +94,7 @@ fn test_ctx() -> ToolContext {
         cancel: tokio_util::sync::CancellationToken::new(),
         on_update: None,
         on_progress: None,
+        timeout: None,
     }
 }
 
@@ -540,3 +541,52 @@ async fn test_execute_path_param_url_encoded() {
 
     assert_eq!(result.details["status"], 200);
 }
+
+#[tokio::test]
+async fn test_tools_from_openapi_json_value() {
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+    use yoagent::openapi::OpenApiAuth;
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/items"))
+        .and(query_param("limit", "2"))
+        .and(header("Authorization", "Bearer tok"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("[1,2]"))
+        .mount(&server)
+        .await;
+
+    // The spec's own server is ignored in favour of the explicit base URL.
+    let spec: serde_json::Value = serde_json::from_str(SPEC).unwrap();
+    let tools = yoagent::tools::openapi::tools_from_openapi(
+        spec,
+        &server.uri(),
+        OpenApiAuth::Bearer("tok".into()),
+    )
+    .unwrap();
+    assert_eq!(tools.len(), 4);
+
+    let list_items = tools.iter().find(|t| t.name() == "listItems").unwrap();
+    assert_eq!(
+        list_items.description(),
+        "Returns a paginated list of items."
+    );
+    let result = list_items
+        .execute(serde_json::json!({"limit": 2}), test_ctx())
+        .await
+        .unwrap();
+    assert_eq!(result.details["status"], 200);
+}
+
+#[test]
+fn test_tools_from_openapi_rejects_invalid_spec() {
+    let err = yoagent::tools::openapi::tools_from_openapi(
+        serde_json::json!({"not": "a spec"}),
+        "",
+        Default::default(),
+    )
+    .err()
+    .unwrap();
+    assert!(matches!(err, yoagent::openapi::OpenApiError::ParseError(_)));
+}
@@ -95,6 +95,15 @@ fn test_content_variants_roundtrip() {
         data: "base64data".into(),
         mime_type: "image/png".into(),
     });
+    roundtrip(&Content::Document {
+        data: vec![0x25, 0x50, 0x44, 0x46, 0xff],
+        mime_type: "application/pdf".into(),
+        filename: "spec.pdf".into(),
+    });
+    roundtrip(&Content::DocumentUrl {
+        url: "https://example.com/spec.pdf".into(),
+        mime_type: "application/pdf".into(),
+    });
     roundtrip(&Content::thinking_signed("let me think...", "sig123"));
     roundtrip(&Content::thinking("unsigned thought"));
     roundtrip(&Content::tool_call(
@@ -104,6 +113,25 @@ fn test_content_variants_roundtrip() {
     ));
 }
 
+#[test]
+fn test_document_data_is_base64_on_the_wire() {
+    let json = serde_json::to_value(Content::Document {
+        data: b"%PDF-1.7".to_vec(),
+        mime_type: "application/pdf".into(),
+        filename: "spec.pdf".into(),
+    })
+    .unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "type": "document",
+            "data": "JVBERi0xLjc=",
+            "mimeType": "application/pdf",
+            "filename": "spec.pdf",
+        })
+    );
+}
+
 // ---------------------------------------------------------------------------
 // Full conversation
 // ---------------------------------------------------------------------------
@@ -169,12 +197,16 @@ fn test_execution_limits_roundtrip() {
         max_turns: 25,
         max_total_tokens: 500_000,
         max_duration: std::time::Duration::from_secs(300),
+        turn_timeout: Some(std::time::Duration::from_secs(30)),
+        max_cost_usd: Some(2.5),
     };
     let json = serde_json::to_string(&limits).expect("serialize");
     let back: ExecutionLimits = serde_json::from_str(&json).expect("deserialize");
     assert_eq!(limits.max_turns, back.max_turns);
     assert_eq!(limits.max_total_tokens, back.max_total_tokens);
     assert_eq!(limits.max_duration, back.max_duration);
+    assert_eq!(limits.turn_timeout, back.turn_timeout);
+    assert_eq!(limits.max_cost_usd, back.max_cost_usd);
 }
 
 #[test]
@@ -182,6 +214,7 @@ fn test_tool_execution_strategy_roundtrip() {
     roundtrip(&ToolExecutionStrategy::Sequential);
     roundtrip(&ToolExecutionStrategy::Parallel);
     roundtrip(&ToolExecutionStrategy::Batched { size: 4 });
+    roundtrip(&ToolExecutionStrategy::ThrottledParallel { limit: 8 });
 }
 
 #[test]
@@ -204,6 +237,15 @@ fn test_refusal_stop_reason_round_trip() {
     assert_eq!(StopReason::Refusal.to_string(), "refusal");
 }
 
+#[test]
+fn test_stop_sequence_stop_reason_wire_name() {
+    assert_eq!(
+        serde_json::to_value(StopReason::StopSequence).unwrap(),
+        "stopSequence"
+    );
+    assert_eq!(StopReason::StopSequence.to_string(), "stopSequence");
+}
+
 #[test]
 fn test_constructors_pin_fields() {
     // The non_exhaustive variants make these the mandated construction path
@@ -325,6 +367,16 @@ fn all_agent_events() -> Vec<AgentEvent> {
         AgentEvent::MessageEnd {
             message: sample_assistant(),
         },
+        AgentEvent::ToolCallArgsUpdate {
+            tool_call_id: "tc-1".into(),
+            tool_name: "bash".into(),
+            partial_args: serde_json::json!({"command": "l"}),
+        },
+        AgentEvent::ToolApprovalRequired {
+            tool_call_id: "tc-1".into(),
+            tool_name: "bash".into(),
+            args: serde_json::json!({"command": "rm -rf build"}),
+        },
         AgentEvent::ToolExecutionStart {
             tool_call_id: "tc-1".into(),
             tool_name: "bash".into(),
@@ -349,6 +401,13 @@ fn all_agent_events() -> Vec<AgentEvent> {
         AgentEvent::InputRejected {
             reason: "injection detected".into(),
         },
+        AgentEvent::ContextCompacted {
+            messages_before: 120,
+            messages_after: 40,
+            tokens_before: 120_000,
+            tokens_after: 80_000,
+            level: CompactionLevel::Level2,
+        },
     ]
 }
 
@@ -389,11 +448,14 @@ fn expected_event_tag(event: &AgentEvent) -> &'static str {
         AgentEvent::MessageStart { .. } => "messageStart",
         AgentEvent::MessageUpdate { .. } => "messageUpdate",
         AgentEvent::MessageEnd { .. } => "messageEnd",
+        AgentEvent::ToolCallArgsUpdate { .. } => "toolCallArgsUpdate",
+        AgentEvent::ToolApprovalRequired { .. } => "toolApprovalRequired",
         AgentEvent::ToolExecutionStart { .. } => "toolExecutionStart",
         AgentEvent::ToolExecutionUpdate { .. } => "toolExecutionUpdate",
         AgentEvent::ToolExecutionEnd { .. } => "toolExecutionEnd",
         AgentEvent::ProgressMessage { .. } => "progressMessage",
         AgentEvent::InputRejected { .. } => "inputRejected",
+        AgentEvent::ContextCompacted { .. } => "contextCompacted",
     }
 }
 
@@ -407,7 +469,7 @@ fn expected_delta_tag(delta: &StreamDelta) -> &'static str {
 }
 
 /// Number of arms in `expected_event_tag` — bump together with the match.
-const EVENT_VARIANT_COUNT: usize = 12;
+const EVENT_VARIANT_COUNT: usize = 15;
 
 #[test]
 fn test_agent_event_type_tags_are_frozen() {
@@ -602,6 +664,18 @@ fn test_agent_event_fields_are_camel_case() {
     };
     let v = serde_json::to_value(&rejected).expect("serialize");
     assert_eq!(v["reason"], "nope");
+
+    let compacted = AgentEvent::ContextCompacted {
+        messages_before: 30,
+        messages_after: 6,
+        tokens_before: 9_000,
+        tokens_after: 4_000,
+        level: CompactionLevel::Level3,
+    };
+    let v = serde_json::to_value(&compacted).expect("serialize");
+    assert_eq!(v["messagesAfter"], 6);
+    assert_eq!(v["tokensBefore"], 9_000);
+    assert_eq!(v["level"], "level3");
 }
 
 /// Unit variants carry only the tag: `{"type":"agentStart"}`.
@@ -630,3 +704,287 @@ fn test_agent_event_deserializes_from_raw_json() {
     assert_eq!(tool_name, "read");
     assert_eq!(args["path"], "a.rs");
 }
+
+#[test]
+fn test_message_parse_json_roundtrip() {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Answer {
+        city: String,
+        population: u64,
+    }
+    let expected = Answer {
+        city: "Lisbon".into(),
+        population: 545_000,
+    };
+    let json = serde_json::to_string(&expected).unwrap();
+    let (head, tail) = json.split_at(10);
+
+    // Text split across blocks and wrapped in a code fence.
+    let msg = Message::assistant(
+        vec![
+            Content::Text {
+                text: format!("```json\n{head}"),
+            },
+            Content::Text {
+                text: format!("{tail}\n```"),
+            },
+        ],
+        StopReason::Stop,
+        "gpt-4o",
+        "openai",
+        Usage::default(),
+    );
+    let restored: Message = serde_json::from_str(&serde_json::to_string(&msg).unwrap()).unwrap();
+    assert_eq!(restored.parse_json::<Answer>().unwrap(), expected);
+
+    assert!(Message::user(json).parse_json::<Answer>().is_err());
+}
+
+#[test]
+fn test_response_format_wire_names() {
+    use yoagent::provider::ResponseFormat;
+    assert_eq!(
+        serde_json::to_value(ResponseFormat::JsonObject).unwrap(),
+        "json_object"
+    );
+    assert_eq!(serde_json::to_value(ResponseFormat::Text).unwrap(), "text");
+}
+
+// ---------------------------------------------------------------------------
+// Conversation persistence
+// ---------------------------------------------------------------------------
+
+fn sample_conversation() -> Vec<AgentMessage> {
+    vec![
+        AgentMessage::Llm(Message::user("list the files")),
+        AgentMessage::Llm(Message::assistant(
+            vec![
+                Content::thinking_signed("use bash", "sig"),
+                Content::tool_call("tc-1", "bash", serde_json::json!({"command": "ls"})),
+            ],
+            StopReason::ToolUse,
+            "claude-sonnet",
+            "anthropic",
+            Usage {
+                input: 120,
+                output: 30,
+                ..Default::default()
+            },
+        )),
+        AgentMessage::Llm(Message::ToolResult {
+            tool_call_id: "tc-1".into(),
+            tool_name: "bash".into(),
+            content: vec![Content::Text {
+                text: "ls: permission denied".into(),
+            }],
+            is_error: true,
+            timestamp: 42,
+        }),
+        AgentMessage::Extension(ExtensionMessage::new(
+            "status",
+            serde_json::json!({"phase": "retrying"}),
+        )),
+    ]
+}
+
+#[test]
+fn test_conversation_session_roundtrip() {
+    let session = ConversationSession::new("You are helpful.", sample_conversation());
+    assert!(!session.session_id.is_empty());
+    assert!(session.created_at > 0);
+
+    let json = serde_json::to_string(&session).unwrap();
+    let back: ConversationSession = serde_json::from_str(&json).unwrap();
+    assert_eq!(back, session);
+    assert_eq!(
+        yoagent::context::total_tokens(&back.messages),
+        yoagent::context::total_tokens(&session.messages)
+    );
+}
+
+#[test]
+fn test_agent_context_json_reattaches_tools() {
+    let context = AgentContext {
+        system_prompt: "You are helpful.".into(),
+        messages: sample_conversation(),
+        tools: vec![Box::new(yoagent::tools::BashTool::new())],
+    };
+    let json = context.to_json().unwrap();
+    assert!(!json.contains("\"tools\""));
+
+    let restored =
+        AgentContext::from_json(&json, vec![Box::new(yoagent::tools::BashTool::new())]).unwrap();
+    assert_eq!(restored.system_prompt, context.system_prompt);
+    assert_eq!(restored.messages, context.messages);
+    assert_eq!(restored.tools.len(), 1);
+
+    // A saved ConversationSession loads the same way.
+    let session_json = serde_json::to_string(&ConversationSession::from_context(&context)).unwrap();
+    let restored = AgentContext::from_json(&session_json, Vec::new()).unwrap();
+    assert_eq!(restored.messages, context.messages);
+}
+
+#[test]
+fn test_agent_context_messages_roundtrip_through_value() {
+    let mut messages = sample_conversation();
+    messages.push(AgentMessage::Llm(
+        Message::assistant(
+            vec![Content::Text {
+                text: "You lack permission.".into(),
+            }],
+            StopReason::Stop,
+            "claude-sonnet",
+            "anthropic",
+            Usage::default(),
+        )
+        .with_timestamp(1_700_000_000_123),
+    ));
+    let context = AgentContext {
+        system_prompt: "You are helpful.".into(),
+        messages,
+        tools: vec![Box::new(yoagent::tools::BashTool::new())],
+    };
+
+    // Through a string, as it would be stored.
+    let saved = serde_json::to_string(&context.save_messages()).unwrap();
+    let mut restored = AgentContext {
+        system_prompt: "You are helpful.".into(),
+        messages: Vec::new(),
+        tools: vec![Box::new(yoagent::tools::BashTool::new())],
+    };
+    restored
+        .load_messages(serde_json::from_str(&saved).unwrap())
+        .unwrap();
+    assert_eq!(restored.messages, context.messages);
+
+    let Some(AgentMessage::Llm(Message::Assistant { content, .. })) = restored.messages.get(1)
+    else {
+        panic!("expected the tool-calling assistant turn");
+    };
+    assert!(matches!(&content[0], Content::Thinking { signature: Some(s), .. } if s == "sig"));
+    assert!(matches!(&content[1], Content::ToolCall { id, .. } if id == "tc-1"));
+    assert!(matches!(
+        &restored.messages[4],
+        AgentMessage::Llm(Message::Assistant {
+            timestamp: 1_700_000_000_123,
+            ..
+        })
+    ));
+
+    // A bad payload leaves the history alone.
+    assert!(restored
+        .load_messages(serde_json::json!({"not": "messages"}))
+        .is_err());
+    assert_eq!(restored.messages, context.messages);
+}
+
+#[tokio::test]
+async fn test_conversation_logger_writes_roundtrippable_jsonl() {
+    use std::sync::Arc;
+    use yoagent::agent_loop::AgentLoopConfig;
+    use yoagent::logging::ConversationLogger;
+    use yoagent::provider::mock::{MockResponse, MockToolCall};
+    use yoagent::provider::MockProvider;
+
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "missing_tool".into(),
+            arguments: serde_json::json!({"path": "a.rs"}),
+        }]),
+        MockResponse::Text("done".into()),
+    ]);
+    let config = AgentLoopConfig::builder(Arc::new(provider)).build();
+    let mut context = AgentContext {
+        system_prompt: "You are helpful.".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let logger = tokio::spawn(ConversationLogger::from_writer(Vec::new()).run(rx));
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        tokio_util::sync::CancellationToken::new(),
+    )
+    .await;
+    let bytes = logger.await.unwrap().unwrap();
+
+    let events: Vec<AgentEvent> = String::from_utf8(bytes)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is one event"))
+        .collect();
+    assert!(matches!(events.first(), Some(AgentEvent::AgentStart)));
+    assert!(matches!(events.last(), Some(AgentEvent::AgentEnd { .. })));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, AgentEvent::ToolExecutionEnd { is_error: true, .. })));
+}
+
+#[tokio::test]
+async fn test_conversation_logger_replays_every_event() {
+    use futures::StreamExt;
+    use std::sync::Arc;
+    use yoagent::agent_loop::AgentLoopConfig;
+    use yoagent::logging::ConversationLogger;
+    use yoagent::provider::mock::{MockResponse, MockToolCall};
+    use yoagent::provider::MockProvider;
+
+    let provider = MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "missing_tool".into(),
+            arguments: serde_json::json!({"path": "a.rs"}),
+        }]),
+        MockResponse::Text("done".into()),
+    ]);
+    let config = AgentLoopConfig::builder(Arc::new(provider)).build();
+    let mut context = AgentContext {
+        system_prompt: "You are helpful.".into(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    };
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("run.jsonl");
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let logger = ConversationLogger::new(&path).unwrap().spawn(rx);
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("go"))],
+        &mut context,
+        &config,
+        tx,
+        tokio_util::sync::CancellationToken::new(),
+    )
+    .await;
+    let dropped = logger.dropped();
+    let written = logger.close().await.unwrap();
+    assert_eq!(dropped, 0);
+
+    let events: Vec<AgentEvent> = ConversationLogger::replay(&path).collect().await;
+    assert_eq!(events.len() as u64, written);
+    assert!(matches!(events.first(), Some(AgentEvent::AgentStart)));
+    assert!(matches!(events.last(), Some(AgentEvent::AgentEnd { .. })));
+    assert!(events
+        .iter()
+        .any(|e| matches!(e, AgentEvent::ToolExecutionEnd { is_error: true, .. })));
+
+    // A second logger appends after a line torn by a crash, which replay
+    // skips.
+    std::fs::write(
+        &path,
+        std::fs::read_to_string(&path).unwrap() + "{\"type\":\"agentSt",
+    )
+    .unwrap();
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let logger = ConversationLogger::new(&path).unwrap().spawn(rx);
+    tx.send(AgentEvent::AgentStart).unwrap();
+    drop(tx);
+    assert_eq!(logger.close().await.unwrap(), 1);
+    let replayed = ConversationLogger::replay(&path).count().await;
+    assert_eq!(replayed, events.len() + 1);
+}
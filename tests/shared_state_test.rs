@@ -45,6 +45,7 @@ async fn test_sub_agent_reads_shared_state() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -93,6 +94,7 @@ async fn test_sub_agent_writes_shared_state() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -156,6 +158,7 @@ async fn test_parallel_sub_agents_share_state() {
         cancel: CancellationToken::new(),
         on_update: None,
         on_progress: None,
+        timeout: None,
     };
 
     // Run in parallel
@@ -192,6 +195,7 @@ async fn test_sub_agent_without_shared_state_unchanged() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -237,6 +241,7 @@ async fn test_shared_state_summary_in_system_prompt() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -4,10 +4,11 @@ use std::sync::Arc;
 use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use yoagent::agent_loop::{agent_loop, AgentLoopConfig};
+use yoagent::context::ExecutionLimits;
 use yoagent::provider::mock::*;
 use yoagent::provider::MockProvider;
-use yoagent::provider::ModelConfig;
-use yoagent::sub_agent::SubAgentTool;
+use yoagent::provider::{ModelConfig, ResponseFormat, ToolChoice};
+use yoagent::sub_agent::{AgentFanOut, SubAgentTool};
 use yoagent::*;
 
 fn make_config(provider: MockProvider) -> AgentLoopConfig {
@@ -28,13 +29,32 @@ fn make_config(provider: MockProvider) -> AgentLoopConfig {
         execution_limits: None,
         cache_config: CacheConfig::default(),
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         tool_execution: ToolExecutionStrategy::default(),
         retry_config: yoagent::RetryConfig::default(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         turn_delay: None,
     }
 }
@@ -72,6 +92,7 @@ async fn test_sub_agent_basic() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -158,6 +179,7 @@ async fn test_sub_agent_with_tools() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -196,6 +218,7 @@ async fn test_sub_agent_cancellation() {
                 cancel,
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -250,6 +273,7 @@ async fn test_sub_agent_max_turns() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -264,6 +288,122 @@ async fn test_sub_agent_max_turns() {
     assert_ne!(text, "Should not reach");
 }
 
+// ---------------------------------------------------------------------------
+// Tools shared with the parent
+// ---------------------------------------------------------------------------
+
+/// Counts calls; the count lives in the tool, so sharing it shares the count.
+struct MockCountingTool {
+    calls: std::sync::atomic::AtomicUsize,
+}
+
+#[async_trait::async_trait]
+impl AgentTool for MockCountingTool {
+    fn name(&self) -> &str {
+        "count"
+    }
+    fn label(&self) -> &str {
+        "Count"
+    }
+    fn description(&self) -> &str {
+        "Counts calls"
+    }
+    fn parameters_schema(&self) -> serde_json::Value {
+        serde_json::json!({"type": "object", "properties": {}})
+    }
+    async fn execute(
+        &self,
+        _params: serde_json::Value,
+        _ctx: ToolContext,
+    ) -> Result<ToolResult, ToolError> {
+        let n = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        Ok(ToolResult {
+            content: vec![Content::Text {
+                text: n.to_string(),
+            }],
+            details: serde_json::Value::Null,
+        })
+    }
+}
+
+fn count_call() -> MockToolCall {
+    MockToolCall {
+        provider_metadata: None,
+        name: "count".into(),
+        arguments: serde_json::json!({}),
+    }
+}
+
+#[tokio::test]
+async fn test_sub_agent_shares_tools_with_parent() {
+    let counter = Arc::new(MockCountingTool {
+        calls: std::sync::atomic::AtomicUsize::new(0),
+    });
+
+    let sub_agent = SubAgentTool::from_provider(
+        "helper",
+        Arc::new(MockProvider::new(vec![
+            MockResponse::ToolCalls(vec![count_call()]),
+            MockResponse::Text("counted".into()),
+        ])),
+        ModelConfig::mock(),
+    )
+    .with_shared_tools(vec![counter.clone().as_shared()]);
+
+    // The parent counts once itself, then delegates a second count.
+    let config = make_config(MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![count_call()]),
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "helper".into(),
+            arguments: serde_json::json!({"task": "count once"}),
+        }]),
+        MockResponse::Text("done".into()),
+    ]));
+    let mut context = AgentContext {
+        system_prompt: String::new(),
+        messages: Vec::new(),
+        tools: vec![
+            Box::new(SharedToolWrapper(counter.clone())),
+            Box::new(sub_agent),
+        ],
+    };
+
+    let (tx, _rx) = mpsc::unbounded_channel();
+    agent_loop(
+        vec![AgentMessage::Llm(Message::user("count twice"))],
+        &mut context,
+        &config,
+        tx,
+        CancellationToken::new(),
+    )
+    .await;
+
+    assert_eq!(counter.calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+    // Cloning the wrapper keeps pointing at the same tool.
+    let copy = context.tools[0].clone_box();
+    let ctx = ToolContext {
+        tool_call_id: "tc-copy".into(),
+        tool_name: "count".into(),
+        cancel: CancellationToken::new(),
+        on_update: None,
+        on_progress: None,
+        timeout: None,
+    };
+    copy.execute(serde_json::json!({}), ctx).await.unwrap();
+    assert_eq!(counter.calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+}
+
+#[test]
+#[should_panic(expected = "does not implement clone_box")]
+fn test_clone_box_default_panics() {
+    let _ = MockCountingTool {
+        calls: std::sync::atomic::AtomicUsize::new(0),
+    }
+    .clone_box();
+}
+
 // ---------------------------------------------------------------------------
 // Parallel sub-agent execution (via parent agent loop)
 // ---------------------------------------------------------------------------
@@ -380,6 +520,314 @@ async fn test_sub_agent_parallel() {
     );
 }
 
+/// MockProvider whose replies each report 100 input and 50 output tokens.
+struct MeteredProvider {
+    inner: MockProvider,
+}
+
+#[async_trait::async_trait]
+impl yoagent::provider::StreamProvider for MeteredProvider {
+    async fn stream(
+        &self,
+        config: yoagent::provider::StreamConfig,
+        tx: mpsc::UnboundedSender<yoagent::provider::StreamEvent>,
+        cancel: CancellationToken,
+    ) -> Result<Message, yoagent::provider::ProviderError> {
+        let mut message = self.inner.stream(config, tx, cancel).await?;
+        if let Message::Assistant { usage, .. } = &mut message {
+            *usage = Usage {
+                input: 100,
+                output: 50,
+                total_tokens: 150,
+                ..Usage::default()
+            };
+        }
+        Ok(message)
+    }
+}
+
+#[tokio::test]
+async fn test_sub_agent_token_limit_stops_runaway_loop() {
+    // Would keep calling echo for 50 turns.
+    let calls = (0..50)
+        .map(|_| {
+            MockResponse::ToolCalls(vec![MockToolCall {
+                provider_metadata: None,
+                name: "echo".into(),
+                arguments: serde_json::json!({"text": "again"}),
+            }])
+        })
+        .collect();
+    let sub_agent = SubAgentTool::from_provider(
+        "looper",
+        Arc::new(MeteredProvider {
+            inner: MockProvider::new(calls),
+        }),
+        ModelConfig::mock(),
+    )
+    .with_tools(vec![Arc::new(EchoTool)])
+    .with_execution_limits(ExecutionLimits {
+        max_turns: 50,
+        max_total_tokens: 400,
+        ..ExecutionLimits::default()
+    });
+
+    let result = sub_agent
+        .execute(
+            serde_json::json!({"task": "Loop"}),
+            ToolContext {
+                tool_call_id: "tc-1".into(),
+                tool_name: "looper".into(),
+                cancel: CancellationToken::new(),
+                on_update: None,
+                on_progress: None,
+                timeout: None,
+            },
+        )
+        .await
+        .expect("a limit stop is not a failure");
+
+    // Three turns of 150 tokens pass the 400 budget.
+    assert_eq!(result.details["usage"]["input"], 300);
+    assert_eq!(result.details["usage"]["output"], 150);
+    let reason = result.details["limit_reached"].as_str().unwrap();
+    assert!(reason.starts_with("Max tokens reached"), "{}", reason);
+    let Content::Text { text } = &result.content[0] else {
+        panic!("Expected text content");
+    };
+    assert!(
+        text.ends_with(&format!("[Agent stopped: {}]", reason)),
+        "{}",
+        text
+    );
+}
+
+// ---------------------------------------------------------------------------
+// AgentFanOut
+// ---------------------------------------------------------------------------
+
+/// MockProvider that takes `delay` to answer.
+struct DelayedProvider {
+    delay: std::time::Duration,
+    inner: MockProvider,
+}
+
+#[async_trait::async_trait]
+impl yoagent::provider::StreamProvider for DelayedProvider {
+    async fn stream(
+        &self,
+        config: yoagent::provider::StreamConfig,
+        tx: mpsc::UnboundedSender<yoagent::provider::StreamEvent>,
+        cancel: CancellationToken,
+    ) -> Result<Message, yoagent::provider::ProviderError> {
+        tokio::time::sleep(self.delay).await;
+        self.inner.stream(config, tx, cancel).await
+    }
+}
+
+fn delayed_agent(name: &str, reply: &str) -> SubAgentTool {
+    SubAgentTool::from_provider(
+        name,
+        Arc::new(DelayedProvider {
+            delay: std::time::Duration::from_millis(50),
+            inner: MockProvider::text(reply),
+        }),
+        ModelConfig::mock(),
+    )
+}
+
+fn fan_out_jobs() -> Vec<(SubAgentTool, serde_json::Value)> {
+    ["a", "b", "c"]
+        .iter()
+        .map(|name| {
+            (
+                delayed_agent(name, &format!("Result {}", name)),
+                serde_json::json!({"task": format!("Do {}", name)}),
+            )
+        })
+        .collect()
+}
+
+fn result_text(result: &Result<ToolResult, ToolError>) -> &str {
+    match &result.as_ref().expect("sub-agent should succeed").content[0] {
+        Content::Text { text } => text,
+        _ => panic!("Expected text content"),
+    }
+}
+
+#[tokio::test]
+async fn test_fan_out_runs_concurrently_in_order() {
+    let start = std::time::Instant::now();
+    let results = AgentFanOut::new(fan_out_jobs())
+        .run(CancellationToken::new())
+        .await;
+    let elapsed = start.elapsed();
+
+    let texts: Vec<&str> = results.iter().map(result_text).collect();
+    assert_eq!(texts, vec!["Result a", "Result b", "Result c"]);
+    assert!(
+        elapsed.as_millis() < 100,
+        "3 concurrent sub-agents took {}ms, expected <100ms",
+        elapsed.as_millis()
+    );
+}
+
+#[tokio::test]
+async fn test_fan_out_respects_max_concurrent() {
+    let start = std::time::Instant::now();
+    let results = AgentFanOut::new(fan_out_jobs())
+        .with_max_concurrent(1)
+        .run(CancellationToken::new())
+        .await;
+    assert!(start.elapsed().as_millis() >= 150);
+
+    // Errors stay in their slot.
+    assert_eq!(results.len(), 3);
+    let mut jobs = fan_out_jobs();
+    jobs[1].1 = serde_json::json!({});
+    let results = AgentFanOut::new(jobs).run(CancellationToken::new()).await;
+    assert_eq!(result_text(&results[0]), "Result a");
+    assert!(matches!(results[1], Err(ToolError::InvalidArgs(_))));
+    assert_eq!(result_text(&results[2]), "Result c");
+}
+
+#[tokio::test]
+async fn test_sub_agent_run_without_tool_context() {
+    let agent = delayed_agent("solo", "Done");
+    let result = agent.run("Do it", CancellationToken::new()).await;
+    assert_eq!(result_text(&result), "Done");
+}
+
+#[tokio::test]
+async fn test_sub_agent_include_history() {
+    let provider = || {
+        Arc::new(MockProvider::new(vec![
+            MockResponse::ToolCalls(vec![MockToolCall {
+                provider_metadata: None,
+                name: "echo".into(),
+                arguments: serde_json::json!({"text": "hello"}),
+            }]),
+            MockResponse::Text("Done".into()),
+        ]))
+    };
+
+    let sub_agent = SubAgentTool::from_provider("echo_agent", provider(), ModelConfig::mock())
+        .with_tools(vec![Arc::new(EchoTool)])
+        .with_include_history(true);
+    let result = sub_agent
+        .run("Echo hello", CancellationToken::new())
+        .await
+        .unwrap();
+    let conversation = result.details["conversation"].as_array().unwrap();
+    let roles: Vec<&str> = conversation
+        .iter()
+        .map(|m| m["role"].as_str().unwrap())
+        .collect();
+    assert_eq!(roles, ["user", "assistant", "toolResult", "assistant"]);
+    assert_eq!(conversation[1]["content"][0]["name"], "echo");
+    assert_eq!(result.details["tools_called"], serde_json::json!(["echo"]));
+    assert!(result.details["total_tokens"]["totalTokens"].is_u64());
+
+    // Off by default.
+    let sub_agent = SubAgentTool::from_provider("echo_agent", provider(), ModelConfig::mock())
+        .with_tools(vec![Arc::new(EchoTool)]);
+    let result = sub_agent
+        .run("Echo hello", CancellationToken::new())
+        .await
+        .unwrap();
+    assert!(result.details.get("conversation").is_none());
+    assert!(result.details.get("tools_called").is_none());
+}
+
+#[tokio::test]
+async fn test_sub_agent_shared_context_persists_across_calls() {
+    let shared = Arc::new(tokio::sync::Mutex::new(AgentContext {
+        system_prompt: String::new(),
+        messages: Vec::new(),
+        tools: Vec::new(),
+    }));
+    let agent = SubAgentTool::from_provider(
+        "reader",
+        Arc::new(MockProvider::texts(vec![
+            "Loaded the report",
+            "Revenue grew 12%",
+            "Starting over",
+        ])),
+        ModelConfig::mock(),
+    )
+    .with_system_prompt("You answer questions about one document.")
+    .with_shared_context(shared.clone());
+    assert!(agent.parameters_schema()["properties"]["reset"].is_object());
+
+    let ctx = || ToolContext {
+        tool_call_id: "tc-1".into(),
+        tool_name: "reader".into(),
+        cancel: CancellationToken::new(),
+        on_update: None,
+        on_progress: None,
+        timeout: None,
+    };
+    let history = |context: &AgentContext| -> Vec<String> {
+        context
+            .messages
+            .iter()
+            .map(|m| match m.as_llm().unwrap().content() {
+                [Content::Text { text }] => format!("{}: {}", m.role(), text),
+                other => panic!("unexpected content {:?}", other),
+            })
+            .collect()
+    };
+
+    agent
+        .execute(serde_json::json!({"task": "Read report.pdf"}), ctx())
+        .await
+        .unwrap();
+    let result = agent
+        .execute(
+            serde_json::json!({"task": "What was revenue growth?"}),
+            ctx(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result_text(&Ok(result)), "Revenue grew 12%");
+    {
+        let context = shared.lock().await;
+        assert_eq!(
+            context.system_prompt,
+            "You answer questions about one document."
+        );
+        assert_eq!(
+            history(&context),
+            [
+                "user: Read report.pdf",
+                "assistant: Loaded the report",
+                "user: What was revenue growth?",
+                "assistant: Revenue grew 12%",
+            ]
+        );
+    }
+
+    agent
+        .execute(
+            serde_json::json!({"task": "New document", "reset": true}),
+            ctx(),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        history(&*shared.lock().await),
+        ["user: New document", "assistant: Starting over"]
+    );
+}
+
+#[tokio::test]
+async fn test_sub_agent_reset_param_needs_shared_context() {
+    let agent = delayed_agent("solo", "Done");
+    assert!(agent.parameters_schema()["properties"]
+        .get("reset")
+        .is_none());
+}
+
 // ---------------------------------------------------------------------------
 // Event forwarding via on_update
 // ---------------------------------------------------------------------------
@@ -411,6 +859,7 @@ async fn test_sub_agent_event_forwarding() {
                 cancel: CancellationToken::new(),
                 on_update: Some(on_update),
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -437,6 +886,123 @@ async fn test_sub_agent_event_forwarding() {
     );
 }
 
+#[tokio::test]
+async fn test_sub_agent_final_answer_tool_commits_result() {
+    let sub_provider = Arc::new(MockProvider::new(vec![
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "echo".into(),
+            arguments: serde_json::json!({"text": "working"}),
+        }]),
+        MockResponse::ToolCalls(vec![MockToolCall {
+            provider_metadata: None,
+            name: "final_answer".into(),
+            arguments: serde_json::json!({"answer": "The answer is 42"}),
+        }]),
+        MockResponse::Text("Chatter after the answer".into()),
+    ]));
+    let sub_agent = SubAgentTool::from_provider("solver", sub_provider, ModelConfig::mock())
+        .with_tools(vec![Arc::new(EchoTool)])
+        .with_final_answer_tool(true);
+
+    let result = sub_agent
+        .run("Find the answer", CancellationToken::new())
+        .await
+        .unwrap();
+    let Content::Text { text } = &result.content[0] else {
+        panic!("Expected text content");
+    };
+    assert_eq!(text, "The answer is 42");
+    assert_eq!(result.details["final_answer"], true);
+
+    // Never called: the last assistant text, as without the tool.
+    let sub_agent = SubAgentTool::from_provider(
+        "solver",
+        Arc::new(MockProvider::text("Plain reply")),
+        ModelConfig::mock(),
+    )
+    .with_final_answer_tool(true);
+    let result = sub_agent
+        .run("Find the answer", CancellationToken::new())
+        .await
+        .unwrap();
+    let Content::Text { text } = &result.content[0] else {
+        panic!("Expected text content");
+    };
+    assert_eq!(text, "Plain reply");
+    assert_eq!(result.details["final_answer"], false);
+}
+
+#[tokio::test]
+async fn test_sub_agent_forwards_nested_tool_events() {
+    let researcher = |forward| {
+        let provider = Arc::new(MockProvider::new(vec![
+            MockResponse::ToolCalls(vec![MockToolCall {
+                provider_metadata: None,
+                name: "echo".into(),
+                arguments: serde_json::json!({"text": "hello"}),
+            }]),
+            MockResponse::Text("done".into()),
+        ]));
+        SubAgentTool::from_provider("researcher", provider, ModelConfig::mock())
+            .with_tools(vec![Arc::new(EchoTool) as Arc<dyn AgentTool>])
+            .with_event_forwarding(forward)
+    };
+
+    let updates: Arc<std::sync::Mutex<Vec<ToolResult>>> = Arc::new(std::sync::Mutex::new(vec![]));
+    let sink = updates.clone();
+    let ctx = ToolContext {
+        tool_call_id: "tc-1".into(),
+        tool_name: "researcher".into(),
+        cancel: CancellationToken::new(),
+        on_update: Some(Arc::new(move |r| sink.lock().unwrap().push(r))),
+        on_progress: None,
+        timeout: None,
+    };
+    researcher(true)
+        .execute(serde_json::json!({"task": "Echo hello"}), ctx.clone())
+        .await
+        .unwrap();
+
+    let nested: Vec<serde_json::Value> = updates
+        .lock()
+        .unwrap()
+        .iter()
+        .filter_map(|r| r.details.get("event").cloned())
+        .collect();
+    let types: Vec<&str> = nested.iter().map(|e| e["type"].as_str().unwrap()).collect();
+    assert_eq!(
+        types,
+        [
+            "turnStart",
+            "toolExecutionStart",
+            "toolExecutionEnd",
+            "turnEnd",
+            "turnStart",
+            "turnEnd"
+        ]
+    );
+    assert_eq!(nested[1]["toolName"], "echo");
+    assert_eq!(nested[1]["args"]["text"], "hello");
+    assert_eq!(nested[2]["isError"], false);
+    assert!(updates.lock().unwrap().iter().any(|r| matches!(
+        r.content.first(),
+        Some(Content::Text { text }) if text == "[researcher] running echo"
+    )));
+
+    // Off by default: only the plain text updates.
+    updates.lock().unwrap().clear();
+    researcher(false)
+        .execute(serde_json::json!({"task": "Echo hello"}), ctx)
+        .await
+        .unwrap();
+    assert!(updates
+        .lock()
+        .unwrap()
+        .iter()
+        .all(|r| r.details.get("event").is_none()));
+}
+
 // ---------------------------------------------------------------------------
 // Invalid parameters
 // ---------------------------------------------------------------------------
@@ -458,6 +1024,7 @@ async fn test_sub_agent_missing_task_parameter() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await;
@@ -557,6 +1124,7 @@ async fn capture_system_prompt(
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -767,6 +1335,7 @@ async fn run_sub_agent(tool: &SubAgentTool) {
             cancel: CancellationToken::new(),
             on_update: None,
             on_progress: None,
+            timeout: None,
         },
     )
     .await
@@ -832,6 +1401,7 @@ async fn test_sub_agent_from_provider_construction() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -943,6 +1513,7 @@ async fn test_sub_agent_tool_middleware_denies() {
                 cancel: CancellationToken::new(),
                 on_update: None,
                 on_progress: None,
+                timeout: None,
             },
         )
         .await
@@ -10,7 +10,7 @@ use tokio_util::sync::CancellationToken;
 use tracing::instrument::WithSubscriber;
 use tracing_subscriber::layer::SubscriberExt;
 use yoagent::provider::mock::*;
-use yoagent::provider::MockProvider;
+use yoagent::provider::{MockProvider, ResponseFormat, ToolChoice};
 use yoagent::*;
 
 /// Layer that records every new span's name.
@@ -80,12 +80,31 @@ fn loop_config(provider: MockProvider) -> yoagent::agent_loop::AgentLoopConfig {
         cache_config: CacheConfig::default(),
         tool_execution: ToolExecutionStrategy::default(),
         tool_middleware: vec![],
+        tool_timeout: None,
+        tool_cache: None,
+        metrics_sink: None,
+        approve_tool: None,
+        on_tool_call: None,
         output_schema: None,
+        tool_choice: ToolChoice::Auto,
+        stop_sequences: None,
+        response_format: ResponseFormat::Text,
+        server_tools: Vec::new(),
         retry_config: yoagent::RetryConfig::none(),
+        circuit_breaker: None,
+        rate_limiter: None,
         before_turn: None,
+        prepare_turn: None,
         after_turn: None,
+        checkpoint: None,
+        stop_if: None,
+        on_context_compact: None,
+        on_message: None,
+        event_buffer_size: None,
+        system_prompt_hook: None,
         on_error: None,
         input_filters: vec![],
+        output_filters: vec![],
         turn_delay: None,
     }
 }
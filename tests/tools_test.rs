@@ -15,6 +15,7 @@ fn ctx(name: &str) -> ToolContext {
         cancel: CancellationToken::new(),
         on_update: None,
         on_progress: None,
+        timeout: None,
     }
 }
 
@@ -25,6 +26,7 @@ fn ctx_with_cancel(name: &str, cancel: CancellationToken) -> ToolContext {
         cancel,
         on_update: None,
         on_progress: None,
+        timeout: None,
     }
 }
 
@@ -83,6 +85,236 @@ async fn test_bash_timeout() {
     assert!(err.contains("timed out"));
 }
 
+#[tokio::test]
+async fn test_bash_timeout_ms_param_tightens_limit() {
+    let tool = BashTool::new();
+    let start = std::time::Instant::now();
+    let result = tool
+        .execute(
+            serde_json::json!({"command": "sleep 10", "timeout_ms": 100}),
+            ctx("bash"),
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(ToolError::Timeout(d)) if d == std::time::Duration::from_millis(100)
+    ));
+    assert!(start.elapsed() < std::time::Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn test_bash_timeout_ms_param_cannot_extend_limit() {
+    let tool = BashTool::new().with_timeout(std::time::Duration::from_millis(100));
+    let result = tool
+        .execute(
+            serde_json::json!({"command": "sleep 10", "timeout_ms": 60_000}),
+            ctx("bash"),
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(ToolError::Timeout(d)) if d == std::time::Duration::from_millis(100)
+    ));
+}
+
+/// Runs `command` with streaming on, returning the `(stream, text)` of every
+/// update and the final result.
+async fn stream_bash(command: &str) -> (Vec<(String, String)>, ToolResult) {
+    let updates = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let sink = updates.clone();
+    let mut context = ctx("bash");
+    context.on_update = Some(std::sync::Arc::new(move |partial: ToolResult| {
+        if let Some(Content::Text { text }) = partial.content.first() {
+            assert_eq!(partial.details["delta"], true);
+            let stream = partial.details["stream"].as_str().unwrap_or_default();
+            sink.lock()
+                .unwrap()
+                .push((stream.to_string(), text.clone()));
+        }
+    }));
+
+    let result = BashTool::new()
+        .with_streaming(true)
+        .execute(serde_json::json!({ "command": command }), context)
+        .await
+        .unwrap();
+    let updates = updates.lock().unwrap().clone();
+    (updates, result)
+}
+
+#[tokio::test]
+async fn test_bash_streaming_reports_lines_as_they_arrive() {
+    let (updates, result) = stream_bash("echo one; echo two >&2; echo three").await;
+
+    // One update per line, carrying only that line; stdout/stderr may
+    // interleave in either order.
+    assert_eq!(updates.len(), 3);
+    let lines = |stream: &str| -> Vec<String> {
+        updates
+            .iter()
+            .filter(|(s, _)| s == stream)
+            .map(|(_, text)| text.clone())
+            .collect()
+    };
+    assert_eq!(lines("stdout"), ["one\n", "three\n"]);
+    assert_eq!(lines("stderr"), ["two\n"]);
+
+    let Content::Text { text } = &result.content[0] else {
+        panic!("expected text");
+    };
+    assert!(text.starts_with("Exit code: 0"));
+    assert!(text.contains("one\nthree") && text.contains("two"));
+}
+
+#[tokio::test]
+async fn test_bash_streaming_keeps_an_unterminated_last_line() {
+    let (updates, result) = stream_bash("printf 'a\\nb'").await;
+
+    let texts: Vec<&str> = updates.iter().map(|(_, text)| text.as_str()).collect();
+    assert_eq!(texts, ["a\n", "b"]);
+    assert_eq!(result.details["stdout_bytes"], 3);
+}
+
+#[tokio::test]
+async fn test_bash_without_streaming_sends_no_updates() {
+    let called = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+    let flag = called.clone();
+    let mut context = ctx("bash");
+    context.on_update = Some(std::sync::Arc::new(move |_| {
+        flag.store(true, std::sync::atomic::Ordering::SeqCst);
+    }));
+
+    BashTool::new()
+        .execute(serde_json::json!({"command": "echo hi"}), context)
+        .await
+        .unwrap();
+    assert!(!called.load(std::sync::atomic::Ordering::SeqCst));
+}
+
+#[tokio::test]
+async fn test_bash_cwd_and_env() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("sub")).unwrap();
+    let root = dir.path().canonicalize().unwrap();
+    let tool = BashTool::new()
+        .with_cwd(root.to_str().unwrap())
+        .with_env([("YOAGENT_TEST_VAR", "injected")]);
+
+    let result = tool
+        .execute(
+            serde_json::json!({"command": "pwd; echo $YOAGENT_TEST_VAR"}),
+            ctx("bash"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        text_of(&result),
+        format!("Exit code: 0\n{}\ninjected\n", root.display())
+    );
+
+    // A per-call cwd resolves against the configured one.
+    let result = tool
+        .execute(
+            serde_json::json!({"command": "pwd", "cwd": "sub"}),
+            ctx("bash"),
+        )
+        .await
+        .unwrap();
+    assert!(text_of(&result).contains(&format!("{}/sub", root.display())));
+}
+
+#[tokio::test]
+async fn test_bash_cwd_cannot_escape_root() {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path().join("root");
+    std::fs::create_dir(&root).unwrap();
+    std::os::unix::fs::symlink(dir.path(), root.join("up")).unwrap();
+    let tool = BashTool::new().with_cwd(root.to_str().unwrap());
+
+    for cwd in ["..", "up", "/"] {
+        let err = tool
+            .execute(
+                serde_json::json!({"command": "pwd", "cwd": cwd}),
+                ctx("bash"),
+            )
+            .await
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("outside the allowed directory"),
+            "{}: {}",
+            cwd,
+            err
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_bash_without_inherited_env() {
+    std::env::set_var("YOAGENT_TEST_PARENT_VAR", "leaked");
+    let tool = BashTool::new()
+        .with_inherit_env(false)
+        .with_env([("ONLY", "this")]);
+    let result = tool
+        .execute(
+            serde_json::json!({"command": "echo \"[$YOAGENT_TEST_PARENT_VAR][$ONLY]\""}),
+            ctx("bash"),
+        )
+        .await
+        .unwrap();
+    assert!(
+        text_of(&result).contains("[][this]\n"),
+        "{}",
+        text_of(&result)
+    );
+}
+
+#[tokio::test]
+async fn test_bash_truncates_long_output_keeping_head_and_tail() {
+    let tool = BashTool::new().with_max_output_bytes(1000);
+    // Lines "1".."5000": 23893 bytes.
+    let result = tool
+        .execute(serde_json::json!({"command": "seq 1 5000"}), ctx("bash"))
+        .await
+        .unwrap();
+
+    let text = text_of(&result);
+    assert!(text.starts_with("Exit code: 0\n1\n2\n3\n"), "{}", text);
+    assert!(text.ends_with("4999\n5000\n"), "{}", text);
+    assert!(text.contains("[... 22893 bytes truncated ...]"), "{}", text);
+    assert!(text.len() < 1100);
+    assert_eq!(result.details["exit_code"], 0);
+    assert_eq!(result.details["stdout_bytes"], 23893);
+    assert_eq!(result.details["truncated_bytes"], 22893);
+}
+
+#[tokio::test]
+async fn test_bash_truncates_streamed_output_and_stderr() {
+    let mut context = ctx("bash");
+    context.on_update = Some(std::sync::Arc::new(|_| {}));
+    let tool = BashTool::new()
+        .with_streaming(true)
+        .with_max_output_bytes(100);
+    let result = tool
+        .execute(
+            serde_json::json!({"command": "seq 1 1000 >&2; echo done; exit 3"}),
+            context,
+        )
+        .await
+        .unwrap();
+
+    let text = text_of(&result);
+    assert!(text.contains("STDOUT:\ndone\n"), "{}", text);
+    assert!(text.contains("STDERR:\n1\n2\n"), "{}", text);
+    assert!(text.ends_with("999\n1000\n"), "{}", text);
+    assert!(text.contains("[... 3793 bytes truncated ...]"), "{}", text);
+    assert_eq!(result.details["exit_code"], 3);
+    assert_eq!(result.details["stdout_bytes"], 5);
+    assert_eq!(result.details["stderr_bytes"], 3893);
+    assert_eq!(result.details["truncated_bytes"], 3793);
+}
+
 #[tokio::test]
 async fn test_bash_cancel() {
     let tool = BashTool::new();
@@ -168,6 +400,217 @@ async fn test_read_file_with_offset_limit() {
     let _ = std::fs::remove_file(tmp);
 }
 
+fn numbered_file(dir: &std::path::Path, lines: usize) -> std::path::PathBuf {
+    let path = dir.join("numbered.txt");
+    let content: String = (1..=lines).map(|i| format!("line {}\n", i)).collect();
+    std::fs::write(&path, content).unwrap();
+    path
+}
+
+#[tokio::test]
+async fn test_read_file_range_header_reports_total() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = numbered_file(dir.path(), 50);
+    let result = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "offset": 10, "limit": 5}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap();
+    let text = text_of(&result);
+    assert!(
+        text.starts_with("[Lines 10-14 of 50]\n  10 | line 10"),
+        "{}",
+        text
+    );
+    assert!(text.ends_with("  14 | line 14"), "{}", text);
+    // An explicit limit is not a truncation.
+    assert!(!text.contains("truncated"));
+    assert_eq!(result.details["total_lines"], 50);
+
+    // Offset only: everything from there to the end.
+    let result = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "offset": 48}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap();
+    assert!(text_of(&result).starts_with("[Lines 48-50 of 50]"));
+}
+
+#[tokio::test]
+async fn test_read_file_offset_out_of_bounds() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = numbered_file(dir.path(), 5);
+    let err = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "offset": 6}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        err.to_string().contains("Offset 6 is past the end"),
+        "{}",
+        err
+    );
+    assert!(err.to_string().contains("(5 lines)"));
+
+    // A limit running past the end is clamped.
+    let result = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "offset": 4, "limit": 100}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap();
+    assert!(text_of(&result).starts_with("[Lines 4-5 of 5]"));
+}
+
+#[tokio::test]
+async fn test_read_file_start_and_end_line() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = numbered_file(dir.path(), 100);
+    let read = |params: serde_json::Value| {
+        let mut params = params;
+        params["path"] = serde_json::json!(path.to_str().unwrap());
+        async move {
+            ReadFileTool::new()
+                .execute(params, ctx("read_file"))
+                .await
+                .unwrap()
+        }
+    };
+
+    let result = read(serde_json::json!({"start_line": 10, "end_line": 20})).await;
+    let text = text_of(&result);
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], "[Lines 10-20 of 100]");
+    assert_eq!(lines.len(), 1 + 11, "{}", text);
+    assert_eq!(lines[1], "  10 | line 10");
+    assert_eq!(lines[11], "  20 | line 20");
+    assert_eq!(result.details["start_line"], 10);
+    assert_eq!(result.details["end_line"], 20);
+    assert_eq!(result.details["lines_read"], 11);
+    assert_eq!(result.details["total_lines"], 100);
+
+    // Start only: to the end of the file.
+    let result = read(serde_json::json!({"start_line": 95})).await;
+    assert!(text_of(&result).starts_with("[Lines 95-100 of 100]"));
+    assert!(text_of(&result).ends_with(" 100 | line 100"));
+
+    // Out-of-bounds ranges are clamped, and details report the real range.
+    let result = read(serde_json::json!({"start_line": 98, "end_line": 500})).await;
+    assert_eq!(result.details["end_line"], 100);
+    assert_eq!(result.details["lines_read"], 3);
+    let result = read(serde_json::json!({"start_line": 150, "end_line": 160})).await;
+    assert!(text_of(&result).starts_with("[Lines 100-100 of 100]"));
+    assert_eq!(result.details["lines_read"], 1);
+}
+
+#[tokio::test]
+async fn test_read_file_rejects_backwards_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = numbered_file(dir.path(), 100);
+    let err = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "start_line": 20, "end_line": 10}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(&err, ToolError::InvalidArgs(msg) if msg == "end_line must be >= start_line"),
+        "{:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_read_file_truncates_long_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = numbered_file(dir.path(), 100);
+    let tool = ReadFileTool {
+        max_lines: 30,
+        ..ReadFileTool::new()
+    };
+    let result = tool
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap()}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap();
+    let text = text_of(&result);
+    assert!(text.starts_with("[Lines 1-30 of 100]"), "{}", text);
+    assert!(text.contains("  30 | line 30"));
+    assert!(!text.contains("line 31\n"));
+    assert!(text.ends_with("[truncated: 70 more lines. Use offset=31 to continue.]"));
+    assert_eq!(result.details["truncated"], true);
+
+    // Files over max_bytes are still readable, a window at a time.
+    let tool = ReadFileTool {
+        max_bytes: 64,
+        ..ReadFileTool::new()
+    };
+    let result = tool
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "offset": 90}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap();
+    let text = text_of(&result);
+    assert!(text.starts_with("[Lines 90-97 of 100]"), "{}", text);
+    assert!(text.contains("Use offset=98 to continue"));
+}
+
+#[tokio::test]
+async fn test_read_file_byte_range() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("data.bin");
+    let mut data = b"HEADER".to_vec();
+    data.extend([0u8, 1, 2, 0xff, b'A', b'B']);
+    std::fs::write(&path, &data).unwrap();
+    let path = path.to_str().unwrap();
+
+    let result = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": path, "bytes": {"start": 0, "end": 6}}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(text_of(&result), "[Bytes 0-6 of 12]\nHEADER");
+
+    let result = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": path, "bytes": {"start": 4}}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap();
+    let text = text_of(&result);
+    assert!(
+        text.starts_with("[Bytes 4-12 of 12]\n00000004  45 52 00 01 02 ff 41 42"),
+        "{}",
+        text
+    );
+    assert!(text.ends_with("|ER....AB|"), "{}", text);
+    assert_eq!(result.details["hex"], true);
+
+    let err = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": path, "bytes": {"start": 12}}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("past the end"));
+}
+
 #[tokio::test]
 async fn test_read_file_not_found() {
     let tool = ReadFileTool::new();
@@ -248,6 +691,176 @@ async fn test_search_no_matches() {
     let _ = std::fs::remove_dir_all(tmp_dir);
 }
 
+/// A small tree: two Rust files, a Markdown file and an ignored build dir.
+fn search_tree() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    std::fs::create_dir_all(root.join("target")).unwrap();
+    std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+    std::fs::write(
+        root.join("src/lib.rs"),
+        "use std::io;\n\nfn parse_config() {}\nfn parse_args() {}\n// TODO: Parse env\n",
+    )
+    .unwrap();
+    std::fs::write(
+        root.join("src/main.rs"),
+        "fn main() {\n    parse_args();\n}\n",
+    )
+    .unwrap();
+    std::fs::write(root.join("README.md"), "Call parse_config() first.\n").unwrap();
+    std::fs::write(root.join("target/gen.rs"), "fn parse_generated() {}\n").unwrap();
+    dir
+}
+
+async fn search(root: &std::path::Path, params: serde_json::Value) -> String {
+    let result = SearchTool::new()
+        .with_root(root.to_str().unwrap())
+        .execute(params, ctx("search"))
+        .await
+        .unwrap();
+    text_of(&result)
+        .replace(&format!("{}/", root.display()), "")
+        .to_string()
+}
+
+#[tokio::test]
+async fn test_search_regex_reports_line_and_column() {
+    let dir = search_tree();
+    let text = search(dir.path(), serde_json::json!({"pattern": r"parse_\w+\("})).await;
+    assert_eq!(
+        text,
+        "README.md:1:6:Call parse_config() first.\n\
+         src/lib.rs:3:4:fn parse_config() {}\n\
+         src/lib.rs:4:4:fn parse_args() {}\n\
+         src/main.rs:2:5:    parse_args();\n\
+         (4 matches in 3 files)"
+    );
+
+    // Case-sensitive unless asked: "Parse" only matches with the flag.
+    let text = search(
+        dir.path(),
+        serde_json::json!({"pattern": "^// TODO: parse"}),
+    )
+    .await;
+    assert!(text.starts_with("No matches"), "{}", text);
+    let text = search(
+        dir.path(),
+        serde_json::json!({"pattern": "^// TODO: parse", "case_insensitive": true}),
+    )
+    .await;
+    assert!(text.starts_with("src/lib.rs:5:1:"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_search_filters_by_type_and_glob() {
+    let dir = search_tree();
+    let by_type = search(
+        dir.path(),
+        serde_json::json!({"pattern": "parse_config", "type": "rust"}),
+    )
+    .await;
+    assert_eq!(
+        by_type,
+        "src/lib.rs:3:4:fn parse_config() {}\n(1 match in 1 file)"
+    );
+
+    let by_glob = search(
+        dir.path(),
+        serde_json::json!({"pattern": "parse_config", "glob": "*.md"}),
+    )
+    .await;
+    assert!(by_glob.starts_with("README.md:1:6:"), "{}", by_glob);
+
+    // The gitignored target/ dir is never searched.
+    let ignored = search(
+        dir.path(),
+        serde_json::json!({"pattern": "parse_generated"}),
+    )
+    .await;
+    assert!(ignored.starts_with("No matches"), "{}", ignored);
+
+    let err = SearchTool::new()
+        .with_root(dir.path().to_str().unwrap())
+        .execute(
+            serde_json::json!({"pattern": "x", "type": "not-a-type"}),
+            ctx("search"),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidArgs(_)), "{}", err);
+}
+
+#[tokio::test]
+async fn test_search_filters_by_file_extensions() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("lib.rs"), "a\nb\nneedle\nc\nd\ne\n").unwrap();
+    std::fs::write(dir.path().join("notes.txt"), "needle\n").unwrap();
+    std::fs::write(dir.path().join("Cargo.toml"), "needle = 1\n").unwrap();
+
+    let text = search(
+        dir.path(),
+        serde_json::json!({"pattern": "needle", "file_types": ["rs"], "context_lines": 2}),
+    )
+    .await;
+    assert_eq!(
+        text,
+        "lib.rs-1-a\n\
+         lib.rs-2-b\n\
+         lib.rs:3:1:needle\n\
+         lib.rs-4-c\n\
+         lib.rs-5-d\n\
+         (1 match in 1 file)"
+    );
+
+    // Extensions and ripgrep type names mix.
+    let text = search(
+        dir.path(),
+        serde_json::json!({"pattern": "needle", "file_types": [".rs", "toml"]}),
+    )
+    .await;
+    assert!(text.contains("Cargo.toml:1:1:"), "{}", text);
+    assert!(text.contains("lib.rs:3:1:"), "{}", text);
+    assert!(!text.contains("notes.txt"), "{}", text);
+}
+
+#[tokio::test]
+async fn test_search_renders_context_lines() {
+    let dir = search_tree();
+    let text = search(
+        dir.path(),
+        serde_json::json!({"pattern": "parse_args", "context_lines": 1}),
+    )
+    .await;
+    assert_eq!(
+        text,
+        "src/lib.rs-3-fn parse_config() {}\n\
+         src/lib.rs:4:4:fn parse_args() {}\n\
+         src/lib.rs-5-// TODO: Parse env\n\
+         --\n\
+         src/main.rs-1-fn main() {\n\
+         src/main.rs:2:5:    parse_args();\n\
+         src/main.rs-3-}\n\
+         (2 matches in 2 files)"
+    );
+}
+
+#[tokio::test]
+async fn test_search_caps_results() {
+    let dir = search_tree();
+    let text = search(
+        dir.path(),
+        serde_json::json!({"pattern": "parse", "max_results": 2}),
+    )
+    .await;
+    assert_eq!(
+        text,
+        "README.md:1:6:Call parse_config() first.\n\
+         src/lib.rs:3:4:fn parse_config() {}\n\
+         ... (showing first 2 matches; narrow the search to see the rest)"
+    );
+}
+
 // --- Edit tool tests ---
 
 #[tokio::test]
@@ -295,6 +908,503 @@ async fn test_edit_file_no_match() {
     let _ = std::fs::remove_file(tmp);
 }
 
+async fn edit_file(
+    path: &std::path::Path,
+    old_text: &str,
+    new_text: &str,
+) -> Result<ToolResult, ToolError> {
+    EditFileTool::new()
+        .execute(
+            serde_json::json!({
+                "path": path.to_str().unwrap(),
+                "old_text": old_text,
+                "new_text": new_text,
+            }),
+            ctx("edit_file"),
+        )
+        .await
+}
+
+#[tokio::test]
+async fn test_edit_file_prefers_exact_match() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    // The second copy would also match once whitespace is ignored.
+    std::fs::write(&file, "let x = 1;\n    let x = 1;\n").unwrap();
+
+    let result = edit_file(&file, "    let x = 1;", "    let x = 2;")
+        .await
+        .unwrap();
+    assert!(result.details.get("whitespace_normalized").is_none());
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "let x = 1;\n    let x = 2;\n"
+    );
+}
+
+#[tokio::test]
+async fn test_edit_file_matches_ignoring_whitespace() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    std::fs::write(
+        &file,
+        "fn main() {\n    if ok {\n        run();   \n    }\n}\n",
+    )
+    .unwrap();
+
+    // Two-space indentation and no trailing spaces: not an exact match.
+    let result = edit_file(
+        &file,
+        "  if ok {\n    run();\n  }\n",
+        "    if ok {\n        run_fast();\n    }\n",
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "fn main() {\n    if ok {\n        run_fast();\n    }\n}\n"
+    );
+    assert_eq!(result.details["whitespace_normalized"], true);
+    assert_eq!(result.details["matched_lines"], serde_json::json!([2, 4]));
+    assert!(text_of(&result).contains("matched lines 2-4"));
+}
+
+#[tokio::test]
+async fn test_edit_file_rejects_ambiguous_normalized_match() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    let original = "fn a() {\n    x += 1;\n}\nfn b() {\n\tx += 1;\n}\n";
+    std::fs::write(&file, original).unwrap();
+
+    let err = edit_file(&file, "x += 1; ", "x += 2;").await.unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("ambiguous"), "{}", message);
+    assert!(message.contains("lines 2, 5"), "{}", message);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), original);
+}
+
+async fn edit_file_batch(
+    path: &std::path::Path,
+    edits: serde_json::Value,
+) -> Result<ToolResult, ToolError> {
+    EditFileTool::new_multi()
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "edits": edits}),
+            ctx("edit_file"),
+        )
+        .await
+}
+
+#[tokio::test]
+async fn test_edit_file_batch_renames_in_one_call() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    std::fs::write(
+        &file,
+        "fn foo() -> i32 { 1 }\nlet x = foo();\nassert_eq!(foo(), x);\n",
+    )
+    .unwrap();
+
+    let result = edit_file_batch(
+        &file,
+        serde_json::json!([
+            {"old_text": "fn foo()", "new_text": "fn bar()"},
+            {"old_text": "let x = foo();", "new_text": "let x = bar();"},
+            {"old_text": "assert_eq!(foo(), x);", "new_text": "assert_eq!(bar(), x);"},
+        ]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "fn bar() -> i32 { 1 }\nlet x = bar();\nassert_eq!(bar(), x);\n"
+    );
+    assert_eq!(result.details["applied"], 3);
+    assert_eq!(result.details["edit_errors"], serde_json::json!([]));
+}
+
+#[tokio::test]
+async fn test_edit_file_batch_reports_failed_edits() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    std::fs::write(&file, "let a = 1;\nlet b = 2;\n").unwrap();
+
+    let result = edit_file_batch(
+        &file,
+        serde_json::json!([
+            {"old_text": "let a = 1;", "new_text": "let a = 10;"},
+            // Already replaced by the edit before it.
+            {"old_text": "let a = 1;", "new_text": "let a = 100;"},
+            {"old_text": "let b = 2;", "new_text": "let b = 20;"},
+        ]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "let a = 10;\nlet b = 20;\n"
+    );
+    assert_eq!(result.details["applied"], 2);
+    let errors = result.details["edit_errors"].as_array().unwrap();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0]["index"], 1);
+    let Content::Text { text } = &result.content[0] else {
+        panic!("expected text");
+    };
+    assert!(text.starts_with("Applied 2 of 3 edits"), "{}", text);
+
+    // Nothing applied: an error, and the file is untouched.
+    let err = edit_file_batch(
+        &file,
+        serde_json::json!([{"old_text": "missing", "new_text": "x"}]),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("nothing was written"), "{}", err);
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "let a = 10;\nlet b = 20;\n"
+    );
+}
+
+#[test]
+fn test_edit_file_schema_exposes_edits_only_when_multi() {
+    let single = EditFileTool::new().parameters_schema();
+    assert!(single["properties"].get("edits").is_none());
+    let multi = EditFileTool::new_multi().parameters_schema();
+    assert_eq!(multi["properties"]["edits"]["type"], "array");
+    assert_eq!(multi["required"], serde_json::json!(["path"]));
+}
+
+async fn multi_edit(
+    path: &std::path::Path,
+    edits: serde_json::Value,
+) -> Result<ToolResult, ToolError> {
+    MultiEditTool::new()
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "edits": edits}),
+            ctx("multi_edit"),
+        )
+        .await
+}
+
+#[tokio::test]
+async fn test_multi_edit_applies_all_in_order() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    std::fs::write(&file, "fn old() {}\nfn main() { old(); }\n").unwrap();
+
+    let result = multi_edit(
+        &file,
+        serde_json::json!([
+            {"old_text": "fn old() {}", "new_text": "fn helper() {}"},
+            // Sees the first edit's output: only the call site is left.
+            {"old_text": "old();", "new_text": "helper();"}
+        ]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "fn helper() {}\nfn main() { helper(); }\n"
+    );
+    assert!(text_of(&result).contains("Applied 2 edits"));
+}
+
+#[tokio::test]
+async fn test_multi_edit_failure_writes_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    let original = "let a = 1;\nlet b = 1;\n";
+    std::fs::write(&file, original).unwrap();
+
+    let err = multi_edit(
+        &file,
+        serde_json::json!([
+            {"old_text": "let a", "new_text": "let x"},
+            {"old_text": "let missing", "new_text": "let y"}
+        ]),
+    )
+    .await
+    .unwrap_err();
+    assert!(err.to_string().contains("edits[1]: old_text not found"));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), original);
+
+    // An ambiguous match is a failure too.
+    let err = multi_edit(
+        &file,
+        serde_json::json!([{"old_text": "= 1;", "new_text": "= 2;"}]),
+    )
+    .await
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("edits[0]: old_text matches 2 locations"));
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), original);
+}
+
+#[tokio::test]
+async fn test_multi_edit_replace_all() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("lib.rs");
+    std::fs::write(&file, "count += 1;\ncount += 1;\nreturn count;\n").unwrap();
+
+    let result = multi_edit(
+        &file,
+        serde_json::json!([{"old_text": "count", "new_text": "total", "replace_all": true}]),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "total += 1;\ntotal += 1;\nreturn total;\n"
+    );
+    assert_eq!(result.details["replacements"], 3);
+}
+
+async fn apply_patch(path: &std::path::Path, patch: &str) -> Result<ToolResult, ToolError> {
+    ApplyPatchTool::new()
+        .execute(
+            serde_json::json!({"path": path.to_str().unwrap(), "patch": patch}),
+            ctx("apply_patch"),
+        )
+        .await
+}
+
+#[tokio::test]
+async fn test_apply_patch_clean() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("main.rs");
+    std::fs::write(
+        &file,
+        "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n",
+    )
+    .unwrap();
+
+    let patch = "--- a/main.rs\n+++ b/main.rs\n@@ -1,4 +1,5 @@\n fn main() {\n-    let x = 1;\n+    let x = 2;\n+    let y = x * 2;\n     println!(\"{}\", x);\n }\n";
+    let result = apply_patch(&file, patch).await.unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        "fn main() {\n    let x = 2;\n    let y = x * 2;\n    println!(\"{}\", x);\n}\n"
+    );
+    assert!(text_of(&result).starts_with("Applied 1 hunk to"));
+    assert_eq!(result.details["files"][0]["hunks"], 1);
+}
+
+#[tokio::test]
+async fn test_apply_patch_tolerates_line_drift() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("list.txt");
+    let mut lines: Vec<String> = (1..=30).map(|i| format!("line {}", i)).collect();
+    std::fs::write(&file, lines.join("\n") + "\n").unwrap();
+
+    // Both hunks claim positions 2 lines too early, as if made before two
+    // lines were added at the top.
+    let patch = "@@ -2,3 +2,3 @@\n line 4\n-line 5\n+line five\n line 6\n@@ -17,3 +17,4 @@\n line 19\n line 20\n+line 20.5\n line 21\n";
+    let result = apply_patch(&file, patch).await.unwrap();
+
+    lines[4] = "line five".into();
+    lines.insert(20, "line 20.5".into());
+    assert_eq!(
+        std::fs::read_to_string(&file).unwrap(),
+        lines.join("\n") + "\n"
+    );
+    assert_eq!(
+        result.details["files"][0]["offsets"],
+        serde_json::json!([2, 2])
+    );
+    assert!(text_of(&result).contains("Hunk 2 applied at offset +2"));
+}
+
+#[tokio::test]
+async fn test_apply_patch_rejects_mismatched_hunk() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("config.toml");
+    let original = "[server]\nport = 8080\nhost = \"0.0.0.0\"\n";
+    std::fs::write(&file, original).unwrap();
+
+    // The first hunk matches; the second expects content that isn't there.
+    let patch = "@@ -1,2 +1,2 @@\n [server]\n-port = 8080\n+port = 9090\n@@ -3,1 +3,1 @@\n-host = \"localhost\"\n+host = \"127.0.0.1\"\n";
+    let err = apply_patch(&file, patch).await.unwrap_err().to_string();
+    assert!(
+        err.contains("Hunk 2 (@@ -3,1 +3,1 @@) does not match"),
+        "{}",
+        err
+    );
+    assert!(err.contains("   3 | host = \"0.0.0.0\""), "{}", err);
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), original);
+}
+
+#[tokio::test]
+async fn test_apply_patch_creates_and_deletes() {
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("new/notes.md");
+
+    let create = "--- /dev/null\n+++ b/new/notes.md\n@@ -0,0 +1,2 @@\n+# Notes\n+todo\n";
+    apply_patch(&file, create).await.unwrap();
+    assert_eq!(std::fs::read_to_string(&file).unwrap(), "# Notes\ntodo\n");
+    // Creating over an existing file is refused.
+    assert!(apply_patch(&file, create).await.is_err());
+
+    let delete = "--- a/new/notes.md\n+++ /dev/null\n@@ -1,2 +0,0 @@\n-# Notes\n-todo\n";
+    let result = apply_patch(&file, delete).await.unwrap();
+    assert!(!file.exists());
+    assert_eq!(result.details["files"][0]["deleted"], true);
+}
+
+async fn apply_multi_patch(
+    base: &std::path::Path,
+    patch: &str,
+    dry_run: bool,
+) -> Result<ToolResult, ToolError> {
+    ApplyPatchTool::new()
+        .execute(
+            serde_json::json!({
+                "patch": patch,
+                "base_path": base.to_str().unwrap(),
+                "dry_run": dry_run,
+            }),
+            ctx("apply_patch"),
+        )
+        .await
+}
+
+/// A git-style diff touching two files, as `git diff` would print it.
+const TWO_FILE_PATCH: &str = "diff --git a/src/lib.rs b/src/lib.rs
+index 3b18e51..a8c2f9d 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ pub fn answer() -> u32 {
+-    41
++    42
+ }
+diff --git a/README.md b/README.md
+index 1111111..2222222 100644
+--- a/README.md
++++ b/README.md
+@@ -1,2 +1,3 @@
+ # Demo
++
+ The answer is 42.
+";
+
+#[tokio::test]
+async fn test_apply_patch_multiple_files_from_headers() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    std::fs::write(
+        dir.path().join("src/lib.rs"),
+        "pub fn answer() -> u32 {\n    41\n}\n",
+    )
+    .unwrap();
+    std::fs::write(dir.path().join("README.md"), "# Demo\nThe answer is 42.\n").unwrap();
+
+    let result = apply_multi_patch(dir.path(), TWO_FILE_PATCH, false)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("src/lib.rs")).unwrap(),
+        "pub fn answer() -> u32 {\n    42\n}\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("README.md")).unwrap(),
+        "# Demo\n\nThe answer is 42.\n"
+    );
+    let text = text_of(&result);
+    assert!(text.contains("Applied 1 hunk to") && text.contains("src/lib.rs"));
+    assert!(text.contains("README.md"));
+    assert_eq!(result.details["files"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_apply_patch_dry_run_writes_nothing() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    let lib = "pub fn answer() -> u32 {\n    41\n}\n";
+    std::fs::write(dir.path().join("src/lib.rs"), lib).unwrap();
+    std::fs::write(dir.path().join("README.md"), "# Demo\nThe answer is 42.\n").unwrap();
+
+    let result = apply_multi_patch(dir.path(), TWO_FILE_PATCH, true)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("src/lib.rs")).unwrap(),
+        lib
+    );
+    assert!(text_of(&result).starts_with("Would apply 1 hunk to"));
+    assert_eq!(result.details["dry_run"], true);
+    assert_eq!(
+        result.details["files"][0]["content"],
+        "pub fn answer() -> u32 {\n    42\n}\n"
+    );
+}
+
+#[tokio::test]
+async fn test_apply_patch_failing_file_leaves_all_untouched() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir(dir.path().join("src")).unwrap();
+    let lib = "pub fn answer() -> u32 {\n    41\n}\n";
+    std::fs::write(dir.path().join("src/lib.rs"), lib).unwrap();
+    // The README hunk's context doesn't match.
+    std::fs::write(dir.path().join("README.md"), "# Other\n").unwrap();
+
+    let err = apply_multi_patch(dir.path(), TWO_FILE_PATCH, false)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(
+        err.contains("Hunk 1 (@@ -1,2 +1,3 @@) does not match"),
+        "{}",
+        err
+    );
+    assert!(err.contains("README.md"), "{}", err);
+    // The first file applied cleanly but must not have been written.
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("src/lib.rs")).unwrap(),
+        lib
+    );
+}
+
+#[tokio::test]
+async fn test_apply_patch_write_failure_leaves_all_untouched() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("a.txt"), "old\n").unwrap();
+    // A file where the second patch needs a directory: writing it fails
+    // after a.txt has applied in memory.
+    std::fs::write(dir.path().join("blocker"), "").unwrap();
+    let patch = "--- a/a.txt\n+++ b/a.txt\n@@ -1 +1 @@\n-old\n+new\n\
+                 --- /dev/null\n+++ b/blocker/new.txt\n@@ -0,0 +1 @@\n+hi\n";
+
+    let err = apply_multi_patch(dir.path(), patch, false)
+        .await
+        .unwrap_err()
+        .to_string();
+    assert!(err.contains("blocker"), "{}", err);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("a.txt")).unwrap(),
+        "old\n"
+    );
+    // No temp files left behind.
+    let mut names: Vec<_> = std::fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["a.txt", "blocker"]);
+}
+
+#[tokio::test]
+async fn test_apply_patch_headerless_hunks_need_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let err = apply_multi_patch(dir.path(), "@@ -1 +1 @@\n-a\n+b\n", false)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidArgs(_)), "{}", err);
+}
+
 #[tokio::test]
 async fn test_list_files_tool() {
     let tmp_dir = std::env::temp_dir().join("yoagent-test-list2");
@@ -350,10 +1460,11 @@ async fn test_bash_blocked_command() {
 async fn test_default_tools_complete() {
     let tools = yoagent::tools::default_tools();
     let names: Vec<&str> = tools.iter().map(|t| t.name()).collect();
-    assert_eq!(names.len(), 6);
+    assert_eq!(names.len(), 7);
     assert!(names.contains(&"bash"));
     assert!(names.contains(&"edit_file"));
     assert!(names.contains(&"list_files"));
+    assert!(names.contains(&"glob"));
 }
 
 // --- Image support tests ---
@@ -400,6 +1511,56 @@ async fn test_read_image_file() {
     let _ = std::fs::remove_file(tmp);
 }
 
+#[tokio::test]
+async fn test_read_document_with_return_binary() {
+    let dir = tempfile::tempdir().unwrap();
+    let pdf = dir.path().join("spec.pdf");
+    let bytes = b"%PDF-1.7\n\xe2\xe3\xcf\xd3\n%%EOF".to_vec();
+    std::fs::write(&pdf, &bytes).unwrap();
+
+    let result = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": pdf.to_str().unwrap(), "return_binary": true}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        result.content,
+        vec![Content::Document {
+            data: bytes,
+            mime_type: "application/pdf".into(),
+            filename: "spec.pdf".into(),
+        }]
+    );
+
+    // Without the flag the file is read as text, which a binary PDF isn't.
+    let err = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": pdf.to_str().unwrap()}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Use return_binary"), "{}", err);
+}
+
+#[tokio::test]
+async fn test_read_file_return_binary_rejects_non_documents() {
+    let dir = tempfile::tempdir().unwrap();
+    let notes = dir.path().join("notes.txt");
+    std::fs::write(&notes, "plain text").unwrap();
+
+    let err = ReadFileTool::new()
+        .execute(
+            serde_json::json!({"path": notes.to_str().unwrap(), "return_binary": true}),
+            ctx("read_file"),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidArgs(_)), "{}", err);
+}
+
 #[tokio::test]
 async fn test_read_jpeg_file() {
     let tmp = std::env::temp_dir().join("yoagent-test-image.jpg");
@@ -448,3 +1609,526 @@ async fn test_read_text_file_unchanged() {
 
     let _ = std::fs::remove_file(tmp);
 }
+
+// --- HTTP tool tests ---
+
+use wiremock::matchers::{body_json, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn text_of(result: &ToolResult) -> &str {
+    match &result.content[0] {
+        Content::Text { text } => text,
+        other => panic!("expected text content, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_http_get_returns_status_headers_and_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/health"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("x-request-id", "abc123")
+                .set_body_raw(r#"{"ok":true}"#, "application/json"),
+        )
+        .mount(&server)
+        .await;
+
+    let result = HttpTool::new()
+        .execute(
+            serde_json::json!({"url": format!("{}/health", server.uri())}),
+            ctx("http_request"),
+        )
+        .await
+        .unwrap();
+
+    let text = text_of(&result);
+    assert!(text.starts_with("HTTP/1.1 200 OK\n"), "got: {}", text);
+    assert!(text.contains("x-request-id: abc123"));
+    assert!(text.ends_with(r#"{"ok":true}"#));
+    assert_eq!(result.details["status"], 200);
+    assert_eq!(result.details["content_type"], "application/json");
+    assert_eq!(result.details["truncated"], false);
+}
+
+#[tokio::test]
+async fn test_http_post_sends_headers_and_json_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/hooks"))
+        .and(header("x-token", "secret"))
+        .and(body_json(serde_json::json!({"event": "deploy"})))
+        .respond_with(ResponseTemplate::new(201).set_body_string("created"))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let result = HttpTool::new()
+        .execute(
+            serde_json::json!({
+                "url": format!("{}/hooks", server.uri()),
+                "method": "POST",
+                "headers": {"x-token": "secret"},
+                "body": {"event": "deploy"}
+            }),
+            ctx("http_request"),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(result.details["status"], 201);
+    assert!(text_of(&result).ends_with("created"));
+}
+
+#[tokio::test]
+async fn test_http_truncates_large_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("x".repeat(1000)))
+        .mount(&server)
+        .await;
+
+    let result = HttpTool::new()
+        .with_max_response_bytes(100)
+        .execute(
+            serde_json::json!({"url": server.uri()}),
+            ctx("http_request"),
+        )
+        .await
+        .unwrap();
+
+    let text = text_of(&result);
+    assert!(text.contains(&"x".repeat(100)));
+    assert!(!text.contains(&"x".repeat(101)));
+    assert!(text.contains("[truncated: response exceeded 100 bytes]"));
+    assert_eq!(result.details["truncated"], true);
+}
+
+#[tokio::test]
+async fn test_http_non_2xx_is_result_unless_fail_on_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(404).set_body_string("no such thing"))
+        .mount(&server)
+        .await;
+    let params = serde_json::json!({"url": server.uri()});
+
+    let result = HttpTool::new()
+        .execute(params.clone(), ctx("http_request"))
+        .await
+        .unwrap();
+    assert_eq!(result.details["status"], 404);
+
+    let err = HttpTool::new()
+        .with_fail_on_error(true)
+        .execute(params, ctx("http_request"))
+        .await
+        .unwrap_err();
+    match err {
+        ToolError::Failed(msg) => {
+            assert!(msg.contains("404 Not Found"));
+            assert!(msg.contains("no such thing"));
+        }
+        other => panic!("expected Failed, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_http_timeout_ms_returns_timeout_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_delay(std::time::Duration::from_secs(5)))
+        .mount(&server)
+        .await;
+
+    let err = HttpTool::new()
+        .execute(
+            serde_json::json!({"url": server.uri(), "timeout_ms": 50}),
+            ctx("http_request"),
+        )
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, ToolError::Timeout(d) if d == std::time::Duration::from_millis(50)),
+        "got {:?}",
+        err
+    );
+}
+
+#[tokio::test]
+async fn test_http_network_error_and_bad_method() {
+    // Nothing listens on port 1.
+    let err = HttpTool::new()
+        .execute(
+            serde_json::json!({"url": "http://127.0.0.1:1/"}),
+            ctx("http_request"),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::Failed(_)), "got {:?}", err);
+
+    let err = HttpTool::new()
+        .execute(
+            serde_json::json!({"url": "http://127.0.0.1:1/", "method": "TRACE"}),
+            ctx("http_request"),
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidArgs(_)), "got {:?}", err);
+}
+
+// --- Fetch tool tests ---
+
+async fn fetch(params: serde_json::Value) -> Result<ToolResult, ToolError> {
+    FetchTool::new().execute(params, ctx("fetch")).await
+}
+
+#[tokio::test]
+async fn test_fetch_returns_text_and_json_bodies() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/notes.txt"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw("plain <b>text</b>", "text/plain"))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api"))
+        .and(body_json(serde_json::json!({"q": 1})))
+        .respond_with(ResponseTemplate::new(201).set_body_raw(r#"{"id":7}"#, "application/json"))
+        .mount(&server)
+        .await;
+
+    let result = fetch(serde_json::json!({"url": format!("{}/notes.txt", server.uri())}))
+        .await
+        .unwrap();
+    // Only HTML is converted.
+    assert_eq!(text_of(&result), "plain <b>text</b>");
+    assert_eq!(result.details["status"], 200);
+    assert_eq!(result.details["content_type"], "text/plain");
+    assert_eq!(result.details["html_converted"], false);
+
+    let result = fetch(serde_json::json!({
+        "url": format!("{}/api", server.uri()),
+        "method": "POST",
+        "body": {"q": 1},
+    }))
+    .await
+    .unwrap();
+    assert_eq!(text_of(&result), r#"{"id":7}"#);
+    assert_eq!(result.details["status"], 201);
+    assert_eq!(result.details["content_type"], "application/json");
+}
+
+#[tokio::test]
+async fn test_fetch_converts_html_and_truncates() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/page"))
+        .respond_with(ResponseTemplate::new(200).set_body_raw(
+            "<html><head><script>track()</script></head>\
+             <body><h1>Title</h1><p>Hello &amp; welcome.</p></body></html>",
+            "text/html; charset=utf-8",
+        ))
+        .mount(&server)
+        .await;
+
+    let url = format!("{}/page", server.uri());
+    let result = fetch(serde_json::json!({"url": url})).await.unwrap();
+    assert_eq!(text_of(&result), "Title\nHello & welcome.");
+    assert_eq!(result.details["html_converted"], true);
+    assert_eq!(result.details["truncated"], false);
+
+    let result = fetch(serde_json::json!({"url": url, "max_bytes": 10}))
+        .await
+        .unwrap();
+    assert_eq!(result.details["truncated"], true);
+    assert!(
+        text_of(&result).ends_with("[truncated: response exceeded 10 bytes]"),
+        "{}",
+        text_of(&result)
+    );
+}
+
+#[tokio::test]
+async fn test_fetch_refuses_other_schemes_and_private_addresses() {
+    let err = fetch(serde_json::json!({"url": "file:///etc/passwd"}))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidArgs(_)), "{}", err);
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .respond_with(ResponseTemplate::new(200).set_body_string("internal"))
+        .mount(&server)
+        .await;
+    let guarded = FetchTool::new().with_block_private_ips(true);
+    let err = guarded
+        .execute(serde_json::json!({"url": server.uri()}), ctx("fetch"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("not a public address"), "{}", err);
+
+    // A name that resolves to loopback is refused too.
+    let port = server.address().port();
+    let err = guarded
+        .execute(
+            serde_json::json!({"url": format!("http://localhost:{}/", port)}),
+            ctx("fetch"),
+        )
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("non-public address"), "{}", err);
+}
+
+// --- Web search tool tests ---
+
+/// Returns `n` canned results, however many are asked for, and records the
+/// requested count.
+struct CannedSearch {
+    n: usize,
+    requested: std::sync::Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[async_trait::async_trait]
+impl SearchBackend for CannedSearch {
+    async fn search(&self, query: &str, count: usize) -> Result<Vec<WebSearchResult>, ToolError> {
+        self.requested
+            .store(count, std::sync::atomic::Ordering::SeqCst);
+        Ok((1..=self.n)
+            .map(|i| WebSearchResult {
+                title: format!("{} result {}", query, i),
+                url: format!("https://example.com/{}", i),
+                snippet: if i == 2 {
+                    String::new()
+                } else {
+                    "x".repeat(i * 10)
+                },
+            })
+            .collect())
+    }
+}
+
+#[tokio::test]
+async fn test_web_search_formats_and_caps_results() {
+    let requested = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let tool = WebSearchTool::new(CannedSearch {
+        n: 30,
+        requested: requested.clone(),
+    })
+    .with_max_results(8)
+    .with_max_snippet_chars(25);
+
+    let result = tool
+        .execute(
+            serde_json::json!({"query": "rust", "count": 3}),
+            ctx("web_search"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        text_of(&result),
+        "1. rust result 1\n   https://example.com/1\n   xxxxxxxxxx\n\
+         2. rust result 2\n   https://example.com/2\n\
+         3. rust result 3\n   https://example.com/3\n   xxxxxxxxxxxxxxxxxxxxxxxxx…"
+    );
+    assert_eq!(requested.load(std::sync::atomic::Ordering::SeqCst), 3);
+    // Details keep the full snippets.
+    assert_eq!(result.details["query"], "rust");
+    assert_eq!(result.details["results"].as_array().unwrap().len(), 3);
+    assert_eq!(result.details["results"][2]["snippet"], "x".repeat(30));
+
+    // Counts above the cap are clamped; the default is 5.
+    let result = tool
+        .execute(
+            serde_json::json!({"query": "rust", "count": 50}),
+            ctx("web_search"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.details["results"].as_array().unwrap().len(), 8);
+    let result = tool
+        .execute(serde_json::json!({"query": "rust"}), ctx("web_search"))
+        .await
+        .unwrap();
+    assert_eq!(result.details["results"].as_array().unwrap().len(), 5);
+
+    let empty = WebSearchTool::new(CannedSearch {
+        n: 0,
+        requested: requested.clone(),
+    });
+    let result = empty
+        .execute(serde_json::json!({"query": "nothing"}), ctx("web_search"))
+        .await
+        .unwrap();
+    assert_eq!(text_of(&result), "No results for \"nothing\"");
+
+    let err = empty
+        .execute(serde_json::json!({"query": "  "}), ctx("web_search"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ToolError::InvalidArgs(_)), "{}", err);
+}
+
+#[tokio::test]
+async fn test_brave_search_backend() {
+    use wiremock::matchers::query_param;
+
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/search"))
+        .and(query_param("q", "rust async"))
+        .and(query_param("count", "2"))
+        .and(header("X-Subscription-Token", "brave-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "web": {"results": [
+                {
+                    "title": "The <strong>Rust</strong> Book",
+                    "url": "https://doc.rust-lang.org/book/",
+                    "description": "Learn <strong>async</strong> &amp; more."
+                },
+                {"title": "No URL", "description": "skipped"},
+                {"title": "Tokio", "url": "https://tokio.rs"}
+            ]}
+        })))
+        .mount(&server)
+        .await;
+
+    let brave = BraveSearch::new("brave-key").with_endpoint(format!("{}/search", server.uri()));
+    let results = brave.search("rust async", 2).await.unwrap();
+    assert_eq!(
+        results,
+        vec![
+            WebSearchResult {
+                title: "The Rust Book".into(),
+                url: "https://doc.rust-lang.org/book/".into(),
+                snippet: "Learn async & more.".into(),
+            },
+            WebSearchResult {
+                title: "Tokio".into(),
+                url: "https://tokio.rs".into(),
+                snippet: String::new(),
+            },
+        ]
+    );
+
+    let err = BraveSearch::new("wrong-key")
+        .with_endpoint(format!("{}/search", server.uri()))
+        .search("rust async", 2)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("HTTP 404"), "{}", err);
+}
+
+// --- Glob tool tests ---
+
+/// `src/{old.rs, new.rs, notes.txt, deep/inner.rs}` plus a `.gitignore`d
+/// `target/`, with `new.rs` modified last.
+fn glob_tree() -> tempfile::TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    let root = dir.path();
+    std::fs::create_dir_all(root.join("src/deep")).unwrap();
+    std::fs::create_dir_all(root.join("target")).unwrap();
+    std::fs::write(root.join(".gitignore"), "target/\n").unwrap();
+    std::fs::write(root.join("target/gen.rs"), "").unwrap();
+    std::fs::write(root.join("src/notes.txt"), "").unwrap();
+    std::fs::write(root.join("src/deep/inner.rs"), "").unwrap();
+    let epoch = std::time::SystemTime::UNIX_EPOCH;
+    for (name, secs) in [("src/old.rs", 1_000), ("src/new.rs", 2_000)] {
+        let file = std::fs::File::create(root.join(name)).unwrap();
+        file.set_modified(epoch + std::time::Duration::from_secs(secs))
+            .unwrap();
+    }
+    let inner = std::fs::File::options()
+        .write(true)
+        .open(root.join("src/deep/inner.rs"))
+        .unwrap();
+    inner
+        .set_modified(epoch + std::time::Duration::from_secs(500))
+        .unwrap();
+    dir
+}
+
+async fn glob(tool: &GlobTool, params: serde_json::Value) -> (String, serde_json::Value) {
+    let result = tool.execute(params, ctx("glob")).await.unwrap();
+    (text_of(&result).to_string(), result.details)
+}
+
+#[tokio::test]
+async fn test_glob_sorts_newest_first_and_skips_ignored() {
+    let dir = glob_tree();
+    let tool = GlobTool::new().with_root(dir.path().to_str().unwrap());
+
+    let (text, details) = glob(&tool, serde_json::json!({"pattern": "**/*.rs"})).await;
+    let paths: Vec<&str> = text.lines().take_while(|l| !l.is_empty()).collect();
+    assert_eq!(paths, vec!["src/new.rs", "src/old.rs", "src/deep/inner.rs"]);
+    assert_eq!(details["total"], 3);
+
+    // `*` stays within one directory.
+    let (text, _) = glob(&tool, serde_json::json!({"pattern": "src/*.rs"})).await;
+    assert!(!text.contains("inner.rs"));
+
+    let unfiltered = GlobTool {
+        respect_gitignore: false,
+        ..GlobTool::new().with_root(dir.path().to_str().unwrap())
+    };
+    let (text, _) = glob(&unfiltered, serde_json::json!({"pattern": "**/*.rs"})).await;
+    assert!(text.contains("target/gen.rs"));
+}
+
+#[tokio::test]
+async fn test_glob_alternation_and_cwd() {
+    let dir = glob_tree();
+    let (text, details) = glob(
+        &GlobTool::new(),
+        serde_json::json!({
+            "pattern": "*.{rs,txt}",
+            "cwd": dir.path().join("src").to_str().unwrap()
+        }),
+    )
+    .await;
+    assert_eq!(details["total"], 3);
+    assert!(text.contains("notes.txt"));
+    assert!(text.contains("new.rs"));
+}
+
+#[tokio::test]
+async fn test_glob_absolute_pattern() {
+    let dir = glob_tree();
+    let pattern = format!("{}/src/**/inner.rs", dir.path().display());
+    let (text, _) = glob(&GlobTool::new(), serde_json::json!({"pattern": pattern})).await;
+    let expected = dir.path().join("src/deep/inner.rs");
+    assert!(
+        text.starts_with(&expected.display().to_string()),
+        "{}",
+        text
+    );
+}
+
+#[tokio::test]
+async fn test_glob_no_matches_and_truncation() {
+    let dir = glob_tree();
+    let root = dir.path().to_str().unwrap();
+
+    let (text, details) = glob(
+        &GlobTool::new().with_root(root),
+        serde_json::json!({"pattern": "**/*.py"}),
+    )
+    .await;
+    assert!(text.starts_with("No files match '**/*.py'"));
+    assert_eq!(details["total"], 0);
+
+    let capped = GlobTool {
+        max_results: 1,
+        ..GlobTool::new().with_root(root)
+    };
+    let (text, details) = glob(&capped, serde_json::json!({"pattern": "**/*.rs"})).await;
+    assert!(text.starts_with("src/new.rs\n"));
+    assert!(text.contains("3 files, showing the 1 most recently modified"));
+    assert_eq!(details["truncated"], true);
+
+    let bad = GlobTool::new()
+        .execute(serde_json::json!({"pattern": "src/[rs"}), ctx("glob"))
+        .await;
+    assert!(matches!(bad, Err(ToolError::InvalidArgs(_))));
+}